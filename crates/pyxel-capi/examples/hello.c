@@ -0,0 +1,41 @@
+#include <stdio.h>
+
+#include "pyxel.h"
+
+static void update(Pyxel *pyxel, void *user_data) {
+    (void)user_data;
+
+    if (pyxel_frame_count(pyxel) >= 60) {
+        pyxel_quit(pyxel);
+    }
+}
+
+static void draw(Pyxel *pyxel, void *user_data) {
+    (void)user_data;
+
+    pyxel_cls(pyxel, 0);
+    pyxel_text(pyxel, 55, 41, "Hello, Pyxel!", pyxel_frame_count(pyxel) % 16);
+    pyxel_blt(pyxel, 61, 66, 0, 0, 0, 16, 16, -1);
+}
+
+int main(int argc, char *argv[]) {
+    const char *filename = argc > 1 ? argv[1] : "../../examples/assets/sample.pyxres";
+    Pyxel *pyxel = pyxel_init(160, 120, "Hello Pyxel", -1, -1, -1);
+
+    if (pyxel == NULL) {
+        fprintf(stderr, "Failed to initialize Pyxel\n");
+        return 1;
+    }
+
+    if (!pyxel_load(pyxel, filename)) {
+        fprintf(stderr, "Failed to load '%s'\n", filename);
+        pyxel_shutdown(pyxel);
+        return 1;
+    }
+
+    if (!pyxel_run(pyxel, update, draw, NULL)) {
+        fprintf(stderr, "Pyxel stopped with an error\n");
+    }
+
+    return pyxel_shutdown(pyxel) ? 0 : 1;
+}
@@ -0,0 +1,585 @@
+#![warn(clippy::pedantic, clippy::cargo)]
+#![allow(clippy::missing_panics_doc, clippy::too_many_arguments)]
+
+use std::ffi::{c_char, c_void, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr::null_mut;
+
+use pyxel::{Color, Key, Pyxel, PyxelCallback};
+
+// Negative values are passed from C to leave optional parameters unspecified
+fn to_option<T: TryFrom<i32>>(value: i32) -> Option<T> {
+    if value < 0 {
+        None
+    } else {
+        T::try_from(value).ok()
+    }
+}
+
+unsafe fn to_str<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        None
+    } else {
+        CStr::from_ptr(string).to_str().ok()
+    }
+}
+
+// Unwinding across the FFI boundary is undefined behavior,
+// so every entry point catches panics and reports them as a failure
+fn catch_panic(f: impl FnOnce()) -> bool {
+    panic::catch_unwind(AssertUnwindSafe(f)).is_ok()
+}
+
+fn catch_panic_or<T>(default: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(default)
+}
+
+// A null handle is reported as a failure, the same way as a panic
+unsafe fn call(pyxel: *mut Pyxel, f: impl FnOnce(&mut Pyxel)) -> bool {
+    match pyxel.as_mut() {
+        Some(pyxel) => catch_panic(|| f(pyxel)),
+        None => false,
+    }
+}
+
+unsafe fn call_or<T>(pyxel: *mut Pyxel, default: T, f: impl FnOnce(&mut Pyxel) -> T) -> T {
+    match pyxel.as_mut() {
+        Some(pyxel) => catch_panic_or(default, || f(pyxel)),
+        None => default,
+    }
+}
+
+unsafe fn get_or<T>(pyxel: *const Pyxel, default: T, f: impl FnOnce(&Pyxel) -> T) -> T {
+    match pyxel.as_ref() {
+        Some(pyxel) => catch_panic_or(default, || f(pyxel)),
+        None => default,
+    }
+}
+
+pub type PyxelFunc = extern "C" fn(pyxel: *mut Pyxel, user_data: *mut c_void);
+
+struct CCallback {
+    update: Option<PyxelFunc>,
+    draw: Option<PyxelFunc>,
+    user_data: *mut c_void,
+}
+
+impl PyxelCallback for CCallback {
+    fn update(&mut self, pyxel: &mut Pyxel) {
+        if let Some(update) = self.update {
+            update(pyxel, self.user_data);
+        }
+    }
+
+    fn draw(&mut self, pyxel: &mut Pyxel) {
+        if let Some(draw) = self.draw {
+            draw(pyxel, self.user_data);
+        }
+    }
+}
+
+//
+// System
+//
+// Functions without a result return false if the call failed or the handle is null.
+// Functions with a result return a zero value instead.
+//
+
+/// # Safety
+///
+/// `title` must be null or a valid null-terminated UTF-8 string.
+/// Returns null if Pyxel is already initialized or the window cannot be created.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_init(
+    width: u32,
+    height: u32,
+    title: *const c_char,
+    fps: i32,
+    quit_key: i32,
+    display_scale: i32,
+) -> *mut Pyxel {
+    catch_panic_or(null_mut(), || {
+        pyxel::init(
+            width,
+            height,
+            to_str(title),
+            to_option(fps),
+            to_option(quit_key),
+            to_option(display_scale),
+            None,
+            None,
+        )
+        .map_or(null_mut(), |pyxel| Box::into_raw(Box::new(pyxel)))
+    })
+}
+
+/// # Safety
+///
+/// `pyxel` must be a handle returned by `pyxel_init` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_shutdown(pyxel: *mut Pyxel) -> bool {
+    catch_panic(|| {
+        if !pyxel.is_null() {
+            drop(Box::from_raw(pyxel));
+        }
+    })
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_run(
+    pyxel: *mut Pyxel,
+    update: Option<PyxelFunc>,
+    draw: Option<PyxelFunc>,
+    user_data: *mut c_void,
+) -> bool {
+    call(pyxel, |pyxel| {
+        pyxel.run(CCallback {
+            update,
+            draw,
+            user_data,
+        });
+    })
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_show(pyxel: *mut Pyxel) -> bool {
+    call(pyxel, Pyxel::show)
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_flip(pyxel: *mut Pyxel) -> bool {
+    call_or(pyxel, false, |pyxel| pyxel.flip().is_ok())
+}
+
+/// Inside pyxel_run, the loop ends after the current frame, so callbacks should return
+/// right after calling this.
+///
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_quit(pyxel: *mut Pyxel) -> bool {
+    call(pyxel, |pyxel| pyxel.quit())
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+/// `title` must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_title(pyxel: *mut Pyxel, title: *const c_char) -> bool {
+    call(pyxel, |pyxel| {
+        pyxel.title(to_str(title).unwrap_or_default())
+    })
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_width(pyxel: *const Pyxel) -> u32 {
+    get_or(pyxel, 0, |pyxel| pyxel.width)
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_height(pyxel: *const Pyxel) -> u32 {
+    get_or(pyxel, 0, |pyxel| pyxel.height)
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_frame_count(pyxel: *const Pyxel) -> u32 {
+    get_or(pyxel, 0, |pyxel| pyxel.frame_count)
+}
+
+//
+// Resource
+//
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+/// `filename` must be null or a valid null-terminated UTF-8 string.
+/// Returns false if the filename is null or the file cannot be loaded.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_load(pyxel: *mut Pyxel, filename: *const c_char) -> bool {
+    call_or(pyxel, false, |pyxel| {
+        to_str(filename).is_some_and(|filename| {
+            pyxel
+                .load(filename, None, None, None, None, None, None, None)
+                .is_ok()
+        })
+    })
+}
+
+//
+// Input
+//
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_btn(pyxel: *mut Pyxel, key: Key) -> bool {
+    call_or(pyxel, false, |pyxel| pyxel.btn(key))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_btnp(pyxel: *mut Pyxel, key: Key, hold: i32, repeat: i32) -> bool {
+    call_or(pyxel, false, |pyxel| {
+        pyxel.btnp(key, to_option(hold), to_option(repeat))
+    })
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_btnr(pyxel: *mut Pyxel, key: Key) -> bool {
+    call_or(pyxel, false, |pyxel| pyxel.btnr(key))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_mouse(pyxel: *mut Pyxel, visible: bool) -> bool {
+    call(pyxel, |pyxel| pyxel.mouse(visible))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_mouse_x(pyxel: *const Pyxel) -> i32 {
+    get_or(pyxel, 0, |pyxel| pyxel.mouse_x)
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_mouse_y(pyxel: *const Pyxel) -> i32 {
+    get_or(pyxel, 0, |pyxel| pyxel.mouse_y)
+}
+
+//
+// Graphics
+//
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_clip(pyxel: *mut Pyxel, x: f64, y: f64, w: f64, h: f64) -> bool {
+    call(pyxel, |pyxel| pyxel.clip(x, y, w, h))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_clip0(pyxel: *mut Pyxel) -> bool {
+    call(pyxel, |pyxel| pyxel.clip0())
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_camera(pyxel: *mut Pyxel, x: f64, y: f64) -> bool {
+    call(pyxel, |pyxel| pyxel.camera(x, y))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_camera0(pyxel: *mut Pyxel) -> bool {
+    call(pyxel, |pyxel| pyxel.camera0())
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_pal(pyxel: *mut Pyxel, col1: Color, col2: Color) -> bool {
+    call(pyxel, |pyxel| pyxel.pal(col1, col2))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_pal0(pyxel: *mut Pyxel) -> bool {
+    call(pyxel, |pyxel| pyxel.pal0())
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_cls(pyxel: *mut Pyxel, col: Color) -> bool {
+    call(pyxel, |pyxel| pyxel.cls(col))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_pget(pyxel: *mut Pyxel, x: f64, y: f64) -> Color {
+    call_or(pyxel, 0, |pyxel| pyxel.pget(x, y))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_pset(pyxel: *mut Pyxel, x: f64, y: f64, col: Color) -> bool {
+    call(pyxel, |pyxel| pyxel.pset(x, y, col))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_line(
+    pyxel: *mut Pyxel,
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    col: Color,
+) -> bool {
+    call(pyxel, |pyxel| pyxel.line(x1, y1, x2, y2, col))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_rect(
+    pyxel: *mut Pyxel,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    col: Color,
+) -> bool {
+    call(pyxel, |pyxel| pyxel.rect(x, y, w, h, col))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_rectb(
+    pyxel: *mut Pyxel,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    col: Color,
+) -> bool {
+    call(pyxel, |pyxel| pyxel.rectb(x, y, w, h, col))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_circ(pyxel: *mut Pyxel, x: f64, y: f64, r: f64, col: Color) -> bool {
+    call(pyxel, |pyxel| pyxel.circ(x, y, r, col))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_circb(
+    pyxel: *mut Pyxel,
+    x: f64,
+    y: f64,
+    r: f64,
+    col: Color,
+) -> bool {
+    call(pyxel, |pyxel| pyxel.circb(x, y, r, col))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_tri(
+    pyxel: *mut Pyxel,
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    x3: f64,
+    y3: f64,
+    col: Color,
+) -> bool {
+    call(pyxel, |pyxel| pyxel.tri(x1, y1, x2, y2, x3, y3, col))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_blt(
+    pyxel: *mut Pyxel,
+    x: f64,
+    y: f64,
+    img: u32,
+    u: f64,
+    v: f64,
+    w: f64,
+    h: f64,
+    colkey: i32,
+) -> bool {
+    call(pyxel, |pyxel| {
+        pyxel.blt(x, y, img, u, v, w, h, to_option(colkey))
+    })
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_bltm(
+    pyxel: *mut Pyxel,
+    x: f64,
+    y: f64,
+    tm: u32,
+    u: f64,
+    v: f64,
+    w: f64,
+    h: f64,
+    colkey: i32,
+) -> bool {
+    call(pyxel, |pyxel| {
+        pyxel.bltm(x, y, tm, u, v, w, h, to_option(colkey))
+    })
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+/// `s` must be a valid null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_text(
+    pyxel: *mut Pyxel,
+    x: f64,
+    y: f64,
+    s: *const c_char,
+    col: Color,
+) -> bool {
+    call(pyxel, |pyxel| {
+        pyxel.text(x, y, to_str(s).unwrap_or_default(), col)
+    })
+}
+
+//
+// Audio
+//
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_play(
+    pyxel: *mut Pyxel,
+    ch: u32,
+    snd: u32,
+    should_loop: bool,
+) -> bool {
+    call(pyxel, |pyxel| pyxel.play(ch, &[snd], None, should_loop))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_playm(pyxel: *mut Pyxel, msc: u32, should_loop: bool) -> bool {
+    call(pyxel, |pyxel| pyxel.playm(msc, None, should_loop))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_stop(pyxel: *mut Pyxel, ch: u32) -> bool {
+    call(pyxel, |pyxel| pyxel.stop(ch))
+}
+
+/// # Safety
+///
+/// `pyxel` must be null or a handle returned by `pyxel_init`.
+#[no_mangle]
+pub unsafe extern "C" fn pyxel_stop0(pyxel: *mut Pyxel) -> bool {
+    call(pyxel, |pyxel| pyxel.stop0())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::ptr::null;
+
+    use super::*;
+
+    #[test]
+    fn test_load_and_shutdown() {
+        let pyxel = Box::into_raw(Box::new(Pyxel::builder(16, 16).headless().build().unwrap()));
+        let missing = CString::new("missing.pyxres").unwrap();
+        let invalid = [0xffu8, 0];
+
+        unsafe {
+            assert!(!pyxel_load(pyxel, null()));
+            assert!(!pyxel_load(pyxel, missing.as_ptr()));
+            assert!(!pyxel_load(pyxel, invalid.as_ptr().cast()));
+            assert_eq!(pyxel_width(pyxel), 16);
+            assert!(pyxel_shutdown(pyxel));
+            assert!(pyxel_shutdown(null_mut()));
+        }
+    }
+
+    #[test]
+    fn test_null_handle() {
+        let filename = CString::new("missing.pyxres").unwrap();
+
+        unsafe {
+            assert!(!pyxel_show(null_mut()));
+            assert!(!pyxel_quit(null_mut()));
+            assert!(!pyxel_cls(null_mut(), 0));
+            assert!(!pyxel_load(null_mut(), filename.as_ptr()));
+            assert_eq!(pyxel_pget(null_mut(), 0.0, 0.0), 0);
+            assert_eq!(pyxel_width(null()), 0);
+            assert_eq!(pyxel_frame_count(null()), 0);
+        }
+    }
+
+    #[test]
+    fn test_catch_panic() {
+        assert!(catch_panic(|| {}));
+        assert!(!catch_panic(|| panic!("test panic")));
+        assert_eq!(catch_panic_or(0, || panic!("test panic")), 0);
+    }
+}
@@ -0,0 +1,186 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pyxel::{Image, ImageSource, SharedImage, Tilemap};
+
+const SCREEN_WIDTH: u32 = 256;
+const SCREEN_HEIGHT: u32 = 256;
+const TRANSPARENT_COLOR: u8 = 0;
+
+// Fills an image with a pattern that mixes transparent and opaque pixels
+fn source_image() -> SharedImage {
+    let image = Image::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+    {
+        let mut image = image.lock();
+        for y in 0..SCREEN_HEIGHT as i32 {
+            for x in 0..SCREEN_WIDTH as i32 {
+                image.pset(x, y, ((x / 4 + y / 4) % 16) as u8);
+            }
+        }
+    }
+    image
+}
+
+fn bench_cls(c: &mut Criterion) {
+    let screen = Image::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+    c.bench_function("cls", |b| {
+        b.iter(|| screen.lock().cls(black_box(1)));
+    });
+}
+
+fn bench_blt(c: &mut Criterion) {
+    let screen = Image::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+    let source = source_image();
+    let (w, h) = (SCREEN_WIDTH as i32, SCREEN_HEIGHT as i32);
+    c.bench_function("blt", |b| {
+        b.iter(|| {
+            screen
+                .lock()
+                .blt(0, 0, source.clone(), 0, 0, black_box(w), h, None);
+        });
+    });
+    c.bench_function("blt_colkey", |b| {
+        b.iter(|| {
+            screen.lock().blt(
+                0,
+                0,
+                source.clone(),
+                0,
+                0,
+                black_box(w),
+                h,
+                Some(TRANSPARENT_COLOR),
+            );
+        });
+    });
+    c.bench_function("blt_flip", |b| {
+        b.iter(|| {
+            screen
+                .lock()
+                .blt(0, 0, source.clone(), 0, 0, black_box(-w), -h, None);
+        });
+    });
+}
+
+fn bench_shapes(c: &mut Criterion) {
+    let screen = Image::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+    c.bench_function("line", |b| {
+        b.iter(|| screen.lock().line(0, 0, black_box(255), 191, 7));
+    });
+    c.bench_function("circ", |b| {
+        b.iter(|| screen.lock().circ(128, 128, black_box(100), 7));
+    });
+    c.bench_function("tri", |b| {
+        b.iter(|| screen.lock().tri(0, 0, black_box(255), 64, 64, 255, 7));
+    });
+}
+
+fn bench_bltm(c: &mut Criterion) {
+    let screen = Image::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+    let tilemap = Tilemap::new(32, 32, ImageSource::Image(source_image()));
+    {
+        let mut tilemap = tilemap.lock();
+        for y in 0..32 {
+            for x in 0..32 {
+                tilemap.pset(x, y, ((x % 4) as u8, (y % 4) as u8));
+            }
+        }
+    }
+    let (w, h) = (SCREEN_WIDTH as i32, SCREEN_HEIGHT as i32);
+    c.bench_function("bltm", |b| {
+        b.iter(|| {
+            screen.lock().bltm(
+                0,
+                0,
+                tilemap.clone(),
+                0,
+                0,
+                black_box(w),
+                h,
+                Some(TRANSPARENT_COLOR),
+            );
+        });
+    });
+}
+
+// Blitting with a remapped palette goes through the palette conversion path
+fn bench_palette(c: &mut Criterion) {
+    let screen = Image::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+    let source = source_image();
+    {
+        let mut screen = screen.lock();
+        for color in 0..16 {
+            screen.pal(color, 15 - color);
+        }
+    }
+    let (w, h) = (SCREEN_WIDTH as i32, SCREEN_HEIGHT as i32);
+    c.bench_function("blt_pal", |b| {
+        b.iter(|| {
+            screen
+                .lock()
+                .blt(0, 0, source.clone(), 0, 0, black_box(w), h, None);
+        });
+    });
+}
+
+// Dithered blits skip the row copy fast path and give a per-pixel baseline, while
+// clipped blits copy shorter rows
+fn bench_blt_paths(c: &mut Criterion) {
+    let screen = Image::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+    let source = source_image();
+    let (w, h) = (SCREEN_WIDTH as i32, SCREEN_HEIGHT as i32);
+    c.bench_function("blt_dither", |b| {
+        screen.lock().dither(0.5);
+        b.iter(|| {
+            screen
+                .lock()
+                .blt(0, 0, source.clone(), 0, 0, black_box(w), h, None);
+        });
+        screen.lock().dither(1.0);
+    });
+    c.bench_function("blt_clipped", |b| {
+        screen.lock().clip(16, 16, w - 32, h - 32);
+        b.iter(|| {
+            screen
+                .lock()
+                .blt(black_box(-8), -8, source.clone(), 0, 0, w, h, None);
+        });
+        screen.lock().clip0();
+    });
+}
+
+fn bench_pixels(c: &mut Criterion) {
+    let screen = source_image();
+    c.bench_function("pset", |b| {
+        b.iter(|| {
+            let mut screen = screen.lock();
+            for y in 0..SCREEN_HEIGHT as i32 {
+                for x in 0..SCREEN_WIDTH as i32 {
+                    screen.pset(x, y, black_box(7));
+                }
+            }
+        });
+    });
+    c.bench_function("pget", |b| {
+        b.iter(|| {
+            let screen = screen.read();
+            let mut sum = 0u32;
+            for y in 0..SCREEN_HEIGHT as i32 {
+                for x in 0..SCREEN_WIDTH as i32 {
+                    sum += screen.pget(x, y) as u32;
+                }
+            }
+            black_box(sum)
+        });
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_cls,
+    bench_blt,
+    bench_blt_paths,
+    bench_pixels,
+    bench_shapes,
+    bench_bltm,
+    bench_palette
+);
+criterion_main!(benches);
@@ -0,0 +1,25 @@
+#![allow(dead_code)]
+
+use std::env;
+
+use once_cell::sync::Lazy;
+use pyxel::Pyxel;
+
+// Setting this runs an example for a fixed number of frames, so that it can be used as a smoke test
+static FRAME_LIMIT: Lazy<Option<u32>> = Lazy::new(|| {
+    env::var("PYXEL_EXAMPLE_FRAMES")
+        .ok()
+        .and_then(|frames| frames.parse().ok())
+});
+
+pub fn asset_path(filename: &str) -> String {
+    format!(
+        "{}/../../python/pyxel/examples/assets/{filename}",
+        env!("CARGO_MANIFEST_DIR")
+    )
+}
+
+pub fn should_quit(pyxel: &mut Pyxel) -> bool {
+    pyxel.btnp(pyxel::KEY_Q, None, None)
+        || FRAME_LIMIT.is_some_and(|frame_limit| pyxel.frame_count >= frame_limit)
+}
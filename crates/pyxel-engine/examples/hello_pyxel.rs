@@ -0,0 +1,31 @@
+mod common;
+
+use pyxel::{Pyxel, PyxelCallback};
+
+struct App;
+
+impl PyxelCallback for App {
+    fn update(&mut self, pyxel: &mut Pyxel) {
+        if common::should_quit(pyxel) {
+            pyxel.quit();
+        }
+    }
+
+    fn draw(&mut self, pyxel: &mut Pyxel) {
+        pyxel.cls(0);
+        pyxel.text(55.0, 41.0, "Hello, Pyxel!", (pyxel.frame_count % 16) as u8);
+        pyxel.blt(61.0, 66.0, 0, 0.0, 0.0, 38.0, 16.0, None);
+    }
+}
+
+fn main() {
+    let mut pyxel = Pyxel::builder(160, 120)
+        .title("Hello Pyxel")
+        .build()
+        .unwrap();
+    pyxel.images.lock()[0]
+        .lock()
+        .load(0, 0, &common::asset_path("pyxel_logo_38x16.png"), None)
+        .unwrap();
+    pyxel.run(App);
+}
@@ -0,0 +1,210 @@
+mod common;
+
+use pyxel::{Pyxel, PyxelCallback};
+
+const TRANSPARENT_COLOR: u8 = 12;
+
+struct Floor {
+    x: i32,
+    y: i32,
+    is_alive: bool,
+}
+
+struct Fruit {
+    x: i32,
+    y: i32,
+    kind: i32,
+    is_alive: bool,
+}
+
+struct App {
+    score: i32,
+    player_x: i32,
+    player_y: i32,
+    player_dy: i32,
+    is_alive: bool,
+    far_clouds: [(i32, i32); 3],
+    near_clouds: [(i32, i32); 3],
+    floors: Vec<Floor>,
+    fruits: Vec<Fruit>,
+}
+
+impl App {
+    fn new(pyxel: &mut Pyxel) -> Self {
+        Self {
+            score: 0,
+            player_x: 72,
+            player_y: -16,
+            player_dy: 0,
+            is_alive: true,
+            far_clouds: [(-10, 75), (40, 65), (90, 60)],
+            near_clouds: [(10, 25), (70, 35), (120, 15)],
+            floors: (0..4)
+                .map(|i| Floor {
+                    x: i * 60,
+                    y: pyxel.rndi(8, 104),
+                    is_alive: true,
+                })
+                .collect(),
+            fruits: (0..4)
+                .map(|i| Fruit {
+                    x: i * 60,
+                    y: pyxel.rndi(0, 104),
+                    kind: pyxel.rndi(0, 2),
+                    is_alive: true,
+                })
+                .collect(),
+        }
+    }
+
+    fn update_player(&mut self, pyxel: &mut Pyxel) {
+        if pyxel.btn(pyxel::KEY_LEFT) || pyxel.btn(pyxel::GAMEPAD1_BUTTON_DPAD_LEFT) {
+            self.player_x = (self.player_x - 2).max(0);
+        }
+        if pyxel.btn(pyxel::KEY_RIGHT) || pyxel.btn(pyxel::GAMEPAD1_BUTTON_DPAD_RIGHT) {
+            self.player_x = (self.player_x + 2).min(pyxel.width as i32 - 16);
+        }
+        self.player_y += self.player_dy;
+        self.player_dy = (self.player_dy + 1).min(8);
+
+        if self.player_y > pyxel.height as i32 {
+            if self.is_alive {
+                self.is_alive = false;
+                pyxel.play1(3, 5, None, false);
+            }
+            if self.player_y > 600 {
+                self.score = 0;
+                self.player_x = 72;
+                self.player_y = -16;
+                self.player_dy = 0;
+                self.is_alive = true;
+            }
+        }
+    }
+
+    fn update_floors(&mut self, pyxel: &mut Pyxel) {
+        for floor in &mut self.floors {
+            if floor.is_alive {
+                if self.player_x + 16 >= floor.x
+                    && self.player_x <= floor.x + 40
+                    && self.player_y + 16 >= floor.y
+                    && self.player_y <= floor.y + 8
+                    && self.player_dy > 0
+                {
+                    floor.is_alive = false;
+                    self.score += 10;
+                    self.player_dy = -12;
+                    pyxel.play1(3, 3, None, false);
+                }
+            } else {
+                floor.y += 6;
+            }
+            floor.x -= 4;
+            if floor.x < -40 {
+                floor.x += 240;
+                floor.y = pyxel.rndi(8, 104);
+                floor.is_alive = true;
+            }
+        }
+    }
+
+    fn update_fruits(&mut self, pyxel: &mut Pyxel) {
+        for fruit in &mut self.fruits {
+            if fruit.is_alive
+                && (fruit.x - self.player_x).abs() < 12
+                && (fruit.y - self.player_y).abs() < 12
+            {
+                fruit.is_alive = false;
+                self.score += (fruit.kind + 1) * 100;
+                self.player_dy = self.player_dy.min(-8);
+                pyxel.play1(3, 4, None, false);
+            }
+            fruit.x -= 2;
+            if fruit.x < -40 {
+                fruit.x += 240;
+                fruit.y = pyxel.rndi(0, 104);
+                fruit.kind = pyxel.rndi(0, 2);
+                fruit.is_alive = true;
+            }
+        }
+    }
+}
+
+impl PyxelCallback for App {
+    fn update(&mut self, pyxel: &mut Pyxel) {
+        if common::should_quit(pyxel) {
+            pyxel.quit();
+        }
+        self.update_player(pyxel);
+        self.update_floors(pyxel);
+        self.update_fruits(pyxel);
+    }
+
+    fn draw(&mut self, pyxel: &mut Pyxel) {
+        let key = Some(TRANSPARENT_COLOR);
+        pyxel.cls(12);
+
+        // Draw sky and mountain
+        pyxel.blt(0, 88, 0, 0, 88, 160, 32, None);
+        pyxel.blt(0, 88, 0, 0, 64, 160, 24, key);
+
+        // Draw trees
+        let offset = (pyxel.frame_count % 160) as i32;
+        for i in 0..2 {
+            pyxel.blt(i * 160 - offset, 104, 0, 0, 48, 160, 16, key);
+        }
+
+        // Draw clouds
+        let offset = ((pyxel.frame_count / 16) % 160) as i32;
+        for i in 0..2 {
+            for (x, y) in self.far_clouds {
+                pyxel.blt(x + i * 160 - offset, y, 0, 64, 32, 32, 8, key);
+            }
+        }
+        let offset = ((pyxel.frame_count / 8) % 160) as i32;
+        for i in 0..2 {
+            for (x, y) in self.near_clouds {
+                pyxel.blt(x + i * 160 - offset, y, 0, 0, 32, 56, 8, key);
+            }
+        }
+
+        // Draw floors and fruits
+        for floor in &self.floors {
+            pyxel.blt(floor.x, floor.y, 0, 0, 16, 40, 8, key);
+        }
+        for fruit in self.fruits.iter().filter(|fruit| fruit.is_alive) {
+            pyxel.blt(fruit.x, fruit.y, 0, 32 + fruit.kind * 16, 0, 16, 16, key);
+        }
+
+        // Draw player
+        let u = if self.player_dy > 0 { 16 } else { 0 };
+        pyxel.blt(self.player_x, self.player_y, 0, u, 0, 16, 16, key);
+
+        // Draw score
+        let score = format!("SCORE {:>4}", self.score);
+        pyxel.text(5.0, 4.0, &score, 1);
+        pyxel.text(4.0, 4.0, &score, 7);
+    }
+}
+
+fn main() {
+    let mut pyxel = Pyxel::builder(160, 120)
+        .title("Pyxel Jump")
+        .build()
+        .unwrap();
+    pyxel
+        .load(
+            &common::asset_path("jump_game.pyxres"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+    let app = App::new(&mut pyxel);
+    pyxel.playm(0, None, true);
+    pyxel.run(app);
+}
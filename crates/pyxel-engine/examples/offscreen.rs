@@ -0,0 +1,61 @@
+mod common;
+
+use pyxel::{Image, Pyxel, PyxelCallback, SharedImage};
+
+const FIGURE_WIDTH: u32 = 64;
+const FIGURE_HEIGHT: u32 = 64;
+
+struct App {
+    figure: SharedImage,
+}
+
+impl App {
+    // Drawing functions write into the figure while it is the render target
+    fn render_figure(&self, pyxel: &mut Pyxel) {
+        pyxel.set_render_target(self.figure.clone());
+        pyxel.cls(1);
+        for i in 0..8 {
+            let angle = pyxel.frame_count as f64 * 3.0 + i as f64 * 45.0;
+            let x = 32.0 + pyxel.cos(angle) * 20.0;
+            let y = 32.0 + pyxel.sin(angle) * 20.0;
+            pyxel.circ(x, y, 4.0, 8 + i as u8);
+        }
+        pyxel.text(12.0, 29.0, "OFFSCREEN", 7);
+        pyxel.reset_render_target();
+    }
+}
+
+impl PyxelCallback for App {
+    fn update(&mut self, pyxel: &mut Pyxel) {
+        if common::should_quit(pyxel) {
+            pyxel.quit();
+        }
+    }
+
+    fn draw(&mut self, pyxel: &mut Pyxel) {
+        self.render_figure(pyxel);
+        pyxel.cls(5);
+        let mut screen = pyxel.screen.lock();
+        for (x, y) in [(8, 8), (88, 8), (48, 56)] {
+            screen.blt(
+                x,
+                y,
+                self.figure.clone(),
+                0,
+                0,
+                FIGURE_WIDTH as i32,
+                FIGURE_HEIGHT as i32,
+                None,
+            );
+        }
+    }
+}
+
+fn main() {
+    let mut pyxel = Pyxel::builder(160, 128)
+        .title("Offscreen Rendering")
+        .build()
+        .unwrap();
+    let figure = Image::new(FIGURE_WIDTH, FIGURE_HEIGHT);
+    pyxel.run(App { figure });
+}
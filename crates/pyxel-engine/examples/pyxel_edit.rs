@@ -0,0 +1,16 @@
+use std::env;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() > 2 {
+        eprintln!("usage:");
+        eprintln!("    pyxel_edit [PYXEL_RESOURCE_FILE(.pyxres)]");
+        process::exit(1);
+    }
+    let filename = args.get(1).map_or("my_resource.pyxres", String::as_str);
+    if let Err(err) = pyxel::edit(filename) {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,25 @@
+use std::env;
+use std::process;
+
+use pyxel::AppMetadata;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 || args.len() > 6 {
+        println!("usage:");
+        println!("    pyxel_pack APP_DIR STARTUP_SCRIPT_FILE(.py) [TITLE] [AUTHOR] [VERSION]");
+        process::exit(1);
+    }
+    let metadata = AppMetadata {
+        title: args.get(3).cloned(),
+        author: args.get(4).cloned(),
+        version: args.get(5).cloned(),
+    };
+    match pyxel::pack_app(&args[1], &args[2], &metadata) {
+        Ok(app_filename) => println!("created '{app_filename}'"),
+        Err(err) => {
+            println!("{err}");
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use pyxel::{Pyxel, PyxelCallback};
+
+const SEQUENCES: [&[u32]; 3] = [&[0, 1], &[2, 3], &[4]];
+
+struct App;
+
+impl App {
+    fn play_music(pyxel: &Pyxel, ch0: bool, ch1: bool, ch2: bool) {
+        for (i, is_enabled) in [ch0, ch1, ch2].into_iter().enumerate() {
+            if is_enabled {
+                pyxel.play(i as u32, SEQUENCES[i], None, true);
+            } else {
+                pyxel.stop(i as u32);
+            }
+        }
+    }
+}
+
+impl PyxelCallback for App {
+    fn update(&mut self, pyxel: &mut Pyxel) {
+        if common::should_quit(pyxel) {
+            pyxel.quit();
+        }
+        if pyxel.btnp(pyxel::KEY_1, None, None) {
+            Self::play_music(pyxel, true, true, true);
+        }
+        if pyxel.btnp(pyxel::KEY_2, None, None) {
+            Self::play_music(pyxel, true, false, false);
+        }
+        if pyxel.btnp(pyxel::KEY_3, None, None) {
+            Self::play_music(pyxel, false, true, false);
+        }
+        if pyxel.btnp(pyxel::KEY_4, None, None) {
+            Self::play_music(pyxel, false, false, true);
+        }
+        if pyxel.btnp(pyxel::KEY_5, None, None) {
+            Self::play_music(pyxel, false, false, false);
+        }
+    }
+
+    fn draw(&mut self, pyxel: &mut Pyxel) {
+        pyxel.cls(1);
+
+        pyxel.text(
+            6.0,
+            6.0,
+            "sounds[snd].set(note,tone,volume,effect,speed)",
+            7,
+        );
+        pyxel.rect(12, 14, 177, 35, 2);
+        pyxel.text(16.0, 17.0, "note  :[CDEFGAB] + [ #-] + [0-4] or [R]", 9);
+        pyxel.text(16.0, 25.0, "tone  :[T]riangle [S]quare [P]ulse [N]oise", 9);
+        pyxel.text(16.0, 33.0, "volume:[0-7]", 9);
+        pyxel.text(16.0, 41.0, "effect:[N]one [S]lide [V]ibrato [F]adeOut", 9);
+        pyxel.text(6.0, 53.0, "musics[msc].set(seq0,seq1,seq2,...)", 7);
+        pyxel.text(6.0, 62.0, "play(ch,snd,loop=False)", 7);
+        pyxel.text(6.0, 71.0, "playm(msc,loop=False)", 7);
+        pyxel.text(6.0, 80.0, "stop([ch])", 7);
+
+        pyxel.rectb(6, 97, 188, 47, 14);
+        pyxel.rect(6, 91, 29, 7, 14);
+        pyxel.text(7.0, 92.0, "CONTROL", 1);
+        pyxel.text(12.0, 102.0, "1: Play all channels", 14);
+        pyxel.text(12.0, 110.0, "2: Play channel #0 (Melody)", 14);
+        pyxel.text(12.0, 118.0, "3: Play channel #1 (Bass)", 14);
+        pyxel.text(12.0, 126.0, "4: Play channel #2 (Drums)", 14);
+        pyxel.text(12.0, 134.0, "5: Stop playing", 14);
+        pyxel.text(137.0, 107.0, "play_pos(ch)", 15);
+
+        for i in 0..3 {
+            let x = 140.0 + i as f64 * 16.0;
+            let y = 123.0 + pyxel.sin(pyxel.frame_count as f64 * 5.73 + i as f64 * 120.3) * 5.0;
+            let color = if pyxel.play_pos(i).is_some() { 15 } else { 13 };
+            pyxel.pal(1, color);
+            pyxel.blt(x, y, 0, 0.0, 0.0, 8.0, 8.0, Some(0));
+        }
+        pyxel.pal0();
+    }
+}
+
+fn main() {
+    let mut pyxel = Pyxel::builder(200, 150)
+        .title("Pyxel Sound API")
+        .build()
+        .unwrap();
+    pyxel.images.lock()[0].lock().set(
+        0,
+        0,
+        &[
+            "00011000", "00010100", "00010010", "00010010", "00010100", "00010000", "01110000",
+            "01100000",
+        ],
+    );
+
+    let sounds = pyxel.sounds.lock();
+    sounds[0].lock().set(
+        "e2e2c2g1 g1g1c2e2 d2d2d2g2 g2g2rr c2c2a1e1 e1e1a1c2 b1b1b1e2 e2e2rr",
+        "p",
+        "6",
+        "vffn fnff vffs vfnn",
+        25,
+    );
+    sounds[1].lock().set(
+        "r a1b1c2 b1b1c2d2 g2g2g2g2 c2c2d2e2 f2f2f2e2 f2e2d2c2 d2d2d2d2 g2g2r r ",
+        "s",
+        "6",
+        "nnff vfff vvvv vfff svff vfff vvvv svnn",
+        25,
+    );
+    sounds[2].lock().set(
+        "c1g1c1g1 c1g1c1g1 b0g1b0g1 b0g1b0g1 a0e1a0e1 a0e1a0e1 g0d1g0d1 g0d1g0d1",
+        "t",
+        "7",
+        "n",
+        25,
+    );
+    sounds[3].lock().set(
+        "f0c1f0c1 g0d1g0d1 c1g1c1g1 a0e1a0e1 f0c1f0c1 f0c1f0c1 g0d1g0d1 g0d1g0d1",
+        "t",
+        "7",
+        "n",
+        25,
+    );
+    sounds[4].lock().set(
+        "f0ra4r f0ra4r f0ra4r f0f0a4r",
+        "n",
+        "6622 6622 6622 6422",
+        "f",
+        25,
+    );
+    drop(sounds);
+
+    App::play_music(&pyxel, true, true, true);
+    pyxel.run(App);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use pyxel::{Pyxel, PyxelCallback};
+
+const TRANSPARENT_COLOR: u8 = 2;
+const SCROLL_SPEED: i32 = 2;
+const MAX_SCROLL_X: i32 = 240 * 8 - 128;
+
+struct App {
+    scroll_x: i32,
+    is_auto_scroll: bool,
+}
+
+impl PyxelCallback for App {
+    fn update(&mut self, pyxel: &mut Pyxel) {
+        if common::should_quit(pyxel) {
+            pyxel.quit();
+        }
+        let dx = i32::from(pyxel.btn(pyxel::KEY_RIGHT)) - i32::from(pyxel.btn(pyxel::KEY_LEFT));
+        if dx != 0 {
+            self.is_auto_scroll = false;
+        }
+        let dx = if self.is_auto_scroll { 1 } else { dx };
+        self.scroll_x = (self.scroll_x + dx * SCROLL_SPEED).clamp(0, MAX_SCROLL_X);
+        if self.is_auto_scroll && self.scroll_x == MAX_SCROLL_X {
+            self.scroll_x = 0;
+        }
+    }
+
+    fn draw(&mut self, pyxel: &mut Pyxel) {
+        pyxel.cls(0);
+        pyxel.bltm(0, 0, 0, self.scroll_x, 0, 128, 128, Some(TRANSPARENT_COLOR));
+        pyxel.text(4.0, 4.0, &format!("SCROLL X:{}", self.scroll_x), 7);
+    }
+}
+
+fn main() {
+    let mut pyxel = Pyxel::builder(128, 128)
+        .title("Tilemap Scroll")
+        .build()
+        .unwrap();
+    pyxel
+        .load(
+            &common::asset_path("platformer.pyxres"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+    pyxel.run(App {
+        scroll_x: 0,
+        is_auto_scroll: true,
+    });
+}
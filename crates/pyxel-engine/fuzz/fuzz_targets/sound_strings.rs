@@ -0,0 +1,7 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|string: &str| {
+    pyxel::fuzz_sound_strings(string);
+});
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::error::{PyxelError, PyxelResult};
+use crate::pyxel::Pyxel;
+use crate::settings::{APP_FILE_EXTENSION, APP_METADATA_FILE, APP_STARTUP_SCRIPT_FILE};
+use crate::vfs::{self, FileSystem};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct AppMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub version: Option<String>,
+}
+
+pub struct AppInfo {
+    pub startup_script: String,
+    pub metadata: AppMetadata,
+}
+
+struct AppFs {
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl FileSystem for AppFs {
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        self.files.get(&vfs::normalize_path(path)).cloned()
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.is_dir() {
+            if !path.ends_with("__pycache__") {
+                collect_files(&path, files);
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+}
+
+// Uses the same layout as the 'pyxel package' command, so either tool can play the result
+pub fn pack_app(
+    app_dir: &str,
+    startup_script_file: &str,
+    metadata: &AppMetadata,
+) -> PyxelResult<String> {
+    let app_path =
+        fs::canonicalize(app_dir).map_err(|_| PyxelError::FileOpen(app_dir.to_string()))?;
+    let script_path = fs::canonicalize(startup_script_file)
+        .map_err(|_| PyxelError::FileOpen(startup_script_file.to_string()))?;
+    let startup_script = script_path
+        .strip_prefix(&app_path)
+        .map_err(|_| PyxelError::InvalidFile(startup_script_file.to_string()))?
+        .to_string_lossy()
+        .replace('\\', "/");
+    let app_name = app_path
+        .file_name()
+        .map_or(String::new(), |name| name.to_string_lossy().to_string());
+    let app_filename = app_name.clone() + APP_FILE_EXTENSION;
+
+    let mut files = Vec::new();
+    collect_files(&app_path, &mut files);
+    files.sort();
+
+    let write_error = || PyxelError::FileWrite(app_filename.clone());
+    let file = File::create(Path::new(&app_filename)).map_err(|_| write_error())?;
+    let mut zip = ZipWriter::new(file);
+    let metadata_text = toml::to_string(metadata).unwrap();
+    for (name, data) in [
+        (APP_STARTUP_SCRIPT_FILE, startup_script.as_bytes()),
+        (APP_METADATA_FILE, metadata_text.as_bytes()),
+    ] {
+        zip.start_file(format!("{app_name}/{name}"), FileOptions::default())
+            .map_err(|_| write_error())?;
+        zip.write_all(data).map_err(|_| write_error())?;
+    }
+    for path in files {
+        let relative_path = path.strip_prefix(&app_path).unwrap();
+        let relative_name = relative_path.to_string_lossy();
+        if relative_name.ends_with(APP_FILE_EXTENSION)
+            || relative_name == APP_STARTUP_SCRIPT_FILE
+            || relative_name == APP_METADATA_FILE
+        {
+            continue;
+        }
+        let data = fs::read(&path).map_err(|_| PyxelError::FileOpen(path.display().to_string()))?;
+        let arcname = format!("{app_name}/{}", relative_name.replace('\\', "/"));
+        zip.start_file(arcname, FileOptions::default())
+            .map_err(|_| write_error())?;
+        zip.write_all(&data).map_err(|_| write_error())?;
+    }
+    zip.finish().map_err(|_| write_error())?;
+    Ok(app_filename)
+}
+
+impl Pyxel {
+    // Files in the app can then be loaded by their paths relative to the app directory
+    pub fn mount_app(&mut self, filename: &str) -> PyxelResult<AppInfo> {
+        let invalid_file = || PyxelError::InvalidFile(filename.to_string());
+        let data =
+            vfs::read_file(filename).ok_or_else(|| PyxelError::FileOpen(filename.to_string()))?;
+        let mut archive = ZipArchive::new(Cursor::new(data)).map_err(|_| invalid_file())?;
+
+        let mut files = HashMap::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i).map_err(|_| invalid_file())?;
+            if file.is_dir() {
+                continue;
+            }
+            let Some((_, path)) = file.name().split_once('/') else {
+                continue;
+            };
+            let path = path.to_string();
+            let mut data = Vec::new();
+            file.read_to_end(&mut data).map_err(|_| invalid_file())?;
+            files.insert(path, data);
+        }
+
+        let startup_script = files
+            .get(APP_STARTUP_SCRIPT_FILE)
+            .and_then(|data| String::from_utf8(data.clone()).ok())
+            .ok_or_else(invalid_file)?;
+        let metadata = files
+            .get(APP_METADATA_FILE)
+            .and_then(|data| std::str::from_utf8(data).ok())
+            .and_then(|text| toml::from_str(text).ok())
+            .unwrap_or_default();
+        self.mount(AppFs { files });
+        Ok(AppInfo {
+            startup_script,
+            metadata,
+        })
+    }
+}
@@ -1,25 +1,261 @@
 use std::cmp::min;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Instant;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 
 use crate::blip_buf::BlipBuf;
+use crate::channel::{self, Channel};
+use crate::dsp::Reverb;
+use crate::oscillator::Gain;
 use crate::pyxel::{Pyxel, CHANNELS};
-use crate::settings::{CLOCK_RATE, NUM_CLOCKS_PER_TICK, NUM_SAMPLES, SAMPLE_RATE};
+use crate::settings::{
+    CLOCK_RATE, NUM_CLOCKS_PER_TICK, NUM_OUTPUT_CHANNELS, NUM_SAMPLES, SAMPLE_RATE,
+};
+use crate::sound::Sound;
+use crate::stream;
 use crate::SharedChannel;
 
+static MASTER_VOLUME: Lazy<shared_type!(Gain)> = Lazy::new(|| new_shared_type!(1.0));
+// Callbacks that took longer than the audio they produced. The device's own underruns
+// can't be queried, but these are what cause them.
+static NUM_SLOW_CALLBACKS: AtomicU32 = AtomicU32::new(0);
+static PENDING_COMMANDS: Lazy<Mutex<PendingCommands>> = Lazy::new(|| {
+    Mutex::new(PendingCommands {
+        is_audio_running: false,
+        commands: Vec::new(),
+    })
+});
+
+// Renders the sequences with the settings of the channels they would play on and returns
+// interleaved stereo samples, cut to max_frames frames if given
+pub(crate) fn render_sounds(
+    sound_seqs: Vec<Vec<Sound>>,
+    sample_rate: u32,
+    max_frames: Option<usize>,
+) -> Vec<i16> {
+    let channels: Vec<SharedChannel> = {
+        let live_channels = CHANNELS.lock();
+        sound_seqs
+            .into_iter()
+            .enumerate()
+            .map(|(index, sounds)| {
+                let channel = live_channels
+                    .get(index)
+                    .map_or_else(Channel::new, |channel| channel.lock().copy_settings());
+                channel.lock().play_sounds(sounds, None, false);
+                channel
+            })
+            .collect()
+    };
+    let mut audio_core = AudioCore::new(
+        sample_rate,
+        sample_rate,
+        new_shared_type!(channels),
+        MASTER_VOLUME.clone(),
+    );
+    let mut buffer = vec![0; sample_rate as usize * NUM_OUTPUT_CHANNELS as usize];
+    let max_samples = max_frames.map(|max_frames| max_frames * NUM_OUTPUT_CHANNELS as usize);
+    let mut samples = Vec::new();
+    while max_samples.map_or(true, |max_samples| samples.len() < max_samples) {
+        if !audio_core.update_channels() {
+            break;
+        }
+        let num_frames = audio_core.read_frames(&mut buffer);
+        let out = &mut buffer[..num_frames * NUM_OUTPUT_CHANNELS as usize];
+        audio_core.apply_master_volume(out);
+        samples.extend_from_slice(out);
+    }
+    if let Some(max_samples) = max_samples {
+        samples.truncate(max_samples);
+    }
+    samples
+}
+
+enum AudioCommand {
+    Play {
+        channel_index: u32,
+        sounds: Vec<Sound>,
+        start_tick: Option<u32>,
+        should_loop: bool,
+    },
+    Stop {
+        channel_index: u32,
+    },
+}
+
+impl AudioCommand {
+    const fn channel_index(&self) -> u32 {
+        match self {
+            Self::Play { channel_index, .. } | Self::Stop { channel_index } => *channel_index,
+        }
+    }
+
+    fn play_pos(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::Play {
+                sounds,
+                start_tick,
+                should_loop,
+                ..
+            } => channel::start_position(sounds, start_tick.unwrap_or(0), *should_loop)
+                .map(|(sound_index, note_index, _)| (sound_index, note_index)),
+            Self::Stop { .. } => None,
+        }
+    }
+
+    fn execute(self, channel: &SharedChannel) {
+        let mut channel = channel.lock();
+        match self {
+            Self::Play {
+                sounds,
+                start_tick,
+                should_loop,
+                ..
+            } => channel.play_sounds(sounds, start_tick, should_loop),
+            Self::Stop { .. } => channel.stop(),
+        }
+    }
+
+    // While an audio device is running, the command is applied at the start of its next
+    // callback, so playback starts or stops with a latency of up to one audio buffer.
+    // Only the latest command per channel is kept, because each one replaces the
+    // playback state entirely, so commands don't pile up while the device is paused.
+    // Commands for channels that don't exist are ignored, as in play_pos
+    fn send(self) {
+        let channel_index = self.channel_index();
+        let Some(channel) = CHANNELS.lock().get(channel_index as usize).cloned() else {
+            return;
+        };
+        // Like Channel::play_sounds, a play with nothing to play leaves the channel as it is
+        if matches!(self, Self::Play { .. }) && self.play_pos().is_none() {
+            return;
+        }
+        let mut pending = PENDING_COMMANDS.lock();
+        if !pending.is_audio_running {
+            drop(pending);
+            self.execute(&channel);
+            return;
+        }
+        let index = channel_index as usize;
+        if pending.commands.len() <= index {
+            pending.commands.resize_with(index + 1, || None);
+        }
+        pending.commands[index] = Some(self);
+    }
+}
+
+struct PendingCommands {
+    is_audio_running: bool,
+    commands: Vec<Option<AudioCommand>>,
+}
+
+impl PendingCommands {
+    fn apply(&mut self, channels: &[SharedChannel]) {
+        for (channel, command) in channels.iter().zip(self.commands.drain(..)) {
+            if let Some(command) = command {
+                command.execute(channel);
+            }
+        }
+    }
+}
+
 struct AudioCore {
-    blip_buf: BlipBuf,
+    blip_bufs: [BlipBuf; NUM_OUTPUT_CHANNELS as usize + 1],
+    reverb: Reverb,
+    reverb_buffer: Vec<i16>,
     channels: shared_type!(Vec<SharedChannel>),
+    master_volume: shared_type!(Gain),
 }
 
 impl pyxel_platform::AudioCallback for AudioCore {
     fn update(&mut self, out: &mut [i16]) {
-        let mut samples = self.blip_buf.read_samples(out, false);
-        while samples < out.len() {
-            for channel in &*self.channels.lock() {
-                channel.lock().update(&mut self.blip_buf);
+        let start_time = Instant::now();
+        // The game thread holds this lock only briefly, and a missed update is retried
+        // on the next callback
+        if let Some(mut pending) = PENDING_COMMANDS.try_lock() {
+            pending.apply(&self.channels.lock());
+        }
+        let num_frames = out.len() / NUM_OUTPUT_CHANNELS as usize;
+        let mut frames = self.read_frames(out);
+        while frames < num_frames {
+            self.update_channels();
+            frames += self.read_frames(&mut out[frames * NUM_OUTPUT_CHANNELS as usize..]);
+        }
+        stream::mix_stream(out);
+        self.apply_master_volume(out);
+        // Logging here could block the audio thread, so the game thread logs the count
+        if start_time.elapsed().as_secs_f64() > num_frames as f64 / SAMPLE_RATE as f64 {
+            NUM_SLOW_CALLBACKS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl AudioCore {
+    fn new(
+        buffer_size: u32,
+        sample_rate: u32,
+        channels: shared_type!(Vec<SharedChannel>),
+        master_volume: shared_type!(Gain),
+    ) -> Self {
+        let blip_bufs = std::array::from_fn(|_| {
+            let mut blip_buf = BlipBuf::new(buffer_size as usize);
+            blip_buf.set_rates(CLOCK_RATE as f64, sample_rate as f64);
+            blip_buf
+        });
+        Self {
+            blip_bufs,
+            reverb: Reverb::new(sample_rate),
+            reverb_buffer: Vec::new(),
+            channels,
+            master_volume,
+        }
+    }
+
+    // Renders one tick of every channel and returns whether any of them is still playing
+    fn update_channels(&mut self) -> bool {
+        let mut is_playing = false;
+        for channel in &*self.channels.lock() {
+            let mut channel = channel.lock();
+            channel.update(&mut self.blip_bufs);
+            is_playing |= channel.play_pos().is_some();
+        }
+        for blip_buf in &mut self.blip_bufs {
+            blip_buf.end_frame(NUM_CLOCKS_PER_TICK as u64);
+        }
+        is_playing
+    }
+
+    fn apply_master_volume(&self, out: &mut [i16]) {
+        let master_volume = *self.master_volume.lock();
+        if master_volume < 1.0 {
+            for sample in out.iter_mut() {
+                *sample = (*sample as f64 * master_volume) as i16;
+            }
+        }
+    }
+
+    // Interleaves the left and right buffers into the output and adds the reverb send bus
+    fn read_frames(&mut self, out: &mut [i16]) -> usize {
+        let [left_blip_buf, right_blip_buf, reverb_blip_buf] = &mut self.blip_bufs;
+        let frames = left_blip_buf.read_samples(out, true);
+        if out.len() > 1 {
+            right_blip_buf.read_samples(&mut out[1..], true);
+        }
+        self.reverb_buffer.resize(frames, 0);
+        reverb_blip_buf.read_samples(&mut self.reverb_buffer, false);
+        for (out_frame, send) in out
+            .chunks_mut(NUM_OUTPUT_CHANNELS as usize)
+            .zip(&self.reverb_buffer)
+        {
+            let reverb = self.reverb.process(*send as f64);
+            for out_sample in out_frame {
+                *out_sample =
+                    (*out_sample as f64 + reverb).clamp(i16::MIN as f64, i16::MAX as f64) as i16;
             }
-            self.blip_buf.end_frame(NUM_CLOCKS_PER_TICK as u64);
-            samples += self.blip_buf.read_samples(&mut out[samples..], false);
         }
+        frames
     }
 }
 
@@ -27,19 +263,33 @@ pub struct Audio {}
 
 impl Audio {
     pub fn new(sample_rate: u32, num_samples: u32) -> Self {
-        let mut blip_buf = BlipBuf::new(NUM_SAMPLES as usize);
-        blip_buf.set_rates(CLOCK_RATE as f64, SAMPLE_RATE as f64);
+        PENDING_COMMANDS.lock().is_audio_running = true;
         pyxel_platform::start_audio(
             sample_rate,
-            1,
+            NUM_OUTPUT_CHANNELS as u8,
             num_samples as u16,
-            new_shared_type!(AudioCore {
-                blip_buf,
-                channels: CHANNELS.clone()
-            }),
+            new_shared_type!(AudioCore::new(
+                NUM_SAMPLES,
+                SAMPLE_RATE,
+                CHANNELS.clone(),
+                MASTER_VOLUME.clone(),
+            )),
         );
         Self {}
     }
+
+    pub fn reset() {
+        *MASTER_VOLUME.lock() = 1.0;
+        NUM_SLOW_CALLBACKS.store(0, Ordering::Relaxed);
+    }
+
+    // Called before the device shuts down, so that later commands are applied at once
+    // instead of waiting for a callback that never comes
+    pub fn stop() {
+        let mut pending = PENDING_COMMANDS.lock();
+        pending.is_audio_running = false;
+        pending.commands.clear();
+    }
 }
 
 impl Pyxel {
@@ -55,11 +305,15 @@ impl Pyxel {
         }
         let sounds = sequence
             .iter()
-            .map(|sound_index| self.sounds.lock()[*sound_index as usize].clone())
+            .map(|sound_index| self.sounds.lock()[*sound_index as usize].lock().clone())
             .collect();
-        self.channels.lock()[channel_index as usize]
-            .lock()
-            .play(sounds, start_tick, should_loop);
+        AudioCommand::Play {
+            channel_index,
+            sounds,
+            start_tick,
+            should_loop,
+        }
+        .send();
     }
 
     pub fn play1(
@@ -69,11 +323,13 @@ impl Pyxel {
         start_tick: Option<u32>,
         should_loop: bool,
     ) {
-        self.channels.lock()[channel_index as usize].lock().play1(
-            self.sounds.lock()[sound_index as usize].clone(),
+        AudioCommand::Play {
+            channel_index,
+            sounds: vec![self.sounds.lock()[sound_index as usize].lock().clone()],
             start_tick,
             should_loop,
-        );
+        }
+        .send();
     }
 
     pub fn playm(&self, music_index: u32, start_tick: Option<u32>, should_loop: bool) {
@@ -86,7 +342,7 @@ impl Pyxel {
     }
 
     pub fn stop(&self, channel_index: u32) {
-        self.channels.lock()[channel_index as usize].lock().stop();
+        AudioCommand::Stop { channel_index }.send();
     }
 
     pub fn stop0(&self) {
@@ -96,9 +352,126 @@ impl Pyxel {
         }
     }
 
+    // A command that the audio thread has not applied yet already decides the position
     pub fn play_pos(&self, channel_index: u32) -> Option<(u32, u32)> {
-        self.channels.lock()[channel_index as usize]
+        let pending = PENDING_COMMANDS.lock();
+        if let Some(Some(command)) = pending.commands.get(channel_index as usize) {
+            return command.play_pos();
+        }
+        self.channels
             .lock()
-            .play_pos()
+            .get(channel_index as usize)
+            .and_then(|channel| channel.lock().play_pos())
+    }
+
+    // Counted since init, so a rising count means the audio thread can't keep up
+    pub fn slow_audio_callbacks(&self) -> u32 {
+        NUM_SLOW_CALLBACKS.load(Ordering::Relaxed)
+    }
+
+    pub fn master_volume(&self) -> Gain {
+        *MASTER_VOLUME.lock()
+    }
+
+    pub fn set_master_volume(&self, volume: Gain) {
+        *MASTER_VOLUME.lock() = volume.clamp(0.0, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+    use crate::settings::INITIAL_CHANNEL_GAIN;
+
+    #[test]
+    fn test_pending_commands() {
+        let pyxel = headless_pyxel(16, 16);
+        pyxel.sounds.lock()[0]
+            .lock()
+            .set("c2e2g2", "s", "5", "", 10);
+        PENDING_COMMANDS.lock().is_audio_running = true;
+
+        // Positions reflect commands the audio thread has not applied yet
+        pyxel.play(1, &[0], Some(25), false);
+        assert_eq!(pyxel.play_pos(1), Some((0, 2)));
+        assert_eq!(pyxel.channels.lock()[1].lock().play_pos(), None);
+        pyxel.stop(1);
+        assert_eq!(pyxel.play_pos(1), None);
+        pyxel.play(1, &[0], None, true);
+        pyxel.play(1, &[0], Some(100), false);
+        assert_eq!(pyxel.play_pos(1), Some((0, 0)));
+        assert_eq!(PENDING_COMMANDS.lock().commands.len(), 2);
+
+        PENDING_COMMANDS.lock().apply(&CHANNELS.lock());
+        assert_eq!(pyxel.play_pos(1), Some((0, 0)));
+        pyxel.stop0();
+        PENDING_COMMANDS.lock().apply(&CHANNELS.lock());
+        assert_eq!(pyxel.play_pos(1), None);
+
+        PENDING_COMMANDS.lock().is_audio_running = false;
+        pyxel.play(1, &[0], None, false);
+        assert_eq!(pyxel.channels.lock()[1].lock().play_pos(), Some((0, 0)));
+        pyxel.stop(1);
+    }
+
+    #[test]
+    fn test_invalid_channel() {
+        let pyxel = headless_pyxel(16, 16);
+        pyxel.sounds.lock()[0].lock().set("c2", "s", "5", "", 10);
+        pyxel.play(99, &[0], None, false);
+        pyxel.stop(99);
+        assert_eq!(pyxel.play_pos(99), None);
+    }
+
+    #[test]
+    fn test_drop_stops_pending_commands() {
+        {
+            let pyxel = headless_pyxel(16, 16);
+            pyxel.sounds.lock()[0].lock().set("c2", "s", "5", "", 10);
+            PENDING_COMMANDS.lock().is_audio_running = true;
+            pyxel.play(0, &[0], None, true);
+            assert_eq!(PENDING_COMMANDS.lock().commands.len(), 1);
+        }
+
+        let pyxel = headless_pyxel(16, 16);
+        {
+            let pending = PENDING_COMMANDS.lock();
+            assert!(!pending.is_audio_running);
+            assert!(pending.commands.is_empty());
+        }
+        pyxel.play(0, &[0], None, false);
+        assert_eq!(pyxel.channels.lock()[0].lock().play_pos(), Some((0, 0)));
+        pyxel.stop(0);
+    }
+
+    #[test]
+    fn test_render_sounds_uses_channel_settings() {
+        let pyxel = headless_pyxel(16, 16);
+        let sound = Sound::new();
+        sound.lock().set("c2e2", "t", "7", "n", 12);
+        let sound = sound.lock().clone();
+        let render = |max_frames| render_sounds(vec![vec![sound.clone()]], SAMPLE_RATE, max_frames);
+        assert_eq!(render(Some(100)).len(), 200);
+
+        let samples = render(None);
+        assert!(samples.iter().step_by(2).any(|sample| *sample != 0));
+        assert!(samples.iter().skip(1).step_by(2).any(|sample| *sample != 0));
+
+        CHANNELS.lock()[0].lock().pan = -1.0;
+        let samples = render(None);
+        CHANNELS.lock()[0].lock().pan = 0.0;
+        assert!(samples.iter().step_by(2).any(|sample| *sample != 0));
+        assert!(samples.iter().skip(1).step_by(2).all(|sample| *sample == 0));
+
+        CHANNELS.lock()[0].lock().gain = 0.0;
+        let samples = render(None);
+        CHANNELS.lock()[0].lock().gain = INITIAL_CHANNEL_GAIN;
+        assert!(samples.iter().all(|sample| *sample == 0));
+
+        pyxel.set_master_volume(0.0);
+        let samples = render(None);
+        pyxel.set_master_volume(1.0);
+        assert!(samples.iter().all(|sample| *sample == 0));
     }
 }
@@ -0,0 +1,250 @@
+use std::cmp::max;
+
+use crate::image::{Color, SharedImage};
+use crate::keys::{KEY_PAGEDOWN, KEY_PAGEUP, KEY_SHIFT};
+use crate::pyxel::Pyxel;
+use crate::settings::{
+    COLOR_BLACK, COLOR_DARK_BLUE, COLOR_WHITE, COLOR_YELLOW, FONT_HEIGHT, TILE_SIZE,
+};
+use crate::tilemap::SharedTilemap;
+
+const HEADER_HEIGHT: i32 = FONT_HEIGHT as i32 + 2;
+
+enum BankPage {
+    Image(u32, SharedImage),
+    Tilemap(u32, SharedTilemap),
+}
+
+pub struct BankViewer {
+    pub visible: bool,
+    page: usize,
+    scroll_x: i32,
+    scroll_y: i32,
+    saved_screen: Option<Vec<Color>>,
+}
+
+impl BankViewer {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            page: 0,
+            scroll_x: 0,
+            scroll_y: 0,
+            saved_screen: None,
+        }
+    }
+}
+
+impl Pyxel {
+    pub(crate) fn update_bank_viewer(&mut self) {
+        if !self.system.bank_viewer.visible {
+            return;
+        }
+        let num_pages = self.images.lock().len() + self.tilemaps.lock().len();
+        if num_pages == 0 {
+            return;
+        }
+        let page = self.system.bank_viewer.page;
+        let new_page = if self.btnp(KEY_PAGEUP, None, None) {
+            (page + num_pages - 1) % num_pages
+        } else if self.btnp(KEY_PAGEDOWN, None, None) {
+            (page + 1) % num_pages
+        } else {
+            page % num_pages
+        };
+        if new_page != page {
+            self.system.bank_viewer.scroll_x = 0;
+            self.system.bank_viewer.scroll_y = 0;
+        }
+        self.system.bank_viewer.page = new_page;
+
+        let scroll = -self.mouse_wheel * TILE_SIZE as i32;
+        if self.btn(KEY_SHIFT) {
+            self.system.bank_viewer.scroll_x += scroll;
+        } else {
+            self.system.bank_viewer.scroll_y += scroll;
+        }
+        let Some(page) = self.bank_viewer_page() else {
+            return;
+        };
+        let (content_width, content_height) = page.content_size();
+        let (view_width, view_height) = self.bank_viewer_view_size();
+        let bank_viewer = &mut self.system.bank_viewer;
+        bank_viewer.scroll_x = bank_viewer
+            .scroll_x
+            .clamp(0, max(content_width - view_width, 0));
+        bank_viewer.scroll_y = bank_viewer
+            .scroll_y
+            .clamp(0, max(content_height - view_height, 0));
+    }
+
+    pub(crate) fn draw_bank_viewer(&mut self) {
+        if !self.system.bank_viewer.visible {
+            return;
+        }
+        let Some(page) = self.bank_viewer_page() else {
+            return;
+        };
+        self.system.bank_viewer.saved_screen = Some(self.screen.read().canvas.data.clone());
+        let (content_width, content_height) = page.content_size();
+        let (view_width, view_height) = self.bank_viewer_view_size();
+        let scroll_x = self.system.bank_viewer.scroll_x;
+        let scroll_y = self.system.bank_viewer.scroll_y;
+        let visible_width = view_width.min(content_width - scroll_x);
+        let visible_height = view_height.min(content_height - scroll_y);
+
+        let hover_x = self.mouse_x + scroll_x;
+        let hover_y = self.mouse_y - HEADER_HEIGHT + scroll_y;
+        let hovered = self.mouse_x >= 0
+            && self.mouse_x < visible_width
+            && self.mouse_y >= HEADER_HEIGHT
+            && self.mouse_y < HEADER_HEIGHT + visible_height;
+        let mut header = match &page {
+            BankPage::Image(index, _) => format!("IMAGE {index}"),
+            BankPage::Tilemap(index, _) => format!("TILEMAP {index}"),
+        };
+        if hovered {
+            header += &format!(" {hover_x},{hover_y}");
+            if let BankPage::Tilemap(_, tilemap) = &page {
+                let tile_x = hover_x / TILE_SIZE as i32;
+                let tile_y = hover_y / TILE_SIZE as i32;
+                let tile = tilemap
+                    .lock()
+                    .canvas
+                    .read_data(tile_x as usize, tile_y as usize);
+                header += &format!(" ({tile_x},{tile_y})={},{}", tile.0, tile.1);
+            }
+        }
+
+        self.draw_over_screen(|screen| {
+            screen.cls(COLOR_BLACK);
+            screen.text(1.0, 1.0, &header, COLOR_WHITE);
+            let top = HEADER_HEIGHT as f64;
+            match page {
+                BankPage::Image(_, image) => screen.blt(
+                    0.0,
+                    top,
+                    image,
+                    scroll_x as f64,
+                    scroll_y as f64,
+                    visible_width as f64,
+                    visible_height as f64,
+                    None,
+                ),
+                BankPage::Tilemap(_, tilemap) => screen.bltm(
+                    0.0,
+                    top,
+                    tilemap,
+                    scroll_x as f64,
+                    scroll_y as f64,
+                    visible_width as f64,
+                    visible_height as f64,
+                    None,
+                ),
+            }
+
+            screen.dither(0.5);
+            let tile_size = TILE_SIZE as i32;
+            let right = (visible_width - 1) as f64;
+            let bottom = top + (visible_height - 1) as f64;
+            let mut x = (tile_size - scroll_x % tile_size) % tile_size;
+            while x < visible_width {
+                screen.line(x as f64, top, x as f64, bottom, COLOR_DARK_BLUE);
+                x += tile_size;
+            }
+            let mut y = (tile_size - scroll_y % tile_size) % tile_size;
+            while y < visible_height {
+                screen.line(0.0, top + y as f64, right, top + y as f64, COLOR_DARK_BLUE);
+                y += tile_size;
+            }
+            screen.dither(1.0);
+
+            if hovered {
+                let cell_x = hover_x / tile_size * tile_size - scroll_x;
+                let cell_y = hover_y / tile_size * tile_size - scroll_y;
+                screen.rectb(
+                    cell_x as f64,
+                    top + cell_y as f64,
+                    TILE_SIZE as f64,
+                    TILE_SIZE as f64,
+                    COLOR_YELLOW,
+                );
+            }
+        });
+    }
+
+    // The viewer replaces the game's frame only on the display, so captures and the next
+    // frame still see what the game drew
+    pub(crate) fn restore_bank_viewer_screen(&mut self) {
+        if let Some(data) = self.system.bank_viewer.saved_screen.take() {
+            self.screen.lock().canvas.data = data;
+        }
+    }
+
+    // Banks can shrink while the viewer is open, so the page is clamped to the last one
+    fn bank_viewer_page(&self) -> Option<BankPage> {
+        let images = self.images.lock();
+        let tilemaps = self.tilemaps.lock();
+        let last_page = (images.len() + tilemaps.len()).checked_sub(1)?;
+        let page = self.system.bank_viewer.page.min(last_page);
+        Some(if page < images.len() {
+            BankPage::Image(page as u32, images[page].clone())
+        } else {
+            let index = page - images.len();
+            BankPage::Tilemap(index as u32, tilemaps[index].clone())
+        })
+    }
+
+    fn bank_viewer_view_size(&self) -> (i32, i32) {
+        (
+            self.width as i32,
+            max(self.height as i32 - HEADER_HEIGHT, 0),
+        )
+    }
+}
+
+impl BankPage {
+    fn content_size(&self) -> (i32, i32) {
+        match self {
+            Self::Image(_, image) => (image.width() as i32, image.height() as i32),
+            Self::Tilemap(_, tilemap) => {
+                let tilemap = tilemap.lock();
+                (
+                    (tilemap.width() * TILE_SIZE) as i32,
+                    (tilemap.height() * TILE_SIZE) as i32,
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pyxel::headless_pyxel;
+
+    #[test]
+    fn test_bank_viewer_page_out_of_range() {
+        let mut pyxel = headless_pyxel(16, 16);
+        pyxel.system.bank_viewer.visible = true;
+        pyxel.system.bank_viewer.page = 100;
+        pyxel.tilemaps.lock().clear();
+        pyxel.update_bank_viewer();
+        pyxel.draw_bank_viewer();
+
+        pyxel.images.lock().clear();
+        pyxel.system.bank_viewer.page = 100;
+        pyxel.update_bank_viewer();
+        pyxel.draw_bank_viewer();
+    }
+
+    #[test]
+    fn test_bank_viewer_restores_screen() {
+        let mut pyxel = headless_pyxel(16, 16);
+        pyxel.pset(3.0, 12.0, 7);
+        pyxel.system.bank_viewer.visible = true;
+        pyxel.draw_bank_viewer();
+        assert_eq!(pyxel.pget(3.0, 12.0), 0);
+        pyxel.restore_bank_viewer_screen();
+        assert_eq!(pyxel.pget(3.0, 12.0), 7);
+    }
+}
@@ -147,13 +147,14 @@ impl BlipBuf {
     }
 
     pub fn read_samples(&mut self, buf: &mut [i16], stereo: bool) -> usize {
-        let count = if buf.len() > self.avail as usize {
+        let step = if stereo { 2 } else { 1 };
+        let len = buf.len().div_ceil(step);
+        let count = if len > self.avail as usize {
             self.avail as usize
         } else {
-            buf.len()
+            len
         };
 
-        let step = if stereo { 2 } else { 1 };
         // let in = self.buf;
         let mut sum = self.integrator;
         let mut out = 0;
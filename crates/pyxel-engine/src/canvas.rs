@@ -1,14 +1,75 @@
 use std::cmp::max;
 use std::mem::swap;
+use std::sync::atomic::{AtomicU32, Ordering};
 
 use crate::rect_area::RectArea;
 use crate::utils::{f64_to_i32, f64_to_u32};
 
+static NUM_DRAW_CALLS: AtomicU32 = AtomicU32::new(0);
+static NUM_BLIT_PIXELS: AtomicU32 = AtomicU32::new(0);
+
+pub fn count_draw_call() {
+    NUM_DRAW_CALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn count_blit_pixels(num_pixels: u32) {
+    NUM_BLIT_PIXELS.fetch_add(num_pixels, Ordering::Relaxed);
+}
+
+pub fn take_draw_stats() -> (u32, u32) {
+    (
+        NUM_DRAW_CALLS.swap(0, Ordering::Relaxed),
+        NUM_BLIT_PIXELS.swap(0, Ordering::Relaxed),
+    )
+}
+
+// Drawing coordinates are rounded to the nearest pixel with halves rounded away from zero,
+// and negative sizes are treated as zero. i32 coordinates skip the float conversion entirely.
+pub trait IntoCoord: Copy {
+    fn to_coord(self) -> i32;
+
+    fn to_size(self) -> u32 {
+        max(self.to_coord(), 0) as u32
+    }
+}
+
+impl IntoCoord for f64 {
+    fn to_coord(self) -> i32 {
+        f64_to_i32(self)
+    }
+
+    fn to_size(self) -> u32 {
+        f64_to_u32(self)
+    }
+}
+
+impl IntoCoord for i32 {
+    fn to_coord(self) -> i32 {
+        self
+    }
+}
+
 pub trait ToIndex {
     fn to_index(&self) -> usize;
 }
 
-pub struct Canvas<T: Copy + PartialEq + Default + ToIndex> {
+pub trait CopyRow: Copy + PartialEq + ToIndex {
+    fn copy_row(
+        dst: &mut [Self],
+        src: &[Self],
+        transparent: Option<Self>,
+        palette: Option<&[Self]>,
+    ) {
+        for (dst_value, value) in dst.iter_mut().zip(src) {
+            if transparent == Some(*value) {
+                continue;
+            }
+            *dst_value = palette.map_or(*value, |palette| palette[value.to_index()]);
+        }
+    }
+}
+
+pub struct Canvas<T: Copy + PartialEq + Default + ToIndex + CopyRow> {
     pub self_rect: RectArea,
     pub clip_rect: RectArea,
     pub camera_x: i32,
@@ -18,7 +79,7 @@ pub struct Canvas<T: Copy + PartialEq + Default + ToIndex> {
     should_write: fn(&Canvas<T>, i32, i32) -> bool,
 }
 
-impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
+impl<T: Copy + PartialEq + Default + ToIndex + CopyRow> Canvas<T> {
     pub fn new(width: u32, height: u32) -> Self {
         Self {
             self_rect: RectArea::new(0, 0, width, height),
@@ -43,11 +104,27 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         self.data.as_mut_ptr()
     }
 
-    pub fn clip(&mut self, x: f64, y: f64, width: f64, height: f64) {
-        let x = f64_to_i32(x);
-        let y = f64_to_i32(y);
-        let width = f64_to_u32(width);
-        let height = f64_to_u32(height);
+    pub fn row(&self, y: usize) -> &[T] {
+        let width = self.width() as usize;
+        &self.data[width * y..width * (y + 1)]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        let width = self.width() as usize;
+        &mut self.data[width * y..width * (y + 1)]
+    }
+
+    pub fn clip(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+    ) {
+        let x = x.to_coord();
+        let y = y.to_coord();
+        let width = width.to_size();
+        let height = height.to_size();
         self.clip_rect = self
             .self_rect
             .intersects(RectArea::new(x, y, width, height));
@@ -57,9 +134,9 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         self.clip_rect = self.self_rect;
     }
 
-    pub fn camera(&mut self, x: f64, y: f64) {
-        self.camera_x = f64_to_i32(x);
-        self.camera_y = f64_to_i32(y);
+    pub fn camera(&mut self, x: impl IntoCoord, y: impl IntoCoord) {
+        self.camera_x = x.to_coord();
+        self.camera_y = y.to_coord();
     }
 
     pub fn camera0(&mut self) {
@@ -79,21 +156,13 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
     }
 
     pub fn cls(&mut self, value: T) {
-        let width = self.width();
-        let height = self.height();
-        let alpha = self.alpha;
-        self.dither(1.0);
-        for y in 0..height {
-            for x in 0..width {
-                self.write_data(x as usize, y as usize, value);
-            }
-        }
-        self.dither(alpha);
+        count_draw_call();
+        self.data.fill(value);
     }
 
-    pub fn pget(&mut self, x: f64, y: f64) -> T {
-        let x = f64_to_i32(x);
-        let y = f64_to_i32(y);
+    pub fn pget(&self, x: impl IntoCoord, y: impl IntoCoord) -> T {
+        let x = x.to_coord();
+        let y = y.to_coord();
         if self.clip_rect.contains(x, y) {
             self.read_data(x as usize, y as usize)
         } else {
@@ -101,17 +170,26 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
-    pub fn pset(&mut self, x: f64, y: f64, value: T) {
-        let x = f64_to_i32(x) - self.camera_x;
-        let y = f64_to_i32(y) - self.camera_y;
+    pub fn pset(&mut self, x: impl IntoCoord, y: impl IntoCoord, value: T) {
+        count_draw_call();
+        let x = x.to_coord() - self.camera_x;
+        let y = y.to_coord() - self.camera_y;
         self.write_data_with_clipping(x, y, value);
     }
 
-    pub fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, value: T) {
-        let x1 = f64_to_i32(x1) - self.camera_x;
-        let y1 = f64_to_i32(y1) - self.camera_y;
-        let x2 = f64_to_i32(x2) - self.camera_x;
-        let y2 = f64_to_i32(y2) - self.camera_y;
+    pub fn line(
+        &mut self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        value: T,
+    ) {
+        count_draw_call();
+        let x1 = x1.to_coord() - self.camera_x;
+        let y1 = y1.to_coord() - self.camera_y;
+        let x2 = x2.to_coord() - self.camera_x;
+        let y2 = y2.to_coord() - self.camera_y;
 
         if x1 == x2 && y1 == y2 {
             self.write_data_with_clipping(x1, y1, value);
@@ -148,11 +226,19 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
-    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, value: T) {
-        let x = f64_to_i32(x) - self.camera_x;
-        let y = f64_to_i32(y) - self.camera_y;
-        let width = f64_to_u32(width);
-        let height = f64_to_u32(height);
+    pub fn rect(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        value: T,
+    ) {
+        count_draw_call();
+        let x = x.to_coord() - self.camera_x;
+        let y = y.to_coord() - self.camera_y;
+        let width = width.to_size();
+        let height = height.to_size();
         let rect = RectArea::new(x, y, width, height).intersects(self.clip_rect);
         if rect.is_empty() {
             return;
@@ -168,11 +254,19 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
-    pub fn rectb(&mut self, x: f64, y: f64, width: f64, height: f64, value: T) {
-        let x = f64_to_i32(x) - self.camera_x;
-        let y = f64_to_i32(y) - self.camera_y;
-        let width = f64_to_u32(width);
-        let height = f64_to_u32(height);
+    pub fn rectb(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        value: T,
+    ) {
+        count_draw_call();
+        let x = x.to_coord() - self.camera_x;
+        let y = y.to_coord() - self.camera_y;
+        let width = width.to_size();
+        let height = height.to_size();
         let rect = RectArea::new(x, y, width, height);
         if rect.intersects(self.clip_rect).is_empty() {
             return;
@@ -191,10 +285,11 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
-    pub fn circ(&mut self, x: f64, y: f64, radius: f64, value: T) {
-        let x = f64_to_i32(x) - self.camera_x;
-        let y = f64_to_i32(y) - self.camera_y;
-        let radius = f64_to_u32(radius);
+    pub fn circ(&mut self, x: impl IntoCoord, y: impl IntoCoord, radius: impl IntoCoord, value: T) {
+        count_draw_call();
+        let x = x.to_coord() - self.camera_x;
+        let y = y.to_coord() - self.camera_y;
+        let radius = radius.to_size();
         for xi in 0..=radius as i32 {
             let (x1, y1, x2, y2) = Self::ellipse_area(0.0, 0.0, radius as f64, radius as f64, xi);
             for yi in y1..=y2 {
@@ -206,10 +301,17 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
-    pub fn circb(&mut self, x: f64, y: f64, radius: f64, value: T) {
-        let x = f64_to_i32(x) - self.camera_x;
-        let y = f64_to_i32(y) - self.camera_y;
-        let radius = f64_to_u32(radius);
+    pub fn circb(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        radius: impl IntoCoord,
+        value: T,
+    ) {
+        count_draw_call();
+        let x = x.to_coord() - self.camera_x;
+        let y = y.to_coord() - self.camera_y;
+        let radius = radius.to_size();
         for xi in 0..=radius as i32 {
             let (x1, y1, x2, y2) = Self::ellipse_area(0.0, 0.0, radius as f64, radius as f64, xi);
             self.write_data_with_clipping(x + x1, y + y1, value);
@@ -224,11 +326,19 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
-    pub fn elli(&mut self, x: f64, y: f64, width: f64, height: f64, value: T) {
-        let x = f64_to_i32(x) - self.camera_x;
-        let y = f64_to_i32(y) - self.camera_y;
-        let width = f64_to_u32(width);
-        let height = f64_to_u32(height);
+    pub fn elli(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        value: T,
+    ) {
+        count_draw_call();
+        let x = x.to_coord() - self.camera_x;
+        let y = y.to_coord() - self.camera_y;
+        let width = width.to_size();
+        let height = height.to_size();
         let (ra, rb, cx, cy) = Self::ellipse_params(x, y, width, height);
         for xi in x..=(x + width as i32 / 2) {
             let (x1, y1, x2, y2) = Self::ellipse_area(cx, cy, ra, rb, xi);
@@ -246,11 +356,19 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
-    pub fn ellib(&mut self, x: f64, y: f64, width: f64, height: f64, value: T) {
-        let x = f64_to_i32(x) - self.camera_x;
-        let y = f64_to_i32(y) - self.camera_y;
-        let width = f64_to_u32(width);
-        let height = f64_to_u32(height);
+    pub fn ellib(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        value: T,
+    ) {
+        count_draw_call();
+        let x = x.to_coord() - self.camera_x;
+        let y = y.to_coord() - self.camera_y;
+        let width = width.to_size();
+        let height = height.to_size();
         let (ra, rb, cx, cy) = Self::ellipse_params(x, y, width, height);
         for xi in x..=(x + width as i32 / 2) {
             let (x1, y1, x2, y2) = Self::ellipse_area(cx, cy, ra, rb, xi);
@@ -268,13 +386,23 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
-    pub fn tri(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, value: T) {
-        let mut x1 = f64_to_i32(x1) - self.camera_x;
-        let mut y1 = f64_to_i32(y1) - self.camera_y;
-        let mut x2 = f64_to_i32(x2) - self.camera_x;
-        let mut y2 = f64_to_i32(y2) - self.camera_y;
-        let mut x3 = f64_to_i32(x3) - self.camera_x;
-        let mut y3 = f64_to_i32(y3) - self.camera_y;
+    pub fn tri(
+        &mut self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        x3: impl IntoCoord,
+        y3: impl IntoCoord,
+        value: T,
+    ) {
+        count_draw_call();
+        let mut x1 = x1.to_coord() - self.camera_x;
+        let mut y1 = y1.to_coord() - self.camera_y;
+        let mut x2 = x2.to_coord() - self.camera_x;
+        let mut y2 = y2.to_coord() - self.camera_y;
+        let mut x3 = x3.to_coord() - self.camera_x;
+        let mut y3 = y3.to_coord() - self.camera_y;
         if y1 > y2 {
             swap(&mut y1, &mut y2);
             swap(&mut x1, &mut x2);
@@ -339,15 +467,25 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         }
     }
 
-    pub fn trib(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, value: T) {
+    pub fn trib(
+        &mut self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        x3: impl IntoCoord,
+        y3: impl IntoCoord,
+        value: T,
+    ) {
         self.line(x1, y1, x2, y2, value);
         self.line(x1, y1, x3, y3, value);
         self.line(x2, y2, x3, y3, value);
     }
 
-    pub fn fill(&mut self, x: f64, y: f64, value: T) {
-        let x = f64_to_i32(x) - self.camera_x;
-        let y = f64_to_i32(y) - self.camera_y;
+    pub fn fill(&mut self, x: impl IntoCoord, y: impl IntoCoord, value: T) {
+        count_draw_call();
+        let x = x.to_coord() - self.camera_x;
+        let y = y.to_coord() - self.camera_y;
         if !self.clip_rect.contains(x, y) {
             return;
         }
@@ -359,22 +497,23 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
 
     pub fn blt(
         &mut self,
-        x: f64,
-        y: f64,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
         canvas: &Self,
-        canvas_x: f64,
-        canvas_y: f64,
-        width: f64,
-        height: f64,
+        canvas_x: impl IntoCoord,
+        canvas_y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
         transparent: Option<T>,
         palette: Option<&[T]>,
     ) {
-        let x = f64_to_i32(x) - self.camera_x;
-        let y = f64_to_i32(y) - self.camera_y;
-        let canvas_x = f64_to_i32(canvas_x);
-        let canvas_y = f64_to_i32(canvas_y);
-        let width = f64_to_i32(width);
-        let height = f64_to_i32(height);
+        count_draw_call();
+        let x = x.to_coord() - self.camera_x;
+        let y = y.to_coord() - self.camera_y;
+        let canvas_x = canvas_x.to_coord();
+        let canvas_y = canvas_y.to_coord();
+        let width = width.to_coord();
+        let height = height.to_coord();
 
         let CopyArea {
             dst_x,
@@ -400,6 +539,30 @@ impl<T: Copy + PartialEq + Default + ToIndex> Canvas<T> {
         if width == 0 || height == 0 {
             return;
         }
+        count_blit_pixels((width * height) as u32);
+
+        if sign_x > 0 && self.alpha >= 1.0 {
+            let palette = palette.filter(|palette| {
+                !palette
+                    .iter()
+                    .enumerate()
+                    .all(|(i, value)| value.to_index() == i)
+            });
+            let src_start = (src_x + offset_x) as usize;
+            let dst_start = dst_x as usize;
+            for yi in 0..height {
+                let value_y = (src_y + sign_y * yi + offset_y) as usize;
+                let src_row = &canvas.row(value_y)[src_start..src_start + width as usize];
+                let dst_row =
+                    &mut self.row_mut((dst_y + yi) as usize)[dst_start..dst_start + width as usize];
+                if transparent.is_none() && palette.is_none() {
+                    dst_row.copy_from_slice(src_row);
+                } else {
+                    T::copy_row(dst_row, src_row, transparent, palette);
+                }
+            }
+            return;
+        }
 
         for yi in 0..height {
             for xi in 0..width {
@@ -1,9 +1,10 @@
 use std::cmp::max;
 
 use crate::blip_buf::BlipBuf;
-use crate::oscillator::{Effect, Gain, Oscillator};
+use crate::oscillator::{Effect, Envelope, Gain, Oscillator};
 use crate::settings::{
-    EFFECT_NONE, INITIAL_CHANNEL_GAIN, MAX_EFFECT, MAX_NOTE, MAX_TONE, MAX_VOLUME, TONE_TRIANGLE,
+    EFFECT_NONE, INITIAL_CHANNEL_GAIN, INITIAL_ENVELOPE, MAX_EFFECT, MAX_NOTE, MAX_TONE,
+    MAX_VOLUME, TONE_TRIANGLE, VIBRATO_DEPTH, VIBRATO_FREQUENCY,
 };
 use crate::sound::{SharedSound, Sound};
 
@@ -22,10 +23,58 @@ pub struct Channel {
     tick_count: u32,
     pub gain: Gain,
     pub detune: Detune,
+    pub pan: f64,
+    pub reverb_send: Gain,
+    pub lowpass_cutoff: Option<f64>,
+    pub highpass_cutoff: Option<f64>,
+    pub envelope: Envelope,
+    pub vibrato_depth: f64,
+    pub vibrato_frequency: f64,
 }
 
 pub type SharedChannel = shared_type!(Channel);
 
+// Returns the sound index, note index and tick count where playback starts,
+// or None if there is nothing to play from the start tick
+pub(crate) fn start_position(
+    sounds: &[Sound],
+    start_tick: u32,
+    should_loop: bool,
+) -> Option<(u32, u32, u32)> {
+    if sounds.iter().all(|sound| sound.notes.is_empty()) {
+        return None;
+    }
+    let mut sound_index = 0;
+    let mut tick_count = start_tick;
+    loop {
+        let sound = &sounds[sound_index as usize];
+        let sound_ticks = sound.notes.len() as u32 * sound.speed;
+        if tick_count < sound_ticks {
+            return Some((
+                sound_index,
+                tick_count / sound.speed,
+                tick_count % sound.speed,
+            ));
+        }
+        tick_count -= sound_ticks;
+        sound_index += 1;
+        if sound_index >= sounds.len() as u32 {
+            if !should_loop {
+                return None;
+            }
+            sound_index = 0;
+        }
+    }
+}
+
+pub(crate) struct PlaybackState {
+    pub sounds: Vec<Sound>,
+    pub should_loop: bool,
+    pub sound_index: u32,
+    pub note_index: u32,
+    pub tick_count: u32,
+}
+
 impl Channel {
     pub fn new() -> SharedChannel {
         new_shared_type!(Self {
@@ -38,37 +87,55 @@ impl Channel {
             tick_count: 0,
             gain: INITIAL_CHANNEL_GAIN,
             detune: 0,
+            pan: 0.0,
+            reverb_send: 0.0,
+            lowpass_cutoff: None,
+            highpass_cutoff: None,
+            envelope: INITIAL_ENVELOPE,
+            vibrato_depth: VIBRATO_DEPTH,
+            vibrato_frequency: VIBRATO_FREQUENCY,
         })
     }
 
+    // Offline renders play on a copy of the settings so that live playback is left untouched
+    pub(crate) fn copy_settings(&self) -> SharedChannel {
+        let channel = Self::new();
+        {
+            let mut channel = channel.lock();
+            channel.gain = self.gain;
+            channel.detune = self.detune;
+            channel.pan = self.pan;
+            channel.reverb_send = self.reverb_send;
+            channel.lowpass_cutoff = self.lowpass_cutoff;
+            channel.highpass_cutoff = self.highpass_cutoff;
+            channel.envelope = self.envelope;
+            channel.vibrato_depth = self.vibrato_depth;
+            channel.vibrato_frequency = self.vibrato_frequency;
+        }
+        channel
+    }
+
     pub fn play(&mut self, sounds: Vec<SharedSound>, start_tick: Option<u32>, should_loop: bool) {
-        let sounds: Vec<Sound> = sounds.iter().map(|sound| sound.lock().clone()).collect();
-        if sounds.is_empty() || sounds.iter().all(|sound| sound.notes.is_empty()) {
+        let sounds = sounds.iter().map(|sound| sound.lock().clone()).collect();
+        self.play_sounds(sounds, start_tick, should_loop);
+    }
+
+    pub(crate) fn play_sounds(
+        &mut self,
+        sounds: Vec<Sound>,
+        start_tick: Option<u32>,
+        should_loop: bool,
+    ) {
+        let Some((sound_index, note_index, tick_count)) =
+            start_position(&sounds, start_tick.unwrap_or(0), should_loop)
+        else {
             return;
-        }
+        };
         self.sounds = sounds;
         self.should_loop = should_loop;
-        self.sound_index = 0;
-        self.note_index = 0;
-        self.tick_count = start_tick.unwrap_or(0);
-        loop {
-            let sound = &self.sounds[self.sound_index as usize];
-            let sound_ticks = sound.notes.len() as u32 * sound.speed;
-            if self.tick_count < sound_ticks {
-                self.note_index = self.tick_count / sound.speed;
-                self.tick_count %= sound.speed;
-                break;
-            }
-            self.tick_count -= sound_ticks;
-            self.sound_index += 1;
-            if self.sound_index >= self.sounds.len() as u32 {
-                if should_loop {
-                    self.sound_index = 0;
-                } else {
-                    return;
-                }
-            }
-        }
+        self.sound_index = sound_index;
+        self.note_index = note_index;
+        self.tick_count = tick_count;
         self.is_playing = true;
     }
 
@@ -89,7 +156,30 @@ impl Channel {
         }
     }
 
-    pub(crate) fn update(&mut self, blip_buf: &mut BlipBuf) {
+    pub(crate) fn playback_state(&self) -> Option<PlaybackState> {
+        self.is_playing.then(|| PlaybackState {
+            sounds: self.sounds.clone(),
+            should_loop: self.should_loop,
+            sound_index: self.sound_index,
+            note_index: self.note_index,
+            tick_count: self.tick_count,
+        })
+    }
+
+    pub(crate) fn set_playback_state(&mut self, playback_state: Option<PlaybackState>) {
+        self.stop();
+        if let Some(playback_state) = playback_state {
+            // The current note is retriggered from the next note boundary
+            self.sounds = playback_state.sounds;
+            self.should_loop = playback_state.should_loop;
+            self.sound_index = playback_state.sound_index;
+            self.note_index = playback_state.note_index;
+            self.tick_count = playback_state.tick_count;
+            self.is_playing = true;
+        }
+    }
+
+    pub(crate) fn update(&mut self, blip_bufs: &mut [BlipBuf]) {
         if !self.is_playing {
             return;
         }
@@ -130,10 +220,19 @@ impl Channel {
                     self.gain * volume as f64 / MAX_VOLUME as f64,
                     effect,
                     speed,
+                    self.envelope,
+                    self.vibrato_depth,
+                    self.vibrato_frequency,
                 );
             }
         }
-        self.oscillator.update(blip_buf);
+        self.oscillator.update(
+            blip_bufs,
+            self.pan,
+            self.reverb_send,
+            self.lowpass_cutoff,
+            self.highpass_cutoff,
+        );
         self.tick_count += 1;
     }
 
@@ -0,0 +1,236 @@
+use std::cmp::{max, min};
+
+use crate::image::{Color, Image};
+use crate::pyxel::Pyxel;
+use crate::utils::f64_to_i32;
+
+fn rect_overlaps(x1: f64, y1: f64, w1: f64, h1: f64, x2: f64, y2: f64, w2: f64, h2: f64) -> bool {
+    x1 < x2 + w2 && x2 < x1 + w1 && y1 < y2 + h2 && y2 < y1 + h1
+}
+
+fn circ_rect_overlaps(cx: f64, cy: f64, r: f64, x: f64, y: f64, w: f64, h: f64) -> bool {
+    let dx = cx - cx.clamp(x, x + w);
+    let dy = cy - cy.clamp(y, y + h);
+    dx * dx + dy * dy < r * r
+}
+
+// Entry and exit times of the moving span against the fixed span along one axis
+fn sweep_axis(pos: f64, size: f64, delta: f64, other_pos: f64, other_size: f64) -> (f64, f64) {
+    if delta > 0.0 {
+        (
+            (other_pos - (pos + size)) / delta,
+            (other_pos + other_size - pos) / delta,
+        )
+    } else if delta < 0.0 {
+        (
+            (other_pos + other_size - pos) / delta,
+            (other_pos - (pos + size)) / delta,
+        )
+    } else if pos < other_pos + other_size && other_pos < pos + size {
+        (f64::NEG_INFINITY, f64::INFINITY)
+    } else {
+        (f64::INFINITY, f64::NEG_INFINITY)
+    }
+}
+
+fn sweep_rect(
+    x1: f64,
+    y1: f64,
+    w1: f64,
+    h1: f64,
+    dx: f64,
+    dy: f64,
+    x2: f64,
+    y2: f64,
+    w2: f64,
+    h2: f64,
+) -> Option<(f64, f64, f64)> {
+    let (entry_x, exit_x) = sweep_axis(x1, w1, dx, x2, w2);
+    let (entry_y, exit_y) = sweep_axis(y1, h1, dy, y2, h2);
+    let entry = entry_x.max(entry_y);
+    let exit = exit_x.min(exit_y);
+    if entry >= exit || !(0.0..=1.0).contains(&entry) {
+        return None;
+    }
+    if entry_x > entry_y {
+        Some((entry, -dx.signum(), 0.0))
+    } else {
+        Some((entry, 0.0, -dy.signum()))
+    }
+}
+
+fn is_opaque(image: &Image, x: i32, y: i32, colkey: Option<Color>) -> bool {
+    if x < 0 || y < 0 || x >= image.width() as i32 || y >= image.height() as i32 {
+        return false;
+    }
+    Some(image.canvas.read_data(x as usize, y as usize)) != colkey
+}
+
+fn mask_overlaps(
+    x1: i32,
+    y1: i32,
+    image1: &Image,
+    u1: i32,
+    v1: i32,
+    w1: i32,
+    h1: i32,
+    colkey1: Option<Color>,
+    x2: i32,
+    y2: i32,
+    image2: &Image,
+    u2: i32,
+    v2: i32,
+    w2: i32,
+    h2: i32,
+    colkey2: Option<Color>,
+) -> bool {
+    // Only the intersection of both regions in screen space needs to be scanned
+    let left = max(x1, x2);
+    let top = max(y1, y2);
+    let right = min(x1 + w1, x2 + w2);
+    let bottom = min(y1 + h1, y2 + h2);
+    (top..bottom).any(|y| {
+        (left..right).any(|x| {
+            is_opaque(image1, u1 + x - x1, v1 + y - y1, colkey1)
+                && is_opaque(image2, u2 + x - x2, v2 + y - y2, colkey2)
+        })
+    })
+}
+
+impl Pyxel {
+    pub fn rect_collide(
+        &self,
+        x1: f64,
+        y1: f64,
+        w1: f64,
+        h1: f64,
+        x2: f64,
+        y2: f64,
+        w2: f64,
+        h2: f64,
+    ) -> bool {
+        rect_overlaps(x1, y1, w1, h1, x2, y2, w2, h2)
+    }
+
+    pub fn circ_rect_collide(
+        &self,
+        cx: f64,
+        cy: f64,
+        r: f64,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+    ) -> bool {
+        circ_rect_overlaps(cx, cy, r, x, y, w, h)
+    }
+
+    // Returns the time of impact in the range 0 to 1 and the collision normal
+    pub fn sweep_rect(
+        &self,
+        x1: f64,
+        y1: f64,
+        w1: f64,
+        h1: f64,
+        dx: f64,
+        dy: f64,
+        x2: f64,
+        y2: f64,
+        w2: f64,
+        h2: f64,
+    ) -> Option<(f64, f64, f64)> {
+        sweep_rect(x1, y1, w1, h1, dx, dy, x2, y2, w2, h2)
+    }
+
+    pub fn pixel_collide(
+        &self,
+        x1: f64,
+        y1: f64,
+        image_index1: u32,
+        u1: f64,
+        v1: f64,
+        w1: f64,
+        h1: f64,
+        colkey1: Option<Color>,
+        x2: f64,
+        y2: f64,
+        image_index2: u32,
+        u2: f64,
+        v2: f64,
+        w2: f64,
+        h2: f64,
+        colkey2: Option<Color>,
+    ) -> bool {
+        let images = self.images.lock();
+        let image1 = images[image_index1 as usize].lock();
+        let image2_guard;
+        // Locking the same image twice would deadlock, so the first guard is shared
+        let image2 = if image_index1 == image_index2 {
+            &*image1
+        } else {
+            image2_guard = images[image_index2 as usize].lock();
+            &*image2_guard
+        };
+        mask_overlaps(
+            f64_to_i32(x1),
+            f64_to_i32(y1),
+            &image1,
+            f64_to_i32(u1),
+            f64_to_i32(v1),
+            f64_to_i32(w1),
+            f64_to_i32(h1),
+            colkey1,
+            f64_to_i32(x2),
+            f64_to_i32(y2),
+            image2,
+            f64_to_i32(u2),
+            f64_to_i32(v2),
+            f64_to_i32(w2),
+            f64_to_i32(h2),
+            colkey2,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rect_overlaps() {
+        assert!(rect_overlaps(0.0, 0.0, 8.0, 8.0, 4.0, 4.0, 8.0, 8.0));
+        assert!(!rect_overlaps(0.0, 0.0, 8.0, 8.0, 8.0, 0.0, 8.0, 8.0));
+        assert!(circ_rect_overlaps(10.0, 4.0, 3.0, 0.0, 0.0, 8.0, 8.0));
+        assert!(!circ_rect_overlaps(11.0, 11.0, 3.0, 0.0, 0.0, 8.0, 8.0));
+    }
+
+    #[test]
+    fn test_sweep_rect() {
+        let hit = sweep_rect(0.0, 0.0, 4.0, 4.0, 10.0, 0.0, 8.0, 0.0, 4.0, 4.0);
+        assert_eq!(hit, Some((0.4, -1.0, 0.0)));
+        assert_eq!(
+            sweep_rect(0.0, 0.0, 4.0, 4.0, 0.0, 10.0, 8.0, 0.0, 4.0, 4.0),
+            None
+        );
+        assert_eq!(
+            sweep_rect(0.0, 0.0, 4.0, 4.0, 2.0, 0.0, 8.0, 0.0, 4.0, 4.0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_mask_overlaps() {
+        let image = Image::new(4, 4);
+        let mut image = image.lock();
+        image.pset(0.0, 0.0, 1);
+        image.pset(3.0, 3.0, 1);
+        let overlaps = |x2, y2, colkey| {
+            mask_overlaps(
+                0, 0, &image, 0, 0, 4, 4, colkey, x2, y2, &image, 0, 0, 4, 4, colkey,
+            )
+        };
+        assert!(overlaps(3, 3, Some(0)));
+        assert!(!overlaps(1, 1, Some(0)));
+        assert!(overlaps(1, 1, None));
+    }
+}
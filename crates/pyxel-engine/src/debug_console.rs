@@ -0,0 +1,51 @@
+use std::collections::VecDeque;
+
+use crate::pyxel::Pyxel;
+use crate::settings::{COLOR_BLACK, COLOR_WHITE, DEBUG_CONSOLE_MAX_LINES, FONT_HEIGHT};
+
+pub struct DebugConsole {
+    pub visible: bool,
+    lines: VecDeque<String>,
+}
+
+impl DebugConsole {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            lines: VecDeque::with_capacity(DEBUG_CONSOLE_MAX_LINES as usize),
+        }
+    }
+
+    pub fn add_line(&mut self, line: &str) {
+        if self.lines.len() >= DEBUG_CONSOLE_MAX_LINES as usize {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.to_string());
+    }
+}
+
+impl Pyxel {
+    pub fn dbg_print(&mut self, text: &str) {
+        log::debug!("{text}");
+        for line in text.lines() {
+            self.system.debug_console.add_line(line);
+        }
+    }
+
+    pub(crate) fn draw_debug_console(&self) {
+        let debug_console = &self.system.debug_console;
+        if !debug_console.visible {
+            return;
+        }
+        let num_lines = (self.height / FONT_HEIGHT) as usize;
+        let start_index = debug_console.lines.len().saturating_sub(num_lines);
+        let mut y = self.height.saturating_sub(FONT_HEIGHT) as f64;
+        self.draw_over_screen(|screen| {
+            for line in debug_console.lines.iter().skip(start_index).rev() {
+                screen.text(1.0, y + 1.0, line, COLOR_BLACK);
+                screen.text(0.0, y, line, COLOR_WHITE);
+                y -= FONT_HEIGHT as f64;
+            }
+        });
+    }
+}
@@ -0,0 +1,329 @@
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use crate::keys::{Key, KeyValue};
+use crate::pyxel::Pyxel;
+use crate::system::PyxelCallback;
+
+const FRAC_BITS: u32 = 16;
+const FRAC_MASK: i32 = (1 << FRAC_BITS) - 1;
+
+// Sine of 0 to 90 degrees in 16.16 fixed point, so that results don't depend on the platform's libm
+const SIN_TABLE: [i32; 91] = [
+    0, 1144, 2287, 3430, 4572, 5712, 6850, 7987, 9121, 10252, 11380, 12505, 13626, 14742, 15855,
+    16962, 18064, 19161, 20252, 21336, 22415, 23486, 24550, 25607, 26656, 27697, 28729, 29753,
+    30767, 31772, 32768, 33754, 34729, 35693, 36647, 37590, 38521, 39441, 40348, 41243, 42126,
+    42995, 43852, 44695, 45525, 46341, 47143, 47930, 48703, 49461, 50203, 50931, 51643, 52339,
+    53020, 53684, 54332, 54963, 55578, 56175, 56756, 57319, 57865, 58393, 58903, 59396, 59870,
+    60326, 60764, 61183, 61584, 61966, 62328, 62672, 62997, 63303, 63589, 63856, 64104, 64332,
+    64540, 64729, 64898, 65048, 65177, 65287, 65376, 65446, 65496, 65526, 65536,
+];
+
+// 16.16 fixed-point number whose arithmetic gives identical results on every platform.
+// Addition, subtraction and multiplication wrap on overflow, while division and abs
+// saturate, so that no operation panics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(pub i32);
+
+impl Fixed {
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self(1 << FRAC_BITS);
+
+    pub const fn from_int(value: i32) -> Self {
+        Self(value << FRAC_BITS)
+    }
+
+    // Only for constants and display, since float conversion is where determinism ends
+    pub fn from_f64(value: f64) -> Self {
+        Self((value * Self::ONE.0 as f64).round() as i32)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::ONE.0 as f64
+    }
+
+    pub const fn floor(self) -> i32 {
+        self.0 >> FRAC_BITS
+    }
+
+    pub const fn abs(self) -> Self {
+        Self(self.0.saturating_abs())
+    }
+
+    pub fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Self::ZERO;
+        }
+        let value = (self.0 as u64) << FRAC_BITS;
+        let mut root = 0;
+        let mut bit = 1 << 62;
+        let mut remainder = value;
+        while bit > remainder {
+            bit >>= 2;
+        }
+        while bit != 0 {
+            if remainder >= root + bit {
+                remainder -= root + bit;
+                root = (root >> 1) + bit;
+            } else {
+                root >>= 1;
+            }
+            bit >>= 2;
+        }
+        Self(root as i32)
+    }
+
+    // Takes degrees like Pyxel::sin, interpolating between whole degrees
+    pub fn sin(self) -> Self {
+        let deg = self.0.rem_euclid(360 << FRAC_BITS);
+        let index = deg >> FRAC_BITS;
+        let frac = (deg & FRAC_MASK) as i64;
+        let value1 = sin_deg(index) as i64;
+        let value2 = sin_deg((index + 1) % 360) as i64;
+        Self((value1 + (((value2 - value1) * frac) >> FRAC_BITS)) as i32)
+    }
+
+    pub fn cos(self) -> Self {
+        (self + Self::from_int(90)).sin()
+    }
+}
+
+fn sin_deg(deg: i32) -> i32 {
+    match deg / 90 {
+        0 => SIN_TABLE[deg as usize],
+        1 => SIN_TABLE[(180 - deg) as usize],
+        2 => -SIN_TABLE[(deg - 180) as usize],
+        _ => -SIN_TABLE[(360 - deg) as usize],
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0.wrapping_add(other.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0.wrapping_sub(other.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self(((self.0 as i64 * other.0 as i64) >> FRAC_BITS) as i32)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    // Dividing by zero gives the largest value with the sign of the dividend, or zero for 0 / 0
+    fn div(self, other: Self) -> Self {
+        if other.0 == 0 {
+            return Self(match self.0.signum() {
+                1 => i32::MAX,
+                -1 => i32::MIN,
+                _ => 0,
+            });
+        }
+        let quotient = ((self.0 as i64) << FRAC_BITS) / other.0 as i64;
+        Self(quotient.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.wrapping_neg())
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+// Inputs of one frame, which are exchanged between peers and replayed when rolling back
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameInput {
+    pub keys: Vec<Key>,
+    pub values: Vec<(Key, KeyValue)>,
+}
+
+impl FrameInput {
+    // Little-endian counts followed by keys and key-value pairs
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(8 + self.keys.len() * 4 + self.values.len() * 8);
+        data.extend((self.keys.len() as u32).to_le_bytes());
+        for key in &self.keys {
+            data.extend(key.to_le_bytes());
+        }
+        data.extend((self.values.len() as u32).to_le_bytes());
+        for (key, value) in &self.values {
+            data.extend(key.to_le_bytes());
+            data.extend(value.to_le_bytes());
+        }
+        data
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() % 4 != 0 {
+            return None;
+        }
+        let mut words = data
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()));
+        let num_keys = words.next()? as usize;
+        let keys = (0..num_keys)
+            .map(|_| words.next())
+            .collect::<Option<Vec<_>>>()?;
+        let num_values = words.next()? as usize;
+        let values = (0..num_values)
+            .map(|_| Some((words.next()?, words.next()? as KeyValue)))
+            .collect::<Option<Vec<_>>>()?;
+        if words.next().is_some() {
+            return None;
+        }
+        Some(Self { keys, values })
+    }
+}
+
+impl Pyxel {
+    pub fn capture_input(&mut self) -> FrameInput {
+        FrameInput {
+            keys: self.held_keys(),
+            values: self.key_values(),
+        }
+    }
+
+    // Runs one update with the given inputs instead of the platform's, without drawing.
+    // Tasks, idle detection and scenes advance just as in a regular frame.
+    // Together with rseed, fixed-point math, and save_state, this allows rollback netcode.
+    pub fn advance_with_inputs(&mut self, callback: &mut dyn PyxelCallback, inputs: &FrameInput) {
+        self.reset_input_states();
+        for key in self.held_keys() {
+            if !inputs.keys.contains(&key) {
+                self.release_key(key);
+            }
+        }
+        let held_keys = self.held_keys();
+        for key in &inputs.keys {
+            if !held_keys.contains(key) {
+                self.press_key(*key, 0);
+            }
+        }
+        for (key, value) in &inputs.values {
+            self.set_key_value(*key, *value);
+        }
+        self.update_game(Some(callback));
+        self.frame_count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+    use crate::scene::Scene;
+
+    struct NoUpdate;
+
+    impl PyxelCallback for NoUpdate {
+        fn update(&mut self, _pyxel: &mut Pyxel) {}
+        fn draw(&mut self, _pyxel: &mut Pyxel) {}
+    }
+
+    struct CountingScene(Rc<Cell<u32>>);
+
+    impl Scene for CountingScene {
+        fn update(&mut self, _pyxel: &mut Pyxel) {
+            self.0.set(self.0.get() + 1);
+        }
+
+        fn draw(&mut self, _pyxel: &mut Pyxel) {}
+    }
+
+    #[test]
+    fn test_fixed_arithmetic() {
+        let a = Fixed::from_f64(1.5);
+        let b = Fixed::from_int(2);
+        assert_eq!(a + b, Fixed::from_f64(3.5));
+        assert_eq!(a - b, Fixed::from_f64(-0.5));
+        assert_eq!(a * b, Fixed::from_int(3));
+        assert_eq!(b / a, Fixed(87381));
+        assert_eq!(Fixed::from_f64(-1.5).floor(), -2);
+        assert_eq!(Fixed::from_int(9).sqrt(), Fixed::from_int(3));
+    }
+
+    #[test]
+    fn test_fixed_saturation() {
+        assert_eq!(Fixed::from_int(3) / Fixed::ZERO, Fixed(i32::MAX));
+        assert_eq!(Fixed::from_int(-3) / Fixed::ZERO, Fixed(i32::MIN));
+        assert_eq!(Fixed::ZERO / Fixed::ZERO, Fixed::ZERO);
+        assert_eq!(Fixed::from_int(30000) / Fixed(1), Fixed(i32::MAX));
+        assert_eq!(Fixed(i32::MIN).abs(), Fixed(i32::MAX));
+        assert_eq!(Fixed::from_f64(-2.5).abs(), Fixed::from_f64(2.5));
+    }
+
+    #[test]
+    fn test_fixed_sin() {
+        assert_eq!(Fixed::from_int(30).sin(), Fixed(32768));
+        assert_eq!(Fixed::from_int(90).sin(), Fixed::ONE);
+        assert_eq!(Fixed::from_int(210).sin(), Fixed(-32768));
+        assert_eq!(Fixed::from_int(-90).sin(), -Fixed::ONE);
+        assert_eq!(Fixed::from_int(0).cos(), Fixed::ONE);
+        assert_eq!(Fixed::from_f64(0.5).sin(), Fixed(572));
+    }
+
+    #[test]
+    fn test_frame_input_bytes() {
+        let input = FrameInput {
+            keys: vec![1, 0x4000_0000],
+            values: vec![(5, -3)],
+        };
+        let data = input.to_bytes();
+        assert_eq!(data.len(), 24);
+        assert_eq!(FrameInput::from_bytes(&data), Some(input));
+        assert_eq!(FrameInput::from_bytes(&data[..20]), None);
+        assert_eq!(FrameInput::from_bytes(&[]), None);
+    }
+
+    #[test]
+    fn test_advance_with_inputs() {
+        let mut pyxel = headless_pyxel(16, 16);
+        let scene_updates = Rc::new(Cell::new(0));
+        let idle_calls = Rc::new(Cell::new(0));
+        pyxel.scenes.push(CountingScene(scene_updates.clone()));
+        let idle_counter = idle_calls.clone();
+        pyxel.on_idle(
+            2,
+            Box::new(move |_| idle_counter.set(idle_counter.get() + 1)),
+        );
+
+        let inputs = FrameInput::default();
+        let start_frame = pyxel.frame_count;
+        for _ in 0..3 {
+            pyxel.advance_with_inputs(&mut NoUpdate, &inputs);
+        }
+        assert_eq!(pyxel.frame_count, start_frame + 3);
+        assert_eq!(scene_updates.get(), 3);
+        assert_eq!(idle_calls.get(), 1);
+    }
+}
@@ -0,0 +1,160 @@
+use std::f64::consts::PI;
+
+use crate::settings::{
+    REVERB_ALLPASS_DELAYS, REVERB_ALLPASS_FEEDBACK, REVERB_COMB_DELAYS, REVERB_DAMPING,
+    REVERB_FEEDBACK, REVERB_GAIN,
+};
+
+pub struct Filter {
+    lowpass_value: f64,
+    highpass_value: f64,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self {
+            lowpass_value: 0.0,
+            highpass_value: 0.0,
+        }
+    }
+
+    // One-pole filters evaluated at the oscillator step interval, given in seconds
+    pub fn process(
+        &mut self,
+        input: f64,
+        lowpass_cutoff: Option<f64>,
+        highpass_cutoff: Option<f64>,
+        interval: f64,
+    ) -> f64 {
+        let mut output = input;
+        if let Some(cutoff) = lowpass_cutoff {
+            self.lowpass_value += (output - self.lowpass_value) * Self::rate(cutoff, interval);
+            output = self.lowpass_value;
+        }
+        if let Some(cutoff) = highpass_cutoff {
+            self.highpass_value += (output - self.highpass_value) * Self::rate(cutoff, interval);
+            output -= self.highpass_value;
+        }
+        output
+    }
+
+    fn rate(cutoff: f64, interval: f64) -> f64 {
+        1.0 - (-2.0 * PI * cutoff.max(0.0) * interval).exp()
+    }
+}
+
+struct CombFilter {
+    buffer: Vec<f64>,
+    index: usize,
+    damped_value: f64,
+}
+
+impl CombFilter {
+    fn new(delay: usize) -> Self {
+        Self {
+            buffer: vec![0.0; delay.max(1)],
+            index: 0,
+            damped_value: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.buffer[self.index];
+        self.damped_value = output * (1.0 - REVERB_DAMPING) + self.damped_value * REVERB_DAMPING;
+        self.buffer[self.index] = input + self.damped_value * REVERB_FEEDBACK;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+struct AllPassFilter {
+    buffer: Vec<f64>,
+    index: usize,
+}
+
+impl AllPassFilter {
+    fn new(delay: usize) -> Self {
+        Self {
+            buffer: vec![0.0; delay.max(1)],
+            index: 0,
+        }
+    }
+
+    fn process(&mut self, input: f64) -> f64 {
+        let buffered = self.buffer[self.index];
+        self.buffer[self.index] = input + buffered * REVERB_ALLPASS_FEEDBACK;
+        self.index = (self.index + 1) % self.buffer.len();
+        buffered - input
+    }
+}
+
+// Schroeder reverb with parallel comb filters followed by serial all-pass filters
+pub struct Reverb {
+    comb_filters: Vec<CombFilter>,
+    allpass_filters: Vec<AllPassFilter>,
+}
+
+impl Reverb {
+    pub fn new(sample_rate: u32) -> Self {
+        // Delays are tuned for 44.1kHz and scaled to the actual sample rate
+        let scale_delay = |delay: u32| (delay as f64 * sample_rate as f64 / 44100.0) as usize;
+        Self {
+            comb_filters: REVERB_COMB_DELAYS
+                .iter()
+                .map(|delay| CombFilter::new(scale_delay(*delay)))
+                .collect(),
+            allpass_filters: REVERB_ALLPASS_DELAYS
+                .iter()
+                .map(|delay| AllPassFilter::new(scale_delay(*delay)))
+                .collect(),
+        }
+    }
+
+    pub fn process(&mut self, input: f64) -> f64 {
+        let input = input * REVERB_GAIN;
+        let mut output = self
+            .comb_filters
+            .iter_mut()
+            .map(|comb_filter| comb_filter.process(input))
+            .sum::<f64>();
+        for allpass_filter in &mut self.allpass_filters {
+            output = allpass_filter.process(output);
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::SAMPLE_RATE;
+
+    #[test]
+    fn test_filter() {
+        let interval = 1.0 / SAMPLE_RATE as f64;
+        let mut filter = Filter::new();
+        assert!((filter.process(1.0, None, None, interval) - 1.0).abs() < 1e-9);
+
+        let mut output = 0.0;
+        for _ in 0..SAMPLE_RATE {
+            output = filter.process(1.0, Some(1000.0), None, interval);
+        }
+        assert!((output - 1.0).abs() < 1e-6);
+
+        let mut filter = Filter::new();
+        for _ in 0..SAMPLE_RATE {
+            output = filter.process(1.0, None, Some(1000.0), interval);
+        }
+        assert!(output.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_reverb() {
+        let mut reverb = Reverb::new(SAMPLE_RATE);
+        assert!(reverb.process(0.0).abs() < 1e-9);
+        reverb.process(1.0);
+        let tail: Vec<f64> = (0..SAMPLE_RATE / 4).map(|_| reverb.process(0.0)).collect();
+        assert!(tail.iter().any(|value| value.abs() > 1e-9));
+        assert!(tail.iter().all(|value| value.abs() < 1.0));
+    }
+}
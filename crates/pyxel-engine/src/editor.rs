@@ -0,0 +1,426 @@
+use std::path::Path;
+
+use crate::canvas::{Canvas, CopyRow};
+use crate::error::PyxelResult;
+use crate::image::Image;
+use crate::image_editor::ImageEditor;
+use crate::keys::{KEY_CTRL, KEY_GUI, KEY_S, MOUSE_BUTTON_LEFT, MOUSE_BUTTON_RIGHT};
+use crate::music_editor::MusicEditor;
+use crate::pyxel::Pyxel;
+use crate::settings::{
+    COLOR_GRAY, COLOR_NAVY, COLOR_WHITE, COLOR_YELLOW, FONT_HEIGHT, FONT_WIDTH, HOT_CLICK,
+};
+use crate::sound_editor::SoundEditor;
+use crate::system::PyxelCallback;
+use crate::tilemap_editor::TilemapEditor;
+
+pub(crate) const APP_WIDTH: u32 = 240;
+pub(crate) const APP_HEIGHT: u32 = 180;
+pub(crate) const TAB_Y: i32 = 3;
+pub(crate) const TOOL_Y: i32 = 14;
+pub(crate) const CANVAS_X: i32 = 4;
+pub(crate) const CANVAS_Y: i32 = 25;
+pub(crate) const CANVAS_PIXELS: i32 = 128;
+pub(crate) const VIEWER_X: i32 = 140;
+pub(crate) const VIEWER_Y: i32 = 25;
+pub(crate) const VIEWER_SIZE: i32 = 96;
+pub(crate) const BUTTON_WIDTH: i32 = FONT_WIDTH as i32 * 5;
+pub(crate) const STATUS_Y: i32 = APP_HEIGHT as i32 - FONT_HEIGHT as i32 - 3;
+pub(crate) const HOT_ID_BASE: u32 = u32::MAX - 0x200;
+const TAB_LABELS: [&str; 4] = ["IMAGE", "TILEMAP", "SOUND", "MUSIC"];
+const TAB_WIDTH: i32 = FONT_WIDTH as i32 * 8;
+const SAVE_HOT_ID: u32 = HOT_ID_BASE + TAB_LABELS.len() as u32;
+const TOOL_LABELS: [&str; 3] = ["PEN", "FILL", "SEL"];
+const MAX_UNDO_COUNT: usize = 100;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Tool {
+    Pencil,
+    Fill,
+    Select,
+}
+
+const TOOLS: [Tool; 3] = [Tool::Pencil, Tool::Fill, Tool::Select];
+
+pub(crate) struct Clip<T> {
+    width: i32,
+    height: i32,
+    data: Vec<T>,
+}
+
+// Snapshot of an edited area of an image or tilemap
+pub(crate) struct EditRecord<T> {
+    pub index: usize,
+    pub x: i32,
+    pub y: i32,
+    pub size: i32,
+    pub data: Vec<T>,
+}
+
+// Undo and redo stacks of records that each hold the state to swap back in
+pub(crate) struct EditHistory<R> {
+    undo_records: Vec<R>,
+    redo_records: Vec<R>,
+}
+
+impl<R> EditHistory<R> {
+    pub fn new() -> Self {
+        Self {
+            undo_records: Vec::new(),
+            redo_records: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, record: R) {
+        self.undo_records.push(record);
+        if self.undo_records.len() > MAX_UNDO_COUNT {
+            self.undo_records.remove(0);
+        }
+        self.redo_records.clear();
+    }
+
+    pub fn take(&mut self, undo: bool) -> Option<R> {
+        if undo {
+            self.undo_records.pop()
+        } else {
+            self.redo_records.pop()
+        }
+    }
+
+    // Called with the state replaced by a taken record, so it can be restored the other way
+    pub fn put_back(&mut self, record: R, undo: bool) {
+        if undo {
+            self.redo_records.push(record);
+        } else {
+            self.undo_records.push(record);
+        }
+    }
+}
+
+pub(crate) struct Selection {
+    start: Option<(i32, i32)>,
+    pub area: Option<(i32, i32, i32, i32)>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self {
+            start: None,
+            area: None,
+        }
+    }
+
+    pub fn update(&mut self, is_pressed: bool, x: i32, y: i32) {
+        if is_pressed {
+            self.start = Some((x, y));
+        }
+        if let Some((start_x, start_y)) = self.start {
+            self.area = Some((
+                start_x.min(x),
+                start_y.min(y),
+                start_x.max(x),
+                start_y.max(y),
+            ));
+        }
+    }
+
+    pub fn release(&mut self) {
+        self.start = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.start = None;
+        self.area = None;
+    }
+}
+
+// Areas outside the canvas read as the default value and are left unchanged on write
+pub(crate) fn read_region<T: CopyRow + Default>(
+    canvas: &Canvas<T>,
+    x: i32,
+    y: i32,
+    size: i32,
+) -> Vec<T> {
+    let (width, height) = (canvas.width() as i32, canvas.height() as i32);
+    let mut data = Vec::with_capacity((size * size) as usize);
+    for yi in y..y + size {
+        for xi in x..x + size {
+            data.push(if (0..width).contains(&xi) && (0..height).contains(&yi) {
+                canvas.read_data(xi as usize, yi as usize)
+            } else {
+                T::default()
+            });
+        }
+    }
+    data
+}
+
+pub(crate) fn write_region<T: CopyRow + Default>(
+    canvas: &mut Canvas<T>,
+    x: i32,
+    y: i32,
+    size: i32,
+    data: &[T],
+) {
+    let (width, height) = (canvas.width() as i32, canvas.height() as i32);
+    for yi in 0..size {
+        for xi in 0..size {
+            if (0..width).contains(&(x + xi)) && (0..height).contains(&(y + yi)) {
+                let value = data[(yi * size + xi) as usize];
+                canvas.write_data((x + xi) as usize, (y + yi) as usize, value);
+            }
+        }
+    }
+}
+
+pub(crate) fn flood_fill<T: Copy + PartialEq>(data: &mut [T], size: i32, x: i32, y: i32, value: T) {
+    let target = data[(y * size + x) as usize];
+    if target == value {
+        return;
+    }
+    let mut stack = vec![(x, y)];
+    while let Some((x, y)) = stack.pop() {
+        if x < 0 || x >= size || y < 0 || y >= size {
+            continue;
+        }
+        let index = (y * size + x) as usize;
+        if data[index] != target {
+            continue;
+        }
+        data[index] = value;
+        stack.extend([(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]);
+    }
+}
+
+// Fills the points between two mouse positions, so that fast strokes don't leave gaps
+pub(crate) fn draw_stroke<T: Copy>(
+    data: &mut [T],
+    size: i32,
+    (x1, y1): (i32, i32),
+    (x2, y2): (i32, i32),
+    value: T,
+) {
+    let steps = (x2 - x1).abs().max((y2 - y1).abs()).max(1);
+    for i in 0..=steps {
+        let t = i as f64 / steps as f64;
+        let x = (x1 as f64 + (x2 - x1) as f64 * t).round() as i32;
+        let y = (y1 as f64 + (y2 - y1) as f64 * t).round() as i32;
+        data[(y * size + x) as usize] = value;
+    }
+}
+
+pub(crate) fn copy_area<T: Copy>(
+    data: &[T],
+    size: i32,
+    area: Option<(i32, i32, i32, i32)>,
+) -> Clip<T> {
+    let (x1, y1, x2, y2) = area.unwrap_or((0, 0, size - 1, size - 1));
+    Clip {
+        width: x2 - x1 + 1,
+        height: y2 - y1 + 1,
+        data: (y1..=y2)
+            .flat_map(|y| (x1..=x2).map(move |x| data[(y * size + x) as usize]))
+            .collect(),
+    }
+}
+
+pub(crate) fn paste_clip<T: Copy>(data: &mut [T], size: i32, clip: &Clip<T>, left: i32, top: i32) {
+    for yi in 0..clip.height.min(size - top) {
+        for xi in 0..clip.width.min(size - left) {
+            data[((top + yi) * size + left + xi) as usize] =
+                clip.data[(yi * clip.width + xi) as usize];
+        }
+    }
+}
+
+pub(crate) fn is_ctrl_pressed(pyxel: &mut Pyxel) -> bool {
+    pyxel.btn(KEY_CTRL) || pyxel.btn(KEY_GUI)
+}
+
+// Left click and wheel up step a value forward, while right click and wheel down step it back
+pub(crate) fn value_step(pyxel: &mut Pyxel, x: i32, y: i32, width: i32) -> i32 {
+    if !pyxel.mouse_in(x as f64, y as f64, width as f64, FONT_HEIGHT as f64) {
+        return 0;
+    }
+    pyxel.mouse_wheel + i32::from(pyxel.btnp(MOUSE_BUTTON_LEFT, None, None))
+        - i32::from(pyxel.btnp(MOUSE_BUTTON_RIGHT, None, None))
+}
+
+pub(crate) fn update_tool_buttons(pyxel: &mut Pyxel, hot_id_base: u32, tool: &mut Tool) {
+    for (i, button_tool) in TOOLS.iter().enumerate() {
+        let hot_state = pyxel.engine_hot_rect(
+            hot_id_base + i as u32,
+            (CANVAS_X + BUTTON_WIDTH * i as i32) as f64,
+            TOOL_Y as f64,
+            BUTTON_WIDTH as f64,
+            FONT_HEIGHT as f64,
+        );
+        if hot_state == HOT_CLICK {
+            *tool = *button_tool;
+        }
+    }
+}
+
+pub(crate) fn draw_tool_buttons(screen: &mut Image, tool: Tool) {
+    for (i, (button_tool, label)) in TOOLS.iter().zip(TOOL_LABELS).enumerate() {
+        let x = (CANVAS_X + BUTTON_WIDTH * i as i32) as f64;
+        let color = if *button_tool == tool {
+            COLOR_YELLOW
+        } else {
+            COLOR_GRAY
+        };
+        screen.text(x, TOOL_Y as f64, label, color);
+    }
+}
+
+pub struct Editor {
+    filename: String,
+    tab: usize,
+    message: String,
+    image_editor: ImageEditor,
+    tilemap_editor: TilemapEditor,
+    sound_editor: SoundEditor,
+    music_editor: MusicEditor,
+}
+
+impl Editor {
+    fn new(filename: &str) -> Self {
+        Self {
+            filename: filename.to_string(),
+            tab: 0,
+            message: String::new(),
+            image_editor: ImageEditor::new(),
+            tilemap_editor: TilemapEditor::new(),
+            sound_editor: SoundEditor::new(),
+            music_editor: MusicEditor::new(),
+        }
+    }
+
+    fn save(&mut self, pyxel: &mut Pyxel) {
+        self.message = match pyxel.save(&self.filename, None, None, None, None, None, None, None) {
+            Ok(()) => format!("SAVED {}", self.filename),
+            Err(err) => err.to_string(),
+        };
+    }
+}
+
+impl PyxelCallback for Editor {
+    fn update(&mut self, pyxel: &mut Pyxel) {
+        for i in 0..TAB_LABELS.len() {
+            let hot_state = pyxel.engine_hot_rect(
+                HOT_ID_BASE + i as u32,
+                (CANVAS_X + TAB_WIDTH * i as i32) as f64,
+                TAB_Y as f64,
+                TAB_WIDTH as f64,
+                FONT_HEIGHT as f64,
+            );
+            if hot_state == HOT_CLICK && self.tab != i {
+                self.tab = i;
+                self.sound_editor.stop(pyxel);
+                self.music_editor.stop(pyxel);
+            }
+        }
+        let hot_state = pyxel.engine_hot_rect(
+            SAVE_HOT_ID,
+            (APP_WIDTH as i32 - BUTTON_WIDTH) as f64,
+            TAB_Y as f64,
+            BUTTON_WIDTH as f64,
+            FONT_HEIGHT as f64,
+        );
+        if hot_state == HOT_CLICK || is_ctrl_pressed(pyxel) && pyxel.btnp(KEY_S, None, None) {
+            self.save(pyxel);
+        }
+        match self.tab {
+            0 => self.image_editor.update(pyxel, &mut self.message),
+            1 => self.tilemap_editor.update(pyxel, &mut self.message),
+            2 => self.sound_editor.update(pyxel),
+            _ => self.music_editor.update(pyxel),
+        }
+    }
+
+    fn draw(&mut self, pyxel: &mut Pyxel) {
+        let screen = pyxel.screen.clone();
+        let mut screen = screen.lock();
+        screen.cls(COLOR_NAVY);
+        for (i, label) in TAB_LABELS.iter().enumerate() {
+            let x = (CANVAS_X + TAB_WIDTH * i as i32) as f64;
+            let color = if i == self.tab {
+                COLOR_WHITE
+            } else {
+                COLOR_GRAY
+            };
+            screen.text(x, TAB_Y as f64, label, color);
+        }
+        let save_x = (APP_WIDTH as i32 - BUTTON_WIDTH) as f64;
+        screen.text(save_x, TAB_Y as f64, "SAVE", COLOR_WHITE);
+        let status = match self.tab {
+            0 => self.image_editor.draw(pyxel, &mut screen),
+            1 => self.tilemap_editor.draw(pyxel, &mut screen),
+            2 => self.sound_editor.draw(pyxel, &mut screen),
+            _ => self.music_editor.draw(pyxel, &mut screen),
+        };
+        let status = status.unwrap_or_else(|| self.message.clone());
+        screen.text(CANVAS_X as f64, STATUS_Y as f64, &status, COLOR_WHITE);
+    }
+
+    fn on_drop_file(&mut self, pyxel: &mut Pyxel, filename: &str) {
+        if let Err(err) = pyxel.load(filename, None, None, None, None, None, None, None) {
+            self.message = err.to_string();
+            return;
+        }
+        self.filename = filename.to_string();
+        self.image_editor = ImageEditor::new();
+        self.tilemap_editor = TilemapEditor::new();
+        self.sound_editor.stop(pyxel);
+        self.sound_editor = SoundEditor::new();
+        self.music_editor.stop(pyxel);
+        self.music_editor = MusicEditor::new();
+    }
+}
+
+// Opens a resource file in a standalone editor, creating the file on save
+pub fn edit(filename: &str) -> PyxelResult<()> {
+    let mut pyxel = Pyxel::builder(APP_WIDTH, APP_HEIGHT)
+        .title(&format!("Pyxel Editor - {filename}"))
+        .build()?;
+    if Path::new(filename).exists() {
+        pyxel.load(filename, None, None, None, None, None, None, None)?;
+    }
+    pyxel.mouse(true);
+    pyxel.run(Editor::new(filename));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flood_fill() {
+        let mut data = vec![0, 0, 1, 0, 0, 1, 1, 1, 0];
+        flood_fill(&mut data, 3, 0, 0, 2);
+        assert_eq!(data, vec![2, 2, 1, 2, 2, 1, 1, 1, 0]);
+        flood_fill(&mut data, 3, 2, 2, 2);
+        assert_eq!(data, vec![2, 2, 1, 2, 2, 1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn test_draw_stroke() {
+        let mut data = vec![0; 9];
+        draw_stroke(&mut data, 3, (0, 0), (2, 2), 1);
+        assert_eq!(data, vec![1, 0, 0, 0, 1, 0, 0, 0, 1]);
+        draw_stroke(&mut data, 3, (1, 0), (1, 0), 2);
+        assert_eq!(data, vec![1, 2, 0, 0, 1, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_copy_area() {
+        let data = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let clip = copy_area(&data, 3, Some((1, 1, 2, 2)));
+        let mut pasted = vec![0; 9];
+        paste_clip(&mut pasted, 3, &clip, 0, 1);
+        assert_eq!(pasted, vec![0, 0, 0, 5, 6, 0, 8, 9, 0]);
+        paste_clip(&mut pasted, 3, &clip, 2, 2);
+        assert_eq!(pasted, vec![0, 0, 0, 5, 6, 0, 8, 9, 5]);
+    }
+}
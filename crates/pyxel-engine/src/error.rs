@@ -0,0 +1,30 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug)]
+pub enum PyxelError {
+    AlreadyInitialized,
+    Platform(String),
+    FileOpen(String),
+    FileWrite(String),
+    InvalidFile(String),
+    InvalidArgument(String),
+    Script(String),
+}
+
+pub type PyxelResult<T> = Result<T, PyxelError>;
+
+impl Display for PyxelError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::AlreadyInitialized => write!(f, "Pyxel already initialized"),
+            Self::Platform(message) => write!(f, "{message}"),
+            Self::FileOpen(filename) => write!(f, "Failed to open file '{filename}'"),
+            Self::FileWrite(filename) => write!(f, "Failed to write file '{filename}'"),
+            Self::InvalidFile(filename) => write!(f, "Invalid file format in '{filename}'"),
+            Self::InvalidArgument(message) | Self::Script(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl Error for PyxelError {}
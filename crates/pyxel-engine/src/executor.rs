@@ -0,0 +1,103 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::pyxel::Pyxel;
+use crate::resource_loader::ResourceLoad;
+use crate::system::PyxelCallback;
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+// Tasks are polled every frame anyway, so waking them has nothing to do
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+pub struct Executor {
+    tasks: Vec<Task>,
+    waker: Waker,
+}
+
+impl Executor {
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            waker: Waker::from(Arc::new(NoopWaker)),
+        }
+    }
+}
+
+// Resolves on the next frame, so that async code can wait without blocking the run loop
+pub struct NextFrame {
+    is_polled: bool,
+}
+
+impl Future for NextFrame {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _context: &mut Context) -> Poll<()> {
+        if self.is_polled {
+            Poll::Ready(())
+        } else {
+            self.is_polled = true;
+            Poll::Pending
+        }
+    }
+}
+
+pub fn next_frame() -> NextFrame {
+    NextFrame { is_polled: false }
+}
+
+impl Future for ResourceLoad {
+    type Output = Result<(), String>;
+
+    fn poll(self: Pin<&mut Self>, _context: &mut Context) -> Poll<Self::Output> {
+        if self.is_done() {
+            Poll::Ready(self.error().map_or(Ok(()), Err))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Pyxel {
+    pub fn spawn(&mut self, future: impl Future<Output = ()> + 'static) {
+        self.system.executor.tasks.push(Box::pin(future));
+    }
+
+    pub fn run_async<T: PyxelCallback>(
+        &mut self,
+        callback: T,
+        future: impl Future<Output = ()> + 'static,
+    ) {
+        self.spawn(future);
+        self.run(callback);
+    }
+
+    // Each task is polled once per frame before update, and dropped once it completes
+    pub(crate) fn poll_tasks(&mut self) {
+        let executor = &mut self.system.executor;
+        let mut context = Context::from_waker(&executor.waker);
+        executor
+            .tasks
+            .retain_mut(|task| task.as_mut().poll(&mut context).is_pending());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_frame() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut context = Context::from_waker(&waker);
+        let mut future = Box::pin(next_frame());
+        assert!(future.as_mut().poll(&mut context).is_pending());
+        assert!(future.as_mut().poll(&mut context).is_ready());
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use crate::error::{PyxelError, PyxelResult};
+use crate::image::{Color, Image};
+use crate::pyxel::{Pyxel, FALLBACK_FONT};
+use crate::settings::FONT_HEIGHT;
+use crate::vfs;
+
+struct Glyph {
+    advance: i32,
+    width: u32,
+    height: u32,
+    x_offset: i32,
+    y_offset: i32,
+    bitmap: Vec<bool>,
+}
+
+pub struct Font {
+    ascent: i32,
+    descent: i32,
+    glyphs: HashMap<char, Glyph>,
+}
+
+impl Font {
+    // Glyph encodings are treated as code points, so the font must use the ISO10646 charset
+    pub fn from_bdf(bdf_text: &str) -> Option<Self> {
+        let mut ascent = None;
+        let mut descent = None;
+        let mut bounding_box = None;
+        let mut glyphs = HashMap::new();
+        let mut lines = bdf_text.lines();
+
+        while let Some(line) = lines.next() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("FONTBOUNDINGBOX") => {
+                    let values = parse_ints(fields);
+                    if values.len() == 4 {
+                        bounding_box = Some((values[1], values[3]));
+                    }
+                }
+                Some("FONT_ASCENT") => ascent = parse_ints(fields).first().copied(),
+                Some("FONT_DESCENT") => descent = parse_ints(fields).first().copied(),
+                Some("STARTCHAR") => {
+                    if let Some((c, glyph)) = parse_glyph(&mut lines) {
+                        glyphs.insert(c, glyph);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (ascent, descent) = match (ascent, descent, bounding_box) {
+            (Some(ascent), Some(descent), _) => (ascent, descent),
+            (_, _, Some((height, y_offset))) => (height + y_offset, -y_offset),
+            _ => return None,
+        };
+        if glyphs.is_empty() {
+            return None;
+        }
+        Some(Self {
+            ascent,
+            descent,
+            glyphs,
+        })
+    }
+
+    pub fn load(filename: &str) -> PyxelResult<Self> {
+        let bdf_data =
+            vfs::read_file(filename).ok_or_else(|| PyxelError::FileOpen(filename.to_string()))?;
+        let bdf_text = String::from_utf8(bdf_data)
+            .map_err(|_| PyxelError::InvalidFile(filename.to_string()))?;
+        Self::from_bdf(&bdf_text).ok_or_else(|| PyxelError::InvalidFile(filename.to_string()))
+    }
+
+    pub fn height(&self) -> u32 {
+        (self.ascent + self.descent).max(0) as u32
+    }
+
+    pub fn advance(&self, c: char) -> Option<u32> {
+        self.glyphs.get(&c).map(|glyph| glyph.advance.max(0) as u32)
+    }
+
+    // Draws a glyph with its top at y and returns how far to advance, or None if it's missing
+    pub(crate) fn draw_glyph(
+        &self,
+        image: &mut Image,
+        x: i32,
+        y: i32,
+        c: char,
+        value: Color,
+    ) -> Option<i32> {
+        let glyph = self.glyphs.get(&c)?;
+        let left = x + glyph.x_offset;
+        let top = y + self.ascent - glyph.y_offset - glyph.height as i32;
+        for yi in 0..glyph.height {
+            for xi in 0..glyph.width {
+                if glyph.bitmap[(glyph.width * yi + xi) as usize] {
+                    image.canvas.pset(left + xi as i32, top + yi as i32, value);
+                }
+            }
+        }
+        Some(glyph.advance)
+    }
+}
+
+// Lines grow to fit the fallback font so that its glyphs don't overlap the next line
+pub(crate) fn line_height(font: Option<&Font>) -> u32 {
+    font.map_or(FONT_HEIGHT, |font| font.height().max(FONT_HEIGHT))
+}
+
+fn parse_ints<'a>(fields: impl Iterator<Item = &'a str>) -> Vec<i32> {
+    fields.filter_map(|field| field.parse().ok()).collect()
+}
+
+fn parse_glyph<'a>(lines: &mut impl Iterator<Item = &'a str>) -> Option<(char, Glyph)> {
+    let mut code = None;
+    let mut advance = 0;
+    let mut bbx = None;
+    let mut bitmap = Vec::new();
+    let mut in_bitmap = false;
+
+    for line in lines.by_ref() {
+        let mut fields = line.split_whitespace();
+        let keyword = fields.next().unwrap_or("");
+        if keyword == "ENDCHAR" {
+            break;
+        }
+        if in_bitmap {
+            bitmap.push(keyword.to_string());
+            continue;
+        }
+        match keyword {
+            "ENCODING" => code = parse_ints(fields).first().copied(),
+            "DWIDTH" => advance = parse_ints(fields).first().copied().unwrap_or(0),
+            "BBX" => {
+                let values = parse_ints(fields);
+                if values.len() == 4 {
+                    bbx = Some((values[0], values[1], values[2], values[3]));
+                }
+            }
+            "BITMAP" => in_bitmap = true,
+            _ => {}
+        }
+    }
+
+    // Unencoded glyphs have a negative encoding and can't be looked up by character
+    let c = char::from_u32(u32::try_from(code?).ok()?)?;
+    let (width, height, x_offset, y_offset) = bbx?;
+    let width = u32::try_from(width).ok()?;
+    let height = u32::try_from(height).ok()?;
+    if bitmap.len() < height as usize {
+        return None;
+    }
+
+    let mut pixels = Vec::with_capacity((width * height) as usize);
+    for row in bitmap.iter().take(height as usize) {
+        let bits: Vec<bool> = row
+            .chars()
+            .filter_map(|digit| digit.to_digit(16))
+            .flat_map(|nibble| (0..4).rev().map(move |i| (nibble >> i) & 1 == 1))
+            .collect();
+        pixels.extend((0..width as usize).map(|xi| bits.get(xi).copied().unwrap_or(false)));
+    }
+
+    Some((
+        c,
+        Glyph {
+            advance,
+            width,
+            height,
+            x_offset,
+            y_offset,
+            bitmap: pixels,
+        },
+    ))
+}
+
+impl Pyxel {
+    pub fn load_font(&mut self, filename: &str) -> PyxelResult<()> {
+        *FALLBACK_FONT.lock() = Some(Font::load(filename)?);
+        Ok(())
+    }
+
+    pub fn reset_font(&mut self) {
+        *FALLBACK_FONT.lock() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BDF_TEXT: &str = "STARTFONT 2.1
+FONTBOUNDINGBOX 4 4 0 -1
+STARTPROPERTIES 2
+FONT_ASCENT 3
+FONT_DESCENT 1
+ENDPROPERTIES
+CHARS 2
+STARTCHAR uni3042
+ENCODING 12354
+DWIDTH 5 0
+BBX 4 4 0 -1
+BITMAP
+90
+60
+60
+90
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+DWIDTH 5 0
+BBX 4 4 0 -1
+BITMAP
+F0
+F0
+F0
+F0
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn test_from_bdf() {
+        let font = Font::from_bdf(BDF_TEXT).unwrap();
+        assert_eq!(font.height(), 4);
+        assert_eq!(font.advance('あ'), Some(5));
+        assert_eq!(font.advance('a'), None);
+        assert!(Font::from_bdf("").is_none());
+    }
+
+    #[test]
+    fn test_draw_glyph() {
+        let font = Font::from_bdf(BDF_TEXT).unwrap();
+        let image = Image::new(8, 8);
+        let mut image = image.lock();
+        assert_eq!(font.draw_glyph(&mut image, 1, 1, 'あ', 7), Some(5));
+        assert_eq!(image.pget(1.0, 1.0), 7);
+        assert_eq!(image.pget(2.0, 1.0), 0);
+        assert_eq!(image.pget(2.0, 2.0), 7);
+        assert_eq!(font.draw_glyph(&mut image, 0, 0, 'a', 7), None);
+    }
+}
@@ -0,0 +1,54 @@
+// Entry points for the fuzz targets, which feed them arbitrary bytes and expect errors instead of panics
+
+use std::cell::RefCell;
+
+use crate::image::Image;
+use crate::pyxel::Pyxel;
+use crate::sound;
+
+const FUZZ_FILENAME: &str = "fuzz_input";
+const FUZZ_SCREEN_SIZE: u32 = 16;
+
+thread_local! {
+    // Only one Pyxel may exist at a time, so every input is loaded into the same scratch runtime
+    static SCRATCH_PYXEL: RefCell<Option<Pyxel>> = RefCell::new(None);
+}
+
+pub fn fuzz_resource_file(data: &[u8]) {
+    let Ok(resource_file) = Pyxel::decode_resource_file(FUZZ_FILENAME, data.to_vec()) else {
+        return;
+    };
+    SCRATCH_PYXEL.with(|pyxel| {
+        let mut pyxel = pyxel.borrow_mut();
+        if pyxel.is_none() {
+            *pyxel = Some(
+                Pyxel::builder(FUZZ_SCREEN_SIZE, FUZZ_SCREEN_SIZE)
+                    .headless()
+                    .build()
+                    .unwrap(),
+            );
+        }
+        let _ = pyxel.as_mut().unwrap().apply_resource_file(
+            FUZZ_FILENAME,
+            resource_file,
+            Some(false),
+            Some(false),
+            Some(false),
+            Some(false),
+            Some(true),
+            Some(true),
+            Some(true),
+        );
+    });
+}
+
+pub fn fuzz_image_file(data: &[u8], include_colors: bool) {
+    let _ = Image::from_file_data(FUZZ_FILENAME, data, include_colors);
+}
+
+pub fn fuzz_sound_strings(string: &str) {
+    let _ = sound::parse_notes(string);
+    let _ = sound::parse_tones(string);
+    let _ = sound::parse_volumes(string);
+    let _ = sound::parse_effects(string);
+}
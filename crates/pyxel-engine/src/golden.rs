@@ -0,0 +1,135 @@
+use std::env;
+use std::path::Path;
+
+use crate::image::Image;
+use crate::pyxel::Pyxel;
+use crate::system::PyxelCallback;
+
+const UPDATE_GOLDEN_ENV: &str = "PYXEL_UPDATE_GOLDEN";
+
+impl Pyxel {
+    // Skips event polling and presentation, so the screen only depends on the callback
+    pub fn render_frames(&mut self, callback: &mut dyn PyxelCallback, count: u32) {
+        for _ in 0..count {
+            callback.update(self);
+            callback.draw(self);
+            self.frame_count += 1;
+        }
+    }
+}
+
+fn mismatch_ratio(actual: &[u8], expected: &[u8]) -> f64 {
+    if actual.is_empty() {
+        return 0.0;
+    }
+    let num_mismatches = actual
+        .iter()
+        .zip(expected)
+        .filter(|(actual, expected)| actual != expected)
+        .count();
+    num_mismatches as f64 / actual.len() as f64
+}
+
+fn save_indexed_image(screen: &Image, filename: &str) -> Result<(), String> {
+    image::GrayImage::from_raw(screen.width(), screen.height(), screen.canvas.data.clone())
+        .unwrap()
+        .save(filename)
+        .map_err(|_| format!("Failed to write golden image '{filename}'"))
+}
+
+// Golden images store color indices as grayscale, so palette changes don't affect them
+fn check_screen(
+    screen: &Image,
+    filename: &str,
+    tolerance: f64,
+    should_update: bool,
+) -> Result<(), String> {
+    if should_update {
+        return save_indexed_image(screen, filename);
+    }
+
+    let golden = image::open(filename)
+        .map_err(|_| format!("Golden image '{filename}' not found, set {UPDATE_GOLDEN_ENV}"))?
+        .to_luma8();
+    if golden.dimensions() != (screen.width(), screen.height()) {
+        return Err(format!(
+            "Golden image '{filename}' differs in size from the screen"
+        ));
+    }
+
+    let ratio = mismatch_ratio(&screen.canvas.data, golden.as_raw());
+    if ratio > tolerance {
+        let actual_filename = Path::new(filename).with_extension("actual.png");
+        let actual_filename = actual_filename.to_string_lossy();
+        let _ = save_indexed_image(screen, &actual_filename);
+        return Err(format!(
+            "Screen differs from golden image '{filename}' in {:.2}% of pixels, see '{actual_filename}'",
+            ratio * 100.0
+        ));
+    }
+    Ok(())
+}
+
+// Renders the given number of frames and compares the screen with a golden image,
+// allowing the given ratio of differing pixels. Setting PYXEL_UPDATE_GOLDEN writes
+// the rendered screen as the new golden image instead.
+pub fn assert_screen_matches(
+    pyxel: &mut Pyxel,
+    callback: &mut dyn PyxelCallback,
+    frames: u32,
+    filename: &str,
+    tolerance: f64,
+) {
+    pyxel.render_frames(callback, frames);
+    let should_update = env::var(UPDATE_GOLDEN_ENV).is_ok();
+    if let Err(message) = check_screen(&pyxel.screen.lock(), filename, tolerance, should_update) {
+        panic!("{message}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+
+    struct Bouncer {
+        x: f64,
+    }
+
+    impl PyxelCallback for Bouncer {
+        fn update(&mut self, _pyxel: &mut Pyxel) {
+            self.x += 3.0;
+        }
+
+        fn draw(&mut self, pyxel: &mut Pyxel) {
+            pyxel.cls(1);
+            pyxel.rect(self.x, 10.0, 8.0, 8.0, 8);
+            pyxel.circ(32.0, 40.0, 6.0, 11);
+        }
+    }
+
+    #[test]
+    fn test_mismatch_ratio() {
+        assert_eq!(mismatch_ratio(&[], &[]), 0.0);
+        assert_eq!(mismatch_ratio(&[1, 2, 3, 4], &[1, 2, 3, 4]), 0.0);
+        assert_eq!(mismatch_ratio(&[1, 2, 3, 4], &[1, 0, 3, 0]), 0.5);
+    }
+
+    #[test]
+    fn test_assert_screen_matches() {
+        let mut pyxel = headless_pyxel(64, 64);
+        let dir = env::temp_dir().join(format!("pyxel_golden_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let filename = dir.join("bouncer.png");
+        let filename = filename.to_str().unwrap();
+
+        pyxel.render_frames(&mut Bouncer { x: 0.0 }, 5);
+        check_screen(&pyxel.screen.lock(), filename, 0.0, true).unwrap();
+        assert_screen_matches(&mut pyxel, &mut Bouncer { x: 0.0 }, 5, filename, 0.0);
+
+        pyxel.render_frames(&mut Bouncer { x: 20.0 }, 5);
+        let result = check_screen(&pyxel.screen.lock(), filename, 0.0, false);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(result.unwrap_err().contains("differs from golden image"));
+    }
+}
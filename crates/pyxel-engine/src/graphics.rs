@@ -4,9 +4,14 @@ use std::mem::size_of;
 use cfg_if::cfg_if;
 use glow::HasContext;
 
-use crate::image::Color;
+use crate::canvas::IntoCoord;
+use crate::image::{Color, Image, Rgb24, SharedImage};
 use crate::pyxel::Pyxel;
-use crate::settings::{BACKGROUND_COLOR, MAX_COLORS, NUM_SCREEN_TYPES};
+use crate::settings::{
+    BACKGROUND_COLOR, COLOR_FILTER_NONE, DEBUG_WINDOW_SCALE, DEBUG_WINDOW_TITLE, MAX_COLORS,
+    NUM_SCREEN_TYPES,
+};
+use crate::utils::f64_to_i32;
 
 cfg_if! {
     if #[cfg(target_os = "macos")] {
@@ -24,6 +29,25 @@ const SCREEN_FRAGS: [&str; NUM_SCREEN_TYPES as usize] = [
     include_str!("shaders/retro.frag"),
 ];
 
+pub type ViewportCallback = Box<dyn FnMut(&mut Pyxel)>;
+
+#[derive(Default)]
+struct Viewport {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    camera_x: f64,
+    camera_y: f64,
+    callback: Option<ViewportCallback>,
+}
+
+struct PaletteCycle {
+    start: usize,
+    end: usize,
+    speed: i32,
+}
+
 pub struct ScreenShader {
     shader_program: glow::Program,
     uniform_locations: HashMap<String, glow::UniformLocation>,
@@ -32,25 +56,71 @@ pub struct ScreenShader {
 
 pub struct Graphics {
     screen_shaders: Vec<ScreenShader>,
-    screen_texture: glow::NativeTexture,
-    colors_texture: glow::NativeTexture,
+    screen_texture: Option<glow::NativeTexture>,
+    uploaded_screen_data: Vec<Color>,
+    uploaded_screen_size: (u32, u32),
+    colors_texture: Option<glow::NativeTexture>,
+    border_texture: Option<glow::NativeTexture>,
+    border_color: Rgb24,
+    border_image: Option<SharedImage>,
+    uploaded_border_data: Vec<Color>,
+    uploaded_border_size: (u32, u32),
+    viewports: Vec<Viewport>,
+    debug_image: Option<SharedImage>,
+    render_target: Option<SharedImage>,
+    draw_offset_x: f64,
+    draw_offset_y: f64,
+    shake_intensity: f64,
+    shake_frames: u32,
+    shake_frame_count: u32,
+    shake_seed: u32,
+    present_offset: (f32, f32),
+    palette_cycles: Vec<PaletteCycle>,
+    color_filter: u32,
 }
 
 impl Graphics {
     pub fn new() -> Self {
         unsafe {
             let gl = pyxel_platform::glow_context();
-            let screen_shaders = Self::create_screen_shaders(gl);
-            let screen_texture = Self::create_screen_texture(gl);
-            let colors_texture = Self::create_colors_texture(gl);
             Self {
-                screen_shaders,
-                screen_texture,
-                colors_texture,
+                screen_shaders: Self::create_screen_shaders(gl),
+                screen_texture: Some(Self::create_screen_texture(gl)),
+                colors_texture: Some(Self::create_colors_texture(gl)),
+                border_texture: Some(Self::create_border_texture(gl)),
+                ..Self::new_headless()
             }
         }
     }
 
+    // Headless instances draw into the screen image but never present it
+    pub fn new_headless() -> Self {
+        Self {
+            screen_shaders: Vec::new(),
+            screen_texture: None,
+            uploaded_screen_data: Vec::new(),
+            uploaded_screen_size: (0, 0),
+            colors_texture: None,
+            border_texture: None,
+            border_color: BACKGROUND_COLOR,
+            border_image: None,
+            uploaded_border_data: Vec::new(),
+            uploaded_border_size: (0, 0),
+            viewports: Vec::new(),
+            debug_image: None,
+            render_target: None,
+            draw_offset_x: 0.0,
+            draw_offset_y: 0.0,
+            shake_intensity: 0.0,
+            shake_frames: 0,
+            shake_frame_count: 0,
+            shake_seed: 1,
+            present_offset: (0.0, 0.0),
+            palette_cycles: Vec::new(),
+            color_filter: COLOR_FILTER_NONE,
+        }
+    }
+
     unsafe fn create_screen_shaders(gl: &mut glow::Context) -> Vec<ScreenShader> {
         let glsl_version = if pyxel_platform::is_gles_enabled() {
             GLES_VERSION
@@ -105,8 +175,11 @@ impl Graphics {
                 "u_screenScale",
                 "u_numColors",
                 "u_backgroundColor",
+                "u_borderSize",
                 "u_screenTexture",
                 "u_colorsTexture",
+                "u_borderTexture",
+                "u_colorFilter",
             ];
             for &uniform_name in &uniform_names {
                 if let Some(location) = gl.get_uniform_location(shader_program, uniform_name) {
@@ -199,101 +272,201 @@ impl Graphics {
         );
         colors_texture
     }
+
+    unsafe fn create_border_texture(gl: &mut glow::Context) -> glow::NativeTexture {
+        let border_texture = gl.create_texture().unwrap();
+        gl.active_texture(glow::TEXTURE2);
+        gl.bind_texture(glow::TEXTURE_2D, Some(border_texture));
+        gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_MIN_FILTER,
+            glow::NEAREST as i32,
+        );
+        gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_MAG_FILTER,
+            glow::NEAREST as i32,
+        );
+        gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_WRAP_S,
+            glow::CLAMP_TO_EDGE as i32,
+        );
+        gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_WRAP_T,
+            glow::CLAMP_TO_EDGE as i32,
+        );
+        border_texture
+    }
 }
 
 impl Pyxel {
-    pub fn clip(&self, x: f64, y: f64, width: f64, height: f64) {
-        self.screen.lock().clip(x, y, width, height);
+    pub fn clip(
+        &self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+    ) {
+        self.render_target().lock().clip(x, y, width, height);
     }
 
     pub fn clip0(&self) {
-        self.screen.lock().clip0();
+        self.render_target().lock().clip0();
     }
 
-    pub fn camera(&self, x: f64, y: f64) {
-        self.screen.lock().camera(x, y);
+    pub fn camera(&self, x: impl IntoCoord, y: impl IntoCoord) {
+        self.render_target().lock().camera(x, y);
     }
 
     pub fn camera0(&self) {
-        self.screen.lock().camera0();
+        self.render_target().lock().camera0();
     }
 
     pub fn pal(&self, src_color: Color, dst_color: Color) {
-        self.screen.lock().pal(src_color, dst_color);
+        self.render_target().lock().pal(src_color, dst_color);
     }
 
     pub fn pal0(&self) {
-        self.screen.lock().pal0();
+        self.render_target().lock().pal0();
     }
 
     pub fn dither(&self, alpha: f32) {
-        self.screen.lock().dither(alpha);
+        self.render_target().lock().dither(alpha);
     }
 
     pub fn cls(&self, color: Color) {
-        self.screen.lock().cls(color);
+        self.render_target().lock().cls(color);
     }
 
-    pub fn pget(&self, x: f64, y: f64) -> Color {
-        self.screen.lock().pget(x, y)
+    pub fn pget(&self, x: impl IntoCoord, y: impl IntoCoord) -> Color {
+        self.render_target().read().pget(x, y)
     }
 
-    pub fn pset(&self, x: f64, y: f64, color: Color) {
-        self.screen.lock().pset(x, y, color);
+    pub fn pset(&self, x: impl IntoCoord, y: impl IntoCoord, color: Color) {
+        self.render_target().lock().pset(x, y, color);
     }
 
-    pub fn line(&self, x1: f64, y1: f64, x2: f64, y2: f64, color: Color) {
-        self.screen.lock().line(x1, y1, x2, y2, color);
+    pub fn line(
+        &self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        color: Color,
+    ) {
+        self.render_target().lock().line(x1, y1, x2, y2, color);
     }
 
-    pub fn rect(&self, x: f64, y: f64, width: f64, height: f64, color: Color) {
-        self.screen.lock().rect(x, y, width, height, color);
+    pub fn rect(
+        &self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        color: Color,
+    ) {
+        self.render_target().lock().rect(x, y, width, height, color);
     }
 
-    pub fn rectb(&self, x: f64, y: f64, width: f64, height: f64, color: Color) {
-        self.screen.lock().rectb(x, y, width, height, color);
+    pub fn rectb(
+        &self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        color: Color,
+    ) {
+        self.render_target()
+            .lock()
+            .rectb(x, y, width, height, color);
     }
 
-    pub fn circ(&self, x: f64, y: f64, radius: f64, color: Color) {
-        self.screen.lock().circ(x, y, radius, color);
+    pub fn circ(&self, x: impl IntoCoord, y: impl IntoCoord, radius: impl IntoCoord, color: Color) {
+        self.render_target().lock().circ(x, y, radius, color);
     }
 
-    pub fn circb(&self, x: f64, y: f64, radius: f64, color: Color) {
-        self.screen.lock().circb(x, y, radius, color);
+    pub fn circb(
+        &self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        radius: impl IntoCoord,
+        color: Color,
+    ) {
+        self.render_target().lock().circb(x, y, radius, color);
     }
 
-    pub fn elli(&self, x: f64, y: f64, width: f64, height: f64, color: Color) {
-        self.screen.lock().elli(x, y, width, height, color);
+    pub fn elli(
+        &self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        color: Color,
+    ) {
+        self.render_target().lock().elli(x, y, width, height, color);
     }
 
-    pub fn ellib(&self, x: f64, y: f64, width: f64, height: f64, color: Color) {
-        self.screen.lock().ellib(x, y, width, height, color);
+    pub fn ellib(
+        &self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        color: Color,
+    ) {
+        self.render_target()
+            .lock()
+            .ellib(x, y, width, height, color);
     }
 
-    pub fn tri(&self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, color: Color) {
-        self.screen.lock().tri(x1, y1, x2, y2, x3, y3, color);
+    pub fn tri(
+        &self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        x3: impl IntoCoord,
+        y3: impl IntoCoord,
+        color: Color,
+    ) {
+        self.render_target()
+            .lock()
+            .tri(x1, y1, x2, y2, x3, y3, color);
     }
 
-    pub fn trib(&self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, color: Color) {
-        self.screen.lock().trib(x1, y1, x2, y2, x3, y3, color);
+    pub fn trib(
+        &self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        x3: impl IntoCoord,
+        y3: impl IntoCoord,
+        color: Color,
+    ) {
+        self.render_target()
+            .lock()
+            .trib(x1, y1, x2, y2, x3, y3, color);
     }
 
-    pub fn fill(&self, x: f64, y: f64, color: Color) {
-        self.screen.lock().fill(x, y, color);
+    pub fn fill(&self, x: impl IntoCoord, y: impl IntoCoord, color: Color) {
+        self.render_target().lock().fill(x, y, color);
     }
 
     pub fn blt(
         &self,
-        x: f64,
-        y: f64,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
         image_index: u32,
-        image_x: f64,
-        image_y: f64,
-        width: f64,
-        height: f64,
+        image_x: impl IntoCoord,
+        image_y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
         color_key: Option<Color>,
     ) {
-        self.screen.lock().blt(
+        self.render_target().lock().blt(
             x,
             y,
             self.images.lock()[image_index as usize].clone(),
@@ -307,16 +480,16 @@ impl Pyxel {
 
     pub fn bltm(
         &self,
-        x: f64,
-        y: f64,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
         tilemap_index: u32,
-        tilemap_x: f64,
-        tilemap_y: f64,
-        width: f64,
-        height: f64,
+        tilemap_x: impl IntoCoord,
+        tilemap_y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
         color_key: Option<Color>,
     ) {
-        self.screen.lock().bltm(
+        self.render_target().lock().bltm(
             x,
             y,
             self.tilemaps.lock()[tilemap_index as usize].clone(),
@@ -329,105 +502,412 @@ impl Pyxel {
     }
 
     pub fn text(&self, x: f64, y: f64, string: &str, color: Color) {
-        self.screen.lock().text(x, y, string, color);
+        self.render_target().lock().text(x, y, string, color);
+    }
+
+    // Takes the exclusive lock on the render target once for the whole closure, instead of
+    // once per primitive as the functions above do
+    pub fn draw_batch<R>(&self, draw: impl FnOnce(&mut Image) -> R) -> R {
+        draw(&mut self.render_target().lock())
+    }
+
+    pub fn set_render_target(&mut self, image: SharedImage) {
+        self.graphics.render_target = Some(image);
+    }
+
+    pub fn reset_render_target(&mut self) {
+        self.graphics.render_target = None;
+    }
+
+    // Drawing functions target this image, which is the screen unless another image is set
+    pub fn render_target(&self) -> &SharedImage {
+        self.graphics.render_target.as_ref().unwrap_or(&self.screen)
+    }
+
+    pub fn border_color(&mut self, color: Rgb24) {
+        self.graphics.border_color = color;
+    }
+
+    pub fn border_image(&mut self, image: Option<SharedImage>) {
+        self.graphics.border_image = image;
+    }
+
+    // Only changes how the screen is presented, so captures and pget still see the real colors
+    pub fn color_filter(&mut self, mode: u32) {
+        self.graphics.color_filter = mode;
+    }
+
+    pub fn viewport(
+        &mut self,
+        index: u32,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        callback: ViewportCallback,
+    ) {
+        let index = index as usize;
+        if index >= self.graphics.viewports.len() {
+            self.graphics
+                .viewports
+                .resize_with(index + 1, Viewport::default);
+        }
+        let viewport = &mut self.graphics.viewports[index];
+        viewport.x = x;
+        viewport.y = y;
+        viewport.width = width;
+        viewport.height = height;
+        viewport.callback = Some(callback);
+    }
+
+    pub fn viewport_camera(&mut self, index: u32, x: f64, y: f64) {
+        if let Some(viewport) = self.graphics.viewports.get_mut(index as usize) {
+            viewport.camera_x = x;
+            viewport.camera_y = y;
+        }
+    }
+
+    pub fn viewport0(&mut self) {
+        self.graphics.viewports.clear();
+    }
+
+    // Rotates colors start to end by one every speed frames when presenting, reversed if negative
+    pub fn palette_cycle(&mut self, start: u32, end: u32, speed: i32) {
+        let (start, end) = (start.min(end) as usize, start.max(end) as usize);
+        let cycles = &mut self.graphics.palette_cycles;
+        cycles.retain(|cycle| cycle.start != start || cycle.end != end);
+        if speed != 0 {
+            cycles.push(PaletteCycle { start, end, speed });
+        }
+    }
+
+    pub fn palette_cycle0(&mut self) {
+        self.graphics.palette_cycles.clear();
+    }
+
+    // Offsets are applied when the screen is presented, so they don't affect camera or input
+    pub fn draw_offset(&mut self, x: f64, y: f64) {
+        self.graphics.draw_offset_x = x;
+        self.graphics.draw_offset_y = y;
+    }
+
+    pub fn screen_shake(&mut self, intensity: f64, frames: u32) {
+        self.graphics.shake_intensity = intensity.max(0.0);
+        self.graphics.shake_frames = frames;
+        self.graphics.shake_frame_count = frames;
+    }
+
+    pub fn open_debug_window(&mut self, width: u32, height: u32) -> SharedImage {
+        let image = Image::new(width, height);
+        pyxel_platform::open_sub_window(
+            DEBUG_WINDOW_TITLE,
+            width * DEBUG_WINDOW_SCALE,
+            height * DEBUG_WINDOW_SCALE,
+        );
+        self.graphics.debug_image = Some(image.clone());
+        image
+    }
+
+    pub fn close_debug_window(&mut self) {
+        pyxel_platform::close_sub_window();
+        self.graphics.debug_image = None;
+    }
+
+    pub(crate) fn draw_viewports(&mut self) {
+        for index in 0..self.graphics.viewports.len() {
+            if let Some(callback) = self
+                .graphics
+                .viewports
+                .get_mut(index)
+                .and_then(|viewport| viewport.callback.take())
+            {
+                self.draw_viewport(index, callback);
+            }
+        }
     }
 
     pub(crate) fn render_screen(&mut self) {
+        if self.system.is_headless {
+            return;
+        }
         unsafe {
             let gl = pyxel_platform::glow_context();
             self.set_viewport(gl);
+            self.update_present_offset();
             self.use_screen_shader(gl);
             self.bind_screen_texture(gl);
             self.bind_colors_texture(gl);
+            self.bind_border_texture(gl);
             gl.draw_arrays(glow::TRIANGLE_STRIP, 0, 4);
             pyxel_platform::swap_window();
         }
     }
 
+    pub(crate) fn render_debug_window(&self) {
+        if let Some(image) = &self.graphics.debug_image {
+            let image = image.read();
+            let colors = self.colors.lock();
+            let rgb_data: Vec<Rgb24> = image
+                .canvas
+                .data
+                .iter()
+                .map(|&color| colors[color as usize])
+                .collect();
+            pyxel_platform::update_sub_window(image.width(), image.height(), &rgb_data);
+        }
+    }
+
+    fn draw_viewport(&mut self, index: usize, mut callback: ViewportCallback) {
+        let viewport = &self.graphics.viewports[index];
+        let (clip_rect, camera_x, camera_y) = {
+            let mut screen = self.screen.lock();
+            let state = (
+                screen.canvas.clip_rect,
+                screen.canvas.camera_x,
+                screen.canvas.camera_y,
+            );
+            screen.clip(viewport.x, viewport.y, viewport.width, viewport.height);
+            screen.canvas.camera_x = f64_to_i32(viewport.camera_x) - f64_to_i32(viewport.x);
+            screen.canvas.camera_y = f64_to_i32(viewport.camera_y) - f64_to_i32(viewport.y);
+            state
+        };
+        callback(self);
+        {
+            let mut screen = self.screen.lock();
+            screen.canvas.clip_rect = clip_rect;
+            screen.canvas.camera_x = camera_x;
+            screen.canvas.camera_y = camera_y;
+        }
+        if let Some(viewport) = self.graphics.viewports.get_mut(index) {
+            if viewport.callback.is_none() {
+                viewport.callback = Some(callback);
+            }
+        }
+    }
+
+    fn update_present_offset(&mut self) {
+        let graphics = &mut self.graphics;
+        let mut offset_x = graphics.draw_offset_x;
+        let mut offset_y = graphics.draw_offset_y;
+        if graphics.shake_frame_count > 0 {
+            // Shake decays linearly and uses its own generator to keep the game's rndf reproducible
+            let intensity = graphics.shake_intensity * graphics.shake_frame_count as f64
+                / graphics.shake_frames as f64;
+            let mut next_random = || {
+                graphics.shake_seed ^= graphics.shake_seed << 13;
+                graphics.shake_seed ^= graphics.shake_seed >> 17;
+                graphics.shake_seed ^= graphics.shake_seed << 5;
+                graphics.shake_seed as f64 / u32::MAX as f64 * 2.0 - 1.0
+            };
+            offset_x += (next_random() * intensity).round();
+            offset_y += (next_random() * intensity).round();
+            graphics.shake_frame_count -= 1;
+        }
+        let scale_x = self.system.screen_width as f64 / self.width as f64;
+        let scale_y = self.system.screen_height as f64 / self.height as f64;
+        graphics.present_offset = ((offset_x * scale_x) as f32, (offset_y * scale_y) as f32);
+    }
+
     unsafe fn set_viewport(&self, gl: &mut glow::Context) {
-        let (window_width, window_height) = pyxel_platform::window_size();
-        gl.viewport(0, 0, window_width as i32, window_height as i32);
+        let (drawable_width, drawable_height) = pyxel_platform::drawable_size();
+        gl.viewport(0, 0, drawable_width as i32, drawable_height as i32);
     }
 
     unsafe fn use_screen_shader(&self, gl: &mut glow::Context) {
         let shader = &self.graphics.screen_shaders[self.system.screen_mode as usize];
         gl.use_program(Some(shader.shader_program));
         let uniform_locations = &shader.uniform_locations;
+
+        // Screen parameters are in window units, while the shader works in drawable pixels
+        let dpi_scale = self.dpi_scale() as f32;
         if let Some(location) = uniform_locations.get("u_screenPos") {
-            let (_, window_height) = pyxel_platform::window_size();
+            let (_, drawable_height) = pyxel_platform::drawable_size();
+            let (offset_x, offset_y) = self.graphics.present_offset;
             gl.uniform_2_f32(
                 Some(location),
-                self.system.screen_x as f32,
-                (window_height as i32
-                    - self.system.screen_y
-                    - (self.height * self.system.screen_scale) as i32) as f32,
+                (self.system.screen_x as f32 + offset_x) * dpi_scale,
+                drawable_height as f32
+                    - ((self.system.screen_y + self.system.screen_height as i32) as f32 + offset_y)
+                        * dpi_scale,
             );
         }
         if let Some(location) = uniform_locations.get("u_screenSize") {
             gl.uniform_2_f32(
                 Some(location),
-                (self.width * self.system.screen_scale) as f32,
-                (self.height * self.system.screen_scale) as f32,
+                self.system.screen_width as f32 * dpi_scale,
+                self.system.screen_height as f32 * dpi_scale,
             );
         }
         if let Some(location) = uniform_locations.get("u_screenScale") {
-            gl.uniform_1_f32(Some(location), self.system.screen_scale as f32);
+            gl.uniform_2_f32(
+                Some(location),
+                self.system.screen_width as f32 * dpi_scale / self.width as f32,
+                self.system.screen_height as f32 * dpi_scale / self.height as f32,
+            );
         }
         if let Some(location) = uniform_locations.get("u_numColors") {
             gl.uniform_1_i32(Some(location), self.colors.lock().len() as i32);
         }
         if let Some(location) = uniform_locations.get("u_backgroundColor") {
+            let border_color = self.graphics.border_color;
             gl.uniform_3_f32(
                 Some(location),
-                ((BACKGROUND_COLOR >> 16) as u8) as f32 / 255.0,
-                ((BACKGROUND_COLOR >> 8) as u8) as f32 / 255.0,
-                (BACKGROUND_COLOR as u8) as f32 / 255.0,
+                ((border_color >> 16) as u8) as f32 / 255.0,
+                ((border_color >> 8) as u8) as f32 / 255.0,
+                (border_color as u8) as f32 / 255.0,
+            );
+        }
+        if let Some(location) = uniform_locations.get("u_borderSize") {
+            let (width, height) = self
+                .graphics
+                .border_image
+                .as_ref()
+                .map_or((0, 0), |image| (image.width(), image.height()));
+            gl.uniform_2_f32(
+                Some(location),
+                (width * self.system.screen_width) as f32 * dpi_scale / self.width as f32,
+                (height * self.system.screen_height) as f32 * dpi_scale / self.height as f32,
             );
         }
+        if let Some(location) = uniform_locations.get("u_colorFilter") {
+            gl.uniform_1_i32(Some(location), self.graphics.color_filter as i32);
+        }
         if let Some(location) = uniform_locations.get("u_screenTexture") {
             gl.uniform_1_i32(Some(location), 0);
         }
         if let Some(location) = uniform_locations.get("u_colorsTexture") {
             gl.uniform_1_i32(Some(location), 1);
         }
+        if let Some(location) = uniform_locations.get("u_borderTexture") {
+            gl.uniform_1_i32(Some(location), 2);
+        }
         gl.bind_vertex_array(Some(shader.vertex_array));
     }
 
-    unsafe fn bind_screen_texture(&self, gl: &mut glow::Context) {
+    unsafe fn bind_screen_texture(&mut self, gl: &mut glow::Context) {
         gl.active_texture(glow::TEXTURE0);
-        gl.bind_texture(glow::TEXTURE_2D, Some(self.graphics.screen_texture));
+        gl.bind_texture(glow::TEXTURE_2D, self.graphics.screen_texture);
         gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
         let texture_format = if pyxel_platform::is_gles_enabled() {
             glow::LUMINANCE
         } else {
             glow::RED
         };
-        gl.tex_image_2d(
+        let screen = self.screen.read();
+        let transition_data =
+            self.system
+                .transition
+                .apply_to_screen(&screen.canvas.data, self.width, self.height);
+        let screen_data = transition_data.as_ref().unwrap_or(&screen.canvas.data);
+        let uploaded_data = &mut self.graphics.uploaded_screen_data;
+
+        // The texture is reallocated on any size change, since a reshaped screen can
+        // keep the same number of pixels
+        if self.graphics.uploaded_screen_size != (self.width, self.height) {
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                texture_format as i32,
+                self.width as i32,
+                self.height as i32,
+                0,
+                texture_format,
+                glow::UNSIGNED_BYTE,
+                Some(screen_data),
+            );
+            uploaded_data.clone_from(screen_data);
+            self.graphics.uploaded_screen_size = (self.width, self.height);
+            return;
+        }
+
+        // Dirty rows are found by comparing with the last upload, since the screen
+        // data can also be written directly through its raw pointer
+        let width = self.width as usize;
+        let is_dirty_row = |(row, uploaded_row): (&[Color], &[Color])| row != uploaded_row;
+        let rows = || screen_data.chunks(width).zip(uploaded_data.chunks(width));
+        let Some(top) = rows().position(is_dirty_row) else {
+            return;
+        };
+        let bottom = self.height as usize - 1 - rows().rev().position(is_dirty_row).unwrap();
+        let dirty_data = &screen_data[width * top..width * (bottom + 1)];
+        gl.tex_sub_image_2d(
             glow::TEXTURE_2D,
             0,
-            texture_format as i32,
-            self.width as i32,
-            self.height as i32,
             0,
+            top as i32,
+            self.width as i32,
+            (bottom - top + 1) as i32,
             texture_format,
             glow::UNSIGNED_BYTE,
-            Some(&self.screen.lock().canvas.data),
+            glow::PixelUnpackData::Slice(dirty_data),
         );
+        uploaded_data[width * top..width * (bottom + 1)].copy_from_slice(dirty_data);
+    }
+
+    unsafe fn bind_border_texture(&mut self, gl: &mut glow::Context) {
+        if let Some(border_image) = &self.graphics.border_image {
+            gl.active_texture(glow::TEXTURE2);
+            gl.bind_texture(glow::TEXTURE_2D, self.graphics.border_texture);
+
+            // Border images rarely change, so they're only uploaded when they differ
+            // from the last upload
+            let border_image = border_image.read();
+            let border_size = (border_image.width(), border_image.height());
+            if self.graphics.uploaded_border_size == border_size
+                && self.graphics.uploaded_border_data == border_image.canvas.data
+            {
+                return;
+            }
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            let texture_format = if pyxel_platform::is_gles_enabled() {
+                glow::LUMINANCE
+            } else {
+                glow::RED
+            };
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                texture_format as i32,
+                border_image.width() as i32,
+                border_image.height() as i32,
+                0,
+                texture_format,
+                glow::UNSIGNED_BYTE,
+                Some(&border_image.canvas.data),
+            );
+            self.graphics
+                .uploaded_border_data
+                .clone_from(&border_image.canvas.data);
+            self.graphics.uploaded_border_size = border_size;
+        }
     }
 
     #[allow(clippy::uninlined_format_args)]
     unsafe fn bind_colors_texture(&self, gl: &mut glow::Context) {
         gl.active_texture(glow::TEXTURE1);
-        gl.bind_texture(glow::TEXTURE_2D, Some(self.graphics.colors_texture));
+        gl.bind_texture(glow::TEXTURE_2D, self.graphics.colors_texture);
         gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
-        let colors = self.colors.lock();
+        let mut colors = self.colors.lock().clone();
         assert!(
             colors.len() >= 1 && colors.len() <= MAX_COLORS as usize,
             "Number of colors must be between 1 to {}",
             MAX_COLORS
         );
+        for cycle in &self.graphics.palette_cycles {
+            if cycle.end >= colors.len() {
+                continue;
+            }
+            let range = &mut colors[cycle.start..=cycle.end];
+            let shift = (self.frame_count / cycle.speed.unsigned_abs()) as usize % range.len();
+            if cycle.speed > 0 {
+                range.rotate_right(shift);
+            } else {
+                range.rotate_left(shift);
+            }
+        }
+        self.system.transition.fade_colors(&mut colors);
         let mut pixels: Vec<u8> = Vec::with_capacity(colors.len() * 3);
-        for color in &*colors {
+        for color in &colors {
             pixels.push((color >> 16) as u8);
             pixels.push((color >> 8) as u8);
             pixels.push(*color as u8);
@@ -0,0 +1,288 @@
+use std::fs;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::error::PyxelResult;
+use crate::image::SharedImage;
+use crate::pyxel::Pyxel;
+use crate::tilemap::SharedTilemap;
+
+#[derive(Clone)]
+enum ReloadTarget {
+    Resource {
+        exclude_images: Option<bool>,
+        exclude_tilemaps: Option<bool>,
+        exclude_sounds: Option<bool>,
+        exclude_musics: Option<bool>,
+        include_colors: Option<bool>,
+        include_channels: Option<bool>,
+        include_tones: Option<bool>,
+    },
+    Image {
+        image: SharedImage,
+        x: i32,
+        y: i32,
+        include_colors: Option<bool>,
+    },
+    Tilemap {
+        tilemap: SharedTilemap,
+        x: i32,
+        y: i32,
+        layer_index: u32,
+    },
+}
+
+impl ReloadTarget {
+    fn is_same(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Resource { .. }, Self::Resource { .. }) => true,
+            (Self::Image { image: image1, .. }, Self::Image { image: image2, .. }) => {
+                image1.ptr_eq(image2)
+            }
+            (
+                Self::Tilemap {
+                    tilemap: tilemap1, ..
+                },
+                Self::Tilemap {
+                    tilemap: tilemap2, ..
+                },
+            ) => Arc::ptr_eq(tilemap1, tilemap2),
+            _ => false,
+        }
+    }
+}
+
+struct WatchedFile {
+    filename: String,
+    modified: Option<SystemTime>,
+    pending_modified: Option<SystemTime>,
+    target: ReloadTarget,
+}
+
+impl WatchedFile {
+    // Returns true once a new timestamp has been seen twice in a row, so partially written files are not read
+    fn update_modified(&mut self, modified: Option<SystemTime>) -> bool {
+        if modified.is_none() || modified == self.modified {
+            self.pending_modified = None;
+            false
+        } else if modified == self.pending_modified {
+            self.modified = modified;
+            self.pending_modified = None;
+            true
+        } else {
+            self.pending_modified = modified;
+            false
+        }
+    }
+}
+
+pub struct HotReload {
+    enabled: bool,
+    watched_files: Vec<WatchedFile>,
+}
+
+impl HotReload {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            watched_files: Vec::new(),
+        }
+    }
+
+    fn watch(&mut self, filename: &str, target: ReloadTarget) {
+        if !self.enabled {
+            return;
+        }
+        let watched_file = WatchedFile {
+            filename: filename.to_string(),
+            modified: Self::modified_time(filename),
+            pending_modified: None,
+            target,
+        };
+        if let Some(index) = self.watched_files.iter().position(|file| {
+            file.filename == watched_file.filename && file.target.is_same(&watched_file.target)
+        }) {
+            self.watched_files[index] = watched_file;
+        } else {
+            self.watched_files.push(watched_file);
+        }
+    }
+
+    fn modified_time(filename: &str) -> Option<SystemTime> {
+        fs::metadata(filename)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
+
+impl Pyxel {
+    pub fn hot_reload(&mut self, enabled: bool) {
+        self.resource.hot_reload.enabled = enabled;
+        if !enabled {
+            self.resource.hot_reload.watched_files.clear();
+        }
+    }
+
+    // Loads the file into the image and reloads it on change while hot reload is enabled
+    pub fn load_image_file(
+        &mut self,
+        image: SharedImage,
+        x: i32,
+        y: i32,
+        filename: &str,
+        include_colors: Option<bool>,
+    ) -> PyxelResult<()> {
+        image.lock().load(x, y, filename, include_colors)?;
+        self.resource.hot_reload.watch(
+            filename,
+            ReloadTarget::Image {
+                image,
+                x,
+                y,
+                include_colors,
+            },
+        );
+        Ok(())
+    }
+
+    // Loads the TMX layer into the tilemap and reloads it on change while hot reload is enabled
+    pub fn load_tilemap_file(
+        &mut self,
+        tilemap: SharedTilemap,
+        x: i32,
+        y: i32,
+        filename: &str,
+        layer_index: u32,
+    ) -> PyxelResult<()> {
+        tilemap.lock().load(x, y, filename, layer_index)?;
+        self.resource.hot_reload.watch(
+            filename,
+            ReloadTarget::Tilemap {
+                tilemap,
+                x,
+                y,
+                layer_index,
+            },
+        );
+        Ok(())
+    }
+
+    pub(crate) fn watch_resource_file(
+        &mut self,
+        filename: &str,
+        exclude_images: Option<bool>,
+        exclude_tilemaps: Option<bool>,
+        exclude_sounds: Option<bool>,
+        exclude_musics: Option<bool>,
+        include_colors: Option<bool>,
+        include_channels: Option<bool>,
+        include_tones: Option<bool>,
+    ) {
+        self.resource.hot_reload.watch(
+            filename,
+            ReloadTarget::Resource {
+                exclude_images,
+                exclude_tilemaps,
+                exclude_sounds,
+                exclude_musics,
+                include_colors,
+                include_channels,
+                include_tones,
+            },
+        );
+    }
+
+    pub(crate) fn check_hot_reload(&mut self) {
+        if !self.resource.hot_reload.enabled {
+            return;
+        }
+        let mut reload_files = Vec::new();
+        for watched_file in &mut self.resource.hot_reload.watched_files {
+            let modified = HotReload::modified_time(&watched_file.filename);
+            if watched_file.update_modified(modified) {
+                reload_files.push((watched_file.filename.clone(), watched_file.target.clone()));
+            }
+        }
+
+        for (filename, target) in reload_files {
+            let result = match target {
+                ReloadTarget::Resource {
+                    exclude_images,
+                    exclude_tilemaps,
+                    exclude_sounds,
+                    exclude_musics,
+                    include_colors,
+                    include_channels,
+                    include_tones,
+                } => self.load(
+                    &filename,
+                    exclude_images,
+                    exclude_tilemaps,
+                    exclude_sounds,
+                    exclude_musics,
+                    include_colors,
+                    include_channels,
+                    include_tones,
+                ),
+                ReloadTarget::Image {
+                    image,
+                    x,
+                    y,
+                    include_colors,
+                } => image.lock().load(x, y, &filename, include_colors),
+                ReloadTarget::Tilemap {
+                    tilemap,
+                    x,
+                    y,
+                    layer_index,
+                } => tilemap.lock().load(x, y, &filename, layer_index),
+            };
+            if result.is_ok() {
+                log::info!("Reloaded '{filename}'");
+            }
+            self.print_error(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_update_modified() {
+        let time = |sec| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(sec));
+        let mut watched_file = WatchedFile {
+            filename: "test.png".to_string(),
+            modified: time(1),
+            pending_modified: None,
+            target: ReloadTarget::Resource {
+                exclude_images: None,
+                exclude_tilemaps: None,
+                exclude_sounds: None,
+                exclude_musics: None,
+                include_colors: None,
+                include_channels: None,
+                include_tones: None,
+            },
+        };
+
+        assert!(!watched_file.update_modified(time(1)));
+        assert!(!watched_file.update_modified(None));
+
+        // A change is reported only after the timestamp stays the same for two checks
+        assert!(!watched_file.update_modified(time(2)));
+        assert!(!watched_file.update_modified(time(3)));
+        assert!(watched_file.update_modified(time(3)));
+        assert_eq!(watched_file.modified, time(3));
+        assert!(!watched_file.update_modified(time(3)));
+
+        // A deleted file cancels a pending reload
+        assert!(!watched_file.update_modified(time(4)));
+        assert!(!watched_file.update_modified(None));
+        assert!(!watched_file.update_modified(time(4)));
+        assert!(watched_file.update_modified(time(4)));
+    }
+}
@@ -1,17 +1,23 @@
 use std::array;
 use std::collections::HashMap;
-use std::path::Path;
+use std::sync::Arc;
 
 use image::{self, imageops};
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use crate::canvas::{Canvas, CopyArea, ToIndex};
-use crate::pyxel::{COLORS, FONT_IMAGE, IMAGES};
+use crate::canvas::{self, Canvas, CopyArea, CopyRow, IntoCoord, ToIndex};
+use crate::error::{PyxelError, PyxelResult};
+use crate::font;
+use crate::pyxel::{COLORS, FALLBACK_FONT, FONT_IMAGE, IMAGES};
 use crate::rect_area::RectArea;
+use crate::scale_filter::ScaleFilter;
 use crate::settings::{
     FONT_HEIGHT, FONT_WIDTH, MAX_COLORS, MAX_FONT_CODE, MIN_FONT_CODE, NUM_FONT_ROWS, TILE_SIZE,
 };
+use crate::simd;
 use crate::tilemap::{ImageSource, SharedTilemap};
 use crate::utils;
+use crate::vfs;
 
 pub type Rgb24 = u32;
 pub type Color = u8;
@@ -22,33 +28,103 @@ impl ToIndex for Color {
     }
 }
 
+impl CopyRow for Color {
+    fn copy_row(
+        dst: &mut [Self],
+        src: &[Self],
+        transparent: Option<Self>,
+        palette: Option<&[Self]>,
+    ) {
+        match (palette, transparent) {
+            (Some(palette), _) => simd::remap_row(dst, src, palette, transparent),
+            (None, Some(transparent)) => simd::copy_row_with_transparent(dst, src, transparent),
+            (None, None) => dst.copy_from_slice(src),
+        }
+    }
+}
+
 pub struct Image {
     pub(crate) canvas: Canvas<Color>,
     pub(crate) palette: [Color; MAX_COLORS as usize],
 }
 
-pub type SharedImage = shared_type!(Image);
+// Threading contract: images are drawn only from the main thread. Drawing takes the
+// exclusive lock, while code that only reads pixels, such as texture uploads, captures and
+// blt sources, takes the shared lock, so readers never wait on each other. The size of an
+// image never changes, so it is cached here and can be read without any lock.
+//
+// Drawing from another image keeps the destination locked while reading the source: blt
+// reads the source image, and bltm locks the tilemap, then IMAGES briefly to resolve the
+// tile source, then reads that image. Sources are taken with try_read, so drawing an image
+// onto itself reads from a copy instead of deadlocking.
+#[derive(Clone)]
+pub struct SharedImage {
+    image: Arc<RwLock<Image>>,
+    width: u32,
+    height: u32,
+}
+
+impl SharedImage {
+    fn new(image: Image) -> Self {
+        Self {
+            width: image.width(),
+            height: image.height(),
+            image: Arc::new(RwLock::new(image)),
+        }
+    }
+
+    // Exclusive access for drawing
+    pub fn lock(&self) -> RwLockWriteGuard<'_, Image> {
+        self.image.write()
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, Image> {
+        self.image.read()
+    }
+
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, Image>> {
+        self.image.try_read()
+    }
+
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.image, &other.image)
+    }
+}
 
 impl Image {
     pub fn new(width: u32, height: u32) -> SharedImage {
-        new_shared_type!(Self {
+        SharedImage::new(Self {
             canvas: Canvas::new(width, height),
             palette: array::from_fn(|i| i as Color),
         })
     }
 
-    pub fn from_image(filename: &str, include_colors: Option<bool>) -> SharedImage {
-        let include_colors = include_colors.unwrap_or(false);
+    pub fn from_image(filename: &str, include_colors: Option<bool>) -> PyxelResult<SharedImage> {
+        let file_data =
+            vfs::read_file(filename).ok_or_else(|| PyxelError::FileOpen(filename.to_string()))?;
+        Self::from_file_data(filename, &file_data, include_colors.unwrap_or(false))
+    }
+
+    pub(crate) fn from_file_data(
+        filename: &str,
+        file_data: &[u8],
+        include_colors: bool,
+    ) -> PyxelResult<SharedImage> {
+        let file_image = image::load_from_memory(file_data)
+            .map_err(|_| PyxelError::InvalidFile(filename.to_string()))?
+            .to_rgb8();
         let mut colors = COLORS.lock();
         if include_colors {
             colors.clear();
         }
-        let file = image::open(Path::new(&filename));
-        if file.is_err() {
-            println!("Failed to open file '{filename}'");
-            return Self::new(1, 1);
-        }
-        let file_image = file.unwrap().to_rgb8();
         let (width, height) = file_image.dimensions();
         let image = Self::new(width, height);
         {
@@ -62,7 +138,8 @@ impl Image {
                         image.canvas.write_data(x as usize, y as usize, *color);
                     } else {
                         let mut closest_color: Color = 0;
-                        if include_colors {
+                        // Colors beyond the palette limit fall back to the closest registered color
+                        if include_colors && colors.len() < MAX_COLORS as usize {
                             colors.push(
                                 (src_rgb.0 as u32) << 16
                                     | (src_rgb.1 as u32) << 8
@@ -92,7 +169,7 @@ impl Image {
                 }
             }
         }
-        image
+        Ok(image)
     }
 
     pub const fn width(&self) -> u32 {
@@ -107,20 +184,16 @@ impl Image {
         self.canvas.data_ptr()
     }
 
-    pub fn set(&mut self, x: i32, y: i32, data_str: &[&str]) {
-        let width = utils::simplify_string(data_str[0]).len() as u32;
-        let height = data_str.len() as u32;
+    pub fn set(&mut self, x: i32, y: i32, data_str: &[&str]) -> PyxelResult<()> {
+        let rows = utils::parse_hex_rows(data_str, 1).map_err(PyxelError::InvalidArgument)?;
+        let width = rows[0].len() as u32;
+        let height = rows.len() as u32;
         let image = Self::new(width, height);
         {
             let mut image = image.lock();
-            for y in 0..height {
-                let src_data = utils::simplify_string(data_str[y as usize]);
-                for x in 0..width {
-                    let color =
-                        utils::parse_hex_string(&src_data[x as usize..=x as usize]).unwrap();
-                    image
-                        .canvas
-                        .write_data(x as usize, y as usize, color as Color);
+            for (y, row) in rows.iter().enumerate() {
+                for (x, color) in row.iter().enumerate() {
+                    image.canvas.write_data(x, y, *color as Color);
                 }
             }
         }
@@ -134,12 +207,18 @@ impl Image {
             height as f64,
             None,
         );
+        Ok(())
     }
 
-    pub fn load(&mut self, x: i32, y: i32, filename: &str, include_colors: Option<bool>) {
-        let image = Self::from_image(filename, include_colors);
-        let width = image.lock().width();
-        let height = image.lock().height();
+    pub fn load(
+        &mut self,
+        x: i32,
+        y: i32,
+        filename: &str,
+        include_colors: Option<bool>,
+    ) -> PyxelResult<()> {
+        let image = Self::from_image(filename, include_colors)?;
+        let (width, height) = (image.width(), image.height());
         self.blt(
             x as f64,
             y as f64,
@@ -150,35 +229,65 @@ impl Image {
             height as f64,
             None,
         );
+        Ok(())
+    }
+
+    pub fn save(&self, filename: &str, scale: u32) -> PyxelResult<()> {
+        self.save_with_filter(filename, scale, ScaleFilter::Nearest)
+    }
+
+    pub(crate) fn save_with_filter(
+        &self,
+        filename: &str,
+        scale: u32,
+        filter: ScaleFilter,
+    ) -> PyxelResult<()> {
+        let image = self.to_rgb_image(scale, filter);
+        let filename = utils::add_file_extension(filename, ".png");
+        image
+            .save(&filename)
+            .map_err(|_| PyxelError::FileWrite(filename))
     }
 
-    pub fn save(&self, filename: &str, scale: u32) {
+    pub(crate) fn to_rgb_image(&self, scale: u32, filter: ScaleFilter) -> image::RgbImage {
         let colors = COLORS.lock();
+        let filter = filter.for_scale(scale);
+        let factor = filter.factor();
         let width = self.width();
         let height = self.height();
+        let data: Vec<Color> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| self.canvas.read_data(x as usize, y as usize))
+            .collect();
+        let data = filter.apply(&data, width, height);
+        let width = width * factor;
+        let height = height * factor;
+        let scale = scale / factor;
         let mut image = image::RgbImage::new(width, height);
         for y in 0..height {
             for x in 0..width {
-                let rgb = colors[self.canvas.read_data(x as usize, y as usize) as usize];
+                let rgb = colors[data[(width * y + x) as usize] as usize];
                 let r = (rgb >> 16) as u8;
                 let g = (rgb >> 8) as u8;
                 let b = rgb as u8;
                 image.put_pixel(x, y, image::Rgb([r, g, b]));
             }
         }
-        let image = imageops::resize(
+        imageops::resize(
             &image,
             width * scale,
             height * scale,
             imageops::FilterType::Nearest,
-        );
-        let filename = utils::add_file_extension(filename, ".png");
-        image
-            .save(&filename)
-            .unwrap_or_else(|_| panic!("Failed to open file '{filename}'"));
+        )
     }
 
-    pub fn clip(&mut self, x: f64, y: f64, width: f64, height: f64) {
+    pub fn clip(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+    ) {
         self.canvas.clip(x, y, width, height);
     }
 
@@ -186,7 +295,7 @@ impl Image {
         self.canvas.clip0();
     }
 
-    pub fn camera(&mut self, x: f64, y: f64) {
+    pub fn camera(&mut self, x: impl IntoCoord, y: impl IntoCoord) {
         self.canvas.camera(x, y);
     }
 
@@ -212,74 +321,139 @@ impl Image {
         self.canvas.cls(self.palette[color as usize]);
     }
 
-    pub fn pget(&mut self, x: f64, y: f64) -> Color {
+    pub fn pget(&self, x: impl IntoCoord, y: impl IntoCoord) -> Color {
         self.canvas.pget(x, y)
     }
 
-    pub fn pset(&mut self, x: f64, y: f64, color: Color) {
+    pub fn pset(&mut self, x: impl IntoCoord, y: impl IntoCoord, color: Color) {
         self.canvas.pset(x, y, self.palette[color as usize]);
     }
 
-    pub fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, color: Color) {
+    pub fn line(
+        &mut self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        color: Color,
+    ) {
         self.canvas
             .line(x1, y1, x2, y2, self.palette[color as usize]);
     }
 
-    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, color: Color) {
+    pub fn rect(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        color: Color,
+    ) {
         self.canvas
             .rect(x, y, width, height, self.palette[color as usize]);
     }
 
-    pub fn rectb(&mut self, x: f64, y: f64, width: f64, height: f64, color: Color) {
+    pub fn rectb(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        color: Color,
+    ) {
         self.canvas
             .rectb(x, y, width, height, self.palette[color as usize]);
     }
 
-    pub fn circ(&mut self, x: f64, y: f64, radius: f64, color: Color) {
+    pub fn circ(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        radius: impl IntoCoord,
+        color: Color,
+    ) {
         self.canvas.circ(x, y, radius, self.palette[color as usize]);
     }
 
-    pub fn circb(&mut self, x: f64, y: f64, radius: f64, color: Color) {
+    pub fn circb(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        radius: impl IntoCoord,
+        color: Color,
+    ) {
         self.canvas
             .circb(x, y, radius, self.palette[color as usize]);
     }
 
-    pub fn elli(&mut self, x: f64, y: f64, width: f64, height: f64, color: Color) {
+    pub fn elli(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        color: Color,
+    ) {
         self.canvas
             .elli(x, y, width, height, self.palette[color as usize]);
     }
 
-    pub fn ellib(&mut self, x: f64, y: f64, width: f64, height: f64, color: Color) {
+    pub fn ellib(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        color: Color,
+    ) {
         self.canvas
             .ellib(x, y, width, height, self.palette[color as usize]);
     }
 
-    pub fn tri(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, color: Color) {
+    pub fn tri(
+        &mut self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        x3: impl IntoCoord,
+        y3: impl IntoCoord,
+        color: Color,
+    ) {
         self.canvas
             .tri(x1, y1, x2, y2, x3, y3, self.palette[color as usize]);
     }
 
-    pub fn trib(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, color: Color) {
+    pub fn trib(
+        &mut self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        x3: impl IntoCoord,
+        y3: impl IntoCoord,
+        color: Color,
+    ) {
         self.canvas
             .trib(x1, y1, x2, y2, x3, y3, self.palette[color as usize]);
     }
 
-    pub fn fill(&mut self, x: f64, y: f64, color: Color) {
+    pub fn fill(&mut self, x: impl IntoCoord, y: impl IntoCoord, color: Color) {
         self.canvas.fill(x, y, self.palette[color as usize]);
     }
 
     pub fn blt(
         &mut self,
-        x: f64,
-        y: f64,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
         image: SharedImage,
-        image_x: f64,
-        image_y: f64,
-        width: f64,
-        height: f64,
+        image_x: impl IntoCoord,
+        image_y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
         transparent: Option<Color>,
     ) {
-        if let Some(image) = image.try_lock() {
+        if let Some(image) = image.try_read() {
             self.canvas.blt(
                 x,
                 y,
@@ -292,8 +466,8 @@ impl Image {
                 Some(&self.palette),
             );
         } else {
-            let copy_width = utils::f64_to_u32(width.abs());
-            let copy_height = utils::f64_to_u32(height.abs());
+            let copy_width = width.to_coord().unsigned_abs();
+            let copy_height = height.to_coord().unsigned_abs();
             let mut canvas = Canvas::new(copy_width, copy_height);
             canvas.blt(
                 0.0,
@@ -301,8 +475,8 @@ impl Image {
                 &self.canvas,
                 image_x,
                 image_y,
-                copy_width as f64,
-                copy_height as f64,
+                copy_width as i32,
+                copy_height as i32,
                 None,
                 None,
             );
@@ -320,23 +494,128 @@ impl Image {
         }
     }
 
+    // Repeats the source region over the destination area, cropping the last row and column
+    pub fn blt_tiled(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        image: SharedImage,
+        image_x: impl IntoCoord,
+        image_y: impl IntoCoord,
+        tile_width: impl IntoCoord,
+        tile_height: impl IntoCoord,
+        transparent: Option<Color>,
+    ) {
+        let (x, y) = (x.to_coord(), y.to_coord());
+        let (width, height) = (width.to_size() as i32, height.to_size() as i32);
+        let (image_x, image_y) = (image_x.to_coord(), image_y.to_coord());
+        let (tile_width, tile_height) = (tile_width.to_size() as i32, tile_height.to_size() as i32);
+        if tile_width == 0 || tile_height == 0 {
+            return;
+        }
+        for offset_y in (0..height).step_by(tile_height as usize) {
+            for offset_x in (0..width).step_by(tile_width as usize) {
+                self.blt(
+                    x + offset_x,
+                    y + offset_y,
+                    image.clone(),
+                    image_x,
+                    image_y,
+                    tile_width.min(width - offset_x),
+                    tile_height.min(height - offset_y),
+                    transparent,
+                );
+            }
+        }
+    }
+
+    // Keeps the corners at their original size and tiles the edges and center to fill the area
+    pub fn blt_9slice(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        image: SharedImage,
+        image_x: impl IntoCoord,
+        image_y: impl IntoCoord,
+        image_width: impl IntoCoord,
+        image_height: impl IntoCoord,
+        margins: (u32, u32, u32, u32),
+        transparent: Option<Color>,
+    ) {
+        let (x, y) = (x.to_coord(), y.to_coord());
+        let (width, height) = (width.to_size() as i32, height.to_size() as i32);
+        let (image_x, image_y) = (image_x.to_coord(), image_y.to_coord());
+        let (image_width, image_height) =
+            (image_width.to_size() as i32, image_height.to_size() as i32);
+        let (left, top, right, bottom) = margins;
+
+        // Margins are shrunk when the destination is smaller than the corners
+        let left = (left as i32).min(width / 2).min(image_width);
+        let right = (right as i32).min(width - left).min(image_width - left);
+        let top = (top as i32).min(height / 2).min(image_height);
+        let bottom = (bottom as i32).min(height - top).min(image_height - top);
+
+        let src_columns = [
+            (image_x, left),
+            (image_x + left, image_width - left - right),
+            (image_x + image_width - right, right),
+        ];
+        let src_rows = [
+            (image_y, top),
+            (image_y + top, image_height - top - bottom),
+            (image_y + image_height - bottom, bottom),
+        ];
+        let dst_columns = [
+            (x, left),
+            (x + left, width - left - right),
+            (x + width - right, right),
+        ];
+        let dst_rows = [
+            (y, top),
+            (y + top, height - top - bottom),
+            (y + height - bottom, bottom),
+        ];
+        for ((src_y, src_height), (dst_y, dst_height)) in src_rows.into_iter().zip(dst_rows) {
+            for ((src_x, src_width), (dst_x, dst_width)) in src_columns.into_iter().zip(dst_columns)
+            {
+                self.blt_tiled(
+                    dst_x,
+                    dst_y,
+                    dst_width,
+                    dst_height,
+                    image.clone(),
+                    src_x,
+                    src_y,
+                    src_width,
+                    src_height,
+                    transparent,
+                );
+            }
+        }
+    }
+
     pub fn bltm(
         &mut self,
-        x: f64,
-        y: f64,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
         tilemap: SharedTilemap,
-        tilemap_x: f64,
-        tilemap_y: f64,
-        width: f64,
-        height: f64,
+        tilemap_x: impl IntoCoord,
+        tilemap_y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
         transparent: Option<Color>,
     ) {
-        let x = utils::f64_to_i32(x) - self.canvas.camera_x;
-        let y = utils::f64_to_i32(y) - self.canvas.camera_y;
-        let tilemap_x = utils::f64_to_i32(tilemap_x);
-        let tilemap_y = utils::f64_to_i32(tilemap_y);
-        let width = utils::f64_to_i32(width);
-        let height = utils::f64_to_i32(height);
+        canvas::count_draw_call();
+        let x = x.to_coord() - self.canvas.camera_x;
+        let y = y.to_coord() - self.canvas.camera_y;
+        let tilemap_x = tilemap_x.to_coord();
+        let tilemap_y = tilemap_y.to_coord();
+        let width = width.to_coord();
+        let height = height.to_coord();
 
         let tilemap = tilemap.lock();
         let tilemap_rect = RectArea::new(
@@ -370,11 +649,34 @@ impl Image {
         if width == 0 || height == 0 {
             return;
         }
+        canvas::count_blit_pixels((width * height) as u32);
 
-        let images = IMAGES.lock();
         let image = match &tilemap.imgsrc {
-            ImageSource::Index(index) => images[*index as usize].lock(),
-            ImageSource::Image(image) => image.lock(),
+            ImageSource::Index(index) => IMAGES.lock()[*index as usize].clone(),
+            ImageSource::Image(image) => image.clone(),
+        };
+        let image = image.try_read();
+        // A failed try_read means the tiles come from this image, so read them from a copy
+        let self_copy;
+        let image_canvas = if let Some(image) = &image {
+            &image.canvas
+        } else {
+            let width = self.width();
+            let height = self.height();
+            let mut canvas = Canvas::new(width, height);
+            canvas.blt(
+                0.0,
+                0.0,
+                &self.canvas,
+                0.0,
+                0.0,
+                width as i32,
+                height as i32,
+                None,
+                None,
+            );
+            self_copy = canvas;
+            &self_copy
         };
         for yi in 0..height {
             for xi in 0..width {
@@ -387,7 +689,7 @@ impl Image {
 
                 let value_x = tile.0 as i32 * TILE_SIZE as i32 + tilemap_x % TILE_SIZE as i32;
                 let value_y = tile.1 as i32 * TILE_SIZE as i32 + tilemap_y % TILE_SIZE as i32;
-                let value = image.canvas.read_data(value_x as usize, value_y as usize);
+                let value = image_canvas.read_data(value_x as usize, value_y as usize);
 
                 if let Some(transparent) = transparent {
                     if value == transparent {
@@ -407,14 +709,22 @@ impl Image {
         let color = self.palette[color as usize];
         let palette1 = self.palette[1];
         self.pal(1, color);
+        let fallback_font = FALLBACK_FONT.lock();
+        let line_height = font::line_height(fallback_font.as_ref());
         let start_x = x;
         for c in string.chars() {
             if c == '\n' {
                 x = start_x;
-                y += FONT_HEIGHT as i32;
+                y += line_height as i32;
                 continue;
             }
             if c < MIN_FONT_CODE || c > MAX_FONT_CODE {
+                if let Some(advance) = fallback_font
+                    .as_ref()
+                    .and_then(|font| font.draw_glyph(self, x, y, c, color))
+                {
+                    x += advance;
+                }
                 continue;
             }
             let code = c as i32 - MIN_FONT_CODE as i32;
@@ -444,3 +754,127 @@ impl Image {
         dx * dx + dy * dy + dz * dz
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+
+    #[test]
+    fn test_bltm_from_own_image_bank() {
+        let mut pyxel = headless_pyxel(16, 16);
+        let image = pyxel.images.lock()[0].clone();
+        let tilemap = pyxel.tilemaps.lock()[0].clone();
+        tilemap.lock().imgsrc = ImageSource::Index(0);
+        tilemap.lock().pset(0.0, 0.0, (1, 0));
+        image.lock().cls(0);
+        image.lock().pset(9.0, 2.0, 7);
+
+        // Drawing into the bank the tiles come from must not deadlock
+        pyxel.set_render_target(image.clone());
+        pyxel.bltm(0.0, 16.0, 0, 0.0, 0.0, 8.0, 8.0, None);
+        pyxel.reset_render_target();
+
+        let image = image.read();
+        assert_eq!(image.pget(1.0, 18.0), 7);
+        assert_eq!(image.pget(0.0, 16.0), 0);
+        assert_eq!(image.pget(9.0, 2.0), 7);
+    }
+
+    #[test]
+    fn test_blt_tiled_clipping() {
+        let source = Image::new(4, 4);
+        source
+            .lock()
+            .set(0, 0, &["1234", "5678", "9abc", "def1"])
+            .unwrap();
+        let dest = Image::new(5, 5);
+        let mut dest = dest.lock();
+        dest.cls(0);
+
+        // Tiles start off the top-left edge and the last column is cropped to one pixel
+        dest.blt_tiled(-1, -1, 5, 5, source.clone(), 1, 1, 2, 2, None);
+        assert_eq!(dest.pget(0, 0), 11);
+        assert_eq!(dest.pget(1, 0), 10);
+        assert_eq!(dest.pget(0, 1), 7);
+        assert_eq!(dest.pget(3, 3), 6);
+        assert_eq!(dest.pget(4, 0), 0);
+        assert_eq!(dest.pget(0, 4), 0);
+
+        // Areas running past the bottom-right edge or entirely outside are clipped
+        dest.cls(0);
+        dest.blt_tiled(3, 3, 8, 8, source.clone(), 0, 0, 4, 4, None);
+        dest.blt_tiled(10, -10, 4, 4, source, 0, 0, 2, 2, None);
+        assert_eq!(dest.pget(3, 3), 1);
+        assert_eq!(dest.pget(4, 4), 6);
+        assert_eq!(dest.pget(2, 2), 0);
+    }
+
+    #[test]
+    fn test_blt_9slice_clipping() {
+        let source = Image::new(3, 3);
+        source.lock().set(0, 0, &["123", "456", "789"]).unwrap();
+        let dest = Image::new(6, 6);
+        let mut dest = dest.lock();
+        dest.cls(0);
+
+        // The top and left borders fall outside, leaving the tiled center and far borders
+        dest.blt_9slice(-1, -1, 7, 7, source.clone(), 0, 0, 3, 3, (1, 1, 1, 1), None);
+        assert_eq!(dest.pget(0, 0), 5);
+        assert_eq!(dest.pget(2, 3), 5);
+        assert_eq!(dest.pget(5, 0), 6);
+        assert_eq!(dest.pget(0, 5), 8);
+        assert_eq!(dest.pget(5, 5), 9);
+
+        // Only the top-left corner and borders remain when the area runs off the far edges
+        dest.cls(0);
+        dest.blt_9slice(4, 4, 8, 8, source, 0, 0, 3, 3, (1, 1, 1, 1), None);
+        assert_eq!(dest.pget(4, 4), 1);
+        assert_eq!(dest.pget(5, 4), 2);
+        assert_eq!(dest.pget(4, 5), 4);
+        assert_eq!(dest.pget(5, 5), 5);
+        assert_eq!(dest.pget(3, 3), 0);
+    }
+
+    #[test]
+    fn test_shared_image_locks() {
+        let image = Image::new(3, 2);
+        assert_eq!((image.width(), image.height()), (3, 2));
+        {
+            let _reader = image.read();
+            assert!(image.try_read().is_some());
+        }
+        let other = image.clone();
+        assert!(other.ptr_eq(&image));
+        assert!(!other.ptr_eq(&Image::new(3, 2)));
+
+        // Drawing an image onto itself reads the source from a copy
+        let mut writer = image.lock();
+        assert!(other.try_read().is_none());
+        writer.pset(0, 0, 5);
+        writer.blt(1, 0, other, 0, 0, 2, 1, None);
+        assert_eq!(writer.pget(1, 0), 5);
+        assert_eq!(writer.pget(2, 0), 0);
+    }
+
+    #[test]
+    fn test_set_invalid_data() {
+        let image = Image::new(4, 4);
+        let mut image = image.lock();
+        image.set(0, 0, &["12", "34"]).unwrap();
+        assert_eq!(image.pget(1, 1), 4);
+        assert!(matches!(
+            image.set(0, 0, &[]),
+            Err(PyxelError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            image.set(0, 0, &["12", "3"]),
+            Err(PyxelError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            image.set(0, 0, &["1x"]),
+            Err(PyxelError::InvalidArgument(_))
+        ));
+        assert_eq!(image.pget(0, 0), 1);
+    }
+}
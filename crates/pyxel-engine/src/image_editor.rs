@@ -0,0 +1,383 @@
+use crate::editor::{
+    self, Clip, EditHistory, EditRecord, Selection, Tool, BUTTON_WIDTH, CANVAS_PIXELS, CANVAS_X,
+    CANVAS_Y, TOOL_Y, VIEWER_SIZE, VIEWER_X, VIEWER_Y,
+};
+use crate::image::{Color, Image, SharedImage};
+use crate::keys::{
+    KEY_B, KEY_C, KEY_DOWN, KEY_LEFT, KEY_P, KEY_RIGHT, KEY_S, KEY_UP, KEY_V, KEY_Y, KEY_Z,
+    MOUSE_BUTTON_LEFT, MOUSE_BUTTON_RIGHT,
+};
+use crate::pyxel::Pyxel;
+use crate::settings::{
+    COLOR_BLACK, COLOR_DARK_BLUE, COLOR_WHITE, COLOR_YELLOW, FONT_HEIGHT, HOT_CLICK, NUM_COLORS,
+    TILE_SIZE,
+};
+
+const CANVAS_SIZE: i32 = 16;
+const CANVAS_ZOOM: i32 = CANVAS_PIXELS / CANVAS_SIZE;
+const SWATCH_X: i32 = VIEWER_X;
+const SWATCH_Y: i32 = VIEWER_Y + VIEWER_SIZE + 4;
+const SWATCH_SIZE: i32 = 12;
+const SWATCHES_PER_ROW: i32 = 8;
+const HOT_ID_BASE: u32 = editor::HOT_ID_BASE + 0x20;
+const BANK_HOT_ID: u32 = HOT_ID_BASE + 0x10;
+const SWATCH_HOT_ID_BASE: u32 = BANK_HOT_ID + 1;
+
+pub struct ImageEditor {
+    tool: Tool,
+    color: Color,
+    bank: usize,
+    canvas_x: i32,
+    canvas_y: i32,
+    last_pos: Option<(i32, i32)>,
+    is_painting: bool,
+    selection: Selection,
+    clip: Option<Clip<Color>>,
+    history: EditHistory<EditRecord<Color>>,
+}
+
+impl ImageEditor {
+    pub fn new() -> Self {
+        Self {
+            tool: Tool::Pencil,
+            color: COLOR_WHITE,
+            bank: 0,
+            canvas_x: 0,
+            canvas_y: 0,
+            last_pos: None,
+            is_painting: false,
+            selection: Selection::new(),
+            clip: None,
+            history: EditHistory::new(),
+        }
+    }
+
+    fn image(&self, pyxel: &Pyxel) -> SharedImage {
+        pyxel.images.lock()[self.bank].clone()
+    }
+
+    fn read_canvas(&self, pyxel: &Pyxel) -> Vec<Color> {
+        let image = self.image(pyxel);
+        let image = image.lock();
+        editor::read_region(&image.canvas, self.canvas_x, self.canvas_y, CANVAS_SIZE)
+    }
+
+    fn write_canvas(&self, pyxel: &Pyxel, data: &[Color]) {
+        let image = self.image(pyxel);
+        let mut image = image.lock();
+        editor::write_region(
+            &mut image.canvas,
+            self.canvas_x,
+            self.canvas_y,
+            CANVAS_SIZE,
+            data,
+        );
+    }
+
+    fn push_undo(&mut self, pyxel: &Pyxel) {
+        self.history.push(EditRecord {
+            index: self.bank,
+            x: self.canvas_x,
+            y: self.canvas_y,
+            size: CANVAS_SIZE,
+            data: self.read_canvas(pyxel),
+        });
+    }
+
+    fn undo(&mut self, pyxel: &Pyxel, undo: bool) {
+        let Some(mut record) = self.history.take(undo) else {
+            return;
+        };
+        self.bank = record.index;
+        self.canvas_x = record.x;
+        self.canvas_y = record.y;
+        self.selection.clear();
+        let data = self.read_canvas(pyxel);
+        self.write_canvas(pyxel, &record.data);
+        record.data = data;
+        self.history.put_back(record, undo);
+    }
+
+    fn canvas_pos(pyxel: &Pyxel) -> Option<(i32, i32)> {
+        let x = (pyxel.mouse_x - CANVAS_X).div_euclid(CANVAS_ZOOM);
+        let y = (pyxel.mouse_y - CANVAS_Y).div_euclid(CANVAS_ZOOM);
+        ((0..CANVAS_SIZE).contains(&x) && (0..CANVAS_SIZE).contains(&y)).then_some((x, y))
+    }
+
+    fn move_canvas(&mut self, pyxel: &Pyxel, x: i32, y: i32) {
+        let image = self.image(pyxel);
+        let image = image.lock();
+        let tile_size = TILE_SIZE as i32;
+        let max_x = (image.width() as i32 - CANVAS_SIZE).max(0);
+        let max_y = (image.height() as i32 - CANVAS_SIZE).max(0);
+        self.canvas_x = (x.div_euclid(tile_size) * tile_size).clamp(0, max_x);
+        self.canvas_y = (y.div_euclid(tile_size) * tile_size).clamp(0, max_y);
+        self.selection.clear();
+    }
+
+    fn viewer_origin(&self, pyxel: &Pyxel) -> (i32, i32) {
+        let image = self.image(pyxel);
+        let image = image.lock();
+        let offset = (VIEWER_SIZE - CANVAS_SIZE) / 2;
+        let max_x = (image.width() as i32 - VIEWER_SIZE).max(0);
+        let max_y = (image.height() as i32 - VIEWER_SIZE).max(0);
+        (
+            (self.canvas_x - offset).clamp(0, max_x),
+            (self.canvas_y - offset).clamp(0, max_y),
+        )
+    }
+
+    fn update_buttons(&mut self, pyxel: &mut Pyxel) {
+        editor::update_tool_buttons(pyxel, HOT_ID_BASE, &mut self.tool);
+        let hot_state = pyxel.engine_hot_rect(
+            BANK_HOT_ID,
+            VIEWER_X as f64,
+            TOOL_Y as f64,
+            (BUTTON_WIDTH * 2) as f64,
+            FONT_HEIGHT as f64,
+        );
+        if hot_state == HOT_CLICK {
+            self.bank = (self.bank + 1) % pyxel.images.lock().len();
+            self.move_canvas(pyxel, self.canvas_x, self.canvas_y);
+        }
+        for i in 0..NUM_COLORS as i32 {
+            let hot_state = pyxel.engine_hot_rect(
+                SWATCH_HOT_ID_BASE + i as u32,
+                (SWATCH_X + (i % SWATCHES_PER_ROW) * SWATCH_SIZE) as f64,
+                (SWATCH_Y + (i / SWATCHES_PER_ROW) * SWATCH_SIZE) as f64,
+                SWATCH_SIZE as f64,
+                SWATCH_SIZE as f64,
+            );
+            if hot_state == HOT_CLICK {
+                self.color = i as Color;
+            }
+        }
+    }
+
+    fn update_shortcuts(&mut self, pyxel: &mut Pyxel, message: &mut String) {
+        if editor::is_ctrl_pressed(pyxel) {
+            if pyxel.btnp(KEY_Z, None, None) {
+                self.undo(pyxel, true);
+            } else if pyxel.btnp(KEY_Y, None, None) {
+                self.undo(pyxel, false);
+            } else if pyxel.btnp(KEY_C, None, None) {
+                let data = self.read_canvas(pyxel);
+                self.clip = Some(editor::copy_area(&data, CANVAS_SIZE, self.selection.area));
+                *message = "COPIED".to_string();
+            } else if pyxel.btnp(KEY_V, None, None) && self.clip.is_some() {
+                self.push_undo(pyxel);
+                let (left, top, _, _) = self.selection.area.unwrap_or((0, 0, 0, 0));
+                let mut data = self.read_canvas(pyxel);
+                editor::paste_clip(
+                    &mut data,
+                    CANVAS_SIZE,
+                    self.clip.as_ref().unwrap(),
+                    left,
+                    top,
+                );
+                self.write_canvas(pyxel, &data);
+            }
+            return;
+        }
+        for (key, tool) in [
+            (KEY_P, Tool::Pencil),
+            (KEY_B, Tool::Fill),
+            (KEY_S, Tool::Select),
+        ] {
+            if pyxel.btnp(key, None, None) {
+                self.tool = tool;
+            }
+        }
+        let tile_size = TILE_SIZE as i32;
+        for (key, dx, dy) in [
+            (KEY_LEFT, -tile_size, 0),
+            (KEY_RIGHT, tile_size, 0),
+            (KEY_UP, 0, -tile_size),
+            (KEY_DOWN, 0, tile_size),
+        ] {
+            if pyxel.btnp(key, Some(10), Some(2)) {
+                self.move_canvas(pyxel, self.canvas_x + dx, self.canvas_y + dy);
+            }
+        }
+    }
+
+    fn update_viewer(&mut self, pyxel: &mut Pyxel) {
+        if !pyxel.btn(MOUSE_BUTTON_LEFT)
+            || !pyxel.mouse_in(
+                VIEWER_X as f64,
+                VIEWER_Y as f64,
+                VIEWER_SIZE as f64,
+                VIEWER_SIZE as f64,
+            )
+        {
+            return;
+        }
+        let (origin_x, origin_y) = self.viewer_origin(pyxel);
+        let x = origin_x + pyxel.mouse_x - VIEWER_X - CANVAS_SIZE / 2;
+        let y = origin_y + pyxel.mouse_y - VIEWER_Y - CANVAS_SIZE / 2;
+        self.move_canvas(pyxel, x, y);
+    }
+
+    fn update_canvas(&mut self, pyxel: &mut Pyxel) {
+        if !pyxel.btn(MOUSE_BUTTON_LEFT) {
+            self.last_pos = None;
+            self.is_painting = false;
+            self.selection.release();
+        }
+        let Some((x, y)) = Self::canvas_pos(pyxel) else {
+            self.last_pos = None;
+            return;
+        };
+        if pyxel.btnp(MOUSE_BUTTON_RIGHT, None, None) {
+            self.color = self.read_canvas(pyxel)[(y * CANVAS_SIZE + x) as usize];
+            return;
+        }
+        if !pyxel.btn(MOUSE_BUTTON_LEFT) {
+            return;
+        }
+        let is_pressed = pyxel.btnp(MOUSE_BUTTON_LEFT, None, None);
+        match self.tool {
+            Tool::Pencil => {
+                // Strokes may start outside the canvas, so undo is saved on the first painted frame
+                if !self.is_painting {
+                    self.push_undo(pyxel);
+                    self.is_painting = true;
+                }
+                let mut data = self.read_canvas(pyxel);
+                let last_pos = self.last_pos.unwrap_or((x, y));
+                editor::draw_stroke(&mut data, CANVAS_SIZE, last_pos, (x, y), self.color);
+                self.write_canvas(pyxel, &data);
+                self.last_pos = Some((x, y));
+            }
+            Tool::Fill => {
+                if is_pressed {
+                    self.push_undo(pyxel);
+                    let mut data = self.read_canvas(pyxel);
+                    editor::flood_fill(&mut data, CANVAS_SIZE, x, y, self.color);
+                    self.write_canvas(pyxel, &data);
+                }
+            }
+            Tool::Select => self.selection.update(is_pressed, x, y),
+        }
+    }
+
+    pub(crate) fn update(&mut self, pyxel: &mut Pyxel, message: &mut String) {
+        self.update_buttons(pyxel);
+        self.update_shortcuts(pyxel, message);
+        self.update_viewer(pyxel);
+        self.update_canvas(pyxel);
+    }
+
+    fn draw_canvas(&self, pyxel: &Pyxel, screen: &mut Image) {
+        let data = self.read_canvas(pyxel);
+        let zoom = CANVAS_ZOOM as f64;
+        for yi in 0..CANVAS_SIZE {
+            for xi in 0..CANVAS_SIZE {
+                let x = (CANVAS_X + xi * CANVAS_ZOOM) as f64;
+                let y = (CANVAS_Y + yi * CANVAS_ZOOM) as f64;
+                screen.rect(x, y, zoom, zoom, data[(yi * CANVAS_SIZE + xi) as usize]);
+            }
+        }
+        let (left, top) = (CANVAS_X as f64, CANVAS_Y as f64);
+        let size = CANVAS_PIXELS as f64;
+        let half = size / 2.0;
+        screen.line(
+            left + half,
+            top,
+            left + half,
+            top + size - 1.0,
+            COLOR_DARK_BLUE,
+        );
+        screen.line(
+            left,
+            top + half,
+            left + size - 1.0,
+            top + half,
+            COLOR_DARK_BLUE,
+        );
+        screen.rectb(left - 1.0, top - 1.0, size + 2.0, size + 2.0, COLOR_WHITE);
+        if let Some((x1, y1, x2, y2)) = self.selection.area {
+            screen.rectb(
+                left + (x1 * CANVAS_ZOOM) as f64,
+                top + (y1 * CANVAS_ZOOM) as f64,
+                ((x2 - x1 + 1) * CANVAS_ZOOM) as f64,
+                ((y2 - y1 + 1) * CANVAS_ZOOM) as f64,
+                COLOR_YELLOW,
+            );
+        }
+    }
+
+    fn draw_viewer(&self, pyxel: &Pyxel, screen: &mut Image) {
+        let (origin_x, origin_y) = self.viewer_origin(pyxel);
+        let (left, top) = (VIEWER_X as f64, VIEWER_Y as f64);
+        let size = VIEWER_SIZE as f64;
+        screen.blt(
+            left,
+            top,
+            self.image(pyxel),
+            origin_x as f64,
+            origin_y as f64,
+            size,
+            size,
+            None,
+        );
+        screen.rectb(left - 1.0, top - 1.0, size + 2.0, size + 2.0, COLOR_WHITE);
+        screen.rectb(
+            left + (self.canvas_x - origin_x) as f64,
+            top + (self.canvas_y - origin_y) as f64,
+            CANVAS_SIZE as f64,
+            CANVAS_SIZE as f64,
+            COLOR_YELLOW,
+        );
+        let bank_label = format!("IMAGE {}", self.bank);
+        screen.text(left, TOOL_Y as f64, &bank_label, COLOR_WHITE);
+
+        for i in 0..NUM_COLORS as i32 {
+            let x = (SWATCH_X + (i % SWATCHES_PER_ROW) * SWATCH_SIZE) as f64;
+            let y = (SWATCH_Y + (i / SWATCHES_PER_ROW) * SWATCH_SIZE) as f64;
+            let size = SWATCH_SIZE as f64;
+            screen.rect(x, y, size, size, i as Color);
+            if i as Color == self.color {
+                screen.rectb(x, y, size, size, COLOR_WHITE);
+                screen.rectb(x + 1.0, y + 1.0, size - 2.0, size - 2.0, COLOR_BLACK);
+            }
+        }
+    }
+
+    // Returns the status text for the hovered pixel
+    pub(crate) fn draw(&self, pyxel: &Pyxel, screen: &mut Image) -> Option<String> {
+        editor::draw_tool_buttons(screen, self.tool);
+        self.draw_canvas(pyxel, screen);
+        self.draw_viewer(pyxel, screen);
+        Self::canvas_pos(pyxel).map(|(x, y)| format!("{},{}", self.canvas_x + x, self.canvas_y + y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+
+    #[test]
+    fn test_stroke_from_outside_canvas() {
+        let mut pyxel = headless_pyxel(16, 16);
+        let mut editor = ImageEditor::new();
+        pyxel.press_key(MOUSE_BUTTON_LEFT, 0);
+        editor.update_canvas(&mut pyxel);
+
+        for (frame_count, x) in [(1, 0), (2, 1)] {
+            pyxel.frame_count = frame_count;
+            pyxel.mouse_x = CANVAS_X + x * CANVAS_ZOOM;
+            pyxel.mouse_y = CANVAS_Y;
+            editor.update_canvas(&mut pyxel);
+        }
+        let image = pyxel.images.lock()[0].clone();
+        assert_eq!(image.lock().pget(0, 0), COLOR_WHITE);
+        assert_eq!(image.lock().pget(1, 0), COLOR_WHITE);
+
+        editor.undo(&pyxel, true);
+        assert_eq!(image.lock().pget(0, 0), 0);
+        assert_eq!(image.lock().pget(1, 0), 0);
+        assert!(editor.history.take(true).is_none());
+    }
+}
@@ -1,10 +1,41 @@
-use std::collections::HashMap;
+#[cfg(feature = "midi")]
+use std::cmp::max;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
 
-use crate::keys::{Key, KeyValue, MOUSE_POS_X, MOUSE_POS_Y, MOUSE_WHEEL_X, MOUSE_WHEEL_Y};
-use crate::pyxel::Pyxel;
-use crate::utils::f64_to_i32;
+#[cfg(feature = "midi")]
+use crate::channel::{Note, Volume};
+use pyxel_platform::SystemCursor;
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq)]
+use crate::error::{PyxelError, PyxelResult};
+use crate::image::SharedImage;
+use crate::keys::{
+    key_from_name, key_name, Key, KeyValue, GAMEPAD1_ACCEL_Z, GAMEPAD1_GYRO_X,
+    GAMEPAD_KEY_INDEX_INTERVAL, GAMEPAD_KEY_START_INDEX, MIDI_KEY_START_INDEX, MOUSE_BUTTON_LEFT,
+    MOUSE_KEY_START_INDEX, MOUSE_POS_X, MOUSE_POS_Y, MOUSE_WHEEL_X, MOUSE_WHEEL_Y,
+    SPECIAL_KEY_START_INDEX,
+};
+#[cfg(feature = "midi")]
+use crate::keys::{MIDI_NOTE_0, NUM_MIDI_NOTES};
+use crate::pyxel::{Pyxel, CURSOR_IMAGE};
+use crate::rect_area::RectArea;
+#[cfg(feature = "midi")]
+use crate::settings::{
+    EFFECT_NONE, INITIAL_SOUND_SPEED, MAX_MIDI_VELOCITY, MAX_NOTE, MAX_VOLUME, MIDI_NOTE_OFFSET,
+    TONE_TRIANGLE,
+};
+use crate::settings::{
+    HOT_CLICK, HOT_HOVER, HOT_NONE, HOT_PRESS, SYSTEM_CURSOR_CROSSHAIR, SYSTEM_CURSOR_HAND,
+    SYSTEM_CURSOR_IBEAM, SYSTEM_CURSOR_WAIT,
+};
+#[cfg(feature = "midi")]
+use crate::sound::Sound;
+use crate::utils::{f64_to_i32, f64_to_u32};
+
+pub type IdleCallback = Box<dyn FnMut(&mut Pyxel)>;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 enum KeyState {
     Pressed,
     Released,
@@ -12,20 +43,156 @@ enum KeyState {
     ReleasedAndPressed,
 }
 
+#[derive(Serialize, Deserialize)]
+pub(crate) struct InputData {
+    key_states: Vec<(Key, u32, KeyState)>,
+    key_values: Vec<(Key, KeyValue)>,
+    last_input_frame: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum HotId {
+    User(u32),
+    Engine(u32),
+}
+
 pub struct Input {
     mouse_visible: bool,
+    cursor_hotspot: (i32, i32),
+    system_cursor: Option<u32>,
     key_states: HashMap<Key, (u32, KeyState)>,
     key_values: HashMap<Key, KeyValue>,
+    motion_offsets: HashMap<Key, KeyValue>,
+    last_input_frame: u32,
+    idle_frames: u32,
+    idle_callback: Option<IdleCallback>,
+    is_idle_callback_replaced: bool,
+    is_idle: bool,
+    hot_pressed: HashSet<HotId>,
+    hot_seen: HashSet<HotId>,
+    dropped_file_data: Vec<Option<Vec<u8>>>,
+    last_pressed_key: Option<Key>,
+    press_times: HashMap<Key, u32>,
+    input_map: InputMap,
+    #[cfg(feature = "midi")]
+    midi_channel: Option<u32>,
+    #[cfg(feature = "midi")]
+    midi_tone: u32,
+    #[cfg(feature = "midi")]
+    midi_key: Option<Key>,
 }
 
 impl Input {
     pub fn new() -> Self {
         Self {
             mouse_visible: false,
+            cursor_hotspot: (0, 0),
+            system_cursor: None,
             key_states: HashMap::new(),
             key_values: HashMap::new(),
+            motion_offsets: HashMap::new(),
+            last_input_frame: 0,
+            idle_frames: 0,
+            idle_callback: None,
+            is_idle_callback_replaced: false,
+            is_idle: false,
+            hot_pressed: HashSet::new(),
+            hot_seen: HashSet::new(),
+            dropped_file_data: Vec::new(),
+            last_pressed_key: None,
+            press_times: HashMap::new(),
+            input_map: InputMap::new(),
+            #[cfg(feature = "midi")]
+            midi_channel: None,
+            #[cfg(feature = "midi")]
+            midi_tone: TONE_TRIANGLE,
+            #[cfg(feature = "midi")]
+            midi_key: None,
+        }
+    }
+
+    pub(crate) fn to_data(&self) -> InputData {
+        InputData {
+            key_states: self
+                .key_states
+                .iter()
+                .map(|(key, (frame_count, key_state))| (*key, *frame_count, *key_state))
+                .collect(),
+            key_values: self
+                .key_values
+                .iter()
+                .map(|(key, value)| (*key, *value))
+                .collect(),
+            last_input_frame: self.last_input_frame,
+        }
+    }
+
+    pub(crate) fn set_data(&mut self, input_data: &InputData) {
+        self.key_states = input_data
+            .key_states
+            .iter()
+            .map(|(key, frame_count, key_state)| (*key, (*frame_count, *key_state)))
+            .collect();
+        self.key_values = input_data.key_values.iter().copied().collect();
+        self.last_input_frame = input_data.last_input_frame;
+        self.hot_pressed.clear();
+        self.hot_seen.clear();
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct InputMapData {
+    actions: BTreeMap<String, Vec<String>>,
+}
+
+pub struct InputMap {
+    actions: HashMap<String, Vec<Key>>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self {
+            actions: HashMap::new(),
         }
     }
+
+    pub fn bind(&mut self, action: &str, keys: &[Key]) {
+        self.actions.insert(action.to_string(), keys.to_vec());
+    }
+
+    pub fn unbind(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    pub fn keys(&self, action: &str) -> Vec<Key> {
+        self.actions.get(action).cloned().unwrap_or_default()
+    }
+
+    // Keys are saved by name so that users can edit the bindings by hand
+    pub fn to_toml(&self) -> String {
+        let actions = self
+            .actions
+            .iter()
+            .map(|(action, keys)| {
+                let names = keys.iter().map(|key| key_name(*key).to_string()).collect();
+                (action.clone(), names)
+            })
+            .collect();
+        toml::to_string(&InputMapData { actions }).unwrap()
+    }
+
+    pub fn from_toml(toml_text: &str) -> Option<Self> {
+        let input_map_data: InputMapData = toml::from_str(toml_text).ok()?;
+        let mut actions = HashMap::new();
+        for (action, names) in input_map_data.actions {
+            let keys = names
+                .iter()
+                .map(|name| key_from_name(name))
+                .collect::<Option<Vec<Key>>>()?;
+            actions.insert(action, keys);
+        }
+        Some(Self { actions })
+    }
 }
 
 impl Pyxel {
@@ -82,14 +249,193 @@ impl Pyxel {
         false
     }
 
+    // Returns when the key was last pressed in milliseconds, which is finer than the frame time
+    pub fn btn_press_time(&self, key: Key) -> Option<u32> {
+        self.input.press_times.get(&key).copied()
+    }
+
     pub fn btnv(&mut self, key: Key) -> KeyValue {
         self.input.key_values.get(&key).copied().unwrap_or(0)
     }
 
+    pub fn bind_action(&mut self, action: &str, keys: &[Key]) {
+        self.input.input_map.bind(action, keys);
+    }
+
+    pub fn unbind_action(&mut self, action: &str) {
+        self.input.input_map.unbind(action);
+    }
+
+    pub fn action_keys(&self, action: &str) -> Vec<Key> {
+        self.input.input_map.keys(action)
+    }
+
+    pub fn action(&mut self, action: &str) -> bool {
+        let keys = self.input.input_map.keys(action);
+        keys.into_iter().any(|key| self.btn(key))
+    }
+
+    pub fn actionp(
+        &mut self,
+        action: &str,
+        hold_frame_count: Option<u32>,
+        repeat_frame_count: Option<u32>,
+    ) -> bool {
+        let keys = self.input.input_map.keys(action);
+        keys.into_iter()
+            .any(|key| self.btnp(key, hold_frame_count, repeat_frame_count))
+    }
+
+    pub fn actionr(&mut self, action: &str) -> bool {
+        let keys = self.input.input_map.keys(action);
+        keys.into_iter().any(|key| self.btnr(key))
+    }
+
+    pub fn save_input_map(&self, filename: &str) -> PyxelResult<()> {
+        fs::write(filename, self.input.input_map.to_toml())
+            .map_err(|_| PyxelError::FileWrite(filename.to_string()))
+    }
+
+    pub fn load_input_map(&mut self, filename: &str) -> PyxelResult<()> {
+        let toml_text =
+            fs::read_to_string(filename).map_err(|_| PyxelError::FileOpen(filename.to_string()))?;
+        self.input.input_map = InputMap::from_toml(&toml_text)
+            .ok_or_else(|| PyxelError::InvalidFile(filename.to_string()))?;
+        Ok(())
+    }
+
+    pub fn gamepad_count(&self) -> u32 {
+        pyxel_platform::gamepad_count()
+    }
+
+    // Motor strengths range from 0.0 to 1.0 for the low and high frequency motors
+    pub fn rumble(&self, gamepad: u32, low_freq: f64, high_freq: f64, duration_ms: u32) -> bool {
+        let to_strength = |value: f64| (value.clamp(0.0, 1.0) * u16::MAX as f64).round() as u16;
+        pyxel_platform::rumble_gamepad(
+            gamepad,
+            to_strength(low_freq),
+            to_strength(high_freq),
+            duration_ms,
+        )
+    }
+
     pub fn mouse(&mut self, visible: bool) {
         self.input.mouse_visible = visible;
     }
 
+    // The hotspot is the point in the image that sits at the mouse position
+    pub fn set_cursor(&mut self, image: SharedImage, hot_x: i32, hot_y: i32) {
+        self.cursor = image;
+        self.set_cursor_hotspot(hot_x, hot_y);
+        if self.input.system_cursor.take().is_some() {
+            pyxel_platform::set_system_cursor(SystemCursor::Arrow);
+        }
+    }
+
+    // Lets games that draw into the cursor image in place align it without replacing it
+    pub fn set_cursor_hotspot(&mut self, hot_x: i32, hot_y: i32) {
+        self.input.cursor_hotspot = (hot_x, hot_y);
+    }
+
+    pub fn reset_cursor(&mut self) {
+        self.set_cursor(CURSOR_IMAGE.clone(), 0, 0);
+    }
+
+    // While the mouse is visible, the OS cursor is shown over the screen instead of the image
+    pub fn use_system_cursor(&mut self, kind: u32) {
+        let cursor = match kind {
+            SYSTEM_CURSOR_CROSSHAIR => SystemCursor::Crosshair,
+            SYSTEM_CURSOR_HAND => SystemCursor::Hand,
+            SYSTEM_CURSOR_IBEAM => SystemCursor::IBeam,
+            SYSTEM_CURSOR_WAIT => SystemCursor::Wait,
+            _ => SystemCursor::Arrow,
+        };
+        pyxel_platform::set_system_cursor(cursor);
+        self.input.system_cursor = Some(kind);
+    }
+
+    pub fn calibrate_motion(&mut self) {
+        let motion_values: Vec<(Key, KeyValue)> = self
+            .input
+            .key_values
+            .iter()
+            .filter(|(key, _)| is_motion_key(**key))
+            .map(|(key, value)| (*key, *value))
+            .collect();
+        for (key, value) in motion_values {
+            *self.input.motion_offsets.entry(key).or_insert(0) += value;
+            self.input.key_values.insert(key, 0);
+        }
+    }
+
+    pub fn on_idle(&mut self, frames: u32, callback: IdleCallback) {
+        self.input.idle_frames = frames;
+        self.input.idle_callback = Some(callback);
+        self.input.is_idle_callback_replaced = true;
+        self.reset_idle();
+    }
+
+    pub fn mouse_in(&self, x: f64, y: f64, width: f64, height: f64) -> bool {
+        RectArea::new(
+            f64_to_i32(x),
+            f64_to_i32(y),
+            f64_to_u32(width),
+            f64_to_u32(height),
+        )
+        .contains(self.mouse_x, self.mouse_y)
+    }
+
+    pub fn hot_rect(&mut self, id: u32, x: f64, y: f64, width: f64, height: f64) -> u32 {
+        self.update_hot_rect(HotId::User(id), x, y, width, height)
+    }
+
+    // Engine widgets such as the pause menu and editors use their own ids, so any u32 is free
+    // for games to use with hot_rect
+    pub(crate) fn engine_hot_rect(
+        &mut self,
+        id: u32,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    ) -> u32 {
+        self.update_hot_rect(HotId::Engine(id), x, y, width, height)
+    }
+
+    fn update_hot_rect(&mut self, id: HotId, x: f64, y: f64, width: f64, height: f64) -> u32 {
+        let is_inside = self.mouse_in(x, y, width, height);
+        self.input.hot_seen.insert(id);
+        if is_inside && self.btnp(MOUSE_BUTTON_LEFT, None, None) {
+            self.input.hot_pressed.insert(id);
+        }
+        if self.btnr(MOUSE_BUTTON_LEFT) && self.input.hot_pressed.remove(&id) && is_inside {
+            return HOT_CLICK;
+        }
+        if !is_inside {
+            HOT_NONE
+        } else if self.input.hot_pressed.contains(&id) {
+            HOT_PRESS
+        } else {
+            HOT_HOVER
+        }
+    }
+
+    #[cfg(feature = "midi")]
+    pub fn midi_ports(&self) -> Vec<String> {
+        pyxel_platform::midi_input_ports()
+    }
+
+    #[cfg(feature = "midi")]
+    pub fn midi_in(&mut self, port: u32, channel: Option<u32>, tone: Option<u32>) -> bool {
+        if let Some(channel_index) = self.input.midi_channel {
+            self.stop(channel_index);
+        }
+        self.input.midi_channel = channel;
+        self.input.midi_tone = tone.unwrap_or(TONE_TRIANGLE);
+        self.input.midi_key = None;
+        pyxel_platform::open_midi_input(port)
+    }
+
     pub fn warp_mouse(&mut self, x: f64, y: f64) {
         let x = f64_to_i32(x);
         let y = f64_to_i32(y);
@@ -104,9 +450,19 @@ impl Pyxel {
         self.mouse_wheel = 0;
         self.input_text = String::new();
         self.dropped_files.clear();
+        self.input.dropped_file_data.clear();
+        self.input.last_pressed_key = None;
+
+        // A rect that wasn't checked last frame is gone, so its press must not turn into a
+        // click when it comes back
+        let hot_seen = &self.input.hot_seen;
+        self.input.hot_pressed.retain(|id| hot_seen.contains(id));
+        self.input.hot_seen.clear();
     }
 
-    pub(crate) fn press_key(&mut self, key: Key) {
+    pub(crate) fn press_key(&mut self, key: Key, timestamp: u32) {
+        self.reset_idle();
+        self.input.press_times.insert(key, timestamp);
         let mut key_state = KeyState::Pressed;
         if let Some((last_frame_count, last_key_state)) = self.input.key_states.get(&key) {
             if *last_frame_count == self.frame_count && *last_key_state != KeyState::Pressed {
@@ -116,9 +472,20 @@ impl Pyxel {
         self.input
             .key_states
             .insert(key, (self.frame_count, key_state));
+        // Unified modifier keys follow their left or right key, which is the more specific one
+        if !(SPECIAL_KEY_START_INDEX..MOUSE_KEY_START_INDEX).contains(&key) {
+            self.input.last_pressed_key = Some(key);
+        }
+        #[cfg(feature = "midi")]
+        self.play_midi_note(key);
+    }
+
+    pub fn last_pressed_key(&self) -> Option<Key> {
+        self.input.last_pressed_key
     }
 
     pub(crate) fn release_key(&mut self, key: Key) {
+        self.reset_idle();
         let mut key_state = KeyState::Released;
         if let Some((last_frame_count, last_key_state)) = self.input.key_states.get(&key) {
             if *last_frame_count == self.frame_count && *last_key_state != KeyState::Released {
@@ -128,17 +495,30 @@ impl Pyxel {
         self.input
             .key_states
             .insert(key, (self.frame_count, key_state));
+        #[cfg(feature = "midi")]
+        self.stop_midi_note(key);
+    }
+
+    // Removes the state of a key so that the game doesn't see it, and returns whether the key
+    // is down in this frame and whether it stays held, or None if the key has no state
+    pub(crate) fn take_key_state(&mut self, key: Key) -> Option<(bool, bool)> {
+        let is_down = self.btn(key);
+        let (_, key_state) = self.input.key_states.remove(&key)?;
+        let is_held = matches!(key_state, KeyState::Pressed | KeyState::ReleasedAndPressed);
+        Some((is_down, is_held))
     }
 
     pub(crate) fn change_key_value(&mut self, key: Key, value: KeyValue) {
         let mut value = value;
         match key {
             MOUSE_POS_X => {
-                value = (value - self.system.screen_x) / self.system.screen_scale as i32;
+                value = (value - self.system.screen_x) * self.width as i32
+                    / self.system.screen_width as i32;
                 self.mouse_x = value;
             }
             MOUSE_POS_Y => {
-                value = (value - self.system.screen_y) / self.system.screen_scale as i32;
+                value = (value - self.system.screen_y) * self.height as i32
+                    / self.system.screen_height as i32;
                 self.mouse_y = value;
             }
             MOUSE_WHEEL_Y => {
@@ -146,18 +526,296 @@ impl Pyxel {
             }
             _ => {}
         }
+        if let Some(offset) = self.input.motion_offsets.get(&key) {
+            value -= offset;
+        }
+        // Sensor noise changes motion values every frame, so it doesn't count as input
+        if !is_motion_key(key) && self.input.key_values.get(&key) != Some(&value) {
+            self.reset_idle();
+        }
+        self.input.key_values.insert(key, value);
+    }
+
+    // Sorted by key, so that captured inputs serialize the same on every machine
+    pub(crate) fn held_keys(&mut self) -> Vec<Key> {
+        let mut keys: Vec<Key> = self.input.key_states.keys().copied().collect();
+        keys.sort_unstable();
+        keys.retain(|key| self.btn(*key));
+        keys
+    }
+
+    pub(crate) fn key_values(&self) -> Vec<(Key, KeyValue)> {
+        let mut key_values: Vec<_> = self
+            .input
+            .key_values
+            .iter()
+            .map(|(key, value)| (*key, *value))
+            .collect();
+        key_values.sort_unstable();
+        key_values
+    }
+
+    // Unlike change_key_value, mouse positions are taken as screen coordinates as they are
+    pub(crate) fn set_key_value(&mut self, key: Key, value: KeyValue) {
+        match key {
+            MOUSE_POS_X => self.mouse_x = value,
+            MOUSE_POS_Y => self.mouse_y = value,
+            MOUSE_WHEEL_Y => self.mouse_wheel = value,
+            _ => {}
+        }
         self.input.key_values.insert(key, value);
     }
 
     pub(crate) fn add_input_text(&mut self, text: &str) {
         self.input_text += text;
+        self.set_input_text_composition("", 0);
+    }
+
+    pub(crate) fn set_input_text_composition(&mut self, text: &str, cursor: u32) {
+        self.input_text_composition = text.to_string();
+        self.input_text_cursor = cursor;
     }
 
-    pub(crate) fn add_dropped_file(&mut self, filename: &str) {
+    pub(crate) fn add_dropped_file(&mut self, filename: &str, data: Option<Vec<u8>>) {
         self.dropped_files.push(filename.to_string());
+        self.input.dropped_file_data.push(data);
+    }
+
+    // Files dropped on desktop are read from their path, since only web builds carry the data
+    pub fn dropped_file_data(&self, index: u32) -> Option<Vec<u8>> {
+        match self.input.dropped_file_data.get(index as usize)? {
+            Some(data) => Some(data.clone()),
+            None => fs::read(&self.dropped_files[index as usize]).ok(),
+        }
     }
 
     pub(crate) fn is_mouse_visible(&self) -> bool {
         self.input.mouse_visible
     }
+
+    pub(crate) fn cursor_hotspot(&self) -> (i32, i32) {
+        self.input.cursor_hotspot
+    }
+
+    pub(crate) fn is_system_cursor_used(&self) -> bool {
+        self.input.system_cursor.is_some()
+    }
+
+    pub(crate) fn check_idle(&mut self) {
+        // Restored input states may come from a later frame than the current one
+        if self.input.is_idle
+            || self.frame_count.saturating_sub(self.input.last_input_frame) < self.input.idle_frames
+        {
+            return;
+        }
+        if let Some(mut callback) = self.input.idle_callback.take() {
+            self.input.is_idle = true;
+            self.input.is_idle_callback_replaced = false;
+            callback(self);
+
+            // A callback registered from inside the callback takes its place
+            if !self.input.is_idle_callback_replaced {
+                self.input.idle_callback = Some(callback);
+            }
+        }
+    }
+
+    fn reset_idle(&mut self) {
+        self.input.last_input_frame = self.frame_count;
+        self.input.is_idle = false;
+    }
+
+    #[cfg(feature = "midi")]
+    fn play_midi_note(&mut self, key: Key) {
+        if !(MIDI_NOTE_0..MIDI_NOTE_0 + NUM_MIDI_NOTES).contains(&key) {
+            return;
+        }
+        if let Some(channel_index) = self.input.midi_channel {
+            let note = (key - MIDI_NOTE_0) as i32 - MIDI_NOTE_OFFSET as i32;
+            if note < 0 || note > MAX_NOTE as i32 {
+                return;
+            }
+            let volume = max(self.btnv(key) * MAX_VOLUME as i32 / MAX_MIDI_VELOCITY, 1);
+            let sound = Sound::new();
+            {
+                let mut sound = sound.lock();
+                sound.notes = vec![note as Note];
+                sound.tones = vec![self.input.midi_tone];
+                sound.volumes = vec![volume as Volume];
+                sound.effects = vec![EFFECT_NONE];
+                sound.speed = INITIAL_SOUND_SPEED;
+            }
+            // midi_in accepts any index, and the channel list can be resized later
+            let Some(channel) = self.channels.lock().get(channel_index as usize).cloned() else {
+                return;
+            };
+            channel.lock().play1(sound, None, true);
+            self.input.midi_key = Some(key);
+        }
+    }
+
+    #[cfg(feature = "midi")]
+    fn stop_midi_note(&mut self, key: Key) {
+        if self.input.midi_key != Some(key) {
+            return;
+        }
+        if let Some(channel_index) = self.input.midi_channel {
+            self.stop(channel_index);
+        }
+        self.input.midi_key = None;
+    }
+}
+
+fn is_motion_key(key: Key) -> bool {
+    if !(GAMEPAD_KEY_START_INDEX..MIDI_KEY_START_INDEX).contains(&key) {
+        return false;
+    }
+    let gamepad1_key =
+        GAMEPAD_KEY_START_INDEX + (key - GAMEPAD_KEY_START_INDEX) % GAMEPAD_KEY_INDEX_INTERVAL;
+    (GAMEPAD1_GYRO_X..=GAMEPAD1_ACCEL_Z).contains(&gamepad1_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::keys::{
+        GAMEPAD1_BUTTON_A, GAMEPAD4_ACCEL_Z, KEY_A, KEY_AUDIOFASTFORWARD, KEY_GUI, KEY_SPACE,
+        KEY_UNKNOWN, MIDI_NOTE_0, MOUSE_BUTTON_X2, NUM_MIDI_NOTES,
+    };
+    use crate::pyxel::headless_pyxel;
+    use crate::settings::{HOT_CLICK, HOT_HOVER, HOT_NONE, HOT_PRESS};
+
+    #[test]
+    fn test_input_map_toml() {
+        let mut input_map = InputMap::new();
+        input_map.bind("jump", &[KEY_SPACE, GAMEPAD1_BUTTON_A]);
+        let toml_text = input_map.to_toml();
+        assert!(toml_text.contains("KEY_SPACE"));
+
+        let input_map = InputMap::from_toml(&toml_text).unwrap();
+        assert_eq!(input_map.keys("jump"), vec![KEY_SPACE, GAMEPAD1_BUTTON_A]);
+        assert!(input_map.keys("fire").is_empty());
+        assert!(InputMap::from_toml("[actions]\njump = [\"KEY_NOTHING\"]").is_none());
+    }
+
+    #[test]
+    fn test_engine_hot_ids() {
+        let mut pyxel = headless_pyxel(32, 32);
+        pyxel.set_key_value(MOUSE_POS_X, 4);
+        pyxel.set_key_value(MOUSE_POS_Y, 4);
+        pyxel.press_key(MOUSE_BUTTON_LEFT, 0);
+        assert_eq!(pyxel.engine_hot_rect(7, 0.0, 0.0, 8.0, 8.0), HOT_PRESS);
+
+        // A game rect with the same id doesn't take over the engine widget's press
+        pyxel.frame_count += 1;
+        pyxel.set_key_value(MOUSE_POS_X, 20);
+        pyxel.release_key(MOUSE_BUTTON_LEFT);
+        assert_eq!(pyxel.hot_rect(7, 16.0, 0.0, 8.0, 8.0), HOT_HOVER);
+        assert_eq!(pyxel.engine_hot_rect(7, 0.0, 0.0, 8.0, 8.0), HOT_NONE);
+    }
+
+    #[test]
+    fn test_hot_rect_not_seen() {
+        let mut pyxel = headless_pyxel(32, 32);
+        pyxel.set_key_value(MOUSE_POS_X, 4);
+        pyxel.set_key_value(MOUSE_POS_Y, 4);
+        pyxel.press_key(MOUSE_BUTTON_LEFT, 0);
+        assert_eq!(pyxel.hot_rect(1, 0.0, 0.0, 8.0, 8.0), HOT_PRESS);
+        assert_eq!(pyxel.hot_rect(2, 0.0, 0.0, 8.0, 8.0), HOT_PRESS);
+
+        // Only the rect checked during the frame keeps its press
+        pyxel.frame_count += 1;
+        pyxel.reset_input_states();
+        assert_eq!(pyxel.hot_rect(1, 0.0, 0.0, 8.0, 8.0), HOT_PRESS);
+        pyxel.frame_count += 1;
+        pyxel.reset_input_states();
+        pyxel.release_key(MOUSE_BUTTON_LEFT);
+        assert_eq!(pyxel.hot_rect(1, 0.0, 0.0, 8.0, 8.0), HOT_CLICK);
+        assert_eq!(pyxel.hot_rect(2, 0.0, 0.0, 8.0, 8.0), HOT_HOVER);
+    }
+
+    #[cfg(feature = "midi")]
+    #[test]
+    fn test_midi_note_invalid_channel() {
+        let mut pyxel = headless_pyxel(16, 16);
+        pyxel.input.midi_channel = Some(99);
+        pyxel.press_key(MIDI_NOTE_0 + MIDI_NOTE_OFFSET, 0);
+        assert_eq!(pyxel.input.midi_key, None);
+    }
+
+    #[test]
+    fn test_check_idle() {
+        let mut pyxel = headless_pyxel(16, 16);
+        let num_calls = Rc::new(Cell::new(0));
+        let counter = num_calls.clone();
+        pyxel.on_idle(10, Box::new(move |_| counter.set(counter.get() + 1)));
+        pyxel.frame_count = 5;
+        pyxel.input.last_input_frame = 20;
+        pyxel.check_idle();
+        assert_eq!(num_calls.get(), 0);
+
+        pyxel.frame_count = 30;
+        pyxel.check_idle();
+        pyxel.check_idle();
+        assert_eq!(num_calls.get(), 1);
+    }
+
+    #[test]
+    fn test_check_idle_replaced_callback() {
+        let mut pyxel = headless_pyxel(16, 16);
+        let num_calls = Rc::new(Cell::new(0));
+        let counter = num_calls.clone();
+        pyxel.on_idle(
+            10,
+            Box::new(move |pyxel| {
+                let counter = counter.clone();
+                pyxel.on_idle(10, Box::new(move |_| counter.set(counter.get() + 10)));
+            }),
+        );
+        pyxel.frame_count = 30;
+        pyxel.check_idle();
+        assert_eq!(num_calls.get(), 0);
+
+        pyxel.frame_count = 60;
+        pyxel.check_idle();
+        assert_eq!(num_calls.get(), 10);
+    }
+
+    #[test]
+    fn test_motion_keys_keep_idle() {
+        let mut pyxel = headless_pyxel(16, 16);
+        pyxel.frame_count = 30;
+        pyxel.set_key_value(GAMEPAD1_GYRO_X, 3);
+        assert_eq!(pyxel.input.last_input_frame, 0);
+
+        pyxel.set_key_value(MOUSE_WHEEL_Y, 1);
+        assert_eq!(pyxel.input.last_input_frame, 30);
+    }
+
+    #[test]
+    fn test_key_name_round_trip() {
+        let midi_notes = MIDI_NOTE_0..MIDI_NOTE_0 + NUM_MIDI_NOTES;
+        for key in [
+            KEY_UNKNOWN,
+            KEY_A,
+            KEY_AUDIOFASTFORWARD,
+            KEY_GUI,
+            MOUSE_BUTTON_X2,
+            GAMEPAD1_BUTTON_A,
+            GAMEPAD4_ACCEL_Z,
+        ]
+        .into_iter()
+        .chain(midi_notes)
+        {
+            let name = key_name(key);
+            assert_eq!(key_from_name(name), Some(key), "{name}");
+        }
+        assert_eq!(key_name(KEY_A), "KEY_A");
+        assert_eq!(key_name(MIDI_NOTE_0 + 60), "MIDI_NOTE_60");
+        assert_eq!(key_from_name("gamepad4_accel_z"), Some(GAMEPAD4_ACCEL_Z));
+        assert_eq!(key_name(MIDI_NOTE_0 + NUM_MIDI_NOTES), "KEY_UNKNOWN");
+    }
 }
@@ -18,41 +18,102 @@
 
 #[macro_use]
 mod utils;
+mod app_package;
 mod audio;
+mod bank_viewer;
 mod blip_buf;
 mod canvas;
 mod channel;
+mod collision;
+mod constants;
+mod debug_console;
+mod deterministic;
+mod dsp;
+mod editor;
+mod error;
+mod executor;
+mod font;
+#[cfg(feature = "fuzzing")]
+mod fuzzing;
+mod golden;
 mod graphics;
+mod hot_reload;
 mod image;
+mod image_editor;
 mod input;
+mod logger;
+#[cfg(feature = "lua")]
+mod lua_script;
 mod math;
+mod midi_file;
 mod music;
+mod music_editor;
+#[cfg(feature = "net")]
+mod net;
 mod old_resource_data;
 mod oscillator;
+mod palette_editor;
+mod pause_menu;
 mod profiler;
 mod pyxel;
 mod rect_area;
 mod resource;
 mod resource_data;
+mod resource_loader;
+mod sample;
+mod save_data;
+mod scale_filter;
+mod scene;
 mod screencast;
 mod settings;
+mod simd;
 mod sound;
+mod sound_editor;
+mod sprite;
+mod state;
+mod stream;
 mod system;
+mod text_layout;
 mod tiled_map_file;
 mod tilemap;
+mod tilemap_editor;
 mod tone;
+mod transition;
+mod user_config;
+mod vfs;
+mod virtual_gamepad;
 mod watch_info;
 
 use pyxel_platform::keys;
 
+pub use crate::app_package::{pack_app, AppInfo, AppMetadata};
+pub use crate::canvas::IntoCoord;
 pub use crate::channel::{Channel, Detune, Note, SharedChannel, Speed, Volume};
+pub use crate::deterministic::{Fixed, FrameInput};
+pub use crate::editor::edit;
+pub use crate::error::{PyxelError, PyxelResult};
+pub use crate::executor::{next_frame, NextFrame};
+pub use crate::font::Font;
+#[cfg(feature = "fuzzing")]
+pub use crate::fuzzing::{fuzz_image_file, fuzz_resource_file, fuzz_sound_strings};
+pub use crate::golden::assert_screen_matches;
 pub use crate::image::{Color, Image, Rgb24, SharedImage};
 pub use crate::keys::*;
+pub use crate::logger::init_logger;
 pub use crate::music::{Music, SharedMusic, SharedSeq};
-pub use crate::oscillator::{Effect, Gain};
-pub use crate::pyxel::{init, Pyxel};
+pub use crate::oscillator::{Effect, Envelope, Gain};
+pub use crate::profiler::{FrameStats, Perf};
+pub use crate::pyxel::{init, Pyxel, PyxelBuilder};
+pub use crate::resource::Resource;
+pub use crate::resource_loader::ResourceLoad;
+pub use crate::sample::{Sample, SharedSample};
+pub use crate::scene::{Scene, SceneStack};
 pub use crate::settings::*;
 pub use crate::sound::{SharedSound, Sound};
-pub use crate::system::PyxelCallback;
+pub use crate::sprite::{Animation, Sprite};
+pub use crate::system::{AnnounceHandler, PyxelCallback, QuitHandler, SystemKeys};
+pub use crate::text_layout::{text_width, wrap_text};
 pub use crate::tilemap::{ImageSource, SharedTilemap, Tile, TileCoord, Tilemap};
 pub use crate::tone::{Amp4, Noise, SharedTone, Tone, Waveform};
+pub use crate::vfs::{EmbeddedFs, FileSystem};
+pub use pyxel_platform::Event;
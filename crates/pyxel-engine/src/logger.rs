@@ -0,0 +1,54 @@
+use std::env::var;
+use std::str::FromStr;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::settings::{DEFAULT_LOG_LEVEL, LOG_LEVEL_ENVVAR};
+
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+// The level can be given as one of off, error, warn, info, debug and trace
+fn parse_level(level: &str) -> Option<LevelFilter> {
+    LevelFilter::from_str(level.trim()).ok()
+}
+
+// Installs a minimal stderr logger for applications that don't set up their own
+pub fn init_logger(level: Option<&str>) -> bool {
+    let level = level
+        .and_then(parse_level)
+        .or_else(|| var(LOG_LEVEL_ENVVAR).ok().as_deref().and_then(parse_level))
+        .unwrap_or(DEFAULT_LOG_LEVEL);
+    if log::set_logger(&LOGGER).is_err() {
+        return false;
+    }
+    log::set_max_level(level);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_level("debug"), Some(LevelFilter::Debug));
+        assert_eq!(parse_level(" WARN "), Some(LevelFilter::Warn));
+        assert_eq!(parse_level("verbose"), None);
+    }
+}
@@ -0,0 +1,321 @@
+use std::cell::Cell;
+use std::fs;
+use std::ptr::null_mut;
+use std::rc::Rc;
+
+use mlua::{Function, Lua, Table, Value};
+
+use crate::error::{PyxelError, PyxelResult};
+use crate::image::Color;
+use crate::keys::Key;
+use crate::pyxel::Pyxel;
+use crate::system::PyxelCallback;
+
+// Pyxel is only reachable from Lua while the script body or a callback is running
+type PyxelRef = Rc<Cell<*mut Pyxel>>;
+
+fn with_pyxel<R>(pyxel_ref: &PyxelRef, func: impl FnOnce(&mut Pyxel) -> R) -> mlua::Result<R> {
+    let pyxel = pyxel_ref.get();
+    if pyxel.is_null() {
+        return Err(mlua::Error::RuntimeError(
+            "Pyxel API called outside of the script".to_string(),
+        ));
+    }
+    Ok(func(unsafe { &mut *pyxel }))
+}
+
+fn script_error(err: mlua::Error) -> PyxelError {
+    PyxelError::Script(err.to_string())
+}
+
+fn add_constants(table: &Table) -> mlua::Result<()> {
+    macro_rules! add_constant {
+        ($name: ident) => {
+            table.set(stringify!($name), crate::$name)?
+        };
+    }
+
+    crate::for_each_constant!(add_constant);
+
+    Ok(())
+}
+
+fn add_functions(lua: &Lua, table: &Table, pyxel_ref: &PyxelRef) -> mlua::Result<()> {
+    macro_rules! add_function {
+        ($name: ident($($arg: ident: $arg_type: ty),*) => |$pyxel: ident| $body: expr) => {{
+            let pyxel_ref = pyxel_ref.clone();
+            table.set(
+                stringify!($name),
+                lua.create_function(move |_, ($($arg,)*): ($($arg_type,)*)| {
+                    with_pyxel(&pyxel_ref, |$pyxel| $body)
+                })?,
+            )?;
+        }};
+        ($name: ident($($arg: ident: $arg_type: ty),*) =>? |$pyxel: ident| $body: expr) => {{
+            let pyxel_ref = pyxel_ref.clone();
+            table.set(
+                stringify!($name),
+                lua.create_function(move |_, ($($arg,)*): ($($arg_type,)*)| {
+                    with_pyxel(&pyxel_ref, |$pyxel| $body)?.map_err(mlua::Error::external)
+                })?,
+            )?;
+        }};
+    }
+
+    // System
+    add_function!(quit() => |pyxel| pyxel.quit());
+    add_function!(title(title: String) => |pyxel| pyxel.title(&title));
+
+    // Resource
+    add_function!(load(filename: String) =>? |pyxel| {
+        pyxel.load(&filename, None, None, None, None, None, None, None)
+    });
+
+    // Input
+    add_function!(btn(key: Key) => |pyxel| pyxel.btn(key));
+    add_function!(btnp(key: Key, hold: Option<u32>, repeat: Option<u32>) => |pyxel| {
+        pyxel.btnp(key, hold, repeat)
+    });
+    add_function!(btnr(key: Key) => |pyxel| pyxel.btnr(key));
+    add_function!(mouse(visible: bool) => |pyxel| pyxel.mouse(visible));
+
+    // Graphics
+    add_function!(clip(x: Option<f64>, y: Option<f64>, w: Option<f64>, h: Option<f64>) => |pyxel| {
+        if let (Some(x), Some(y), Some(w), Some(h)) = (x, y, w, h) {
+            pyxel.clip(x, y, w, h);
+        } else {
+            pyxel.clip0();
+        }
+    });
+    add_function!(camera(x: Option<f64>, y: Option<f64>) => |pyxel| {
+        if let (Some(x), Some(y)) = (x, y) {
+            pyxel.camera(x, y);
+        } else {
+            pyxel.camera0();
+        }
+    });
+    add_function!(pal(col1: Option<Color>, col2: Option<Color>) => |pyxel| {
+        if let (Some(col1), Some(col2)) = (col1, col2) {
+            pyxel.pal(col1, col2);
+        } else {
+            pyxel.pal0();
+        }
+    });
+    add_function!(dither(alpha: f32) => |pyxel| pyxel.dither(alpha));
+    add_function!(cls(col: Color) => |pyxel| pyxel.cls(col));
+    add_function!(pget(x: f64, y: f64) => |pyxel| pyxel.pget(x, y));
+    add_function!(pset(x: f64, y: f64, col: Color) => |pyxel| pyxel.pset(x, y, col));
+    add_function!(line(x1: f64, y1: f64, x2: f64, y2: f64, col: Color) => |pyxel| {
+        pyxel.line(x1, y1, x2, y2, col);
+    });
+    add_function!(rect(x: f64, y: f64, w: f64, h: f64, col: Color) => |pyxel| {
+        pyxel.rect(x, y, w, h, col);
+    });
+    add_function!(rectb(x: f64, y: f64, w: f64, h: f64, col: Color) => |pyxel| {
+        pyxel.rectb(x, y, w, h, col);
+    });
+    add_function!(circ(x: f64, y: f64, r: f64, col: Color) => |pyxel| pyxel.circ(x, y, r, col));
+    add_function!(circb(x: f64, y: f64, r: f64, col: Color) => |pyxel| pyxel.circb(x, y, r, col));
+    add_function!(elli(x: f64, y: f64, w: f64, h: f64, col: Color) => |pyxel| {
+        pyxel.elli(x, y, w, h, col);
+    });
+    add_function!(ellib(x: f64, y: f64, w: f64, h: f64, col: Color) => |pyxel| {
+        pyxel.ellib(x, y, w, h, col);
+    });
+    add_function!(tri(
+        x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, col: Color
+    ) => |pyxel| {
+        pyxel.tri(x1, y1, x2, y2, x3, y3, col);
+    });
+    add_function!(trib(
+        x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, col: Color
+    ) => |pyxel| {
+        pyxel.trib(x1, y1, x2, y2, x3, y3, col);
+    });
+    add_function!(fill(x: f64, y: f64, col: Color) => |pyxel| pyxel.fill(x, y, col));
+    add_function!(blt(
+        x: f64, y: f64, img: u32, u: f64, v: f64, w: f64, h: f64, colkey: Option<Color>
+    ) => |pyxel| {
+        pyxel.blt(x, y, img, u, v, w, h, colkey);
+    });
+    add_function!(bltm(
+        x: f64, y: f64, tm: u32, u: f64, v: f64, w: f64, h: f64, colkey: Option<Color>
+    ) => |pyxel| {
+        pyxel.bltm(x, y, tm, u, v, w, h, colkey);
+    });
+    add_function!(text(x: f64, y: f64, s: String, col: Color) => |pyxel| pyxel.text(x, y, &s, col));
+
+    // Audio
+    add_function!(play(
+        ch: u32, snd: Value, tick: Option<u32>, should_loop: Option<bool>
+    ) =>? |pyxel| {
+        let should_loop = should_loop.unwrap_or(false);
+        match snd {
+            Value::Table(snd) => {
+                let snd: Vec<u32> = snd.sequence_values().filter_map(Result::ok).collect();
+                pyxel.play(ch, &snd, tick, should_loop);
+            }
+            Value::Integer(snd) => pyxel.play1(ch, snd as u32, tick, should_loop),
+            Value::Number(snd) => pyxel.play1(ch, snd as u32, tick, should_loop),
+            _ => {
+                return Err(PyxelError::InvalidArgument(format!(
+                    "play expects a sound number or a table of them, got {}",
+                    snd.type_name()
+                )));
+            }
+        }
+        Ok(())
+    });
+    add_function!(playm(msc: u32, tick: Option<u32>, should_loop: Option<bool>) => |pyxel| {
+        pyxel.playm(msc, tick, should_loop.unwrap_or(false));
+    });
+    add_function!(stop(ch: Option<u32>) => |pyxel| {
+        ch.map_or_else(|| pyxel.stop0(), |ch| pyxel.stop(ch));
+    });
+
+    Ok(())
+}
+
+struct LuaCallback {
+    lua: Lua,
+    pyxel_ref: PyxelRef,
+    has_failed: bool,
+}
+
+impl LuaCallback {
+    fn new(pyxel: &mut Pyxel, filename: &str) -> PyxelResult<Self> {
+        let source =
+            fs::read_to_string(filename).map_err(|_| PyxelError::FileOpen(filename.to_string()))?;
+        let lua = Lua::new();
+        let pyxel_ref: PyxelRef = Rc::new(Cell::new(null_mut()));
+        let table = lua.create_table().map_err(script_error)?;
+        add_constants(&table).map_err(script_error)?;
+        add_functions(&lua, &table, &pyxel_ref).map_err(script_error)?;
+        lua.globals().set("pyxel", table).map_err(script_error)?;
+
+        let callback = Self {
+            lua,
+            pyxel_ref,
+            has_failed: false,
+        };
+        callback
+            .call(pyxel, |lua| lua.load(&source).set_name(filename).exec())
+            .map_err(script_error)?;
+        Ok(callback)
+    }
+
+    fn call(
+        &self,
+        pyxel: &mut Pyxel,
+        func: impl FnOnce(&Lua) -> mlua::Result<()>,
+    ) -> mlua::Result<()> {
+        // Variables are refreshed before each call since Lua tables cannot observe Rust fields
+        let table: Table = self.lua.globals().get("pyxel")?;
+        table.set("width", pyxel.width)?;
+        table.set("height", pyxel.height)?;
+        table.set("frame_count", pyxel.frame_count)?;
+        table.set("mouse_x", pyxel.mouse_x)?;
+        table.set("mouse_y", pyxel.mouse_y)?;
+        table.set("mouse_wheel", pyxel.mouse_wheel)?;
+
+        self.pyxel_ref.set(pyxel);
+        let result = func(&self.lua);
+        self.pyxel_ref.set(null_mut());
+        result
+    }
+
+    // After a runtime error the script is no longer called, so the error is reported once
+    fn call_global(&mut self, pyxel: &mut Pyxel, name: &str) {
+        if self.has_failed {
+            return;
+        }
+        let result = self.call(pyxel, |lua| {
+            if let Some(func) = lua.globals().get::<_, Option<Function>>(name)? {
+                func.call::<_, ()>(())?;
+            }
+            Ok(())
+        });
+        if result.is_err() {
+            self.has_failed = true;
+            pyxel.print_error(result.map_err(script_error));
+        }
+    }
+}
+
+impl PyxelCallback for LuaCallback {
+    fn update(&mut self, pyxel: &mut Pyxel) {
+        self.call_global(pyxel, "update");
+    }
+
+    fn draw(&mut self, pyxel: &mut Pyxel) {
+        self.call_global(pyxel, "draw");
+    }
+}
+
+impl Pyxel {
+    pub fn run_lua(&mut self, filename: &str) -> PyxelResult<()> {
+        let callback = LuaCallback::new(self, filename)?;
+        self.run(callback);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+
+    fn write_script(name: &str, source: &str) -> String {
+        let filename = env::temp_dir().join(format!("pyxel_{name}_{}.lua", std::process::id()));
+        fs::write(&filename, source).unwrap();
+        filename.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_lua_callback() {
+        let mut pyxel = headless_pyxel(16, 16);
+        let filename = write_script(
+            "callback",
+            r#"
+            x = pyxel.width - 1
+            function update()
+                x = x - 1
+            end
+            function draw()
+                pyxel.cls(pyxel.COLOR_NAVY)
+                pyxel.pset(x, 2, pyxel.COLOR_RED)
+            end
+            "#,
+        );
+        let mut callback = LuaCallback::new(&mut pyxel, &filename).unwrap();
+        fs::remove_file(&filename).unwrap();
+
+        callback.update(&mut pyxel);
+        callback.draw(&mut pyxel);
+        assert!(!callback.has_failed);
+        assert_eq!(pyxel.screen.lock().pget(14.0, 2.0), crate::COLOR_RED);
+        assert_eq!(pyxel.screen.lock().pget(15.0, 2.0), crate::COLOR_NAVY);
+    }
+
+    #[test]
+    fn test_lua_errors() {
+        let mut pyxel = headless_pyxel(16, 16);
+        assert!(matches!(
+            LuaCallback::new(&mut pyxel, "missing_script.lua"),
+            Err(PyxelError::FileOpen(_))
+        ));
+
+        let filename = write_script("syntax_error", "function update(");
+        let result = LuaCallback::new(&mut pyxel, &filename);
+        fs::remove_file(&filename).unwrap();
+        assert!(matches!(result, Err(PyxelError::Script(_))));
+
+        let filename = write_script("runtime_error", "function update() pyxel.play(0, 'a') end");
+        let mut callback = LuaCallback::new(&mut pyxel, &filename).unwrap();
+        fs::remove_file(&filename).unwrap();
+        callback.update(&mut pyxel);
+        assert!(callback.has_failed);
+    }
+}
@@ -1,23 +1,89 @@
 use std::f64::consts::PI;
 
 use noise::{NoiseFn, Perlin, Seedable};
-use rand::{Rng, SeedableRng};
-use rand_xoshiro::Xoshiro256StarStar;
+use rand::{Rng, RngCore};
 
 use crate::pyxel::Pyxel;
 
+// Xoshiro256** with a readable state, so that saving the state leaves the sequence untouched.
+// Seeding and output match rand_xoshiro, so existing seeds give the same numbers.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Xoshiro256 {
+    state: [u64; 4],
+}
+
+impl Xoshiro256 {
+    pub(crate) fn seed_from_u64(seed: u64) -> Self {
+        // The state is filled with SplitMix64, as recommended by the Xoshiro authors
+        let mut x = seed;
+        let state = [0; 4].map(|_: u64| {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        });
+        Self { state }
+    }
+
+    pub(crate) fn state(&self) -> [u64; 4] {
+        self.state
+    }
+
+    // An all-zero state would only ever produce zeros, so it is rejected
+    pub(crate) fn from_state(state: [u64; 4]) -> Option<Self> {
+        (state != [0; 4]).then_some(Self { state })
+    }
+}
+
+impl RngCore for Xoshiro256 {
+    fn next_u32(&mut self) -> u32 {
+        // The lowest bits are weaker, so the upper ones are used
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
 pub struct Math {
-    rng: Xoshiro256StarStar,
+    pub(crate) rng: Xoshiro256,
     perlin: Perlin,
 }
 
 impl Math {
     pub fn new() -> Self {
         let seed = pyxel_platform::elapsed_time();
-        let rng = Xoshiro256StarStar::seed_from_u64(seed as u64);
+        let rng = Xoshiro256::seed_from_u64(seed as u64);
         let perlin = Perlin::new().set_seed(seed);
         Self { rng, perlin }
     }
+
+    pub(crate) fn noise_seed(&self) -> u32 {
+        self.perlin.seed()
+    }
 }
 
 impl Pyxel {
@@ -60,7 +126,7 @@ impl Pyxel {
     }
 
     pub fn rseed(&mut self, seed: u32) {
-        self.math.rng = Xoshiro256StarStar::seed_from_u64(seed as u64);
+        self.math.rng = Xoshiro256::seed_from_u64(seed as u64);
     }
 
     pub fn rndi(&mut self, a: i32, b: i32) -> i32 {
@@ -81,3 +147,34 @@ impl Pyxel {
         self.math.perlin.get([x, y, z])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xoshiro256_seeding() {
+        // Reference outputs of SplitMix64 started from 0
+        let rng = Xoshiro256::seed_from_u64(0);
+        assert_eq!(
+            rng.state(),
+            [
+                0xe220_a839_7b1d_cdaf,
+                0x6e78_9e6a_a1b9_65f4,
+                0x06c4_5d18_8009_454f,
+                0xf88b_b8a8_724c_81ec,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xoshiro256_state_round_trip() {
+        let mut rng = Xoshiro256::seed_from_u64(12345);
+        rng.next_u64();
+        let mut restored = Xoshiro256::from_state(rng.state()).unwrap();
+        let values: Vec<u64> = (0..8).map(|_| rng.next_u64()).collect();
+        let restored_values: Vec<u64> = (0..8).map(|_| restored.next_u64()).collect();
+        assert_eq!(values, restored_values);
+        assert!(Xoshiro256::from_state([0; 4]).is_none());
+    }
+}
@@ -0,0 +1,445 @@
+use std::cmp::max;
+use std::fs;
+
+use crate::channel::{Note, Speed, Volume};
+use crate::error::{PyxelError, PyxelResult};
+use crate::pyxel::Pyxel;
+use crate::settings::{
+    CLOCK_RATE, EFFECT_NONE, MAX_MIDI_VELOCITY, MAX_NOTE, MAX_VOLUME, MIDI_NOTE_OFFSET,
+    NUM_CLOCKS_PER_TICK, TONE_NOISE, TONE_SQUARE,
+};
+
+const DEFAULT_TEMPO: u32 = 500_000; // Microseconds per quarter note (120 BPM)
+const NUM_STEPS_PER_QUARTER_NOTE: u32 = 4; // Quantize to 16th notes
+const DRUM_CHANNEL: u8 = 9;
+const MAX_NUM_STEPS: u32 = 65_536; // About 68 minutes at 120 BPM
+
+struct MidiNote {
+    start: u32,
+    end: u32,
+    key: u8,
+    velocity: u8,
+}
+
+struct MidiTrack {
+    notes: Vec<MidiNote>,
+    is_drum: bool,
+}
+
+struct MidiFile {
+    division: u32,
+    tempo: u32,
+    tracks: Vec<MidiTrack>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_end(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    // Each read returns None when the data ends before the value does
+    fn read_u8(&mut self) -> Option<u8> {
+        let value = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(value)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let bytes = self.data.get(self.pos..end)?;
+        self.pos = end;
+        Some(bytes)
+    }
+
+    fn read_u16(&mut self) -> Option<u16> {
+        let bytes = self.read_bytes(2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        let bytes = self.read_bytes(4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_var_len(&mut self) -> Option<u32> {
+        let mut value: u32 = 0;
+        loop {
+            let byte = self.read_u8()?;
+            value = (value << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+    }
+}
+
+fn end_of_data() -> String {
+    "Unexpected end of MIDI data".to_string()
+}
+
+impl MidiFile {
+    // Errors are reasons for rejecting the data, which the caller reports with the filename
+    fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(data);
+        if reader.read_bytes(4) != Some(&b"MThd"[..]) {
+            return Err("MIDI header not found".to_string());
+        }
+        let header_size = reader.read_u32().ok_or_else(end_of_data)? as usize;
+        if header_size < 6 {
+            return Err("MIDI header is too short".to_string());
+        }
+        let _format = reader.read_u16().ok_or_else(end_of_data)?;
+        let num_tracks = reader.read_u16().ok_or_else(end_of_data)?;
+        let division = reader.read_u16().ok_or_else(end_of_data)?;
+        if division & 0x8000 != 0 {
+            return Err("SMPTE time division is not supported".to_string());
+        }
+        reader.read_bytes(header_size - 6).ok_or_else(end_of_data)?;
+
+        let mut midi_file = Self {
+            division: max(division as u32, 1),
+            tempo: DEFAULT_TEMPO,
+            tracks: Vec::new(),
+        };
+        let mut tempo = None;
+        for _ in 0..num_tracks {
+            if reader.is_end() {
+                break;
+            }
+            let chunk_type = reader.read_bytes(4).ok_or_else(end_of_data)?;
+            let chunk_size = reader.read_u32().ok_or_else(end_of_data)? as usize;
+            let chunk_data = reader.read_bytes(chunk_size).ok_or_else(end_of_data)?;
+            if chunk_type == b"MTrk" {
+                midi_file
+                    .tracks
+                    .push(Self::parse_track(chunk_data, &mut tempo)?);
+            }
+        }
+        midi_file.tempo = tempo.unwrap_or(DEFAULT_TEMPO);
+        Ok(midi_file)
+    }
+
+    fn parse_track(data: &[u8], tempo: &mut Option<u32>) -> Result<MidiTrack, String> {
+        let mut reader = Reader::new(data);
+        let mut notes = Vec::new();
+        let mut active_notes: Vec<(u8, u32, u8)> = Vec::new();
+        let mut channels = Vec::new();
+        let mut time: u32 = 0;
+        let mut running_status = 0;
+
+        while !reader.is_end() {
+            time = time.saturating_add(reader.read_var_len().ok_or_else(end_of_data)?);
+            let mut status = reader.read_u8().ok_or_else(end_of_data)?;
+            if status < 0x80 {
+                // Running status reuses the previous status byte
+                reader.pos -= 1;
+                status = running_status;
+            }
+            match status {
+                0xff => {
+                    let meta_type = reader.read_u8().ok_or_else(end_of_data)?;
+                    let len = reader.read_var_len().ok_or_else(end_of_data)? as usize;
+                    let meta_data = reader.read_bytes(len).ok_or_else(end_of_data)?;
+                    if meta_type == 0x51 && len == 3 && tempo.is_none() {
+                        *tempo = Some(u32::from_be_bytes([
+                            0,
+                            meta_data[0],
+                            meta_data[1],
+                            meta_data[2],
+                        ]));
+                    } else if meta_type == 0x2f {
+                        break;
+                    }
+                }
+                0xf0 | 0xf7 => {
+                    let len = reader.read_var_len().ok_or_else(end_of_data)? as usize;
+                    reader.read_bytes(len).ok_or_else(end_of_data)?;
+                }
+                0x80..=0xef => {
+                    running_status = status;
+                    let channel = status & 0x0f;
+                    let data1 = reader.read_u8().ok_or_else(end_of_data)?;
+                    let data2 = if matches!(status & 0xf0, 0xc0 | 0xd0) {
+                        0
+                    } else {
+                        reader.read_u8().ok_or_else(end_of_data)?
+                    };
+                    let is_note_on = status & 0xf0 == 0x90 && data2 > 0;
+                    let is_note_off =
+                        status & 0xf0 == 0x80 || (status & 0xf0 == 0x90 && data2 == 0);
+                    if is_note_on || is_note_off {
+                        if let Some(index) =
+                            active_notes.iter().position(|(key, _, _)| *key == data1)
+                        {
+                            let (key, start, velocity) = active_notes.remove(index);
+                            notes.push(MidiNote {
+                                start,
+                                end: time,
+                                key,
+                                velocity,
+                            });
+                        }
+                    }
+                    if is_note_on {
+                        active_notes.push((data1, time, data2));
+                        if !channels.contains(&channel) {
+                            channels.push(channel);
+                        }
+                    }
+                }
+                _ => return Err(format!("Invalid MIDI event {status:#04x}")),
+            }
+        }
+        for (key, start, velocity) in active_notes {
+            notes.push(MidiNote {
+                start,
+                end: time,
+                key,
+                velocity,
+            });
+        }
+
+        Ok(MidiTrack {
+            notes,
+            is_drum: !channels.is_empty()
+                && channels.iter().all(|channel| *channel == DRUM_CHANNEL),
+        })
+    }
+}
+
+impl MidiTrack {
+    // Tracks are monophonic in the synth, so the highest sounding key wins at each step
+    fn quantize(&self, step_len: u32, num_steps: u32) -> (Vec<Note>, Vec<Volume>) {
+        let mut notes = Vec::with_capacity(num_steps as usize);
+        let mut volumes = Vec::with_capacity(num_steps as usize);
+        for step in 0..num_steps {
+            let time = step * step_len;
+            let midi_note = self
+                .notes
+                .iter()
+                .filter(|note| {
+                    let start = note.start.saturating_add(step_len / 2) / step_len * step_len;
+                    let end = max(
+                        note.end.saturating_add(step_len / 2) / step_len * step_len,
+                        start.saturating_add(step_len),
+                    );
+                    start <= time && time < end
+                })
+                .max_by_key(|note| note.key);
+            if let Some(midi_note) = midi_note {
+                notes.push(Self::to_note(midi_note.key));
+                volumes.push(max(
+                    (midi_note.velocity as u32 * MAX_VOLUME as u32 + MAX_MIDI_VELOCITY as u32 / 2)
+                        / MAX_MIDI_VELOCITY as u32,
+                    1,
+                ) as Volume);
+            } else {
+                notes.push(-1);
+                volumes.push(0);
+            }
+        }
+        (notes, volumes)
+    }
+
+    fn to_note(key: u8) -> Note {
+        let mut note = key as i32 - MIDI_NOTE_OFFSET as i32;
+        while note < 0 {
+            note += 12;
+        }
+        while note > MAX_NOTE as i32 {
+            note -= 12;
+        }
+        note as Note
+    }
+}
+
+impl Pyxel {
+    pub fn load_midi(
+        &self,
+        filename: &str,
+        music_index: u32,
+        sound_index: u32,
+        speed: Option<Speed>,
+    ) -> PyxelResult<()> {
+        let data = fs::read(filename).map_err(|_| PyxelError::FileOpen(filename.to_string()))?;
+        let midi_file = MidiFile::parse(&data).map_err(|reason| {
+            log::warn!("{reason} in '{filename}'");
+            PyxelError::InvalidFile(filename.to_string())
+        })?;
+        let music = self
+            .musics
+            .lock()
+            .get(music_index as usize)
+            .cloned()
+            .ok_or_else(|| {
+                PyxelError::InvalidArgument(format!("Invalid music index {music_index}"))
+            })?;
+        let step_len = max(midi_file.division / NUM_STEPS_PER_QUARTER_NOTE, 1);
+        let mut num_steps = midi_file
+            .tracks
+            .iter()
+            .flat_map(|track| &track.notes)
+            .map(|note| note.end.div_ceil(step_len))
+            .max()
+            .unwrap_or(0);
+        if num_steps > MAX_NUM_STEPS {
+            log::warn!("MIDI file '{filename}' is cut to {MAX_NUM_STEPS} steps");
+            num_steps = MAX_NUM_STEPS;
+        }
+        let speed = speed.unwrap_or_else(|| {
+            let ticks_per_second = (CLOCK_RATE / NUM_CLOCKS_PER_TICK) as f64;
+            let step_seconds =
+                midi_file.tempo as f64 / 1_000_000.0 / NUM_STEPS_PER_QUARTER_NOTE as f64;
+            max((step_seconds * ticks_per_second).round() as Speed, 1)
+        });
+
+        let num_channels = self.channels.lock().len();
+        let sounds = self.sounds.lock();
+        let tracks: Vec<&MidiTrack> = midi_file
+            .tracks
+            .iter()
+            .filter(|track| !track.notes.is_empty())
+            .take(num_channels)
+            .collect();
+        if sound_index as usize + tracks.len() > sounds.len() {
+            return Err(PyxelError::InvalidArgument(format!(
+                "Not enough sounds from index {sound_index} for {} MIDI tracks",
+                tracks.len()
+            )));
+        }
+        let mut seqs = Vec::new();
+        for (i, track) in tracks.into_iter().enumerate() {
+            let index = sound_index as usize + i;
+            let (notes, volumes) = track.quantize(step_len, num_steps);
+            let mut sound = sounds[index].lock();
+            sound.notes = notes;
+            sound.tones = vec![if track.is_drum {
+                TONE_NOISE
+            } else {
+                TONE_SQUARE
+            }];
+            sound.volumes = volumes;
+            sound.effects = vec![EFFECT_NONE];
+            sound.speed = speed;
+            seqs.push(vec![index as u32]);
+        }
+        music.lock().set(&seqs);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+
+    fn midi_data(track: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"MThd");
+        data.extend_from_slice(&6u32.to_be_bytes());
+        data.extend_from_slice(&[0, 0, 0, 1, 0, 96]);
+        data.extend_from_slice(b"MTrk");
+        data.extend_from_slice(&(track.len() as u32).to_be_bytes());
+        data.extend_from_slice(track);
+        data
+    }
+
+    #[test]
+    fn test_parse_midi_file() {
+        let track = [
+            0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20, // Tempo 500000
+            0x00, 0x90, 69, 100, // A4 on
+            0x18, 69, 0, // A4 off by running status
+            0x18, 0x90, 105, 127, // A7 on
+            0x18, 0x80, 105, 0, // A7 off
+            0x00, 0xff, 0x2f, 0x00,
+        ];
+        let midi_file = MidiFile::parse(&midi_data(&track)).unwrap();
+        assert_eq!(midi_file.division, 96);
+        assert_eq!(midi_file.tempo, 500_000);
+        assert_eq!(midi_file.tracks.len(), 1);
+        let track = &midi_file.tracks[0];
+        assert_eq!(track.notes.len(), 2);
+        assert!(!track.is_drum);
+
+        let (notes, volumes) = track.quantize(24, 4);
+        assert_eq!(notes, vec![33, -1, 57, -1]);
+        assert_eq!(volumes, vec![6, 0, 7, 0]);
+    }
+
+    #[test]
+    fn test_parse_invalid_midi_file() {
+        let data = midi_data(&[0x00, 0x90, 69, 100, 0x18, 0x80, 69, 0]);
+        for len in [10, 16, 25, data.len() - 1] {
+            assert_eq!(
+                MidiFile::parse(&data[..len]).err().as_deref(),
+                Some("Unexpected end of MIDI data"),
+                "length {len}"
+            );
+        }
+
+        assert_eq!(
+            MidiFile::parse(&midi_data(&[0x00, 0x90, 69]))
+                .err()
+                .as_deref(),
+            Some("Unexpected end of MIDI data")
+        );
+
+        // A track chunk whose size runs past the end of the data
+        let mut data = midi_data(&[]);
+        let len = data.len();
+        data[len - 4..].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(MidiFile::parse(&data).is_err());
+
+        assert_eq!(
+            MidiFile::parse(&midi_data(&[0x00, 0xf4])).err().as_deref(),
+            Some("Invalid MIDI event 0xf4")
+        );
+        assert!(MidiFile::parse(b"RIFF\0\0\0\x06").is_err());
+    }
+
+    #[test]
+    fn test_load_midi_errors() {
+        let pyxel = headless_pyxel(16, 16);
+        let filename = env::temp_dir().join(format!("pyxel_load_midi_{}.mid", std::process::id()));
+        let filename = filename.to_str().unwrap();
+        assert!(matches!(
+            pyxel.load_midi(filename, 0, 0, None),
+            Err(PyxelError::FileOpen(_))
+        ));
+
+        fs::write(filename, midi_data(&[0x00, 0xf4])).unwrap();
+        assert!(matches!(
+            pyxel.load_midi(filename, 0, 0, None),
+            Err(PyxelError::InvalidFile(_))
+        ));
+
+        fs::write(
+            filename,
+            midi_data(&[0x00, 0x90, 69, 100, 0x18, 0x80, 69, 0]),
+        )
+        .unwrap();
+        let num_sounds = pyxel.sounds.lock().len() as u32;
+        assert!(matches!(
+            pyxel.load_midi(filename, 0, num_sounds, None),
+            Err(PyxelError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            pyxel.load_midi(filename, u32::MAX, 0, None),
+            Err(PyxelError::InvalidArgument(_))
+        ));
+        pyxel.load_midi(filename, 0, 0, None).unwrap();
+        fs::remove_file(filename).unwrap();
+    }
+}
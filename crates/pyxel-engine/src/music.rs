@@ -1,4 +1,8 @@
-use crate::pyxel::CHANNELS;
+use crate::audio;
+use crate::error::{PyxelError, PyxelResult};
+use crate::pyxel::{CHANNELS, SOUNDS};
+use crate::settings::{NUM_OUTPUT_CHANNELS, SAMPLE_RATE};
+use crate::stream;
 
 pub type SharedSeq = shared_type!(Vec<u32>);
 
@@ -24,6 +28,33 @@ impl Music {
             self.seqs.push(new_shared_type!(Vec::new()));
         }
     }
+
+    pub fn save(&self, filename: &str, sec: f64) -> PyxelResult<()> {
+        let sound_seqs = {
+            let sounds = SOUNDS.lock();
+            self.seqs
+                .iter()
+                .map(|seq| {
+                    seq.lock()
+                        .iter()
+                        .map(|sound_index| {
+                            sounds
+                                .get(*sound_index as usize)
+                                .map(|sound| sound.lock().clone())
+                                .ok_or_else(|| {
+                                    PyxelError::InvalidArgument(format!(
+                                        "Invalid sound index {sound_index}"
+                                    ))
+                                })
+                        })
+                        .collect()
+                })
+                .collect::<PyxelResult<_>>()?
+        };
+        let max_frames = (sec * SAMPLE_RATE as f64) as usize;
+        let samples = audio::render_sounds(sound_seqs, SAMPLE_RATE, Some(max_frames));
+        stream::save_wav_file(filename, &samples, SAMPLE_RATE, NUM_OUTPUT_CHANNELS)
+    }
 }
 
 #[cfg(test)]
@@ -49,4 +80,14 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_music_save_invalid_sound() {
+        let music = Music::new();
+        music.lock().set(&[vec![0, u32::MAX]]);
+        assert!(matches!(
+            music.lock().save("unused.wav", 1.0),
+            Err(PyxelError::InvalidArgument(_))
+        ));
+    }
 }
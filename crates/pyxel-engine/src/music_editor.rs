@@ -0,0 +1,329 @@
+use crate::editor::{self, EditHistory, CANVAS_X, CANVAS_Y, TOOL_Y, VIEWER_X, VIEWER_Y};
+use crate::image::Image;
+use crate::keys::{
+    KEY_BACKSPACE, KEY_DELETE, KEY_DOWN, KEY_LEFT, KEY_RIGHT, KEY_SPACE, KEY_UP, KEY_Y, KEY_Z,
+    MOUSE_BUTTON_LEFT,
+};
+use crate::music::SharedMusic;
+use crate::pyxel::Pyxel;
+use crate::settings::{
+    COLOR_BLACK, COLOR_DARK_BLUE, COLOR_GRAY, COLOR_WHITE, COLOR_YELLOW, FONT_HEIGHT, FONT_WIDTH,
+    HOT_CLICK,
+};
+
+const MAX_MUSIC_LENGTH: usize = 32;
+const CHANNEL_WIDTH: i32 = FONT_WIDTH as i32 * 8;
+const ROW_HEIGHT: i32 = FONT_HEIGHT as i32 + 1;
+const ROWS_Y: i32 = CANVAS_Y + ROW_HEIGHT;
+const NUM_VISIBLE_ROWS: usize = 16;
+const PICKER_COLUMNS: i32 = 8;
+const PICKER_CELL_WIDTH: i32 = FONT_WIDTH as i32 * 3;
+const PICKER_CELL_HEIGHT: i32 = ROW_HEIGHT;
+const MUSIC_LABEL_X: i32 = CANVAS_X;
+const PLAY_LABEL_X: i32 = CANVAS_X + FONT_WIDTH as i32 * 11;
+const HOT_ID_BASE: u32 = editor::HOT_ID_BASE + 0x60;
+
+struct MusicRecord {
+    index: usize,
+    seqs: Vec<Vec<u32>>,
+}
+
+pub struct MusicEditor {
+    music: usize,
+    channel: usize,
+    position: usize,
+    scroll: usize,
+    is_playing: bool,
+    has_started: bool,
+    history: EditHistory<MusicRecord>,
+}
+
+impl MusicEditor {
+    pub fn new() -> Self {
+        Self {
+            music: 0,
+            channel: 0,
+            position: 0,
+            scroll: 0,
+            is_playing: false,
+            has_started: false,
+            history: EditHistory::new(),
+        }
+    }
+
+    // Musics created without sequences get one per channel, so that every channel can be edited
+    fn shared_music(&self, pyxel: &Pyxel) -> SharedMusic {
+        let shared_music = pyxel.musics.lock()[self.music].clone();
+        {
+            let num_channels = pyxel.channels.lock().len();
+            let mut music = shared_music.lock();
+            while music.seqs.len() < num_channels {
+                music.seqs.push(new_shared_type!(Vec::new()));
+            }
+        }
+        shared_music
+    }
+
+    fn seqs(&self, pyxel: &Pyxel) -> Vec<Vec<u32>> {
+        self.shared_music(pyxel)
+            .lock()
+            .seqs
+            .iter()
+            .map(|seq| seq.lock().clone())
+            .collect()
+    }
+
+    fn set_seqs(&self, pyxel: &Pyxel, seqs: &[Vec<u32>]) {
+        let shared_music = self.shared_music(pyxel);
+        let music = shared_music.lock();
+        for (seq, data) in music.seqs.iter().zip(seqs) {
+            *seq.lock() = data.clone();
+        }
+    }
+
+    fn push_undo(&mut self, pyxel: &Pyxel) {
+        self.history.push(MusicRecord {
+            index: self.music,
+            seqs: self.seqs(pyxel),
+        });
+    }
+
+    fn undo(&mut self, pyxel: &Pyxel, undo: bool) {
+        let Some(mut record) = self.history.take(undo) else {
+            return;
+        };
+        self.music = record.index;
+        let seqs = self.seqs(pyxel);
+        self.set_seqs(pyxel, &record.seqs);
+        record.seqs = seqs;
+        self.history.put_back(record, undo);
+        self.move_cursor(pyxel, self.channel, self.position);
+    }
+
+    fn move_cursor(&mut self, pyxel: &Pyxel, channel: usize, position: usize) {
+        let seqs = self.seqs(pyxel);
+        self.channel = channel.min(seqs.len() - 1);
+        self.position = position
+            .min(seqs[self.channel].len())
+            .min(MAX_MUSIC_LENGTH - 1);
+        if self.position < self.scroll {
+            self.scroll = self.position;
+        } else if self.position >= self.scroll + NUM_VISIBLE_ROWS {
+            self.scroll = self.position + 1 - NUM_VISIBLE_ROWS;
+        }
+    }
+
+    fn insert_sound(&mut self, pyxel: &Pyxel, sound: u32) {
+        let mut seqs = self.seqs(pyxel);
+        if seqs[self.channel].len() >= MAX_MUSIC_LENGTH {
+            return;
+        }
+        self.push_undo(pyxel);
+        seqs[self.channel].insert(self.position, sound);
+        self.set_seqs(pyxel, &seqs);
+        self.move_cursor(pyxel, self.channel, self.position + 1);
+    }
+
+    fn delete_sound(&mut self, pyxel: &Pyxel, position: usize) {
+        let mut seqs = self.seqs(pyxel);
+        if position >= seqs[self.channel].len() {
+            return;
+        }
+        self.push_undo(pyxel);
+        seqs[self.channel].remove(position);
+        self.set_seqs(pyxel, &seqs);
+        self.move_cursor(pyxel, self.channel, position);
+    }
+
+    pub(crate) fn stop(&mut self, pyxel: &Pyxel) {
+        if self.is_playing {
+            pyxel.stop0();
+            self.is_playing = false;
+        }
+    }
+
+    fn toggle_playback(&mut self, pyxel: &Pyxel) {
+        if self.is_playing {
+            pyxel.stop0();
+            self.is_playing = false;
+        } else if !self.seqs(pyxel).iter().all(Vec::is_empty) {
+            pyxel.playm(self.music as u32, None, false);
+            self.is_playing = true;
+            self.has_started = false;
+        }
+    }
+
+    // Treats the music as finished only after one of its channels has started
+    fn update_playback_state(&mut self, pyxel: &Pyxel) {
+        if !self.is_playing {
+            return;
+        }
+        if Self::is_any_channel_playing(pyxel) {
+            self.has_started = true;
+        } else if self.has_started {
+            self.is_playing = false;
+        }
+    }
+
+    fn is_any_channel_playing(pyxel: &Pyxel) -> bool {
+        let num_channels = pyxel.channels.lock().len() as u32;
+        (0..num_channels).any(|channel| pyxel.play_pos(channel).is_some())
+    }
+
+    fn update_header(&mut self, pyxel: &mut Pyxel) {
+        let label_width = FONT_WIDTH as i32 * 8;
+        let step = editor::value_step(pyxel, MUSIC_LABEL_X, TOOL_Y, label_width);
+        if step != 0 {
+            let num_musics = pyxel.musics.lock().len() as i32;
+            self.music = (self.music as i32 + step).rem_euclid(num_musics) as usize;
+            self.move_cursor(pyxel, self.channel, self.position);
+        }
+        let play_width = FONT_WIDTH as i32 * 4;
+        if editor::value_step(pyxel, PLAY_LABEL_X, TOOL_Y, play_width) != 0
+            || pyxel.btnp(KEY_SPACE, None, None)
+        {
+            self.toggle_playback(pyxel);
+        }
+        self.update_playback_state(pyxel);
+    }
+
+    fn update_shortcuts(&mut self, pyxel: &mut Pyxel) {
+        if editor::is_ctrl_pressed(pyxel) {
+            if pyxel.btnp(KEY_Z, None, None) {
+                self.undo(pyxel, true);
+            } else if pyxel.btnp(KEY_Y, None, None) {
+                self.undo(pyxel, false);
+            }
+            return;
+        }
+        if pyxel.btnp(KEY_BACKSPACE, Some(10), Some(2)) && self.position > 0 {
+            self.delete_sound(pyxel, self.position - 1);
+        } else if pyxel.btnp(KEY_DELETE, Some(10), Some(2)) {
+            self.delete_sound(pyxel, self.position);
+        }
+        let (channel, position) = (self.channel, self.position);
+        if pyxel.btnp(KEY_LEFT, Some(10), Some(2)) {
+            self.move_cursor(pyxel, channel.saturating_sub(1), position);
+        } else if pyxel.btnp(KEY_RIGHT, Some(10), Some(2)) {
+            self.move_cursor(pyxel, channel + 1, position);
+        } else if pyxel.btnp(KEY_UP, Some(10), Some(2)) {
+            self.move_cursor(pyxel, channel, position.saturating_sub(1));
+        } else if pyxel.btnp(KEY_DOWN, Some(10), Some(2)) {
+            self.move_cursor(pyxel, channel, position + 1);
+        }
+    }
+
+    fn update_fields(&mut self, pyxel: &mut Pyxel) {
+        let num_channels = self.seqs(pyxel).len() as i32;
+        let width = (CHANNEL_WIDTH * num_channels) as f64;
+        let height = (ROW_HEIGHT * NUM_VISIBLE_ROWS as i32) as f64;
+        if !pyxel.mouse_in(CANVAS_X as f64, ROWS_Y as f64, width, height) {
+            return;
+        }
+        let wheel = pyxel.mouse_wheel;
+        if wheel != 0 {
+            let max_scroll = MAX_MUSIC_LENGTH - NUM_VISIBLE_ROWS;
+            self.scroll = (self.scroll as i32 - wheel).clamp(0, max_scroll as i32) as usize;
+        }
+        if pyxel.btnp(MOUSE_BUTTON_LEFT, None, None) {
+            let channel = (pyxel.mouse_x - CANVAS_X) / CHANNEL_WIDTH;
+            let row = (pyxel.mouse_y - ROWS_Y) / ROW_HEIGHT;
+            self.move_cursor(pyxel, channel as usize, self.scroll + row as usize);
+        }
+    }
+
+    fn update_picker(&mut self, pyxel: &mut Pyxel) {
+        let num_sounds = pyxel.sounds.lock().len() as i32;
+        for i in 0..num_sounds {
+            let hot_state = pyxel.engine_hot_rect(
+                HOT_ID_BASE + i as u32,
+                (VIEWER_X + (i % PICKER_COLUMNS) * PICKER_CELL_WIDTH) as f64,
+                (VIEWER_Y + (i / PICKER_COLUMNS) * PICKER_CELL_HEIGHT) as f64,
+                PICKER_CELL_WIDTH as f64,
+                PICKER_CELL_HEIGHT as f64,
+            );
+            if hot_state == HOT_CLICK {
+                self.insert_sound(pyxel, i as u32);
+            }
+        }
+    }
+
+    pub(crate) fn update(&mut self, pyxel: &mut Pyxel) {
+        self.update_header(pyxel);
+        self.update_shortcuts(pyxel);
+        self.update_fields(pyxel);
+        self.update_picker(pyxel);
+    }
+
+    fn draw_fields(&self, pyxel: &Pyxel, screen: &mut Image, seqs: &[Vec<u32>]) {
+        let height = (ROW_HEIGHT * NUM_VISIBLE_ROWS as i32) as f64;
+        let num_channels = pyxel.channels.lock().len();
+        for (channel, seq) in seqs.iter().enumerate() {
+            let x = (CANVAS_X + CHANNEL_WIDTH * channel as i32) as f64;
+            screen.text(x, CANVAS_Y as f64, &format!("CH{channel}"), COLOR_GRAY);
+            let width = (CHANNEL_WIDTH - 2) as f64;
+            screen.rect(x, ROWS_Y as f64, width, height, COLOR_BLACK);
+            let play_position = (channel < num_channels)
+                .then(|| pyxel.play_pos(channel as u32))
+                .flatten()
+                .filter(|_| self.is_playing)
+                .map(|(position, _)| position as usize);
+            for row in 0..NUM_VISIBLE_ROWS {
+                let position = self.scroll + row;
+                let y = (ROWS_Y + ROW_HEIGHT * row as i32) as f64;
+                if channel == self.channel && position == self.position {
+                    screen.rect(x, y, width, ROW_HEIGHT as f64, COLOR_DARK_BLUE);
+                }
+                let Some(sound) = seq.get(position) else {
+                    continue;
+                };
+                let color = if play_position == Some(position) {
+                    COLOR_YELLOW
+                } else {
+                    COLOR_WHITE
+                };
+                let text = format!("{position:02}:{sound:02}");
+                screen.text(x + 1.0, y + 1.0, &text, color);
+            }
+        }
+    }
+
+    fn draw_picker(pyxel: &Pyxel, screen: &mut Image) {
+        let num_sounds = pyxel.sounds.lock().len() as i32;
+        for i in 0..num_sounds {
+            let x = (VIEWER_X + (i % PICKER_COLUMNS) * PICKER_CELL_WIDTH) as f64;
+            let y = (VIEWER_Y + (i / PICKER_COLUMNS) * PICKER_CELL_HEIGHT) as f64;
+            let color = if pyxel.mouse_in(x, y, PICKER_CELL_WIDTH as f64, PICKER_CELL_HEIGHT as f64)
+            {
+                COLOR_YELLOW
+            } else {
+                COLOR_WHITE
+            };
+            screen.text(x, y + 1.0, &format!("{i:02}"), color);
+        }
+    }
+
+    // Returns the status text for the hovered entry
+    pub(crate) fn draw(&self, pyxel: &Pyxel, screen: &mut Image) -> Option<String> {
+        let seqs = self.seqs(pyxel);
+        let music_label = format!("MUSIC {}", self.music);
+        screen.text(
+            MUSIC_LABEL_X as f64,
+            TOOL_Y as f64,
+            &music_label,
+            COLOR_WHITE,
+        );
+        let play_label = if self.is_playing { "STOP" } else { "PLAY" };
+        screen.text(PLAY_LABEL_X as f64, TOOL_Y as f64, play_label, COLOR_YELLOW);
+        self.draw_fields(pyxel, screen, &seqs);
+        Self::draw_picker(pyxel, screen);
+        let channel = (pyxel.mouse_x - CANVAS_X).div_euclid(CHANNEL_WIDTH);
+        let row = (pyxel.mouse_y - ROWS_Y).div_euclid(ROW_HEIGHT);
+        let seq = seqs.get(usize::try_from(channel).ok()?)?;
+        if !(0..NUM_VISIBLE_ROWS as i32).contains(&row) {
+            return None;
+        }
+        let position = self.scroll + row as usize;
+        Some(format!("CH{channel} {position}/{}", seq.len()))
+    }
+}
@@ -0,0 +1,355 @@
+use cfg_if::cfg_if;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::error::{PyxelError, PyxelResult};
+use crate::pyxel::Pyxel;
+
+cfg_if! {
+    if #[cfg(target_os = "emscripten")] {
+        use pyxel_platform::emscripten;
+
+        use crate::utils::{decode_hex, encode_hex};
+
+        struct Connection;
+
+        impl Connection {
+            fn connect(address: &str) -> PyxelResult<Self> {
+                if address.contains(['\'', '\\']) {
+                    return Err(PyxelError::Platform(format!("Invalid address '{address}'")));
+                }
+                emscripten::run_script(&format!(
+                    "window._pyxelNet = {{ socket: new WebSocket('{address}'), messages: [] }};
+                    _pyxelNet.socket.binaryType = 'arraybuffer';
+                    _pyxelNet.socket.onmessage = (event) => {{
+                        const bytes = typeof event.data === 'string'
+                            ? new TextEncoder().encode(event.data)
+                            : new Uint8Array(event.data);
+                        _pyxelNet.messages.push(
+                            Array.from(bytes, (byte) => byte.toString(16).padStart(2, '0')).join('')
+                        );
+                    }};"
+                ));
+                Ok(Self)
+            }
+
+            fn send(&self, data: &[u8]) {
+                emscripten::run_script(&format!(
+                    "if (_pyxelNet.socket.readyState === WebSocket.OPEN) {{
+                        _pyxelNet.socket.send(new Uint8Array(
+                            ('{}'.match(/../g) || []).map((hex) => parseInt(hex, 16))
+                        ));
+                    }}",
+                    encode_hex(data)
+                ));
+            }
+
+            fn receive(&mut self, messages: &mut Vec<Vec<u8>>) {
+                let hex_messages =
+                    emscripten::run_script_string("_pyxelNet.messages.splice(0).join(',')");
+                messages.extend(
+                    hex_messages
+                        .split(',')
+                        .filter(|hex| !hex.is_empty())
+                        .filter_map(decode_hex),
+                );
+            }
+        }
+
+        impl Drop for Connection {
+            fn drop(&mut self) {
+                emscripten::run_script("_pyxelNet.socket.close(); window._pyxelNet = null;");
+            }
+        }
+    } else {
+        use std::io::{self, ErrorKind};
+        use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+        use std::time::{Duration, Instant};
+
+        use crate::settings::{MAX_NET_MESSAGE_SIZE, NET_PEER_TIMEOUT_SEC};
+
+        struct Peer {
+            address: SocketAddr,
+            socket_index: usize,
+            last_received: Instant,
+        }
+
+        // A client sends to its address, while a host replies to every peer it has heard from
+        // within the timeout
+        struct Connection {
+            sockets: Vec<UdpSocket>,
+            peers: Option<Vec<Peer>>,
+        }
+
+        impl Connection {
+            fn bind(address: SocketAddr) -> io::Result<UdpSocket> {
+                let socket = UdpSocket::bind(address)?;
+                socket.set_nonblocking(true)?;
+                Ok(socket)
+            }
+
+            fn connect(address: &str) -> PyxelResult<Self> {
+                let platform_error = |err: io::Error| PyxelError::Platform(err.to_string());
+                let address = address
+                    .to_socket_addrs()
+                    .map_err(platform_error)?
+                    .next()
+                    .ok_or_else(|| PyxelError::Platform(format!("Failed to resolve '{address}'")))?;
+                let local_address: SocketAddr = if address.is_ipv6() {
+                    (Ipv6Addr::UNSPECIFIED, 0).into()
+                } else {
+                    (Ipv4Addr::UNSPECIFIED, 0).into()
+                };
+                let socket = Self::bind(local_address).map_err(platform_error)?;
+                socket.connect(address).map_err(platform_error)?;
+                Ok(Self {
+                    sockets: vec![socket],
+                    peers: None,
+                })
+            }
+
+            // Whether an IPv6 socket also accepts IPv4 depends on the platform, so an IPv4
+            // socket is bound on the same port as well unless the IPv6 one already covers it
+            fn listen(port: u16) -> PyxelResult<Self> {
+                let ipv6_socket = Self::bind((Ipv6Addr::UNSPECIFIED, port).into());
+                let port = ipv6_socket
+                    .as_ref()
+                    .ok()
+                    .and_then(|socket| socket.local_addr().ok())
+                    .map_or(port, |address| address.port());
+                let ipv4_socket = Self::bind((Ipv4Addr::UNSPECIFIED, port).into());
+                if let (Err(_), Err(err)) = (&ipv6_socket, &ipv4_socket) {
+                    return Err(PyxelError::Platform(err.to_string()));
+                }
+                Ok(Self {
+                    sockets: [ipv6_socket, ipv4_socket]
+                        .into_iter()
+                        .filter_map(Result::ok)
+                        .collect(),
+                    peers: Some(Vec::new()),
+                })
+            }
+
+            #[cfg(test)]
+            fn local_port(&self) -> u16 {
+                self.sockets[0].local_addr().unwrap().port()
+            }
+
+            fn send(&mut self, data: &[u8]) {
+                let Some(peers) = &mut self.peers else {
+                    if let Err(err) = self.sockets[0].send(data) {
+                        log::warn!("Failed to send a message: {err}");
+                    }
+                    return;
+                };
+
+                // An unreachable peer is dropped without keeping the others from getting the message
+                let sockets = &self.sockets;
+                peers.retain(|peer| {
+                    match sockets[peer.socket_index].send_to(data, peer.address) {
+                        Ok(_) => true,
+                        Err(err) if err.kind() == ErrorKind::WouldBlock => true,
+                        Err(err) => {
+                            log::warn!("Failed to send a message to {}: {err}", peer.address);
+                            false
+                        }
+                    }
+                });
+            }
+
+            fn receive(&mut self, messages: &mut Vec<Vec<u8>>) {
+                let now = Instant::now();
+                let mut buffer = vec![0; MAX_NET_MESSAGE_SIZE];
+                for (socket_index, socket) in self.sockets.iter().enumerate() {
+                    loop {
+                        match socket.recv_from(&mut buffer) {
+                            Ok((size, address)) => {
+                                if let Some(peers) = &mut self.peers {
+                                    add_peer(peers, address, socket_index, now);
+                                }
+                                messages.push(buffer[..size].to_vec());
+                            }
+                            Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                            Err(err) => {
+                                log::warn!("Failed to receive a message: {err}");
+                                break;
+                            }
+                        }
+                    }
+                }
+                if let Some(peers) = &mut self.peers {
+                    remove_stale_peers(peers, now);
+                }
+            }
+        }
+
+        fn add_peer(peers: &mut Vec<Peer>, address: SocketAddr, socket_index: usize, now: Instant) {
+            if let Some(peer) = peers.iter_mut().find(|peer| peer.address == address) {
+                peer.last_received = now;
+            } else {
+                peers.push(Peer {
+                    address,
+                    socket_index,
+                    last_received: now,
+                });
+            }
+        }
+
+        fn remove_stale_peers(peers: &mut Vec<Peer>, now: Instant) {
+            let timeout = Duration::from_secs(NET_PEER_TIMEOUT_SEC);
+            peers.retain(|peer| now.duration_since(peer.last_received) < timeout);
+        }
+    }
+}
+
+struct NetState {
+    connection: Option<Connection>,
+    messages: Vec<Vec<u8>>,
+}
+
+static NET_STATE: Lazy<Mutex<NetState>> = Lazy::new(|| {
+    Mutex::new(NetState {
+        connection: None,
+        messages: Vec::new(),
+    })
+});
+
+impl Pyxel {
+    // Connects over UDP on desktop and over WebSocket on the web, replacing any previous connection.
+    // Desktop addresses are "host:port", such as "127.0.0.1:5000" or "[::1]:5000", while web
+    // addresses are WebSocket URLs such as "ws://example.com:5000".
+    pub fn connect(&mut self, address: &str) -> PyxelResult<()> {
+        let mut net_state = NET_STATE.lock();
+        net_state.connection = None;
+        net_state.connection = Some(Connection::connect(address)?);
+        Ok(())
+    }
+
+    // Hosts on the port over IPv4 and IPv6, replying to peers heard from in the last
+    // NET_PEER_TIMEOUT_SEC seconds. Only desktop platforms can host.
+    pub fn listen(&mut self, port: u16) -> PyxelResult<()> {
+        cfg_if! {
+            if #[cfg(target_os = "emscripten")] {
+                Err(PyxelError::Platform(format!("Cannot listen on port {port} in the browser")))
+            } else {
+                let mut net_state = NET_STATE.lock();
+                net_state.connection = None;
+                net_state.connection = Some(Connection::listen(port)?);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn disconnect(&mut self) {
+        let mut net_state = NET_STATE.lock();
+        net_state.connection = None;
+        net_state.messages.clear();
+    }
+
+    pub fn send(&self, data: &[u8]) {
+        if let Some(connection) = &mut NET_STATE.lock().connection {
+            connection.send(data);
+        }
+    }
+
+    // Messages received before the current frame, which stay the same until the next update
+    pub fn poll_messages(&self) -> Vec<Vec<u8>> {
+        NET_STATE.lock().messages.clone()
+    }
+
+    pub(crate) fn receive_net_messages(&mut self) {
+        let mut net_state = NET_STATE.lock();
+        let net_state = &mut *net_state;
+        net_state.messages.clear();
+        if let Some(connection) = &mut net_state.connection {
+            connection.receive(&mut net_state.messages);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "emscripten")))]
+mod tests {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn receive_until(connection: &mut Connection, num_messages: usize) -> Vec<Vec<u8>> {
+        let mut messages = Vec::new();
+        for _ in 0..100 {
+            connection.receive(&mut messages);
+            if messages.len() >= num_messages {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        messages
+    }
+
+    #[test]
+    fn test_loopback() {
+        let mut host = Connection::listen(0).unwrap();
+        let port = host.local_port();
+        let mut client1 = Connection::connect(&format!("127.0.0.1:{port}")).unwrap();
+        let mut client2 = Connection::connect(&format!("localhost:{port}")).unwrap();
+
+        client1.send(b"hello");
+        client2.send(b"world");
+        let mut messages = receive_until(&mut host, 2);
+        messages.sort();
+        assert_eq!(messages, [b"hello".to_vec(), b"world".to_vec()]);
+        assert_eq!(host.peers.as_ref().unwrap().len(), 2);
+
+        host.send(b"reply");
+        assert_eq!(receive_until(&mut client1, 1), [b"reply".to_vec()]);
+        assert_eq!(receive_until(&mut client2, 1), [b"reply".to_vec()]);
+    }
+
+    #[test]
+    fn test_loopback_ipv6() {
+        let mut host = Connection::listen(0).unwrap();
+        let port = host.local_port();
+        let Ok(mut client) = Connection::connect(&format!("[::1]:{port}")) else {
+            return; // IPv6 is not available
+        };
+        client.send(b"hello");
+        assert_eq!(receive_until(&mut host, 1), [b"hello".to_vec()]);
+    }
+
+    #[test]
+    fn test_poll_messages() {
+        let mut pyxel = crate::pyxel::headless_pyxel(16, 16);
+        pyxel.listen(0).unwrap();
+        let port = NET_STATE.lock().connection.as_ref().unwrap().local_port();
+        let mut client = Connection::connect(&format!("127.0.0.1:{port}")).unwrap();
+        client.send(b"input");
+        for _ in 0..100 {
+            pyxel.receive_net_messages();
+            if !pyxel.poll_messages().is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(pyxel.poll_messages(), [b"input".to_vec()]);
+
+        pyxel.send(b"state");
+        assert_eq!(receive_until(&mut client, 1), [b"state".to_vec()]);
+        pyxel.disconnect();
+        assert!(pyxel.poll_messages().is_empty());
+    }
+
+    #[test]
+    fn test_remove_stale_peers() {
+        let now = Instant::now();
+        let timeout = Duration::from_secs(NET_PEER_TIMEOUT_SEC);
+        let mut peers = Vec::new();
+        add_peer(&mut peers, "127.0.0.1:1".parse().unwrap(), 0, now);
+        add_peer(&mut peers, "127.0.0.1:2".parse().unwrap(), 0, now);
+        add_peer(&mut peers, "127.0.0.1:1".parse().unwrap(), 0, now + timeout);
+        assert_eq!(peers.len(), 2);
+
+        remove_stale_peers(&mut peers, now + timeout);
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].address, "127.0.0.1:1".parse().unwrap());
+    }
+}
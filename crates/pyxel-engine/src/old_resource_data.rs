@@ -1,16 +1,15 @@
 use std::fmt;
-use std::fs::File;
-use std::io::Read;
-use std::path::Path;
+use std::io::{Read, Seek};
 
 use zip::ZipArchive;
 
-use crate::image::{Color, Image, Rgb24};
+use crate::error::{PyxelError, PyxelResult};
+use crate::image::{Color, Image};
 use crate::music::Music;
 use crate::pyxel::Pyxel;
 use crate::settings::{
     INITIAL_SOUND_SPEED, NUM_CHANNELS, NUM_IMAGES, NUM_MUSICS, NUM_SOUNDS, NUM_TILEMAPS,
-    PALETTE_FILE_EXTENSION, TILEMAP_SIZE, VERSION,
+    TILEMAP_SIZE, VERSION,
 };
 use crate::sound::Sound;
 use crate::tilemap::{ImageSource, TileCoord, Tilemap};
@@ -22,7 +21,8 @@ trait ResourceItem {
     fn resource_name(item_index: u32) -> String;
     fn is_modified(&self) -> bool;
     fn clear(&mut self);
-    fn deserialize(&mut self, version: u32, input: &str);
+    // Returns None for malformed input, which may leave the item partly deserialized
+    fn deserialize(&mut self, version: u32, input: &str) -> Option<()>;
 }
 
 impl ResourceItem for Image {
@@ -45,13 +45,17 @@ impl ResourceItem for Image {
         self.cls(0);
     }
 
-    fn deserialize(&mut self, _version: u32, input: &str) {
+    fn deserialize(&mut self, _version: u32, input: &str) -> Option<()> {
         for (i, line) in input.lines().enumerate() {
+            if i >= self.height() as usize || line.len() > self.width() as usize {
+                return None;
+            }
             string_loop!(j, color, line, 1, {
                 self.canvas
-                    .write_data(j, i, parse_hex_string(&color).unwrap() as Color);
+                    .write_data(j, i, parse_hex_string(&color).ok()? as Color);
             });
         }
+        Some(())
     }
 }
 
@@ -84,12 +88,19 @@ impl ResourceItem for Tilemap {
         self.cls((0, 0));
     }
 
-    fn deserialize(&mut self, version: u32, input: &str) {
+    fn deserialize(&mut self, version: u32, input: &str) -> Option<()> {
+        let width = self.width() as usize;
         for (y, line) in input.lines().enumerate() {
             if y < TILEMAP_SIZE as usize {
+                if y >= self.height() as usize {
+                    return None;
+                }
                 if version < 10500 {
+                    if line.len() / 3 > width {
+                        return None;
+                    }
                     string_loop!(x, tile, line, 3, {
-                        let tile = parse_hex_string(&tile).unwrap();
+                        let tile = parse_hex_string(&tile).ok()?;
                         self.canvas.write_data(
                             x,
                             y,
@@ -97,17 +108,25 @@ impl ResourceItem for Tilemap {
                         );
                     });
                 } else {
+                    if line.len() / 4 > width {
+                        return None;
+                    }
                     string_loop!(x, tile, line, 4, {
-                        let tile_x = parse_hex_string(&tile[0..2]).unwrap();
-                        let tile_y = parse_hex_string(&tile[2..4]).unwrap();
+                        let tile_x = parse_hex_string(&tile[0..2]).ok()?;
+                        let tile_y = parse_hex_string(&tile[2..4]).ok()?;
                         self.canvas
                             .write_data(x, y, (tile_x as TileCoord, tile_y as TileCoord));
                     });
                 }
             } else {
-                self.imgsrc = ImageSource::Index(line.parse::<usize>().unwrap() as u32);
+                let imgsrc = line
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|index| *index < NUM_IMAGES)?;
+                self.imgsrc = ImageSource::Index(imgsrc);
             }
         }
+        Some(())
     }
 }
 
@@ -131,7 +150,7 @@ impl ResourceItem for Sound {
         self.speed = INITIAL_SOUND_SPEED;
     }
 
-    fn deserialize(&mut self, _version: u32, input: &str) {
+    fn deserialize(&mut self, _version: u32, input: &str) -> Option<()> {
         self.clear();
         for (i, line) in input.lines().enumerate() {
             if line == "none" {
@@ -139,26 +158,27 @@ impl ResourceItem for Sound {
             }
             if i == 0 {
                 string_loop!(j, value, line, 2, {
-                    self.notes.push(parse_hex_string(&value).unwrap() as i8);
+                    self.notes.push(parse_hex_string(&value).ok()? as i8);
                 });
                 continue;
             } else if i == 1 {
                 string_loop!(j, value, line, 1, {
-                    self.tones.push(parse_hex_string(&value).unwrap() as u32);
+                    self.tones.push(parse_hex_string(&value).ok()?);
                 });
             } else if i == 2 {
                 string_loop!(j, value, line, 1, {
-                    self.volumes.push(parse_hex_string(&value).unwrap() as u8);
+                    self.volumes.push(parse_hex_string(&value).ok()? as u8);
                 });
             } else if i == 3 {
                 string_loop!(j, value, line, 1, {
-                    self.effects.push(parse_hex_string(&value).unwrap() as u8);
+                    self.effects.push(parse_hex_string(&value).ok()? as u8);
                 });
             } else if i == 4 {
-                self.speed = line.parse().unwrap();
+                self.speed = line.parse().ok()?;
                 continue;
             }
         }
+        self.is_valid().then_some(())
     }
 }
 
@@ -177,53 +197,70 @@ impl ResourceItem for Music {
             .collect();
     }
 
-    fn deserialize(&mut self, _version: u32, input: &str) {
+    fn deserialize(&mut self, _version: u32, input: &str) -> Option<()> {
         self.clear();
         for (i, line) in input.lines().enumerate() {
             if line == "none" {
                 continue;
             }
+            let mut seq = self.seqs.get(i)?.lock();
             string_loop!(j, value, line, 2, {
-                self.seqs[i].lock().push(parse_hex_string(&value).unwrap());
+                let sound_index = parse_hex_string(&value).ok()?;
+                if sound_index >= NUM_SOUNDS {
+                    return None;
+                }
+                seq.push(sound_index);
             });
         }
+        Some(())
     }
 }
 
 impl Pyxel {
     pub fn load_old_resource(
         &mut self,
-        archive: &mut ZipArchive<File>,
+        archive: &mut ZipArchive<impl Read + Seek>,
         filename: &str,
         include_images: bool,
         include_tilemaps: bool,
         include_sounds: bool,
         include_musics: bool,
-    ) {
+    ) -> PyxelResult<()> {
+        let invalid_file = || PyxelError::InvalidFile(filename.to_string());
         let version_name = RESOURCE_ARCHIVE_DIRNAME.to_string() + "version";
         let contents = {
-            let mut file = archive.by_name(&version_name).unwrap();
+            let mut file = archive.by_name(&version_name).map_err(|_| invalid_file())?;
             let mut contents = String::new();
-            file.read_to_string(&mut contents).unwrap();
+            file.read_to_string(&mut contents)
+                .map_err(|_| invalid_file())?;
             contents
         };
-        let version = parse_version_string(&contents).unwrap();
-        assert!(
-            version <= parse_version_string(VERSION).unwrap(),
-            "Unsupported resource file version '{contents}'"
-        );
+        let version = parse_version_string(&contents).map_err(|_| invalid_file())?;
 
+        // Files from newer Pyxel versions are not supported
+        if version > parse_version_string(VERSION).unwrap() {
+            return Err(invalid_file());
+        }
+
+        // The text is sliced by bytes, so anything other than ASCII is rejected up front
         macro_rules! deserialize {
             ($type: ty, $list: ident, $count: expr) => {
                 for i in 0..$count {
+                    let Some(item) = self.$list.lock().get(i as usize).cloned() else {
+                        continue;
+                    };
                     if let Ok(mut file) = archive.by_name(&<$type>::resource_name(i)) {
                         let mut input = String::new();
-                        file.read_to_string(&mut input).unwrap();
-                        self.$list.lock()[i as usize]
-                            .lock()
-                            .deserialize(version, &input);
+                        file.read_to_string(&mut input)
+                            .map_err(|_| invalid_file())?;
+                        if !input.is_ascii() {
+                            return Err(invalid_file());
+                        }
+                        item.lock()
+                            .deserialize(version, &input)
+                            .ok_or_else(invalid_file)?;
                     } else {
-                        self.$list.lock()[i as usize].lock().clear();
+                        item.lock().clear();
                     }
                 }
             };
@@ -242,25 +279,7 @@ impl Pyxel {
             deserialize!(Music, musics, NUM_MUSICS);
         }
 
-        // Try to load Pyxel palette file
-        let filename = filename
-            .rfind('.')
-            .map_or(filename, |i| &filename[..i])
-            .to_string()
-            + PALETTE_FILE_EXTENSION;
-        if let Ok(mut file) = File::open(Path::new(&filename)) {
-            let mut contents = String::new();
-            file.read_to_string(&mut contents).unwrap();
-            let colors: Vec<Rgb24> = contents
-                .replace("\r\n", "\n")
-                .replace('\r', "\n")
-                .split('\n')
-                .filter(|s| !s.is_empty())
-                .map(|s| u32::from_str_radix(s.trim(), 16).unwrap() as Rgb24)
-                .collect();
-            self.colors.lock().clear();
-            self.colors.lock().extend(colors.iter());
-        }
+        Ok(())
     }
 }
 
@@ -275,11 +294,11 @@ fn parse_version_string(string: &str) -> Result<u32, &str> {
         } else {
             return Err("invalid version string");
         };
-        if let Ok(number) = number.parse::<u32>() {
-            version = version * 100 + number;
-        } else {
-            return Err("invalid version string");
-        }
+        version = number
+            .parse::<u32>()
+            .ok()
+            .and_then(|number| version.checked_mul(100)?.checked_add(number))
+            .ok_or("invalid version string")?;
     }
     Ok(version)
 }
@@ -302,5 +321,49 @@ mod tests {
             Err("invalid version string")
         );
         assert_eq!(parse_version_string(" "), Err("invalid version string"));
+        assert_eq!(
+            parse_version_string("99999999.1"),
+            Err("invalid version string")
+        );
+    }
+
+    #[test]
+    fn test_load_old_resource_rejects_malformed_data() {
+        use std::io::{Cursor, Write};
+        use zip::write::FileOptions;
+        use zip::ZipWriter;
+
+        use crate::settings::IMAGE_SIZE;
+
+        let archive = |version: &str, name: &str, contents: &str| {
+            let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+            for (name, contents) in [("version", version), (name, contents)] {
+                zip.start_file(
+                    RESOURCE_ARCHIVE_DIRNAME.to_string() + name,
+                    FileOptions::default(),
+                )
+                .unwrap();
+                zip.write_all(contents.as_bytes()).unwrap();
+            }
+            ZipArchive::new(Cursor::new(zip.finish().unwrap().into_inner())).unwrap()
+        };
+        let mut pyxel = crate::pyxel::headless_pyxel(16, 16);
+        let mut load_version = |version: &str, name: &str, contents: &str| {
+            let mut archive = archive(version, name, contents);
+            pyxel.load_old_resource(&mut archive, "test", true, true, true, true)
+        };
+        assert!(load_version("99.0.0", "sound0", "").is_err());
+        assert!(load_version("1.x", "sound0", "").is_err());
+
+        let mut load = |name: &str, contents: &str| load_version("1.4.0", name, contents);
+
+        assert!(load("sound0", "0010\n00\n77\n00\n30").is_ok());
+        assert!(load("sound0", "0010\n00\n88\n00\n30").is_err());
+        assert!(load("sound0", "zz\n0\n7\n0\n30").is_err());
+        assert!(load("music0", "0001\n0203\n0405\n0607\n0809").is_err());
+        assert!(load("music0", "ff").is_err());
+        assert!(load("image0", &"0".repeat(IMAGE_SIZE as usize + 1)).is_err());
+        assert!(load("image0", "\u{e9}").is_err());
+        assert!(load("tilemap0", &("\n".repeat(TILEMAP_SIZE as usize) + "99")).is_err());
     }
 }
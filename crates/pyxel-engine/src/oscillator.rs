@@ -1,21 +1,52 @@
+use std::cmp::max;
+
 use crate::blip_buf::BlipBuf;
-use crate::pyxel::TONES;
+use crate::dsp::Filter;
+use crate::pyxel::{SAMPLES, TONES};
 use crate::settings::{
-    CLOCK_RATE, EFFECT_FADEOUT, EFFECT_NONE, EFFECT_SLIDE, EFFECT_VIBRATO, INITIAL_NOISE_REG,
-    NUM_CLOCKS_PER_TICK, OSCILLATOR_RESOLUTION, TONE_TRIANGLE, VIBRATO_DEPTH, VIBRATO_FREQUENCY,
+    CLOCK_RATE, EFFECT_FADEOUT, EFFECT_HALF_FADEOUT, EFFECT_NONE, EFFECT_SLIDE, EFFECT_VIBRATO,
+    INITIAL_ENVELOPE, INITIAL_NOISE_REG, NUM_CLOCKS_PER_TICK, OSCILLATOR_RESOLUTION, TONE_TRIANGLE,
+    VIBRATO_DEPTH, VIBRATO_FREQUENCY,
 };
 
 pub type Gain = f64;
 pub type Effect = u8;
 
-const VIBRATO_PERIOD: u32 =
-    (CLOCK_RATE as f64 / VIBRATO_FREQUENCY / OSCILLATOR_RESOLUTION as f64) as u32;
+// Attack, decay and release are measured in ticks
+#[derive(Clone, Copy, PartialEq)]
+pub struct Envelope {
+    pub attack: u32,
+    pub decay: u32,
+    pub sustain: Gain,
+    pub release: u32,
+}
+
+impl Envelope {
+    // The release phase occupies the last ticks of the note since notes have a fixed length
+    fn level(&self, time: u32, duration: u32) -> Gain {
+        let mut level = if time < self.attack {
+            (time + 1) as Gain / (self.attack + 1) as Gain
+        } else if time < self.attack + self.decay {
+            let rate = (time - self.attack + 1) as Gain / self.decay as Gain;
+            1.0 + (self.sustain - 1.0) * rate
+        } else {
+            self.sustain
+        };
+        let remaining = duration.saturating_sub(time);
+        if remaining <= self.release {
+            level *= remaining as Gain / (self.release + 1) as Gain;
+        }
+        level
+    }
+}
 
 struct Slide {
     pitch: f64,
 }
 
 struct Vibrato {
+    depth: f64,
+    period: u32,
     time: u32,
     phase: u32,
 }
@@ -29,10 +60,14 @@ pub struct Oscillator {
     tone: u32,
     gain: Gain,
     effect: Effect,
+    envelope: Envelope,
+    note_duration: u32,
     duration: u32,
     time: u32,
     phase: u32,
-    amplitude: i16,
+    sample_position: u32,
+    amplitudes: [i16; 3],
+    filter: Filter,
     noise_reg: u16,
     slide: Slide,
     vibrato: Vibrato,
@@ -46,29 +81,55 @@ impl Oscillator {
             tone: TONE_TRIANGLE,
             gain: 0.0,
             effect: EFFECT_NONE,
+            envelope: INITIAL_ENVELOPE,
+            note_duration: 0,
             duration: 0,
             time: 0,
             phase: 0,
-            amplitude: 0,
+            sample_position: 0,
+            amplitudes: [0; 3],
+            filter: Filter::new(),
             noise_reg: INITIAL_NOISE_REG,
             slide: Slide { pitch: 0.0 },
-            vibrato: Vibrato { time: 0, phase: 0 },
+            vibrato: Vibrato {
+                depth: VIBRATO_DEPTH,
+                period: Self::vibrato_period(VIBRATO_FREQUENCY),
+                time: 0,
+                phase: 0,
+            },
             fadeout: FadeOut { gain: 0.0 },
         }
     }
 
-    pub fn play(&mut self, note: f64, tone: u32, gain: Gain, effect: Effect, duration: u32) {
+    pub fn play(
+        &mut self,
+        note: f64,
+        tone: u32,
+        gain: Gain,
+        effect: Effect,
+        duration: u32,
+        envelope: Envelope,
+        vibrato_depth: f64,
+        vibrato_frequency: f64,
+    ) {
         let last_pitch = self.pitch;
         self.pitch = Self::note_to_pitch(note);
         self.tone = tone;
         self.gain = gain;
         self.effect = effect;
+        self.envelope = envelope;
+        self.note_duration = duration;
         self.duration = duration;
+        self.sample_position = 0;
+        self.vibrato.depth = vibrato_depth;
+        self.vibrato.period = Self::vibrato_period(vibrato_frequency);
         if effect == EFFECT_SLIDE {
             self.slide.pitch = (self.pitch - last_pitch) / self.duration as f64;
             self.pitch = last_pitch;
         } else if effect == EFFECT_FADEOUT {
             self.fadeout.gain = -self.gain / self.duration as f64;
+        } else if effect == EFFECT_HALF_FADEOUT {
+            self.fadeout.gain = -self.gain / 2.0 / self.duration as f64;
         }
     }
 
@@ -76,7 +137,15 @@ impl Oscillator {
         self.duration = 0;
     }
 
-    pub fn update(&mut self, blip_buf: &mut BlipBuf) {
+    // Writes to one buffer for mono output, or to left, right and reverb send buffers
+    pub fn update(
+        &mut self,
+        blip_bufs: &mut [BlipBuf],
+        pan: f64,
+        reverb_send: Gain,
+        lowpass_cutoff: Option<f64>,
+        highpass_cutoff: Option<f64>,
+    ) {
         if self.duration == 0 {
             self.time = 0;
             return;
@@ -89,23 +158,51 @@ impl Oscillator {
                     } else {
                         3.0 - self.vibrato.phase as f64 / (OSCILLATOR_RESOLUTION / 4) as f64
                     })
-                    * VIBRATO_DEPTH
+                    * self.vibrato.depth
             } else {
                 0.0
             };
-        let period = (CLOCK_RATE as f64 / pitch / OSCILLATOR_RESOLUTION as f64) as u32;
         let tones = TONES.lock();
-        let tone = tones[self.tone as usize].lock();
+        let Some(tone) = tones.get(self.tone as usize).map(|tone| tone.lock()) else {
+            // Sounds may reference tones that were not loaded along with them
+            self.output(blip_bufs, 0.0, [1.0; 3]);
+            self.duration -= 1;
+            self.time = 0;
+            return;
+        };
+        let samples = SAMPLES.lock();
+        let sample = tone
+            .sample
+            .and_then(|index| samples.get(index as usize))
+            .map(|sample| sample.lock())
+            .filter(|sample| sample.sample_rate > 0);
+        let period = if let Some(sample) = &sample {
+            // Samples advance one frame per step so that the base note plays at the recorded rate
+            let rate =
+                sample.sample_rate as f64 * pitch / Self::note_to_pitch(sample.base_note as f64);
+            max((CLOCK_RATE as f64 / rate) as u32, 1)
+        } else {
+            (CLOCK_RATE as f64 / pitch / OSCILLATOR_RESOLUTION as f64) as u32
+        };
+        let gain = self.gain
+            * self
+                .envelope
+                .level(self.note_duration - self.duration, self.note_duration);
+        let output_gains = Self::output_gains(pan, reverb_send, blip_bufs.len());
+        let interval = period as f64 / CLOCK_RATE as f64;
         while self.time < NUM_CLOCKS_PER_TICK {
-            let last_amplitude = self.amplitude;
-            self.phase = (self.phase + 1) % OSCILLATOR_RESOLUTION;
-            self.amplitude = (tone.amplitude(self.phase, &mut self.noise_reg)
-                * self.gain
-                * i16::MAX as f64) as i16;
-            blip_buf.add_delta(
-                self.time as u64,
-                self.amplitude as i32 - last_amplitude as i32,
-            );
+            let amplitude = if let Some(sample) = &sample {
+                let amplitude = sample.amplitude(self.sample_position) * tone.gain;
+                self.sample_position = self.sample_position.saturating_add(1);
+                amplitude
+            } else {
+                self.phase = (self.phase + 1) % OSCILLATOR_RESOLUTION;
+                tone.amplitude(self.phase, &mut self.noise_reg)
+            };
+            let amplitude =
+                self.filter
+                    .process(amplitude * gain, lowpass_cutoff, highpass_cutoff, interval);
+            self.output(blip_bufs, amplitude, output_gains);
             self.time += period;
         }
         match self.effect {
@@ -115,11 +212,11 @@ impl Oscillator {
             }
             EFFECT_VIBRATO => {
                 self.vibrato.time += NUM_CLOCKS_PER_TICK;
-                self.vibrato.phase = (self.vibrato.phase + self.vibrato.time / VIBRATO_PERIOD)
+                self.vibrato.phase = (self.vibrato.phase + self.vibrato.time / self.vibrato.period)
                     % OSCILLATOR_RESOLUTION;
-                self.vibrato.time %= VIBRATO_PERIOD;
+                self.vibrato.time %= self.vibrato.period;
             }
-            EFFECT_FADEOUT => {
+            EFFECT_FADEOUT | EFFECT_HALF_FADEOUT => {
                 self.gain += self.fadeout.gain;
             }
             _ => panic!("Invalid effect '{}'", self.effect),
@@ -128,6 +225,34 @@ impl Oscillator {
         self.time -= NUM_CLOCKS_PER_TICK;
     }
 
+    fn output(&mut self, blip_bufs: &mut [BlipBuf], amplitude: f64, output_gains: [Gain; 3]) {
+        for ((blip_buf, last_amplitude), output_gain) in blip_bufs
+            .iter_mut()
+            .zip(&mut self.amplitudes)
+            .zip(output_gains)
+        {
+            let amplitude = (amplitude * output_gain * i16::MAX as f64) as i16;
+            blip_buf.add_delta(self.time as u64, amplitude as i32 - *last_amplitude as i32);
+            *last_amplitude = amplitude;
+        }
+    }
+
+    // Linear pan law that keeps centered channels at full gain on both sides
+    fn output_gains(pan: f64, reverb_send: Gain, num_outputs: usize) -> [Gain; 3] {
+        if num_outputs < 2 {
+            return [1.0; 3];
+        }
+        let pan = pan.clamp(-1.0, 1.0);
+        [(1.0 - pan).min(1.0), (1.0 + pan).min(1.0), reverb_send]
+    }
+
+    fn vibrato_period(frequency: f64) -> u32 {
+        max(
+            (CLOCK_RATE as f64 / frequency / OSCILLATOR_RESOLUTION as f64) as u32,
+            1,
+        )
+    }
+
     fn note_to_pitch(note: f64) -> f64 {
         440.0 * ((note - 33.0) / 12.0).exp2()
     }
@@ -0,0 +1,250 @@
+use std::cmp::min;
+
+use crate::image::{Color, Rgb24};
+use crate::pyxel::Pyxel;
+use crate::settings::{
+    COLOR_BLACK, COLOR_GRAY, COLOR_WHITE, COLOR_YELLOW, FONT_HEIGHT, FONT_WIDTH, HOT_CLICK,
+    HOT_NONE, HOT_PRESS, NUM_COLORS,
+};
+
+const SWATCH_SIZE: i32 = 8;
+const SWATCHES_PER_ROW: i32 = 8;
+const PANEL_PADDING: i32 = 4;
+const PANEL_WIDTH: i32 = SWATCH_SIZE * SWATCHES_PER_ROW + PANEL_PADDING * 2;
+const ROW_HEIGHT: i32 = FONT_HEIGHT as i32 + 2;
+const BAR_OFFSET_X: i32 = PANEL_PADDING + FONT_WIDTH as i32 * 2;
+const BAR_WIDTH: i32 = 40;
+const BUTTON_WIDTH: i32 = FONT_WIDTH as i32 * 4;
+const BUTTON_LABELS: [&str; 2] = ["RUST", "TOML"];
+const CHANNEL_LABELS: [&str; 3] = ["R", "G", "B"];
+const SWATCH_HOT_ID_BASE: u32 = 0x100;
+const BAR_HOT_ID_BASE: u32 = SWATCH_HOT_ID_BASE + NUM_COLORS;
+const BUTTON_HOT_ID_BASE: u32 = BAR_HOT_ID_BASE + CHANNEL_LABELS.len() as u32;
+
+struct PanelLayout {
+    x: i32,
+    y: i32,
+    height: i32,
+    bars_y: i32,
+    buttons_y: i32,
+}
+
+impl PanelLayout {
+    fn swatch_pos(&self, color_index: usize) -> (i32, i32) {
+        (
+            self.x + PANEL_PADDING + (color_index as i32 % SWATCHES_PER_ROW) * SWATCH_SIZE,
+            self.y + PANEL_PADDING + (color_index as i32 / SWATCHES_PER_ROW) * SWATCH_SIZE,
+        )
+    }
+
+    fn bar_pos(&self, channel: usize) -> (i32, i32) {
+        (
+            self.x + BAR_OFFSET_X,
+            self.bars_y + ROW_HEIGHT * channel as i32,
+        )
+    }
+
+    fn button_pos(&self, button_index: usize) -> (i32, i32) {
+        (
+            self.x + PANEL_PADDING + (BUTTON_WIDTH + FONT_WIDTH as i32 * 2) * button_index as i32,
+            self.buttons_y,
+        )
+    }
+}
+
+pub struct PaletteEditor {
+    pub visible: bool,
+    color_index: usize,
+}
+
+impl PaletteEditor {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            color_index: 0,
+        }
+    }
+}
+
+impl Pyxel {
+    pub(crate) fn update_palette_editor(&mut self) {
+        if !self.system.palette_editor.visible {
+            return;
+        }
+        let num_colors = self.palette_editor_num_colors();
+        let layout = self.palette_editor_layout(num_colors);
+        for i in 0..num_colors {
+            let (x, y) = layout.swatch_pos(i);
+            let hot_state = self.engine_hot_rect(
+                SWATCH_HOT_ID_BASE + i as u32,
+                x as f64,
+                y as f64,
+                SWATCH_SIZE as f64,
+                SWATCH_SIZE as f64,
+            );
+            if hot_state == HOT_CLICK {
+                self.system.palette_editor.color_index = i;
+            }
+        }
+        for channel in 0..CHANNEL_LABELS.len() {
+            let (x, y) = layout.bar_pos(channel);
+            let hot_state = self.engine_hot_rect(
+                BAR_HOT_ID_BASE + channel as u32,
+                x as f64,
+                y as f64,
+                BAR_WIDTH as f64,
+                FONT_HEIGHT as f64,
+            );
+            let value = self.palette_editor_value(channel) as i32;
+            let new_value = if hot_state == HOT_PRESS || hot_state == HOT_CLICK {
+                (self.mouse_x - x) * 0xff / (BAR_WIDTH - 1)
+            } else if hot_state == HOT_NONE {
+                value
+            } else {
+                value + self.mouse_wheel
+            };
+            if new_value != value {
+                self.set_palette_editor_value(channel, new_value.clamp(0, 0xff) as Rgb24);
+            }
+        }
+        for (i, label) in BUTTON_LABELS.iter().enumerate() {
+            let (x, y) = layout.button_pos(i);
+            let hot_state = self.engine_hot_rect(
+                BUTTON_HOT_ID_BASE + i as u32,
+                x as f64,
+                y as f64,
+                BUTTON_WIDTH as f64,
+                FONT_HEIGHT as f64,
+            );
+            if hot_state == HOT_CLICK {
+                let result = self.dump_palette_source(*label == "TOML");
+                self.print_error(result);
+            }
+        }
+    }
+
+    pub(crate) fn draw_palette_editor(&self) {
+        if !self.system.palette_editor.visible {
+            return;
+        }
+        let num_colors = self.palette_editor_num_colors();
+        let layout = self.palette_editor_layout(num_colors);
+        let color_index = self.system.palette_editor.color_index;
+        let values: Vec<Rgb24> = (0..CHANNEL_LABELS.len())
+            .map(|channel| self.palette_editor_value(channel))
+            .collect();
+        let hovered_buttons: Vec<bool> = (0..BUTTON_LABELS.len())
+            .map(|i| {
+                let (x, y) = layout.button_pos(i);
+                self.mouse_in(x as f64, y as f64, BUTTON_WIDTH as f64, FONT_HEIGHT as f64)
+            })
+            .collect();
+        self.draw_over_screen(|screen| {
+            let (x, y) = (layout.x as f64, layout.y as f64);
+            screen.rect(x, y, PANEL_WIDTH as f64, layout.height as f64, COLOR_BLACK);
+            screen.rectb(x, y, PANEL_WIDTH as f64, layout.height as f64, COLOR_WHITE);
+
+            for i in 0..num_colors {
+                let (x, y) = layout.swatch_pos(i);
+                let (x, y) = (x as f64, y as f64);
+                screen.rect(x, y, SWATCH_SIZE as f64, SWATCH_SIZE as f64, i as Color);
+                if i == color_index {
+                    screen.rectb(x, y, SWATCH_SIZE as f64, SWATCH_SIZE as f64, COLOR_WHITE);
+                    let inner_size = (SWATCH_SIZE - 2) as f64;
+                    screen.rectb(x + 1.0, y + 1.0, inner_size, inner_size, COLOR_BLACK);
+                }
+            }
+
+            for (channel, label) in CHANNEL_LABELS.iter().enumerate() {
+                let (x, y) = layout.bar_pos(channel);
+                let (x, y) = (x as f64, y as f64);
+                let value = values[channel];
+                let fill_width = (value as i32 * BAR_WIDTH / 0xff) as f64;
+                let label_x = (layout.x + PANEL_PADDING) as f64;
+                screen.text(label_x, y, label, COLOR_WHITE);
+                screen.rect(x, y, fill_width, FONT_HEIGHT as f64, COLOR_GRAY);
+                screen.rectb(x, y, BAR_WIDTH as f64, FONT_HEIGHT as f64, COLOR_WHITE);
+                screen.text(
+                    x + (BAR_WIDTH + 4) as f64,
+                    y,
+                    &format!("{value:02X}"),
+                    COLOR_WHITE,
+                );
+            }
+
+            for (i, label) in BUTTON_LABELS.iter().enumerate() {
+                let (x, y) = layout.button_pos(i);
+                let color = if hovered_buttons[i] {
+                    COLOR_YELLOW
+                } else {
+                    COLOR_WHITE
+                };
+                screen.text(x as f64, y as f64, label, color);
+            }
+        });
+    }
+
+    fn palette_editor_num_colors(&self) -> usize {
+        min(self.colors.lock().len(), NUM_COLORS as usize)
+    }
+
+    fn palette_editor_layout(&self, num_colors: usize) -> PanelLayout {
+        let num_rows = (num_colors as i32 + SWATCHES_PER_ROW - 1) / SWATCHES_PER_ROW;
+        let bars_offset_y = PANEL_PADDING + num_rows * SWATCH_SIZE + PANEL_PADDING;
+        let buttons_offset_y = bars_offset_y + ROW_HEIGHT * CHANNEL_LABELS.len() as i32;
+        let height = buttons_offset_y + FONT_HEIGHT as i32 + PANEL_PADDING;
+        let x = (self.width as i32 - PANEL_WIDTH) / 2;
+        let y = (self.height as i32 - height) / 2;
+        PanelLayout {
+            x,
+            y,
+            height,
+            bars_y: y + bars_offset_y,
+            buttons_y: y + buttons_offset_y,
+        }
+    }
+
+    fn palette_editor_value(&self, channel: usize) -> Rgb24 {
+        let colors = self.colors.lock();
+        let Some(last_index) = colors.len().checked_sub(1) else {
+            return 0;
+        };
+        let color_index = min(self.system.palette_editor.color_index, last_index);
+        (colors[color_index] >> (16 - channel * 8)) & 0xff
+    }
+
+    fn set_palette_editor_value(&mut self, channel: usize, value: Rgb24) {
+        let mut colors = self.colors.lock();
+        let Some(last_index) = colors.len().checked_sub(1) else {
+            return;
+        };
+        let color_index = min(self.system.palette_editor.color_index, last_index);
+        let shift = 16 - channel * 8;
+        colors[color_index] = (colors[color_index] & !(0xff << shift)) | (value << shift);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pyxel::headless_pyxel;
+
+    #[test]
+    fn test_palette_editor_value() {
+        let mut pyxel = headless_pyxel(128, 128);
+        let colors = pyxel.colors.lock().clone();
+        *pyxel.colors.lock() = vec![0x123456];
+        pyxel.system.palette_editor.color_index = 5;
+        assert_eq!(pyxel.palette_editor_value(0), 0x12);
+        pyxel.set_palette_editor_value(2, 0xff);
+        assert_eq!(*pyxel.colors.lock(), [0x1234ff]);
+
+        // An empty palette has nothing to edit but must not panic
+        pyxel.colors.lock().clear();
+        assert_eq!(pyxel.palette_editor_value(1), 0);
+        pyxel.set_palette_editor_value(1, 0xff);
+        pyxel.system.palette_editor.visible = true;
+        pyxel.update_palette_editor();
+        pyxel.draw_palette_editor();
+        *pyxel.colors.lock() = colors;
+    }
+}
@@ -0,0 +1,294 @@
+use std::cmp::{max, min};
+
+use crate::image::{Image, SharedImage};
+use crate::keys::{
+    Key, GAMEPAD1_BUTTON_A, GAMEPAD1_BUTTON_B, GAMEPAD1_BUTTON_DPAD_DOWN,
+    GAMEPAD1_BUTTON_DPAD_LEFT, GAMEPAD1_BUTTON_DPAD_RIGHT, GAMEPAD1_BUTTON_DPAD_UP, KEY_DOWN,
+    KEY_LEFT, KEY_RETURN, KEY_RIGHT, KEY_SPACE, KEY_UP,
+};
+use crate::pyxel::Pyxel;
+use crate::settings::{
+    COLOR_BLACK, COLOR_WHITE, COLOR_YELLOW, FONT_HEIGHT, FONT_WIDTH, HOT_CLICK, HOT_NONE,
+    PAUSE_MENU_MAX_SCALE, PAUSE_MENU_VOLUME_STEPS,
+};
+use crate::system::PyxelCallback;
+
+const MENU_ITEMS: [MenuItem; 5] = [
+    MenuItem::Resume,
+    MenuItem::Volume,
+    MenuItem::Scale,
+    MenuItem::Fullscreen,
+    MenuItem::Quit,
+];
+const LABEL_LENGTH: u32 = 14;
+const ITEM_HEIGHT: u32 = FONT_HEIGHT + 2;
+const MENU_PADDING: u32 = 4;
+const MENU_WIDTH: u32 = (LABEL_LENGTH + 2) * FONT_WIDTH + MENU_PADDING * 2;
+const MENU_HEIGHT: u32 = MENU_ITEMS.len() as u32 * ITEM_HEIGHT + MENU_PADDING * 2 - 2;
+// Engine hot ids live apart from the ids games pass to hot_rect, so this range can't collide
+// with them, and it sits above the editor ids that start at u32::MAX - 0x200
+const HOT_ID_BASE: u32 = u32::MAX - MENU_ITEMS.len() as u32;
+
+#[derive(Copy, Clone)]
+enum MenuItem {
+    Resume,
+    Volume,
+    Scale,
+    Fullscreen,
+    Quit,
+}
+
+pub struct PauseMenu {
+    pub key: Key,
+    is_open: bool,
+    cursor: usize,
+    saved_screen: Option<SharedImage>,
+}
+
+impl PauseMenu {
+    pub fn new(key: Key) -> Self {
+        Self {
+            key,
+            is_open: false,
+            cursor: 0,
+            saved_screen: None,
+        }
+    }
+
+    pub const fn is_open(&self) -> bool {
+        self.is_open
+    }
+}
+
+impl Pyxel {
+    pub fn pause_key(&mut self, key: Key) {
+        self.system.pause_menu.key = key;
+    }
+
+    pub(crate) fn update_pause_menu(
+        &mut self,
+        mut callback: Option<&mut (dyn PyxelCallback + '_)>,
+    ) -> bool {
+        if self.btnp(self.system.pause_menu.key, None, None) {
+            if self.system.pause_menu.is_open {
+                self.close_pause_menu();
+            } else {
+                self.open_pause_menu();
+            }
+            return self.system.pause_menu.is_open;
+        }
+        if !self.system.pause_menu.is_open {
+            return false;
+        }
+        if self.btnp(GAMEPAD1_BUTTON_B, None, None) {
+            self.close_pause_menu();
+            return false;
+        }
+
+        let num_items = MENU_ITEMS.len();
+        let cursor = self.system.pause_menu.cursor;
+        if self.btnp(KEY_UP, None, None) || self.btnp(GAMEPAD1_BUTTON_DPAD_UP, None, None) {
+            self.system.pause_menu.cursor = (cursor + num_items - 1) % num_items;
+        } else if self.btnp(KEY_DOWN, None, None)
+            || self.btnp(GAMEPAD1_BUTTON_DPAD_DOWN, None, None)
+        {
+            self.system.pause_menu.cursor = (cursor + 1) % num_items;
+        } else if self.btnp(KEY_LEFT, None, None)
+            || self.btnp(GAMEPAD1_BUTTON_DPAD_LEFT, None, None)
+        {
+            self.change_pause_menu_item(MENU_ITEMS[cursor], -1);
+        } else if self.btnp(KEY_RIGHT, None, None)
+            || self.btnp(GAMEPAD1_BUTTON_DPAD_RIGHT, None, None)
+        {
+            self.change_pause_menu_item(MENU_ITEMS[cursor], 1);
+        } else if self.btnp(KEY_RETURN, None, None)
+            || self.btnp(KEY_SPACE, None, None)
+            || self.btnp(GAMEPAD1_BUTTON_A, None, None)
+        {
+            self.select_pause_menu_item(MENU_ITEMS[cursor], callback.as_deref_mut());
+        }
+
+        let (menu_x, menu_y) = self.pause_menu_pos();
+        for (i, item) in MENU_ITEMS.iter().enumerate() {
+            let hot_state = self.engine_hot_rect(
+                HOT_ID_BASE + i as u32,
+                menu_x as f64,
+                (menu_y + (MENU_PADDING + ITEM_HEIGHT * i as u32) as i32 - 1) as f64,
+                MENU_WIDTH as f64,
+                ITEM_HEIGHT as f64,
+            );
+            if hot_state != HOT_NONE {
+                self.system.pause_menu.cursor = i;
+            }
+            if hot_state == HOT_CLICK {
+                self.select_pause_menu_item(*item, callback.as_deref_mut());
+            }
+        }
+        self.system.pause_menu.is_open
+    }
+
+    pub(crate) fn draw_pause_menu(&self) {
+        let Some(saved_screen) = self.system.pause_menu.saved_screen.clone() else {
+            return;
+        };
+        let labels: Vec<String> = MENU_ITEMS
+            .iter()
+            .map(|item| self.pause_menu_label(*item))
+            .collect();
+        let (menu_x, menu_y) = self.pause_menu_pos();
+        let cursor = self.system.pause_menu.cursor;
+        self.draw_over_screen(|screen| {
+            screen.blt(
+                0.0,
+                0.0,
+                saved_screen,
+                0.0,
+                0.0,
+                self.width as f64,
+                self.height as f64,
+                None,
+            );
+            let (x, y) = (menu_x as f64, menu_y as f64);
+            screen.rect(x, y, MENU_WIDTH as f64, MENU_HEIGHT as f64, COLOR_BLACK);
+            screen.rectb(x, y, MENU_WIDTH as f64, MENU_HEIGHT as f64, COLOR_WHITE);
+            for (i, label) in labels.iter().enumerate() {
+                let text_x = x + MENU_PADDING as f64;
+                let text_y = y + (MENU_PADDING + ITEM_HEIGHT * i as u32) as f64;
+                let color = if i == cursor {
+                    screen.text(text_x, text_y, ">", COLOR_YELLOW);
+                    COLOR_YELLOW
+                } else {
+                    COLOR_WHITE
+                };
+                screen.text(text_x + (FONT_WIDTH * 2) as f64, text_y, label, color);
+            }
+        });
+    }
+
+    fn open_pause_menu(&mut self) {
+        let saved_screen = Image::new(self.width, self.height);
+        saved_screen.lock().blt(
+            0.0,
+            0.0,
+            self.screen.clone(),
+            0.0,
+            0.0,
+            self.width as f64,
+            self.height as f64,
+            None,
+        );
+        self.system.pause_menu.saved_screen = Some(saved_screen);
+        self.system.pause_menu.cursor = 0;
+        self.system.pause_menu.is_open = true;
+    }
+
+    fn close_pause_menu(&mut self) {
+        if let Some(saved_screen) = self.system.pause_menu.saved_screen.take() {
+            self.draw_over_screen(|screen| {
+                screen.blt(
+                    0.0,
+                    0.0,
+                    saved_screen,
+                    0.0,
+                    0.0,
+                    self.width as f64,
+                    self.height as f64,
+                    None,
+                );
+            });
+        }
+        self.system.pause_menu.is_open = false;
+    }
+
+    fn select_pause_menu_item(
+        &mut self,
+        item: MenuItem,
+        callback: Option<&mut (dyn PyxelCallback + '_)>,
+    ) {
+        match item {
+            MenuItem::Resume => self.close_pause_menu(),
+            MenuItem::Volume => {
+                let volume_level = self.pause_menu_volume_level();
+                let volume_level = if volume_level >= PAUSE_MENU_VOLUME_STEPS {
+                    0
+                } else {
+                    volume_level + 1
+                };
+                self.set_master_volume(volume_level as f64 / PAUSE_MENU_VOLUME_STEPS as f64);
+            }
+            MenuItem::Scale => {
+                let scale = self.pause_menu_scale();
+                self.set_pause_menu_scale(if scale >= PAUSE_MENU_MAX_SCALE {
+                    1
+                } else {
+                    scale + 1
+                });
+            }
+            MenuItem::Fullscreen => self.fullscreen(!pyxel_platform::is_fullscreen(), None),
+            MenuItem::Quit => self.request_quit(callback),
+        }
+    }
+
+    fn change_pause_menu_item(&mut self, item: MenuItem, delta: i32) {
+        match item {
+            MenuItem::Volume => {
+                let volume_level = (self.pause_menu_volume_level() as i32 + delta)
+                    .clamp(0, PAUSE_MENU_VOLUME_STEPS as i32);
+                self.set_master_volume(volume_level as f64 / PAUSE_MENU_VOLUME_STEPS as f64);
+            }
+            MenuItem::Scale => {
+                let scale =
+                    (self.pause_menu_scale() as i32 + delta).clamp(1, PAUSE_MENU_MAX_SCALE as i32);
+                self.set_pause_menu_scale(scale as u32);
+            }
+            MenuItem::Fullscreen => self.fullscreen(!pyxel_platform::is_fullscreen(), None),
+            MenuItem::Resume | MenuItem::Quit => {}
+        }
+    }
+
+    fn pause_menu_label(&self, item: MenuItem) -> String {
+        let (name, value) = match item {
+            MenuItem::Resume => ("RESUME", String::new()),
+            MenuItem::Volume => ("VOLUME", self.pause_menu_volume_level().to_string()),
+            MenuItem::Scale => ("SCALE", self.pause_menu_scale().to_string()),
+            MenuItem::Fullscreen => (
+                "FULLSCREEN",
+                if pyxel_platform::is_fullscreen() {
+                    "ON"
+                } else {
+                    "OFF"
+                }
+                .to_string(),
+            ),
+            MenuItem::Quit => ("QUIT", String::new()),
+        };
+        let value_length = LABEL_LENGTH as usize - name.len();
+        format!("{name}{value:>value_length$}")
+    }
+
+    fn pause_menu_pos(&self) -> (i32, i32) {
+        (
+            (self.width as i32 - MENU_WIDTH as i32) / 2,
+            (self.height as i32 - MENU_HEIGHT as i32) / 2,
+        )
+    }
+
+    fn pause_menu_volume_level(&self) -> u32 {
+        (self.master_volume() * PAUSE_MENU_VOLUME_STEPS as f64).round() as u32
+    }
+
+    fn pause_menu_scale(&self) -> u32 {
+        max(
+            min(
+                self.display_width / self.width,
+                self.display_height / self.height,
+            ),
+            1,
+        )
+    }
+
+    fn set_pause_menu_scale(&mut self, scale: u32) {
+        pyxel_platform::set_window_size(self.width * scale, self.height * scale);
+        self.update_screen_params();
+    }
+}
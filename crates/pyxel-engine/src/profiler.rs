@@ -1,3 +1,26 @@
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Default)]
+pub struct FrameStats {
+    pub average: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+#[derive(Clone, Default)]
+pub struct Perf {
+    pub fps: f64,
+    pub update: FrameStats,
+    pub draw: FrameStats,
+    pub frame: FrameStats,
+    pub histogram: Vec<u32>,
+    pub draw_calls: u32,
+    pub blit_pixels: u32,
+    pub slow_audio_callbacks: u32,
+}
+
 pub struct Profiler {
     num_measure_frames: u32,
     num_measured_frames: u32,
@@ -5,6 +28,7 @@ pub struct Profiler {
     total_time: u32,
     average_time: f64,
     average_fps: f64,
+    frame_times: VecDeque<u32>,
 }
 
 impl Profiler {
@@ -17,6 +41,7 @@ impl Profiler {
             total_time: 0,
             average_time: 0.0,
             average_fps: 0.0,
+            frame_times: VecDeque::with_capacity(num_measure_frames as usize),
         }
     }
 
@@ -35,7 +60,12 @@ impl Profiler {
     }
 
     pub fn end(&mut self, tick_count: u32) {
-        self.total_time += tick_count - self.start_time;
+        let frame_time = tick_count - self.start_time;
+        if self.frame_times.len() >= self.num_measure_frames as usize {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+        self.total_time += frame_time;
         self.num_measured_frames += 1;
         if self.num_measured_frames >= self.num_measure_frames {
             self.average_time = self.total_time as f64 / self.num_measured_frames as f64;
@@ -44,4 +74,76 @@ impl Profiler {
             self.total_time = 0;
         }
     }
+
+    pub fn stats(&self) -> FrameStats {
+        if self.frame_times.is_empty() {
+            return FrameStats::default();
+        }
+        let mut frame_times: Vec<u32> = self.frame_times.iter().copied().collect();
+        frame_times.sort_unstable();
+        let percentile = |rate: f64| {
+            frame_times[((frame_times.len() - 1) as f64 * rate).round() as usize] as f64
+        };
+        FrameStats {
+            average: frame_times.iter().sum::<u32>() as f64 / frame_times.len() as f64,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            max: percentile(1.0),
+        }
+    }
+
+    pub fn histogram(&self, num_bins: u32) -> Vec<u32> {
+        let mut histogram = vec![0; num_bins as usize];
+        if num_bins > 0 {
+            for frame_time in &self.frame_times {
+                histogram[(*frame_time).min(num_bins - 1) as usize] += 1;
+            }
+        }
+        histogram
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    fn profiler_with_times(num_measure_frames: u32, frame_times: &[u32]) -> Profiler {
+        let mut profiler = Profiler::new(num_measure_frames);
+        let mut tick_count = 0;
+        for frame_time in frame_times {
+            profiler.start(tick_count);
+            tick_count += frame_time;
+            profiler.end(tick_count);
+        }
+        profiler
+    }
+
+    #[test]
+    fn test_stats() {
+        let profiler = profiler_with_times(10, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        let stats = profiler.stats();
+        assert_eq!(stats.average, 5.5);
+        assert_eq!(stats.p50, 6.0);
+        assert_eq!(stats.p95, 10.0);
+        assert_eq!(stats.max, 10.0);
+        assert_eq!(profiler.average_time(), 5.5);
+    }
+
+    #[test]
+    fn test_stats_window() {
+        let profiler = profiler_with_times(3, &[100, 100, 1, 2, 3]);
+        let stats = profiler.stats();
+        assert_eq!(stats.average, 2.0);
+        assert_eq!(stats.max, 3.0);
+        assert_eq!(Profiler::new(3).stats().max, 0.0);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let profiler = profiler_with_times(10, &[0, 1, 1, 2, 50]);
+        assert_eq!(profiler.histogram(4), vec![1, 2, 1, 1]);
+        assert!(profiler.histogram(0).is_empty());
+    }
 }
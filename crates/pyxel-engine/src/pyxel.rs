@@ -5,6 +5,8 @@ use once_cell::sync::Lazy;
 
 use crate::audio::Audio;
 use crate::channel::{Channel, SharedChannel};
+use crate::error::{PyxelError, PyxelResult};
+use crate::font::Font;
 use crate::graphics::Graphics;
 use crate::image::{Image, Rgb24, SharedImage};
 use crate::input::Input;
@@ -12,37 +14,35 @@ use crate::keys::Key;
 use crate::math::Math;
 use crate::music::{Music, SharedMusic};
 use crate::resource::Resource;
+use crate::sample::{Sample, SharedSample};
+use crate::scene::SceneStack;
 use crate::settings::{
-    CURSOR_DATA, CURSOR_HEIGHT, CURSOR_WIDTH, DEFAULT_COLORS, DEFAULT_FPS, DEFAULT_QUIT_KEY,
-    DEFAULT_TITLE, DEFAULT_TONES, DISPLAY_RATIO, FONT_DATA, FONT_HEIGHT, FONT_WIDTH, ICON_COLKEY,
-    ICON_DATA, ICON_SCALE, IMAGE_SIZE, NUM_CHANNELS, NUM_FONT_ROWS, NUM_IMAGES, NUM_MUSICS,
-    NUM_SAMPLES, NUM_SOUNDS, NUM_TILEMAPS, NUM_TONES, SAMPLE_RATE, TILEMAP_SIZE,
+    CURSOR_DATA, CURSOR_HEIGHT, CURSOR_WIDTH, DEFAULT_COLORS, DEFAULT_FPS, DEFAULT_PAUSE_KEY,
+    DEFAULT_QUIT_KEY, DEFAULT_TITLE, DEFAULT_TONES, DEFAULT_VSYNC, DISPLAY_RATIO, FONT_DATA,
+    FONT_HEIGHT, FONT_WIDTH, ICON_COLKEY, ICON_DATA, ICON_SCALE, IMAGE_SIZE, NUM_CHANNELS,
+    NUM_FONT_ROWS, NUM_IMAGES, NUM_MUSICS, NUM_SAMPLES, NUM_SAMPLE_SLOTS, NUM_SOUNDS, NUM_TILEMAPS,
+    NUM_TONES, SAMPLE_RATE, TILEMAP_SIZE,
 };
 use crate::sound::{SharedSound, Sound};
 use crate::system::System;
 use crate::tilemap::{ImageSource, SharedTilemap, Tilemap};
 use crate::tone::{SharedTone, Tone};
+use crate::user_config::UserConfig;
 
 static IS_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
 pub static COLORS: Lazy<shared_type!(Vec<Rgb24>)> =
     Lazy::new(|| new_shared_type!(DEFAULT_COLORS.to_vec()));
 
-pub static IMAGES: Lazy<shared_type!(Vec<SharedImage>)> = Lazy::new(|| {
-    new_shared_type!((0..NUM_IMAGES)
-        .map(|_| Image::new(IMAGE_SIZE, IMAGE_SIZE))
-        .collect())
-});
+pub static IMAGES: Lazy<shared_type!(Vec<SharedImage>)> =
+    Lazy::new(|| new_shared_type!(default_images()));
 
-static TILEMAPS: Lazy<shared_type!(Vec<SharedTilemap>)> = Lazy::new(|| {
-    new_shared_type!((0..NUM_TILEMAPS)
-        .map(|_| Tilemap::new(TILEMAP_SIZE, TILEMAP_SIZE, ImageSource::Index(0)))
-        .collect())
-});
+static TILEMAPS: Lazy<shared_type!(Vec<SharedTilemap>)> =
+    Lazy::new(|| new_shared_type!(default_tilemaps()));
 
-static CURSOR_IMAGE: Lazy<SharedImage> = Lazy::new(|| {
+pub static CURSOR_IMAGE: Lazy<SharedImage> = Lazy::new(|| {
     let image = Image::new(CURSOR_WIDTH, CURSOR_HEIGHT);
-    image.lock().set(0, 0, &CURSOR_DATA);
+    image.lock().set(0, 0, &CURSOR_DATA).unwrap();
     image
 });
 
@@ -70,11 +70,41 @@ pub static FONT_IMAGE: Lazy<SharedImage> = Lazy::new(|| {
     image
 });
 
+pub static FALLBACK_FONT: Lazy<shared_type!(Option<Font>)> = Lazy::new(|| new_shared_type!(None));
+
 pub static CHANNELS: Lazy<shared_type!(Vec<SharedChannel>)> =
-    Lazy::new(|| new_shared_type!((0..NUM_CHANNELS).map(|_| Channel::new()).collect()));
+    Lazy::new(|| new_shared_type!(default_channels()));
+
+pub static TONES: Lazy<shared_type!(Vec<SharedTone>)> =
+    Lazy::new(|| new_shared_type!(default_tones()));
+
+pub static SAMPLES: Lazy<shared_type!(Vec<SharedSample>)> =
+    Lazy::new(|| new_shared_type!(default_samples()));
+
+pub static SOUNDS: Lazy<shared_type!(Vec<SharedSound>)> =
+    Lazy::new(|| new_shared_type!(default_sounds()));
+
+static MUSICS: Lazy<shared_type!(Vec<SharedMusic>)> =
+    Lazy::new(|| new_shared_type!(default_musics()));
+
+fn default_images() -> Vec<SharedImage> {
+    (0..NUM_IMAGES)
+        .map(|_| Image::new(IMAGE_SIZE, IMAGE_SIZE))
+        .collect()
+}
+
+fn default_tilemaps() -> Vec<SharedTilemap> {
+    (0..NUM_TILEMAPS)
+        .map(|_| Tilemap::new(TILEMAP_SIZE, TILEMAP_SIZE, ImageSource::Index(0)))
+        .collect()
+}
+
+fn default_channels() -> Vec<SharedChannel> {
+    (0..NUM_CHANNELS).map(|_| Channel::new()).collect()
+}
 
-pub static TONES: Lazy<shared_type!(Vec<SharedTone>)> = Lazy::new(|| {
-    new_shared_type!((0..NUM_TONES)
+fn default_tones() -> Vec<SharedTone> {
+    (0..NUM_TONES)
         .map(|index| {
             let tone = Tone::new();
             {
@@ -85,21 +115,45 @@ pub static TONES: Lazy<shared_type!(Vec<SharedTone>)> = Lazy::new(|| {
             }
             tone
         })
-        .collect())
-});
+        .collect()
+}
+
+fn default_samples() -> Vec<SharedSample> {
+    (0..NUM_SAMPLE_SLOTS).map(|_| Sample::new()).collect()
+}
 
-static SOUNDS: Lazy<shared_type!(Vec<SharedSound>)> =
-    Lazy::new(|| new_shared_type!((0..NUM_SOUNDS).map(|_| Sound::new()).collect()));
+fn default_sounds() -> Vec<SharedSound> {
+    (0..NUM_SOUNDS).map(|_| Sound::new()).collect()
+}
 
-static MUSICS: Lazy<shared_type!(Vec<SharedMusic>)> =
-    Lazy::new(|| new_shared_type!((0..NUM_MUSICS).map(|_| Music::new()).collect()));
+fn default_musics() -> Vec<SharedMusic> {
+    (0..NUM_MUSICS).map(|_| Music::new()).collect()
+}
+
+// The banks are process globals, so a new Pyxel would otherwise inherit the previous one's data.
+// The contents are replaced in place because the audio thread shares these locks.
+fn reset_globals() {
+    *COLORS.lock() = DEFAULT_COLORS.to_vec();
+    *IMAGES.lock() = default_images();
+    *TILEMAPS.lock() = default_tilemaps();
+    *FALLBACK_FONT.lock() = None;
+    *CHANNELS.lock() = default_channels();
+    *TONES.lock() = default_tones();
+    *SAMPLES.lock() = default_samples();
+    *SOUNDS.lock() = default_sounds();
+    *MUSICS.lock() = default_musics();
+    Audio::reset();
+}
 
 pub struct Pyxel {
     // System
     pub(crate) system: System,
     pub width: u32,
     pub height: u32,
+    pub display_width: u32,
+    pub display_height: u32,
     pub frame_count: u32,
+    pub scenes: SceneStack,
 
     // Resource
     pub(crate) resource: Resource,
@@ -110,6 +164,8 @@ pub struct Pyxel {
     pub mouse_y: i32,
     pub mouse_wheel: i32,
     pub input_text: String,
+    pub input_text_composition: String,
+    pub input_text_cursor: u32,
     pub dropped_files: Vec<String>,
 
     // Graphics
@@ -124,6 +180,7 @@ pub struct Pyxel {
     // Audio
     pub channels: shared_type!(Vec<SharedChannel>),
     pub tones: shared_type!(Vec<SharedTone>),
+    pub samples: shared_type!(Vec<SharedSample>),
     pub sounds: shared_type!(Vec<SharedSound>),
     pub musics: shared_type!(Vec<SharedMusic>),
 
@@ -131,6 +188,7 @@ pub struct Pyxel {
     pub(crate) math: Math,
 }
 
+// Newer options are only set through Pyxel::builder, so this signature stays as it is
 pub fn init(
     width: u32,
     height: u32,
@@ -140,93 +198,370 @@ pub fn init(
     display_scale: Option<u32>,
     capture_scale: Option<u32>,
     capture_sec: Option<u32>,
-) -> Pyxel {
-    assert!(
-        !IS_INITIALIZED.swap(true, Ordering::Relaxed),
-        "Pyxel already initialized"
-    );
-
-    // Default parameters
-    let title = title.unwrap_or(DEFAULT_TITLE);
-    let quit_key = quit_key.unwrap_or(DEFAULT_QUIT_KEY);
-    let fps = fps.unwrap_or(DEFAULT_FPS);
-
-    // Platform
-    pyxel_platform::init(|display_width, display_height| {
-        let display_scale = max(
-            display_scale.map_or_else(
-                || {
-                    (f64::min(
-                        display_width as f64 / width as f64,
-                        display_height as f64 / height as f64,
-                    ) * DISPLAY_RATIO) as u32
-                },
-                |display_scale| display_scale,
-            ),
-            1,
+) -> PyxelResult<Pyxel> {
+    let mut builder = Pyxel::builder(width, height);
+    builder.title = title.map(ToString::to_string);
+    builder.fps = fps;
+    builder.quit_key = quit_key;
+    builder.display_scale = display_scale;
+    builder.capture_scale = capture_scale;
+    builder.capture_sec = capture_sec;
+    builder.build()
+}
+
+#[must_use]
+pub struct PyxelBuilder {
+    width: u32,
+    height: u32,
+    title: Option<String>,
+    fps: Option<u32>,
+    quit_key: Option<Key>,
+    display_scale: Option<u32>,
+    capture_scale: Option<u32>,
+    capture_sec: Option<u32>,
+    max_update_per_frame: Option<u32>,
+    max_elapsed_ms: Option<u32>,
+    resizable: Option<bool>,
+    scaling_mode: Option<u32>,
+    pause_key: Option<Key>,
+    vsync: Option<bool>,
+    headless: bool,
+}
+
+impl PyxelBuilder {
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn fps(mut self, fps: u32) -> Self {
+        self.fps = Some(fps);
+        self
+    }
+
+    pub fn quit_key(mut self, key: Key) -> Self {
+        self.quit_key = Some(key);
+        self
+    }
+
+    pub fn display_scale(mut self, scale: u32) -> Self {
+        self.display_scale = Some(scale);
+        self
+    }
+
+    pub fn capture_scale(mut self, scale: u32) -> Self {
+        self.capture_scale = Some(scale);
+        self
+    }
+
+    pub fn capture_sec(mut self, sec: u32) -> Self {
+        self.capture_sec = Some(sec);
+        self
+    }
+
+    pub fn max_update_per_frame(mut self, count: u32) -> Self {
+        self.max_update_per_frame = Some(count);
+        self
+    }
+
+    pub fn max_elapsed_ms(mut self, ms: u32) -> Self {
+        self.max_elapsed_ms = Some(ms);
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = Some(resizable);
+        self
+    }
+
+    pub fn scaling_mode(mut self, mode: u32) -> Self {
+        self.scaling_mode = Some(mode);
+        self
+    }
+
+    pub fn pause_key(mut self, key: Key) -> Self {
+        self.pause_key = Some(key);
+        self
+    }
+
+    pub fn vsync(mut self, enabled: bool) -> Self {
+        self.vsync = Some(enabled);
+        self
+    }
+
+    // Builds without a window or audio device, for tests and tools that only draw offscreen
+    pub fn headless(mut self) -> Self {
+        self.headless = true;
+        self
+    }
+
+    pub fn build(self) -> PyxelResult<Pyxel> {
+        let Self {
+            width,
+            height,
+            title,
+            fps,
+            quit_key,
+            display_scale,
+            capture_scale,
+            capture_sec,
+            max_update_per_frame,
+            max_elapsed_ms,
+            resizable,
+            scaling_mode,
+            pause_key,
+            vsync,
+            headless,
+        } = self;
+
+        if IS_INITIALIZED.swap(true, Ordering::Relaxed) {
+            return Err(PyxelError::AlreadyInitialized);
+        }
+        reset_globals();
+
+        // Player settings override the values given by the game, except in headless runs
+        let user_config = if headless {
+            UserConfig::default()
+        } else {
+            UserConfig::load()
+        };
+        let display_scale = user_config.display_scale.or(display_scale);
+        let scaling_mode = user_config.scaling_mode.or(scaling_mode);
+        let vsync = user_config.vsync.or(vsync);
+
+        // Default parameters
+        let title = title.as_deref().unwrap_or(DEFAULT_TITLE);
+        let quit_key = quit_key.unwrap_or(DEFAULT_QUIT_KEY);
+        let pause_key = pause_key.unwrap_or(DEFAULT_PAUSE_KEY);
+        let fps = fps.unwrap_or(DEFAULT_FPS);
+        let resizable = resizable.unwrap_or(true);
+        let vsync = vsync.unwrap_or(DEFAULT_VSYNC);
+
+        // Platform
+        let result = if headless {
+            Ok(())
+        } else {
+            pyxel_platform::init(|display_width, display_height| {
+                let display_scale = max(
+                    display_scale.map_or_else(
+                        || {
+                            (f64::min(
+                                display_width as f64 / width as f64,
+                                display_height as f64 / height as f64,
+                            ) * DISPLAY_RATIO) as u32
+                        },
+                        |display_scale| display_scale,
+                    ),
+                    1,
+                );
+                (title, width * display_scale, height * display_scale)
+            })
+        };
+        if let Err(message) = result {
+            IS_INITIALIZED.store(false, Ordering::Relaxed);
+            return Err(PyxelError::Platform(message));
+        }
+        if !headless {
+            pyxel_platform::set_window_resizable(resizable);
+            pyxel_platform::set_vsync(vsync);
+            if user_config.fullscreen == Some(true) {
+                pyxel_platform::set_fullscreen(true, false);
+            }
+        }
+
+        // System
+        let mut system = System::new(
+            fps,
+            quit_key,
+            pause_key,
+            max_update_per_frame,
+            max_elapsed_ms,
+            scaling_mode,
         );
-        (title, width * display_scale, height * display_scale)
-    });
+        system.is_headless = headless;
+        let (display_width, display_height) = if headless {
+            (width, height)
+        } else {
+            pyxel_platform::window_size()
+        };
+        let frame_count = 0;
+        let scenes = SceneStack::new();
 
-    // System
-    let system = System::new(fps, quit_key);
-    let frame_count = 0;
+        // Resource
+        let resource = Resource::new(title, capture_scale, capture_sec, fps);
 
-    // Resource
-    let resource = Resource::new(capture_scale, capture_sec, fps);
+        // Input
+        let input = Input::new();
+        let mouse_x = 0;
+        let mouse_y = 0;
+        let mouse_wheel = 0;
+        let input_text = String::new();
+        let input_text_composition = String::new();
+        let input_text_cursor = 0;
+        let dropped_files = Vec::new();
 
-    // Input
-    let input = Input::new();
-    let mouse_x = 0;
-    let mouse_y = 0;
-    let mouse_wheel = 0;
-    let input_text = String::new();
-    let dropped_files = Vec::new();
+        // Graphics
+        let graphics = if headless {
+            Graphics::new_headless()
+        } else {
+            Graphics::new()
+        };
+        let colors = COLORS.clone();
+        let images = IMAGES.clone();
+        let tilemaps = TILEMAPS.clone();
+        let screen = Image::new(width, height);
+        let cursor = CURSOR_IMAGE.clone();
+        let font = FONT_IMAGE.clone();
 
-    // Graphics
-    let graphics = Graphics::new();
-    let colors = COLORS.clone();
-    let images = IMAGES.clone();
-    let tilemaps = TILEMAPS.clone();
-    let screen = Image::new(width, height);
-    let cursor = CURSOR_IMAGE.clone();
-    let font = FONT_IMAGE.clone();
+        // Audio
+        if !headless {
+            let _ = Audio::new(SAMPLE_RATE, NUM_SAMPLES);
+        }
+        let channels = CHANNELS.clone();
+        let tones = TONES.clone();
+        let samples = SAMPLES.clone();
+        let sounds = SOUNDS.clone();
+        let musics = MUSICS.clone();
 
-    // Audio
-    let _ = Audio::new(SAMPLE_RATE, NUM_SAMPLES);
-    let channels = CHANNELS.clone();
-    let tones = TONES.clone();
-    let sounds = SOUNDS.clone();
-    let musics = MUSICS.clone();
+        // Math
+        let math = Math::new();
 
-    // Math
-    let math = Math::new();
-
-    let pyxel = Pyxel {
-        system,
-        width,
-        height,
-        frame_count,
-        resource,
-        input,
-        mouse_x,
-        mouse_y,
-        mouse_wheel,
-        input_text,
-        dropped_files,
-        graphics,
-        colors,
-        images,
-        tilemaps,
-        screen,
-        cursor,
-        font,
-        channels,
-        tones,
-        sounds,
-        musics,
-        math,
-    };
-    pyxel.icon(&ICON_DATA, ICON_SCALE, ICON_COLKEY);
-    pyxel
+        let pyxel = Pyxel {
+            system,
+            width,
+            height,
+            display_width,
+            display_height,
+            frame_count,
+            scenes,
+            resource,
+            input,
+            mouse_x,
+            mouse_y,
+            mouse_wheel,
+            input_text,
+            input_text_composition,
+            input_text_cursor,
+            dropped_files,
+            graphics,
+            colors,
+            images,
+            tilemaps,
+            screen,
+            cursor,
+            font,
+            channels,
+            tones,
+            samples,
+            sounds,
+            musics,
+            math,
+        };
+        if !headless {
+            pyxel.icon(&ICON_DATA, ICON_SCALE, ICON_COLKEY)?;
+        }
+        Ok(pyxel)
+    }
+}
+
+impl Pyxel {
+    pub fn builder(width: u32, height: u32) -> PyxelBuilder {
+        PyxelBuilder {
+            width,
+            height,
+            title: None,
+            fps: None,
+            quit_key: None,
+            display_scale: None,
+            capture_scale: None,
+            capture_sec: None,
+            max_update_per_frame: None,
+            max_elapsed_ms: None,
+            resizable: None,
+            scaling_mode: None,
+            pause_key: None,
+            vsync: None,
+            headless: false,
+        }
+    }
+}
+
+impl Drop for Pyxel {
+    fn drop(&mut self) {
+        Audio::stop();
+        if !self.system.is_headless {
+            pyxel_platform::shutdown();
+        }
+        self.stop_stream();
+        for channel in self.channels.lock().iter() {
+            channel.lock().stop();
+        }
+        IS_INITIALIZED.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+static HEADLESS_LOCK: parking_lot::Mutex<()> = parking_lot::Mutex::new(());
+
+// Only one Pyxel may exist at a time, so tests that need one take turns
+#[cfg(test)]
+pub(crate) struct HeadlessPyxel {
+    pyxel: Pyxel,
+    _guard: parking_lot::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+impl std::ops::Deref for HeadlessPyxel {
+    type Target = Pyxel;
+
+    fn deref(&self) -> &Pyxel {
+        &self.pyxel
+    }
+}
+
+#[cfg(test)]
+impl std::ops::DerefMut for HeadlessPyxel {
+    fn deref_mut(&mut self) -> &mut Pyxel {
+        &mut self.pyxel
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn headless_pyxel(width: u32, height: u32) -> HeadlessPyxel {
+    let guard = HEADLESS_LOCK.lock();
+    HeadlessPyxel {
+        pyxel: Pyxel::builder(width, height).headless().build().unwrap(),
+        _guard: guard,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::INITIAL_CHANNEL_GAIN;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_build_restores_defaults() {
+        {
+            let pyxel = headless_pyxel(16, 16);
+            pyxel.colors.lock()[0] = 0x123456;
+            pyxel.images.lock()[0].lock().pset(1, 2, 7);
+            pyxel.tilemaps.lock()[0].lock().pset(1, 2, (3, 4));
+            pyxel.sounds.lock()[0].lock().set("c2", "s", "5", "", 10);
+            pyxel.musics.lock()[0].lock().set(&[vec![0]]);
+            pyxel.channels.lock()[0].lock().gain = 0.0;
+            pyxel.set_master_volume(0.5);
+        }
+
+        let pyxel = headless_pyxel(16, 16);
+        assert_eq!(*pyxel.colors.lock(), DEFAULT_COLORS);
+        assert_eq!(pyxel.images.lock()[0].lock().pget(1, 2), 0);
+        assert_eq!(pyxel.tilemaps.lock()[0].lock().pget(1, 2), (0, 0));
+        assert!(pyxel.sounds.lock()[0].lock().notes.is_empty());
+        assert!(pyxel.musics.lock()[0].lock().seqs.is_empty());
+        assert_eq!(pyxel.channels.lock()[0].lock().gain, INITIAL_CHANNEL_GAIN);
+        assert_eq!(pyxel.master_volume(), 1.0);
+        assert_eq!(pyxel.slow_audio_callbacks(), 0);
+    }
 }
@@ -1,6 +1,6 @@
 use std::cmp::max;
-use std::fs::File;
-use std::io::{Read, Write};
+use std::fs::{self, File};
+use std::io::{Cursor, Read, Write};
 use std::path::{Path, PathBuf};
 
 use cfg_if::cfg_if;
@@ -8,26 +8,124 @@ use platform_dirs::UserDirs;
 use zip::write::FileOptions;
 use zip::{ZipArchive, ZipWriter};
 
-use crate::image::{Color, Image, Rgb24};
-use crate::pyxel::Pyxel;
+use crate::error::{PyxelError, PyxelResult};
+use crate::hot_reload::HotReload;
+use crate::image::{Color, Image, Rgb24, SharedImage};
+use crate::pyxel::{Pyxel, COLORS};
 use crate::resource_data::{ResourceData1, ResourceData3};
-use crate::screencast::Screencast;
-use crate::settings::{DEFAULT_CAPTURE_SCALE, DEFAULT_CAPTURE_SEC};
+use crate::resource_loader::PendingLoad;
+use crate::save_data::encode_save_name;
+use crate::scale_filter::ScaleFilter;
+use crate::screencast::{Screencast, ScreencastFormat};
+use crate::settings::{
+    DEFAULT_CAPTURE_NAME, DEFAULT_CAPTURE_SCALE, DEFAULT_CAPTURE_SEC, MAX_COLORS,
+    MAX_RESOURCE_TEXT_SIZE, RESOURCE_READ_CHUNK_SIZE,
+};
+use crate::utils::add_file_extension;
+use crate::vfs;
 use crate::{PALETTE_FILE_EXTENSION, RESOURCE_ARCHIVE_NAME, RESOURCE_FORMAT_VERSION};
 
+pub(crate) enum ResourceFile {
+    Old(ZipArchive<Cursor<Vec<u8>>>),
+    Version3(ResourceData3),
+    Version1(ResourceData1),
+}
+
 pub struct Resource {
     capture_scale: u32,
+    capture_filter: ScaleFilter,
     screencast: Screencast,
+    capture_dir: Option<PathBuf>,
+    capture_name: String,
+    screenshot_to_clipboard: bool,
+    // Some platforms drop the clipboard contents along with their owner, so it is kept alive
+    #[cfg(all(feature = "clipboard", not(target_os = "emscripten")))]
+    clipboard: Option<arboard::Clipboard>,
+    pub(crate) hot_reload: HotReload,
+    pub(crate) pending_loads: Vec<PendingLoad>,
+    pub(crate) save_name: String,
 }
 
 impl Resource {
-    pub fn new(capture_scale: Option<u32>, capture_sec: Option<u32>, fps: u32) -> Self {
+    pub fn new(
+        title: &str,
+        capture_scale: Option<u32>,
+        capture_sec: Option<u32>,
+        fps: u32,
+    ) -> Self {
         let capture_scale = capture_scale.unwrap_or(DEFAULT_CAPTURE_SCALE);
         let capture_sec = capture_sec.unwrap_or(DEFAULT_CAPTURE_SEC);
         Self {
             capture_scale: max(capture_scale, 1),
+            capture_filter: ScaleFilter::Nearest,
             screencast: Screencast::new(fps, capture_sec),
+            capture_dir: None,
+            capture_name: DEFAULT_CAPTURE_NAME.to_string(),
+            screenshot_to_clipboard: false,
+            #[cfg(all(feature = "clipboard", not(target_os = "emscripten")))]
+            clipboard: None,
+            hot_reload: HotReload::new(),
+            pending_loads: Vec::new(),
+            save_name: encode_save_name(title),
+        }
+    }
+
+    // The name template may contain {datetime} and {frame}, which are replaced on each capture
+    pub fn set_capture_dir(&mut self, dir: Option<&str>, name_template: Option<&str>) {
+        self.capture_dir = dir.map(PathBuf::from);
+        self.capture_name = name_template.unwrap_or(DEFAULT_CAPTURE_NAME).to_string();
+    }
+
+    fn capture_filename(&self, datetime: &str, frame_count: u32) -> PyxelResult<String> {
+        let basename = self
+            .capture_name
+            .replace("{datetime}", datetime)
+            .replace("{frame}", &frame_count.to_string());
+        let Some(capture_dir) = &self.capture_dir else {
+            return Ok(Pyxel::prepend_desktop_path(&basename));
+        };
+        fs::create_dir_all(capture_dir)
+            .map_err(|_| PyxelError::FileWrite(capture_dir.to_string_lossy().to_string()))?;
+        Ok(capture_dir.join(basename).to_string_lossy().to_string())
+    }
+
+    // Frames are written in order, one per 1/fps seconds, using the current palette
+    pub fn export_gif(
+        frames: &[SharedImage],
+        filename: &str,
+        fps: u32,
+        scale: u32,
+    ) -> PyxelResult<()> {
+        let Some(first_frame) = frames.first() else {
+            return Err(PyxelError::InvalidArgument(
+                "No frames to export".to_string(),
+            ));
+        };
+        let filename = add_file_extension(filename, ".gif");
+        let (width, height) = (first_frame.width(), first_frame.height());
+        let colors = COLORS.lock().clone();
+        let mut screencast = Screencast::with_max_screens(max(fps, 1), frames.len() as u32);
+        for (frame_count, frame) in frames.iter().enumerate() {
+            let image = frame.read();
+            if image.width() != width || image.height() != height {
+                return Err(PyxelError::InvalidArgument(format!(
+                    "Frame {frame_count} is {}x{}, but the first frame is {width}x{height}",
+                    image.width(),
+                    image.height()
+                )));
+            }
+            screencast.capture(
+                width,
+                height,
+                &image.canvas.data,
+                &colors,
+                frame_count as u32,
+            );
         }
+        screencast.save(&filename, max(scale, 1), ScaleFilter::Nearest)?;
+        #[cfg(target_os = "emscripten")]
+        pyxel_platform::emscripten::save_file(&filename);
+        Ok(())
     }
 }
 
@@ -42,68 +140,163 @@ impl Pyxel {
         include_colors: Option<bool>,
         include_channels: Option<bool>,
         include_tones: Option<bool>,
-    ) {
-        let mut archive = ZipArchive::new(
-            File::open(Path::new(&filename))
-                .unwrap_or_else(|_| panic!("Failed to open file '{filename}'")),
+    ) -> PyxelResult<()> {
+        self.watch_resource_file(
+            filename,
+            exclude_images,
+            exclude_tilemaps,
+            exclude_sounds,
+            exclude_musics,
+            include_colors,
+            include_channels,
+            include_tones,
+        );
+        let resource_file = Self::read_resource_file(filename, |_| {})?;
+        self.apply_resource_file(
+            filename,
+            resource_file,
+            exclude_images,
+            exclude_tilemaps,
+            exclude_sounds,
+            exclude_musics,
+            include_colors,
+            include_channels,
+            include_tones,
         )
-        .unwrap();
+    }
+
+    // Only reads and decodes the file without touching the runtime, so it can run on any thread
+    pub(crate) fn read_resource_file(
+        filename: &str,
+        on_progress: impl FnMut(f64),
+    ) -> PyxelResult<ResourceFile> {
+        let data = match vfs::read_mounted_file(filename) {
+            Some(data) => {
+                log::debug!("Read resource file '{filename}' from a mounted file system");
+                data
+            }
+            None => {
+                log::debug!("Reading resource file '{filename}'");
+                Self::read_file_in_chunks(filename, on_progress)?
+            }
+        };
+        Self::decode_resource_file(filename, data)
+    }
+
+    // Rejects malformed data with an error instead of panicking, since resource files may come from anywhere
+    pub(crate) fn decode_resource_file(filename: &str, data: Vec<u8>) -> PyxelResult<ResourceFile> {
+        let invalid_file = || PyxelError::InvalidFile(filename.to_string());
+        let mut archive = ZipArchive::new(Cursor::new(data)).map_err(|_| invalid_file())?;
 
         // Old resource file
         if archive.by_name("pyxel_resource/version").is_ok() {
-            Self::warn_format_version(filename);
-            self.load_old_resource(
-                &mut archive,
-                filename,
-                !exclude_images.unwrap_or(false),
-                !exclude_tilemaps.unwrap_or(false),
-                !exclude_sounds.unwrap_or(false),
-                !exclude_musics.unwrap_or(false),
-            );
-            self.load_pyxel_palette_file(filename);
-            return;
+            return Ok(ResourceFile::Old(archive));
         }
 
         // New resource file
-        let mut file = archive.by_name(RESOURCE_ARCHIVE_NAME).unwrap();
+        let file = archive
+            .by_name(RESOURCE_ARCHIVE_NAME)
+            .map_err(|_| invalid_file())?;
         let mut toml_text = String::new();
-        file.read_to_string(&mut toml_text).unwrap();
-        let format_version = Self::parse_format_version(&toml_text);
+        file.take(MAX_RESOURCE_TEXT_SIZE)
+            .read_to_string(&mut toml_text)
+            .map_err(|_| invalid_file())?;
+        let format_version = Self::parse_format_version(&toml_text).ok_or_else(invalid_file)?;
         if format_version < RESOURCE_FORMAT_VERSION {
             Self::warn_format_version(filename);
         }
         if format_version == 3 || format_version == 2 {
-            let resource_data = ResourceData3::from_toml(&toml_text);
-            resource_data.to_runtime(
-                self,
-                exclude_images.unwrap_or(false),
-                exclude_tilemaps.unwrap_or(false),
-                exclude_sounds.unwrap_or(false),
-                exclude_musics.unwrap_or(false),
-                include_colors.unwrap_or(false),
-                include_channels.unwrap_or(false),
-                include_tones.unwrap_or(false),
-            );
-            self.load_pyxel_palette_file(filename);
+            let resource_data = ResourceData3::from_toml(&toml_text)
+                .ok()
+                .filter(ResourceData3::is_valid)
+                .ok_or_else(invalid_file)?;
+            Ok(ResourceFile::Version3(resource_data))
         } else if format_version == 1 {
-            let resource_data = ResourceData1::from_toml(&toml_text);
-            resource_data.to_runtime(
-                self,
-                exclude_images.unwrap_or(false),
-                exclude_tilemaps.unwrap_or(false),
-                exclude_sounds.unwrap_or(false),
-                exclude_musics.unwrap_or(false),
-                include_colors.unwrap_or(false),
-                include_channels.unwrap_or(false),
-                include_tones.unwrap_or(false),
-            );
-            self.load_pyxel_palette_file(filename);
+            let resource_data = ResourceData1::from_toml(&toml_text)
+                .ok()
+                .filter(ResourceData1::is_valid)
+                .ok_or_else(invalid_file)?;
+            Ok(ResourceFile::Version1(resource_data))
         } else {
-            assert!(
-                format_version <= RESOURCE_FORMAT_VERSION,
-                "Unknown resource file version"
-            );
+            Err(invalid_file())
+        }
+    }
+
+    fn read_file_in_chunks(
+        filename: &str,
+        mut on_progress: impl FnMut(f64),
+    ) -> PyxelResult<Vec<u8>> {
+        let mut file = File::open(Path::new(&filename))
+            .map_err(|_| PyxelError::FileOpen(filename.to_string()))?;
+        let file_size = file.metadata().map_or(0, |metadata| metadata.len());
+        let mut data = Vec::new();
+        let mut chunk = vec![0; RESOURCE_READ_CHUNK_SIZE];
+        loop {
+            let size = file
+                .read(&mut chunk)
+                .map_err(|_| PyxelError::FileOpen(filename.to_string()))?;
+            if size == 0 {
+                break;
+            }
+            data.extend_from_slice(&chunk[..size]);
+            if file_size > 0 {
+                on_progress(data.len() as f64 / file_size as f64);
+            }
+        }
+        Ok(data)
+    }
+
+    pub(crate) fn apply_resource_file(
+        &mut self,
+        filename: &str,
+        resource_file: ResourceFile,
+        exclude_images: Option<bool>,
+        exclude_tilemaps: Option<bool>,
+        exclude_sounds: Option<bool>,
+        exclude_musics: Option<bool>,
+        include_colors: Option<bool>,
+        include_channels: Option<bool>,
+        include_tones: Option<bool>,
+    ) -> PyxelResult<()> {
+        match resource_file {
+            ResourceFile::Old(mut archive) => {
+                Self::warn_format_version(filename);
+                self.load_old_resource(
+                    &mut archive,
+                    filename,
+                    !exclude_images.unwrap_or(false),
+                    !exclude_tilemaps.unwrap_or(false),
+                    !exclude_sounds.unwrap_or(false),
+                    !exclude_musics.unwrap_or(false),
+                )?;
+            }
+            ResourceFile::Version3(resource_data) => {
+                resource_data.to_runtime(
+                    self,
+                    exclude_images.unwrap_or(false),
+                    exclude_tilemaps.unwrap_or(false),
+                    exclude_sounds.unwrap_or(false),
+                    exclude_musics.unwrap_or(false),
+                    include_colors.unwrap_or(false),
+                    include_channels.unwrap_or(false),
+                    include_tones.unwrap_or(false),
+                );
+            }
+            ResourceFile::Version1(resource_data) => {
+                resource_data.to_runtime(
+                    self,
+                    exclude_images.unwrap_or(false),
+                    exclude_tilemaps.unwrap_or(false),
+                    exclude_sounds.unwrap_or(false),
+                    exclude_musics.unwrap_or(false),
+                    include_colors.unwrap_or(false),
+                    include_channels.unwrap_or(false),
+                    include_tones.unwrap_or(false),
+                );
+            }
         }
+        self.load_pyxel_palette_file(filename);
+        Ok(())
     }
 
     pub fn save(
@@ -116,7 +309,7 @@ impl Pyxel {
         include_colors: Option<bool>,
         include_channels: Option<bool>,
         include_tones: Option<bool>,
-    ) {
+    ) -> PyxelResult<()> {
         let toml_text = ResourceData3::from_runtime(self).to_toml(
             exclude_images.unwrap_or(false),
             exclude_tilemaps.unwrap_or(false),
@@ -126,32 +319,84 @@ impl Pyxel {
             include_channels.unwrap_or(false),
             include_tones.unwrap_or(false),
         );
-        let path = std::path::Path::new(&filename);
-        let file = std::fs::File::create(path)
-            .unwrap_or_else(|_| panic!("Failed to open file '{filename}'"));
+        let write_error = || PyxelError::FileWrite(filename.to_string());
+        let file = File::create(Path::new(&filename)).map_err(|_| write_error())?;
         let mut zip = ZipWriter::new(file);
         zip.start_file(RESOURCE_ARCHIVE_NAME, FileOptions::default())
-            .unwrap();
-        zip.write_all(toml_text.as_bytes()).unwrap();
-        zip.finish().unwrap();
+            .map_err(|_| write_error())?;
+        zip.write_all(toml_text.as_bytes())
+            .map_err(|_| write_error())?;
+        zip.finish().map_err(|_| write_error())?;
+        log::debug!("Saved resource file '{filename}'");
         #[cfg(target_os = "emscripten")]
         pyxel_platform::emscripten::save_file(filename);
+        Ok(())
     }
 
-    pub fn screenshot(&mut self, scale: Option<u32>) {
-        let filename = Self::prepend_desktop_path(&format!("pyxel-{}", Self::datetime_string()));
+    pub fn screenshot(&mut self, scale: Option<u32>) -> PyxelResult<()> {
         let scale = max(scale.unwrap_or(self.resource.capture_scale), 1);
-        self.screen.lock().save(&filename, scale);
+        if self.resource.screenshot_to_clipboard {
+            return self.copy_screen_to_clipboard(scale);
+        }
+        let filename = self.capture_filename()?;
+        self.screen
+            .read()
+            .save_with_filter(&filename, scale, self.resource.capture_filter)?;
         #[cfg(target_os = "emscripten")]
         pyxel_platform::emscripten::save_file(&(filename + ".png"));
+        Ok(())
     }
 
-    pub fn screencast(&mut self, scale: Option<u32>) {
-        let filename = Self::prepend_desktop_path(&format!("pyxel-{}", Self::datetime_string()));
+    pub fn screencast(&mut self, scale: Option<u32>) -> PyxelResult<()> {
+        let filename = self.capture_filename()?;
         let scale = max(scale.unwrap_or(self.resource.capture_scale), 1);
-        self.resource.screencast.save(&filename, scale);
+        let filter = self.resource.capture_filter;
+        let format = self.resource.screencast.format();
+        match format {
+            ScreencastFormat::Gif => self.resource.screencast.save(&filename, scale, filter)?,
+
+            // WebM frames were already encoded at the capture scale and filter
+            #[cfg(feature = "webm")]
+            ScreencastFormat::Webm => self.resource.screencast.save_webm(&filename)?,
+        }
         #[cfg(target_os = "emscripten")]
-        pyxel_platform::emscripten::save_file(&(filename + ".gif"));
+        pyxel_platform::emscripten::save_file(&(filename + format.file_extension()));
+        Ok(())
+    }
+
+    pub fn screencast_format(&mut self, format: u32, capture_sec: Option<u32>) -> PyxelResult<()> {
+        let format = ScreencastFormat::from_index(format).ok_or_else(|| {
+            PyxelError::Platform(format!("Unsupported screencast format {format}"))
+        })?;
+        self.resource.screencast.set_format(
+            format,
+            self.resource.capture_scale,
+            self.resource.capture_filter,
+        );
+        if let Some(capture_sec) = capture_sec {
+            self.resource.screencast.set_capture_sec(capture_sec);
+        }
+        Ok(())
+    }
+
+    pub fn set_capture_dir(&mut self, dir: Option<&str>, name_template: Option<&str>) {
+        self.resource.set_capture_dir(dir, name_template);
+    }
+
+    pub fn screenshot_to_clipboard(&mut self, enabled: bool) {
+        self.resource.screenshot_to_clipboard = enabled;
+    }
+
+    pub fn capture_filter(&mut self, filter: u32) {
+        self.resource.capture_filter = ScaleFilter::from_index(filter);
+        let format = self.resource.screencast.format();
+        if format != ScreencastFormat::Gif {
+            self.resource.screencast.set_format(
+                format,
+                self.resource.capture_scale,
+                self.resource.capture_filter,
+            );
+        }
     }
 
     pub fn reset_screencast(&mut self) {
@@ -162,22 +407,23 @@ impl Pyxel {
         self.resource.screencast.capture(
             self.width,
             self.height,
-            &self.screen.lock().canvas.data,
+            &self.screen.read().canvas.data,
             &self.colors.lock(),
             self.frame_count,
         );
     }
 
-    pub(crate) fn dump_image_bank(&self, image_index: u32) {
+    pub(crate) fn dump_image_bank(&self, image_index: u32) -> PyxelResult<()> {
         let filename = Self::prepend_desktop_path(&format!("pyxel-image{image_index}"));
         if let Some(image) = self.images.lock().get(image_index as usize) {
-            image.lock().save(&filename, 1);
+            image.read().save(&filename, 1)?;
             #[cfg(target_os = "emscripten")]
             pyxel_platform::emscripten::save_file(&(filename + ".png"));
         }
+        Ok(())
     }
 
-    pub(crate) fn dump_palette(&self) {
+    pub(crate) fn dump_palette(&self) -> PyxelResult<()> {
         let filename = Self::prepend_desktop_path("pyxel-palette");
         let num_colors = self.colors.lock().len();
         let image = Image::new(num_colors as u32, 1);
@@ -186,10 +432,22 @@ impl Pyxel {
             for i in 0..num_colors {
                 image.pset(i as f64, 0.0, i as Color);
             }
-            image.save(&filename, 16);
+            image.save(&filename, 16)?;
             #[cfg(target_os = "emscripten")]
             pyxel_platform::emscripten::save_file(&(filename + ".png"));
         }
+        Ok(())
+    }
+
+    pub(crate) fn dump_palette_source(&self, as_toml: bool) -> PyxelResult<()> {
+        let (extension, source) = palette_source(&self.colors.lock(), as_toml);
+        let filename = Self::prepend_desktop_path("pyxel-palette") + extension;
+        File::create(&filename)
+            .and_then(|mut file| file.write_all(source.as_bytes()))
+            .map_err(|_| PyxelError::FileWrite(filename.clone()))?;
+        #[cfg(target_os = "emscripten")]
+        pyxel_platform::emscripten::save_file(&filename);
+        Ok(())
     }
 
     fn datetime_string() -> String {
@@ -202,23 +460,60 @@ impl Pyxel {
         }
     }
 
+    fn capture_filename(&self) -> PyxelResult<String> {
+        self.resource
+            .capture_filename(&Self::datetime_string(), self.frame_count)
+    }
+
+    fn copy_screen_to_clipboard(&mut self, scale: u32) -> PyxelResult<()> {
+        cfg_if! {
+            if #[cfg(all(feature = "clipboard", not(target_os = "emscripten")))] {
+                let rgb_image = self
+                    .screen
+                    .read()
+                    .to_rgb_image(scale, self.resource.capture_filter);
+                let (width, height) = rgb_image.dimensions();
+                let rgba_image = image::DynamicImage::ImageRgb8(rgb_image).to_rgba8();
+                let clipboard_error = |err: arboard::Error| PyxelError::Platform(err.to_string());
+                if self.resource.clipboard.is_none() {
+                    let clipboard = arboard::Clipboard::new().map_err(clipboard_error)?;
+                    self.resource.clipboard = Some(clipboard);
+                }
+                self.resource
+                    .clipboard
+                    .as_mut()
+                    .unwrap()
+                    .set_image(arboard::ImageData {
+                        width: width as usize,
+                        height: height as usize,
+                        bytes: rgba_image.into_raw().into(),
+                    })
+                    .map_err(clipboard_error)
+            } else {
+                let _ = scale;
+                Err(PyxelError::Platform(
+                    "Copying images to the clipboard needs the clipboard feature and is not supported on the web"
+                        .to_string(),
+                ))
+            }
+        }
+    }
+
     fn prepend_desktop_path(basename: &str) -> String {
         let desktop_dir = if let Some(user_dirs) = UserDirs::new() {
             user_dirs.desktop_dir
         } else {
             PathBuf::new()
         };
-        desktop_dir.join(basename).to_str().unwrap().to_string()
+        desktop_dir.join(basename).to_string_lossy().into_owned()
     }
 
-    fn parse_format_version(toml_text: &str) -> u32 {
+    fn parse_format_version(toml_text: &str) -> Option<u32> {
         toml_text
             .lines()
             .find(|line| line.trim().starts_with("format_version"))
             .and_then(|line| line.split_once('='))
-            .map(|(_, value)| value.trim().parse::<u32>())
-            .unwrap()
-            .unwrap()
+            .and_then(|(_, value)| value.trim().parse::<u32>().ok())
     }
 
     fn warn_format_version(filename: &str) {
@@ -234,16 +529,109 @@ impl Pyxel {
             .map_or(filename, |i| &filename[..i])
             .to_string()
             + PALETTE_FILE_EXTENSION;
-        if let Ok(mut file) = File::open(Path::new(&filename)) {
-            let mut contents = String::new();
-            file.read_to_string(&mut contents).unwrap();
-            *self.colors.lock() = contents
-                .replace("\r\n", "\n")
-                .replace('\r', "\n")
-                .split('\n')
-                .filter(|s| !s.is_empty())
-                .map(|s| u32::from_str_radix(s.trim(), 16).unwrap() as Rgb24)
-                .collect();
+        let Ok(mut file) = File::open(Path::new(&filename)) else {
+            return;
+        };
+        let mut contents = String::new();
+        if file.read_to_string(&mut contents).is_err() {
+            return;
         }
+
+        // A malformed palette file is ignored rather than leaving a partial palette
+        let colors: Result<Vec<Rgb24>, _> = contents
+            .replace("\r\n", "\n")
+            .replace('\r', "\n")
+            .split('\n')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| u32::from_str_radix(s, 16))
+            .collect();
+        match colors {
+            Ok(colors) if (1..=MAX_COLORS as usize).contains(&colors.len()) => {
+                *self.colors.lock() = colors;
+            }
+            _ => log::warn!("Ignored invalid palette file '{filename}'"),
+        }
+    }
+}
+
+fn palette_source(colors: &[Rgb24], as_toml: bool) -> (&'static str, String) {
+    let values: Vec<String> = colors.iter().map(|rgb| format!("0x{rgb:06x}")).collect();
+    let values = values.join(", ");
+    if as_toml {
+        (".toml", format!("colors = [{values}]\n"))
+    } else {
+        (
+            ".rs",
+            format!("pub const COLORS: [u32; {}] = [{values}];\n", colors.len()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_palette_source() {
+        assert_eq!(
+            palette_source(&[0x000000, 0x1a2b3c], true),
+            (".toml", "colors = [0x000000, 0x1a2b3c]\n".to_string())
+        );
+        assert_eq!(
+            palette_source(&[0xffffff], false),
+            (
+                ".rs",
+                "pub const COLORS: [u32; 1] = [0xffffff];\n".to_string()
+            )
+        );
+        assert_eq!(
+            palette_source(&[], false),
+            (".rs", "pub const COLORS: [u32; 0] = [];\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_capture_filename() {
+        let mut resource = Resource::new("test", None, None, 30);
+        let dir = env::temp_dir().join(format!("pyxel_capture_test_{}", std::process::id()));
+        resource.set_capture_dir(dir.to_str(), Some("shot-{frame}-{datetime}-{frame}"));
+        assert_eq!(
+            resource.capture_filename("20240102-030405", 42).unwrap(),
+            dir.join("shot-42-20240102-030405-42").to_string_lossy()
+        );
+        assert!(dir.is_dir());
+
+        resource.set_capture_dir(dir.to_str(), None);
+        assert_eq!(
+            resource.capture_filename("20240102-030405", 0).unwrap(),
+            dir.join("pyxel-20240102-030405").to_string_lossy()
+        );
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_gif() {
+        let path = env::temp_dir().join(format!("pyxel_export_gif_{}", std::process::id()));
+        let filename = path.to_str().unwrap();
+        let gif_path = path.with_extension("gif");
+        assert!(matches!(
+            Resource::export_gif(&[], filename, 30, 1),
+            Err(PyxelError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            Resource::export_gif(&[Image::new(4, 4), Image::new(4, 3)], filename, 30, 1),
+            Err(PyxelError::InvalidArgument(_))
+        ));
+        assert!(!gif_path.exists());
+
+        let frames = [Image::new(4, 4), Image::new(4, 4)];
+        frames[1].lock().cls(7);
+        Resource::export_gif(&frames, filename, 30, 2).unwrap();
+        let data = fs::read(&gif_path).unwrap();
+        assert!(data.starts_with(b"GIF89a"));
+        fs::remove_file(&gif_path).unwrap();
     }
 }
@@ -5,7 +5,11 @@ use crate::image::{Color, Image, SharedImage};
 use crate::music::{Music, SharedMusic};
 use crate::oscillator::{Effect, Gain};
 use crate::pyxel::Pyxel;
-use crate::settings::RESOURCE_FORMAT_VERSION;
+use crate::sample::{Sample, SharedSample};
+use crate::settings::{
+    MAX_RESOURCE_CELLS, MAX_RESOURCE_SIZE, MAX_WAVEFORM_AMP, NUM_IMAGES, NUM_SAMPLE_SLOTS,
+    NUM_SOUNDS, RESOURCE_FORMAT_VERSION,
+};
 use crate::sound::{SharedSound, Sound};
 use crate::tilemap::{ImageSource, SharedTilemap, TileCoord, Tilemap};
 use crate::tone::{Noise, SharedTone, Tone, Waveform};
@@ -13,15 +17,15 @@ use crate::utils::{compress_vec2, expand_vec2, trim_empty_vecs};
 use crate::{Rgb24, SharedChannel};
 
 #[derive(Clone, Serialize, Deserialize)]
-struct ImageData {
+pub(crate) struct ImageData {
     width: u32,
     height: u32,
     data: Vec<Vec<Color>>,
 }
 
 impl ImageData {
-    fn from_image(image: SharedImage) -> Self {
-        let image = image.lock();
+    pub(crate) fn from_image(image: SharedImage) -> Self {
+        let image = image.read();
         let width = image.width();
         let height = image.height();
         let data: Vec<Vec<_>> = image
@@ -38,7 +42,11 @@ impl ImageData {
         }
     }
 
-    fn to_image(&self) -> SharedImage {
+    pub(crate) fn is_valid(&self) -> bool {
+        is_valid_grid(&self.data, self.width, self.height)
+    }
+
+    pub(crate) fn to_image(&self) -> SharedImage {
         let data = expand_vec2(&self.data, self.height as usize, self.width as usize);
         let image = Image::new(self.width, self.height);
         {
@@ -85,6 +93,12 @@ impl TilemapData {
         }
     }
 
+    // Tilemaps index images when drawn, so their image must exist after loading
+    fn is_valid(&self, num_images: usize) -> bool {
+        is_valid_grid(&self.data, self.width, self.height)
+            && (self.imgsrc as usize) < num_images.max(NUM_IMAGES as usize)
+    }
+
     fn to_tilemap(&self) -> SharedTilemap {
         let data = expand_vec2(&self.data, self.height as usize, (self.width * 2) as usize);
         let tilemap = Tilemap::new(self.width, self.height, ImageSource::Index(self.imgsrc));
@@ -97,20 +111,55 @@ impl TilemapData {
     }
 }
 
+// Compressed rows are expanded on load, which requires at least one non-empty row
+fn is_valid_grid<T>(data: &[Vec<T>], width: u32, height: u32) -> bool {
+    width <= MAX_RESOURCE_SIZE
+        && height <= MAX_RESOURCE_SIZE
+        && !data.is_empty()
+        && data.iter().all(|row| !row.is_empty())
+}
+
+// Bounds the memory allocated on load, since a few bytes of text can declare a huge image
+fn total_cells(images: &[ImageData], tilemaps: &[TilemapData]) -> u64 {
+    let images = images
+        .iter()
+        .map(|image| image.width as u64 * image.height as u64);
+    let tilemaps = tilemaps
+        .iter()
+        .map(|tilemap| tilemap.width as u64 * tilemap.height as u64);
+    images.chain(tilemaps).sum()
+}
+
+fn is_valid_colors(colors: &[String]) -> bool {
+    colors
+        .iter()
+        .all(|hex| u32::from_str_radix(hex, 16).is_ok())
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 struct ToneData {
     gain: Gain,
     noise: u32,
     waveform: Waveform,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sample: Option<u32>,
 }
 
 impl ToneData {
+    fn is_valid(&self) -> bool {
+        self.gain.is_finite()
+            && self.noise <= Noise::LongPeriod.to_index()
+            && self.waveform.iter().all(|amp| *amp <= MAX_WAVEFORM_AMP)
+            && self.sample.map_or(true, |sample| sample < NUM_SAMPLE_SLOTS)
+    }
+
     fn from_tone(tone: SharedTone) -> Self {
         let tone = tone.lock();
         Self {
             gain: tone.gain,
             noise: tone.noise.to_index(),
             waveform: tone.waveform,
+            sample: tone.sample,
         }
     }
 
@@ -121,11 +170,47 @@ impl ToneData {
             tone.gain = self.gain;
             tone.noise = Noise::from_index(self.noise);
             tone.waveform = self.waveform;
+            tone.sample = self.sample;
         }
         tone
     }
 }
 
+// Samples are saved along with the tones that reference them by slot index
+#[derive(Clone, Serialize, Deserialize)]
+struct SampleData {
+    sample_rate: u32,
+    base_note: Note,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    data: Vec<i16>,
+}
+
+impl SampleData {
+    fn is_valid(&self) -> bool {
+        self.sample_rate > 0 || self.data.is_empty()
+    }
+
+    fn from_sample(sample: SharedSample) -> Self {
+        let sample = sample.lock();
+        Self {
+            sample_rate: sample.sample_rate,
+            base_note: sample.base_note,
+            data: sample.data.clone(),
+        }
+    }
+
+    fn to_sample(&self) -> SharedSample {
+        let sample = Sample::new();
+        {
+            let mut sample = sample.lock();
+            sample.sample_rate = self.sample_rate;
+            sample.base_note = self.base_note;
+            sample.data = self.data.clone();
+        }
+        sample
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 struct ChannelData {
     gain: Gain,
@@ -153,7 +238,7 @@ impl ChannelData {
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-struct SoundData {
+pub(crate) struct SoundData {
     notes: Vec<Note>,
     tones: Vec<u32>,
     volumes: Vec<Volume>,
@@ -162,7 +247,11 @@ struct SoundData {
 }
 
 impl SoundData {
-    fn from_sound(sound: SharedSound) -> Self {
+    pub(crate) fn is_valid(&self) -> bool {
+        self.to_sound().lock().is_valid()
+    }
+
+    pub(crate) fn from_sound(sound: SharedSound) -> Self {
         let sound = sound.lock();
         Self {
             notes: sound.notes.clone(),
@@ -173,7 +262,7 @@ impl SoundData {
         }
     }
 
-    fn to_sound(&self) -> SharedSound {
+    pub(crate) fn to_sound(&self) -> SharedSound {
         let sound = Sound::new();
         {
             let mut sound = sound.lock();
@@ -193,6 +282,14 @@ struct MusicData {
 }
 
 impl MusicData {
+    fn is_valid(&self, num_sounds: usize) -> bool {
+        let num_sounds = num_sounds.max(NUM_SOUNDS as usize);
+        self.seqs
+            .iter()
+            .flatten()
+            .all(|sound_index| (*sound_index as usize) < num_sounds)
+    }
+
     fn from_music(music: SharedMusic) -> Self {
         let music = music.lock();
         let seqs: Vec<_> = music.seqs.iter().map(|seq| seq.lock().clone()).collect();
@@ -228,14 +325,34 @@ pub struct ResourceData3 {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     tones: Vec<ToneData>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    samples: Vec<SampleData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     sounds: Vec<SoundData>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     musics: Vec<MusicData>,
 }
 
 impl ResourceData3 {
-    pub fn from_toml(toml_text: &str) -> Self {
-        toml::from_str(toml_text).unwrap()
+    pub fn from_toml(toml_text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml_text)
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        is_valid_colors(&self.colors)
+            && self.images.iter().all(ImageData::is_valid)
+            && self
+                .tilemaps
+                .iter()
+                .all(|tilemap| tilemap.is_valid(self.images.len()))
+            && total_cells(&self.images, &self.tilemaps) <= MAX_RESOURCE_CELLS
+            && self.tones.iter().all(ToneData::is_valid)
+            && self.samples.len() <= NUM_SAMPLE_SLOTS as usize
+            && self.samples.iter().all(SampleData::is_valid)
+            && self.sounds.iter().all(SoundData::is_valid)
+            && self
+                .musics
+                .iter()
+                .all(|music| music.is_valid(self.sounds.len()))
     }
 
     pub fn from_runtime(pyxel: &Pyxel) -> Self {
@@ -246,6 +363,7 @@ impl ResourceData3 {
             tilemaps: Vec::new(),
             channels: Vec::new(),
             tones: Vec::new(),
+            samples: Vec::new(),
             sounds: Vec::new(),
             musics: Vec::new(),
         };
@@ -273,6 +391,19 @@ impl ResourceData3 {
         for tone in &*pyxel.tones.lock() {
             resource_data.tones.push(ToneData::from_tone(tone.clone()));
         }
+        // Unused sample slots are left out, so files without samples stay as they were
+        if pyxel
+            .samples
+            .lock()
+            .iter()
+            .any(|sample| !sample.lock().data.is_empty())
+        {
+            for sample in &*pyxel.samples.lock() {
+                resource_data
+                    .samples
+                    .push(SampleData::from_sample(sample.clone()));
+            }
+        }
         for sound in &*pyxel.sounds.lock() {
             resource_data
                 .sounds
@@ -346,6 +477,12 @@ impl ResourceData3 {
             }
             *pyxel.tones.lock() = tones;
         }
+        if include_tones && !self.samples.is_empty() {
+            let mut samples: Vec<SharedSample> =
+                self.samples.iter().map(SampleData::to_sample).collect();
+            samples.resize_with(NUM_SAMPLE_SLOTS as usize, Sample::new);
+            *pyxel.samples.lock() = samples;
+        }
     }
 
     pub fn to_toml(
@@ -379,6 +516,7 @@ impl ResourceData3 {
         }
         if !include_tones {
             resource_data.tones.clear();
+            resource_data.samples.clear();
         }
         toml::to_string(&resource_data).unwrap()
     }
@@ -397,8 +535,24 @@ pub struct ResourceData1 {
 }
 
 impl ResourceData1 {
-    pub fn from_toml(toml_text: &str) -> Self {
-        toml::from_str(toml_text).unwrap()
+    pub fn from_toml(toml_text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml_text)
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        is_valid_colors(&self.colors)
+            && self.images.iter().all(ImageData::is_valid)
+            && self
+                .tilemaps
+                .iter()
+                .all(|tilemap| tilemap.is_valid(self.images.len()))
+            && total_cells(&self.images, &self.tilemaps) <= MAX_RESOURCE_CELLS
+            && self.waveforms.iter().all(ToneData::is_valid)
+            && self.sounds.iter().all(SoundData::is_valid)
+            && self
+                .musics
+                .iter()
+                .all(|music| music.is_valid(self.sounds.len()))
     }
 
     pub fn to_runtime(
@@ -463,3 +617,80 @@ impl ResourceData1 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+    use crate::settings::NUM_WAVEFORM_STEPS;
+
+    fn is_valid_toml(toml_text: &str) -> bool {
+        ResourceData3::from_toml(&format!("format_version = 3\n{toml_text}"))
+            .unwrap()
+            .is_valid()
+    }
+
+    #[test]
+    fn test_is_valid_audio_data() {
+        let sound = |volume: u8, speed: u32| {
+            format!(
+                "[[sounds]]\nnotes = [0, 59]\ntones = [0, 9]\nvolumes = [{volume}]\neffects = [4]\nspeed = {speed}\n"
+            )
+        };
+        assert!(is_valid_toml(&sound(7, 30)));
+        assert!(!is_valid_toml(&sound(8, 30)));
+        assert!(!is_valid_toml(&sound(7, 0)));
+
+        let music = |sound_index: u32| format!("[[musics]]\nseqs = [[0, {sound_index}]]\n");
+        assert!(is_valid_toml(&music(NUM_SOUNDS - 1)));
+        assert!(!is_valid_toml(&music(NUM_SOUNDS)));
+
+        let tone = |noise: u32, amp: u8| {
+            let waveform = vec![amp.to_string(); NUM_WAVEFORM_STEPS as usize].join(", ");
+            format!("[[tones]]\ngain = 0.5\nnoise = {noise}\nwaveform = [{waveform}]\n")
+        };
+        assert!(is_valid_toml(&tone(0, MAX_WAVEFORM_AMP)));
+        assert!(!is_valid_toml(&tone(3, MAX_WAVEFORM_AMP)));
+        assert!(!is_valid_toml(&tone(0, MAX_WAVEFORM_AMP + 1)));
+
+        assert!(is_valid_toml(
+            "[[samples]]\nsample_rate = 0\nbase_note = 33\n"
+        ));
+        assert!(!is_valid_toml(
+            "[[samples]]\nsample_rate = 0\nbase_note = 33\ndata = [1]\n"
+        ));
+    }
+
+    #[test]
+    fn test_samples_round_trip() {
+        let pyxel = headless_pyxel(16, 16);
+        let saved_samples = pyxel.samples.lock().clone();
+        {
+            let samples = pyxel.samples.lock();
+            let mut sample = samples[2].lock();
+            sample.data = vec![0, 1000, -1000];
+            sample.sample_rate = 22050;
+            sample.base_note = 40;
+        }
+        let resource_data = ResourceData3::from_runtime(&pyxel);
+        let toml_text = resource_data.to_toml(false, false, false, false, false, false, true);
+        assert!(!resource_data
+            .to_toml(false, false, false, false, false, false, false)
+            .contains("[[samples]]"));
+
+        *pyxel.samples.lock() = vec![Sample::new()];
+        ResourceData3::from_toml(&toml_text)
+            .unwrap()
+            .to_runtime(&pyxel, false, false, false, false, false, false, true);
+        {
+            let samples = pyxel.samples.lock();
+            assert_eq!(samples.len(), NUM_SAMPLE_SLOTS as usize);
+            let sample = samples[2].lock();
+            assert_eq!(sample.data, [0, 1000, -1000]);
+            assert_eq!(sample.sample_rate, 22050);
+            assert_eq!(sample.base_note, 40);
+            assert!(samples[0].lock().data.is_empty());
+        }
+        *pyxel.samples.lock() = saved_samples;
+    }
+}
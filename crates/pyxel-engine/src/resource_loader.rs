@@ -0,0 +1,201 @@
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(not(target_os = "emscripten"))]
+use std::thread;
+
+use crate::error::{PyxelError, PyxelResult};
+use crate::pyxel::Pyxel;
+use crate::resource::ResourceFile;
+
+struct LoadState {
+    progress: f64,
+    result: Option<PyxelResult<ResourceFile>>,
+    is_done: bool,
+    error: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct ResourceLoad {
+    state: shared_type!(LoadState),
+}
+
+impl ResourceLoad {
+    fn new() -> Self {
+        Self {
+            state: new_shared_type!(LoadState {
+                progress: 0.0,
+                result: None,
+                is_done: false,
+                error: None,
+            }),
+        }
+    }
+
+    // Progress of reading the file, which reaches 1.0 before the data is applied
+    pub fn progress(&self) -> f64 {
+        self.state.lock().progress
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state.lock().is_done
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.state.lock().error.clone()
+    }
+}
+
+pub(crate) struct PendingLoad {
+    filename: String,
+    exclude_images: Option<bool>,
+    exclude_tilemaps: Option<bool>,
+    exclude_sounds: Option<bool>,
+    exclude_musics: Option<bool>,
+    include_colors: Option<bool>,
+    include_channels: Option<bool>,
+    include_tones: Option<bool>,
+    load: ResourceLoad,
+}
+
+impl Pyxel {
+    pub fn load_async(
+        &mut self,
+        filename: &str,
+        exclude_images: Option<bool>,
+        exclude_tilemaps: Option<bool>,
+        exclude_sounds: Option<bool>,
+        exclude_musics: Option<bool>,
+        include_colors: Option<bool>,
+        include_channels: Option<bool>,
+        include_tones: Option<bool>,
+    ) -> ResourceLoad {
+        self.watch_resource_file(
+            filename,
+            exclude_images,
+            exclude_tilemaps,
+            exclude_sounds,
+            exclude_musics,
+            include_colors,
+            include_channels,
+            include_tones,
+        );
+
+        let load = ResourceLoad::new();
+        let state = load.state.clone();
+        let thread_filename = filename.to_string();
+        let read = move || {
+            // A panicking reader still completes the load, so that callers never wait forever
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                Self::read_resource_file(&thread_filename, |progress| {
+                    state.lock().progress = progress;
+                })
+            }))
+            .unwrap_or_else(|_| Err(PyxelError::InvalidFile(thread_filename.clone())));
+            let mut state = state.lock();
+            state.progress = 1.0;
+            state.result = Some(result);
+        };
+
+        // Web builds have no threads, so the file is read right away and applied next frame
+        #[cfg(target_os = "emscripten")]
+        read();
+        #[cfg(not(target_os = "emscripten"))]
+        thread::spawn(read);
+
+        self.resource.pending_loads.push(PendingLoad {
+            filename: filename.to_string(),
+            exclude_images,
+            exclude_tilemaps,
+            exclude_sounds,
+            exclude_musics,
+            include_colors,
+            include_channels,
+            include_tones,
+            load: load.clone(),
+        });
+        load
+    }
+
+    // Read files are applied on the main thread, since the runtime isn't shared with workers
+    pub(crate) fn apply_pending_loads(&mut self) {
+        let pending_loads = std::mem::take(&mut self.resource.pending_loads);
+        for pending_load in pending_loads {
+            let result = pending_load.load.state.lock().result.take();
+            let Some(result) = result else {
+                self.resource.pending_loads.push(pending_load);
+                continue;
+            };
+            let result = result.and_then(|resource_file| {
+                self.apply_resource_file(
+                    &pending_load.filename,
+                    resource_file,
+                    pending_load.exclude_images,
+                    pending_load.exclude_tilemaps,
+                    pending_load.exclude_sounds,
+                    pending_load.exclude_musics,
+                    pending_load.include_colors,
+                    pending_load.include_channels,
+                    pending_load.include_tones,
+                )
+            });
+            if let Err(err) = &result {
+                log::warn!(
+                    "Failed to load '{}' asynchronously: {err}",
+                    pending_load.filename
+                );
+            }
+            let mut state = pending_load.load.state.lock();
+            state.is_done = true;
+            state.error = result.err().map(|err| err.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+
+    fn wait_for_load(pyxel: &mut Pyxel, load: &ResourceLoad) {
+        for _ in 0..500 {
+            pyxel.apply_pending_loads();
+            if load.is_done() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("Resource load did not complete");
+    }
+
+    #[test]
+    fn test_load_async() {
+        let mut pyxel = headless_pyxel(16, 16);
+        let filename =
+            env::temp_dir().join(format!("pyxel_load_async_{}.pyxres", std::process::id()));
+        let filename = filename.to_str().unwrap();
+        pyxel.images.lock()[0].lock().pset(3.0, 4.0, 9);
+        pyxel
+            .save(filename, None, None, None, None, None, None, None)
+            .unwrap();
+        pyxel.images.lock()[0].lock().cls(0);
+
+        let load = pyxel.load_async(filename, None, None, None, None, None, None, None);
+        wait_for_load(&mut pyxel, &load);
+        fs::remove_file(filename).unwrap();
+        assert_eq!(load.error(), None);
+        assert_eq!(load.progress(), 1.0);
+        assert_eq!(pyxel.images.lock()[0].lock().pget(3.0, 4.0), 9);
+        pyxel.images.lock()[0].lock().cls(0);
+    }
+
+    #[test]
+    fn test_load_async_missing_file() {
+        let mut pyxel = headless_pyxel(16, 16);
+        let load = pyxel.load_async("missing.pyxres", None, None, None, None, None, None, None);
+        wait_for_load(&mut pyxel, &load);
+        assert!(load.error().unwrap().contains("missing.pyxres"));
+    }
+}
@@ -0,0 +1,45 @@
+use crate::channel::Note;
+use crate::error::PyxelResult;
+use crate::settings::DEFAULT_SAMPLE_NOTE;
+use crate::stream;
+
+pub struct Sample {
+    pub data: Vec<i16>,
+    pub sample_rate: u32,
+    pub base_note: Note,
+}
+
+pub type SharedSample = shared_type!(Sample);
+
+impl Sample {
+    pub fn new() -> SharedSample {
+        new_shared_type!(Self {
+            data: Vec::new(),
+            sample_rate: 0,
+            base_note: DEFAULT_SAMPLE_NOTE,
+        })
+    }
+
+    pub fn load(&mut self, filename: &str) -> PyxelResult<()> {
+        let (sample_rate, data) = Self::read_wav_file(filename)?;
+        self.set_data(sample_rate, data);
+        Ok(())
+    }
+
+    // The audio thread locks samples while playing them, so shared samples should be read
+    // with this first and then swapped in with set_data rather than loaded under the lock
+    pub fn read_wav_file(filename: &str) -> PyxelResult<(u32, Vec<i16>)> {
+        stream::load_wav_file(filename)
+    }
+
+    pub fn set_data(&mut self, sample_rate: u32, data: Vec<i16>) {
+        self.sample_rate = sample_rate;
+        self.data = data;
+    }
+
+    pub fn amplitude(&self, position: u32) -> f64 {
+        self.data
+            .get(position as usize)
+            .map_or(0.0, |value| *value as f64 / i16::MAX as f64)
+    }
+}
@@ -0,0 +1,105 @@
+use cfg_if::cfg_if;
+
+use crate::error::PyxelResult;
+use crate::pyxel::Pyxel;
+use crate::settings::SAVE_DATA_DIR;
+
+cfg_if! {
+    if #[cfg(target_os = "emscripten")] {
+        use crate::utils::{decode_hex, encode_hex};
+
+        fn save_data_key(save_name: &str, slot: u32) -> String {
+            format!("{SAVE_DATA_DIR}:{save_name}:{slot}")
+        }
+    } else {
+        use std::fs;
+        use std::path::PathBuf;
+
+        use platform_dirs::AppDirs;
+
+        use crate::error::PyxelError;
+        use crate::settings::SAVE_DATA_FILE_EXTENSION;
+
+        fn save_data_path(save_name: &str, slot: u32) -> PyxelResult<PathBuf> {
+            let app_dirs = AppDirs::new(Some(SAVE_DATA_DIR), false).ok_or_else(|| {
+                PyxelError::Platform("Failed to find the user data directory".to_string())
+            })?;
+            Ok(app_dirs
+                .data_dir
+                .join(save_name)
+                .join(format!("{slot}{SAVE_DATA_FILE_EXTENSION}")))
+        }
+    }
+}
+
+// Save names become part of file paths and storage keys, so every byte other than ASCII
+// letters, digits and '-' is percent-encoded. Different names stay distinct, and
+// the empty name becomes "_", which no other name can produce.
+pub(crate) fn encode_save_name(name: &str) -> String {
+    if name.is_empty() {
+        return "_".to_string();
+    }
+    name.bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() || byte == b'-' {
+                (byte as char).to_string()
+            } else {
+                format!("%{byte:02X}")
+            }
+        })
+        .collect()
+}
+
+impl Pyxel {
+    pub fn set_save_name(&mut self, name: &str) {
+        self.resource.save_name = encode_save_name(name);
+    }
+
+    pub fn save_data(&self, slot: u32, data: &[u8]) -> PyxelResult<()> {
+        let save_name = &self.resource.save_name;
+        cfg_if! {
+            if #[cfg(target_os = "emscripten")] {
+                pyxel_platform::emscripten::set_local_storage(
+                    &save_data_key(save_name, slot),
+                    &encode_hex(data),
+                );
+                Ok(())
+            } else {
+                let path = save_data_path(save_name, slot)?;
+                let write_error = || PyxelError::FileWrite(path.display().to_string());
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|_| write_error())?;
+                }
+                fs::write(&path, data).map_err(|_| write_error())
+            }
+        }
+    }
+
+    pub fn load_data(&self, slot: u32) -> Option<Vec<u8>> {
+        let save_name = &self.resource.save_name;
+        cfg_if! {
+            if #[cfg(target_os = "emscripten")] {
+                let value =
+                    pyxel_platform::emscripten::local_storage(&save_data_key(save_name, slot))?;
+                decode_hex(&value)
+            } else {
+                fs::read(save_data_path(save_name, slot).ok()?).ok()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_save_name() {
+        assert_eq!(encode_save_name("My Game-2_x"), "My%20Game-2%5Fx");
+        assert_eq!(encode_save_name("../save"), "%2E%2E%2Fsave");
+        assert_eq!(encode_save_name("ゲーム"), "%E3%82%B2%E3%83%BC%E3%83%A0");
+        assert_ne!(encode_save_name("ゲーム"), encode_save_name("游戏"));
+        assert_eq!(encode_save_name(""), "_");
+        assert_ne!(encode_save_name("_"), encode_save_name(""));
+    }
+}
@@ -0,0 +1,169 @@
+#[derive(PartialEq, Copy, Clone)]
+pub enum ScaleFilter {
+    Nearest,
+    Scale2x,
+    Scale3x,
+}
+
+impl ScaleFilter {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            1 => Self::Scale2x,
+            2 => Self::Scale3x,
+            _ => Self::Nearest,
+        }
+    }
+
+    pub fn factor(self) -> u32 {
+        match self {
+            Self::Nearest => 1,
+            Self::Scale2x => 2,
+            Self::Scale3x => 3,
+        }
+    }
+
+    pub fn for_scale(self, scale: u32) -> Self {
+        if scale % self.factor() == 0 {
+            self
+        } else {
+            Self::Nearest
+        }
+    }
+
+    pub fn apply<T: Copy + PartialEq>(self, data: &[T], width: u32, height: u32) -> Vec<T> {
+        match self {
+            Self::Nearest => data.to_vec(),
+            Self::Scale2x => scale2x(data, width, height),
+            Self::Scale3x => scale3x(data, width, height),
+        }
+    }
+}
+
+fn neighbors<T: Copy>(data: &[T], width: u32, height: u32, x: u32, y: u32) -> [T; 9] {
+    let x0 = x.saturating_sub(1);
+    let x2 = (x + 1).min(width - 1);
+    let y0 = y.saturating_sub(1);
+    let y2 = (y + 1).min(height - 1);
+    let get = |x: u32, y: u32| data[(width * y + x) as usize];
+    [
+        get(x0, y0),
+        get(x, y0),
+        get(x2, y0),
+        get(x0, y),
+        get(x, y),
+        get(x2, y),
+        get(x0, y2),
+        get(x, y2),
+        get(x2, y2),
+    ]
+}
+
+#[allow(clippy::many_single_char_names)]
+fn scale2x<T: Copy + PartialEq>(data: &[T], width: u32, height: u32) -> Vec<T> {
+    let dst_width = width * 2;
+    let mut dst = vec![data[0]; (dst_width * height * 2) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let [_, b, _, d, e, f, _, h, _] = neighbors(data, width, height, x, y);
+            let pixels = if b != h && d != f {
+                [
+                    if d == b { d } else { e },
+                    if b == f { f } else { e },
+                    if d == h { d } else { e },
+                    if h == f { f } else { e },
+                ]
+            } else {
+                [e; 4]
+            };
+            for (i, pixel) in pixels.into_iter().enumerate() {
+                let dx = x * 2 + i as u32 % 2;
+                let dy = y * 2 + i as u32 / 2;
+                dst[(dst_width * dy + dx) as usize] = pixel;
+            }
+        }
+    }
+    dst
+}
+
+#[allow(clippy::many_single_char_names)]
+fn scale3x<T: Copy + PartialEq>(data: &[T], width: u32, height: u32) -> Vec<T> {
+    let dst_width = width * 3;
+    let mut dst = vec![data[0]; (dst_width * height * 3) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let [a, b, c, d, e, f, g, h, i] = neighbors(data, width, height, x, y);
+            let pixels = if b != h && d != f {
+                [
+                    if d == b { d } else { e },
+                    if (d == b && e != c) || (b == f && e != a) {
+                        b
+                    } else {
+                        e
+                    },
+                    if b == f { f } else { e },
+                    if (d == b && e != g) || (d == h && e != a) {
+                        d
+                    } else {
+                        e
+                    },
+                    e,
+                    if (b == f && e != i) || (h == f && e != c) {
+                        f
+                    } else {
+                        e
+                    },
+                    if d == h { d } else { e },
+                    if (d == h && e != i) || (h == f && e != g) {
+                        h
+                    } else {
+                        e
+                    },
+                    if h == f { f } else { e },
+                ]
+            } else {
+                [e; 9]
+            };
+            for (j, pixel) in pixels.into_iter().enumerate() {
+                let dx = x * 3 + j as u32 % 3;
+                let dy = y * 3 + j as u32 / 3;
+                dst[(dst_width * dy + dx) as usize] = pixel;
+            }
+        }
+    }
+    dst
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_scale() {
+        assert!(ScaleFilter::Scale2x.for_scale(4) == ScaleFilter::Scale2x);
+        assert!(ScaleFilter::Scale2x.for_scale(3) == ScaleFilter::Nearest);
+        assert!(ScaleFilter::Scale3x.for_scale(6) == ScaleFilter::Scale3x);
+        assert!(ScaleFilter::Scale3x.for_scale(2) == ScaleFilter::Nearest);
+    }
+
+    #[test]
+    fn test_scale2x() {
+        let data = [0, 1, 1, 1];
+        assert_eq!(
+            ScaleFilter::Scale2x.apply(&data, 2, 2),
+            vec![0, 0, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1]
+        );
+
+        let data = [5, 5, 5, 5];
+        assert_eq!(ScaleFilter::Scale2x.apply(&data, 2, 2), vec![5; 16]);
+    }
+
+    #[test]
+    fn test_scale3x() {
+        let data = [0, 1, 1, 1];
+        let result = ScaleFilter::Scale3x.apply(&data, 2, 2);
+        assert_eq!(result.len(), 36);
+        assert_eq!(result[0], 0);
+        assert_eq!(result[4 * 6 + 4], 1);
+        assert!(result.iter().all(|&pixel| pixel == 0 || pixel == 1));
+    }
+}
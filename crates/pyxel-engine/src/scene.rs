@@ -0,0 +1,246 @@
+use std::mem::take;
+
+use crate::pyxel::Pyxel;
+
+pub trait Scene {
+    fn update(&mut self, pyxel: &mut Pyxel);
+    fn draw(&mut self, pyxel: &mut Pyxel);
+    fn on_enter(&mut self, _pyxel: &mut Pyxel) {}
+    fn on_exit(&mut self, _pyxel: &mut Pyxel) {}
+    fn on_pause(&mut self, _pyxel: &mut Pyxel) {}
+    fn on_resume(&mut self, _pyxel: &mut Pyxel) {}
+}
+
+enum SceneChange {
+    Push(Box<dyn Scene>),
+    Pop,
+    Replace(Box<dyn Scene>),
+}
+
+// Changes are queued and applied between frames, since scenes request them from their own update
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    changes: Vec<SceneChange>,
+    num_detached: usize,
+}
+
+impl SceneStack {
+    pub(crate) fn new() -> Self {
+        Self {
+            scenes: Vec::new(),
+            changes: Vec::new(),
+            num_detached: 0,
+        }
+    }
+
+    pub fn push(&mut self, scene: impl Scene + 'static) {
+        self.changes.push(SceneChange::Push(Box::new(scene)));
+    }
+
+    pub fn pop(&mut self) {
+        self.changes.push(SceneChange::Pop);
+    }
+
+    pub fn replace(&mut self, scene: impl Scene + 'static) {
+        self.changes.push(SceneChange::Replace(Box::new(scene)));
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len() + self.num_detached
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Pyxel {
+    pub(crate) fn update_scenes(&mut self) {
+        self.apply_scene_changes();
+        self.with_top_scene(|scene, pyxel| scene.update(pyxel));
+        self.apply_scene_changes();
+    }
+
+    pub(crate) fn draw_scenes(&mut self) {
+        self.with_top_scene(|scene, pyxel| scene.draw(pyxel));
+    }
+
+    fn apply_scene_changes(&mut self) {
+        // Hooks may queue further changes, which are applied in the same pass
+        while !self.scenes.changes.is_empty() {
+            for change in take(&mut self.scenes.changes) {
+                match change {
+                    SceneChange::Push(mut scene) => {
+                        self.with_top_scene(|scene, pyxel| scene.on_pause(pyxel));
+                        self.run_detached_scene(scene.as_mut(), |scene, pyxel| {
+                            scene.on_enter(pyxel);
+                        });
+                        self.scenes.scenes.push(scene);
+                    }
+                    SceneChange::Pop => {
+                        if let Some(mut scene) = self.scenes.scenes.pop() {
+                            self.run_detached_scene(scene.as_mut(), |scene, pyxel| {
+                                scene.on_exit(pyxel);
+                            });
+                            self.with_top_scene(|scene, pyxel| scene.on_resume(pyxel));
+                        }
+                    }
+                    SceneChange::Replace(mut scene) => {
+                        if let Some(mut old_scene) = self.scenes.scenes.pop() {
+                            self.run_detached_scene(old_scene.as_mut(), |scene, pyxel| {
+                                scene.on_exit(pyxel);
+                            });
+                        }
+                        self.run_detached_scene(scene.as_mut(), |scene, pyxel| {
+                            scene.on_enter(pyxel);
+                        });
+                        self.scenes.scenes.push(scene);
+                    }
+                }
+            }
+        }
+    }
+
+    fn with_top_scene(&mut self, func: impl FnOnce(&mut dyn Scene, &mut Pyxel)) {
+        if let Some(mut scene) = self.scenes.scenes.pop() {
+            self.run_detached_scene(scene.as_mut(), func);
+            self.scenes.scenes.push(scene);
+        }
+    }
+
+    // A scene is off the stack while its hooks borrow Pyxel, so len() counts it separately
+    fn run_detached_scene(
+        &mut self,
+        scene: &mut dyn Scene,
+        func: impl FnOnce(&mut dyn Scene, &mut Pyxel),
+    ) {
+        self.scenes.num_detached += 1;
+        func(scene, self);
+        self.scenes.num_detached -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    struct TestScene {
+        name: &'static str,
+        log: Log,
+    }
+
+    impl TestScene {
+        fn new(name: &'static str, log: &Log) -> Self {
+            Self {
+                name,
+                log: log.clone(),
+            }
+        }
+
+        fn record(&self, event: &str, pyxel: &Pyxel) {
+            let len = pyxel.scenes.len();
+            self.log
+                .borrow_mut()
+                .push(format!("{} {event} {len}", self.name));
+        }
+    }
+
+    impl Scene for TestScene {
+        fn update(&mut self, pyxel: &mut Pyxel) {
+            self.record("update", pyxel);
+        }
+
+        fn draw(&mut self, pyxel: &mut Pyxel) {
+            self.record("draw", pyxel);
+        }
+
+        fn on_enter(&mut self, pyxel: &mut Pyxel) {
+            self.record("enter", pyxel);
+        }
+
+        fn on_exit(&mut self, pyxel: &mut Pyxel) {
+            self.record("exit", pyxel);
+        }
+
+        fn on_pause(&mut self, pyxel: &mut Pyxel) {
+            self.record("pause", pyxel);
+        }
+
+        fn on_resume(&mut self, pyxel: &mut Pyxel) {
+            self.record("resume", pyxel);
+        }
+    }
+
+    fn take_log(log: &Log) -> Vec<String> {
+        take(&mut *log.borrow_mut())
+    }
+
+    #[test]
+    fn test_scene_changes() {
+        let mut pyxel = headless_pyxel(16, 16);
+        let log = Log::default();
+        pyxel.scenes.push(TestScene::new("a", &log));
+        assert!(pyxel.scenes.is_empty());
+        pyxel.update_scenes();
+        assert_eq!(take_log(&log), ["a enter 1", "a update 1"]);
+
+        pyxel.scenes.push(TestScene::new("b", &log));
+        pyxel.update_scenes();
+        pyxel.draw_scenes();
+        assert_eq!(
+            take_log(&log),
+            ["a pause 1", "b enter 2", "b update 2", "b draw 2"]
+        );
+
+        pyxel.scenes.pop();
+        pyxel.update_scenes();
+        assert_eq!(take_log(&log), ["b exit 2", "a resume 1", "a update 1"]);
+
+        pyxel.scenes.replace(TestScene::new("c", &log));
+        pyxel.update_scenes();
+        assert_eq!(take_log(&log), ["a exit 1", "c enter 1", "c update 1"]);
+        assert_eq!(pyxel.scenes.len(), 1);
+
+        pyxel.scenes.pop();
+        pyxel.update_scenes();
+        assert_eq!(take_log(&log), ["c exit 1"]);
+        assert!(pyxel.scenes.is_empty());
+    }
+
+    #[test]
+    fn test_scene_changes_from_update() {
+        struct PushingScene {
+            log: Log,
+        }
+
+        impl Scene for PushingScene {
+            fn update(&mut self, pyxel: &mut Pyxel) {
+                pyxel.scenes.push(TestScene::new("b", &self.log));
+            }
+
+            fn draw(&mut self, _pyxel: &mut Pyxel) {}
+        }
+
+        let mut pyxel = headless_pyxel(16, 16);
+        let log = Log::default();
+        pyxel.scenes.push(PushingScene { log: log.clone() });
+        pyxel.update_scenes();
+        assert_eq!(take_log(&log), ["b enter 2"]);
+        assert_eq!(pyxel.scenes.len(), 2);
+    }
+
+    #[test]
+    fn test_scenes_without_callback() {
+        let mut pyxel = headless_pyxel(16, 16);
+        let log = Log::default();
+        pyxel.scenes.push(TestScene::new("a", &log));
+        pyxel.update_game(None);
+        assert_eq!(take_log(&log), ["a enter 1", "a update 1"]);
+    }
+}
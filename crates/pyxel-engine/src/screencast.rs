@@ -1,16 +1,55 @@
 use std::borrow::Cow;
 use std::cmp::{max, min};
+#[cfg(feature = "webm")]
+use std::collections::VecDeque;
 use std::fs::File;
+use std::mem;
 
 use gif::{DisposalMethod, Encoder, Frame, Repeat};
 use indexmap::IndexMap;
+#[cfg(feature = "webm")]
+use webm::mux::{self, Track};
 
+use crate::error::{PyxelError, PyxelResult};
 use crate::image::{Color, Rgb24};
 use crate::rect_area::RectArea;
+use crate::scale_filter::ScaleFilter;
 use crate::utils::add_file_extension;
 
 const TRANSPARENT: Rgb24 = 0xffffffff;
+#[cfg(feature = "webm")]
+const WEBM_BITRATE_KBPS: u32 = 5000;
+#[cfg(feature = "webm")]
+const WEBM_CHUNK_SEC: u32 = 1;
 
+#[derive(PartialEq, Copy, Clone)]
+pub enum ScreencastFormat {
+    Gif,
+    #[cfg(feature = "webm")]
+    Webm,
+}
+
+impl ScreencastFormat {
+    pub fn from_index(index: u32) -> Option<Self> {
+        match index {
+            0 => Some(Self::Gif),
+            #[cfg(feature = "webm")]
+            1 => Some(Self::Webm),
+            _ => None,
+        }
+    }
+
+    #[cfg(target_os = "emscripten")]
+    pub fn file_extension(self) -> &'static str {
+        match self {
+            Self::Gif => ".gif",
+            #[cfg(feature = "webm")]
+            Self::Webm => ".webm",
+        }
+    }
+}
+
+#[derive(Default)]
 struct Screen {
     width: u32,
     height: u32,
@@ -20,17 +59,207 @@ struct Screen {
 }
 
 impl Screen {
-    fn to_rgb_image(&self) -> Vec<Vec<Rgb24>> {
-        let mut rgb_image: Vec<Vec<Rgb24>> = Vec::new();
-        for y in 0..self.height {
-            let mut rgb_line: Vec<Rgb24> = Vec::new();
-            for x in 0..self.width {
-                let rgb = self.colors[self.image[(self.width * y + x) as usize] as usize];
-                rgb_line.push(rgb);
+    fn to_rgb_image(&self, filter: ScaleFilter) -> Vec<Vec<Rgb24>> {
+        to_rgb_image(&self.image, &self.colors, self.width, self.height, filter)
+    }
+}
+
+fn to_rgb_image(
+    image: &[Color],
+    colors: &[Rgb24],
+    width: u32,
+    height: u32,
+    filter: ScaleFilter,
+) -> Vec<Vec<Rgb24>> {
+    let image = filter.apply(image, width, height);
+    let width = width * filter.factor();
+    let height = height * filter.factor();
+    let mut rgb_image: Vec<Vec<Rgb24>> = Vec::new();
+    for y in 0..height {
+        let mut rgb_line: Vec<Rgb24> = Vec::new();
+        for x in 0..width {
+            let rgb = colors[image[(width * y + x) as usize] as usize];
+            rgb_line.push(rgb);
+        }
+        rgb_image.push(rgb_line);
+    }
+    rgb_image
+}
+
+#[cfg(feature = "webm")]
+struct EncodedFrame {
+    data: Vec<u8>,
+    time_ms: i64,
+    is_key: bool,
+}
+
+// Each chunk has its own encoder, so it starts with a key frame and the oldest chunk can be
+// dropped without re-encoding the rest
+#[cfg(feature = "webm")]
+#[derive(Default)]
+struct WebmChunk {
+    frames: Vec<EncodedFrame>,
+    num_screens: u32,
+}
+
+// Frames are encoded as soon as they are captured, so only compressed video is kept in memory
+#[cfg(feature = "webm")]
+struct WebmRecorder {
+    scale: u32,
+    filter: ScaleFilter,
+    screen_size: (u32, u32),
+    frame_size: (u32, u32),
+    encoder: Option<vpx_encode::Encoder>,
+    chunks: VecDeque<WebmChunk>,
+    last_frame_count: Option<u32>,
+}
+
+#[cfg(feature = "webm")]
+impl WebmRecorder {
+    fn new(scale: u32, filter: ScaleFilter) -> Self {
+        let filter = filter.for_scale(scale);
+        Self {
+            scale: scale / filter.factor(),
+            filter,
+            screen_size: (0, 0),
+            frame_size: (0, 0),
+            encoder: None,
+            chunks: VecDeque::new(),
+            last_frame_count: None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.encoder = None;
+        self.chunks.clear();
+        self.last_frame_count = None;
+    }
+
+    fn capture(
+        &mut self,
+        width: u32,
+        height: u32,
+        image: &[Color],
+        colors: &[Rgb24],
+        frame_count: u32,
+        fps: u32,
+        max_screens: u32,
+    ) {
+        if self
+            .last_frame_count
+            .is_some_and(|last_frame_count| frame_count <= last_frame_count)
+        {
+            // Frames drawn again within the same frame are skipped, while a rewound frame count
+            // starts over since the timestamps must increase
+            if self.last_frame_count == Some(frame_count) {
+                return;
             }
-            rgb_image.push(rgb_line);
+            self.reset();
+        }
+        if self.screen_size != (width, height) {
+            self.reset();
+            self.screen_size = (width, height);
+        }
+        let is_chunk_full = self
+            .chunks
+            .back()
+            .map_or(true, |chunk| chunk.num_screens >= fps * WEBM_CHUNK_SEC);
+        if (self.encoder.is_none() || is_chunk_full) && self.start_chunk(max_screens).is_err() {
+            log::warn!("Failed to encode the WebM screencast");
+            self.reset();
+            return;
+        }
+        let rgb_image = to_rgb_image(image, colors, width, height, self.filter);
+        let (frame_width, frame_height) = self.frame_size;
+        let buffer = Screencast::make_yuv_buffer(&rgb_image, self.scale, frame_width, frame_height);
+        let time_ms = frame_count as i64 * 1000 / fps as i64;
+        if self.encode(time_ms, &buffer).is_err() {
+            log::warn!("Failed to encode the WebM screencast");
+            self.reset();
+            return;
+        }
+        self.last_frame_count = Some(frame_count);
+    }
+
+    fn encode(&mut self, time_ms: i64, buffer: &[u8]) -> Result<(), vpx_encode::Error> {
+        let encoder = self.encoder.as_mut().unwrap();
+        let chunk = self.chunks.back_mut().unwrap();
+        for frame in encoder.encode(time_ms, buffer)? {
+            chunk.frames.push(EncodedFrame {
+                data: frame.data.to_vec(),
+                time_ms: frame.pts,
+                is_key: frame.key,
+            });
+        }
+        chunk.num_screens += 1;
+        Ok(())
+    }
+
+    fn start_chunk(&mut self, max_screens: u32) -> Result<(), vpx_encode::Error> {
+        self.finish_chunk()?;
+        while self.chunks.len() > 1
+            && self
+                .chunks
+                .iter()
+                .skip(1)
+                .map(|chunk| chunk.num_screens)
+                .sum::<u32>()
+                >= max_screens
+        {
+            self.chunks.pop_front();
+        }
+
+        // VP8 encodes 4:2:0 frames, so odd sizes are padded to even ones
+        let (width, height) = self.screen_size;
+        let factor = self.filter.factor() * self.scale;
+        self.frame_size = ((width * factor + 1) & !1, (height * factor + 1) & !1);
+        self.encoder = Some(vpx_encode::Encoder::new(vpx_encode::Config {
+            width: self.frame_size.0,
+            height: self.frame_size.1,
+            timebase: [1, 1000],
+            bitrate: WEBM_BITRATE_KBPS,
+            codec: vpx_encode::VideoCodecId::VP8,
+        })?);
+        self.chunks.push_back(WebmChunk::default());
+        Ok(())
+    }
+
+    fn finish_chunk(&mut self) -> Result<(), vpx_encode::Error> {
+        let Some(encoder) = self.encoder.take() else {
+            return Ok(());
+        };
+        let chunk = self.chunks.back_mut().unwrap();
+        let mut frames = encoder.finish()?;
+        while let Some(frame) = frames.next()? {
+            chunk.frames.push(EncodedFrame {
+                data: frame.data.to_vec(),
+                time_ms: frame.pts,
+                is_key: frame.key,
+            });
         }
-        rgb_image
+        Ok(())
+    }
+
+    fn save(&mut self, filename: &str) -> PyxelResult<()> {
+        let write_error = || PyxelError::FileWrite(filename.to_string());
+        self.finish_chunk().map_err(|_| write_error())?;
+        let frames: Vec<_> = self.chunks.iter().flat_map(|chunk| &chunk.frames).collect();
+        let Some(start_ms) = frames.first().map(|frame| frame.time_ms) else {
+            return Ok(());
+        };
+        let file = File::create(filename).map_err(|_| write_error())?;
+        let mut segment = mux::Segment::new(mux::Writer::new(file)).ok_or_else(write_error)?;
+        let (width, height) = self.frame_size;
+        let mut track = segment.add_video_track(width, height, None, mux::VideoCodecId::VP8);
+        for frame in frames {
+            let time_ns = (frame.time_ms - start_ms) as u64 * 1_000_000;
+            track.add_frame(&frame.data, time_ns, frame.is_key);
+        }
+        if !segment.finalize(None) {
+            return Err(write_error());
+        }
+        self.reset();
+        Ok(())
     }
 }
 
@@ -40,32 +269,68 @@ pub struct Screencast {
     screens: Vec<Screen>,
     capture_start_index: u32,
     num_captured_screens: u32,
+    format: ScreencastFormat,
+    #[cfg(feature = "webm")]
+    webm_recorder: Option<WebmRecorder>,
 }
 
 impl Screencast {
     pub fn new(fps: u32, capture_sec: u32) -> Self {
-        let max_screens = fps * capture_sec;
-        let screens = (0..max_screens)
-            .map(|_| Screen {
-                width: 0,
-                height: 0,
-                image: Vec::new(),
-                colors: Vec::new(),
-                frame_count: 0,
-            })
-            .collect();
+        Self::with_max_screens(fps, fps * capture_sec)
+    }
+
+    pub fn with_max_screens(fps: u32, max_screens: u32) -> Self {
+        let screens = (0..max_screens).map(|_| Screen::default()).collect();
         Self {
             fps,
             max_screens,
             screens,
             capture_start_index: 0,
             num_captured_screens: 0,
+            format: ScreencastFormat::Gif,
+            #[cfg(feature = "webm")]
+            webm_recorder: None,
         }
     }
 
     pub fn reset(&mut self) {
         self.capture_start_index = 0;
         self.num_captured_screens = 0;
+        #[cfg(feature = "webm")]
+        if let Some(webm_recorder) = &mut self.webm_recorder {
+            webm_recorder.reset();
+        }
+    }
+
+    pub fn format(&self) -> ScreencastFormat {
+        self.format
+    }
+
+    // WebM frames are encoded as they are captured, so the scale and filter are fixed here
+    #[cfg_attr(not(feature = "webm"), allow(unused_variables))]
+    pub fn set_format(&mut self, format: ScreencastFormat, scale: u32, filter: ScaleFilter) {
+        self.reset();
+        self.format = format;
+        #[cfg(feature = "webm")]
+        {
+            self.webm_recorder =
+                (format == ScreencastFormat::Webm).then(|| WebmRecorder::new(scale, filter));
+        }
+    }
+
+    // The most recent frames that fit in the new length are kept
+    pub fn set_capture_sec(&mut self, capture_sec: u32) {
+        let max_screens = self.fps * capture_sec;
+        let num_kept_screens = min(self.num_captured_screens, max_screens);
+        let mut screens: Vec<Screen> = (self.num_captured_screens - num_kept_screens
+            ..self.num_captured_screens)
+            .map(|index| mem::take(self.screen_mut(index)))
+            .collect();
+        screens.resize_with(max_screens as usize, Screen::default);
+        self.screens = screens;
+        self.max_screens = max_screens;
+        self.capture_start_index = 0;
+        self.num_captured_screens = num_kept_screens;
     }
 
     pub fn capture(
@@ -79,7 +344,24 @@ impl Screencast {
         if self.screens.is_empty() {
             return;
         }
-        if self.num_captured_screens == self.max_screens {
+        #[cfg(feature = "webm")]
+        if let Some(webm_recorder) = &mut self.webm_recorder {
+            webm_recorder.capture(
+                width,
+                height,
+                image,
+                colors,
+                frame_count,
+                self.fps,
+                self.max_screens,
+            );
+            return;
+        }
+        if self.num_captured_screens > 0
+            && self.screen(self.num_captured_screens - 1).frame_count == frame_count
+        {
+            self.num_captured_screens -= 1;
+        } else if self.num_captured_screens == self.max_screens {
             self.capture_start_index = (self.capture_start_index + 1) % self.max_screens;
             self.num_captured_screens -= 1;
         }
@@ -93,13 +375,15 @@ impl Screencast {
         self.num_captured_screens += 1;
     }
 
-    pub fn save(&mut self, filename: &str, scale: u32) {
+    pub fn save(&mut self, filename: &str, scale: u32, filter: ScaleFilter) -> PyxelResult<()> {
         if self.num_captured_screens == 0 {
-            return;
+            return Ok(());
         }
+        let filter = filter.for_scale(scale);
+        let factor = filter.factor();
         let filename = add_file_extension(filename, ".gif");
         let mut file =
-            File::create(&filename).unwrap_or_else(|_| panic!("Unable to open file '{filename}'"));
+            File::create(&filename).map_err(|_| PyxelError::FileWrite(filename.clone()))?;
         let screen = self.screen(0);
         let mut encoder = Encoder::new(
             &mut file,
@@ -111,11 +395,11 @@ impl Screencast {
         encoder.set_repeat(Repeat::Infinite).unwrap();
 
         // Write first frame
-        let mut base_image = screen.to_rgb_image();
+        let mut base_image = screen.to_rgb_image(filter);
         let (rect, palette, buffer) = Self::make_gif_buffer(
-            RectArea::new(0, 0, screen.width, screen.height),
+            RectArea::new(0, 0, screen.width * factor, screen.height * factor),
             &base_image,
-            scale,
+            scale / factor,
         );
         encoder
             .write_frame(&Frame {
@@ -136,9 +420,9 @@ impl Screencast {
         // Write subsequent frames
         for i in 1..self.num_captured_screens {
             let screen = &self.screen(i);
-            let image = screen.to_rgb_image();
+            let image = screen.to_rgb_image(filter);
             let (rect, image) = Self::make_diff_image(&mut base_image, &image);
-            let (rect, palette, buffer) = Self::make_gif_buffer(rect, &image, scale);
+            let (rect, palette, buffer) = Self::make_gif_buffer(rect, &image, scale / factor);
             encoder
                 .write_frame(&Frame {
                     delay: self.screen_delay(i),
@@ -156,12 +440,25 @@ impl Screencast {
                 .unwrap();
         }
         self.reset();
+        Ok(())
+    }
+
+    #[cfg(feature = "webm")]
+    pub fn save_webm(&mut self, filename: &str) -> PyxelResult<()> {
+        let Some(webm_recorder) = &mut self.webm_recorder else {
+            return Ok(());
+        };
+        webm_recorder.save(&add_file_extension(filename, ".webm"))
     }
 
     fn screen(&self, index: u32) -> &Screen {
         &self.screens[((self.capture_start_index + index) % self.max_screens) as usize]
     }
 
+    fn screen_mut(&mut self, index: u32) -> &mut Screen {
+        &mut self.screens[((self.capture_start_index + index) % self.max_screens) as usize]
+    }
+
     fn screen_delay(&self, index: u32) -> u16 {
         let frame_count = self.screen(index).frame_count;
         let next_frame_count = self.screen(index + 1).frame_count;
@@ -233,6 +530,39 @@ impl Screencast {
         (rect, palette, buffer)
     }
 
+    #[cfg(feature = "webm")]
+    fn make_yuv_buffer(image: &[Vec<Rgb24>], scale: u32, width: u32, height: u32) -> Vec<u8> {
+        let image_width = image[0].len() as u32;
+        let image_height = image.len() as u32;
+        let rgb = |x: u32, y: u32| {
+            let rgb = image[(y / scale).min(image_height - 1) as usize]
+                [(x / scale).min(image_width - 1) as usize];
+            (
+                (rgb >> 16) as u8 as i32,
+                (rgb >> 8) as u8 as i32,
+                rgb as u8 as i32,
+            )
+        };
+
+        // Converts with the BT.601 coefficients and samples chroma once per 2x2 block
+        let mut buffer = Vec::with_capacity((width * height * 3 / 2) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let (r, g, b) = rgb(x, y);
+                buffer.push((((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8);
+            }
+        }
+        for (r_coef, g_coef, b_coef) in [(-38, -74, 112), (112, -94, -18)] {
+            for y in (0..height).step_by(2) {
+                for x in (0..width).step_by(2) {
+                    let (r, g, b) = rgb(x, y);
+                    buffer.push((((r_coef * r + g_coef * g + b_coef * b + 128) >> 8) + 128) as u8);
+                }
+            }
+        }
+        buffer
+    }
+
     fn make_diff_image(
         base_image: &mut [Vec<Rgb24>],
         new_image: &[Vec<Rgb24>],
@@ -279,3 +609,34 @@ impl Screencast {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captured_frame_counts(screencast: &Screencast) -> Vec<u32> {
+        (0..screencast.num_captured_screens)
+            .map(|index| screencast.screen(index).frame_count)
+            .collect()
+    }
+
+    #[test]
+    fn test_set_capture_sec() {
+        let mut screencast = Screencast::new(2, 3);
+        for frame_count in 0..8 {
+            screencast.capture(1, 1, &[0], &[0], frame_count);
+        }
+        assert_eq!(captured_frame_counts(&screencast), [2, 3, 4, 5, 6, 7]);
+
+        screencast.set_capture_sec(2);
+        assert_eq!(captured_frame_counts(&screencast), [4, 5, 6, 7]);
+
+        screencast.set_capture_sec(4);
+        assert_eq!(captured_frame_counts(&screencast), [4, 5, 6, 7]);
+        screencast.capture(1, 1, &[0], &[0], 8);
+        assert_eq!(captured_frame_counts(&screencast), [4, 5, 6, 7, 8]);
+
+        screencast.set_capture_sec(0);
+        assert!(captured_frame_counts(&screencast).is_empty());
+    }
+}
@@ -1,22 +1,57 @@
+use log::LevelFilter;
+
 use crate::channel::{Note, Speed, Volume};
 use crate::image::{Color, Rgb24};
-use crate::keys::{Key, KEY_ESCAPE};
-use crate::oscillator::{Effect, Gain};
-use crate::tone::{Noise, Waveform};
+use crate::keys::{Key, KeyValue, KEY_ESCAPE, KEY_NONE};
+use crate::oscillator::{Effect, Envelope, Gain};
+use crate::tone::{Amp4, Noise, Waveform};
 
 // System
 pub const VERSION: &str = "2.0.7";
 pub const WORKING_DIR: &str = ".pyxel";
 pub const WATCH_INFO_FILE_ENVVAR: &str = "PYXEL_WATCH_INFO_FILE";
+pub const USER_CONFIG_FILE: &str = "pyxel.toml";
+pub const DISPLAY_SCALE_ENVVAR: &str = "PYXEL_DISPLAY_SCALE";
+pub const SCALING_MODE_ENVVAR: &str = "PYXEL_SCALING_MODE";
+pub const FULLSCREEN_ENVVAR: &str = "PYXEL_FULLSCREEN";
+pub const VSYNC_ENVVAR: &str = "PYXEL_VSYNC";
+pub const LOG_LEVEL_ENVVAR: &str = "PYXEL_LOG";
+pub const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Warn;
 pub const DEFAULT_TITLE: &str = "Pyxel";
 pub const DEFAULT_FPS: u32 = 30;
+pub const DEFAULT_VSYNC: bool = true;
 pub const DEFAULT_QUIT_KEY: Key = KEY_ESCAPE;
+pub const DEFAULT_PAUSE_KEY: Key = KEY_NONE;
+pub const PAUSE_MENU_VOLUME_STEPS: u32 = 10;
+pub const PAUSE_MENU_MAX_SCALE: u32 = 8;
 pub const DEFAULT_CAPTURE_SCALE: u32 = 2;
 pub const DEFAULT_CAPTURE_SEC: u32 = 10;
+pub const DEFAULT_CAPTURE_NAME: &str = "pyxel-{datetime}";
 pub const DISPLAY_RATIO: f64 = 0.75;
+pub const SCALING_INTEGER: u32 = 0;
+pub const SCALING_STRETCH: u32 = 1;
+pub const SCALING_FIT: u32 = 2;
+pub const TRANSITION_FADE: u32 = 0;
+pub const TRANSITION_WIPE: u32 = 1;
+pub const TRANSITION_PIXELATE: u32 = 2;
+pub const MAX_PIXELATE_SIZE: u32 = 16;
+pub const TEXT_ALIGN_LEFT: u32 = 0;
+pub const TEXT_ALIGN_CENTER: u32 = 1;
+pub const TEXT_ALIGN_RIGHT: u32 = 2;
+pub const COLOR_FILTER_NONE: u32 = 0;
+pub const COLOR_FILTER_PROTANOPIA: u32 = 1;
+pub const COLOR_FILTER_DEUTERANOPIA: u32 = 2;
+pub const COLOR_FILTER_TRITANOPIA: u32 = 3;
+pub const COLOR_FILTER_HIGH_CONTRAST: u32 = 4;
+pub const DEBUG_WINDOW_TITLE: &str = "Pyxel Debug";
+pub const DEBUG_WINDOW_SCALE: u32 = 2;
+pub const DEBUG_CONSOLE_MAX_LINES: u32 = 64;
 pub const BACKGROUND_COLOR: Rgb24 = 0x202224;
-pub const MAX_ELAPSED_MS: u32 = 100;
+pub const DEFAULT_MAX_UPDATE_PER_FRAME: u32 = u32::MAX;
+pub const DEFAULT_MAX_ELAPSED_MS: u32 = 100;
 pub const NUM_MEASURE_FRAMES: u32 = 10;
+pub const NUM_PERF_HISTOGRAM_BINS: u32 = 64;
+pub const NUM_PERF_MONITOR_LEVELS: u32 = 4;
 pub const ICON_SIZE: u32 = 16;
 pub const ICON_SCALE: u32 = 4;
 pub const ICON_COLKEY: Option<Color> = Some(0);
@@ -42,10 +77,30 @@ pub const ICON_DATA: [&str; ICON_SIZE as usize] = [
 // Resource
 pub const APP_FILE_EXTENSION: &str = ".pyxapp";
 pub const APP_STARTUP_SCRIPT_FILE: &str = ".pyxapp_startup_script";
+pub const APP_METADATA_FILE: &str = ".pyxapp_metadata";
 pub const RESOURCE_FILE_EXTENSION: &str = ".pyxres";
 pub const RESOURCE_ARCHIVE_NAME: &str = "pyxel_resource.toml";
 pub const RESOURCE_FORMAT_VERSION: u32 = 3;
+pub const RESOURCE_READ_CHUNK_SIZE: usize = 64 * 1024;
+pub const MAX_RESOURCE_TEXT_SIZE: u64 = 256 * 1024 * 1024;
+pub const MAX_RESOURCE_SIZE: u32 = 4096;
+pub const MAX_RESOURCE_CELLS: u64 = 64 * 1024 * 1024;
+pub const STATE_ARCHIVE_NAME: &str = "pyxel_state.toml";
+pub const STATE_FORMAT_VERSION: u32 = 3;
 pub const PALETTE_FILE_EXTENSION: &str = ".pyxpal";
+pub const SAVE_DATA_DIR: &str = "pyxel";
+pub const SAVE_DATA_FILE_EXTENSION: &str = ".pyxsav";
+pub const CAPTURE_FILTER_NEAREST: u32 = 0;
+pub const CAPTURE_FILTER_SCALE2X: u32 = 1;
+pub const CAPTURE_FILTER_SCALE3X: u32 = 2;
+pub const SCREENCAST_FORMAT_GIF: u32 = 0;
+pub const SCREENCAST_FORMAT_WEBM: u32 = 1;
+
+// Input
+pub const HOT_NONE: u32 = 0;
+pub const HOT_HOVER: u32 = 1;
+pub const HOT_PRESS: u32 = 2;
+pub const HOT_CLICK: u32 = 3;
 
 // Graphics
 pub const NUM_COLORS: u32 = 16;
@@ -80,6 +135,11 @@ pub const CURSOR_HEIGHT: u32 = 8;
 pub const CURSOR_DATA: [&str; CURSOR_HEIGHT as usize] = [
     "11111100", "17776100", "17761000", "17676100", "16167610", "11016761", "00001610", "00000100",
 ];
+pub const SYSTEM_CURSOR_ARROW: u32 = 0;
+pub const SYSTEM_CURSOR_CROSSHAIR: u32 = 1;
+pub const SYSTEM_CURSOR_HAND: u32 = 2;
+pub const SYSTEM_CURSOR_IBEAM: u32 = 3;
+pub const SYSTEM_CURSOR_WAIT: u32 = 4;
 pub const MIN_FONT_CODE: char = 32 as char;
 pub const MAX_FONT_CODE: char = 127 as char;
 pub const NUM_FONT_ROWS: u32 = 16;
@@ -104,18 +164,27 @@ pub const NUM_SCREEN_TYPES: u32 = 3;
 pub const CLOCK_RATE: u32 = 120_000_000; // 120MHz clock rate
 pub const SAMPLE_RATE: u32 = 44100; // 44.1kHz sample rate
 pub const NUM_SAMPLES: u32 = 1024; // 2^n ~ SAMPLE_RATE / DEFAULT_FPS;
+pub const NUM_OUTPUT_CHANNELS: u32 = 2; // Stereo
 pub const NUM_CLOCKS_PER_TICK: u32 = CLOCK_RATE / 120;
 pub const OSCILLATOR_RESOLUTION: u32 = 32;
 pub const VIBRATO_DEPTH: f64 = 0.015;
 pub const VIBRATO_FREQUENCY: f64 = 6.0;
 pub const NUM_CHANNELS: u32 = 4;
 pub const NUM_TONES: u32 = 4;
+pub const NUM_SAMPLE_SLOTS: u32 = 16;
 pub const NUM_SOUNDS: u32 = 64;
 pub const NUM_MUSICS: u32 = 8;
 pub const NUM_WAVEFORM_STEPS: u32 = 32;
+pub const MAX_WAVEFORM_AMP: Amp4 = 15;
 pub const INITIAL_CHANNEL_GAIN: Gain = 0.125;
 pub const INITIAL_SOUND_SPEED: Speed = 30;
 pub const INITIAL_NOISE_REG: u16 = 1;
+pub const INITIAL_ENVELOPE: Envelope = Envelope {
+    attack: 0,
+    decay: 0,
+    sustain: 1.0,
+    release: 0,
+};
 pub const TONE_TRIANGLE: u32 = 0;
 pub const TONE_SQUARE: u32 = 1;
 pub const TONE_PULSE: u32 = 2;
@@ -124,10 +193,24 @@ pub const EFFECT_NONE: Effect = 0;
 pub const EFFECT_SLIDE: Effect = 1;
 pub const EFFECT_VIBRATO: Effect = 2;
 pub const EFFECT_FADEOUT: Effect = 3;
+pub const EFFECT_HALF_FADEOUT: Effect = 4;
 pub const MAX_TONE: u32 = 9;
+pub const DEFAULT_SAMPLE_NOTE: Note = 33; // Plays at the recorded speed
 pub const MAX_NOTE: Note = 12 * 5 - 1; // 5 octaves
+pub const MIDI_NOTE_OFFSET: Note = 36; // MIDI note 69 (A4) -> 33 (A2)
+pub const MAX_MIDI_VELOCITY: KeyValue = 127;
 pub const MAX_VOLUME: Volume = 7;
-pub const MAX_EFFECT: Effect = 3;
+pub const MAX_EFFECT: Effect = 4;
+pub const STREAM_GAIN: f64 = 0.5;
+pub const REVERB_COMB_DELAYS: [u32; 4] = [1116, 1188, 1277, 1356];
+pub const REVERB_ALLPASS_DELAYS: [u32; 2] = [556, 441];
+pub const REVERB_FEEDBACK: f64 = 0.84;
+pub const REVERB_DAMPING: f64 = 0.2;
+pub const REVERB_ALLPASS_FEEDBACK: f64 = 0.5;
+pub const REVERB_GAIN: f64 = 0.05;
+pub const NUM_STREAM_FRAMES_PER_READ: u32 = 1024;
+pub const MAX_STREAM_BUFFERED_SAMPLES: u32 = 65536;
+pub const STREAM_WORKER_INTERVAL_MS: u64 = 10;
 pub const DEFAULT_TONES: [(Gain, Noise, Waveform); NUM_TONES as usize] = [
     // Triangle
     (
@@ -159,3 +242,7 @@ pub const DEFAULT_TONES: [(Gain, Noise, Waveform); NUM_TONES as usize] = [
     // Noise
     (0.6, Noise::LongPeriod, [0; 32]),
 ];
+
+// Network
+pub const MAX_NET_MESSAGE_SIZE: usize = 65507; // Largest UDP payload over IPv4
+pub const NET_PEER_TIMEOUT_SEC: u64 = 10;
@@ -1,10 +1,38 @@
 uniform vec2 u_screenPos;
 uniform vec2 u_screenSize;
-uniform float u_screenScale;
+uniform vec2 u_screenScale;
 uniform int u_numColors;
 uniform vec3 u_backgroundColor;
+uniform vec2 u_borderSize;
 uniform sampler2D u_screenTexture;
 uniform sampler2D u_colorsTexture;
+uniform sampler2D u_borderTexture;
+uniform int u_colorFilter;
+
+// Color vision deficiency simulation matrices from Machado et al. (2009) at full severity
+vec3 applyColorFilter(vec3 color) {
+    if (u_colorFilter == 1) {
+        color = vec3(
+            dot(vec3(0.152286, 1.052583, -0.204868), color),
+            dot(vec3(0.114503, 0.786281, 0.099216), color),
+            dot(vec3(-0.003882, -0.048116, 1.051998), color));
+    } else if (u_colorFilter == 2) {
+        color = vec3(
+            dot(vec3(0.367322, 0.860646, -0.227968), color),
+            dot(vec3(0.280085, 0.672501, 0.047413), color),
+            dot(vec3(-0.011820, 0.042940, 0.968881), color));
+    } else if (u_colorFilter == 3) {
+        color = vec3(
+            dot(vec3(1.255528, -0.076749, -0.178779), color),
+            dot(vec3(-0.078411, 0.930809, 0.147602), color),
+            dot(vec3(0.004733, 0.691367, 0.303900), color));
+    } else if (u_colorFilter == 4) {
+        float luminance = dot(vec3(0.299, 0.587, 0.114), color);
+        color = mix(vec3(luminance), color, 1.5);
+        color = (color - 0.5) * 1.6 + 0.5;
+    }
+    return clamp(color, 0.0, 1.0);
+}
 
 void getScreenParams(out vec2 screenFragCoord, out vec2 screenTexCoord) {
     screenFragCoord = gl_FragCoord.xy - u_screenPos;
@@ -19,5 +47,16 @@ bool isInScreen(vec2 screenTexCoord) {
 vec3 getScreenColor(vec2 screenTexCoord) {
     float indexColor = texture2D(u_screenTexture, screenTexCoord).r * 255.0;
     vec2 colorsTexCoord = vec2((indexColor + 0.5) / float(u_numColors), 0.5);
-    return texture2D(u_colorsTexture, colorsTexCoord).rgb;
+    return applyColorFilter(texture2D(u_colorsTexture, colorsTexCoord).rgb);
+}
+
+vec3 getBorderColor(vec2 screenFragCoord) {
+    if (u_borderSize.x <= 0.0 || u_borderSize.y <= 0.0) {
+        return applyColorFilter(u_backgroundColor);
+    }
+    vec2 borderFragCoord = vec2(screenFragCoord.x, u_screenSize.y - screenFragCoord.y);
+    vec2 borderTexCoord = fract(borderFragCoord / u_borderSize);
+    float indexColor = texture2D(u_borderTexture, borderTexCoord).r * 255.0;
+    vec2 colorsTexCoord = vec2((indexColor + 0.5) / float(u_numColors), 0.5);
+    return applyColorFilter(texture2D(u_colorsTexture, colorsTexCoord).rgb);
 }
@@ -79,7 +79,7 @@ void main() {
     vec2 screenFragCoord, screenTexCoord;
     getScreenParams(screenFragCoord, screenTexCoord);
     if (!isInScreen(screenTexCoord)) {
-        FragColor = vec4(u_backgroundColor, 1.0);
+        FragColor = vec4(getBorderColor(screenFragCoord), 1.0);
         return;
     }
 
@@ -0,0 +1,214 @@
+use crate::image::Color;
+
+const LANES: usize = 16;
+
+#[allow(clippy::cast_ptr_alignment)] // Unaligned loads and stores are used
+pub fn copy_row_with_transparent(dst: &mut [Color], src: &[Color], transparent: Color) {
+    let len = dst.len().min(src.len());
+    #[allow(unused_mut)]
+    let mut start = 0;
+
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        use std::arch::x86_64::{
+            __m128i, _mm_and_si128, _mm_andnot_si128, _mm_cmpeq_epi8, _mm_loadu_si128,
+            _mm_or_si128, _mm_set1_epi8, _mm_storeu_si128,
+        };
+
+        // SSE2 is always available on x86_64
+        let key = _mm_set1_epi8(transparent as i8);
+        while start + LANES <= len {
+            let src_ptr = src.as_ptr().add(start).cast::<__m128i>();
+            let dst_ptr = dst.as_mut_ptr().add(start).cast::<__m128i>();
+            let src_values = _mm_loadu_si128(src_ptr);
+            let dst_values = _mm_loadu_si128(dst_ptr);
+            let mask = _mm_cmpeq_epi8(src_values, key);
+            let values = _mm_or_si128(
+                _mm_and_si128(mask, dst_values),
+                _mm_andnot_si128(mask, src_values),
+            );
+            _mm_storeu_si128(dst_ptr, values);
+            start += LANES;
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        use std::arch::aarch64::{vbslq_u8, vceqq_u8, vdupq_n_u8, vld1q_u8, vst1q_u8};
+
+        // NEON is always available on aarch64
+        let key = vdupq_n_u8(transparent);
+        while start + LANES <= len {
+            let src_values = vld1q_u8(src.as_ptr().add(start));
+            let dst_values = vld1q_u8(dst.as_ptr().add(start));
+            let mask = vceqq_u8(src_values, key);
+            vst1q_u8(
+                dst.as_mut_ptr().add(start),
+                vbslq_u8(mask, dst_values, src_values),
+            );
+            start += LANES;
+        }
+    }
+
+    for (dst_value, value) in dst[start..len].iter_mut().zip(&src[start..len]) {
+        if *value != transparent {
+            *dst_value = *value;
+        }
+    }
+}
+
+pub fn remap_row(dst: &mut [Color], src: &[Color], palette: &[Color], transparent: Option<Color>) {
+    let len = dst.len().min(src.len());
+    #[allow(unused_mut)]
+    let mut start = 0;
+
+    // SSE2 has no byte shuffle, so table lookups need SSSE3, which is checked at runtime
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("ssse3") {
+            start = unsafe { remap_row_ssse3(dst, src, &remap_table(palette), transparent, len) };
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        use std::arch::aarch64::{
+            uint8x16x4_t, vbslq_u8, vceqq_u8, vdupq_n_u8, vld1q_u8, vld1q_u8_x4, vqtbl4q_u8,
+            vqtbx4q_u8, vst1q_u8, vsubq_u8,
+        };
+
+        // Four 64-entry table lookups cover every 8-bit color
+        let table = remap_table(palette);
+        let tables: [uint8x16x4_t; 4] =
+            std::array::from_fn(|i| vld1q_u8_x4(table.as_ptr().add(i * 64)));
+        let offset = vdupq_n_u8(64);
+        let key = vdupq_n_u8(transparent.unwrap_or(0));
+        while start + LANES <= len {
+            let src_values = vld1q_u8(src.as_ptr().add(start));
+            let mut indices = src_values;
+            let mut values = vqtbl4q_u8(tables[0], indices);
+            for table in &tables[1..] {
+                indices = vsubq_u8(indices, offset);
+                values = vqtbx4q_u8(values, *table, indices);
+            }
+            if transparent.is_some() {
+                let dst_values = vld1q_u8(dst.as_ptr().add(start));
+                let mask = vceqq_u8(src_values, key);
+                values = vbslq_u8(mask, dst_values, values);
+            }
+            vst1q_u8(dst.as_mut_ptr().add(start), values);
+            start += LANES;
+        }
+    }
+
+    for (dst_value, value) in dst[start..len].iter_mut().zip(&src[start..len]) {
+        if transparent != Some(*value) {
+            *dst_value = palette.get(*value as usize).copied().unwrap_or(*value);
+        }
+    }
+}
+
+// Colors beyond the end of the palette are left as they are
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn remap_table(palette: &[Color]) -> [Color; 256] {
+    std::array::from_fn(|i| palette.get(i).copied().unwrap_or(i as Color))
+}
+
+// Returns the number of colors remapped, which is a multiple of LANES
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "ssse3")]
+#[allow(clippy::cast_ptr_alignment)] // Unaligned loads and stores are used
+unsafe fn remap_row_ssse3(
+    dst: &mut [Color],
+    src: &[Color],
+    table: &[Color; 256],
+    transparent: Option<Color>,
+    len: usize,
+) -> usize {
+    use std::arch::x86_64::{
+        __m128i, _mm_and_si128, _mm_andnot_si128, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_or_si128,
+        _mm_set1_epi8, _mm_setzero_si128, _mm_shuffle_epi8, _mm_srli_epi16, _mm_storeu_si128,
+    };
+
+    // Sixteen 16-entry table lookups cover every 8-bit color, selected by the high nibble
+    let tables: [__m128i; 16] =
+        std::array::from_fn(|i| _mm_loadu_si128(table.as_ptr().add(i * 16).cast::<__m128i>()));
+    let low_mask = _mm_set1_epi8(0x0f);
+    let key = _mm_set1_epi8(transparent.unwrap_or(0) as i8);
+    let mut start = 0;
+    while start + LANES <= len {
+        let src_ptr = src.as_ptr().add(start).cast::<__m128i>();
+        let dst_ptr = dst.as_mut_ptr().add(start).cast::<__m128i>();
+        let src_values = _mm_loadu_si128(src_ptr);
+        let low = _mm_and_si128(src_values, low_mask);
+        let high = _mm_and_si128(_mm_srli_epi16(src_values, 4), low_mask);
+        let mut values = _mm_setzero_si128();
+        for (i, table) in tables.iter().enumerate() {
+            let mask = _mm_cmpeq_epi8(high, _mm_set1_epi8(i as i8));
+            values = _mm_or_si128(values, _mm_and_si128(mask, _mm_shuffle_epi8(*table, low)));
+        }
+        if transparent.is_some() {
+            let dst_values = _mm_loadu_si128(dst_ptr);
+            let mask = _mm_cmpeq_epi8(src_values, key);
+            values = _mm_or_si128(
+                _mm_and_si128(mask, dst_values),
+                _mm_andnot_si128(mask, values),
+            );
+        }
+        _mm_storeu_si128(dst_ptr, values);
+        start += LANES;
+    }
+    start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_values(len: usize) -> Vec<Color> {
+        (0..len).map(|i| (i * 7 % 19) as Color).collect()
+    }
+
+    #[test]
+    fn test_copy_row_with_transparent() {
+        for len in [0, 5, 16, 37] {
+            let src = test_values(len);
+            let mut dst = vec![100; len];
+            copy_row_with_transparent(&mut dst, &src, 0);
+            for (dst_value, value) in dst.iter().zip(&src) {
+                assert_eq!(*dst_value, if *value == 0 { 100 } else { *value });
+            }
+        }
+    }
+
+    #[test]
+    fn test_remap_row() {
+        let palette: Vec<Color> = (0..255).map(|i| (254 - i) as Color).collect();
+        for len in [0, 5, 16, 37] {
+            let src = test_values(len);
+            let mut dst = vec![100; len];
+            remap_row(&mut dst, &src, &palette, Some(3));
+            for (dst_value, value) in dst.iter().zip(&src) {
+                assert_eq!(*dst_value, if *value == 3 { 100 } else { 254 - *value });
+            }
+            remap_row(&mut dst, &src, &palette, None);
+            for (dst_value, value) in dst.iter().zip(&src) {
+                assert_eq!(*dst_value, 254 - *value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_remap_row_out_of_palette() {
+        let palette: Vec<Color> = (0..8).map(|i| (i + 100) as Color).collect();
+        for len in [5, 16, 37] {
+            let src: Vec<Color> = (0..len).map(|i| (i * 37 % 256) as Color).collect();
+            let mut dst = vec![0; len];
+            remap_row(&mut dst, &src, &palette, None);
+            for (dst_value, value) in dst.iter().zip(&src) {
+                let expected = if *value < 8 { *value + 100 } else { *value };
+                assert_eq!(*dst_value, expected);
+            }
+        }
+    }
+}
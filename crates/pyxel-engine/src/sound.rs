@@ -1,9 +1,13 @@
+use crate::audio;
 use crate::channel::{Note, Speed, Volume};
+use crate::error::PyxelResult;
 use crate::oscillator::Effect;
 use crate::settings::{
-    EFFECT_FADEOUT, EFFECT_NONE, EFFECT_SLIDE, EFFECT_VIBRATO, INITIAL_SOUND_SPEED, TONE_NOISE,
-    TONE_PULSE, TONE_SQUARE, TONE_TRIANGLE,
+    EFFECT_FADEOUT, EFFECT_HALF_FADEOUT, EFFECT_NONE, EFFECT_SLIDE, EFFECT_VIBRATO,
+    INITIAL_SOUND_SPEED, MAX_EFFECT, MAX_NOTE, MAX_TONE, MAX_VOLUME, NUM_OUTPUT_CHANNELS,
+    SAMPLE_RATE, TONE_NOISE, TONE_PULSE, TONE_SQUARE, TONE_TRIANGLE,
 };
+use crate::stream;
 use crate::utils::simplify_string;
 
 #[derive(Clone)]
@@ -44,88 +48,130 @@ impl Sound {
     }
 
     pub fn set_notes(&mut self, note_str: &str) {
-        let note_str = simplify_string(note_str);
-        let mut chars = note_str.chars();
-        self.notes.clear();
-        while let Some(c) = chars.next() {
-            let mut note: Note;
-            if ('a'..='g').contains(&c) {
-                note = match c {
-                    'c' => 0,
-                    'd' => 2,
-                    'e' => 4,
-                    'f' => 5,
-                    'g' => 7,
-                    'a' => 9,
-                    'b' => 11,
-                    _ => panic!("Invalid sound note '{c}'"),
-                };
-                let mut c = chars.next().unwrap_or(0 as char);
-                if c == '#' {
-                    note += 1;
-                    c = chars.next().unwrap_or(0 as char);
-                } else if c == '-' {
-                    note -= 1;
-                    c = chars.next().unwrap_or(0 as char);
-                }
-                if ('0'..='4').contains(&c) {
-                    note += (c.to_digit(10).unwrap() as Note) * 12;
-                } else {
-                    panic!("Invalid sound note '{c}'");
-                }
-            } else if c == 'r' {
-                note = -1;
-            } else {
-                panic!("Invalid sound note '{c}'");
-            }
-            self.notes.push(note);
-        }
+        self.notes = parse_notes(note_str).unwrap_or_else(|message| panic!("{message}"));
     }
 
     pub fn set_tones(&mut self, tone_str: &str) {
-        self.tones.clear();
-        for c in simplify_string(tone_str).chars() {
-            let tone = match c {
-                't' => TONE_TRIANGLE,
-                's' => TONE_SQUARE,
-                'p' => TONE_PULSE,
-                'n' => TONE_NOISE,
-                '0'..='9' => c.to_digit(10).unwrap(),
-                _ => panic!("Invalid sound tone '{c}'"),
-            };
-            self.tones.push(tone);
-        }
+        self.tones = parse_tones(tone_str).unwrap_or_else(|message| panic!("{message}"));
     }
 
     pub fn set_volumes(&mut self, volume_str: &str) {
-        self.volumes.clear();
-        for c in simplify_string(volume_str).chars() {
-            if ('0'..='7').contains(&c) {
-                self.volumes.push(c.to_digit(10).unwrap() as Volume);
-            } else {
-                panic!("Invalid sound volume '{c}'");
-            }
-        }
+        self.volumes = parse_volumes(volume_str).unwrap_or_else(|message| panic!("{message}"));
     }
 
     pub fn set_effects(&mut self, effect_str: &str) {
-        self.effects.clear();
-        for c in simplify_string(effect_str).chars() {
-            let effect = match c {
-                'n' => EFFECT_NONE,
-                's' => EFFECT_SLIDE,
-                'v' => EFFECT_VIBRATO,
-                'f' => EFFECT_FADEOUT,
-                _ => panic!("Invalid sound effect '{c}'"),
+        self.effects = parse_effects(effect_str).unwrap_or_else(|message| panic!("{message}"));
+    }
+
+    // Channels assert these ranges while playing, so loaders reject sounds outside them
+    pub(crate) fn is_valid(&self) -> bool {
+        self.speed > 0
+            && self.notes.iter().all(|note| *note <= MAX_NOTE)
+            && self.tones.iter().all(|tone| *tone <= MAX_TONE)
+            && self.volumes.iter().all(|volume| *volume <= MAX_VOLUME)
+            && self.effects.iter().all(|effect| *effect <= MAX_EFFECT)
+    }
+
+    // Renders a mono mix of the stereo output of channel 0
+    pub fn render(&self, sample_rate: u32) -> Vec<i16> {
+        audio::render_sounds(vec![vec![self.clone()]], sample_rate, None)
+            .chunks_exact(NUM_OUTPUT_CHANNELS as usize)
+            .map(|frame| {
+                (frame.iter().map(|sample| *sample as i32).sum::<i32>() / frame.len() as i32) as i16
+            })
+            .collect()
+    }
+
+    pub fn save(&self, filename: &str, sec: f64) -> PyxelResult<()> {
+        let max_frames = (sec * SAMPLE_RATE as f64) as usize;
+        let samples = audio::render_sounds(vec![vec![self.clone()]], SAMPLE_RATE, Some(max_frames));
+        stream::save_wav_file(filename, &samples, SAMPLE_RATE, NUM_OUTPUT_CHANNELS)
+    }
+}
+
+// The parsers report invalid characters as errors so that they can also be checked without panicking
+pub(crate) fn parse_notes(note_str: &str) -> Result<Vec<Note>, String> {
+    let note_str = simplify_string(note_str);
+    let mut chars = note_str.chars();
+    let mut notes = Vec::new();
+    while let Some(c) = chars.next() {
+        let mut note: Note;
+        if ('a'..='g').contains(&c) {
+            note = match c {
+                'c' => 0,
+                'd' => 2,
+                'e' => 4,
+                'f' => 5,
+                'g' => 7,
+                'a' => 9,
+                'b' => 11,
+                _ => return Err(format!("Invalid sound note '{c}'")),
             };
-            self.effects.push(effect);
+            let mut c = chars.next().unwrap_or(0 as char);
+            if c == '#' {
+                note += 1;
+                c = chars.next().unwrap_or(0 as char);
+            } else if c == '-' {
+                note -= 1;
+                c = chars.next().unwrap_or(0 as char);
+            }
+            if ('0'..='4').contains(&c) {
+                note += (c.to_digit(10).unwrap() as Note) * 12;
+            } else {
+                return Err(format!("Invalid sound note '{c}'"));
+            }
+        } else if c == 'r' {
+            note = -1;
+        } else {
+            return Err(format!("Invalid sound note '{c}'"));
         }
+        notes.push(note);
     }
+    Ok(notes)
+}
+
+pub(crate) fn parse_tones(tone_str: &str) -> Result<Vec<u32>, String> {
+    simplify_string(tone_str)
+        .chars()
+        .map(|c| match c {
+            't' => Ok(TONE_TRIANGLE),
+            's' => Ok(TONE_SQUARE),
+            'p' => Ok(TONE_PULSE),
+            'n' => Ok(TONE_NOISE),
+            '0'..='9' => Ok(c.to_digit(10).unwrap()),
+            _ => Err(format!("Invalid sound tone '{c}'")),
+        })
+        .collect()
+}
+
+pub(crate) fn parse_volumes(volume_str: &str) -> Result<Vec<Volume>, String> {
+    simplify_string(volume_str)
+        .chars()
+        .map(|c| match c {
+            '0'..='7' => Ok(c.to_digit(10).unwrap() as Volume),
+            _ => Err(format!("Invalid sound volume '{c}'")),
+        })
+        .collect()
+}
+
+pub(crate) fn parse_effects(effect_str: &str) -> Result<Vec<Effect>, String> {
+    simplify_string(effect_str)
+        .chars()
+        .map(|c| match c {
+            'n' => Ok(EFFECT_NONE),
+            's' => Ok(EFFECT_SLIDE),
+            'v' => Ok(EFFECT_VIBRATO),
+            'f' => Ok(EFFECT_FADEOUT),
+            'h' => Ok(EFFECT_HALF_FADEOUT),
+            _ => Err(format!("Invalid sound effect '{c}'")),
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::settings::{CLOCK_RATE, NUM_CLOCKS_PER_TICK};
 
     #[test]
     fn test_sound_new() {
@@ -183,10 +229,52 @@ mod tests {
     #[test]
     fn test_sound_set_effect() {
         let sound = Sound::new();
-        sound.lock().set_effects(" n s v f ");
+        sound.lock().set_effects(" n s v f h ");
         assert_eq!(
             &sound.lock().effects,
-            &vec![EFFECT_NONE, EFFECT_SLIDE, EFFECT_VIBRATO, EFFECT_FADEOUT]
+            &vec![
+                EFFECT_NONE,
+                EFFECT_SLIDE,
+                EFFECT_VIBRATO,
+                EFFECT_FADEOUT,
+                EFFECT_HALF_FADEOUT
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_strings() {
+        assert_eq!(parse_notes("c5"), Err("Invalid sound note '5'".to_string()));
+        assert_eq!(
+            parse_notes("c#"),
+            Err("Invalid sound note '\0'".to_string())
+        );
+        assert_eq!(parse_tones("x"), Err("Invalid sound tone 'x'".to_string()));
+        assert_eq!(
+            parse_volumes("8"),
+            Err("Invalid sound volume '8'".to_string())
         );
+        assert_eq!(
+            parse_effects("z"),
+            Err("Invalid sound effect 'z'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sound_render() {
+        // Renders use the settings of the global channels
+        let _pyxel = crate::pyxel::headless_pyxel(16, 16);
+        let sound = Sound::new();
+        assert!(sound.lock().render(SAMPLE_RATE).is_empty());
+
+        sound.lock().set("c2e2", "t", "7", "n", 12);
+        let samples = sound.lock().render(SAMPLE_RATE);
+        let expected_len = SAMPLE_RATE * 24 / (CLOCK_RATE / NUM_CLOCKS_PER_TICK);
+        assert!((samples.len() as i32 - expected_len as i32).abs() <= 1);
+        assert!(samples.iter().any(|sample| *sample != 0));
+
+        sound.lock().set_volumes("0");
+        let samples = sound.lock().render(SAMPLE_RATE);
+        assert!(samples.iter().all(|sample| *sample == 0));
     }
 }
@@ -0,0 +1,376 @@
+use crate::channel::Note;
+use crate::editor::{self, EditHistory, CANVAS_X, CANVAS_Y, TOOL_Y};
+use crate::image::Image;
+use crate::keys::{KEY_BACKSPACE, KEY_SPACE, KEY_Y, KEY_Z, MOUSE_BUTTON_LEFT, MOUSE_BUTTON_RIGHT};
+use crate::oscillator::Effect;
+use crate::pyxel::Pyxel;
+use crate::settings::{
+    COLOR_BLACK, COLOR_DARK_BLUE, COLOR_GRAY, COLOR_RED, COLOR_WHITE, COLOR_YELLOW, EFFECT_NONE,
+    FONT_HEIGHT, FONT_WIDTH, MAX_NOTE, MAX_VOLUME, TONE_TRIANGLE,
+};
+use crate::sound::{SharedSound, Sound};
+
+const MAX_SOUND_LENGTH: usize = 48;
+const ROLL_X: i32 = CANVAS_X + FONT_WIDTH as i32 * 3;
+const ROLL_Y: i32 = CANVAS_Y;
+const COLUMN_WIDTH: i32 = FONT_WIDTH as i32;
+const NOTE_HEIGHT: i32 = 2;
+const ROLL_WIDTH: i32 = COLUMN_WIDTH * MAX_SOUND_LENGTH as i32;
+const ROLL_HEIGHT: i32 = NOTE_HEIGHT * (MAX_NOTE as i32 + 1);
+const ROW_Y: i32 = ROLL_Y + ROLL_HEIGHT + 3;
+const ROW_HEIGHT: i32 = FONT_HEIGHT as i32 + 1;
+const ROW_LABELS: [&str; 3] = ["TON", "VOL", "EFX"];
+const TONE_CHARS: [char; 4] = ['T', 'S', 'P', 'N'];
+const EFFECT_CHARS: [char; 5] = ['N', 'S', 'V', 'F', 'H'];
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+const SOUND_LABEL_X: i32 = CANVAS_X;
+const SPEED_LABEL_X: i32 = CANVAS_X + FONT_WIDTH as i32 * 11;
+const PLAY_LABEL_X: i32 = CANVAS_X + FONT_WIDTH as i32 * 22;
+const PREVIEW_CHANNEL: u32 = 0;
+
+struct SoundRecord {
+    index: usize,
+    sound: Sound,
+}
+
+pub struct SoundEditor {
+    sound: usize,
+    is_playing: bool,
+    has_started: bool,
+    history: EditHistory<SoundRecord>,
+}
+
+// Keeps every row as long as the notes, so that each column can be edited on its own
+fn resize_sound(sound: &mut Sound, length: usize) {
+    sound.notes.resize(length, -1);
+    let tone = sound.tones.last().copied().unwrap_or(TONE_TRIANGLE);
+    sound.tones.resize(length, tone);
+    let volume = sound.volumes.last().copied().unwrap_or(MAX_VOLUME);
+    sound.volumes.resize(length, volume);
+    let effect = sound.effects.last().copied().unwrap_or(EFFECT_NONE);
+    sound.effects.resize(length, effect);
+}
+
+fn note_name(note: Note) -> String {
+    if note < 0 {
+        "REST".to_string()
+    } else {
+        format!("{}{}", NOTE_NAMES[note as usize % 12], note / 12)
+    }
+}
+
+impl SoundEditor {
+    pub fn new() -> Self {
+        Self {
+            sound: 0,
+            is_playing: false,
+            has_started: false,
+            history: EditHistory::new(),
+        }
+    }
+
+    fn shared_sound(&self, pyxel: &Pyxel) -> SharedSound {
+        pyxel.sounds.lock()[self.sound].clone()
+    }
+
+    fn push_undo(&mut self, pyxel: &Pyxel) {
+        let sound = self.shared_sound(pyxel).lock().clone();
+        self.history.push(SoundRecord {
+            index: self.sound,
+            sound,
+        });
+    }
+
+    fn undo(&mut self, pyxel: &Pyxel, undo: bool) {
+        let Some(mut record) = self.history.take(undo) else {
+            return;
+        };
+        self.sound = record.index;
+        let shared_sound = self.shared_sound(pyxel);
+        std::mem::swap(&mut *shared_sound.lock(), &mut record.sound);
+        self.history.put_back(record, undo);
+    }
+
+    fn roll_pos(pyxel: &Pyxel) -> Option<(usize, Note)> {
+        let column = (pyxel.mouse_x - ROLL_X).div_euclid(COLUMN_WIDTH);
+        let row = (pyxel.mouse_y - ROLL_Y).div_euclid(NOTE_HEIGHT);
+        ((0..MAX_SOUND_LENGTH as i32).contains(&column) && (0..=MAX_NOTE as i32).contains(&row))
+            .then_some((column as usize, MAX_NOTE - row as Note))
+    }
+
+    fn row_pos(pyxel: &Pyxel) -> Option<(usize, usize)> {
+        let column = (pyxel.mouse_x - ROLL_X).div_euclid(COLUMN_WIDTH);
+        let row = (pyxel.mouse_y - ROW_Y).div_euclid(ROW_HEIGHT);
+        ((0..MAX_SOUND_LENGTH as i32).contains(&column)
+            && (0..ROW_LABELS.len() as i32).contains(&row))
+        .then_some((column as usize, row as usize))
+    }
+
+    pub(crate) fn stop(&mut self, pyxel: &Pyxel) {
+        if self.is_playing {
+            pyxel.stop(PREVIEW_CHANNEL);
+            self.is_playing = false;
+        }
+    }
+
+    fn toggle_playback(&mut self, pyxel: &Pyxel) {
+        if self.is_playing {
+            pyxel.stop(PREVIEW_CHANNEL);
+            self.is_playing = false;
+        } else if !self.shared_sound(pyxel).lock().notes.is_empty() {
+            pyxel.play(PREVIEW_CHANNEL, &[self.sound as u32], None, false);
+            self.is_playing = true;
+            self.has_started = false;
+        }
+    }
+
+    // Playback may begin a few frames after the request, so it only counts as
+    // finished once the channel has been seen playing
+    fn update_playback_state(&mut self, pyxel: &Pyxel) {
+        if !self.is_playing {
+            return;
+        }
+        if pyxel.play_pos(PREVIEW_CHANNEL).is_some() {
+            self.has_started = true;
+        } else if self.has_started {
+            self.is_playing = false;
+        }
+    }
+
+    fn update_header(&mut self, pyxel: &mut Pyxel) {
+        let label_width = FONT_WIDTH as i32 * 8;
+        let step = editor::value_step(pyxel, SOUND_LABEL_X, TOOL_Y, label_width);
+        if step != 0 {
+            let num_sounds = pyxel.sounds.lock().len() as i32;
+            self.sound = (self.sound as i32 + step).rem_euclid(num_sounds) as usize;
+        }
+        let step = editor::value_step(pyxel, SPEED_LABEL_X, TOOL_Y, label_width);
+        if step != 0 {
+            self.push_undo(pyxel);
+            let shared_sound = self.shared_sound(pyxel);
+            let mut sound = shared_sound.lock();
+            sound.speed = (sound.speed as i32 + step).clamp(1, 999) as u32;
+        }
+        let play_width = FONT_WIDTH as i32 * 4;
+        if editor::value_step(pyxel, PLAY_LABEL_X, TOOL_Y, play_width) != 0
+            || pyxel.btnp(KEY_SPACE, None, None)
+        {
+            self.toggle_playback(pyxel);
+        }
+        self.update_playback_state(pyxel);
+    }
+
+    fn update_shortcuts(&mut self, pyxel: &mut Pyxel) {
+        if editor::is_ctrl_pressed(pyxel) {
+            if pyxel.btnp(KEY_Z, None, None) {
+                self.undo(pyxel, true);
+            } else if pyxel.btnp(KEY_Y, None, None) {
+                self.undo(pyxel, false);
+            }
+        } else if pyxel.btnp(KEY_BACKSPACE, Some(10), Some(2)) {
+            self.push_undo(pyxel);
+            let shared_sound = self.shared_sound(pyxel);
+            let mut sound = shared_sound.lock();
+            let length = sound.notes.len().saturating_sub(1);
+            resize_sound(&mut sound, length);
+        }
+    }
+
+    fn update_roll(&mut self, pyxel: &mut Pyxel) {
+        let Some((column, note)) = Self::roll_pos(pyxel) else {
+            return;
+        };
+        let is_pressed =
+            pyxel.btnp(MOUSE_BUTTON_LEFT, None, None) || pyxel.btnp(MOUSE_BUTTON_RIGHT, None, None);
+        let note = if pyxel.btn(MOUSE_BUTTON_LEFT) {
+            note
+        } else if pyxel.btn(MOUSE_BUTTON_RIGHT) {
+            -1
+        } else {
+            return;
+        };
+        if is_pressed {
+            self.push_undo(pyxel);
+        }
+        let shared_sound = self.shared_sound(pyxel);
+        let mut sound = shared_sound.lock();
+        let length = sound.notes.len().max(column + 1);
+        resize_sound(&mut sound, length);
+        sound.notes[column] = note;
+    }
+
+    fn update_rows(&mut self, pyxel: &mut Pyxel) {
+        let Some((column, row)) = Self::row_pos(pyxel) else {
+            return;
+        };
+        let step = i32::from(pyxel.btnp(MOUSE_BUTTON_LEFT, None, None))
+            - i32::from(pyxel.btnp(MOUSE_BUTTON_RIGHT, None, None));
+        if step == 0 || column >= self.shared_sound(pyxel).lock().notes.len() {
+            return;
+        }
+        self.push_undo(pyxel);
+        let shared_sound = self.shared_sound(pyxel);
+        let mut sound = shared_sound.lock();
+        let length = sound.notes.len();
+        resize_sound(&mut sound, length);
+        match row {
+            0 => {
+                let tone = sound.tones[column] as i32 + step;
+                sound.tones[column] = tone.rem_euclid(TONE_CHARS.len() as i32) as u32;
+            }
+            1 => {
+                let volume = sound.volumes[column] as i32 + step;
+                sound.volumes[column] = volume.clamp(0, MAX_VOLUME as i32) as u8;
+            }
+            _ => {
+                let effect = sound.effects[column] as i32 + step;
+                sound.effects[column] = effect.rem_euclid(EFFECT_CHARS.len() as i32) as Effect;
+            }
+        }
+    }
+
+    pub(crate) fn update(&mut self, pyxel: &mut Pyxel) {
+        self.update_header(pyxel);
+        self.update_shortcuts(pyxel);
+        self.update_roll(pyxel);
+        self.update_rows(pyxel);
+    }
+
+    fn draw_roll(&self, pyxel: &Pyxel, screen: &mut Image, sound: &Sound) {
+        let (left, top) = (ROLL_X as f64, ROLL_Y as f64);
+        let (width, height) = (ROLL_WIDTH as f64, ROLL_HEIGHT as f64);
+        screen.rect(left, top, width, height, COLOR_BLACK);
+        for octave in 1..=MAX_NOTE as i32 / 12 {
+            let y = top + height - (octave * 12 * NOTE_HEIGHT) as f64;
+            screen.line(left, y, left + width - 1.0, y, COLOR_DARK_BLUE);
+        }
+        for beat in (4..MAX_SOUND_LENGTH as i32).step_by(4) {
+            let x = left + (beat * COLUMN_WIDTH) as f64;
+            screen.line(x, top, x, top + height - 1.0, COLOR_DARK_BLUE);
+        }
+        if let Some((_, note_index)) = pyxel.play_pos(PREVIEW_CHANNEL).filter(|_| self.is_playing) {
+            let x = left + (note_index as i32 * COLUMN_WIDTH) as f64;
+            screen.rect(x, top, COLUMN_WIDTH as f64, height, COLOR_GRAY);
+        }
+        for (column, &note) in sound.notes.iter().enumerate() {
+            if note < 0 {
+                continue;
+            }
+            let x = left + (column as i32 * COLUMN_WIDTH) as f64;
+            let y = top + ((MAX_NOTE - note) as i32 * NOTE_HEIGHT) as f64;
+            screen.rect(
+                x,
+                y,
+                (COLUMN_WIDTH - 1) as f64,
+                NOTE_HEIGHT as f64,
+                COLOR_RED,
+            );
+        }
+        screen.rectb(
+            left - 1.0,
+            top - 1.0,
+            width + 2.0,
+            height + 2.0,
+            COLOR_WHITE,
+        );
+    }
+
+    fn draw_rows(screen: &mut Image, sound: &Sound) {
+        for (row, label) in ROW_LABELS.iter().enumerate() {
+            let y = (ROW_Y + row as i32 * ROW_HEIGHT) as f64;
+            screen.text(CANVAS_X as f64, y, label, COLOR_GRAY);
+            for column in 0..sound.notes.len() {
+                let x = (ROLL_X + column as i32 * COLUMN_WIDTH) as f64;
+                let c = match row {
+                    0 => sound
+                        .tones
+                        .get(column)
+                        .and_then(|&tone| TONE_CHARS.get(tone as usize).copied()),
+                    1 => sound
+                        .volumes
+                        .get(column)
+                        .and_then(|&volume| char::from_digit(volume as u32, 10)),
+                    _ => sound
+                        .effects
+                        .get(column)
+                        .and_then(|&effect| EFFECT_CHARS.get(effect as usize).copied()),
+                };
+                if let Some(c) = c {
+                    screen.text(x, y, &c.to_string(), COLOR_WHITE);
+                }
+            }
+        }
+    }
+
+    // Returns the status text for the hovered note
+    pub(crate) fn draw(&self, pyxel: &Pyxel, screen: &mut Image) -> Option<String> {
+        let sound = self.shared_sound(pyxel).lock().clone();
+        let sound_label = format!("SOUND {:02}", self.sound);
+        screen.text(
+            SOUND_LABEL_X as f64,
+            TOOL_Y as f64,
+            &sound_label,
+            COLOR_WHITE,
+        );
+        let speed_label = format!("SPEED {}", sound.speed);
+        screen.text(
+            SPEED_LABEL_X as f64,
+            TOOL_Y as f64,
+            &speed_label,
+            COLOR_WHITE,
+        );
+        let play_label = if self.is_playing { "STOP" } else { "PLAY" };
+        screen.text(PLAY_LABEL_X as f64, TOOL_Y as f64, play_label, COLOR_YELLOW);
+        self.draw_roll(pyxel, screen, &sound);
+        Self::draw_rows(screen, &sound);
+        Self::roll_pos(pyxel).map(|(column, note)| format!("{column} {}", note_name(note)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+
+    #[test]
+    fn test_resize_sound() {
+        let shared_sound = Sound::new();
+        let mut sound = shared_sound.lock();
+        sound.set("c2e2", "s", "5", "", 20);
+        resize_sound(&mut sound, 4);
+        assert_eq!(sound.notes, vec![24, 28, -1, -1]);
+        assert_eq!(sound.tones, vec![1, 1, 1, 1]);
+        assert_eq!(sound.volumes, vec![5, 5, 5, 5]);
+        assert_eq!(sound.effects, vec![0, 0, 0, 0]);
+        resize_sound(&mut sound, 1);
+        assert_eq!(sound.notes, vec![24]);
+        assert_eq!(note_name(25), "C#2");
+        assert_eq!(note_name(-1), "REST");
+    }
+
+    #[test]
+    fn test_playback_state() {
+        let pyxel = headless_pyxel(16, 16);
+        pyxel.sounds.lock()[0].lock().set("c2e2", "s", "5", "", 1);
+        let mut editor = SoundEditor::new();
+
+        // The frame that starts playback must not report it as finished
+        editor.toggle_playback(&pyxel);
+        editor.update_playback_state(&pyxel);
+        assert!(editor.is_playing);
+
+        pyxel.stop(PREVIEW_CHANNEL);
+        editor.update_playback_state(&pyxel);
+        assert!(!editor.is_playing);
+
+        editor.toggle_playback(&pyxel);
+        editor.toggle_playback(&pyxel);
+        assert!(!editor.is_playing);
+
+        pyxel.sounds.lock()[0].lock().notes.clear();
+        editor.toggle_playback(&pyxel);
+        assert!(!editor.is_playing);
+    }
+}
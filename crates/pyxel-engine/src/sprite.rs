@@ -0,0 +1,154 @@
+use std::cmp::max;
+
+use crate::image::Color;
+use crate::pyxel::Pyxel;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprite {
+    pub image: u32,
+    pub u: f64,
+    pub v: f64,
+    pub w: f64,
+    pub h: f64,
+    pub colkey: Option<Color>,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Sprite {
+    pub fn new(image: u32, u: f64, v: f64, w: f64, h: f64, colkey: Option<Color>) -> Self {
+        Self {
+            image,
+            u,
+            v,
+            w,
+            h,
+            colkey,
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+
+    pub fn flipped(self, flip_x: bool, flip_y: bool) -> Self {
+        Self {
+            flip_x,
+            flip_y,
+            ..self
+        }
+    }
+}
+
+pub struct Animation {
+    frames: Vec<(Sprite, u32)>,
+    looped: bool,
+    frame_index: usize,
+    elapsed_frames: u32,
+}
+
+impl Animation {
+    pub fn new(sprites: &[Sprite], duration: u32, looped: bool) -> Self {
+        let frames: Vec<(Sprite, u32)> = sprites.iter().map(|sprite| (*sprite, duration)).collect();
+        Self::with_durations(&frames, looped)
+    }
+
+    pub fn with_durations(frames: &[(Sprite, u32)], looped: bool) -> Self {
+        assert!(!frames.is_empty(), "Animation has no frames");
+        Self {
+            frames: frames
+                .iter()
+                .map(|(sprite, duration)| (*sprite, max(*duration, 1)))
+                .collect(),
+            looped,
+            frame_index: 0,
+            elapsed_frames: 0,
+        }
+    }
+
+    pub fn update(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+        self.elapsed_frames += 1;
+        if self.elapsed_frames < self.frames[self.frame_index].1 {
+            return;
+        }
+        self.elapsed_frames = 0;
+        if self.frame_index + 1 < self.frames.len() {
+            self.frame_index += 1;
+        } else if self.looped {
+            self.frame_index = 0;
+        } else {
+            // Stay on the last frame once a one-shot animation ends
+            self.elapsed_frames = self.frames[self.frame_index].1;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.frame_index = 0;
+        self.elapsed_frames = 0;
+    }
+
+    pub fn sprite(&self) -> Sprite {
+        self.frames[self.frame_index].0
+    }
+
+    pub fn frame_index(&self) -> usize {
+        self.frame_index
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.looped
+            && self.frame_index == self.frames.len() - 1
+            && self.elapsed_frames >= self.frames[self.frame_index].1
+    }
+}
+
+impl Pyxel {
+    pub fn draw_sprite(&self, x: f64, y: f64, sprite: &Sprite) {
+        // Negative sizes make blt flip the copied region
+        self.blt(
+            x,
+            y,
+            sprite.image,
+            sprite.u,
+            sprite.v,
+            if sprite.flip_x { -sprite.w } else { sprite.w },
+            if sprite.flip_y { -sprite.h } else { sprite.h },
+            sprite.colkey,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_animation() {
+        let sprites = [
+            Sprite::new(0, 0.0, 0.0, 8.0, 8.0, None),
+            Sprite::new(0, 8.0, 0.0, 8.0, 8.0, None),
+        ];
+
+        let mut animation = Animation::new(&sprites, 2, true);
+        let indices: Vec<usize> = (0..5)
+            .map(|_| {
+                let index = animation.frame_index();
+                animation.update();
+                index
+            })
+            .collect();
+        assert_eq!(indices, vec![0, 0, 1, 1, 0]);
+        assert!(!animation.is_finished());
+
+        let mut animation = Animation::with_durations(&[(sprites[0], 1), (sprites[1], 2)], false);
+        for _ in 0..3 {
+            assert!(!animation.is_finished());
+            animation.update();
+        }
+        assert!(animation.is_finished());
+        assert_eq!(animation.sprite(), sprites[1]);
+        animation.reset();
+        assert_eq!(animation.sprite(), sprites[0]);
+    }
+}
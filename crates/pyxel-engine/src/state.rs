@@ -0,0 +1,246 @@
+use std::io::{Cursor, Read, Write};
+
+use serde::{Deserialize, Serialize};
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::channel::PlaybackState;
+use crate::error::{PyxelError, PyxelResult};
+use crate::input::InputData;
+use crate::math::Xoshiro256;
+use crate::pyxel::Pyxel;
+use crate::resource_data::{ImageData, ResourceData3, SoundData};
+use crate::settings::{STATE_ARCHIVE_NAME, STATE_FORMAT_VERSION};
+
+#[derive(Serialize, Deserialize)]
+struct PlaybackData {
+    is_playing: bool,
+    should_loop: bool,
+    sound_index: u32,
+    note_index: u32,
+    tick_count: u32,
+    sounds: Vec<SoundData>,
+}
+
+impl PlaybackData {
+    fn from_playback_state(playback_state: Option<PlaybackState>) -> Self {
+        let Some(playback_state) = playback_state else {
+            return Self {
+                is_playing: false,
+                should_loop: false,
+                sound_index: 0,
+                note_index: 0,
+                tick_count: 0,
+                sounds: Vec::new(),
+            };
+        };
+        Self {
+            is_playing: true,
+            should_loop: playback_state.should_loop,
+            sound_index: playback_state.sound_index,
+            note_index: playback_state.note_index,
+            tick_count: playback_state.tick_count,
+            sounds: playback_state
+                .sounds
+                .into_iter()
+                .map(|sound| SoundData::from_sound(new_shared_type!(sound)))
+                .collect(),
+        }
+    }
+
+    fn to_playback_state(&self) -> Option<PlaybackState> {
+        self.is_playing.then(|| PlaybackState {
+            sounds: self
+                .sounds
+                .iter()
+                .map(|sound_data| sound_data.to_sound().lock().clone())
+                .collect(),
+            should_loop: self.should_loop,
+            sound_index: self.sound_index,
+            note_index: self.note_index,
+            tick_count: self.tick_count,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateData {
+    format_version: u32,
+    frame_count: u32,
+    rng_state: String,
+    noise_seed: u32,
+    input: InputData,
+    screen: ImageData,
+    playbacks: Vec<PlaybackData>,
+    resource: ResourceData3,
+}
+
+impl Pyxel {
+    pub fn save_state(&self) -> PyxelResult<Vec<u8>> {
+        let state_data = StateData {
+            format_version: STATE_FORMAT_VERSION,
+            frame_count: self.frame_count,
+            rng_state: self
+                .math
+                .rng
+                .state()
+                .iter()
+                .map(|word| format!("{word:016X}"))
+                .collect(),
+            noise_seed: self.math.noise_seed(),
+            input: self.input.to_data(),
+            screen: ImageData::from_image(self.screen.clone()),
+            playbacks: self
+                .channels
+                .lock()
+                .iter()
+                .map(|channel| PlaybackData::from_playback_state(channel.lock().playback_state()))
+                .collect(),
+            resource: ResourceData3::from_runtime(self),
+        };
+        let toml_text = toml::to_string(&state_data)
+            .map_err(|err| PyxelError::Platform(format!("Failed to save state: {err}")))?;
+        compress_state(&toml_text)
+    }
+
+    // Everything is validated before the current state is touched
+    pub fn load_state(&mut self, state: &[u8]) -> PyxelResult<()> {
+        let invalid_state = |message: &str| PyxelError::InvalidArgument(message.to_string());
+        let state_data: StateData = decompress_state(state)
+            .and_then(|toml_text| toml::from_str(&toml_text).ok())
+            .ok_or_else(|| invalid_state("Invalid state data"))?;
+        if state_data.format_version != STATE_FORMAT_VERSION {
+            return Err(invalid_state("Unsupported state data version"));
+        }
+        if !state_data.screen.is_valid() || !state_data.resource.is_valid() {
+            return Err(invalid_state("Invalid state data"));
+        }
+        let rng = parse_rng_state(&state_data.rng_state)
+            .ok_or_else(|| invalid_state("Invalid random number state in state data"))?;
+        let screen = state_data.screen.to_image();
+        let screen = screen.lock();
+        if screen.width() != self.width
+            || screen.height() != self.height
+            || screen.canvas.data.len() != (self.width * self.height) as usize
+        {
+            return Err(invalid_state("Screen size does not match state data"));
+        }
+
+        self.frame_count = state_data.frame_count;
+        self.math.rng = rng;
+        self.nseed(state_data.noise_seed);
+        self.input.set_data(&state_data.input);
+        self.screen
+            .lock()
+            .canvas
+            .data
+            .clone_from(&screen.canvas.data);
+
+        state_data
+            .resource
+            .to_runtime(self, false, false, false, false, true, true, true);
+        let channels = self.channels.lock();
+        for (channel, playback_data) in channels.iter().zip(&state_data.playbacks) {
+            channel
+                .lock()
+                .set_playback_state(playback_data.to_playback_state());
+        }
+        Ok(())
+    }
+}
+
+// Image and tilemap banks make up most of a state, and their rows of numbers deflate well
+fn compress_state(toml_text: &str) -> PyxelResult<Vec<u8>> {
+    let compress_error = |err: zip::result::ZipError| {
+        PyxelError::Platform(format!("Failed to compress state: {err}"))
+    };
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file(STATE_ARCHIVE_NAME, FileOptions::default())
+        .map_err(compress_error)?;
+    zip.write_all(toml_text.as_bytes())
+        .map_err(|err| compress_error(err.into()))?;
+    Ok(zip.finish().map_err(compress_error)?.into_inner())
+}
+
+fn decompress_state(state: &[u8]) -> Option<String> {
+    let mut archive = ZipArchive::new(Cursor::new(state)).ok()?;
+    let mut toml_text = String::new();
+    archive
+        .by_name(STATE_ARCHIVE_NAME)
+        .ok()?
+        .read_to_string(&mut toml_text)
+        .ok()?;
+    Some(toml_text)
+}
+
+fn parse_rng_state(text: &str) -> Option<Xoshiro256> {
+    if text.len() != 64 || !text.is_ascii() {
+        return None;
+    }
+    let mut state = [0; 4];
+    for (word, chunk) in state.iter_mut().zip(text.as_bytes().chunks(16)) {
+        *word = u64::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+    }
+    Xoshiro256::from_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+    use crate::Color;
+
+    #[test]
+    fn test_save_and_load_state() {
+        let mut pyxel = headless_pyxel(32, 24);
+        pyxel.rseed(7);
+        pyxel.rndi(0, 100);
+        pyxel.cls(3);
+        pyxel.pset(5.0, 6.0, 9);
+        pyxel.frame_count = 42;
+        let state = pyxel.save_state().unwrap();
+
+        // Saving must not disturb the random sequence
+        let expected: Vec<i32> = (0..16).map(|_| pyxel.rndi(0, 1000)).collect();
+        let expected_screen = pyxel.screen.lock().canvas.data.clone();
+
+        pyxel.cls(0);
+        pyxel.frame_count = 0;
+        pyxel.rseed(99);
+        pyxel.load_state(&state).unwrap();
+        assert_eq!(pyxel.frame_count, 42);
+        assert_eq!(pyxel.screen.lock().canvas.data, expected_screen);
+        let actual: Vec<i32> = (0..16).map(|_| pyxel.rndi(0, 1000)).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_state_size() {
+        let pyxel = headless_pyxel(32, 24);
+        {
+            let image = pyxel.images.lock()[0].clone();
+            let mut image = image.lock();
+            let width = image.width() as usize;
+            for (i, color) in image.canvas.data.iter_mut().enumerate() {
+                *color = ((i % width + i / width) % 16) as Color;
+            }
+        }
+        let state = pyxel.save_state().unwrap();
+        let toml_text = decompress_state(&state).unwrap();
+        assert!(state.len() * 10 < toml_text.len());
+    }
+
+    #[test]
+    fn test_load_invalid_state() {
+        let mut pyxel = headless_pyxel(32, 24);
+        pyxel.frame_count = 5;
+        assert!(pyxel.load_state(b"not a state").is_err());
+        assert!(pyxel.load_state(&[0xff, 0xfe]).is_err());
+        assert_eq!(pyxel.frame_count, 5);
+
+        let state = decompress_state(&pyxel.save_state().unwrap()).unwrap();
+        assert!(pyxel.load_state(state.as_bytes()).is_err());
+        let state = state.replacen("format_version = 3", "format_version = 99", 1);
+        assert!(pyxel.load_state(&compress_state(&state).unwrap()).is_err());
+    }
+}
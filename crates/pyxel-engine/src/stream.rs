@@ -0,0 +1,533 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+#[cfg(not(target_os = "emscripten"))]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(not(target_os = "emscripten"))]
+use std::thread;
+#[cfg(not(target_os = "emscripten"))]
+use std::time::Duration;
+
+use lewton::inside_ogg::OggStreamReader;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::error::{PyxelError, PyxelResult};
+use crate::pyxel::Pyxel;
+#[cfg(not(target_os = "emscripten"))]
+use crate::settings::STREAM_WORKER_INTERVAL_MS;
+use crate::settings::{
+    MAX_STREAM_BUFFERED_SAMPLES, NUM_OUTPUT_CHANNELS, NUM_STREAM_FRAMES_PER_READ, SAMPLE_RATE,
+    STREAM_GAIN,
+};
+
+static STREAM: Lazy<Mutex<Option<Stream>>> = Lazy::new(|| Mutex::new(None));
+
+trait StreamDecoder: Send {
+    fn sample_rate(&self) -> u32;
+    fn read_samples(&mut self, samples: &mut VecDeque<i16>) -> PyxelResult<bool>;
+    fn rewind(&mut self) -> PyxelResult<()>;
+}
+
+#[derive(Debug, PartialEq)]
+struct WavFormat {
+    sample_rate: u32,
+    num_channels: u16,
+    bits_per_sample: u16,
+    data_size: u64,
+}
+
+// Leaves the reader at the start of the sample data
+fn parse_wav_header<R: Read + Seek>(reader: &mut R, filename: &str) -> PyxelResult<WavFormat> {
+    let invalid_file = || PyxelError::InvalidFile(filename.to_string());
+    let mut header = [0; 12];
+    reader.read_exact(&mut header).map_err(|_| invalid_file())?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err(invalid_file());
+    }
+
+    let mut format = None;
+    loop {
+        let mut chunk_header = [0; 8];
+        reader
+            .read_exact(&mut chunk_header)
+            .map_err(|_| invalid_file())?;
+        let chunk_size = u32::from_le_bytes(chunk_header[4..8].try_into().unwrap()) as u64;
+        match &chunk_header[0..4] {
+            b"fmt " => {
+                if chunk_size < 16 {
+                    return Err(invalid_file());
+                }
+                let mut fmt = vec![0; chunk_size as usize];
+                reader.read_exact(&mut fmt).map_err(|_| invalid_file())?;
+                let format_tag = u16::from_le_bytes([fmt[0], fmt[1]]);
+                let num_channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+                let sample_rate = u32::from_le_bytes(fmt[4..8].try_into().unwrap());
+                let bits_per_sample = u16::from_le_bytes([fmt[14], fmt[15]]);
+                if format_tag != 1
+                    || num_channels == 0
+                    || sample_rate == 0
+                    || (bits_per_sample != 8 && bits_per_sample != 16)
+                {
+                    return Err(invalid_file());
+                }
+                format = Some((sample_rate, num_channels, bits_per_sample));
+                if chunk_size % 2 == 1 {
+                    reader
+                        .seek(SeekFrom::Current(1))
+                        .map_err(|_| invalid_file())?;
+                }
+            }
+            b"data" => {
+                let (sample_rate, num_channels, bits_per_sample) =
+                    format.ok_or_else(invalid_file)?;
+                return Ok(WavFormat {
+                    sample_rate,
+                    num_channels,
+                    bits_per_sample,
+                    data_size: chunk_size,
+                });
+            }
+            _ => {
+                reader
+                    .seek(SeekFrom::Current((chunk_size + chunk_size % 2) as i64))
+                    .map_err(|_| invalid_file())?;
+            }
+        }
+    }
+}
+
+struct WavDecoder<R: Read + Seek> {
+    filename: String,
+    reader: R,
+    format: WavFormat,
+    data_start: u64,
+    data_pos: u64,
+}
+
+impl WavDecoder<BufReader<File>> {
+    fn open(filename: &str) -> PyxelResult<Self> {
+        let file = File::open(Path::new(filename))
+            .map_err(|_| PyxelError::FileOpen(filename.to_string()))?;
+        Self::new(BufReader::new(file), filename)
+    }
+}
+
+impl<R: Read + Seek> WavDecoder<R> {
+    fn new(mut reader: R, filename: &str) -> PyxelResult<Self> {
+        let format = parse_wav_header(&mut reader, filename)?;
+        let data_start = reader
+            .stream_position()
+            .map_err(|_| PyxelError::InvalidFile(filename.to_string()))?;
+        Ok(Self {
+            filename: filename.to_string(),
+            reader,
+            format,
+            data_start,
+            data_pos: 0,
+        })
+    }
+}
+
+impl<R: Read + Seek + Send> StreamDecoder for WavDecoder<R> {
+    fn sample_rate(&self) -> u32 {
+        self.format.sample_rate
+    }
+
+    fn read_samples(&mut self, samples: &mut VecDeque<i16>) -> PyxelResult<bool> {
+        let bytes_per_sample = (self.format.bits_per_sample / 8) as u64;
+        let num_channels = self.format.num_channels;
+        let frame_size = bytes_per_sample * num_channels as u64;
+        let read_size = (NUM_STREAM_FRAMES_PER_READ as u64 * frame_size)
+            .min(self.format.data_size - self.data_pos)
+            / frame_size
+            * frame_size;
+        if read_size == 0 {
+            return Ok(false);
+        }
+        let mut data = vec![0; read_size as usize];
+        self.reader
+            .read_exact(&mut data)
+            .map_err(|_| PyxelError::InvalidFile(self.filename.clone()))?;
+        self.data_pos += read_size;
+        for frame in data.chunks(frame_size as usize) {
+            let sum: i32 = frame
+                .chunks(bytes_per_sample as usize)
+                .map(|bytes| {
+                    if bytes_per_sample == 1 {
+                        (bytes[0] as i32 - 128) << 8
+                    } else {
+                        i16::from_le_bytes([bytes[0], bytes[1]]) as i32
+                    }
+                })
+                .sum();
+            samples.push_back((sum / num_channels as i32) as i16);
+        }
+        Ok(true)
+    }
+
+    fn rewind(&mut self) -> PyxelResult<()> {
+        self.reader
+            .seek(SeekFrom::Start(self.data_start))
+            .map_err(|_| PyxelError::FileOpen(self.filename.clone()))?;
+        self.data_pos = 0;
+        Ok(())
+    }
+}
+
+struct OggDecoder {
+    filename: String,
+    reader: OggStreamReader<BufReader<File>>,
+}
+
+impl OggDecoder {
+    fn open(filename: &str) -> PyxelResult<Self> {
+        Ok(Self {
+            filename: filename.to_string(),
+            reader: Self::open_reader(filename)?,
+        })
+    }
+
+    fn open_reader(filename: &str) -> PyxelResult<OggStreamReader<BufReader<File>>> {
+        let file = File::open(Path::new(filename))
+            .map_err(|_| PyxelError::FileOpen(filename.to_string()))?;
+        OggStreamReader::new(BufReader::new(file))
+            .map_err(|_| PyxelError::InvalidFile(filename.to_string()))
+    }
+}
+
+impl StreamDecoder for OggDecoder {
+    fn sample_rate(&self) -> u32 {
+        self.reader.ident_hdr.audio_sample_rate
+    }
+
+    fn read_samples(&mut self, samples: &mut VecDeque<i16>) -> PyxelResult<bool> {
+        let num_channels = self.reader.ident_hdr.audio_channels.max(1) as usize;
+        match self.reader.read_dec_packet_itl() {
+            Ok(Some(packet)) => {
+                for frame in packet.chunks(num_channels) {
+                    let sum: i32 = frame.iter().map(|sample| *sample as i32).sum();
+                    samples.push_back((sum / num_channels as i32) as i16);
+                }
+                Ok(true)
+            }
+            Ok(None) => Ok(false),
+            Err(_) => Err(PyxelError::InvalidFile(self.filename.clone())),
+        }
+    }
+
+    fn rewind(&mut self) -> PyxelResult<()> {
+        self.reader = Self::open_reader(&self.filename)?;
+        Ok(())
+    }
+}
+
+struct StreamBuffer {
+    samples: VecDeque<i16>,
+    is_finished: bool,
+}
+
+// Decodes ahead of playback so that file I/O never runs in the audio callback
+struct StreamWorker {
+    decoder: Box<dyn StreamDecoder>,
+    should_loop: bool,
+    buffer: Arc<Mutex<StreamBuffer>>,
+    decoded_samples: VecDeque<i16>,
+}
+
+impl StreamWorker {
+    // Returns false when there is nothing more to decode
+    fn decode_packet(&mut self) -> bool {
+        let result = match self.decoder.read_samples(&mut self.decoded_samples) {
+            Ok(false) if self.should_loop => self
+                .decoder
+                .rewind()
+                .and_then(|()| self.decoder.read_samples(&mut self.decoded_samples)),
+            result => result,
+        };
+        let is_decoding = result.unwrap_or_else(|err| {
+            log::error!("{err}");
+            false
+        });
+        let mut buffer = self.buffer.lock();
+        buffer.samples.extend(self.decoded_samples.drain(..));
+        buffer.is_finished = !is_decoding;
+        is_decoding
+    }
+
+    #[cfg(not(target_os = "emscripten"))]
+    fn run(mut self, is_stopped: &AtomicBool) {
+        while !is_stopped.load(Ordering::Relaxed) {
+            if self.buffer.lock().samples.len() >= MAX_STREAM_BUFFERED_SAMPLES as usize {
+                thread::sleep(Duration::from_millis(STREAM_WORKER_INTERVAL_MS));
+            } else if !self.decode_packet() {
+                break;
+            }
+        }
+    }
+}
+
+struct Stream {
+    buffer: Arc<Mutex<StreamBuffer>>,
+    #[cfg(not(target_os = "emscripten"))]
+    is_stopped: Arc<AtomicBool>,
+    // Web builds have no worker thread, so decoding happens just before mixing
+    #[cfg(target_os = "emscripten")]
+    worker: StreamWorker,
+    samples: VecDeque<i16>,
+    position: f64,
+    step: f64,
+}
+
+impl Stream {
+    // Returns false when the stream has finished
+    fn mix(&mut self, out: &mut [i16]) -> bool {
+        let num_frames = out.len() / NUM_OUTPUT_CHANNELS as usize;
+        let num_samples = (self.position + num_frames as f64 * self.step) as usize + 2;
+        #[cfg(target_os = "emscripten")]
+        loop {
+            let num_buffered = self.buffer.lock().samples.len();
+            if num_buffered + self.samples.len() >= num_samples || !self.worker.decode_packet() {
+                break;
+            }
+        }
+
+        // If the worker holds the buffer, its samples are taken on the next callback
+        let mut is_finished = false;
+        if let Some(mut buffer) = self.buffer.try_lock() {
+            let num_taken = num_samples
+                .saturating_sub(self.samples.len())
+                .min(buffer.samples.len());
+            self.samples.extend(buffer.samples.drain(..num_taken));
+            is_finished = buffer.is_finished && buffer.samples.is_empty();
+        }
+
+        for out_frame in out.chunks_mut(NUM_OUTPUT_CHANNELS as usize) {
+            // A starved stream resumes once the worker catches up
+            if self.samples.len() < self.position as usize + 2 {
+                return !is_finished;
+            }
+            let index = self.position as usize;
+            let rate = self.position.fract();
+            let sample =
+                self.samples[index] as f64 * (1.0 - rate) + self.samples[index + 1] as f64 * rate;
+            for out_sample in out_frame {
+                *out_sample = (*out_sample as f64 + sample * STREAM_GAIN)
+                    .clamp(i16::MIN as f64, i16::MAX as f64) as i16;
+            }
+            self.position += self.step;
+            let num_consumed = self.position as usize;
+            self.samples.drain(..num_consumed);
+            self.position -= num_consumed as f64;
+        }
+        true
+    }
+}
+
+#[cfg(not(target_os = "emscripten"))]
+impl Drop for Stream {
+    fn drop(&mut self) {
+        self.is_stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn load_wav_file(filename: &str) -> PyxelResult<(u32, Vec<i16>)> {
+    let mut decoder = WavDecoder::open(filename)?;
+    let mut samples = VecDeque::new();
+    while decoder.read_samples(&mut samples)? {}
+    Ok((decoder.format.sample_rate, samples.into()))
+}
+
+// Samples are interleaved when there is more than one channel
+pub(crate) fn save_wav_file(
+    filename: &str,
+    samples: &[i16],
+    sample_rate: u32,
+    num_channels: u32,
+) -> PyxelResult<()> {
+    let write_error = |_| PyxelError::FileWrite(filename.to_string());
+    let file = File::create(Path::new(filename)).map_err(write_error)?;
+    let mut writer = BufWriter::new(file);
+    let data_size = (samples.len() * 2) as u32;
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(36 + data_size).to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    header.extend_from_slice(&(num_channels as u16).to_le_bytes());
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&(sample_rate * num_channels * 2).to_le_bytes());
+    header.extend_from_slice(&(num_channels as u16 * 2).to_le_bytes());
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_size.to_le_bytes());
+    writer.write_all(&header).map_err(write_error)?;
+    for sample in samples {
+        writer
+            .write_all(&sample.to_le_bytes())
+            .map_err(write_error)?;
+    }
+    writer.flush().map_err(write_error)
+}
+
+pub(crate) fn mix_stream(out: &mut [i16]) {
+    let mut stream = STREAM.lock();
+    if let Some(current_stream) = stream.as_mut() {
+        if !current_stream.mix(out) {
+            *stream = None;
+        }
+    }
+}
+
+impl Pyxel {
+    pub fn play_stream(&self, filename: &str, should_loop: bool) -> PyxelResult<()> {
+        let decoder: Box<dyn StreamDecoder> = if filename.to_lowercase().ends_with(".ogg") {
+            Box::new(OggDecoder::open(filename)?)
+        } else {
+            Box::new(WavDecoder::open(filename)?)
+        };
+        let step = decoder.sample_rate() as f64 / SAMPLE_RATE as f64;
+        let buffer = Arc::new(Mutex::new(StreamBuffer {
+            samples: VecDeque::new(),
+            is_finished: false,
+        }));
+        let worker = StreamWorker {
+            decoder,
+            should_loop,
+            buffer: buffer.clone(),
+            decoded_samples: VecDeque::new(),
+        };
+        #[cfg(not(target_os = "emscripten"))]
+        let is_stopped = {
+            let is_stopped = Arc::new(AtomicBool::new(false));
+            let worker_is_stopped = is_stopped.clone();
+            thread::spawn(move || worker.run(&worker_is_stopped));
+            is_stopped
+        };
+        *STREAM.lock() = Some(Stream {
+            buffer,
+            #[cfg(not(target_os = "emscripten"))]
+            is_stopped,
+            #[cfg(target_os = "emscripten")]
+            worker,
+            samples: VecDeque::new(),
+            position: 0.0,
+            step,
+        });
+        Ok(())
+    }
+
+    pub fn stop_stream(&self) {
+        *STREAM.lock() = None;
+    }
+
+    pub fn is_stream_playing(&self) -> bool {
+        STREAM.lock().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn wav_bytes(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut body = b"WAVE".to_vec();
+        for (id, data) in chunks {
+            body.extend_from_slice(*id);
+            body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            body.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                body.push(0);
+            }
+        }
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    fn fmt_chunk(num_channels: u16, sample_rate: u32, bits_per_sample: u16) -> Vec<u8> {
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&1u16.to_le_bytes());
+        fmt.extend_from_slice(&num_channels.to_le_bytes());
+        fmt.extend_from_slice(&sample_rate.to_le_bytes());
+        fmt.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        fmt.extend_from_slice(&2u16.to_le_bytes());
+        fmt.extend_from_slice(&bits_per_sample.to_le_bytes());
+        fmt
+    }
+
+    #[test]
+    fn test_parse_wav_header_with_odd_chunks() {
+        let fmt = fmt_chunk(1, 22050, 16);
+        let bytes = wav_bytes(&[
+            (b"LIST", &[1, 2, 3]),
+            (b"fmt ", &fmt),
+            (b"junk", &[4]),
+            (b"data", &[1, 0, 2, 0]),
+        ]);
+        let mut reader = Cursor::new(bytes);
+        let format = parse_wav_header(&mut reader, "odd.wav").unwrap();
+        assert_eq!(
+            format,
+            WavFormat {
+                sample_rate: 22050,
+                num_channels: 1,
+                bits_per_sample: 16,
+                data_size: 4,
+            }
+        );
+        let mut decoder = WavDecoder::new(Cursor::new(reader.into_inner()), "odd.wav").unwrap();
+        let mut samples = VecDeque::new();
+        assert!(decoder.read_samples(&mut samples).unwrap());
+        assert_eq!(samples, [1, 2]);
+        assert!(!decoder.read_samples(&mut samples).unwrap());
+    }
+
+    #[test]
+    fn test_parse_truncated_wav_header() {
+        let fmt = fmt_chunk(1, 22050, 16);
+        let bytes = wav_bytes(&[(b"fmt ", &fmt), (b"data", &[1, 0, 2, 0])]);
+        for size in [0, 4, 12, 20, 30] {
+            let mut reader = Cursor::new(bytes[..size].to_vec());
+            assert!(parse_wav_header(&mut reader, "truncated.wav").is_err());
+        }
+        let bytes = wav_bytes(&[(b"data", &[1, 0, 2, 0])]);
+        assert!(parse_wav_header(&mut Cursor::new(bytes), "no_fmt.wav").is_err());
+    }
+
+    #[test]
+    fn test_read_truncated_wav_data() {
+        let fmt = fmt_chunk(1, 22050, 16);
+        let mut bytes = wav_bytes(&[(b"fmt ", &fmt), (b"data", &[1, 0, 2, 0, 3, 0])]);
+        bytes.truncate(bytes.len() - 4);
+        let mut decoder = WavDecoder::new(Cursor::new(bytes), "truncated.wav").unwrap();
+        assert!(decoder.read_samples(&mut VecDeque::new()).is_err());
+    }
+
+    #[test]
+    fn test_save_and_load_wav_file() {
+        let filename =
+            std::env::temp_dir().join(format!("pyxel_save_wav_{}.wav", std::process::id()));
+        let filename = filename.to_str().unwrap();
+        let samples: Vec<i16> = (0..3000).map(|i| (i * 37 % 2000 - 1000) as i16).collect();
+        save_wav_file(filename, &samples, 22050, 1).unwrap();
+        let (sample_rate, loaded_samples) = load_wav_file(filename).unwrap();
+        std::fs::remove_file(filename).unwrap();
+        assert_eq!(sample_rate, 22050);
+        assert_eq!(loaded_samples, samples);
+
+        let missing_dir =
+            std::env::temp_dir().join(format!("pyxel_missing_{}", std::process::id()));
+        let filename = missing_dir.join("save.wav");
+        assert!(matches!(
+            save_wav_file(filename.to_str().unwrap(), &samples, 22050, 1),
+            Err(PyxelError::FileWrite(_))
+        ));
+    }
+}
@@ -1,55 +1,211 @@
 use std::cmp::{max, min};
+use std::mem::{size_of, swap};
 
 use cfg_if::cfg_if;
 use pyxel_platform::Event;
 
+use crate::bank_viewer::BankViewer;
+use crate::canvas;
+use crate::debug_console::DebugConsole;
+use crate::error::{PyxelError, PyxelResult};
+use crate::executor::Executor;
 use crate::image::{Color, Image, SharedImage};
-use crate::keys::{Key, KEY_0, KEY_1, KEY_2, KEY_3, KEY_9, KEY_ALT, KEY_RETURN, KEY_SHIFT};
-use crate::profiler::Profiler;
+use crate::keys::{
+    Key, KEY_0, KEY_1, KEY_2, KEY_3, KEY_9, KEY_ALT, KEY_C, KEY_I, KEY_NONE, KEY_P, KEY_RETURN,
+    KEY_RIGHT, KEY_SHIFT, KEY_SPACE,
+};
+use crate::palette_editor::PaletteEditor;
+use crate::pause_menu::PauseMenu;
+use crate::profiler::{Perf, Profiler};
 use crate::pyxel::Pyxel;
-use crate::settings::{MAX_ELAPSED_MS, NUM_MEASURE_FRAMES, NUM_SCREEN_TYPES};
+use crate::settings::{
+    COLOR_BLACK, COLOR_YELLOW, DEFAULT_MAX_ELAPSED_MS, DEFAULT_MAX_UPDATE_PER_FRAME, FONT_HEIGHT,
+    FONT_WIDTH, NUM_MEASURE_FRAMES, NUM_PERF_HISTOGRAM_BINS, NUM_PERF_MONITOR_LEVELS,
+    NUM_SCREEN_TYPES, SCALING_FIT, SCALING_INTEGER, SCALING_STRETCH,
+};
+use crate::tilemap::Tile;
+use crate::transition::Transition;
 use crate::utils;
+use crate::virtual_gamepad::VirtualGamepad;
 use crate::watch_info::WatchInfo;
 
+pub type QuitHandler = Box<dyn FnMut(&mut Pyxel) -> bool>;
+pub type AnnounceHandler = Box<dyn FnMut(&str)>;
+
+#[derive(Copy, Clone)]
+pub enum ScalingMode {
+    Integer,
+    Stretch,
+    FitKeepAspect,
+}
+
+impl ScalingMode {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            SCALING_STRETCH => Self::Stretch,
+            SCALING_FIT => Self::FitKeepAspect,
+            _ => Self::Integer,
+        }
+    }
+
+    pub const fn index(self) -> u32 {
+        match self {
+            Self::Integer => SCALING_INTEGER,
+            Self::Stretch => SCALING_STRETCH,
+            Self::FitKeepAspect => SCALING_FIT,
+        }
+    }
+}
+
 pub trait PyxelCallback {
     fn update(&mut self, pyxel: &mut Pyxel);
     fn draw(&mut self, pyxel: &mut Pyxel);
+    fn on_pause(&mut self, _pyxel: &mut Pyxel) {}
+    fn on_resume(&mut self, _pyxel: &mut Pyxel) {}
+    fn on_quit_request(&mut self, _pyxel: &mut Pyxel) -> bool {
+        true
+    }
+    fn on_drop_file(&mut self, _pyxel: &mut Pyxel, _filename: &str) {}
+    fn on_resize(&mut self, _pyxel: &mut Pyxel, _width: u32, _height: u32) {}
+}
+
+// Shortcuts are pressed together with the modifier, and KEY_NONE disables any of them.
+// Holding dump_modifier as well turns 0 into a palette dump and 1-9 into image bank dumps.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SystemKeys {
+    pub modifier: Key,
+    pub dump_modifier: Key,
+    pub fullscreen: Key,
+    pub perf_monitor: Key,
+    pub screenshot: Key,
+    pub reset_screencast: Key,
+    pub screencast: Key,
+    pub screen_mode: Key,
+    pub debug_console: Key,
+    pub palette_editor: Key,
+    pub bank_viewer: Key,
+    pub frame_step: Key,
+    pub frame_advance: Key,
+}
+
+impl Default for SystemKeys {
+    fn default() -> Self {
+        Self {
+            modifier: KEY_ALT,
+            dump_modifier: KEY_SHIFT,
+            fullscreen: KEY_RETURN,
+            perf_monitor: KEY_0,
+            screenshot: KEY_1,
+            reset_screencast: KEY_2,
+            screencast: KEY_3,
+            screen_mode: KEY_9,
+            debug_console: KEY_C,
+            palette_editor: KEY_P,
+            bank_viewer: KEY_I,
+            frame_step: KEY_SPACE,
+            frame_advance: KEY_RIGHT,
+        }
+    }
 }
 
 pub struct System {
     one_frame_ms: f64,
     next_update_ms: f64,
+    uncapped_fps: bool,
+    max_update_per_frame: u32,
+    max_elapsed_ms: u32,
+    dropped_frames: u32,
+    logged_slow_audio_callbacks: u32,
     quit_key: Key,
+    system_keys: SystemKeys,
+    quit_handler: Option<QuitHandler>,
+    is_quit_handler_replaced: bool,
+    announce_handler: Option<AnnounceHandler>,
     paused: bool,
+    frame_step_enabled: bool,
+    frame_step_requested: bool,
+    events: Vec<Event>,
+    pub executor: Executor,
+    pub pause_menu: PauseMenu,
     fps_profiler: Profiler,
     update_profiler: Profiler,
     draw_profiler: Profiler,
-    perf_monitor_enabled: bool,
+    perf_monitor_level: u32,
+    pub debug_console: DebugConsole,
+    pub palette_editor: PaletteEditor,
+    pub bank_viewer: BankViewer,
+    pub virtual_gamepad: VirtualGamepad,
+    pub transition: Transition,
+    draw_calls: u32,
+    blit_pixels: u32,
     watch_info: WatchInfo,
     pub screen_x: i32,
     pub screen_y: i32,
-    pub screen_scale: u32,
+    pub screen_width: u32,
+    pub screen_height: u32,
     pub screen_mode: u32,
+    pub scaling_mode: ScalingMode,
+    exclusive_fullscreen: bool,
+    pub(crate) is_headless: bool,
 }
 
 impl System {
-    pub fn new(fps: u32, quit_key: Key) -> Self {
+    pub fn new(
+        fps: u32,
+        quit_key: Key,
+        pause_key: Key,
+        max_update_per_frame: Option<u32>,
+        max_elapsed_ms: Option<u32>,
+        scaling_mode: Option<u32>,
+    ) -> Self {
+        let max_update_per_frame = max_update_per_frame.unwrap_or(DEFAULT_MAX_UPDATE_PER_FRAME);
+        let max_elapsed_ms = max_elapsed_ms.unwrap_or(DEFAULT_MAX_ELAPSED_MS);
         Self {
             one_frame_ms: 1000.0 / fps as f64,
             next_update_ms: 0.0,
+            uncapped_fps: false,
+            max_update_per_frame: max(max_update_per_frame, 1),
+            max_elapsed_ms,
+            dropped_frames: 0,
+            logged_slow_audio_callbacks: 0,
             quit_key,
+            system_keys: SystemKeys::default(),
+            quit_handler: None,
+            is_quit_handler_replaced: false,
+            announce_handler: None,
             paused: false,
+            frame_step_enabled: false,
+            frame_step_requested: false,
+            events: Vec::new(),
+            executor: Executor::new(),
+            pause_menu: PauseMenu::new(pause_key),
             fps_profiler: Profiler::new(NUM_MEASURE_FRAMES),
             update_profiler: Profiler::new(NUM_MEASURE_FRAMES),
             draw_profiler: Profiler::new(NUM_MEASURE_FRAMES),
-            perf_monitor_enabled: false,
+            perf_monitor_level: 0,
+            debug_console: DebugConsole::new(),
+            palette_editor: PaletteEditor::new(),
+            bank_viewer: BankViewer::new(),
+            virtual_gamepad: VirtualGamepad::new(),
+            transition: Transition::new(),
+            draw_calls: 0,
+            blit_pixels: 0,
             watch_info: WatchInfo::new(),
             screen_x: 0,
             screen_y: 0,
-            screen_scale: 0,
+            screen_width: 0,
+            screen_height: 0,
             screen_mode: 0,
+            scaling_mode: ScalingMode::from_index(scaling_mode.unwrap_or(SCALING_INTEGER)),
+            exclusive_fullscreen: false,
+            is_headless: false,
         }
     }
+
+    fn drop_frames(&mut self, count: u32) {
+        log::debug!("Dropped {count} frames");
+        self.dropped_frames += count;
+    }
 }
 
 impl Pyxel {
@@ -59,6 +215,25 @@ impl Pyxel {
         });
     }
 
+    pub fn run_fn(&mut self, update: impl FnMut(&mut Pyxel), draw: impl FnMut(&mut Pyxel)) {
+        struct App<U, D> {
+            update: U,
+            draw: D,
+        }
+
+        impl<U: FnMut(&mut Pyxel), D: FnMut(&mut Pyxel)> PyxelCallback for App<U, D> {
+            fn update(&mut self, pyxel: &mut Pyxel) {
+                (self.update)(pyxel);
+            }
+
+            fn draw(&mut self, pyxel: &mut Pyxel) {
+                (self.draw)(pyxel);
+            }
+        }
+
+        self.run(App { update, draw });
+    }
+
     pub fn show(&mut self) {
         struct App {
             image: SharedImage,
@@ -94,32 +269,68 @@ impl Pyxel {
         self.run(App { image });
     }
 
-    pub fn flip(&mut self) {
+    // Raw platform events received since the previous update, for custom input handling
+    pub fn poll_events(&self) -> &[Event] {
+        &self.system.events
+    }
+
+    // The browser owns the main loop on the web, so a frame can't be finished from inside
+    // the game's own loop there and games have to use run instead
+    pub fn flip(&mut self) -> PyxelResult<()> {
         cfg_if! {
             if #[cfg(target_os = "emscripten")] {
-                panic!("flip is not supported for Web");
+                Err(PyxelError::Platform(
+                    "flip is not supported on the web, use run instead".to_string(),
+                ))
             } else {
                 self.process_frame_for_flip();
+                Ok(())
             }
         }
     }
 
+    // While run is active on desktop, quitting takes effect once the current frame ends, so the
+    // code after this call still runs and callers should return from update or draw themselves.
+    // Outside run and on the web, the process exits immediately.
     pub fn quit(&self) {
         pyxel_platform::quit();
     }
 
+    pub fn set_quit_handler(&mut self, handler: Option<QuitHandler>) {
+        self.system.quit_handler = handler;
+        self.system.is_quit_handler_replaced = true;
+    }
+
+    // Desktop platforms have no screen reader bridge in SDL, so games can route
+    // announcements to a speech or accessibility library with a handler
+    pub fn set_announce_handler(&mut self, handler: Option<AnnounceHandler>) {
+        self.system.announce_handler = handler;
+    }
+
+    pub fn announce(&mut self, text: &str) {
+        log::info!("Announce: {text}");
+        if let Some(handler) = &mut self.system.announce_handler {
+            handler(text);
+            return;
+        }
+        #[cfg(target_os = "emscripten")]
+        pyxel_platform::emscripten::announce(text);
+    }
+
     pub fn title(&self, title: &str) {
         pyxel_platform::set_window_title(title);
     }
 
-    pub fn icon(&self, data_str: &[&str], scale: u32, transparent: Option<Color>) {
+    pub fn icon(
+        &self,
+        data_str: &[&str],
+        scale: u32,
+        transparent: Option<Color>,
+    ) -> PyxelResult<()> {
         let colors = self.colors.lock();
-        let width = utils::simplify_string(data_str[0]).len() as u32;
-        let height = data_str.len() as u32;
-        let image = Image::new(width, height);
-        let mut image = image.lock();
-        image.set(0, 0, data_str);
-        let image_data = &image.canvas.data;
+        let rows = utils::parse_hex_rows(data_str, 1).map_err(PyxelError::InvalidArgument)?;
+        let width = rows[0].len() as u32;
+        let height = rows.len() as u32;
         let scaled_width = width * scale;
         let scaled_height = height * scale;
         let mut rgba_data: Vec<u8> =
@@ -127,8 +338,8 @@ impl Pyxel {
         for y in 0..height {
             for _sy in 0..scale {
                 for x in 0..width {
-                    let color = image_data[(width * y + x) as usize];
-                    let rgb = colors[color as usize];
+                    let color = rows[y as usize][x as usize] as Color;
+                    let rgb = colors.get(color as usize).copied().unwrap_or(0);
                     let r = (rgb >> 16) as u8;
                     let g = (rgb >> 8) as u8;
                     let b = rgb as u8;
@@ -147,33 +358,164 @@ impl Pyxel {
             }
         }
         pyxel_platform::set_window_icon(scaled_width, scaled_height, &rgba_data);
+        Ok(())
+    }
+
+    pub fn fullscreen(&mut self, full: bool, exclusive: Option<bool>) {
+        if let Some(exclusive) = exclusive {
+            self.system.exclusive_fullscreen = exclusive;
+        }
+        pyxel_platform::set_fullscreen(full, self.system.exclusive_fullscreen);
+    }
+
+    // Ratio of drawable pixels to window units, which is above 1.0 on high-DPI displays
+    pub fn dpi_scale(&self) -> f64 {
+        let (window_width, _) = pyxel_platform::window_size();
+        let (drawable_width, _) = pyxel_platform::drawable_size();
+        if window_width == 0 || drawable_width == 0 {
+            1.0
+        } else {
+            drawable_width as f64 / window_width as f64
+        }
+    }
+
+    pub fn window_pos(&self) -> (i32, i32) {
+        pyxel_platform::window_pos()
+    }
+
+    pub fn set_window_pos(&self, x: i32, y: i32) {
+        pyxel_platform::set_window_pos(x, y);
     }
 
-    pub fn fullscreen(&self, full: bool) {
-        pyxel_platform::set_fullscreen(full);
+    pub fn borderless(&self, enabled: bool) {
+        pyxel_platform::set_window_bordered(!enabled);
+    }
+
+    pub fn always_on_top(&self, enabled: bool) {
+        pyxel_platform::set_window_always_on_top(enabled);
+    }
+
+    pub fn displays(&self) -> Vec<String> {
+        pyxel_platform::display_names()
+    }
+
+    pub fn set_display(&mut self, display_index: u32) {
+        pyxel_platform::set_window_display(display_index);
+        self.update_screen_params();
     }
 
     pub fn screen_mode(&mut self, screen_mode: u32) {
         self.system.screen_mode = screen_mode;
     }
 
-    fn process_events(&mut self) {
+    pub fn scaling_mode(&mut self, scaling_mode: u32) {
+        self.system.scaling_mode = ScalingMode::from_index(scaling_mode);
+        self.update_screen_params();
+    }
+
+    pub fn current_scaling_mode(&self) -> u32 {
+        self.system.scaling_mode.index()
+    }
+
+    pub fn set_screen_size(&mut self, width: u32, height: u32) -> PyxelResult<()> {
+        if width == 0 || height == 0 {
+            return Err(PyxelError::InvalidArgument(format!(
+                "Invalid screen size {width}x{height}"
+            )));
+        }
+        let screen = Image::new(width, height);
+        swap(&mut *self.screen.lock(), &mut *screen.lock());
+        self.width = width;
+        self.height = height;
+        self.reset_screencast();
+        self.update_screen_params();
+        Ok(())
+    }
+
+    pub fn set_fps(&mut self, fps: u32) {
+        self.system.uncapped_fps = fps == 0;
+        if fps > 0 {
+            self.system.one_frame_ms = 1000.0 / fps as f64;
+        }
+        pyxel_platform::set_loop_wait_enabled(fps > 0);
+    }
+
+    pub fn vsync(&self, enabled: bool) {
+        pyxel_platform::set_vsync(enabled);
+    }
+
+    pub fn set_quit_key(&mut self, key: Option<Key>) {
+        self.system.quit_key = key.unwrap_or(KEY_NONE);
+    }
+
+    pub fn system_keys(&self) -> SystemKeys {
+        self.system.system_keys
+    }
+
+    pub fn set_system_keys(&mut self, keys: SystemKeys) {
+        self.system.system_keys = keys;
+    }
+
+    // Milliseconds since startup, on the same clock as btn_press_time
+    pub fn elapsed_time(&self) -> u32 {
+        pyxel_platform::elapsed_time()
+    }
+
+    pub fn dropped_frames(&self) -> u32 {
+        self.system.dropped_frames
+    }
+
+    pub fn perf(&self) -> Perf {
+        Perf {
+            fps: self.system.fps_profiler.average_fps(),
+            update: self.system.update_profiler.stats(),
+            draw: self.system.draw_profiler.stats(),
+            frame: self.system.fps_profiler.stats(),
+            histogram: self.system.fps_profiler.histogram(NUM_PERF_HISTOGRAM_BINS),
+            draw_calls: self.system.draw_calls,
+            blit_pixels: self.system.blit_pixels,
+            slow_audio_callbacks: self.slow_audio_callbacks(),
+        }
+    }
+
+    pub fn perf_window(&mut self, num_frames: u32) {
+        self.system.fps_profiler = Profiler::new(num_frames);
+        self.system.update_profiler = Profiler::new(num_frames);
+        self.system.draw_profiler = Profiler::new(num_frames);
+    }
+
+    fn process_events(&mut self, mut callback: Option<&mut (dyn PyxelCallback + '_)>) {
         self.reset_input_states();
         let events = pyxel_platform::poll_events();
-        for event in events {
+        for event in events.iter().cloned() {
             match event {
                 Event::WindowShown => {
                     self.system.paused = false;
                     pyxel_platform::set_audio_enabled(true);
+                    if let Some(callback) = callback.as_deref_mut() {
+                        callback.on_resume(self);
+                    }
                 }
                 Event::WindowHidden => {
                     self.system.paused = true;
                     pyxel_platform::set_audio_enabled(false);
+                    if let Some(callback) = callback.as_deref_mut() {
+                        callback.on_pause(self);
+                    }
+                }
+                Event::WindowResized { width, height } => {
+                    self.update_screen_params();
+                    if let Some(callback) = callback.as_deref_mut() {
+                        callback.on_resize(self, width, height);
+                    }
+                }
+                Event::SubWindowClosed => {
+                    self.close_debug_window();
                 }
-                Event::KeyPressed { key } => {
-                    self.press_key(key);
+                Event::KeyPressed { key, timestamp } => {
+                    self.press_key(key, timestamp);
                 }
-                Event::KeyReleased { key } => {
+                Event::KeyReleased { key, .. } => {
                     self.release_key(key);
                 }
                 Event::KeyValueChanged { key, value } => {
@@ -182,79 +524,261 @@ impl Pyxel {
                 Event::TextInput { text } => {
                     self.add_input_text(&text);
                 }
-                Event::FileDropped { filename } => {
-                    self.add_dropped_file(&filename);
+                Event::TextEditing { text, cursor } => {
+                    self.set_input_text_composition(&text, cursor);
+                }
+                Event::FileDropped { filename, data } => {
+                    self.add_dropped_file(&filename, data);
+                    if let Some(callback) = callback.as_deref_mut() {
+                        callback.on_drop_file(self, &filename);
+                    }
                 }
                 Event::Quit => {
-                    pyxel_platform::quit();
+                    self.request_quit(callback.as_deref_mut());
                 }
+                Event::WindowFocusGained | Event::WindowFocusLost => {}
             }
         }
+        self.system.events = events;
     }
 
-    fn check_special_input(&mut self) {
-        if self.btnp(self.system.quit_key, None, None) {
+    pub(crate) fn request_quit(&mut self, callback: Option<&mut (dyn PyxelCallback + '_)>) {
+        let mut should_quit = match callback {
+            Some(callback) => callback.on_quit_request(self),
+            None => true,
+        };
+        if should_quit {
+            if let Some(mut handler) = self.system.quit_handler.take() {
+                self.system.is_quit_handler_replaced = false;
+                should_quit = handler(self);
+
+                // A handler set or cleared from inside the handler takes its place
+                if !self.system.is_quit_handler_replaced {
+                    self.system.quit_handler = Some(handler);
+                }
+            }
+        }
+        if should_quit {
             self.quit();
-        } else if self.btn(KEY_ALT) {
-            if self.btn(KEY_SHIFT) {
+        }
+    }
+
+    pub(crate) fn print_error(&mut self, result: PyxelResult<()>) {
+        if let Err(err) = result {
+            log::error!("{err}");
+            self.dbg_print(&err.to_string());
+        }
+    }
+
+    fn check_special_input(&mut self, callback: Option<&mut (dyn PyxelCallback + '_)>) {
+        let keys = self.system.system_keys;
+        if self.btnp(self.system.quit_key, None, None) {
+            self.request_quit(callback);
+        } else if self.btn(keys.modifier) {
+            if self.btn(keys.dump_modifier) {
                 if self.btnp(KEY_0, None, None) {
-                    self.dump_palette();
+                    let result = self.dump_palette();
+                    self.print_error(result);
                 } else {
                     for i in 0..=8 {
                         if self.btnp(KEY_1 + i, None, None) {
-                            self.dump_image_bank(i);
+                            let result = self.dump_image_bank(i);
+                            self.print_error(result);
                         }
                     }
                 }
-            } else if self.btnp(KEY_0, None, None) {
-                self.system.perf_monitor_enabled = !self.system.perf_monitor_enabled;
-            } else if self.btnp(KEY_1, None, None) {
-                self.screenshot(None);
-            } else if self.btnp(KEY_2, None, None) {
+            } else if self.btnp(keys.perf_monitor, None, None) {
+                self.system.perf_monitor_level =
+                    (self.system.perf_monitor_level + 1) % NUM_PERF_MONITOR_LEVELS;
+            } else if self.btnp(keys.screenshot, None, None) {
+                let result = self.screenshot(None);
+                self.print_error(result);
+            } else if self.btnp(keys.reset_screencast, None, None) {
                 self.reset_screencast();
-            } else if self.btnp(KEY_3, None, None) {
-                self.screencast(None);
-            } else if self.btnp(KEY_9, None, None) {
+            } else if self.btnp(keys.screencast, None, None) {
+                let result = self.screencast(None);
+                self.print_error(result);
+            } else if self.btnp(keys.debug_console, None, None) {
+                self.system.debug_console.visible = !self.system.debug_console.visible;
+            } else if self.btnp(keys.palette_editor, None, None) {
+                self.system.palette_editor.visible = !self.system.palette_editor.visible;
+            } else if self.btnp(keys.bank_viewer, None, None) {
+                self.system.bank_viewer.visible = !self.system.bank_viewer.visible;
+            } else if self.btnp(keys.frame_step, None, None) {
+                self.system.frame_step_enabled = !self.system.frame_step_enabled;
+                self.system.frame_step_requested = false;
+            } else if self.system.frame_step_enabled && self.btnp(keys.frame_advance, None, None) {
+                self.system.frame_step_requested = true;
+            } else if self.btnp(keys.screen_mode, None, None) {
                 self.system.screen_mode = (self.system.screen_mode + 1) % NUM_SCREEN_TYPES;
-            } else if self.btnp(KEY_RETURN, None, None) {
-                self.fullscreen(!pyxel_platform::is_fullscreen());
+            } else if self.btnp(keys.fullscreen, None, None) {
+                self.fullscreen(!pyxel_platform::is_fullscreen(), None);
             }
         }
     }
 
-    fn update_screen_params(&mut self) {
-        let (window_width, window_height) = pyxel_platform::window_size();
-        self.system.screen_scale = max(
-            min(window_width / self.width, window_height / self.height),
-            1,
-        );
-        self.system.screen_x =
-            (window_width as i32 - (self.width * self.system.screen_scale) as i32) / 2;
-        self.system.screen_y =
-            (window_height as i32 - (self.height * self.system.screen_scale) as i32) / 2;
+    pub(crate) fn update_screen_params(&mut self) {
+        // Headless runs have no window, so the display keeps the size it was created with
+        let (window_width, window_height) = if self.system.is_headless {
+            (self.display_width, self.display_height)
+        } else {
+            pyxel_platform::window_size()
+        };
+        self.display_width = window_width;
+        self.display_height = window_height;
+        let (screen_width, screen_height) = match self.system.scaling_mode {
+            ScalingMode::Integer => {
+                let scale = max(
+                    min(window_width / self.width, window_height / self.height),
+                    1,
+                );
+                (self.width * scale, self.height * scale)
+            }
+            ScalingMode::Stretch => (window_width, window_height),
+            ScalingMode::FitKeepAspect => {
+                let scale = f64::min(
+                    window_width as f64 / self.width as f64,
+                    window_height as f64 / self.height as f64,
+                );
+                (
+                    (self.width as f64 * scale) as u32,
+                    (self.height as f64 * scale) as u32,
+                )
+            }
+        };
+        self.system.screen_width = max(screen_width, 1);
+        self.system.screen_height = max(screen_height, 1);
+        self.system.screen_x = (window_width as i32 - self.system.screen_width as i32) / 2;
+        self.system.screen_y = (window_height as i32 - self.system.screen_height as i32) / 2;
     }
 
-    fn update_frame(&mut self, callback: Option<&mut dyn PyxelCallback>) {
+    fn log_slow_audio_callbacks(&mut self) {
+        let num_slow_callbacks = self.slow_audio_callbacks();
+        let num_new_slow_callbacks =
+            num_slow_callbacks.saturating_sub(self.system.logged_slow_audio_callbacks);
+        if num_new_slow_callbacks > 0 {
+            log::debug!(
+                "{num_new_slow_callbacks} slow audio callbacks ({num_slow_callbacks} in total)"
+            );
+            self.system.logged_slow_audio_callbacks = num_slow_callbacks;
+        }
+    }
+
+    fn update_frame(&mut self, mut callback: Option<&mut dyn PyxelCallback>) {
         self.system
             .update_profiler
             .start(pyxel_platform::elapsed_time());
-        self.process_events();
+        self.process_events(callback.as_deref_mut());
+        self.log_slow_audio_callbacks();
         if self.system.paused {
             return;
         }
-        self.check_special_input();
-        if let Some(callback) = callback {
-            callback.update(self);
+        self.check_special_input(callback.as_deref_mut());
+        self.check_hot_reload();
+        self.apply_pending_loads();
+        self.update_virtual_gamepad();
+        self.update_palette_editor();
+        self.update_bank_viewer();
+        if self.update_pause_menu(callback.as_deref_mut()) {
+            return;
+        }
+        if self.system.frame_step_enabled {
+            // Input states are keyed by frame_count, so it keeps advancing while held
+            if !self.system.frame_step_requested {
+                return;
+            }
+            self.system.frame_step_requested = false;
+        }
+        #[cfg(feature = "net")]
+        self.receive_net_messages();
+        let has_callback = callback.is_some();
+        self.update_game(callback);
+        if has_callback {
             self.system
                 .update_profiler
                 .end(pyxel_platform::elapsed_time());
         }
     }
 
+    // The part of a frame that changes game state, which advance_with_inputs also runs
+    pub(crate) fn update_game(&mut self, callback: Option<&mut dyn PyxelCallback>) {
+        self.poll_tasks();
+        self.check_idle();
+        if let Some(callback) = callback {
+            callback.update(self);
+        }
+        self.update_scenes();
+    }
+
+    pub(crate) fn draw_over_screen(&self, draw: impl FnOnce(&mut Image)) {
+        let mut screen = self.screen.lock();
+        let clip_rect = screen.canvas.clip_rect;
+        let camera_x = screen.canvas.camera_x;
+        let camera_y = screen.canvas.camera_y;
+        let palette = screen.palette;
+        let alpha = screen.canvas.alpha;
+        screen.clip0();
+        screen.camera0();
+        screen.pal0();
+        screen.dither(1.0);
+        draw(&mut screen);
+        screen.canvas.clip_rect = clip_rect;
+        screen.canvas.camera_x = camera_x;
+        screen.canvas.camera_y = camera_y;
+        screen.palette = palette;
+        screen.dither(alpha);
+    }
+
+    fn draw_frame_step_indicator(&self) {
+        if !self.system.frame_step_enabled {
+            return;
+        }
+        let label = "STEP";
+        let width = (label.len() as u32 * FONT_WIDTH + 1) as f64;
+        let x = self.width as f64 - width;
+        self.draw_over_screen(|screen| {
+            screen.rect(x, 0.0, width, (FONT_HEIGHT + 1) as f64, COLOR_BLACK);
+            screen.text(x + 1.0, 1.0, label, COLOR_YELLOW);
+        });
+    }
+
+    fn bank_memory_sizes(&self) -> (usize, usize) {
+        let image_size = self
+            .images
+            .lock()
+            .iter()
+            .map(|image| image.lock().canvas.data.len() * size_of::<Color>())
+            .sum();
+        let tilemap_size = self
+            .tilemaps
+            .lock()
+            .iter()
+            .map(|tilemap| tilemap.lock().canvas.data.len() * size_of::<Tile>())
+            .sum();
+        (image_size, tilemap_size)
+    }
+
     fn draw_perf_monitor(&self) {
-        if !self.system.perf_monitor_enabled {
+        let level = self.system.perf_monitor_level;
+        if level == 0 {
             return;
         }
+        let mut lines = vec![
+            format!("{:.*}", 2, self.system.fps_profiler.average_fps()),
+            format!("{:.*}", 2, self.system.update_profiler.average_time()),
+            format!("{:.*}", 2, self.system.draw_profiler.average_time()),
+        ];
+        if level >= 2 {
+            lines.push(format!("DRW {}", self.system.draw_calls));
+            lines.push(format!("BLT {}", self.system.blit_pixels));
+        }
+        if level >= 3 {
+            let (image_size, tilemap_size) = self.bank_memory_sizes();
+            lines.push(format!("AUD {}", self.slow_audio_callbacks()));
+            lines.push(format!("IMG {}K", image_size / 1024));
+            lines.push(format!("TMP {}K", tilemap_size / 1024));
+        }
+
         let mut screen = self.screen.lock();
         let clip_rect = screen.canvas.clip_rect;
         let camera_x = screen.canvas.camera_x;
@@ -268,17 +792,11 @@ impl Pyxel {
         screen.pal(2, 9);
         screen.dither(1.0);
 
-        let fps = format!("{:.*}", 2, self.system.fps_profiler.average_fps());
-        screen.text(1.0, 0.0, &fps, 1);
-        screen.text(0.0, 0.0, &fps, 2);
-
-        let update_time = format!("{:.*}", 2, self.system.update_profiler.average_time());
-        screen.text(1.0, 6.0, &update_time, 1);
-        screen.text(0.0, 6.0, &update_time, 2);
-
-        let draw_time = format!("{:.*}", 2, self.system.draw_profiler.average_time());
-        screen.text(1.0, 12.0, &draw_time, 1);
-        screen.text(0.0, 12.0, &draw_time, 2);
+        for (i, line) in lines.iter().enumerate() {
+            let y = (i as u32 * FONT_HEIGHT) as f64;
+            screen.text(1.0, y, line, 1);
+            screen.text(0.0, y, line, 2);
+        }
 
         screen.canvas.clip_rect = clip_rect;
         screen.canvas.camera_x = camera_x;
@@ -291,14 +809,22 @@ impl Pyxel {
     fn draw_cursor(&self) {
         let x = self.mouse_x;
         let y = self.mouse_y;
-        pyxel_platform::set_mouse_visible(
-            x < 0 || x >= self.width as i32 || y < 0 || y >= self.height as i32,
-        );
-        if !self.is_mouse_visible() {
+        let is_outside = x < 0 || x >= self.width as i32 || y < 0 || y >= self.height as i32;
+        if self.is_system_cursor_used() {
+            pyxel_platform::set_mouse_visible(is_outside || self.is_mouse_visible());
             return;
         }
-        let width = self.cursor.lock().width() as i32;
-        let height = self.cursor.lock().height() as i32;
+        pyxel_platform::set_mouse_visible(is_outside);
+
+        // Parts of the image around the hotspot could still overlap the screen, so the position
+        // of the mouse rather than the image decides whether the OS cursor takes over
+        if is_outside || !self.is_mouse_visible() {
+            return;
+        }
+        let (hot_x, hot_y) = self.cursor_hotspot();
+        let x = x - hot_x;
+        let y = y - hot_y;
+        let (width, height) = (self.cursor.width() as i32, self.cursor.height() as i32);
         if x <= -width || x >= self.width as i32 || y <= -height || y >= self.height as i32 {
             return;
         }
@@ -332,13 +858,29 @@ impl Pyxel {
         self.system
             .draw_profiler
             .start(pyxel_platform::elapsed_time());
-        if let Some(callback) = callback {
-            callback.draw(self);
+        if self.system.pause_menu.is_open() {
+            self.draw_pause_menu();
+        } else {
+            if let Some(callback) = callback {
+                callback.draw(self);
+            }
+            self.draw_scenes();
+            self.draw_viewports();
         }
+        (self.system.draw_calls, self.system.blit_pixels) = canvas::take_draw_stats();
         self.system.watch_info.update();
+        self.advance_transition();
+        self.draw_bank_viewer();
         self.draw_perf_monitor();
+        self.draw_palette_editor();
+        self.draw_debug_console();
+        self.draw_frame_step_indicator();
+        self.draw_virtual_gamepad();
         self.draw_cursor();
+        canvas::take_draw_stats(); // Exclude overlays from the next frame's stats
         self.render_screen();
+        self.render_debug_window();
+        self.restore_bank_viewer_screen();
         self.capture_screen();
         self.system
             .draw_profiler
@@ -349,6 +891,12 @@ impl Pyxel {
         let tick_count = pyxel_platform::elapsed_time();
         let elapsed_ms = tick_count as f64 - self.system.next_update_ms;
         if elapsed_ms < 0.0 {
+            if self.system.uncapped_fps {
+                self.system.fps_profiler.end(tick_count);
+                self.system.fps_profiler.start(tick_count);
+                self.update_screen_params();
+                self.draw_frame(Some(callback));
+            }
             return;
         }
         if self.frame_count == 0 {
@@ -356,15 +904,22 @@ impl Pyxel {
         } else {
             self.system.fps_profiler.end(tick_count);
             self.system.fps_profiler.start(tick_count);
-            let update_count: u32;
-            if elapsed_ms > MAX_ELAPSED_MS as f64 {
+            let mut update_count: u32;
+            if elapsed_ms > self.system.max_elapsed_ms as f64 {
                 update_count = 1;
+                self.system
+                    .drop_frames((elapsed_ms / self.system.one_frame_ms) as u32);
                 self.system.next_update_ms =
                     pyxel_platform::elapsed_time() as f64 + self.system.one_frame_ms;
             } else {
                 update_count = (elapsed_ms / self.system.one_frame_ms) as u32 + 1;
                 self.system.next_update_ms += self.system.one_frame_ms * update_count as f64;
             }
+            if update_count > self.system.max_update_per_frame {
+                self.system
+                    .drop_frames(update_count - self.system.max_update_per_frame);
+                update_count = self.system.max_update_per_frame;
+            }
             for _ in 1..update_count {
                 self.update_frame(Some(callback));
                 self.frame_count += 1;
@@ -398,7 +953,9 @@ impl Pyxel {
         }
         self.system.fps_profiler.end(tick_count);
         self.system.fps_profiler.start(tick_count);
-        if elapsed_ms > MAX_ELAPSED_MS as f64 {
+        if elapsed_ms > self.system.max_elapsed_ms as f64 {
+            self.system
+                .drop_frames((elapsed_ms / self.system.one_frame_ms) as u32);
             self.system.next_update_ms =
                 pyxel_platform::elapsed_time() as f64 + self.system.one_frame_ms;
         } else {
@@ -407,3 +964,75 @@ impl Pyxel {
         self.update_frame(None);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{KEY_CTRL, KEY_D};
+    use crate::pyxel::headless_pyxel;
+    use crate::settings::{SCALING_FIT, SCALING_INTEGER, SCALING_STRETCH};
+
+    #[test]
+    fn test_system_keys() {
+        let mut pyxel = headless_pyxel(16, 8);
+        assert_eq!(pyxel.system_keys(), SystemKeys::default());
+        pyxel.set_system_keys(SystemKeys {
+            modifier: KEY_CTRL,
+            debug_console: KEY_D,
+            palette_editor: KEY_NONE,
+            ..SystemKeys::default()
+        });
+
+        pyxel.press_key(KEY_ALT, 0);
+        pyxel.press_key(KEY_C, 0);
+        pyxel.check_special_input(None);
+        assert!(!pyxel.system.debug_console.visible);
+
+        pyxel.press_key(KEY_CTRL, 0);
+        pyxel.press_key(KEY_D, 0);
+        pyxel.check_special_input(None);
+        assert!(pyxel.system.debug_console.visible);
+
+        pyxel.frame_count += 1;
+        pyxel.press_key(KEY_P, 0);
+        pyxel.check_special_input(None);
+        assert!(!pyxel.system.palette_editor.visible);
+    }
+
+    #[test]
+    fn test_quit_handler_cleared_by_itself() {
+        let mut pyxel = headless_pyxel(16, 8);
+        pyxel.set_quit_handler(Some(Box::new(|pyxel| {
+            pyxel.set_quit_handler(None);
+            false
+        })));
+        pyxel.request_quit(None);
+        assert!(pyxel.system.quit_handler.is_none());
+    }
+
+    #[test]
+    fn test_current_scaling_mode() {
+        let mut pyxel = headless_pyxel(16, 8);
+        assert_eq!(pyxel.current_scaling_mode(), SCALING_INTEGER);
+        pyxel.scaling_mode(SCALING_FIT);
+        assert_eq!(pyxel.current_scaling_mode(), SCALING_FIT);
+        pyxel.scaling_mode(SCALING_STRETCH);
+        assert_eq!(pyxel.current_scaling_mode(), SCALING_STRETCH);
+        pyxel.scaling_mode(99);
+        assert_eq!(pyxel.current_scaling_mode(), SCALING_INTEGER);
+    }
+
+    #[test]
+    fn test_set_screen_size() {
+        let mut pyxel = headless_pyxel(16, 8);
+        assert!(pyxel.set_screen_size(0, 8).is_err());
+        assert!(pyxel.set_screen_size(16, 0).is_err());
+        assert_eq!((pyxel.width, pyxel.height), (16, 8));
+        assert_eq!(pyxel.screen.lock().width(), 16);
+
+        pyxel.set_screen_size(32, 24).unwrap();
+        assert_eq!((pyxel.width, pyxel.height), (32, 24));
+        let screen = pyxel.screen.lock();
+        assert_eq!((screen.width(), screen.height()), (32, 24));
+    }
+}
@@ -0,0 +1,146 @@
+use std::mem;
+
+use crate::font::{self, Font};
+use crate::image::{Color, Image};
+use crate::pyxel::{Pyxel, FALLBACK_FONT};
+use crate::settings::{
+    FONT_WIDTH, MAX_FONT_CODE, MIN_FONT_CODE, TEXT_ALIGN_CENTER, TEXT_ALIGN_RIGHT,
+};
+
+// Characters missing from both fonts are skipped by text, so they don't take up any width
+fn char_width(c: char, font: Option<&Font>) -> u32 {
+    if (MIN_FONT_CODE..=MAX_FONT_CODE).contains(&c) {
+        FONT_WIDTH
+    } else {
+        font.and_then(|font| font.advance(c)).unwrap_or(0)
+    }
+}
+
+fn line_width(line: &str, font: Option<&Font>) -> u32 {
+    line.chars().map(|c| char_width(c, font)).sum()
+}
+
+pub fn text_width(string: &str) -> u32 {
+    let font = FALLBACK_FONT.lock();
+    string
+        .lines()
+        .map(|line| line_width(line, font.as_ref()))
+        .max()
+        .unwrap_or(0)
+}
+
+fn text_height(string: &str) -> u32 {
+    string.lines().count().max(1) as u32 * font::line_height(FALLBACK_FONT.lock().as_ref())
+}
+
+// Breaks lines on spaces to fit the width, splitting words that are longer than a whole line
+pub fn wrap_text(string: &str, width: u32) -> String {
+    let font = FALLBACK_FONT.lock();
+    let font = font.as_ref();
+    let mut lines = Vec::new();
+    for paragraph in string.split('\n') {
+        let mut line = String::new();
+        let mut cur_width = 0;
+        for word in paragraph.split(' ') {
+            let mut word: Vec<char> = word.chars().collect();
+            while word.len() > 1 && word.iter().map(|&c| char_width(c, font)).sum::<u32>() > width {
+                let mut count = 1;
+                let mut head_width = char_width(word[0], font);
+                while count < word.len() && head_width + char_width(word[count], font) <= width {
+                    head_width += char_width(word[count], font);
+                    count += 1;
+                }
+                if !line.is_empty() {
+                    lines.push(mem::take(&mut line));
+                    cur_width = 0;
+                }
+                lines.push(word.drain(..count).collect());
+            }
+            let word: String = word.into_iter().collect();
+            let word_width = line_width(&word, font);
+            if line.is_empty() && !word.is_empty() {
+                line = word;
+                cur_width = word_width;
+            } else if cur_width + FONT_WIDTH + word_width <= width {
+                line.push(' ');
+                line.push_str(&word);
+                cur_width += FONT_WIDTH + word_width;
+            } else {
+                lines.push(mem::replace(&mut line, word));
+                cur_width = word_width;
+            }
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+impl Image {
+    pub fn text_aligned(&mut self, x: f64, y: f64, string: &str, color: Color, align: u32) {
+        let (widths, line_height) = {
+            let font = FALLBACK_FONT.lock();
+            let widths: Vec<u32> = string
+                .lines()
+                .map(|line| line_width(line, font.as_ref()))
+                .collect();
+            (widths, font::line_height(font.as_ref()))
+        };
+        for (i, (line, width)) in string.lines().zip(widths).enumerate() {
+            let width = width as f64;
+            let x = match align {
+                TEXT_ALIGN_CENTER => x - (width / 2.0).floor(),
+                TEXT_ALIGN_RIGHT => x - width,
+                _ => x,
+            };
+            self.text(x, y + (i as u32 * line_height) as f64, line, color);
+        }
+    }
+
+    pub fn text_wrapped(&mut self, x: f64, y: f64, width: f64, string: &str, color: Color) -> u32 {
+        let wrapped = wrap_text(string, width.max(0.0) as u32);
+        self.text(x, y, &wrapped, color);
+        text_height(&wrapped)
+    }
+}
+
+impl Pyxel {
+    pub fn text_width(&self, string: &str) -> u32 {
+        text_width(string)
+    }
+
+    pub fn text_aligned(&self, x: f64, y: f64, string: &str, color: Color, align: u32) {
+        self.render_target()
+            .lock()
+            .text_aligned(x, y, string, color, align);
+    }
+
+    // Returns the height of the drawn text so that following content can be placed below it
+    pub fn text_wrapped(&self, x: f64, y: f64, width: f64, string: &str, color: Color) -> u32 {
+        self.render_target()
+            .lock()
+            .text_wrapped(x, y, width, string, color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::FONT_HEIGHT;
+
+    #[test]
+    fn test_text_width() {
+        assert_eq!(text_width(""), 0);
+        assert_eq!(text_width("abc"), 3 * FONT_WIDTH);
+        assert_eq!(text_width("ab\nabcd\na"), 4 * FONT_WIDTH);
+        assert_eq!(text_height("ab\nabcd\na"), 3 * FONT_HEIGHT);
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        let width = 10 * FONT_WIDTH;
+        assert_eq!(wrap_text("hello world", width), "hello\nworld");
+        assert_eq!(wrap_text("a b c d", width), "a b c d");
+        assert_eq!(wrap_text("abcdefghijklmn", width), "abcdefghij\nklmn");
+        assert_eq!(wrap_text("one\ntwo three", width), "one\ntwo three");
+    }
+}
@@ -1,11 +1,10 @@
-use std::fs::File;
-use std::io::Read;
-
 use serde::Deserialize;
 
+use crate::error::{PyxelError, PyxelResult};
 use crate::settings::TILE_SIZE;
 use crate::tilemap::{ImageSource, Tilemap};
 use crate::utils::remove_whitespace;
+use crate::vfs;
 use crate::SharedTilemap;
 
 #[derive(Debug, Deserialize)]
@@ -40,71 +39,100 @@ struct TiledMapFile {
 }
 
 impl Tilemap {
-    pub fn from_tmx(filename: &str, layer_index: u32) -> SharedTilemap {
-        macro_rules! assert_or_break {
-            ($condition:expr, $fmt:expr $(,$arg:tt)*) => {
-                if !$condition {
-                    println!($fmt, $($arg)*);
-                    break;
-                }
-            };
+    pub fn from_tmx(filename: &str, layer_index: u32) -> PyxelResult<SharedTilemap> {
+        let invalid_file = |reason: &str| {
+            log::warn!("{reason} in '{filename}'");
+            PyxelError::InvalidFile(filename.to_string())
+        };
+        let tmx_data =
+            vfs::read_file(filename).ok_or_else(|| PyxelError::FileOpen(filename.to_string()))?;
+        let tmx_text =
+            String::from_utf8(tmx_data).map_err(|_| invalid_file("Failed to read TMX"))?;
+        let tmx: TiledMapFile =
+            serde_xml_rs::from_str(&tmx_text).map_err(|_| invalid_file("Failed to parse TMX"))?;
+        if tmx.tilewidth != TILE_SIZE || tmx.tileheight != TILE_SIZE {
+            return Err(invalid_file(&format!(
+                "Tile size is not {TILE_SIZE}x{TILE_SIZE}"
+            )));
+        }
+        let tileset = tmx
+            .tilesets
+            .first()
+            .ok_or_else(|| invalid_file("Tileset not found"))?;
+        let tileset_columns = tileset
+            .columns
+            .filter(|columns| *columns > 0)
+            .ok_or_else(|| invalid_file("Tileset is not embedded"))?;
+        let layer = tmx
+            .layers
+            .get(layer_index as usize)
+            .ok_or_else(|| invalid_file(&format!("Layer {layer_index} not found")))?;
+        if layer.data.encoding != "csv" {
+            return Err(invalid_file("Encoding is not CSV"));
+        }
+        if layer.width == 0 || layer.height == 0 {
+            return Err(invalid_file("Layer is empty"));
+        }
+        let layer_data = remove_whitespace(&layer.data.tiles)
+            .split(',')
+            .map(|s| s.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid_file("Failed to parse tiles"))?;
+        if layer_data.len() as u64 > layer.width as u64 * layer.height as u64 {
+            return Err(invalid_file("Layer has more tiles than its size"));
         }
-        #[allow(clippy::never_loop)]
-        loop {
-            let file = File::open(filename);
-            assert_or_break!(file.is_ok(), "Failed to open file '{filename}'");
-            let mut file = file.unwrap();
-            let mut tmx_text = String::new();
-            assert_or_break!(
-                file.read_to_string(&mut tmx_text).is_ok(),
-                "Failed to read TMX file"
-            );
-            let tmx = serde_xml_rs::from_str(&tmx_text);
-            assert_or_break!(tmx.is_ok(), "Failed to parse TMX file");
-            let tmx: TiledMapFile = tmx.unwrap();
-            assert_or_break!(
-                tmx.tilewidth == TILE_SIZE && tmx.tileheight == TILE_SIZE,
-                "TMX file's tile size is not {TILE_SIZE}x{TILE_SIZE}"
-            );
-            assert_or_break!(!tmx.tilesets.is_empty(), "Tileset not found in TMX file");
-            let tileset = &tmx.tilesets[0];
-            assert_or_break!(
-                tileset.columns.is_some(),
-                "Tileset is not embedded in TMX file"
-            );
-            let tileset_columns = tileset.columns.unwrap();
-            assert_or_break!(
-                layer_index < tmx.layers.len() as u32,
-                "Layer {layer_index} not found in TMX file"
-            );
-            let layer = &tmx.layers[layer_index as usize];
-            assert_or_break!(
-                layer.data.encoding == "csv",
-                "TMX file's encoding is not CSV"
-            );
-            let layer_data: Vec<u32> = remove_whitespace(&layer.data.tiles)
-                .split(',')
-                .map(|s| s.parse::<u32>().unwrap())
-                .collect();
-            let tilemap = Self::new(layer.width, layer.height, ImageSource::Index(0));
-            {
-                let mut tilemap = tilemap.lock();
-                for (i, tile_id) in layer_data.iter().enumerate() {
-                    let x = i % layer.width as usize;
-                    let y = i / layer.width as usize;
-                    let tile_id = if *tile_id > tileset.firstgid {
-                        tile_id - tileset.firstgid
-                    } else {
-                        0
-                    };
-                    let tile_x = (tile_id % tileset_columns) as u8;
-                    let tile_y = (tile_id / tileset_columns) as u8;
-                    tilemap.canvas.write_data(x, y, (tile_x, tile_y));
-                }
+
+        let tilemap = Self::new(layer.width, layer.height, ImageSource::Index(0));
+        {
+            let mut tilemap = tilemap.lock();
+            for (i, tile_id) in layer_data.iter().enumerate() {
+                let x = i % layer.width as usize;
+                let y = i / layer.width as usize;
+                let tile_id = tile_id.saturating_sub(tileset.firstgid);
+                let tile_x = (tile_id % tileset_columns) as u8;
+                let tile_y = (tile_id / tileset_columns) as u8;
+                tilemap.canvas.write_data(x, y, (tile_x, tile_y));
             }
-            return tilemap;
         }
-        // Return a blank tilemap due to an error
-        Self::new(1, 1, ImageSource::Index(0))
+        Ok(tilemap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    fn load_tmx(name: &str, columns: u32, width: u32, tiles: &str) -> PyxelResult<SharedTilemap> {
+        let tmx_text = format!(
+            r#"<map tilewidth="8" tileheight="8">
+                <tileset firstgid="1" columns="{columns}"/>
+                <layer width="{width}" height="2"><data encoding="csv">{tiles}</data></layer>
+            </map>"#
+        );
+        let filename = env::temp_dir().join(format!("pyxel_{name}_{}.tmx", std::process::id()));
+        fs::write(&filename, tmx_text).unwrap();
+        let result = Tilemap::from_tmx(filename.to_str().unwrap(), 0);
+        fs::remove_file(&filename).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_from_tmx() {
+        let tilemap = load_tmx("tmx_valid", 4, 2, "1,2,6,0").unwrap();
+        let tilemap = tilemap.lock();
+        assert_eq!(tilemap.pget(1, 0), (1, 0));
+        assert_eq!(tilemap.pget(0, 1), (1, 1));
+        assert_eq!(tilemap.pget(1, 1), (0, 0));
+    }
+
+    #[test]
+    fn test_from_tmx_malformed() {
+        assert!(load_tmx("tmx_no_columns", 0, 2, "1,2,3,4").is_err());
+        assert!(load_tmx("tmx_no_width", 4, 0, "1,2,3,4").is_err());
+        assert!(load_tmx("tmx_bad_tile", 4, 2, "1,x,3,4").is_err());
+        assert!(load_tmx("tmx_extra_tiles", 4, 2, "1,2,3,4,5").is_err());
+        assert!(Tilemap::from_tmx("missing.tmx", 0).is_err());
     }
 }
@@ -1,6 +1,8 @@
-use crate::canvas::{Canvas, ToIndex};
+use crate::canvas::{Canvas, CopyRow, IntoCoord, ToIndex};
+use crate::error::{PyxelError, PyxelResult};
 use crate::image::SharedImage;
-use crate::utils::{f64_to_u32, parse_hex_string, simplify_string};
+use crate::settings::TILE_SIZE;
+use crate::utils::parse_hex_rows;
 
 pub type TileCoord = u8;
 pub type Tile = (TileCoord, TileCoord);
@@ -11,6 +13,8 @@ impl ToIndex for Tile {
     }
 }
 
+impl CopyRow for Tile {}
+
 #[derive(Clone)]
 pub enum ImageSource {
     Index(u32),
@@ -44,20 +48,18 @@ impl Tilemap {
         self.canvas.data_ptr()
     }
 
-    pub fn set(&mut self, x: i32, y: i32, data_str: &[&str]) {
-        let width = simplify_string(data_str[0]).len() as u32 / 4;
-        let height = data_str.len() as u32;
+    pub fn set(&mut self, x: i32, y: i32, data_str: &[&str]) -> PyxelResult<()> {
+        let rows = parse_hex_rows(data_str, 4).map_err(PyxelError::InvalidArgument)?;
+        let width = rows[0].len() as u32;
+        let height = rows.len() as u32;
         let tilemap = Self::new(width, height, self.imgsrc.clone());
         {
             let mut tilemap = tilemap.lock();
-            for y in 0..height {
-                let src_data = simplify_string(data_str[y as usize]);
-                for x in 0..width {
-                    let index = x as usize * 4;
-                    let tile = parse_hex_string(&src_data[index..index + 4]).unwrap();
+            for (y, row) in rows.iter().enumerate() {
+                for (x, tile) in row.iter().enumerate() {
                     tilemap.canvas.write_data(
-                        x as usize,
-                        y as usize,
+                        x,
+                        y,
                         (
                             ((tile >> 8) & 0xff) as TileCoord,
                             (tile & 0xff) as TileCoord,
@@ -76,12 +78,15 @@ impl Tilemap {
             height as f64,
             None,
         );
+        Ok(())
     }
 
-    pub fn load(&mut self, x: i32, y: i32, filename: &str, layer_index: u32) {
-        let tilemap = Self::from_tmx(filename, layer_index);
-        let tilemap_width = tilemap.lock().width();
-        let tilemap_height = tilemap.lock().height();
+    pub fn load(&mut self, x: i32, y: i32, filename: &str, layer_index: u32) -> PyxelResult<()> {
+        let tilemap = Self::from_tmx(filename, layer_index)?;
+        let (tilemap_width, tilemap_height) = {
+            let tilemap = tilemap.lock();
+            (tilemap.width(), tilemap.height())
+        };
         self.blt(
             x as f64,
             y as f64,
@@ -92,9 +97,16 @@ impl Tilemap {
             tilemap_height as f64,
             None,
         );
+        Ok(())
     }
 
-    pub fn clip(&mut self, x: f64, y: f64, width: f64, height: f64) {
+    pub fn clip(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+    ) {
         self.canvas.clip(x, y, width, height);
     }
 
@@ -102,7 +114,7 @@ impl Tilemap {
         self.canvas.clip0();
     }
 
-    pub fn camera(&mut self, x: f64, y: f64) {
+    pub fn camera(&mut self, x: impl IntoCoord, y: impl IntoCoord) {
         self.canvas.camera(x, y);
     }
 
@@ -114,63 +126,179 @@ impl Tilemap {
         self.canvas.cls(tile);
     }
 
-    pub fn pget(&mut self, x: f64, y: f64) -> Tile {
+    pub fn pget(&self, x: impl IntoCoord, y: impl IntoCoord) -> Tile {
         self.canvas.pget(x, y)
     }
 
-    pub fn pset(&mut self, x: f64, y: f64, tile: Tile) {
+    pub fn pset(&mut self, x: impl IntoCoord, y: impl IntoCoord, tile: Tile) {
         self.canvas.pset(x, y, tile);
     }
 
-    pub fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, tile: Tile) {
+    // Unlike pget and pset, tile access ignores the clip area and reports out-of-range positions
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<Tile> {
+        if x < 0 || y < 0 || x >= self.width() as i32 || y >= self.height() as i32 {
+            None
+        } else {
+            Some(self.canvas.read_data(x as usize, y as usize))
+        }
+    }
+
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: Tile) -> bool {
+        if x < 0 || y < 0 || x >= self.width() as i32 || y >= self.height() as i32 {
+            return false;
+        }
+        let width = self.width() as usize;
+        self.canvas.data[width * y as usize + x as usize] = tile;
+        true
+    }
+
+    pub fn rect_overlaps(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        solid_tiles: &[Tile],
+    ) -> bool {
+        if width <= 0.0 || height <= 0.0 {
+            return false;
+        }
+        let tile_size = TILE_SIZE as f64;
+        // Only the tiles inside the tilemap are scanned, however large the rect is
+        let left = ((x / tile_size).floor() as i32).max(0);
+        let top = ((y / tile_size).floor() as i32).max(0);
+        // The right and bottom edges are exclusive so rects touching a tile don't overlap it
+        let right = (((x + width) / tile_size).ceil() as i32)
+            .saturating_sub(1)
+            .min(self.width() as i32 - 1);
+        let bottom = (((y + height) / tile_size).ceil() as i32)
+            .saturating_sub(1)
+            .min(self.height() as i32 - 1);
+        if left > right || top > bottom {
+            return false;
+        }
+        (top..=bottom).any(|tile_y| {
+            (left..=right).any(|tile_x| {
+                self.get_tile(tile_x, tile_y)
+                    .is_some_and(|tile| solid_tiles.contains(&tile))
+            })
+        })
+    }
+
+    pub fn line(
+        &mut self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        tile: Tile,
+    ) {
         self.canvas.line(x1, y1, x2, y2, tile);
     }
 
-    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, tile: Tile) {
+    pub fn rect(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        tile: Tile,
+    ) {
         self.canvas.rect(x, y, width, height, tile);
     }
 
-    pub fn rectb(&mut self, x: f64, y: f64, width: f64, height: f64, tile: Tile) {
+    pub fn rectb(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        tile: Tile,
+    ) {
         self.canvas.rectb(x, y, width, height, tile);
     }
 
-    pub fn circ(&mut self, x: f64, y: f64, radius: f64, tile: Tile) {
+    pub fn circ(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        radius: impl IntoCoord,
+        tile: Tile,
+    ) {
         self.canvas.circ(x, y, radius, tile);
     }
 
-    pub fn circb(&mut self, x: f64, y: f64, radius: f64, tile: Tile) {
+    pub fn circb(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        radius: impl IntoCoord,
+        tile: Tile,
+    ) {
         self.canvas.circb(x, y, radius, tile);
     }
 
-    pub fn elli(&mut self, x: f64, y: f64, width: f64, height: f64, tile: Tile) {
+    pub fn elli(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        tile: Tile,
+    ) {
         self.canvas.elli(x, y, width, height, tile);
     }
 
-    pub fn ellib(&mut self, x: f64, y: f64, width: f64, height: f64, tile: Tile) {
+    pub fn ellib(
+        &mut self,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
+        tile: Tile,
+    ) {
         self.canvas.ellib(x, y, width, height, tile);
     }
 
-    pub fn tri(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, tile: Tile) {
+    pub fn tri(
+        &mut self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        x3: impl IntoCoord,
+        y3: impl IntoCoord,
+        tile: Tile,
+    ) {
         self.canvas.tri(x1, y1, x2, y2, x3, y3, tile);
     }
 
-    pub fn trib(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, tile: Tile) {
+    pub fn trib(
+        &mut self,
+        x1: impl IntoCoord,
+        y1: impl IntoCoord,
+        x2: impl IntoCoord,
+        y2: impl IntoCoord,
+        x3: impl IntoCoord,
+        y3: impl IntoCoord,
+        tile: Tile,
+    ) {
         self.canvas.trib(x1, y1, x2, y2, x3, y3, tile);
     }
 
-    pub fn fill(&mut self, x: f64, y: f64, tile: Tile) {
+    pub fn fill(&mut self, x: impl IntoCoord, y: impl IntoCoord, tile: Tile) {
         self.canvas.fill(x, y, tile);
     }
 
     pub fn blt(
         &mut self,
-        x: f64,
-        y: f64,
+        x: impl IntoCoord,
+        y: impl IntoCoord,
         tilemap: SharedTilemap,
-        tilemap_x: f64,
-        tilemap_y: f64,
-        width: f64,
-        height: f64,
+        tilemap_x: impl IntoCoord,
+        tilemap_y: impl IntoCoord,
+        width: impl IntoCoord,
+        height: impl IntoCoord,
         transparent: Option<Tile>,
     ) {
         if let Some(tilemap) = tilemap.try_lock() {
@@ -186,8 +314,8 @@ impl Tilemap {
                 None,
             );
         } else {
-            let copy_width = f64_to_u32(width.abs());
-            let copy_height = f64_to_u32(height.abs());
+            let copy_width = width.to_coord().unsigned_abs();
+            let copy_height = height.to_coord().unsigned_abs();
             let mut canvas = Canvas::new(copy_width, copy_height);
             canvas.blt(
                 0.0,
@@ -195,8 +323,8 @@ impl Tilemap {
                 &self.canvas,
                 tilemap_x,
                 tilemap_y,
-                copy_width as f64,
-                copy_height as f64,
+                copy_width as i32,
+                copy_height as i32,
                 None,
                 None,
             );
@@ -205,3 +333,123 @@ impl Tilemap {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tilemap_set() {
+        let tilemap = Tilemap::new(4, 4, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+        tilemap.set(1, 1, &["0102 0304", "0506 0708"]).unwrap();
+        assert_eq!(tilemap.pget(2, 2), (7, 8));
+        assert!(matches!(
+            tilemap.set(0, 0, &["0102", "01"]),
+            Err(PyxelError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            tilemap.set(0, 0, &["zzzz"]),
+            Err(PyxelError::InvalidArgument(_))
+        ));
+        assert_eq!(tilemap.pget(0, 0), (0, 0));
+    }
+
+    #[test]
+    fn test_tilemap_line() {
+        let tilemap = Tilemap::new(8, 8, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+        tilemap.line(1.0, 2.0, 6.0, 2.0, (3, 4));
+        for x in 0..8 {
+            let tile = if (1..=6).contains(&x) { (3, 4) } else { (0, 0) };
+            assert_eq!(tilemap.pget(x as f64, 2.0), tile);
+        }
+        assert_eq!(tilemap.pget(3.0, 1.0), (0, 0));
+    }
+
+    #[test]
+    fn test_tilemap_rect() {
+        let tilemap = Tilemap::new(8, 8, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+        tilemap.rect(1.0, 1.0, 4.0, 3.0, (1, 0));
+        tilemap.rectb(0.0, 0.0, 8.0, 8.0, (2, 0));
+        assert_eq!(tilemap.pget(0.0, 0.0), (2, 0));
+        assert_eq!(tilemap.pget(7.0, 7.0), (2, 0));
+        assert_eq!(tilemap.pget(1.0, 1.0), (1, 0));
+        assert_eq!(tilemap.pget(4.0, 3.0), (1, 0));
+        assert_eq!(tilemap.pget(5.0, 3.0), (0, 0));
+        assert_eq!(tilemap.pget(4.0, 4.0), (0, 0));
+    }
+
+    #[test]
+    fn test_tilemap_int_coords() {
+        let tilemap = Tilemap::new(8, 8, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+        tilemap.rect(1, 1, 4, 3, (1, 0));
+        tilemap.pset(1.5, 5.5, (2, 0));
+        assert_eq!(tilemap.pget(4, 3), (1, 0));
+        assert_eq!(tilemap.pget(5, 3), (0, 0));
+        assert_eq!(tilemap.pget(2, 6), (2, 0));
+        assert_eq!(tilemap.pget(1, 5), (0, 0));
+    }
+
+    #[test]
+    fn test_tilemap_circ() {
+        let tilemap = Tilemap::new(9, 9, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+        tilemap.circ(4.0, 4.0, 2.0, (5, 5));
+        assert_eq!(tilemap.pget(4.0, 4.0), (5, 5));
+        assert_eq!(tilemap.pget(4.0, 2.0), (5, 5));
+        assert_eq!(tilemap.pget(6.0, 4.0), (5, 5));
+        assert_eq!(tilemap.pget(0.0, 0.0), (0, 0));
+        assert_eq!(tilemap.pget(4.0, 7.0), (0, 0));
+    }
+
+    #[test]
+    fn test_tilemap_fill() {
+        let tilemap = Tilemap::new(8, 8, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+        tilemap.rectb(2.0, 2.0, 4.0, 4.0, (1, 1));
+        tilemap.fill(3.0, 3.0, (2, 2));
+        assert_eq!(tilemap.pget(3.0, 3.0), (2, 2));
+        assert_eq!(tilemap.pget(4.0, 4.0), (2, 2));
+        assert_eq!(tilemap.pget(2.0, 2.0), (1, 1));
+        assert_eq!(tilemap.pget(0.0, 0.0), (0, 0));
+
+        tilemap.fill(0.0, 0.0, (3, 3));
+        assert_eq!(tilemap.pget(7.0, 7.0), (3, 3));
+        assert_eq!(tilemap.pget(3.0, 3.0), (2, 2));
+    }
+
+    #[test]
+    fn test_tilemap_rect_overlaps() {
+        let tilemap = Tilemap::new(4, 4, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+        assert!(tilemap.set_tile(2, 1, (1, 0)));
+        assert!(!tilemap.set_tile(4, 0, (1, 0)));
+        assert_eq!(tilemap.get_tile(2, 1), Some((1, 0)));
+        assert_eq!(tilemap.get_tile(-1, 0), None);
+
+        let solid_tiles = [(1, 0)];
+        assert!(tilemap.rect_overlaps(15.0, 7.0, 2.0, 2.0, &solid_tiles));
+        assert!(!tilemap.rect_overlaps(8.0, 8.0, 8.0, 8.0, &solid_tiles));
+        assert!(!tilemap.rect_overlaps(16.0, 0.0, 8.0, 8.0, &solid_tiles));
+        assert!(!tilemap.rect_overlaps(-8.0, -8.0, 4.0, 4.0, &solid_tiles));
+    }
+
+    #[test]
+    fn test_tilemap_rect_overlaps_out_of_bounds() {
+        let tilemap = Tilemap::new(4, 4, ImageSource::Index(0));
+        let mut tilemap = tilemap.lock();
+        tilemap.set_tile(0, 0, (1, 0));
+        tilemap.set_tile(3, 3, (1, 0));
+
+        let solid_tiles = [(1, 0)];
+        assert!(tilemap.rect_overlaps(-1.0e9, -1.0e9, 2.0e9, 2.0e9, &solid_tiles));
+        assert!(tilemap.rect_overlaps(-100.0, -100.0, 101.0, 101.0, &solid_tiles));
+        assert!(tilemap.rect_overlaps(31.0, 31.0, 100.0, 100.0, &solid_tiles));
+        assert!(!tilemap.rect_overlaps(32.0, 0.0, 1.0e9, 8.0, &solid_tiles));
+        assert!(!tilemap.rect_overlaps(0.0, -1.0e9, 8.0, 1.0e9, &solid_tiles));
+        assert!(!tilemap.rect_overlaps(-1.0e30, -1.0e30, 1.0, 1.0, &solid_tiles));
+    }
+}
@@ -0,0 +1,431 @@
+use crate::editor::{
+    self, Clip, EditHistory, EditRecord, Selection, Tool, BUTTON_WIDTH, CANVAS_PIXELS, CANVAS_X,
+    CANVAS_Y, TOOL_Y, VIEWER_SIZE, VIEWER_X, VIEWER_Y,
+};
+use crate::image::{Image, SharedImage};
+use crate::keys::{
+    KEY_B, KEY_C, KEY_DOWN, KEY_LEFT, KEY_P, KEY_RIGHT, KEY_S, KEY_SHIFT, KEY_UP, KEY_V, KEY_Y,
+    KEY_Z, MOUSE_BUTTON_LEFT, MOUSE_BUTTON_RIGHT,
+};
+use crate::pyxel::Pyxel;
+use crate::settings::{COLOR_WHITE, COLOR_YELLOW, FONT_HEIGHT, HOT_CLICK, TILE_SIZE};
+use crate::tilemap::{ImageSource, SharedTilemap, Tile, TileCoord};
+
+const ZOOM_LEVELS: [i32; 3] = [1, 2, 4];
+const HOT_ID_BASE: u32 = editor::HOT_ID_BASE + 0x40;
+const TILEMAP_HOT_ID: u32 = HOT_ID_BASE + 0x10;
+const ZOOM_HOT_ID: u32 = TILEMAP_HOT_ID + 1;
+const PICKER_HOT_ID: u32 = ZOOM_HOT_ID + 1;
+
+pub struct TilemapEditor {
+    tool: Tool,
+    tile: Tile,
+    tilemap: usize,
+    zoom_index: usize,
+    canvas_x: i32,
+    canvas_y: i32,
+    picker_x: i32,
+    picker_y: i32,
+    last_pos: Option<(i32, i32)>,
+    is_painting: bool,
+    selection: Selection,
+    clip: Option<Clip<Tile>>,
+    history: EditHistory<EditRecord<Tile>>,
+}
+
+impl TilemapEditor {
+    pub fn new() -> Self {
+        Self {
+            tool: Tool::Pencil,
+            tile: (0, 0),
+            tilemap: 0,
+            zoom_index: 0,
+            canvas_x: 0,
+            canvas_y: 0,
+            picker_x: 0,
+            picker_y: 0,
+            last_pos: None,
+            is_painting: false,
+            selection: Selection::new(),
+            clip: None,
+            history: EditHistory::new(),
+        }
+    }
+
+    fn tilemap(&self, pyxel: &Pyxel) -> SharedTilemap {
+        pyxel.tilemaps.lock()[self.tilemap].clone()
+    }
+
+    fn image(&self, pyxel: &Pyxel) -> SharedImage {
+        match &self.tilemap(pyxel).lock().imgsrc {
+            ImageSource::Index(index) => pyxel.images.lock()[*index as usize].clone(),
+            ImageSource::Image(image) => image.clone(),
+        }
+    }
+
+    // Number of tiles shown along each side of the canvas at the current zoom
+    fn view_size(&self) -> i32 {
+        CANVAS_PIXELS / (TILE_SIZE as i32 * ZOOM_LEVELS[self.zoom_index])
+    }
+
+    fn read_canvas(&self, pyxel: &Pyxel) -> Vec<Tile> {
+        let tilemap = self.tilemap(pyxel);
+        let tilemap = tilemap.lock();
+        editor::read_region(
+            &tilemap.canvas,
+            self.canvas_x,
+            self.canvas_y,
+            self.view_size(),
+        )
+    }
+
+    fn write_canvas(&self, pyxel: &Pyxel, data: &[Tile]) {
+        let tilemap = self.tilemap(pyxel);
+        let mut tilemap = tilemap.lock();
+        editor::write_region(
+            &mut tilemap.canvas,
+            self.canvas_x,
+            self.canvas_y,
+            self.view_size(),
+            data,
+        );
+    }
+
+    fn push_undo(&mut self, pyxel: &Pyxel) {
+        self.history.push(EditRecord {
+            index: self.tilemap,
+            x: self.canvas_x,
+            y: self.canvas_y,
+            size: self.view_size(),
+            data: self.read_canvas(pyxel),
+        });
+    }
+
+    fn undo(&mut self, pyxel: &Pyxel, undo: bool) {
+        let Some(mut record) = self.history.take(undo) else {
+            return;
+        };
+        self.tilemap = record.index;
+        self.canvas_x = record.x;
+        self.canvas_y = record.y;
+        self.selection.clear();
+        let tilemap = self.tilemap(pyxel);
+        let mut tilemap = tilemap.lock();
+        let data = editor::read_region(&tilemap.canvas, record.x, record.y, record.size);
+        editor::write_region(
+            &mut tilemap.canvas,
+            record.x,
+            record.y,
+            record.size,
+            &record.data,
+        );
+        record.data = data;
+        self.history.put_back(record, undo);
+    }
+
+    fn canvas_pos(&self, pyxel: &Pyxel) -> Option<(i32, i32)> {
+        let tile_pixels = TILE_SIZE as i32 * ZOOM_LEVELS[self.zoom_index];
+        let x = (pyxel.mouse_x - CANVAS_X).div_euclid(tile_pixels);
+        let y = (pyxel.mouse_y - CANVAS_Y).div_euclid(tile_pixels);
+        let view_size = self.view_size();
+        ((0..view_size).contains(&x) && (0..view_size).contains(&y)).then_some((x, y))
+    }
+
+    fn move_canvas(&mut self, pyxel: &Pyxel, x: i32, y: i32) {
+        let tilemap = self.tilemap(pyxel);
+        let tilemap = tilemap.lock();
+        let view_size = self.view_size();
+        self.canvas_x = x.clamp(0, (tilemap.width() as i32 - view_size).max(0));
+        self.canvas_y = y.clamp(0, (tilemap.height() as i32 - view_size).max(0));
+        self.selection.clear();
+    }
+
+    fn scroll_picker(&mut self, pyxel: &Pyxel, dx: i32, dy: i32) {
+        let image = self.image(pyxel);
+        let image = image.lock();
+        let max_x = (image.width() as i32 - VIEWER_SIZE).max(0);
+        let max_y = (image.height() as i32 - VIEWER_SIZE).max(0);
+        self.picker_x = (self.picker_x + dx).clamp(0, max_x);
+        self.picker_y = (self.picker_y + dy).clamp(0, max_y);
+    }
+
+    fn update_buttons(&mut self, pyxel: &mut Pyxel) {
+        editor::update_tool_buttons(pyxel, HOT_ID_BASE, &mut self.tool);
+        let hot_state = pyxel.engine_hot_rect(
+            TILEMAP_HOT_ID,
+            VIEWER_X as f64,
+            TOOL_Y as f64,
+            (BUTTON_WIDTH * 2) as f64,
+            FONT_HEIGHT as f64,
+        );
+        if hot_state == HOT_CLICK {
+            self.tilemap = (self.tilemap + 1) % pyxel.tilemaps.lock().len();
+            self.move_canvas(pyxel, self.canvas_x, self.canvas_y);
+            self.scroll_picker(pyxel, 0, 0);
+        }
+        let hot_state = pyxel.engine_hot_rect(
+            ZOOM_HOT_ID,
+            (VIEWER_X + VIEWER_SIZE - BUTTON_WIDTH) as f64,
+            TOOL_Y as f64,
+            BUTTON_WIDTH as f64,
+            FONT_HEIGHT as f64,
+        );
+        if hot_state == HOT_CLICK {
+            self.zoom_index = (self.zoom_index + 1) % ZOOM_LEVELS.len();
+            self.move_canvas(pyxel, self.canvas_x, self.canvas_y);
+        }
+    }
+
+    fn update_shortcuts(&mut self, pyxel: &mut Pyxel, message: &mut String) {
+        if editor::is_ctrl_pressed(pyxel) {
+            if pyxel.btnp(KEY_Z, None, None) {
+                self.undo(pyxel, true);
+            } else if pyxel.btnp(KEY_Y, None, None) {
+                self.undo(pyxel, false);
+            } else if pyxel.btnp(KEY_C, None, None) {
+                let data = self.read_canvas(pyxel);
+                let view_size = self.view_size();
+                self.clip = Some(editor::copy_area(&data, view_size, self.selection.area));
+                *message = "COPIED".to_string();
+            } else if pyxel.btnp(KEY_V, None, None) && self.clip.is_some() {
+                self.push_undo(pyxel);
+                let (left, top, _, _) = self.selection.area.unwrap_or((0, 0, 0, 0));
+                let mut data = self.read_canvas(pyxel);
+                editor::paste_clip(
+                    &mut data,
+                    self.view_size(),
+                    self.clip.as_ref().unwrap(),
+                    left,
+                    top,
+                );
+                self.write_canvas(pyxel, &data);
+            }
+            return;
+        }
+        for (key, tool) in [
+            (KEY_P, Tool::Pencil),
+            (KEY_B, Tool::Fill),
+            (KEY_S, Tool::Select),
+        ] {
+            if pyxel.btnp(key, None, None) {
+                self.tool = tool;
+            }
+        }
+        for (key, dx, dy) in [
+            (KEY_LEFT, -1, 0),
+            (KEY_RIGHT, 1, 0),
+            (KEY_UP, 0, -1),
+            (KEY_DOWN, 0, 1),
+        ] {
+            if pyxel.btnp(key, Some(10), Some(2)) {
+                self.move_canvas(pyxel, self.canvas_x + dx, self.canvas_y + dy);
+            }
+        }
+    }
+
+    fn update_picker(&mut self, pyxel: &mut Pyxel) {
+        let (left, top, size) = (VIEWER_X as f64, VIEWER_Y as f64, VIEWER_SIZE as f64);
+        if !pyxel.mouse_in(left, top, size, size) {
+            return;
+        }
+        let scroll = -pyxel.mouse_wheel * TILE_SIZE as i32;
+        if pyxel.btn(KEY_SHIFT) {
+            self.scroll_picker(pyxel, scroll, 0);
+        } else {
+            self.scroll_picker(pyxel, 0, scroll);
+        }
+        if pyxel.engine_hot_rect(PICKER_HOT_ID, left, top, size, size) == HOT_CLICK {
+            let tile_size = TILE_SIZE as i32;
+            let u = (self.picker_x + pyxel.mouse_x - VIEWER_X) / tile_size;
+            let v = (self.picker_y + pyxel.mouse_y - VIEWER_Y) / tile_size;
+            self.tile = (u as TileCoord, v as TileCoord);
+        }
+    }
+
+    fn update_canvas(&mut self, pyxel: &mut Pyxel) {
+        if !pyxel.btn(MOUSE_BUTTON_LEFT) {
+            self.last_pos = None;
+            self.is_painting = false;
+            self.selection.release();
+        }
+        let Some((x, y)) = self.canvas_pos(pyxel) else {
+            self.last_pos = None;
+            return;
+        };
+        let view_size = self.view_size();
+        if pyxel.btnp(MOUSE_BUTTON_RIGHT, None, None) {
+            self.tile = self.read_canvas(pyxel)[(y * view_size + x) as usize];
+            return;
+        }
+        if !pyxel.btn(MOUSE_BUTTON_LEFT) {
+            return;
+        }
+        let is_pressed = pyxel.btnp(MOUSE_BUTTON_LEFT, None, None);
+        match self.tool {
+            Tool::Pencil => {
+                // Strokes may start outside the canvas, so undo is saved on the first painted frame
+                if !self.is_painting {
+                    self.push_undo(pyxel);
+                    self.is_painting = true;
+                }
+                let mut data = self.read_canvas(pyxel);
+                let last_pos = self.last_pos.unwrap_or((x, y));
+                editor::draw_stroke(&mut data, view_size, last_pos, (x, y), self.tile);
+                self.write_canvas(pyxel, &data);
+                self.last_pos = Some((x, y));
+            }
+            Tool::Fill => {
+                if is_pressed {
+                    self.push_undo(pyxel);
+                    let mut data = self.read_canvas(pyxel);
+                    editor::flood_fill(&mut data, view_size, x, y, self.tile);
+                    self.write_canvas(pyxel, &data);
+                }
+            }
+            Tool::Select => self.selection.update(is_pressed, x, y),
+        }
+    }
+
+    pub(crate) fn update(&mut self, pyxel: &mut Pyxel, message: &mut String) {
+        self.update_buttons(pyxel);
+        self.update_shortcuts(pyxel, message);
+        self.update_picker(pyxel);
+        self.update_canvas(pyxel);
+    }
+
+    fn draw_canvas(&self, pyxel: &Pyxel, screen: &mut Image) {
+        let (left, top) = (CANVAS_X as f64, CANVAS_Y as f64);
+        let size = CANVAS_PIXELS as f64;
+        let zoom = ZOOM_LEVELS[self.zoom_index];
+        let tile_size = TILE_SIZE as i32;
+        if zoom == 1 {
+            screen.bltm(
+                left,
+                top,
+                self.tilemap(pyxel),
+                (self.canvas_x * tile_size) as f64,
+                (self.canvas_y * tile_size) as f64,
+                size,
+                size,
+                None,
+            );
+        } else {
+            // Blitting has no scaling, so zoomed tiles are drawn a pixel at a time
+            let data = self.read_canvas(pyxel);
+            let image = self.image(pyxel);
+            let image = image.lock();
+            let (image_width, image_height) = (image.width() as i32, image.height() as i32);
+            let view_size = self.view_size();
+            for (i, (u, v)) in data.into_iter().enumerate() {
+                let tile_x = CANVAS_X + (i as i32 % view_size) * tile_size * zoom;
+                let tile_y = CANVAS_Y + (i as i32 / view_size) * tile_size * zoom;
+                for yi in 0..tile_size {
+                    for xi in 0..tile_size {
+                        let image_x = u as i32 * tile_size + xi;
+                        let image_y = v as i32 * tile_size + yi;
+                        if image_x >= image_width || image_y >= image_height {
+                            continue;
+                        }
+                        let color = image.canvas.read_data(image_x as usize, image_y as usize);
+                        screen.rect(
+                            (tile_x + xi * zoom) as f64,
+                            (tile_y + yi * zoom) as f64,
+                            zoom as f64,
+                            zoom as f64,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+        screen.rectb(left - 1.0, top - 1.0, size + 2.0, size + 2.0, COLOR_WHITE);
+        if let Some((x1, y1, x2, y2)) = self.selection.area {
+            let tile_pixels = tile_size * zoom;
+            screen.rectb(
+                left + (x1 * tile_pixels) as f64,
+                top + (y1 * tile_pixels) as f64,
+                ((x2 - x1 + 1) * tile_pixels) as f64,
+                ((y2 - y1 + 1) * tile_pixels) as f64,
+                COLOR_YELLOW,
+            );
+        }
+    }
+
+    fn draw_picker(&self, pyxel: &Pyxel, screen: &mut Image) {
+        let (left, top) = (VIEWER_X as f64, VIEWER_Y as f64);
+        let size = VIEWER_SIZE as f64;
+        screen.blt(
+            left,
+            top,
+            self.image(pyxel),
+            self.picker_x as f64,
+            self.picker_y as f64,
+            size,
+            size,
+            None,
+        );
+        screen.rectb(left - 1.0, top - 1.0, size + 2.0, size + 2.0, COLOR_WHITE);
+        let tile_size = TILE_SIZE as i32;
+        let tile_x = self.tile.0 as i32 * tile_size - self.picker_x;
+        let tile_y = self.tile.1 as i32 * tile_size - self.picker_y;
+        if (0..VIEWER_SIZE).contains(&tile_x) && (0..VIEWER_SIZE).contains(&tile_y) {
+            screen.rectb(
+                left + tile_x as f64,
+                top + tile_y as f64,
+                tile_size as f64,
+                tile_size as f64,
+                COLOR_YELLOW,
+            );
+        }
+        let tilemap_label = format!("TILEMAP {}", self.tilemap);
+        screen.text(left, TOOL_Y as f64, &tilemap_label, COLOR_WHITE);
+        let zoom_label = format!("x{}", ZOOM_LEVELS[self.zoom_index]);
+        let zoom_x = (VIEWER_X + VIEWER_SIZE - BUTTON_WIDTH) as f64;
+        screen.text(zoom_x, TOOL_Y as f64, &zoom_label, COLOR_WHITE);
+    }
+
+    // Returns the status text for the hovered tile
+    pub(crate) fn draw(&self, pyxel: &Pyxel, screen: &mut Image) -> Option<String> {
+        editor::draw_tool_buttons(screen, self.tool);
+        self.draw_canvas(pyxel, screen);
+        self.draw_picker(pyxel, screen);
+        let (x, y) = self.canvas_pos(pyxel)?;
+        let (u, v) = self.read_canvas(pyxel)[(y * self.view_size() + x) as usize];
+        Some(format!(
+            "{},{} TILE {u},{v}",
+            self.canvas_x + x,
+            self.canvas_y + y
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+
+    #[test]
+    fn test_stroke_from_outside_canvas() {
+        let mut pyxel = headless_pyxel(16, 16);
+        let mut editor = TilemapEditor::new();
+        editor.tile = (1, 2);
+        pyxel.press_key(MOUSE_BUTTON_LEFT, 0);
+        editor.update_canvas(&mut pyxel);
+
+        let tile_pixels = TILE_SIZE as i32;
+        for (frame_count, x) in [(1, 0), (2, 1)] {
+            pyxel.frame_count = frame_count;
+            pyxel.mouse_x = CANVAS_X + x * tile_pixels;
+            pyxel.mouse_y = CANVAS_Y;
+            editor.update_canvas(&mut pyxel);
+        }
+        let tilemap = pyxel.tilemaps.lock()[0].clone();
+        assert_eq!(tilemap.lock().pget(0, 0), (1, 2));
+        assert_eq!(tilemap.lock().pget(1, 0), (1, 2));
+
+        editor.undo(&pyxel, true);
+        assert_eq!(tilemap.lock().pget(0, 0), (0, 0));
+        assert_eq!(tilemap.lock().pget(1, 0), (0, 0));
+        assert!(editor.history.take(true).is_none());
+    }
+}
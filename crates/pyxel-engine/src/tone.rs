@@ -33,6 +33,7 @@ pub struct Tone {
     pub gain: Gain,
     pub noise: Noise,
     pub waveform: Waveform,
+    pub sample: Option<u32>,
 }
 
 pub type SharedTone = shared_type!(Tone);
@@ -43,6 +44,7 @@ impl Tone {
             gain: 1.0,
             noise: Noise::Off,
             waveform: [0; NUM_WAVEFORM_STEPS as usize],
+            sample: None,
         })
     }
 
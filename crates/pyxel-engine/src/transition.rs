@@ -0,0 +1,197 @@
+use crate::image::{Color, Rgb24};
+use crate::pyxel::Pyxel;
+use crate::settings::{COLOR_BLACK, MAX_PIXELATE_SIZE, TRANSITION_PIXELATE, TRANSITION_WIPE};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum TransitionKind {
+    Fade,
+    Wipe,
+    Pixelate,
+}
+
+impl TransitionKind {
+    fn from_index(index: u32) -> Self {
+        match index {
+            TRANSITION_WIPE => Self::Wipe,
+            TRANSITION_PIXELATE => Self::Pixelate,
+            _ => Self::Fade,
+        }
+    }
+}
+
+// Transitions are applied when the screen is presented, so they never touch the screen image
+// and don't appear in captures or accumulate on screens that aren't cleared every frame
+pub struct Transition {
+    kind: TransitionKind,
+    frames: u32,
+    frame_count: u32,
+    amount: f64,
+}
+
+impl Transition {
+    pub fn new() -> Self {
+        Self {
+            kind: TransitionKind::Fade,
+            frames: 0,
+            frame_count: 0,
+            amount: 0.0,
+        }
+    }
+
+    // Transitions reveal the screen, going from fully covered to clear over the given frames
+    fn advance(&mut self) {
+        if self.frame_count == 0 {
+            self.amount = 0.0;
+            return;
+        }
+        self.amount = self.frame_count as f64 / self.frames as f64;
+        self.frame_count -= 1;
+    }
+
+    pub(crate) fn fade_colors(&self, colors: &mut [Rgb24]) {
+        if self.kind != TransitionKind::Fade || self.amount <= 0.0 {
+            return;
+        }
+        let scale = 1.0 - self.amount;
+        for color in colors {
+            let fade = |shift: u32| (((*color >> shift) & 0xff) as f64 * scale).round() as Rgb24;
+            *color = (fade(16) << 16) | (fade(8) << 8) | fade(0);
+        }
+    }
+
+    pub(crate) fn apply_to_screen(
+        &self,
+        screen_data: &[Color],
+        width: u32,
+        height: u32,
+    ) -> Option<Vec<Color>> {
+        if self.amount <= 0.0 {
+            return None;
+        }
+        let width = width as usize;
+        let height = height as usize;
+        match self.kind {
+            TransitionKind::Fade => None,
+            TransitionKind::Wipe => {
+                let center_x = width as f64 / 2.0;
+                let center_y = height as f64 / 2.0;
+                let radius = (1.0 - self.amount) * center_x.hypot(center_y);
+                let mut data = screen_data.to_vec();
+                for (y, row) in data.chunks_mut(width).enumerate() {
+                    let dy = y as f64 + 0.5 - center_y;
+                    let half_width = (radius * radius - dy * dy).max(0.0).sqrt();
+                    let left = ((center_x - half_width).round().max(0.0) as usize).min(width);
+                    let right = ((center_x + half_width).round() as usize).clamp(left, width);
+                    row[..left].fill(COLOR_BLACK);
+                    row[right..].fill(COLOR_BLACK);
+                }
+                Some(data)
+            }
+            TransitionKind::Pixelate => {
+                let size = 1 + (self.amount * (MAX_PIXELATE_SIZE - 1) as f64).round() as usize;
+                if size <= 1 {
+                    return None;
+                }
+                let data = (0..width * height)
+                    .map(|i| {
+                        let x = i % width / size * size;
+                        let y = i / width / size * size;
+                        screen_data[y * width + x]
+                    })
+                    .collect();
+                Some(data)
+            }
+        }
+    }
+}
+
+impl Pyxel {
+    pub fn start_transition(&mut self, kind: u32, frames: u32) {
+        let transition = &mut self.system.transition;
+        transition.kind = TransitionKind::from_index(kind);
+        transition.frames = frames;
+        transition.frame_count = frames;
+    }
+
+    pub fn is_transitioning(&self) -> bool {
+        self.system.transition.frame_count > 0
+    }
+
+    pub(crate) fn advance_transition(&mut self) {
+        self.system.transition.advance();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::TRANSITION_FADE;
+
+    fn started_transition(kind: u32, frames: u32) -> Transition {
+        let mut transition = Transition::new();
+        transition.kind = TransitionKind::from_index(kind);
+        transition.frames = frames;
+        transition.frame_count = frames;
+        transition
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut transition = started_transition(TRANSITION_FADE, 4);
+        let mut amounts = Vec::new();
+        for _ in 0..6 {
+            transition.advance();
+            amounts.push(transition.amount);
+        }
+        assert_eq!(amounts, [1.0, 0.75, 0.5, 0.25, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_fade_colors() {
+        let mut transition = started_transition(TRANSITION_FADE, 2);
+        let mut colors = [0xffffff, 0x804020];
+        transition.fade_colors(&mut colors);
+        assert_eq!(colors, [0xffffff, 0x804020]);
+
+        transition.advance();
+        transition.fade_colors(&mut colors);
+        assert_eq!(colors, [0x000000, 0x000000]);
+
+        let mut colors = [0xffffff, 0x804020];
+        transition.advance();
+        transition.fade_colors(&mut colors);
+        assert_eq!(colors, [0x808080, 0x402010]);
+        assert!(transition.apply_to_screen(&[1; 4], 2, 2).is_none());
+    }
+
+    #[test]
+    fn test_wipe() {
+        let mut transition = started_transition(TRANSITION_WIPE, 2);
+        let screen_data = [7; 16];
+        transition.advance();
+        assert_eq!(
+            transition.apply_to_screen(&screen_data, 4, 4),
+            Some(vec![COLOR_BLACK; 16])
+        );
+
+        transition.advance();
+        let data = transition.apply_to_screen(&screen_data, 4, 4).unwrap();
+        assert_eq!(data[0], COLOR_BLACK);
+        assert_eq!(data[5], 7);
+        assert_eq!(data[10], 7);
+
+        transition.advance();
+        assert!(transition.apply_to_screen(&screen_data, 4, 4).is_none());
+    }
+
+    #[test]
+    fn test_pixelate() {
+        let mut transition = started_transition(TRANSITION_PIXELATE, 1);
+        let screen_data: Vec<Color> = (0..20 * 18).map(|i| (i % 16) as Color).collect();
+        transition.advance();
+        let data = transition.apply_to_screen(&screen_data, 20, 18).unwrap();
+        assert_eq!(data.len(), screen_data.len());
+        assert_eq!(data[15], screen_data[0]);
+        assert_eq!(data[20 * 17 + 19], screen_data[20 * 16 + 16]);
+    }
+}
@@ -0,0 +1,78 @@
+use std::env::var;
+use std::fs::read_to_string;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::settings::{
+    DISPLAY_SCALE_ENVVAR, FULLSCREEN_ENVVAR, SCALING_MODE_ENVVAR, USER_CONFIG_FILE, VSYNC_ENVVAR,
+};
+
+#[derive(Default, Deserialize)]
+pub struct UserConfig {
+    pub display_scale: Option<u32>,
+    pub scaling_mode: Option<u32>,
+    pub fullscreen: Option<bool>,
+    pub vsync: Option<bool>,
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "on" | "yes" => Some(true),
+        "0" | "false" | "off" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+fn env_value<T: FromStr>(name: &str) -> Option<T> {
+    var(name).ok().and_then(|value| value.trim().parse().ok())
+}
+
+impl UserConfig {
+    pub fn from_toml(toml_text: &str) -> Self {
+        toml::from_str(toml_text).unwrap_or_default()
+    }
+
+    // Environment variables take priority over the config file in the working directory
+    pub fn load() -> Self {
+        let config = read_to_string(USER_CONFIG_FILE)
+            .map(|toml_text| Self::from_toml(&toml_text))
+            .unwrap_or_default();
+        Self {
+            display_scale: env_value(DISPLAY_SCALE_ENVVAR).or(config.display_scale),
+            scaling_mode: env_value(SCALING_MODE_ENVVAR).or(config.scaling_mode),
+            fullscreen: var(FULLSCREEN_ENVVAR)
+                .ok()
+                .and_then(|value| parse_bool(&value))
+                .or(config.fullscreen),
+            vsync: var(VSYNC_ENVVAR)
+                .ok()
+                .and_then(|value| parse_bool(&value))
+                .or(config.vsync),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() {
+        let config = UserConfig::from_toml("display_scale = 3\nfullscreen = true\n");
+        assert_eq!(config.display_scale, Some(3));
+        assert_eq!(config.fullscreen, Some(true));
+        assert_eq!(config.vsync, None);
+        assert_eq!(
+            UserConfig::from_toml("display_scale = \"x\"").display_scale,
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_bool() {
+        assert_eq!(parse_bool("1"), Some(true));
+        assert_eq!(parse_bool(" False "), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+    }
+}
@@ -52,6 +52,24 @@ pub fn parse_hex_string(string: &str) -> Result<u32, &str> {
     Ok(result)
 }
 
+// Rows may be longer than the first row, whose length sets the width
+pub fn parse_hex_rows(data_str: &[&str], num_digits: usize) -> Result<Vec<Vec<u32>>, String> {
+    let rows: Vec<String> = data_str.iter().map(|row| simplify_string(row)).collect();
+    let width = rows.first().ok_or("Data is empty")?.len() / num_digits;
+    rows.iter()
+        .map(|row| {
+            (0..width)
+                .map(|x| {
+                    let hex = row
+                        .get(x * num_digits..(x + 1) * num_digits)
+                        .ok_or_else(|| format!("Data row '{row}' is too short"))?;
+                    parse_hex_string(hex).map_err(|_| format!("Invalid hex value '{hex}'"))
+                })
+                .collect()
+        })
+        .collect()
+}
+
 pub fn add_file_extension(filename: &str, ext: &str) -> String {
     if filename.to_lowercase().ends_with(ext) {
         filename.to_string()
@@ -60,6 +78,23 @@ pub fn add_file_extension(filename: &str, ext: &str) -> String {
     }
 }
 
+// Binary data is passed to and from JavaScript as hex strings on the web
+#[cfg(target_os = "emscripten")]
+pub fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(target_os = "emscripten")]
+pub fn decode_hex(string: &str) -> Option<Vec<u8>> {
+    if string.len() % 2 != 0 {
+        return None;
+    }
+    (0..string.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(string.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
 pub fn compress_vec<T: PartialEq + Clone>(vec: &[T]) -> Vec<T> {
     assert!(!vec.is_empty());
     let mut new_vec = vec.to_vec();
@@ -187,6 +222,22 @@ mod tests {
         assert_eq!(parse_hex_string(" "), Err("invalid hex string"));
     }
 
+    #[test]
+    fn test_parse_hex_rows() {
+        assert_eq!(
+            parse_hex_rows(&["01 2", "abcd"], 1),
+            Ok(vec![vec![0, 1, 2], vec![10, 11, 12]])
+        );
+        assert_eq!(
+            parse_hex_rows(&["00010a0b"], 4),
+            Ok(vec![vec![0x0001, 0x0a0b]])
+        );
+        assert!(parse_hex_rows(&[], 1).is_err());
+        assert!(parse_hex_rows(&["012", "01"], 1).is_err());
+        assert!(parse_hex_rows(&["01g"], 1).is_err());
+        assert!(parse_hex_rows(&["0é"], 1).is_err());
+    }
+
     #[test]
     fn test_compress_vec() {
         let vec = vec![1, 2, 2, 3, 3, 3];
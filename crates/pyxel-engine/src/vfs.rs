@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::fs;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::pyxel::Pyxel;
+
+pub trait FileSystem: Send {
+    fn read(&self, path: &str) -> Option<Vec<u8>>;
+}
+
+// Files bundled into the binary, typically with include_bytes!
+pub struct EmbeddedFs {
+    files: HashMap<String, &'static [u8]>,
+}
+
+impl EmbeddedFs {
+    pub fn new() -> Self {
+        Self {
+            files: HashMap::new(),
+        }
+    }
+
+    pub fn file(mut self, path: &str, data: &'static [u8]) -> Self {
+        self.files.insert(normalize_path(path), data);
+        self
+    }
+}
+
+impl Default for EmbeddedFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystem for EmbeddedFs {
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        self.files
+            .get(&normalize_path(path))
+            .map(|data| data.to_vec())
+    }
+}
+
+static FILE_SYSTEMS: Lazy<Mutex<Vec<Box<dyn FileSystem>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+pub(crate) fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches("./").to_string()
+}
+
+// The most recently mounted file system takes priority
+pub(crate) fn read_mounted_file(path: &str) -> Option<Vec<u8>> {
+    FILE_SYSTEMS
+        .lock()
+        .iter()
+        .rev()
+        .find_map(|file_system| file_system.read(path))
+}
+
+pub(crate) fn read_file(path: &str) -> Option<Vec<u8>> {
+    read_mounted_file(path).or_else(|| fs::read(path).ok())
+}
+
+impl Pyxel {
+    pub fn mount(&mut self, file_system: impl FileSystem + 'static) {
+        FILE_SYSTEMS.lock().push(Box::new(file_system));
+    }
+
+    pub fn unmount_all(&mut self) {
+        FILE_SYSTEMS.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_fs() {
+        let embedded_fs = EmbeddedFs::new().file("assets/test.txt", b"hello");
+        assert_eq!(embedded_fs.read("assets/test.txt"), Some(b"hello".to_vec()));
+        assert_eq!(
+            embedded_fs.read("./assets\\test.txt"),
+            Some(b"hello".to_vec())
+        );
+        assert_eq!(embedded_fs.read("assets/other.txt"), None);
+    }
+}
@@ -0,0 +1,239 @@
+use std::cmp::max;
+
+use cfg_if::cfg_if;
+
+use crate::keys::{
+    Key, GAMEPAD1_BUTTON_A, GAMEPAD1_BUTTON_B, GAMEPAD1_BUTTON_DPAD_DOWN,
+    GAMEPAD1_BUTTON_DPAD_LEFT, GAMEPAD1_BUTTON_DPAD_RIGHT, GAMEPAD1_BUTTON_DPAD_UP,
+    MOUSE_BUTTON_LEFT,
+};
+use crate::pyxel::Pyxel;
+use crate::settings::{COLOR_BLACK, COLOR_WHITE, FONT_HEIGHT, FONT_WIDTH};
+
+const BUTTON_KEYS: [Key; 6] = [
+    GAMEPAD1_BUTTON_DPAD_UP,
+    GAMEPAD1_BUTTON_DPAD_DOWN,
+    GAMEPAD1_BUTTON_DPAD_LEFT,
+    GAMEPAD1_BUTTON_DPAD_RIGHT,
+    GAMEPAD1_BUTTON_A,
+    GAMEPAD1_BUTTON_B,
+];
+const BUTTON_LABELS: [&str; 6] = ["", "", "", "", "A", "B"];
+const MIN_BUTTON_SIZE: i32 = 8;
+
+pub struct VirtualGamepad {
+    enabled: bool,
+    pressed: [bool; BUTTON_KEYS.len()],
+    is_mouse_captured: bool,
+}
+
+impl VirtualGamepad {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            pressed: [false; BUTTON_KEYS.len()],
+            is_mouse_captured: false,
+        }
+    }
+}
+
+impl Pyxel {
+    pub fn virtual_gamepad(&mut self, enabled: bool) {
+        self.system.virtual_gamepad.enabled = enabled;
+    }
+
+    pub(crate) fn update_virtual_gamepad(&mut self) {
+        let touches = if self.is_virtual_gamepad_visible() {
+            pyxel_platform::touch_positions()
+        } else {
+            Vec::new()
+        };
+        self.update_virtual_gamepad_with_touches(&touches);
+    }
+
+    // Touches are given in window coordinates
+    fn update_virtual_gamepad_with_touches(&mut self, touches: &[(i32, i32)]) {
+        let rects = self.virtual_gamepad_rects();
+        let mut pressed = [false; BUTTON_KEYS.len()];
+        if self.is_virtual_gamepad_visible() {
+            let mut positions: Vec<(i32, i32)> = touches
+                .iter()
+                .filter_map(|(x, y)| self.window_to_screen(*x, *y))
+                .collect();
+            if self.capture_virtual_gamepad_mouse(&rects) {
+                positions.push((self.mouse_x, self.mouse_y));
+            }
+            for (i, rect) in rects.iter().enumerate() {
+                pressed[i] = positions.iter().any(|(x, y)| hits_button(*rect, *x, *y));
+            }
+        } else {
+            self.system.virtual_gamepad.is_mouse_captured = false;
+        }
+        for (i, key) in BUTTON_KEYS.iter().enumerate() {
+            if pressed[i] != self.system.virtual_gamepad.pressed[i] {
+                if pressed[i] {
+                    self.press_key(*key, pyxel_platform::elapsed_time());
+                } else {
+                    self.release_key(*key);
+                }
+            }
+        }
+        self.system.virtual_gamepad.pressed = pressed;
+    }
+
+    // Touches are also reported as the left mouse button on most platforms, so a press that
+    // starts on a button is hidden from the game until it is released and doesn't also click.
+    // Returns whether the captured mouse button is down in this frame.
+    fn capture_virtual_gamepad_mouse(&mut self, rects: &[(i32, i32, i32)]) -> bool {
+        if !self.system.virtual_gamepad.is_mouse_captured {
+            let (mouse_x, mouse_y) = (self.mouse_x, self.mouse_y);
+            if !self.btnp(MOUSE_BUTTON_LEFT, None, None)
+                || !rects
+                    .iter()
+                    .any(|rect| hits_button(*rect, mouse_x, mouse_y))
+            {
+                return false;
+            }
+        }
+        // Without a new event the captured button is still held
+        let (is_down, is_held) = self
+            .take_key_state(MOUSE_BUTTON_LEFT)
+            .unwrap_or((true, true));
+        self.system.virtual_gamepad.is_mouse_captured = is_held;
+        is_down
+    }
+
+    fn is_virtual_gamepad_visible(&self) -> bool {
+        if !self.system.virtual_gamepad.enabled {
+            return false;
+        }
+        cfg_if! {
+            if #[cfg(target_os = "emscripten")] {
+                // The web launcher's HTML gamepad already feeds the same keys when it's shown
+                !pyxel_platform::emscripten::has_virtual_gamepad()
+            } else {
+                true
+            }
+        }
+    }
+
+    fn window_to_screen(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        if self.system.screen_width == 0 || self.system.screen_height == 0 {
+            return None;
+        }
+        Some((
+            (x - self.system.screen_x) * self.width as i32 / self.system.screen_width as i32,
+            (y - self.system.screen_y) * self.height as i32 / self.system.screen_height as i32,
+        ))
+    }
+
+    pub(crate) fn draw_virtual_gamepad(&self) {
+        if !self.is_virtual_gamepad_visible() {
+            return;
+        }
+        let rects = self.virtual_gamepad_rects();
+        let pressed = self.system.virtual_gamepad.pressed;
+        self.draw_over_screen(|screen| {
+            for (i, (x, y, size)) in rects.iter().enumerate() {
+                let (x, y, size) = (*x as f64, *y as f64, *size as f64);
+                screen.dither(if pressed[i] { 0.75 } else { 0.5 });
+                screen.rect(x, y, size, size, COLOR_BLACK);
+                screen.dither(1.0);
+                screen.rectb(x, y, size, size, COLOR_WHITE);
+                if pressed[i] {
+                    screen.rect(x + 2.0, y + 2.0, size - 4.0, size - 4.0, COLOR_WHITE);
+                }
+                let label = BUTTON_LABELS[i];
+                if !label.is_empty() {
+                    screen.text(
+                        x + (size - FONT_WIDTH as f64) / 2.0,
+                        y + (size - FONT_HEIGHT as f64) / 2.0,
+                        label,
+                        if pressed[i] { COLOR_BLACK } else { COLOR_WHITE },
+                    );
+                }
+            }
+        });
+    }
+
+    // D-pad on the bottom left and A/B buttons on the bottom right, as (x, y, size)
+    fn virtual_gamepad_rects(&self) -> [(i32, i32, i32); BUTTON_KEYS.len()] {
+        let width = self.width as i32;
+        let height = self.height as i32;
+        let size = max(width.min(height) / 10, MIN_BUTTON_SIZE);
+        let center_x = size * 2;
+        let center_y = height - size * 2;
+        let half = size / 2;
+        [
+            (center_x - half, center_y - half - size, size),
+            (center_x - half, center_y + half, size),
+            (center_x - half - size, center_y - half, size),
+            (center_x + half, center_y - half, size),
+            (width - size * 2, center_y - size, size),
+            (width - size * 3 - half, center_y - half, size),
+        ]
+    }
+}
+
+// Hit areas are slightly larger than the drawn buttons for fingertips
+fn hits_button((x, y, size): (i32, i32, i32), px: i32, py: i32) -> bool {
+    let margin = size / 4;
+    px >= x - margin && px < x + size + margin && py >= y - margin && py < y + size + margin
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pyxel::headless_pyxel;
+
+    #[test]
+    fn test_virtual_gamepad_touches() {
+        let mut pyxel = headless_pyxel(100, 100);
+        pyxel.virtual_gamepad(true);
+        let (x, y, size) = pyxel.virtual_gamepad_rects()[4];
+
+        // A screen that hasn't been laid out yet ignores touches instead of dividing by zero
+        pyxel.system.screen_width = 0;
+        pyxel.system.screen_height = 0;
+        pyxel.update_virtual_gamepad_with_touches(&[(x, y)]);
+        assert!(!pyxel.btn(GAMEPAD1_BUTTON_A));
+
+        pyxel.system.screen_width = 200;
+        pyxel.system.screen_height = 200;
+        pyxel.update_virtual_gamepad_with_touches(&[(x * 2 + size, y * 2 + size)]);
+        assert!(pyxel.btn(GAMEPAD1_BUTTON_A));
+        pyxel.update_virtual_gamepad_with_touches(&[]);
+        assert!(!pyxel.btn(GAMEPAD1_BUTTON_A));
+    }
+
+    #[test]
+    fn test_virtual_gamepad_mouse_capture() {
+        let mut pyxel = headless_pyxel(100, 100);
+        pyxel.virtual_gamepad(true);
+        let (x, y, _) = pyxel.virtual_gamepad_rects()[0];
+        pyxel.mouse_x = x;
+        pyxel.mouse_y = y;
+        pyxel.press_key(MOUSE_BUTTON_LEFT, 0);
+        pyxel.update_virtual_gamepad_with_touches(&[]);
+        assert!(pyxel.btn(GAMEPAD1_BUTTON_DPAD_UP));
+        assert!(!pyxel.btn(MOUSE_BUTTON_LEFT));
+
+        // The press stays on the gamepad while held, and its release is hidden too
+        pyxel.frame_count += 1;
+        pyxel.update_virtual_gamepad_with_touches(&[]);
+        assert!(pyxel.btn(GAMEPAD1_BUTTON_DPAD_UP));
+        pyxel.frame_count += 1;
+        pyxel.release_key(MOUSE_BUTTON_LEFT);
+        pyxel.update_virtual_gamepad_with_touches(&[]);
+        assert!(!pyxel.btn(GAMEPAD1_BUTTON_DPAD_UP));
+        assert!(!pyxel.btnr(MOUSE_BUTTON_LEFT));
+
+        // Presses elsewhere belong to the game
+        pyxel.frame_count += 1;
+        pyxel.mouse_x = 50;
+        pyxel.mouse_y = 0;
+        pyxel.press_key(MOUSE_BUTTON_LEFT, 0);
+        pyxel.update_virtual_gamepad_with_touches(&[]);
+        assert!(pyxel.btn(MOUSE_BUTTON_LEFT));
+    }
+}
@@ -16,18 +16,22 @@ impl App {
             None,
             None,
             None,
-        );
+        )
+        .unwrap();
         pyxel.mouse(true);
         pyxel.warp_mouse(10.0, 10.0);
 
-        pyxel.images.lock()[0].lock().set(
-            0,
-            0,
-            &[
-                "00011000", "00010100", "00010010", "00010010", "00010100", "00010000", "01110000",
-                "01100000",
-            ],
-        );
+        pyxel.images.lock()[0]
+            .lock()
+            .set(
+                0,
+                0,
+                &[
+                    "00011000", "00010100", "00010010", "00010010", "00010100", "00010000",
+                    "01110000", "01100000",
+                ],
+            )
+            .unwrap();
 
         pyxel.sounds.lock()[0].lock().set(
             "e2e2c2g1 g1g1c2e2 d2d2d2g2 g2g2rr c2c2a1e1 e1e1a1c2 b1b1b1e2 e2e2rr",
@@ -6,7 +6,7 @@ use std::sync::Arc;
 
 use parking_lot::Mutex;
 
-use crate::platform::platform;
+use crate::platform::{platform, sdl_error};
 use crate::sdl2_sys::*;
 
 pub trait AudioCallback {
@@ -42,7 +42,14 @@ pub fn start_audio(
     platform().audio_device_id =
         unsafe { SDL_OpenAudioDevice(null_mut(), 0, &desired, obtained.as_mut_ptr(), 0) };
     if platform().audio_device_id == 0 {
-        println!("PyxelWarning: Failed to initialize audio device");
+        log::warn!("Failed to initialize audio device: {}", sdl_error());
+    } else {
+        let obtained = unsafe { obtained.assume_init() };
+        log::debug!(
+            "Audio device opened at {} Hz with {} samples",
+            obtained.freq,
+            obtained.samples
+        );
     }
     set_audio_enabled(true);
 }
@@ -60,10 +60,57 @@ pub fn run_script_string(script: &str) -> String {
     }
 }
 
+pub fn has_virtual_gamepad() -> bool {
+    run_script_int("document.querySelector('img#pyxel-gamepad-cross') !== null") != 0
+}
+
 pub fn save_file(filename: &str) {
     run_script(&format!("_savePyxelFile('{filename}');"));
 }
 
+pub fn set_local_storage(key: &str, value: &str) {
+    run_script(&format!("localStorage.setItem('{key}', '{value}');"));
+}
+
+pub fn local_storage(key: &str) -> Option<String> {
+    if run_script_int(&format!("localStorage.getItem('{key}') !== null")) == 0 {
+        return None;
+    }
+    Some(run_script_string(&format!("localStorage.getItem('{key}')")))
+}
+
+fn escape_js_string(string: &str) -> String {
+    string
+        .replace('\\', "\\\\")
+        .replace('\'', "\\'")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\0', "")
+}
+
+// Screen readers read changes to an aria-live region, which is cleared first so that
+// repeating the same text is announced again
+pub fn announce(text: &str) {
+    let text = escape_js_string(text);
+    let script = format!(
+        "
+        let region = document.getElementById('pyxel-announcer');
+        if (!region) {{
+            region = document.createElement('div');
+            region.id = 'pyxel-announcer';
+            region.setAttribute('role', 'status');
+            region.setAttribute('aria-live', 'polite');
+            region.style.cssText =
+                'position:absolute;width:1px;height:1px;overflow:hidden;clip:rect(0 0 0 0);';
+            document.body.appendChild(region);
+        }}
+        region.textContent = '';
+        setTimeout(() => {{ region.textContent = '{text}'; }}, 50);
+        "
+    );
+    run_script(&script);
+}
+
 pub fn datetime_string() -> String {
     let script = "
         let now = new Date();
@@ -4,26 +4,57 @@ use std::ptr::addr_of_mut;
 use crate::gamepad::{
     handle_controller_axis_motion, handle_controller_button_down, handle_controller_button_up,
     handle_controller_device_added, handle_controller_device_removed,
+    handle_controller_sensor_update,
 };
 #[cfg(target_os = "emscripten")]
-use crate::gamepad::{handle_joy_button_down, handle_joy_button_up, handle_virtual_gamepad_inputs};
-use crate::keyboard::{handle_key_down, handle_key_up, handle_text_input};
+use crate::gamepad::{
+    handle_device_motion, handle_joy_button_down, handle_joy_button_up,
+    handle_virtual_gamepad_inputs,
+};
+use crate::keyboard::{handle_key_down, handle_key_up, handle_text_editing, handle_text_input};
 use crate::keys::{Key, KeyValue};
+#[cfg(feature = "midi")]
+use crate::midi::handle_midi_events;
 use crate::mouse::{
     handle_mouse_button_down, handle_mouse_button_up, handle_mouse_motion, handle_mouse_wheel,
 };
 use crate::sdl2_sys::*;
-use crate::window::{handle_drop_file, handle_quit, handle_window_event};
+use crate::window::{handle_drop_file, handle_quit, handle_window_event, is_sub_window_id};
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum Event {
     WindowShown,
     WindowHidden,
-    KeyPressed { key: Key },
-    KeyReleased { key: Key },
-    KeyValueChanged { key: Key, value: KeyValue },
-    TextInput { text: String },
-    FileDropped { filename: String },
+    WindowFocusGained,
+    WindowFocusLost,
+    WindowResized {
+        width: u32,
+        height: u32,
+    },
+    SubWindowClosed,
+    KeyPressed {
+        key: Key,
+        timestamp: u32,
+    },
+    KeyReleased {
+        key: Key,
+        timestamp: u32,
+    },
+    KeyValueChanged {
+        key: Key,
+        value: KeyValue,
+    },
+    TextInput {
+        text: String,
+    },
+    TextEditing {
+        text: String,
+        cursor: u32,
+    },
+    FileDropped {
+        filename: String,
+        data: Option<Vec<u8>>,
+    },
     Quit,
 }
 
@@ -31,6 +62,10 @@ pub fn poll_events() -> Vec<Event> {
     let mut pyxel_events = Vec::new();
     let mut sdl_event: SDL_Event = unsafe { zeroed() };
     while unsafe { SDL_PollEvent(addr_of_mut!(sdl_event)) } != 0 {
+        log::trace!("SDL event {:#x}", unsafe { sdl_event.type_ });
+        if is_sub_window_input(&sdl_event) {
+            continue;
+        }
         match unsafe { sdl_event.type_ as SDL_EventType } {
             // Window
             SDL_WINDOWEVENT => {
@@ -53,6 +88,9 @@ pub fn poll_events() -> Vec<Event> {
             SDL_TEXTINPUT => {
                 pyxel_events.extend(handle_text_input(sdl_event));
             }
+            SDL_TEXTEDITING => {
+                pyxel_events.extend(handle_text_editing(sdl_event));
+            }
 
             // Mouse
             SDL_MOUSEBUTTONDOWN => {
@@ -75,6 +113,9 @@ pub fn poll_events() -> Vec<Event> {
             SDL_CONTROLLERAXISMOTION => {
                 pyxel_events.extend(handle_controller_axis_motion(sdl_event));
             }
+            SDL_CONTROLLERSENSORUPDATE => {
+                pyxel_events.extend(handle_controller_sensor_update(sdl_event));
+            }
             SDL_CONTROLLERBUTTONDOWN => {
                 pyxel_events.extend(handle_controller_button_down(sdl_event));
             }
@@ -95,6 +136,27 @@ pub fn poll_events() -> Vec<Event> {
     pyxel_events.extend(handle_mouse_motion());
     #[cfg(target_os = "emscripten")]
     pyxel_events.extend(handle_virtual_gamepad_inputs());
+    #[cfg(target_os = "emscripten")]
+    pyxel_events.extend(handle_device_motion());
+    #[cfg(feature = "midi")]
+    pyxel_events.extend(handle_midi_events());
 
     pyxel_events
 }
+
+// Presses, text and drops aimed at the debug window are dropped, but releases still pass so
+// keys held when the focus moved there don't get stuck
+fn is_sub_window_input(sdl_event: &SDL_Event) -> bool {
+    let window_id = unsafe {
+        match sdl_event.type_ as SDL_EventType {
+            SDL_KEYDOWN => sdl_event.key.windowID,
+            SDL_TEXTINPUT => sdl_event.text.windowID,
+            SDL_TEXTEDITING => sdl_event.edit.windowID,
+            SDL_MOUSEBUTTONDOWN => sdl_event.button.windowID,
+            SDL_MOUSEWHEEL => sdl_event.wheel.windowID,
+            SDL_DROPFILE => sdl_event.drop.windowID,
+            _ => return false,
+        }
+    };
+    is_sub_window_id(window_id)
+}
@@ -2,9 +2,13 @@
 use crate::emscripten::run_script_int;
 use crate::event::Event;
 use crate::keys::*;
+#[cfg(target_os = "emscripten")]
+use crate::platform::elapsed_time;
 use crate::platform::platform;
 use crate::sdl2_sys::*;
 
+const SENSOR_VALUE_SCALE: f32 = 1000.0;
+
 pub enum Gamepad {
     Unused,
     Controller(i32, *mut SDL_GameController),
@@ -19,18 +23,21 @@ pub fn init_gamepads() -> Vec<Gamepad> {
 
 pub fn handle_controller_device_added(sdl_event: SDL_Event) {
     let device_index = unsafe { sdl_event.cdevice.which };
-    if let Some(gamepad) = open_gamepad(device_index) {
-        let unused_gamepad = platform()
-            .gamepads
-            .iter_mut()
-            .find(|gamepad| matches!(gamepad, Gamepad::Unused));
-        match unused_gamepad {
-            Some(unused_gamepad) => {
-                *unused_gamepad = gamepad;
-            }
-            None => {
-                platform().gamepads.push(gamepad);
-            }
+    let Some(gamepad) = open_gamepad(device_index) else {
+        log::debug!("Ignored device {device_index}, which isn't a game controller");
+        return;
+    };
+    log::debug!("Gamepad device {device_index} added");
+    let unused_gamepad = platform()
+        .gamepads
+        .iter_mut()
+        .find(|gamepad| matches!(gamepad, Gamepad::Unused));
+    match unused_gamepad {
+        Some(unused_gamepad) => {
+            *unused_gamepad = gamepad;
+        }
+        None => {
+            platform().gamepads.push(gamepad);
         }
     }
 }
@@ -43,6 +50,7 @@ pub fn handle_controller_device_removed(sdl_event: SDL_Event) {
         .find(|g| matches!(g, Gamepad::Controller(id, _) if *id == instance_id))
     {
         if let Gamepad::Controller(_, controller) = gamepad {
+            log::debug!("Gamepad instance {instance_id} removed");
             unsafe {
                 SDL_GameControllerClose(*controller);
             }
@@ -66,6 +74,27 @@ pub fn handle_controller_axis_motion(sdl_event: SDL_Event) -> Vec<Event> {
     events
 }
 
+pub fn handle_controller_sensor_update(sdl_event: SDL_Event) -> Vec<Event> {
+    let mut events = Vec::new();
+    if let Some(key_offset) = gamepad_key_offset(unsafe { sdl_event.csensor.which }) {
+        let key = match unsafe { sdl_event.csensor.sensor } as SDL_SensorType {
+            SDL_SENSOR_GYRO => GAMEPAD1_GYRO_X,
+            SDL_SENSOR_ACCEL => GAMEPAD1_ACCEL_X,
+            _ => KEY_UNKNOWN,
+        };
+        if key != KEY_UNKNOWN {
+            let data = unsafe { sdl_event.csensor.data };
+            for (i, value) in data.iter().enumerate() {
+                events.push(Event::KeyValueChanged {
+                    key: key + key_offset + i as Key,
+                    value: (value * SENSOR_VALUE_SCALE) as KeyValue,
+                });
+            }
+        }
+    }
+    events
+}
+
 pub fn handle_controller_button_down(sdl_event: SDL_Event) -> Vec<Event> {
     let mut events = Vec::new();
     if let Some(key_offset) = gamepad_key_offset(unsafe { sdl_event.cbutton.which }) {
@@ -74,6 +103,7 @@ pub fn handle_controller_button_down(sdl_event: SDL_Event) -> Vec<Event> {
         if key != KEY_UNKNOWN {
             events.push(Event::KeyPressed {
                 key: key + key_offset,
+                timestamp: unsafe { sdl_event.common.timestamp },
             });
         }
     }
@@ -88,6 +118,7 @@ pub fn handle_controller_button_up(sdl_event: SDL_Event) -> Vec<Event> {
         if key != KEY_UNKNOWN {
             events.push(Event::KeyReleased {
                 key: key + key_offset,
+                timestamp: unsafe { sdl_event.common.timestamp },
             });
         }
     }
@@ -103,6 +134,7 @@ pub fn handle_joy_button_down(sdl_event: SDL_Event) -> Vec<Event> {
         if key != KEY_UNKNOWN {
             events.push(Event::KeyPressed {
                 key: key + key_offset,
+                timestamp: unsafe { sdl_event.common.timestamp },
             });
         }
     }
@@ -118,6 +150,7 @@ pub fn handle_joy_button_up(sdl_event: SDL_Event) -> Vec<Event> {
         if key != KEY_UNKNOWN {
             events.push(Event::KeyReleased {
                 key: key + key_offset,
+                timestamp: unsafe { sdl_event.common.timestamp },
             });
         }
     }
@@ -137,37 +170,86 @@ pub fn handle_virtual_gamepad_inputs() -> Vec<Event> {
         GAMEPAD1_BUTTON_Y,
     ];
     let mut events = Vec::new();
+    let timestamp = elapsed_time();
     for (i, button) in INDEX_TO_BUTTON.iter().enumerate() {
         let button_state = run_script_int(&format!("_virtualGamepadStates[{i}];")) != 0;
         if button_state != platform().virtual_gamepad_states[i] {
             platform().virtual_gamepad_states[i] = button_state;
+            let key = *button;
             if button_state {
-                events.push(Event::KeyPressed { key: *button });
+                events.push(Event::KeyPressed { key, timestamp });
             } else {
-                events.push(Event::KeyReleased { key: *button });
+                events.push(Event::KeyReleased { key, timestamp });
             };
         }
     }
     events
 }
 
+#[cfg(target_os = "emscripten")]
+pub fn handle_device_motion() -> Vec<Event> {
+    const INDEX_TO_AXIS: [Key; 6] = [
+        GAMEPAD1_GYRO_X,
+        GAMEPAD1_GYRO_Y,
+        GAMEPAD1_GYRO_Z,
+        GAMEPAD1_ACCEL_X,
+        GAMEPAD1_ACCEL_Y,
+        GAMEPAD1_ACCEL_Z,
+    ];
+    let mut events = Vec::new();
+    for (i, axis) in INDEX_TO_AXIS.iter().enumerate() {
+        let value = run_script_int(&format!(
+            "Math.round(_deviceMotionValues[{i}] * {SENSOR_VALUE_SCALE});"
+        ));
+        events.push(Event::KeyValueChanged { key: *axis, value });
+    }
+    events
+}
+
+// Returns false if the gamepad isn't connected or doesn't support rumble
+pub fn rumble_gamepad(index: u32, low_freq: u16, high_freq: u16, duration_ms: u32) -> bool {
+    match platform().gamepads.get(index as usize) {
+        Some(Gamepad::Controller(_, controller)) => unsafe {
+            SDL_GameControllerRumble(*controller, low_freq, high_freq, duration_ms) == 0
+        },
+        _ => false,
+    }
+}
+
 fn open_gamepad(device_index: i32) -> Option<Gamepad> {
     let controller = unsafe { SDL_GameControllerOpen(device_index) };
     if controller.is_null() {
         None
     } else {
+        unsafe {
+            SDL_GameControllerSetSensorEnabled(controller, SDL_SENSOR_GYRO, SDL_TRUE);
+            SDL_GameControllerSetSensorEnabled(controller, SDL_SENSOR_ACCEL, SDL_TRUE);
+        }
         let instance_id = unsafe { SDL_JoystickGetDeviceInstanceID(device_index) };
         Some(Gamepad::Controller(instance_id, controller))
     }
 }
 
+pub fn gamepad_count() -> u32 {
+    platform()
+        .gamepads
+        .iter()
+        .take(NUM_GAMEPADS as usize)
+        .filter(|gamepad| matches!(gamepad, Gamepad::Controller(_, _)))
+        .count() as u32
+}
+
+// Each gamepad slot has its own key range, and gamepads beyond the last range are ignored
 fn gamepad_key_offset(instance_id: i32) -> Option<Key> {
     platform()
         .gamepads
         .iter()
+        .take(NUM_GAMEPADS as usize)
         .enumerate()
         .find_map(|(index, slot)| match slot {
-            Gamepad::Controller(id, _) if *id == instance_id => Some(index as Key),
+            Gamepad::Controller(id, _) if *id == instance_id => {
+                Some(index as Key * GAMEPAD_KEY_INDEX_INTERVAL)
+            }
             _ => None,
         })
 }
@@ -1,3 +1,4 @@
+use std::ffi::CStr;
 use std::str::from_utf8 as str_from_utf8;
 
 use crate::event::Event;
@@ -11,9 +12,13 @@ pub fn handle_key_down(sdl_event: SDL_Event) -> Vec<Event> {
     let mut events = Vec::new();
     if unsafe { sdl_event.key.repeat } == 0 {
         let key = unsafe { sdl_event.key.keysym.sym } as Key;
-        events.push(Event::KeyPressed { key });
+        let timestamp = unsafe { sdl_event.key.timestamp };
+        events.push(Event::KeyPressed { key, timestamp });
         if let Some(unified_key) = to_unified_key(key) {
-            events.push(Event::KeyPressed { key: unified_key });
+            events.push(Event::KeyPressed {
+                key: unified_key,
+                timestamp,
+            });
         }
     }
     events
@@ -23,9 +28,13 @@ pub fn handle_key_up(sdl_event: SDL_Event) -> Vec<Event> {
     let mut events = Vec::new();
     if unsafe { sdl_event.key.repeat } == 0 {
         let key = unsafe { sdl_event.key.keysym.sym } as Key;
-        events.push(Event::KeyReleased { key });
+        let timestamp = unsafe { sdl_event.key.timestamp };
+        events.push(Event::KeyReleased { key, timestamp });
         if let Some(unified_key) = to_unified_key(key) {
-            events.push(Event::KeyReleased { key: unified_key });
+            events.push(Event::KeyReleased {
+                key: unified_key,
+                timestamp,
+            });
         }
     }
     events
@@ -45,6 +54,21 @@ pub fn handle_text_input(sdl_event: SDL_Event) -> Vec<Event> {
     events
 }
 
+// The composition text is null-terminated and the cursor is counted in characters
+pub fn handle_text_editing(sdl_event: SDL_Event) -> Vec<Event> {
+    let mut events = Vec::new();
+    let (text, cursor) = unsafe {
+        let text = CStr::from_ptr(sdl_event.edit.text.as_ptr());
+        (text.to_str(), sdl_event.edit.start)
+    };
+    if let Ok(text) = text {
+        let text = text.to_string();
+        let cursor = cursor.max(0) as u32;
+        events.push(Event::TextEditing { text, cursor });
+    }
+    events
+}
+
 fn to_unified_key(key: Key) -> Option<Key> {
     match key {
         KEY_LSHIFT | KEY_RSHIFT => Some(KEY_SHIFT),
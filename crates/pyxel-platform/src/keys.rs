@@ -1,3 +1,4 @@
+use once_cell::sync::Lazy;
 use paste::paste;
 
 use crate::sdl2_sys::*;
@@ -5,295 +6,330 @@ use crate::sdl2_sys::*;
 pub type Key = u32;
 pub type KeyValue = i32;
 
+// Each key table defines the key constants along with the names that key_name and
+// key_from_name use, so a key can't be added without a name
+macro_rules! define_keys {
+    ($names:ident; $($key:ident = $value:expr),* $(,)?) => {
+        $(pub const $key: Key = $value;)*
+        const $names: &[(Key, &str)] = &[$(($key, stringify!($key))),*];
+    };
+}
+
 // Keyboard (based on SDL_keycode.h in SDL 2.0.10)
-pub const KEY_UNKNOWN: Key = SDLK_UNKNOWN as Key;
-pub const KEY_RETURN: Key = SDLK_RETURN as Key;
-pub const KEY_ESCAPE: Key = SDLK_ESCAPE as Key;
-pub const KEY_BACKSPACE: Key = SDLK_BACKSPACE as Key;
-pub const KEY_TAB: Key = SDLK_TAB as Key;
-pub const KEY_SPACE: Key = SDLK_SPACE as Key;
-pub const KEY_EXCLAIM: Key = SDLK_EXCLAIM as Key;
-pub const KEY_QUOTEDBL: Key = SDLK_QUOTEDBL as Key;
-pub const KEY_HASH: Key = SDLK_HASH as Key;
-pub const KEY_PERCENT: Key = SDLK_PERCENT as Key;
-pub const KEY_DOLLAR: Key = SDLK_DOLLAR as Key;
-pub const KEY_AMPERSAND: Key = SDLK_AMPERSAND as Key;
-pub const KEY_QUOTE: Key = SDLK_QUOTE as Key;
-pub const KEY_LEFTPAREN: Key = SDLK_LEFTPAREN as Key;
-pub const KEY_RIGHTPAREN: Key = SDLK_RIGHTPAREN as Key;
-pub const KEY_ASTERISK: Key = SDLK_ASTERISK as Key;
-pub const KEY_PLUS: Key = SDLK_PLUS as Key;
-pub const KEY_COMMA: Key = SDLK_COMMA as Key;
-pub const KEY_MINUS: Key = SDLK_MINUS as Key;
-pub const KEY_PERIOD: Key = SDLK_PERIOD as Key;
-pub const KEY_SLASH: Key = SDLK_SLASH as Key;
-pub const KEY_0: Key = SDLK_0 as Key;
-pub const KEY_1: Key = SDLK_1 as Key;
-pub const KEY_2: Key = SDLK_2 as Key;
-pub const KEY_3: Key = SDLK_3 as Key;
-pub const KEY_4: Key = SDLK_4 as Key;
-pub const KEY_5: Key = SDLK_5 as Key;
-pub const KEY_6: Key = SDLK_6 as Key;
-pub const KEY_7: Key = SDLK_7 as Key;
-pub const KEY_8: Key = SDLK_8 as Key;
-pub const KEY_9: Key = SDLK_9 as Key;
-pub const KEY_COLON: Key = SDLK_COLON as Key;
-pub const KEY_SEMICOLON: Key = SDLK_SEMICOLON as Key;
-pub const KEY_LESS: Key = SDLK_LESS as Key;
-pub const KEY_EQUALS: Key = SDLK_EQUALS as Key;
-pub const KEY_GREATER: Key = SDLK_GREATER as Key;
-pub const KEY_QUESTION: Key = SDLK_QUESTION as Key;
-pub const KEY_AT: Key = SDLK_AT as Key;
-pub const KEY_LEFTBRACKET: Key = SDLK_LEFTBRACKET as Key;
-pub const KEY_BACKSLASH: Key = SDLK_BACKSLASH as Key;
-pub const KEY_RIGHTBRACKET: Key = SDLK_RIGHTBRACKET as Key;
-pub const KEY_CARET: Key = SDLK_CARET as Key;
-pub const KEY_UNDERSCORE: Key = SDLK_UNDERSCORE as Key;
-pub const KEY_BACKQUOTE: Key = SDLK_BACKQUOTE as Key;
-pub const KEY_A: Key = SDLK_a as Key;
-pub const KEY_B: Key = SDLK_b as Key;
-pub const KEY_C: Key = SDLK_c as Key;
-pub const KEY_D: Key = SDLK_d as Key;
-pub const KEY_E: Key = SDLK_e as Key;
-pub const KEY_F: Key = SDLK_f as Key;
-pub const KEY_G: Key = SDLK_g as Key;
-pub const KEY_H: Key = SDLK_h as Key;
-pub const KEY_I: Key = SDLK_i as Key;
-pub const KEY_J: Key = SDLK_j as Key;
-pub const KEY_K: Key = SDLK_k as Key;
-pub const KEY_L: Key = SDLK_l as Key;
-pub const KEY_M: Key = SDLK_m as Key;
-pub const KEY_N: Key = SDLK_n as Key;
-pub const KEY_O: Key = SDLK_o as Key;
-pub const KEY_P: Key = SDLK_p as Key;
-pub const KEY_Q: Key = SDLK_q as Key;
-pub const KEY_R: Key = SDLK_r as Key;
-pub const KEY_S: Key = SDLK_s as Key;
-pub const KEY_T: Key = SDLK_t as Key;
-pub const KEY_U: Key = SDLK_u as Key;
-pub const KEY_V: Key = SDLK_v as Key;
-pub const KEY_W: Key = SDLK_w as Key;
-pub const KEY_X: Key = SDLK_x as Key;
-pub const KEY_Y: Key = SDLK_y as Key;
-pub const KEY_Z: Key = SDLK_z as Key;
-pub const KEY_CAPSLOCK: Key = SDLK_CAPSLOCK as Key;
-pub const KEY_F1: Key = SDLK_F1 as Key;
-pub const KEY_F2: Key = SDLK_F2 as Key;
-pub const KEY_F3: Key = SDLK_F3 as Key;
-pub const KEY_F4: Key = SDLK_F4 as Key;
-pub const KEY_F5: Key = SDLK_F5 as Key;
-pub const KEY_F6: Key = SDLK_F6 as Key;
-pub const KEY_F7: Key = SDLK_F7 as Key;
-pub const KEY_F8: Key = SDLK_F8 as Key;
-pub const KEY_F9: Key = SDLK_F9 as Key;
-pub const KEY_F10: Key = SDLK_F10 as Key;
-pub const KEY_F11: Key = SDLK_F11 as Key;
-pub const KEY_F12: Key = SDLK_F12 as Key;
-pub const KEY_PRINTSCREEN: Key = SDLK_PRINTSCREEN as Key;
-pub const KEY_SCROLLLOCK: Key = SDLK_SCROLLLOCK as Key;
-pub const KEY_PAUSE: Key = SDLK_PAUSE as Key;
-pub const KEY_INSERT: Key = SDLK_INSERT as Key;
-pub const KEY_HOME: Key = SDLK_HOME as Key;
-pub const KEY_PAGEUP: Key = SDLK_PAGEUP as Key;
-pub const KEY_DELETE: Key = SDLK_DELETE as Key;
-pub const KEY_END: Key = SDLK_END as Key;
-pub const KEY_PAGEDOWN: Key = SDLK_PAGEDOWN as Key;
-pub const KEY_RIGHT: Key = SDLK_RIGHT as Key;
-pub const KEY_LEFT: Key = SDLK_LEFT as Key;
-pub const KEY_DOWN: Key = SDLK_DOWN as Key;
-pub const KEY_UP: Key = SDLK_UP as Key;
-pub const KEY_NUMLOCKCLEAR: Key = SDLK_NUMLOCKCLEAR as Key;
-pub const KEY_KP_DIVIDE: Key = SDLK_KP_DIVIDE as Key;
-pub const KEY_KP_MULTIPLY: Key = SDLK_KP_MULTIPLY as Key;
-pub const KEY_KP_MINUS: Key = SDLK_KP_MINUS as Key;
-pub const KEY_KP_PLUS: Key = SDLK_KP_PLUS as Key;
-pub const KEY_KP_ENTER: Key = SDLK_KP_ENTER as Key;
-pub const KEY_KP_1: Key = SDLK_KP_1 as Key;
-pub const KEY_KP_2: Key = SDLK_KP_2 as Key;
-pub const KEY_KP_3: Key = SDLK_KP_3 as Key;
-pub const KEY_KP_4: Key = SDLK_KP_4 as Key;
-pub const KEY_KP_5: Key = SDLK_KP_5 as Key;
-pub const KEY_KP_6: Key = SDLK_KP_6 as Key;
-pub const KEY_KP_7: Key = SDLK_KP_7 as Key;
-pub const KEY_KP_8: Key = SDLK_KP_8 as Key;
-pub const KEY_KP_9: Key = SDLK_KP_9 as Key;
-pub const KEY_KP_0: Key = SDLK_KP_0 as Key;
-pub const KEY_KP_PERIOD: Key = SDLK_KP_PERIOD as Key;
-pub const KEY_APPLICATION: Key = SDLK_APPLICATION as Key;
-pub const KEY_POWER: Key = SDLK_POWER as Key;
-pub const KEY_KP_EQUALS: Key = SDLK_KP_EQUALS as Key;
-pub const KEY_F13: Key = SDLK_F13 as Key;
-pub const KEY_F14: Key = SDLK_F14 as Key;
-pub const KEY_F15: Key = SDLK_F15 as Key;
-pub const KEY_F16: Key = SDLK_F16 as Key;
-pub const KEY_F17: Key = SDLK_F17 as Key;
-pub const KEY_F18: Key = SDLK_F18 as Key;
-pub const KEY_F19: Key = SDLK_F19 as Key;
-pub const KEY_F20: Key = SDLK_F20 as Key;
-pub const KEY_F21: Key = SDLK_F21 as Key;
-pub const KEY_F22: Key = SDLK_F22 as Key;
-pub const KEY_F23: Key = SDLK_F23 as Key;
-pub const KEY_F24: Key = SDLK_F24 as Key;
-pub const KEY_EXECUTE: Key = SDLK_EXECUTE as Key;
-pub const KEY_HELP: Key = SDLK_HELP as Key;
-pub const KEY_MENU: Key = SDLK_MENU as Key;
-pub const KEY_SELECT: Key = SDLK_SELECT as Key;
-pub const KEY_STOP: Key = SDLK_STOP as Key;
-pub const KEY_AGAIN: Key = SDLK_AGAIN as Key;
-pub const KEY_UNDO: Key = SDLK_UNDO as Key;
-pub const KEY_CUT: Key = SDLK_CUT as Key;
-pub const KEY_COPY: Key = SDLK_COPY as Key;
-pub const KEY_PASTE: Key = SDLK_PASTE as Key;
-pub const KEY_FIND: Key = SDLK_FIND as Key;
-pub const KEY_MUTE: Key = SDLK_MUTE as Key;
-pub const KEY_VOLUMEUP: Key = SDLK_VOLUMEUP as Key;
-pub const KEY_VOLUMEDOWN: Key = SDLK_VOLUMEDOWN as Key;
-pub const KEY_KP_COMMA: Key = SDLK_KP_COMMA as Key;
-pub const KEY_KP_EQUALSAS400: Key = SDLK_KP_EQUALSAS400 as Key;
-pub const KEY_ALTERASE: Key = SDLK_ALTERASE as Key;
-pub const KEY_SYSREQ: Key = SDLK_SYSREQ as Key;
-pub const KEY_CANCEL: Key = SDLK_CANCEL as Key;
-pub const KEY_CLEAR: Key = SDLK_CLEAR as Key;
-pub const KEY_PRIOR: Key = SDLK_PRIOR as Key;
-pub const KEY_RETURN2: Key = SDLK_RETURN2 as Key;
-pub const KEY_SEPARATOR: Key = SDLK_SEPARATOR as Key;
-pub const KEY_OUT: Key = SDLK_OUT as Key;
-pub const KEY_OPER: Key = SDLK_OPER as Key;
-pub const KEY_CLEARAGAIN: Key = SDLK_CLEARAGAIN as Key;
-pub const KEY_CRSEL: Key = SDLK_CRSEL as Key;
-pub const KEY_EXSEL: Key = SDLK_EXSEL as Key;
-pub const KEY_KP_00: Key = SDLK_KP_00 as Key;
-pub const KEY_KP_000: Key = SDLK_KP_000 as Key;
-pub const KEY_THOUSANDSSEPARATOR: Key = SDLK_THOUSANDSSEPARATOR as Key;
-pub const KEY_DECIMALSEPARATOR: Key = SDLK_DECIMALSEPARATOR as Key;
-pub const KEY_CURRENCYUNIT: Key = SDLK_CURRENCYUNIT as Key;
-pub const KEY_CURRENCYSUBUNIT: Key = SDLK_CURRENCYSUBUNIT as Key;
-pub const KEY_KP_LEFTPAREN: Key = SDLK_KP_LEFTPAREN as Key;
-pub const KEY_KP_RIGHTPAREN: Key = SDLK_KP_RIGHTPAREN as Key;
-pub const KEY_KP_LEFTBRACE: Key = SDLK_KP_LEFTBRACE as Key;
-pub const KEY_KP_RIGHTBRACE: Key = SDLK_KP_RIGHTBRACE as Key;
-pub const KEY_KP_TAB: Key = SDLK_KP_TAB as Key;
-pub const KEY_KP_BACKSPACE: Key = SDLK_KP_BACKSPACE as Key;
-pub const KEY_KP_A: Key = SDLK_KP_A as Key;
-pub const KEY_KP_B: Key = SDLK_KP_B as Key;
-pub const KEY_KP_C: Key = SDLK_KP_C as Key;
-pub const KEY_KP_D: Key = SDLK_KP_D as Key;
-pub const KEY_KP_E: Key = SDLK_KP_E as Key;
-pub const KEY_KP_F: Key = SDLK_KP_F as Key;
-pub const KEY_KP_XOR: Key = SDLK_KP_XOR as Key;
-pub const KEY_KP_POWER: Key = SDLK_KP_POWER as Key;
-pub const KEY_KP_PERCENT: Key = SDLK_KP_PERCENT as Key;
-pub const KEY_KP_LESS: Key = SDLK_KP_LESS as Key;
-pub const KEY_KP_GREATER: Key = SDLK_KP_GREATER as Key;
-pub const KEY_KP_AMPERSAND: Key = SDLK_KP_AMPERSAND as Key;
-pub const KEY_KP_DBLAMPERSAND: Key = SDLK_KP_DBLAMPERSAND as Key;
-pub const KEY_KP_VERTICALBAR: Key = SDLK_KP_VERTICALBAR as Key;
-pub const KEY_KP_DBLVERTICALBAR: Key = SDLK_KP_DBLVERTICALBAR as Key;
-pub const KEY_KP_COLON: Key = SDLK_KP_COLON as Key;
-pub const KEY_KP_HASH: Key = SDLK_KP_HASH as Key;
-pub const KEY_KP_SPACE: Key = SDLK_KP_SPACE as Key;
-pub const KEY_KP_AT: Key = SDLK_KP_AT as Key;
-pub const KEY_KP_EXCLAM: Key = SDLK_KP_EXCLAM as Key;
-pub const KEY_KP_MEMSTORE: Key = SDLK_KP_MEMSTORE as Key;
-pub const KEY_KP_MEMRECALL: Key = SDLK_KP_MEMRECALL as Key;
-pub const KEY_KP_MEMCLEAR: Key = SDLK_KP_MEMCLEAR as Key;
-pub const KEY_KP_MEMADD: Key = SDLK_KP_MEMADD as Key;
-pub const KEY_KP_MEMSUBTRACT: Key = SDLK_KP_MEMSUBTRACT as Key;
-pub const KEY_KP_MEMMULTIPLY: Key = SDLK_KP_MEMMULTIPLY as Key;
-pub const KEY_KP_MEMDIVIDE: Key = SDLK_KP_MEMDIVIDE as Key;
-pub const KEY_KP_PLUSMINUS: Key = SDLK_KP_PLUSMINUS as Key;
-pub const KEY_KP_CLEAR: Key = SDLK_KP_CLEAR as Key;
-pub const KEY_KP_CLEARENTRY: Key = SDLK_KP_CLEARENTRY as Key;
-pub const KEY_KP_BINARY: Key = SDLK_KP_BINARY as Key;
-pub const KEY_KP_OCTAL: Key = SDLK_KP_OCTAL as Key;
-pub const KEY_KP_DECIMAL: Key = SDLK_KP_DECIMAL as Key;
-pub const KEY_KP_HEXADECIMAL: Key = SDLK_KP_HEXADECIMAL as Key;
-pub const KEY_LCTRL: Key = SDLK_LCTRL as Key;
-pub const KEY_LSHIFT: Key = SDLK_LSHIFT as Key;
-pub const KEY_LALT: Key = SDLK_LALT as Key;
-pub const KEY_LGUI: Key = SDLK_LGUI as Key;
-pub const KEY_RCTRL: Key = SDLK_RCTRL as Key;
-pub const KEY_RSHIFT: Key = SDLK_RSHIFT as Key;
-pub const KEY_RALT: Key = SDLK_RALT as Key;
-pub const KEY_RGUI: Key = SDLK_RGUI as Key;
-pub const KEY_MODE: Key = SDLK_MODE as Key;
-pub const KEY_AUDIONEXT: Key = SDLK_AUDIONEXT as Key;
-pub const KEY_AUDIOPREV: Key = SDLK_AUDIOPREV as Key;
-pub const KEY_AUDIOSTOP: Key = SDLK_AUDIOSTOP as Key;
-pub const KEY_AUDIOPLAY: Key = SDLK_AUDIOPLAY as Key;
-pub const KEY_AUDIOMUTE: Key = SDLK_AUDIOMUTE as Key;
-pub const KEY_MEDIASELECT: Key = SDLK_MEDIASELECT as Key;
-pub const KEY_WWW: Key = SDLK_WWW as Key;
-pub const KEY_MAIL: Key = SDLK_MAIL as Key;
-pub const KEY_CALCULATOR: Key = SDLK_CALCULATOR as Key;
-pub const KEY_COMPUTER: Key = SDLK_COMPUTER as Key;
-pub const KEY_AC_SEARCH: Key = SDLK_AC_SEARCH as Key;
-pub const KEY_AC_HOME: Key = SDLK_AC_HOME as Key;
-pub const KEY_AC_BACK: Key = SDLK_AC_BACK as Key;
-pub const KEY_AC_FORWARD: Key = SDLK_AC_FORWARD as Key;
-pub const KEY_AC_STOP: Key = SDLK_AC_STOP as Key;
-pub const KEY_AC_REFRESH: Key = SDLK_AC_REFRESH as Key;
-pub const KEY_AC_BOOKMARKS: Key = SDLK_AC_BOOKMARKS as Key;
-pub const KEY_BRIGHTNESSDOWN: Key = SDLK_BRIGHTNESSDOWN as Key;
-pub const KEY_BRIGHTNESSUP: Key = SDLK_BRIGHTNESSUP as Key;
-pub const KEY_DISPLAYSWITCH: Key = SDLK_DISPLAYSWITCH as Key;
-pub const KEY_KBDILLUMTOGGLE: Key = SDLK_KBDILLUMTOGGLE as Key;
-pub const KEY_KBDILLUMDOWN: Key = SDLK_KBDILLUMDOWN as Key;
-pub const KEY_KBDILLUMUP: Key = SDLK_KBDILLUMUP as Key;
-pub const KEY_EJECT: Key = SDLK_EJECT as Key;
-pub const KEY_SLEEP: Key = SDLK_SLEEP as Key;
-pub const KEY_APP1: Key = SDLK_APP1 as Key;
-pub const KEY_APP2: Key = SDLK_APP2 as Key;
-pub const KEY_AUDIOREWIND: Key = SDLK_AUDIOREWIND as Key;
-pub const KEY_AUDIOFASTFORWARD: Key = SDLK_AUDIOFASTFORWARD as Key;
+define_keys!(
+    KEYBOARD_KEY_NAMES;
+    KEY_UNKNOWN = SDLK_UNKNOWN as Key,
+    KEY_RETURN = SDLK_RETURN as Key,
+    KEY_ESCAPE = SDLK_ESCAPE as Key,
+    KEY_BACKSPACE = SDLK_BACKSPACE as Key,
+    KEY_TAB = SDLK_TAB as Key,
+    KEY_SPACE = SDLK_SPACE as Key,
+    KEY_EXCLAIM = SDLK_EXCLAIM as Key,
+    KEY_QUOTEDBL = SDLK_QUOTEDBL as Key,
+    KEY_HASH = SDLK_HASH as Key,
+    KEY_PERCENT = SDLK_PERCENT as Key,
+    KEY_DOLLAR = SDLK_DOLLAR as Key,
+    KEY_AMPERSAND = SDLK_AMPERSAND as Key,
+    KEY_QUOTE = SDLK_QUOTE as Key,
+    KEY_LEFTPAREN = SDLK_LEFTPAREN as Key,
+    KEY_RIGHTPAREN = SDLK_RIGHTPAREN as Key,
+    KEY_ASTERISK = SDLK_ASTERISK as Key,
+    KEY_PLUS = SDLK_PLUS as Key,
+    KEY_COMMA = SDLK_COMMA as Key,
+    KEY_MINUS = SDLK_MINUS as Key,
+    KEY_PERIOD = SDLK_PERIOD as Key,
+    KEY_SLASH = SDLK_SLASH as Key,
+    KEY_0 = SDLK_0 as Key,
+    KEY_1 = SDLK_1 as Key,
+    KEY_2 = SDLK_2 as Key,
+    KEY_3 = SDLK_3 as Key,
+    KEY_4 = SDLK_4 as Key,
+    KEY_5 = SDLK_5 as Key,
+    KEY_6 = SDLK_6 as Key,
+    KEY_7 = SDLK_7 as Key,
+    KEY_8 = SDLK_8 as Key,
+    KEY_9 = SDLK_9 as Key,
+    KEY_COLON = SDLK_COLON as Key,
+    KEY_SEMICOLON = SDLK_SEMICOLON as Key,
+    KEY_LESS = SDLK_LESS as Key,
+    KEY_EQUALS = SDLK_EQUALS as Key,
+    KEY_GREATER = SDLK_GREATER as Key,
+    KEY_QUESTION = SDLK_QUESTION as Key,
+    KEY_AT = SDLK_AT as Key,
+    KEY_LEFTBRACKET = SDLK_LEFTBRACKET as Key,
+    KEY_BACKSLASH = SDLK_BACKSLASH as Key,
+    KEY_RIGHTBRACKET = SDLK_RIGHTBRACKET as Key,
+    KEY_CARET = SDLK_CARET as Key,
+    KEY_UNDERSCORE = SDLK_UNDERSCORE as Key,
+    KEY_BACKQUOTE = SDLK_BACKQUOTE as Key,
+    KEY_A = SDLK_a as Key,
+    KEY_B = SDLK_b as Key,
+    KEY_C = SDLK_c as Key,
+    KEY_D = SDLK_d as Key,
+    KEY_E = SDLK_e as Key,
+    KEY_F = SDLK_f as Key,
+    KEY_G = SDLK_g as Key,
+    KEY_H = SDLK_h as Key,
+    KEY_I = SDLK_i as Key,
+    KEY_J = SDLK_j as Key,
+    KEY_K = SDLK_k as Key,
+    KEY_L = SDLK_l as Key,
+    KEY_M = SDLK_m as Key,
+    KEY_N = SDLK_n as Key,
+    KEY_O = SDLK_o as Key,
+    KEY_P = SDLK_p as Key,
+    KEY_Q = SDLK_q as Key,
+    KEY_R = SDLK_r as Key,
+    KEY_S = SDLK_s as Key,
+    KEY_T = SDLK_t as Key,
+    KEY_U = SDLK_u as Key,
+    KEY_V = SDLK_v as Key,
+    KEY_W = SDLK_w as Key,
+    KEY_X = SDLK_x as Key,
+    KEY_Y = SDLK_y as Key,
+    KEY_Z = SDLK_z as Key,
+    KEY_CAPSLOCK = SDLK_CAPSLOCK as Key,
+    KEY_F1 = SDLK_F1 as Key,
+    KEY_F2 = SDLK_F2 as Key,
+    KEY_F3 = SDLK_F3 as Key,
+    KEY_F4 = SDLK_F4 as Key,
+    KEY_F5 = SDLK_F5 as Key,
+    KEY_F6 = SDLK_F6 as Key,
+    KEY_F7 = SDLK_F7 as Key,
+    KEY_F8 = SDLK_F8 as Key,
+    KEY_F9 = SDLK_F9 as Key,
+    KEY_F10 = SDLK_F10 as Key,
+    KEY_F11 = SDLK_F11 as Key,
+    KEY_F12 = SDLK_F12 as Key,
+    KEY_PRINTSCREEN = SDLK_PRINTSCREEN as Key,
+    KEY_SCROLLLOCK = SDLK_SCROLLLOCK as Key,
+    KEY_PAUSE = SDLK_PAUSE as Key,
+    KEY_INSERT = SDLK_INSERT as Key,
+    KEY_HOME = SDLK_HOME as Key,
+    KEY_PAGEUP = SDLK_PAGEUP as Key,
+    KEY_DELETE = SDLK_DELETE as Key,
+    KEY_END = SDLK_END as Key,
+    KEY_PAGEDOWN = SDLK_PAGEDOWN as Key,
+    KEY_RIGHT = SDLK_RIGHT as Key,
+    KEY_LEFT = SDLK_LEFT as Key,
+    KEY_DOWN = SDLK_DOWN as Key,
+    KEY_UP = SDLK_UP as Key,
+    KEY_NUMLOCKCLEAR = SDLK_NUMLOCKCLEAR as Key,
+    KEY_KP_DIVIDE = SDLK_KP_DIVIDE as Key,
+    KEY_KP_MULTIPLY = SDLK_KP_MULTIPLY as Key,
+    KEY_KP_MINUS = SDLK_KP_MINUS as Key,
+    KEY_KP_PLUS = SDLK_KP_PLUS as Key,
+    KEY_KP_ENTER = SDLK_KP_ENTER as Key,
+    KEY_KP_1 = SDLK_KP_1 as Key,
+    KEY_KP_2 = SDLK_KP_2 as Key,
+    KEY_KP_3 = SDLK_KP_3 as Key,
+    KEY_KP_4 = SDLK_KP_4 as Key,
+    KEY_KP_5 = SDLK_KP_5 as Key,
+    KEY_KP_6 = SDLK_KP_6 as Key,
+    KEY_KP_7 = SDLK_KP_7 as Key,
+    KEY_KP_8 = SDLK_KP_8 as Key,
+    KEY_KP_9 = SDLK_KP_9 as Key,
+    KEY_KP_0 = SDLK_KP_0 as Key,
+    KEY_KP_PERIOD = SDLK_KP_PERIOD as Key,
+    KEY_APPLICATION = SDLK_APPLICATION as Key,
+    KEY_POWER = SDLK_POWER as Key,
+    KEY_KP_EQUALS = SDLK_KP_EQUALS as Key,
+    KEY_F13 = SDLK_F13 as Key,
+    KEY_F14 = SDLK_F14 as Key,
+    KEY_F15 = SDLK_F15 as Key,
+    KEY_F16 = SDLK_F16 as Key,
+    KEY_F17 = SDLK_F17 as Key,
+    KEY_F18 = SDLK_F18 as Key,
+    KEY_F19 = SDLK_F19 as Key,
+    KEY_F20 = SDLK_F20 as Key,
+    KEY_F21 = SDLK_F21 as Key,
+    KEY_F22 = SDLK_F22 as Key,
+    KEY_F23 = SDLK_F23 as Key,
+    KEY_F24 = SDLK_F24 as Key,
+    KEY_EXECUTE = SDLK_EXECUTE as Key,
+    KEY_HELP = SDLK_HELP as Key,
+    KEY_MENU = SDLK_MENU as Key,
+    KEY_SELECT = SDLK_SELECT as Key,
+    KEY_STOP = SDLK_STOP as Key,
+    KEY_AGAIN = SDLK_AGAIN as Key,
+    KEY_UNDO = SDLK_UNDO as Key,
+    KEY_CUT = SDLK_CUT as Key,
+    KEY_COPY = SDLK_COPY as Key,
+    KEY_PASTE = SDLK_PASTE as Key,
+    KEY_FIND = SDLK_FIND as Key,
+    KEY_MUTE = SDLK_MUTE as Key,
+    KEY_VOLUMEUP = SDLK_VOLUMEUP as Key,
+    KEY_VOLUMEDOWN = SDLK_VOLUMEDOWN as Key,
+    KEY_KP_COMMA = SDLK_KP_COMMA as Key,
+    KEY_KP_EQUALSAS400 = SDLK_KP_EQUALSAS400 as Key,
+    KEY_ALTERASE = SDLK_ALTERASE as Key,
+    KEY_SYSREQ = SDLK_SYSREQ as Key,
+    KEY_CANCEL = SDLK_CANCEL as Key,
+    KEY_CLEAR = SDLK_CLEAR as Key,
+    KEY_PRIOR = SDLK_PRIOR as Key,
+    KEY_RETURN2 = SDLK_RETURN2 as Key,
+    KEY_SEPARATOR = SDLK_SEPARATOR as Key,
+    KEY_OUT = SDLK_OUT as Key,
+    KEY_OPER = SDLK_OPER as Key,
+    KEY_CLEARAGAIN = SDLK_CLEARAGAIN as Key,
+    KEY_CRSEL = SDLK_CRSEL as Key,
+    KEY_EXSEL = SDLK_EXSEL as Key,
+    KEY_KP_00 = SDLK_KP_00 as Key,
+    KEY_KP_000 = SDLK_KP_000 as Key,
+    KEY_THOUSANDSSEPARATOR = SDLK_THOUSANDSSEPARATOR as Key,
+    KEY_DECIMALSEPARATOR = SDLK_DECIMALSEPARATOR as Key,
+    KEY_CURRENCYUNIT = SDLK_CURRENCYUNIT as Key,
+    KEY_CURRENCYSUBUNIT = SDLK_CURRENCYSUBUNIT as Key,
+    KEY_KP_LEFTPAREN = SDLK_KP_LEFTPAREN as Key,
+    KEY_KP_RIGHTPAREN = SDLK_KP_RIGHTPAREN as Key,
+    KEY_KP_LEFTBRACE = SDLK_KP_LEFTBRACE as Key,
+    KEY_KP_RIGHTBRACE = SDLK_KP_RIGHTBRACE as Key,
+    KEY_KP_TAB = SDLK_KP_TAB as Key,
+    KEY_KP_BACKSPACE = SDLK_KP_BACKSPACE as Key,
+    KEY_KP_A = SDLK_KP_A as Key,
+    KEY_KP_B = SDLK_KP_B as Key,
+    KEY_KP_C = SDLK_KP_C as Key,
+    KEY_KP_D = SDLK_KP_D as Key,
+    KEY_KP_E = SDLK_KP_E as Key,
+    KEY_KP_F = SDLK_KP_F as Key,
+    KEY_KP_XOR = SDLK_KP_XOR as Key,
+    KEY_KP_POWER = SDLK_KP_POWER as Key,
+    KEY_KP_PERCENT = SDLK_KP_PERCENT as Key,
+    KEY_KP_LESS = SDLK_KP_LESS as Key,
+    KEY_KP_GREATER = SDLK_KP_GREATER as Key,
+    KEY_KP_AMPERSAND = SDLK_KP_AMPERSAND as Key,
+    KEY_KP_DBLAMPERSAND = SDLK_KP_DBLAMPERSAND as Key,
+    KEY_KP_VERTICALBAR = SDLK_KP_VERTICALBAR as Key,
+    KEY_KP_DBLVERTICALBAR = SDLK_KP_DBLVERTICALBAR as Key,
+    KEY_KP_COLON = SDLK_KP_COLON as Key,
+    KEY_KP_HASH = SDLK_KP_HASH as Key,
+    KEY_KP_SPACE = SDLK_KP_SPACE as Key,
+    KEY_KP_AT = SDLK_KP_AT as Key,
+    KEY_KP_EXCLAM = SDLK_KP_EXCLAM as Key,
+    KEY_KP_MEMSTORE = SDLK_KP_MEMSTORE as Key,
+    KEY_KP_MEMRECALL = SDLK_KP_MEMRECALL as Key,
+    KEY_KP_MEMCLEAR = SDLK_KP_MEMCLEAR as Key,
+    KEY_KP_MEMADD = SDLK_KP_MEMADD as Key,
+    KEY_KP_MEMSUBTRACT = SDLK_KP_MEMSUBTRACT as Key,
+    KEY_KP_MEMMULTIPLY = SDLK_KP_MEMMULTIPLY as Key,
+    KEY_KP_MEMDIVIDE = SDLK_KP_MEMDIVIDE as Key,
+    KEY_KP_PLUSMINUS = SDLK_KP_PLUSMINUS as Key,
+    KEY_KP_CLEAR = SDLK_KP_CLEAR as Key,
+    KEY_KP_CLEARENTRY = SDLK_KP_CLEARENTRY as Key,
+    KEY_KP_BINARY = SDLK_KP_BINARY as Key,
+    KEY_KP_OCTAL = SDLK_KP_OCTAL as Key,
+    KEY_KP_DECIMAL = SDLK_KP_DECIMAL as Key,
+    KEY_KP_HEXADECIMAL = SDLK_KP_HEXADECIMAL as Key,
+    KEY_LCTRL = SDLK_LCTRL as Key,
+    KEY_LSHIFT = SDLK_LSHIFT as Key,
+    KEY_LALT = SDLK_LALT as Key,
+    KEY_LGUI = SDLK_LGUI as Key,
+    KEY_RCTRL = SDLK_RCTRL as Key,
+    KEY_RSHIFT = SDLK_RSHIFT as Key,
+    KEY_RALT = SDLK_RALT as Key,
+    KEY_RGUI = SDLK_RGUI as Key,
+    KEY_MODE = SDLK_MODE as Key,
+    KEY_AUDIONEXT = SDLK_AUDIONEXT as Key,
+    KEY_AUDIOPREV = SDLK_AUDIOPREV as Key,
+    KEY_AUDIOSTOP = SDLK_AUDIOSTOP as Key,
+    KEY_AUDIOPLAY = SDLK_AUDIOPLAY as Key,
+    KEY_AUDIOMUTE = SDLK_AUDIOMUTE as Key,
+    KEY_MEDIASELECT = SDLK_MEDIASELECT as Key,
+    KEY_WWW = SDLK_WWW as Key,
+    KEY_MAIL = SDLK_MAIL as Key,
+    KEY_CALCULATOR = SDLK_CALCULATOR as Key,
+    KEY_COMPUTER = SDLK_COMPUTER as Key,
+    KEY_AC_SEARCH = SDLK_AC_SEARCH as Key,
+    KEY_AC_HOME = SDLK_AC_HOME as Key,
+    KEY_AC_BACK = SDLK_AC_BACK as Key,
+    KEY_AC_FORWARD = SDLK_AC_FORWARD as Key,
+    KEY_AC_STOP = SDLK_AC_STOP as Key,
+    KEY_AC_REFRESH = SDLK_AC_REFRESH as Key,
+    KEY_AC_BOOKMARKS = SDLK_AC_BOOKMARKS as Key,
+    KEY_BRIGHTNESSDOWN = SDLK_BRIGHTNESSDOWN as Key,
+    KEY_BRIGHTNESSUP = SDLK_BRIGHTNESSUP as Key,
+    KEY_DISPLAYSWITCH = SDLK_DISPLAYSWITCH as Key,
+    KEY_KBDILLUMTOGGLE = SDLK_KBDILLUMTOGGLE as Key,
+    KEY_KBDILLUMDOWN = SDLK_KBDILLUMDOWN as Key,
+    KEY_KBDILLUMUP = SDLK_KBDILLUMUP as Key,
+    KEY_EJECT = SDLK_EJECT as Key,
+    KEY_SLEEP = SDLK_SLEEP as Key,
+    KEY_APP1 = SDLK_APP1 as Key,
+    KEY_APP2 = SDLK_APP2 as Key,
+    KEY_AUDIOREWIND = SDLK_AUDIOREWIND as Key,
+    KEY_AUDIOFASTFORWARD = SDLK_AUDIOFASTFORWARD as Key,
+);
 
 pub const SPECIAL_KEY_START_INDEX: Key = 10000;
-pub const KEY_NONE: Key = SPECIAL_KEY_START_INDEX;
-pub const KEY_SHIFT: Key = SPECIAL_KEY_START_INDEX + 1;
-pub const KEY_CTRL: Key = SPECIAL_KEY_START_INDEX + 2;
-pub const KEY_ALT: Key = SPECIAL_KEY_START_INDEX + 3;
-pub const KEY_GUI: Key = SPECIAL_KEY_START_INDEX + 4;
+define_keys!(
+    SPECIAL_KEY_NAMES;
+    KEY_NONE = SPECIAL_KEY_START_INDEX,
+    KEY_SHIFT = SPECIAL_KEY_START_INDEX + 1,
+    KEY_CTRL = SPECIAL_KEY_START_INDEX + 2,
+    KEY_ALT = SPECIAL_KEY_START_INDEX + 3,
+    KEY_GUI = SPECIAL_KEY_START_INDEX + 4,
+);
 
 // Mouse
 pub const MOUSE_KEY_START_INDEX: Key = 11000;
-pub const MOUSE_POS_X: Key = MOUSE_KEY_START_INDEX;
-pub const MOUSE_POS_Y: Key = MOUSE_KEY_START_INDEX + 1;
-pub const MOUSE_WHEEL_X: Key = MOUSE_KEY_START_INDEX + 2;
-pub const MOUSE_WHEEL_Y: Key = MOUSE_KEY_START_INDEX + 3;
-pub const MOUSE_BUTTON_LEFT: Key = MOUSE_KEY_START_INDEX + 4;
-pub const MOUSE_BUTTON_MIDDLE: Key = MOUSE_KEY_START_INDEX + 5;
-pub const MOUSE_BUTTON_RIGHT: Key = MOUSE_KEY_START_INDEX + 6;
-pub const MOUSE_BUTTON_X1: Key = MOUSE_KEY_START_INDEX + 7;
-pub const MOUSE_BUTTON_X2: Key = MOUSE_KEY_START_INDEX + 8;
+define_keys!(
+    MOUSE_KEY_NAMES;
+    MOUSE_POS_X = MOUSE_KEY_START_INDEX,
+    MOUSE_POS_Y = MOUSE_KEY_START_INDEX + 1,
+    MOUSE_WHEEL_X = MOUSE_KEY_START_INDEX + 2,
+    MOUSE_WHEEL_Y = MOUSE_KEY_START_INDEX + 3,
+    MOUSE_BUTTON_LEFT = MOUSE_KEY_START_INDEX + 4,
+    MOUSE_BUTTON_MIDDLE = MOUSE_KEY_START_INDEX + 5,
+    MOUSE_BUTTON_RIGHT = MOUSE_KEY_START_INDEX + 6,
+    MOUSE_BUTTON_X1 = MOUSE_KEY_START_INDEX + 7,
+    MOUSE_BUTTON_X2 = MOUSE_KEY_START_INDEX + 8,
+);
 
 // Gamepad
 pub const GAMEPAD_KEY_START_INDEX: Key = 12000;
 pub const GAMEPAD_KEY_INDEX_INTERVAL: Key = 1000;
+pub const NUM_GAMEPADS: u32 = 4;
 
 macro_rules! define_gamepad_keys {
     ($gamepad_name:ident, $start_index:expr) => {
+        define_gamepad_keys!(
+            @keys $gamepad_name,
+            $start_index,
+            AXIS_LEFTX = 0,
+            AXIS_LEFTY = 1,
+            AXIS_RIGHTX = 2,
+            AXIS_RIGHTY = 3,
+            AXIS_TRIGGERLEFT = 4,
+            AXIS_TRIGGERRIGHT = 5,
+            BUTTON_A = 6,
+            BUTTON_B = 7,
+            BUTTON_X = 8,
+            BUTTON_Y = 9,
+            BUTTON_BACK = 10,
+            BUTTON_GUIDE = 11,
+            BUTTON_START = 12,
+            BUTTON_LEFTSTICK = 13,
+            BUTTON_RIGHTSTICK = 14,
+            BUTTON_LEFTSHOULDER = 15,
+            BUTTON_RIGHTSHOULDER = 16,
+            BUTTON_DPAD_UP = 17,
+            BUTTON_DPAD_DOWN = 18,
+            BUTTON_DPAD_LEFT = 19,
+            BUTTON_DPAD_RIGHT = 20,
+            GYRO_X = 21,
+            GYRO_Y = 22,
+            GYRO_Z = 23,
+            ACCEL_X = 24,
+            ACCEL_Y = 25,
+            ACCEL_Z = 26,
+        );
+    };
+    (@keys $gamepad_name:ident, $start_index:expr, $($suffix:ident = $offset:expr),* $(,)?) => {
         paste! {
-            pub const [<$gamepad_name _AXIS_LEFTX>]: Key  = $start_index + 0;
-            pub const [<$gamepad_name _AXIS_LEFTY>]: Key  = $start_index + 1;
-            pub const [<$gamepad_name _AXIS_RIGHTX>]: Key  = $start_index + 2;
-            pub const [<$gamepad_name _AXIS_RIGHTY>]: Key  = $start_index + 3;
-            pub const [<$gamepad_name _AXIS_TRIGGERLEFT>]: Key  = $start_index + 4;
-            pub const [<$gamepad_name _AXIS_TRIGGERRIGHT>]: Key  = $start_index + 5;
-            pub const [<$gamepad_name _BUTTON_A>]: Key  = $start_index + 6;
-            pub const [<$gamepad_name _BUTTON_B>]: Key  = $start_index + 7;
-            pub const [<$gamepad_name _BUTTON_X>]: Key  = $start_index + 8;
-            pub const [<$gamepad_name _BUTTON_Y>]: Key  = $start_index + 9;
-            pub const [<$gamepad_name _BUTTON_BACK>]: Key  = $start_index + 10;
-            pub const [<$gamepad_name _BUTTON_GUIDE>]: Key  = $start_index + 11;
-            pub const [<$gamepad_name _BUTTON_START>]: Key  = $start_index + 12;
-            pub const [<$gamepad_name _BUTTON_LEFTSTICK>]: Key  = $start_index + 13;
-            pub const [<$gamepad_name _BUTTON_RIGHTSTICK>]: Key  = $start_index + 14;
-            pub const [<$gamepad_name _BUTTON_LEFTSHOULDER>]: Key  = $start_index + 15;
-            pub const [<$gamepad_name _BUTTON_RIGHTSHOULDER>]: Key  = $start_index + 16;
-            pub const [<$gamepad_name _BUTTON_DPAD_UP>]: Key  = $start_index + 17;
-            pub const [<$gamepad_name _BUTTON_DPAD_DOWN>]: Key  = $start_index + 18;
-            pub const [<$gamepad_name _BUTTON_DPAD_LEFT>]: Key  = $start_index + 19;
-            pub const [<$gamepad_name _BUTTON_DPAD_RIGHT>]: Key  = $start_index + 20;
+            define_keys!(
+                [<$gamepad_name _KEY_NAMES>];
+                $([<$gamepad_name _ $suffix>] = $start_index + $offset),*
+            );
         }
     };
 }
@@ -311,3 +347,43 @@ define_gamepad_keys!(
     GAMEPAD4,
     GAMEPAD_KEY_START_INDEX + GAMEPAD_KEY_INDEX_INTERVAL * 3
 );
+
+// MIDI
+pub const MIDI_KEY_START_INDEX: Key = 16000;
+pub const MIDI_NOTE_0: Key = MIDI_KEY_START_INDEX;
+pub const NUM_MIDI_NOTES: u32 = 128;
+
+static KEY_NAMES: Lazy<Vec<(Key, String)>> = Lazy::new(|| {
+    let mut key_names: Vec<(Key, String)> = [
+        KEYBOARD_KEY_NAMES,
+        SPECIAL_KEY_NAMES,
+        MOUSE_KEY_NAMES,
+        GAMEPAD1_KEY_NAMES,
+        GAMEPAD2_KEY_NAMES,
+        GAMEPAD3_KEY_NAMES,
+        GAMEPAD4_KEY_NAMES,
+    ]
+    .iter()
+    .flat_map(|key_names| key_names.iter())
+    .map(|(key, name)| (*key, (*name).to_string()))
+    .collect();
+    key_names
+        .extend((0..NUM_MIDI_NOTES).map(|note| (MIDI_NOTE_0 + note, format!("MIDI_NOTE_{note}"))));
+    key_names
+});
+
+pub fn key_name(key: Key) -> &'static str {
+    KEY_NAMES
+        .iter()
+        .find(|(key_value, _)| *key_value == key)
+        .map_or("KEY_UNKNOWN", |(_, name)| name.as_str())
+}
+
+// Names are the constant names such as KEY_A, GAMEPAD1_BUTTON_A or MIDI_NOTE_60,
+// compared case-insensitively
+pub fn key_from_name(name: &str) -> Option<Key> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, key_name)| key_name.eq_ignore_ascii_case(name))
+        .map(|(key, _)| *key)
+}
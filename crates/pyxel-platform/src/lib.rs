@@ -20,6 +20,8 @@ mod event;
 mod gamepad;
 mod keyboard;
 pub mod keys;
+#[cfg(feature = "midi")]
+mod midi;
 mod mouse;
 mod platform;
 mod sdl2_sys;
@@ -27,9 +29,15 @@ mod window;
 
 pub use crate::audio::{set_audio_enabled, start_audio, AudioCallback};
 pub use crate::event::{poll_events, Event};
-pub use crate::platform::{elapsed_time, init, quit, run, sleep};
+pub use crate::gamepad::{gamepad_count, rumble_gamepad};
+#[cfg(feature = "midi")]
+pub use crate::midi::{close_midi_input, midi_input_ports, open_midi_input};
+pub use crate::mouse::touch_positions;
+pub use crate::platform::{elapsed_time, init, quit, run, set_loop_wait_enabled, shutdown, sleep};
 pub use crate::window::{
-    glow_context, is_fullscreen, is_gles_enabled, set_fullscreen, set_mouse_pos, set_mouse_visible,
-    set_window_icon, set_window_pos, set_window_size, set_window_title, swap_window, window_pos,
-    window_size,
+    close_sub_window, display_names, drawable_size, glow_context, is_fullscreen, is_gles_enabled,
+    open_sub_window, set_fullscreen, set_mouse_pos, set_mouse_visible, set_system_cursor,
+    set_vsync, set_window_always_on_top, set_window_bordered, set_window_display, set_window_icon,
+    set_window_pos, set_window_resizable, set_window_size, set_window_title, swap_window,
+    update_sub_window, window_pos, window_size, SystemCursor,
 };
@@ -0,0 +1,87 @@
+use std::mem::take;
+
+use midir::{Ignore, MidiInput, MidiInputConnection};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::event::Event;
+use crate::keys::{Key, KeyValue, MIDI_NOTE_0};
+use crate::platform::elapsed_time;
+
+const MIDI_CLIENT_NAME: &str = "pyxel";
+const MIDI_NOTE_OFF: u8 = 0x80;
+const MIDI_NOTE_ON: u8 = 0x90;
+
+static MIDI_CONNECTION: Lazy<Mutex<Option<MidiInputConnection<()>>>> =
+    Lazy::new(|| Mutex::new(None));
+static MIDI_EVENTS: Lazy<Mutex<Vec<Event>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+pub fn midi_input_ports() -> Vec<String> {
+    match MidiInput::new(MIDI_CLIENT_NAME) {
+        Ok(midi_input) => midi_input
+            .ports()
+            .iter()
+            .filter_map(|port| midi_input.port_name(port).ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn open_midi_input(port_index: u32) -> bool {
+    close_midi_input();
+    let Ok(mut midi_input) = MidiInput::new(MIDI_CLIENT_NAME) else {
+        return false;
+    };
+    midi_input.ignore(Ignore::All);
+    let Some(port) = midi_input.ports().get(port_index as usize).cloned() else {
+        return false;
+    };
+    let connection = midi_input.connect(
+        &port,
+        MIDI_CLIENT_NAME,
+        |_, message, ()| {
+            MIDI_EVENTS.lock().extend(handle_midi_message(message));
+        },
+        (),
+    );
+    if let Ok(connection) = connection {
+        *MIDI_CONNECTION.lock() = Some(connection);
+        true
+    } else {
+        false
+    }
+}
+
+pub fn close_midi_input() {
+    if let Some(connection) = MIDI_CONNECTION.lock().take() {
+        connection.close();
+    }
+    MIDI_EVENTS.lock().clear();
+}
+
+pub fn handle_midi_events() -> Vec<Event> {
+    take(&mut *MIDI_EVENTS.lock())
+}
+
+fn handle_midi_message(message: &[u8]) -> Vec<Event> {
+    let mut events = Vec::new();
+    if let [status, note, velocity] = *message {
+        let key = MIDI_NOTE_0 + note as Key;
+        let timestamp = elapsed_time();
+        match status & 0xf0 {
+            MIDI_NOTE_ON if velocity > 0 => {
+                events.push(Event::KeyValueChanged {
+                    key,
+                    value: velocity as KeyValue,
+                });
+                events.push(Event::KeyPressed { key, timestamp });
+            }
+            MIDI_NOTE_ON | MIDI_NOTE_OFF => {
+                events.push(Event::KeyValueChanged { key, value: 0 });
+                events.push(Event::KeyReleased { key, timestamp });
+            }
+            _ => {}
+        }
+    }
+    events
+}
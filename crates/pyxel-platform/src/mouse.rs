@@ -19,7 +19,8 @@ pub fn handle_mouse_button_down(sdl_event: SDL_Event) -> Vec<Event> {
         _ => KEY_UNKNOWN,
     };
     if key != KEY_UNKNOWN {
-        events.push(Event::KeyPressed { key });
+        let timestamp = unsafe { sdl_event.button.timestamp };
+        events.push(Event::KeyPressed { key, timestamp });
     }
     events
 }
@@ -35,7 +36,8 @@ pub fn handle_mouse_button_up(sdl_event: SDL_Event) -> Vec<Event> {
         _ => KEY_UNKNOWN,
     };
     if key != KEY_UNKNOWN {
-        events.push(Event::KeyReleased { key });
+        let timestamp = unsafe { sdl_event.button.timestamp };
+        events.push(Event::KeyReleased { key, timestamp });
     }
     events
 }
@@ -81,3 +83,21 @@ pub fn handle_mouse_motion() -> Vec<Event> {
     }
     events
 }
+
+// Returns the positions of active touches in window coordinates
+pub fn touch_positions() -> Vec<(i32, i32)> {
+    let (width, height) = crate::window_size();
+    let mut positions = Vec::new();
+    for i in 0..unsafe { SDL_GetNumTouchDevices() } {
+        let touch_id = unsafe { SDL_GetTouchDevice(i) };
+        for j in 0..unsafe { SDL_GetNumTouchFingers(touch_id) } {
+            let finger = unsafe { SDL_GetTouchFinger(touch_id, j) };
+            if finger.is_null() {
+                continue;
+            }
+            let (x, y) = unsafe { ((*finger).x, (*finger).y) };
+            positions.push(((x * width as f32) as i32, (y * height as f32) as i32));
+        }
+    }
+    positions
+}
@@ -1,3 +1,4 @@
+use std::ffi::{CStr, CString};
 use std::mem::transmute;
 use std::ptr::{addr_of_mut, null_mut};
 
@@ -10,11 +11,17 @@ use crate::window::{init_glow, init_window};
 
 pub struct Platform {
     pub window: *mut SDL_Window,
+    pub sub_window: *mut SDL_Window,
+    pub gl_context: SDL_GLContext,
     pub glow_context: *mut GlowContext,
     pub audio_device_id: SDL_AudioDeviceID,
+    pub cursor: *mut SDL_Cursor,
     pub mouse_x: i32,
     pub mouse_y: i32,
     pub gamepads: Vec<Gamepad>,
+    pub loop_wait_enabled: bool,
+    pub is_running: bool,
+    pub quit_requested: bool,
     #[cfg(target_os = "emscripten")]
     pub virtual_gamepad_states: [bool; 8],
 }
@@ -25,38 +32,79 @@ pub fn platform() -> &'static mut Platform {
     unsafe { &mut *PLATFORM }
 }
 
-pub fn init<'a, F: FnOnce(u32, u32) -> (&'a str, u32, u32)>(window_params: F) {
-    assert!(
-        unsafe { SDL_Init(SDL_INIT_VIDEO | SDL_INIT_AUDIO | SDL_INIT_GAMECONTROLLER,) } >= 0,
-        "Failed to initialize SDL2"
-    );
-    let mut display_mode = SDL_DisplayMode {
-        format: 0,
+pub fn sdl_error() -> String {
+    unsafe { CStr::from_ptr(SDL_GetError()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+pub fn init<'a, F: FnOnce(u32, u32) -> (&'a str, u32, u32)>(
+    window_params: F,
+) -> Result<(), String> {
+    // Makes Windows report window sizes in logical units like other platforms
+    for (name, value) in [
+        ("SDL_WINDOWS_DPI_AWARENESS", "permonitorv2"),
+        ("SDL_WINDOWS_DPI_SCALING", "1"),
+    ] {
+        let name = CString::new(name).unwrap();
+        let value = CString::new(value).unwrap();
+        unsafe {
+            SDL_SetHint(name.as_ptr(), value.as_ptr());
+        }
+    }
+    if unsafe { SDL_Init(SDL_INIT_VIDEO | SDL_INIT_AUDIO | SDL_INIT_GAMECONTROLLER) } < 0 {
+        log::error!("SDL_Init failed: {}", sdl_error());
+        return Err("Failed to initialize SDL2".to_string());
+    }
+
+    // Display bounds are in the same logical units as the window size, unlike display modes
+    let mut display_bounds = SDL_Rect {
+        x: 0,
+        y: 0,
         w: 0,
         h: 0,
-        refresh_rate: 0,
-        driverdata: null_mut(),
     };
-    assert!(
-        unsafe { SDL_GetCurrentDisplayMode(0, addr_of_mut!(display_mode)) } == 0,
-        "Failed to get display size"
-    );
-    let (title, width, height) = window_params(display_mode.w as u32, display_mode.h as u32);
-    let window = init_window(title, width, height);
-    let glow_context = init_glow(window);
+    if unsafe { SDL_GetDisplayBounds(0, addr_of_mut!(display_bounds)) } != 0 {
+        log::error!("SDL_GetDisplayBounds failed: {}", sdl_error());
+        unsafe {
+            SDL_Quit();
+        }
+        return Err("Failed to get display size".to_string());
+    }
+    let (title, width, height) = window_params(display_bounds.w as u32, display_bounds.h as u32);
+    let window = init_window(title, width, height).map_err(|message| {
+        unsafe {
+            SDL_Quit();
+        }
+        message
+    })?;
+    let (gl_context, glow_context) = init_glow(window).map_err(|message| {
+        unsafe {
+            SDL_DestroyWindow(window);
+            SDL_Quit();
+        }
+        message
+    })?;
     let gamepads = init_gamepads();
     unsafe {
         PLATFORM = transmute(Box::new(Platform {
             window,
+            sub_window: null_mut(),
+            gl_context,
             glow_context,
             audio_device_id: 0,
+            cursor: null_mut(),
             mouse_x: i32::MIN,
             mouse_y: i32::MIN,
             gamepads,
+            loop_wait_enabled: true,
+            is_running: false,
+            quit_requested: false,
             #[cfg(target_os = "emscripten")]
             virtual_gamepad_states: [false; 8],
         }));
     }
+    Ok(())
 }
 
 #[allow(unused_mut)]
@@ -65,9 +113,18 @@ pub fn run<F: FnMut()>(mut main_loop: F) {
         if #[cfg(target_os = "emscripten")] {
             crate::emscripten::run(main_loop);
         } else {
+            platform().is_running = true;
+            platform().quit_requested = false;
             loop {
                 let start_ms = elapsed_time() as f64;
                 main_loop();
+                if platform().quit_requested {
+                    platform().is_running = false;
+                    break;
+                }
+                if !platform().loop_wait_enabled {
+                    continue;
+                }
                 let elapsed_ms = elapsed_time() as f64 - start_ms;
                 let wait_ms = 1000.0 / 60.0 - elapsed_ms;
                 if wait_ms > 0.0 {
@@ -78,20 +135,56 @@ pub fn run<F: FnMut()>(mut main_loop: F) {
     }
 }
 
-pub fn quit() {
-    unsafe {
-        SDL_Quit();
-    }
+pub fn set_loop_wait_enabled(enabled: bool) {
+    platform().loop_wait_enabled = enabled;
+}
 
+// Inside the main loop on desktop, this only requests the loop to end after the current
+// frame, so the caller keeps running until it returns
+pub fn quit() {
     cfg_if! {
         if #[cfg(target_os = "emscripten")] {
+            unsafe {
+                SDL_Quit();
+            }
             crate::emscripten::exit(0);
         } else {
+            // Inside the main loop, only end the loop so that Pyxel can be initialized again
+            if platform().is_running {
+                platform().quit_requested = true;
+                return;
+            }
+            unsafe {
+                SDL_Quit();
+            }
             std::process::exit(0);
         }
     }
 }
 
+pub fn shutdown() {
+    unsafe {
+        if PLATFORM.is_null() {
+            return;
+        }
+        let platform = Box::from_raw(PLATFORM);
+        PLATFORM = null_mut();
+        if platform.audio_device_id != 0 {
+            SDL_CloseAudioDevice(platform.audio_device_id);
+        }
+        if !platform.sub_window.is_null() {
+            SDL_DestroyWindow(platform.sub_window);
+        }
+        if !platform.cursor.is_null() {
+            SDL_FreeCursor(platform.cursor);
+        }
+        drop(Box::from_raw(platform.glow_context));
+        SDL_GL_DeleteContext(platform.gl_context);
+        SDL_DestroyWindow(platform.window);
+        SDL_Quit();
+    }
+}
+
 pub fn elapsed_time() -> u32 {
     unsafe { SDL_GetTicks() }
 }
@@ -1,14 +1,14 @@
 use std::ffi::{CStr, CString};
 use std::mem::transmute;
-use std::ptr::addr_of_mut;
+use std::ptr::{addr_of_mut, null, null_mut};
 
 use glow::Context as GlowContext;
 
 use crate::event::Event;
-use crate::platform::platform;
+use crate::platform::{platform, sdl_error};
 use crate::sdl2_sys::*;
 
-pub fn init_window(title: &str, width: u32, height: u32) -> *mut SDL_Window {
+pub fn init_window(title: &str, width: u32, height: u32) -> Result<*mut SDL_Window, String> {
     unsafe {
         let title = CString::new(title).unwrap();
         let window = SDL_CreateWindow(
@@ -17,17 +17,22 @@ pub fn init_window(title: &str, width: u32, height: u32) -> *mut SDL_Window {
             SDL_WINDOWPOS_UNDEFINED_MASK as i32,
             width as i32,
             height as i32,
-            (SDL_WINDOW_OPENGL as Uint32) | (SDL_WINDOW_RESIZABLE as Uint32),
+            (SDL_WINDOW_OPENGL as Uint32)
+                | (SDL_WINDOW_RESIZABLE as Uint32)
+                | (SDL_WINDOW_ALLOW_HIGHDPI as Uint32),
         );
-        assert!(!window.is_null(), "Failed to create window");
+        if window.is_null() {
+            log::error!("SDL_CreateWindow failed: {}", sdl_error());
+            return Err("Failed to create window".to_string());
+        }
         let name = CString::new("SDL_HINT_MOUSE_FOCUS_CLICKTHROUGH").unwrap();
         let value = CString::new("1").unwrap();
         SDL_SetHint(name.as_ptr(), value.as_ptr());
-        window
+        Ok(window)
     }
 }
 
-pub fn init_glow(window: *mut SDL_Window) -> *mut GlowContext {
+pub fn init_glow(window: *mut SDL_Window) -> Result<(SDL_GLContext, *mut GlowContext), String> {
     unsafe {
         // Try to initialize OpenGL ES 2.0
         SDL_GL_SetAttribute(
@@ -36,7 +41,8 @@ pub fn init_glow(window: *mut SDL_Window) -> *mut GlowContext {
         );
         SDL_GL_SetAttribute(SDL_GL_CONTEXT_MAJOR_VERSION, 2);
         SDL_GL_SetAttribute(SDL_GL_CONTEXT_MINOR_VERSION, 0);
-        if SDL_GL_CreateContext(window).is_null() {
+        let mut gl_context = SDL_GL_CreateContext(window);
+        if gl_context.is_null() {
             // Try to initialize OpenGL 2.1
             SDL_GL_SetAttribute(
                 SDL_GL_CONTEXT_PROFILE_MASK,
@@ -44,14 +50,16 @@ pub fn init_glow(window: *mut SDL_Window) -> *mut GlowContext {
             );
             SDL_GL_SetAttribute(SDL_GL_CONTEXT_MAJOR_VERSION, 2);
             SDL_GL_SetAttribute(SDL_GL_CONTEXT_MINOR_VERSION, 1);
-            assert!(
-                !SDL_GL_CreateContext(window).is_null(),
-                "Failed to create OpenGL context"
-            );
+            gl_context = SDL_GL_CreateContext(window);
+            if gl_context.is_null() {
+                log::error!("SDL_GL_CreateContext failed: {}", sdl_error());
+                return Err("Failed to create OpenGL context".to_string());
+            }
         }
-        transmute(Box::new(GlowContext::from_loader_function(|s| {
+        let glow_context = transmute(Box::new(GlowContext::from_loader_function(|s| {
             SDL_GL_GetProcAddress(s.as_ptr().cast()).cast_const()
-        })))
+        })));
+        Ok((gl_context, glow_context))
     }
 }
 
@@ -74,6 +82,12 @@ pub fn swap_window() {
     }
 }
 
+pub fn set_vsync(enabled: bool) {
+    unsafe {
+        SDL_GL_SetSwapInterval(i32::from(enabled));
+    }
+}
+
 pub fn set_window_title(title: &str) {
     let title = CString::new(title).unwrap();
     unsafe {
@@ -134,21 +148,128 @@ pub fn set_window_size(width: u32, height: u32) {
     }
 }
 
+// Size of the OpenGL drawable in pixels, which is larger than the window size on high-DPI displays
+pub fn drawable_size() -> (u32, u32) {
+    let mut width: i32 = 0;
+    let mut height: i32 = 0;
+    unsafe {
+        SDL_GL_GetDrawableSize(platform().window, addr_of_mut!(width), addr_of_mut!(height));
+    }
+    (width as u32, height as u32)
+}
+
+pub fn set_window_resizable(resizable: bool) {
+    let resizable = if resizable { SDL_TRUE } else { SDL_FALSE };
+    unsafe {
+        SDL_SetWindowResizable(platform().window, resizable);
+    }
+}
+
+pub fn set_window_bordered(bordered: bool) {
+    let bordered = if bordered { SDL_TRUE } else { SDL_FALSE };
+    unsafe {
+        SDL_SetWindowBordered(platform().window, bordered);
+    }
+}
+
+pub fn set_window_always_on_top(enabled: bool) {
+    let enabled = if enabled { SDL_TRUE } else { SDL_FALSE };
+    unsafe {
+        SDL_SetWindowAlwaysOnTop(platform().window, enabled);
+    }
+}
+
 pub fn is_fullscreen() -> bool {
     (unsafe { SDL_GetWindowFlags(platform().window) }) & SDL_WINDOW_FULLSCREEN as Uint32 != 0
 }
 
-pub fn set_fullscreen(full: bool) {
-    let full = if full {
-        SDL_WINDOW_FULLSCREEN_DESKTOP as Uint32
-    } else {
+pub fn set_fullscreen(full: bool, exclusive: bool) {
+    let full = if !full {
         0
+    } else if exclusive {
+        SDL_WINDOW_FULLSCREEN as Uint32
+    } else {
+        SDL_WINDOW_FULLSCREEN_DESKTOP as Uint32
     };
     unsafe {
         SDL_SetWindowFullscreen(platform().window, full);
     }
 }
 
+pub fn display_names() -> Vec<String> {
+    let num_displays = unsafe { SDL_GetNumVideoDisplays() };
+    (0..num_displays)
+        .map(|display_index| {
+            let name = unsafe { SDL_GetDisplayName(display_index) };
+            if name.is_null() {
+                String::new()
+            } else {
+                unsafe { CStr::from_ptr(name) }
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        })
+        .collect()
+}
+
+pub fn set_window_display(display_index: u32) {
+    unsafe {
+        let full = SDL_GetWindowFlags(platform().window) & SDL_WINDOW_FULLSCREEN_DESKTOP as Uint32;
+        let pos = (SDL_WINDOWPOS_CENTERED_MASK | display_index) as i32;
+        SDL_SetWindowFullscreen(platform().window, 0);
+        SDL_SetWindowPosition(platform().window, pos, pos);
+        SDL_SetWindowFullscreen(platform().window, full);
+    }
+}
+
+pub fn open_sub_window(title: &str, width: u32, height: u32) {
+    close_sub_window();
+    unsafe {
+        let title = CString::new(title).unwrap();
+        platform().sub_window = SDL_CreateWindow(
+            title.as_ptr(),
+            SDL_WINDOWPOS_UNDEFINED_MASK as i32,
+            SDL_WINDOWPOS_UNDEFINED_MASK as i32,
+            width as i32,
+            height as i32,
+            0,
+        );
+        SDL_RaiseWindow(platform().window);
+    }
+}
+
+pub fn close_sub_window() {
+    if platform().sub_window.is_null() {
+        return;
+    }
+    unsafe {
+        SDL_DestroyWindow(platform().sub_window);
+    }
+    platform().sub_window = null_mut();
+}
+
+pub fn update_sub_window(width: u32, height: u32, rgb_data: &[u32]) {
+    if platform().sub_window.is_null() {
+        return;
+    }
+    unsafe {
+        let surface = SDL_CreateRGBSurfaceWithFormatFrom(
+            rgb_data.as_ptr().cast_mut().cast(),
+            width as i32,
+            height as i32,
+            32,
+            (width * 4) as i32,
+            SDL_PIXELFORMAT_RGB888 as Uint32,
+        );
+        let window_surface = SDL_GetWindowSurface(platform().sub_window);
+        if !surface.is_null() && !window_surface.is_null() {
+            SDL_UpperBlitScaled(surface, null(), window_surface, null_mut());
+            SDL_UpdateWindowSurface(platform().sub_window);
+        }
+        SDL_FreeSurface(surface);
+    }
+}
+
 pub fn set_mouse_visible(visible: bool) {
     let visible = if visible {
         SDL_ENABLE as i32
@@ -160,6 +281,37 @@ pub fn set_mouse_visible(visible: bool) {
     }
 }
 
+#[derive(Clone, Copy)]
+pub enum SystemCursor {
+    Arrow,
+    Crosshair,
+    Hand,
+    IBeam,
+    Wait,
+}
+
+pub fn set_system_cursor(cursor: SystemCursor) {
+    let id = match cursor {
+        SystemCursor::Arrow => SDL_SYSTEM_CURSOR_ARROW,
+        SystemCursor::Crosshair => SDL_SYSTEM_CURSOR_CROSSHAIR,
+        SystemCursor::Hand => SDL_SYSTEM_CURSOR_HAND,
+        SystemCursor::IBeam => SDL_SYSTEM_CURSOR_IBEAM,
+        SystemCursor::Wait => SDL_SYSTEM_CURSOR_WAIT,
+    };
+    let sdl_cursor = unsafe { SDL_CreateSystemCursor(id) };
+    if sdl_cursor.is_null() {
+        log::warn!("SDL_CreateSystemCursor failed: {}", sdl_error());
+        return;
+    }
+    unsafe {
+        SDL_SetCursor(sdl_cursor);
+        if !platform().cursor.is_null() {
+            SDL_FreeCursor(platform().cursor);
+        }
+    }
+    platform().cursor = sdl_cursor;
+}
+
 pub fn set_mouse_pos(x: i32, y: i32) {
     let (window_x, window_y) = window_pos();
     unsafe {
@@ -167,15 +319,45 @@ pub fn set_mouse_pos(x: i32, y: i32) {
     }
 }
 
+pub fn is_sub_window_id(window_id: Uint32) -> bool {
+    let sub_window = platform().sub_window;
+    !sub_window.is_null() && window_id == unsafe { SDL_GetWindowID(sub_window) }
+}
+
 pub fn handle_window_event(sdl_event: SDL_Event) -> Vec<Event> {
     let mut events = Vec::new();
-    match unsafe { sdl_event.window.event } as SDL_WindowEventID {
+    let window_event = unsafe { sdl_event.window.event } as SDL_WindowEventID;
+    if !platform().sub_window.is_null() {
+        if is_sub_window_id(unsafe { sdl_event.window.windowID }) {
+            if window_event == SDL_WINDOWEVENT_CLOSE {
+                close_sub_window();
+                events.push(Event::SubWindowClosed);
+            }
+            return events;
+        }
+        if window_event == SDL_WINDOWEVENT_CLOSE {
+            events.push(Event::Quit);
+        }
+    }
+    match window_event {
         SDL_WINDOWEVENT_SHOWN | SDL_WINDOWEVENT_MAXIMIZED | SDL_WINDOWEVENT_RESTORED => {
             events.push(Event::WindowShown);
         }
         SDL_WINDOWEVENT_HIDDEN | SDL_WINDOWEVENT_MINIMIZED => {
             events.push(Event::WindowHidden);
         }
+        SDL_WINDOWEVENT_FOCUS_GAINED => {
+            events.push(Event::WindowFocusGained);
+        }
+        SDL_WINDOWEVENT_FOCUS_LOST => {
+            events.push(Event::WindowFocusLost);
+        }
+        SDL_WINDOWEVENT_SIZE_CHANGED => {
+            events.push(Event::WindowResized {
+                width: unsafe { sdl_event.window.data1 } as u32,
+                height: unsafe { sdl_event.window.data2 } as u32,
+            });
+        }
         _ => {}
     }
     events
@@ -188,7 +370,12 @@ pub fn handle_drop_file(sdl_event: SDL_Event) -> Vec<Event> {
     }
     let filename = unsafe { CStr::from_ptr(sdl_event.drop.file) };
     let filename = filename.to_string_lossy().into_owned();
-    events.push(Event::FileDropped { filename });
+    // Dropped files on the web only live in the in-memory filesystem, so their data is read now
+    #[cfg(target_os = "emscripten")]
+    let data = std::fs::read(&filename).ok();
+    #[cfg(not(target_os = "emscripten"))]
+    let data = None;
+    events.push(Event::FileDropped { filename, data });
     unsafe {
         SDL_free(sdl_event.drop.file.cast());
     }
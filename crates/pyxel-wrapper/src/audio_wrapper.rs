@@ -50,6 +50,32 @@ fn play_pos(ch: u32) -> Option<(u32, u32)> {
     pyxel().play_pos(ch)
 }
 
+#[pyfunction]
+#[pyo3(text_signature = "(filename, *, loop)")]
+fn play_stream(filename: &str, r#loop: Option<bool>) -> PyResult<()> {
+    python_result!(pyxel().play_stream(filename, r#loop.unwrap_or(false)))
+}
+
+#[pyfunction]
+fn stop_stream() {
+    pyxel().stop_stream();
+}
+
+#[pyfunction]
+fn is_stream_playing() -> bool {
+    pyxel().is_stream_playing()
+}
+
+#[pyfunction]
+fn master_volume() -> f64 {
+    pyxel().master_volume()
+}
+
+#[pyfunction]
+fn set_master_volume(vol: f64) {
+    pyxel().set_master_volume(vol);
+}
+
 #[pyfunction]
 fn channel(ch: u32) -> Channel {
     CHANNEL_ONCE.call_once(|| {
@@ -79,6 +105,11 @@ pub fn add_audio_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(playm, m)?)?;
     m.add_function(wrap_pyfunction!(stop, m)?)?;
     m.add_function(wrap_pyfunction!(play_pos, m)?)?;
+    m.add_function(wrap_pyfunction!(play_stream, m)?)?;
+    m.add_function(wrap_pyfunction!(stop_stream, m)?)?;
+    m.add_function(wrap_pyfunction!(is_stream_playing, m)?)?;
+    m.add_function(wrap_pyfunction!(master_volume, m)?)?;
+    m.add_function(wrap_pyfunction!(set_master_volume, m)?)?;
 
     // Deprecated functions
     m.add_function(wrap_pyfunction!(channel, m)?)?;
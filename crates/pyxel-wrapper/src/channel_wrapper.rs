@@ -42,6 +42,87 @@ impl Channel {
         self.inner.lock().detune = detune;
     }
 
+    #[getter]
+    pub fn get_pan(&self) -> f64 {
+        self.inner.lock().pan
+    }
+
+    #[setter]
+    pub fn set_pan(&self, pan: f64) {
+        self.inner.lock().pan = pan;
+    }
+
+    #[getter]
+    pub fn get_reverb_send(&self) -> pyxel::Gain {
+        self.inner.lock().reverb_send
+    }
+
+    #[setter]
+    pub fn set_reverb_send(&self, reverb_send: pyxel::Gain) {
+        self.inner.lock().reverb_send = reverb_send;
+    }
+
+    #[getter]
+    pub fn get_lowpass_cutoff(&self) -> Option<f64> {
+        self.inner.lock().lowpass_cutoff
+    }
+
+    #[setter]
+    pub fn set_lowpass_cutoff(&self, lowpass_cutoff: Option<f64>) {
+        self.inner.lock().lowpass_cutoff = lowpass_cutoff;
+    }
+
+    #[getter]
+    pub fn get_highpass_cutoff(&self) -> Option<f64> {
+        self.inner.lock().highpass_cutoff
+    }
+
+    #[setter]
+    pub fn set_highpass_cutoff(&self, highpass_cutoff: Option<f64>) {
+        self.inner.lock().highpass_cutoff = highpass_cutoff;
+    }
+
+    #[getter]
+    pub fn get_envelope(&self) -> (u32, u32, pyxel::Gain, u32) {
+        let envelope = self.inner.lock().envelope;
+        (
+            envelope.attack,
+            envelope.decay,
+            envelope.sustain,
+            envelope.release,
+        )
+    }
+
+    #[setter]
+    pub fn set_envelope(&self, envelope: (u32, u32, pyxel::Gain, u32)) {
+        self.inner.lock().envelope = pyxel::Envelope {
+            attack: envelope.0,
+            decay: envelope.1,
+            sustain: envelope.2,
+            release: envelope.3,
+        };
+    }
+
+    #[getter]
+    pub fn get_vibrato_depth(&self) -> f64 {
+        self.inner.lock().vibrato_depth
+    }
+
+    #[setter]
+    pub fn set_vibrato_depth(&self, vibrato_depth: f64) {
+        self.inner.lock().vibrato_depth = vibrato_depth;
+    }
+
+    #[getter]
+    pub fn get_vibrato_frequency(&self) -> f64 {
+        self.inner.lock().vibrato_frequency
+    }
+
+    #[setter]
+    pub fn set_vibrato_frequency(&self, vibrato_frequency: f64) {
+        self.inner.lock().vibrato_frequency = vibrato_frequency;
+    }
+
     #[pyo3(text_signature = "($self, snd, *, tick, loop)")]
     pub fn play(&self, snd: &PyAny, tick: Option<u32>, r#loop: Option<bool>) -> PyResult<()> {
         let loop_ = r#loop.unwrap_or(false);
@@ -1,6 +1,8 @@
+use std::process::exit;
 use std::sync::Once;
 
 use pyo3::prelude::*;
+use pyxel::Pyxel;
 
 use crate::image_wrapper::Image;
 use crate::pyxel_singleton::pyxel;
@@ -129,7 +131,7 @@ fn blt(
     cast_pyany! {
         img,
         (u32, { pyxel().blt(x, y, img, u, v, w, h, colkey); }),
-        (Image, { pyxel().screen.lock().blt(x, y, img.inner, u, v, w, h, colkey); })
+        (Image, { pyxel().render_target().lock().blt(x, y, img.inner, u, v, w, h, colkey); })
     }
     Ok(())
 }
@@ -148,7 +150,7 @@ fn bltm(
     cast_pyany! {
         tm,
         (u32, { pyxel().bltm(x, y, tm, u, v, w, h, colkey); }),
-        (Tilemap, { pyxel().screen.lock().bltm(x, y, tm.inner, u, v, w, h, colkey); })
+        (Tilemap, { pyxel().render_target().lock().bltm(x, y, tm.inner, u, v, w, h, colkey); })
     }
     Ok(())
 }
@@ -158,6 +160,150 @@ fn text(x: f64, y: f64, s: &str, col: pyxel::Color) {
     pyxel().text(x, y, s, col);
 }
 
+#[pyfunction]
+fn text_width(s: &str) -> u32 {
+    pyxel().text_width(s)
+}
+
+#[pyfunction]
+fn text_aligned(x: f64, y: f64, s: &str, col: pyxel::Color, align: u32) {
+    pyxel().text_aligned(x, y, s, col, align);
+}
+
+#[pyfunction]
+fn text_wrapped(x: f64, y: f64, w: f64, s: &str, col: pyxel::Color) -> u32 {
+    pyxel().text_wrapped(x, y, w, s, col)
+}
+
+#[pyfunction]
+fn load_font(filename: &str) -> PyResult<()> {
+    python_result!(pyxel().load_font(filename))
+}
+
+#[pyfunction]
+fn reset_font() {
+    pyxel().reset_font();
+}
+
+#[pyfunction]
+fn set_render_target(img: &PyAny) -> PyResult<()> {
+    cast_pyany! {
+        img,
+        (u32, { let image = pyxel().images.lock()[img as usize].clone(); pyxel().set_render_target(image); }),
+        (Image, { pyxel().set_render_target(img.inner); })
+    }
+    Ok(())
+}
+
+#[pyfunction]
+fn reset_render_target() {
+    pyxel().reset_render_target();
+}
+
+#[pyfunction]
+fn border_color(rgb: pyxel::Rgb24) {
+    pyxel().border_color(rgb);
+}
+
+#[pyfunction]
+fn color_filter(mode: u32) {
+    pyxel().color_filter(mode);
+}
+
+#[pyfunction]
+fn border_image(img: Option<&PyAny>) -> PyResult<()> {
+    if let Some(img) = img {
+        cast_pyany! {
+            img,
+            (u32, { let image = pyxel().images.lock()[img as usize].clone(); pyxel().border_image(Some(image)); }),
+            (Image, { pyxel().border_image(Some(img.inner)); })
+        }
+    } else {
+        pyxel().border_image(None);
+    }
+    Ok(())
+}
+
+#[pyfunction]
+fn viewport(
+    idx: Option<u32>,
+    x: Option<f64>,
+    y: Option<f64>,
+    w: Option<f64>,
+    h: Option<f64>,
+    draw: Option<PyObject>,
+) -> PyResult<()> {
+    if let (Some(idx), Some(x), Some(y), Some(w), Some(h), Some(draw)) = (idx, x, y, w, h, draw) {
+        pyxel().viewport(
+            idx,
+            x,
+            y,
+            w,
+            h,
+            Box::new(move |_: &mut Pyxel| {
+                Python::with_gil(|py| {
+                    if let Err(err) = draw.call0(py) {
+                        err.print(py);
+                        exit(1);
+                    }
+                });
+            }),
+        );
+    } else if (idx, x, y, w, h) == (None, None, None, None, None) {
+        pyxel().viewport0();
+    } else {
+        python_type_error!("viewport() takes 0 or 6 arguments");
+    }
+    Ok(())
+}
+
+#[pyfunction]
+fn viewport_camera(idx: u32, x: f64, y: f64) {
+    pyxel().viewport_camera(idx, x, y);
+}
+
+#[pyfunction]
+fn palette_cycle(start: Option<u32>, end: Option<u32>, speed: Option<i32>) -> PyResult<()> {
+    if let (Some(start), Some(end), Some(speed)) = (start, end, speed) {
+        pyxel().palette_cycle(start, end, speed);
+    } else if (start, end, speed) == (None, None, None) {
+        pyxel().palette_cycle0();
+    } else {
+        python_type_error!("palette_cycle() takes 0 or 3 arguments");
+    }
+    Ok(())
+}
+
+#[pyfunction]
+fn draw_offset(x: f64, y: f64) {
+    pyxel().draw_offset(x, y);
+}
+
+#[pyfunction]
+fn screen_shake(intensity: f64, frames: u32) {
+    pyxel().screen_shake(intensity, frames);
+}
+
+#[pyfunction]
+fn start_transition(kind: u32, frames: u32) {
+    pyxel().start_transition(kind, frames);
+}
+
+#[pyfunction]
+fn is_transitioning() -> bool {
+    pyxel().is_transitioning()
+}
+
+#[pyfunction]
+fn open_debug_window(w: u32, h: u32) -> Image {
+    Image::wrap(pyxel().open_debug_window(w, h))
+}
+
+#[pyfunction]
+fn close_debug_window() {
+    pyxel().close_debug_window();
+}
+
 #[pyfunction]
 fn image(img: u32) -> Image {
     IMAGE_ONCE.call_once(|| {
@@ -197,6 +343,25 @@ pub fn add_graphics_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(blt, m)?)?;
     m.add_function(wrap_pyfunction!(bltm, m)?)?;
     m.add_function(wrap_pyfunction!(text, m)?)?;
+    m.add_function(wrap_pyfunction!(text_width, m)?)?;
+    m.add_function(wrap_pyfunction!(text_aligned, m)?)?;
+    m.add_function(wrap_pyfunction!(text_wrapped, m)?)?;
+    m.add_function(wrap_pyfunction!(load_font, m)?)?;
+    m.add_function(wrap_pyfunction!(reset_font, m)?)?;
+    m.add_function(wrap_pyfunction!(border_color, m)?)?;
+    m.add_function(wrap_pyfunction!(color_filter, m)?)?;
+    m.add_function(wrap_pyfunction!(set_render_target, m)?)?;
+    m.add_function(wrap_pyfunction!(reset_render_target, m)?)?;
+    m.add_function(wrap_pyfunction!(border_image, m)?)?;
+    m.add_function(wrap_pyfunction!(viewport, m)?)?;
+    m.add_function(wrap_pyfunction!(viewport_camera, m)?)?;
+    m.add_function(wrap_pyfunction!(palette_cycle, m)?)?;
+    m.add_function(wrap_pyfunction!(draw_offset, m)?)?;
+    m.add_function(wrap_pyfunction!(screen_shake, m)?)?;
+    m.add_function(wrap_pyfunction!(start_transition, m)?)?;
+    m.add_function(wrap_pyfunction!(is_transitioning, m)?)?;
+    m.add_function(wrap_pyfunction!(open_debug_window, m)?)?;
+    m.add_function(wrap_pyfunction!(close_debug_window, m)?)?;
 
     // Deprecated functions
     m.add_function(wrap_pyfunction!(image, m)?)?;
@@ -24,18 +24,18 @@ impl Image {
 
     #[staticmethod]
     #[pyo3(text_signature = "(filename, *, incl_colors)")]
-    pub fn from_image(filename: &str, incl_colors: Option<bool>) -> Self {
-        Self::wrap(pyxel::Image::from_image(filename, incl_colors))
+    pub fn from_image(filename: &str, incl_colors: Option<bool>) -> PyResult<Self> {
+        python_result!(pyxel::Image::from_image(filename, incl_colors)).map(Self::wrap)
     }
 
     #[getter]
     pub fn width(&self) -> u32 {
-        self.inner.lock().width()
+        self.inner.width()
     }
 
     #[getter]
     pub fn height(&self) -> u32 {
-        self.inner.lock().height()
+        self.inner.height()
     }
 
     pub fn data_ptr(&self, py: Python) -> PyObject {
@@ -50,17 +50,17 @@ impl Image {
         locals.get_item("c_uint8_array").unwrap().to_object(py)
     }
 
-    pub fn set(&self, x: i32, y: i32, data: Vec<&str>) {
-        self.inner.lock().set(x, y, &data);
+    pub fn set(&self, x: i32, y: i32, data: Vec<&str>) -> PyResult<()> {
+        python_result!(self.inner.lock().set(x, y, &data))
     }
 
     #[pyo3(text_signature = "($self, x, y, filename, *, incl_colors)")]
-    pub fn load(&self, x: i32, y: i32, filename: &str, incl_colors: Option<bool>) {
-        self.inner.lock().load(x, y, filename, incl_colors);
+    pub fn load(&self, x: i32, y: i32, filename: &str, incl_colors: Option<bool>) -> PyResult<()> {
+        python_result!(pyxel().load_image_file(self.inner.clone(), x, y, filename, incl_colors))
     }
 
-    pub fn save(&self, filename: &str, scale: u32) {
-        self.inner.lock().save(filename, scale);
+    pub fn save(&self, filename: &str, scale: u32) -> PyResult<()> {
+        python_result!(self.inner.lock().save(filename, scale))
     }
 
     pub fn clip(
@@ -111,7 +111,7 @@ impl Image {
     }
 
     pub fn pget(&self, x: f64, y: f64) -> pyxel::Color {
-        self.inner.lock().pget(x, y)
+        self.inner.read().pget(x, y)
     }
 
     pub fn pset(&self, x: f64, y: f64, col: pyxel::Color) {
@@ -180,6 +180,63 @@ impl Image {
         Ok(())
     }
 
+    pub fn blt_tiled(
+        &self,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        img: &PyAny,
+        u: f64,
+        v: f64,
+        tile_w: f64,
+        tile_h: f64,
+        colkey: Option<pyxel::Color>,
+    ) -> PyResult<()> {
+        cast_pyany! {
+            img,
+            (u32, {
+                let image = pyxel().images.lock()[img as usize].clone();
+                self.inner.lock().blt_tiled(x, y, w, h, image, u, v, tile_w, tile_h, colkey);
+            }),
+            (Image, {
+                self.inner.lock().blt_tiled(x, y, w, h, img.inner, u, v, tile_w, tile_h, colkey);
+            })
+        }
+        Ok(())
+    }
+
+    pub fn blt_9slice(
+        &self,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        img: &PyAny,
+        u: f64,
+        v: f64,
+        src_w: f64,
+        src_h: f64,
+        margins: (u32, u32, u32, u32),
+        colkey: Option<pyxel::Color>,
+    ) -> PyResult<()> {
+        cast_pyany! {
+            img,
+            (u32, {
+                let image = pyxel().images.lock()[img as usize].clone();
+                self.inner.lock().blt_9slice(
+                    x, y, w, h, image, u, v, src_w, src_h, margins, colkey,
+                );
+            }),
+            (Image, {
+                self.inner.lock().blt_9slice(
+                    x, y, w, h, img.inner, u, v, src_w, src_h, margins, colkey,
+                );
+            })
+        }
+        Ok(())
+    }
+
     pub fn bltm(
         &self,
         x: f64,
@@ -205,6 +262,14 @@ impl Image {
     pub fn text(&self, x: f64, y: f64, s: &str, col: pyxel::Color) {
         self.inner.lock().text(x, y, s, col);
     }
+
+    pub fn text_aligned(&self, x: f64, y: f64, s: &str, col: pyxel::Color, align: u32) {
+        self.inner.lock().text_aligned(x, y, s, col, align);
+    }
+
+    pub fn text_wrapped(&self, x: f64, y: f64, w: f64, s: &str, col: pyxel::Color) -> u32 {
+        self.inner.lock().text_wrapped(x, y, w, s, col)
+    }
 }
 
 pub fn add_image_class(m: &PyModule) -> PyResult<()> {
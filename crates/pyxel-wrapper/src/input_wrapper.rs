@@ -1,5 +1,10 @@
+use std::process::exit;
+
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use pyxel::Pyxel;
 
+use crate::image_wrapper::Image;
 use crate::pyxel_singleton::pyxel;
 
 #[pyfunction]
@@ -18,27 +23,200 @@ fn btnr(key: pyxel::Key) -> bool {
     pyxel().btnr(key)
 }
 
+#[pyfunction]
+fn btn_press_time(key: pyxel::Key) -> Option<u32> {
+    pyxel().btn_press_time(key)
+}
+
 #[pyfunction]
 fn btnv(key: pyxel::Key) -> pyxel::KeyValue {
     pyxel().btnv(key)
 }
 
+#[pyfunction]
+fn bind_action(action: &str, keys: Vec<pyxel::Key>) {
+    pyxel().bind_action(action, &keys);
+}
+
+#[pyfunction]
+fn unbind_action(action: &str) {
+    pyxel().unbind_action(action);
+}
+
+#[pyfunction]
+fn action_keys(action: &str) -> Vec<pyxel::Key> {
+    pyxel().action_keys(action)
+}
+
+#[pyfunction]
+fn action(action: &str) -> bool {
+    pyxel().action(action)
+}
+
+#[pyfunction]
+#[pyo3(text_signature = "(action, *, hold, repeat)")]
+fn actionp(action: &str, hold: Option<u32>, repeat: Option<u32>) -> bool {
+    pyxel().actionp(action, hold, repeat)
+}
+
+#[pyfunction]
+fn actionr(action: &str) -> bool {
+    pyxel().actionr(action)
+}
+
+#[pyfunction]
+fn save_input_map(filename: &str) -> PyResult<()> {
+    python_result!(pyxel().save_input_map(filename))
+}
+
+#[pyfunction]
+fn load_input_map(filename: &str) -> PyResult<()> {
+    python_result!(pyxel().load_input_map(filename))
+}
+
+#[pyfunction]
+fn last_pressed_key() -> Option<pyxel::Key> {
+    pyxel().last_pressed_key()
+}
+
+#[pyfunction]
+fn key_name(key: pyxel::Key) -> &'static str {
+    pyxel::key_name(key)
+}
+
+#[pyfunction]
+fn key_from_name(name: &str) -> Option<pyxel::Key> {
+    pyxel::key_from_name(name)
+}
+
+#[pyfunction]
+fn gamepad_count() -> u32 {
+    pyxel().gamepad_count()
+}
+
+#[pyfunction]
+fn rumble(gamepad: u32, low_freq: f64, high_freq: f64, duration_ms: u32) -> bool {
+    pyxel().rumble(gamepad, low_freq, high_freq, duration_ms)
+}
+
 #[pyfunction]
 fn mouse(visible: bool) {
     pyxel().mouse(visible);
 }
 
+#[pyfunction]
+fn set_cursor(image: Image, hot_x: i32, hot_y: i32) {
+    pyxel().set_cursor(image.inner, hot_x, hot_y);
+}
+
+#[pyfunction]
+fn set_cursor_hotspot(hot_x: i32, hot_y: i32) {
+    pyxel().set_cursor_hotspot(hot_x, hot_y);
+}
+
+#[pyfunction]
+fn reset_cursor() {
+    pyxel().reset_cursor();
+}
+
+#[pyfunction]
+fn use_system_cursor(kind: u32) {
+    pyxel().use_system_cursor(kind);
+}
+
+#[pyfunction]
+fn virtual_gamepad(enabled: bool) {
+    pyxel().virtual_gamepad(enabled);
+}
+
+#[pyfunction]
+fn on_idle(frames: u32, callback: PyObject) {
+    pyxel().on_idle(
+        frames,
+        Box::new(move |_: &mut Pyxel| {
+            Python::with_gil(|py| {
+                if let Err(err) = callback.call0(py) {
+                    err.print(py);
+                    exit(1);
+                }
+            });
+        }),
+    );
+}
+
+#[pyfunction]
+fn calibrate_motion() {
+    pyxel().calibrate_motion();
+}
+
+#[pyfunction]
+fn mouse_in(x: f64, y: f64, w: f64, h: f64) -> bool {
+    pyxel().mouse_in(x, y, w, h)
+}
+
+#[pyfunction]
+fn hot_rect(id: u32, x: f64, y: f64, w: f64, h: f64) -> u32 {
+    pyxel().hot_rect(id, x, y, w, h)
+}
+
+#[cfg(feature = "midi")]
+#[pyfunction]
+fn midi_ports() -> Vec<String> {
+    pyxel().midi_ports()
+}
+
+#[cfg(feature = "midi")]
+#[pyfunction]
+fn midi_in(port: u32, channel: Option<u32>, tone: Option<u32>) -> bool {
+    pyxel().midi_in(port, channel, tone)
+}
+
 #[pyfunction]
 pub fn warp_mouse(x: f64, y: f64) {
     pyxel().warp_mouse(x, y);
 }
 
+#[pyfunction]
+fn dropped_file_data(py: Python, i: u32) -> Option<&PyBytes> {
+    pyxel()
+        .dropped_file_data(i)
+        .map(|data| PyBytes::new(py, &data))
+}
+
 pub fn add_input_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(btn, m)?)?;
     m.add_function(wrap_pyfunction!(btnp, m)?)?;
     m.add_function(wrap_pyfunction!(btnr, m)?)?;
+    m.add_function(wrap_pyfunction!(btn_press_time, m)?)?;
     m.add_function(wrap_pyfunction!(btnv, m)?)?;
+    m.add_function(wrap_pyfunction!(bind_action, m)?)?;
+    m.add_function(wrap_pyfunction!(unbind_action, m)?)?;
+    m.add_function(wrap_pyfunction!(action_keys, m)?)?;
+    m.add_function(wrap_pyfunction!(action, m)?)?;
+    m.add_function(wrap_pyfunction!(actionp, m)?)?;
+    m.add_function(wrap_pyfunction!(actionr, m)?)?;
+    m.add_function(wrap_pyfunction!(save_input_map, m)?)?;
+    m.add_function(wrap_pyfunction!(load_input_map, m)?)?;
+    m.add_function(wrap_pyfunction!(last_pressed_key, m)?)?;
+    m.add_function(wrap_pyfunction!(key_name, m)?)?;
+    m.add_function(wrap_pyfunction!(key_from_name, m)?)?;
+    m.add_function(wrap_pyfunction!(gamepad_count, m)?)?;
+    m.add_function(wrap_pyfunction!(rumble, m)?)?;
     m.add_function(wrap_pyfunction!(mouse, m)?)?;
+    m.add_function(wrap_pyfunction!(set_cursor, m)?)?;
+    m.add_function(wrap_pyfunction!(set_cursor_hotspot, m)?)?;
+    m.add_function(wrap_pyfunction!(reset_cursor, m)?)?;
+    m.add_function(wrap_pyfunction!(use_system_cursor, m)?)?;
+    m.add_function(wrap_pyfunction!(virtual_gamepad, m)?)?;
+    m.add_function(wrap_pyfunction!(on_idle, m)?)?;
+    m.add_function(wrap_pyfunction!(calibrate_motion, m)?)?;
+    m.add_function(wrap_pyfunction!(mouse_in, m)?)?;
+    m.add_function(wrap_pyfunction!(hot_rect, m)?)?;
+    #[cfg(feature = "midi")]
+    m.add_function(wrap_pyfunction!(midi_ports, m)?)?;
+    #[cfg(feature = "midi")]
+    m.add_function(wrap_pyfunction!(midi_in, m)?)?;
     m.add_function(wrap_pyfunction!(warp_mouse, m)?)?;
+    m.add_function(wrap_pyfunction!(dropped_file_data, m)?)?;
     Ok(())
 }
@@ -21,6 +21,7 @@ mod math_wrapper;
 mod music_wrapper;
 mod pyxel_singleton;
 mod resource_wrapper;
+mod sample_wrapper;
 mod sound_wrapper;
 mod system_wrapper;
 mod tilemap_wrapper;
@@ -37,6 +38,7 @@ fn pyxel_wrapper(_py: Python, m: &PyModule) -> PyResult<()> {
     crate::sound_wrapper::add_sound_class(m)?;
     crate::music_wrapper::add_music_class(m)?;
     crate::tone_wrapper::add_tone_class(m)?;
+    crate::sample_wrapper::add_sample_class(m)?;
 
     crate::constant_wrapper::add_module_constants(m)?;
     crate::variable_wrapper::add_module_variables(m)?;
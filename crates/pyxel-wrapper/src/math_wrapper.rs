@@ -64,6 +64,56 @@ fn noise(x: f64, y: Option<f64>, z: Option<f64>) -> f64 {
     pyxel().noise(x, y, z)
 }
 
+#[pyfunction]
+fn rect_collide(x1: f64, y1: f64, w1: f64, h1: f64, x2: f64, y2: f64, w2: f64, h2: f64) -> bool {
+    pyxel().rect_collide(x1, y1, w1, h1, x2, y2, w2, h2)
+}
+
+#[pyfunction]
+fn circ_rect_collide(cx: f64, cy: f64, r: f64, x: f64, y: f64, w: f64, h: f64) -> bool {
+    pyxel().circ_rect_collide(cx, cy, r, x, y, w, h)
+}
+
+#[pyfunction]
+fn sweep_rect(
+    x1: f64,
+    y1: f64,
+    w1: f64,
+    h1: f64,
+    dx: f64,
+    dy: f64,
+    x2: f64,
+    y2: f64,
+    w2: f64,
+    h2: f64,
+) -> Option<(f64, f64, f64)> {
+    pyxel().sweep_rect(x1, y1, w1, h1, dx, dy, x2, y2, w2, h2)
+}
+
+#[pyfunction]
+fn pixel_collide(
+    x1: f64,
+    y1: f64,
+    img1: u32,
+    u1: f64,
+    v1: f64,
+    w1: f64,
+    h1: f64,
+    colkey1: Option<pyxel::Color>,
+    x2: f64,
+    y2: f64,
+    img2: u32,
+    u2: f64,
+    v2: f64,
+    w2: f64,
+    h2: f64,
+    colkey2: Option<pyxel::Color>,
+) -> bool {
+    pyxel().pixel_collide(
+        x1, y1, img1, u1, v1, w1, h1, colkey1, x2, y2, img2, u2, v2, w2, h2, colkey2,
+    )
+}
+
 pub fn add_math_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(ceil, m)?)?;
     m.add_function(wrap_pyfunction!(floor, m)?)?;
@@ -77,5 +127,9 @@ pub fn add_math_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(rndf, m)?)?;
     m.add_function(wrap_pyfunction!(nseed, m)?)?;
     m.add_function(wrap_pyfunction!(noise, m)?)?;
+    m.add_function(wrap_pyfunction!(rect_collide, m)?)?;
+    m.add_function(wrap_pyfunction!(circ_rect_collide, m)?)?;
+    m.add_function(wrap_pyfunction!(sweep_rect, m)?)?;
+    m.add_function(wrap_pyfunction!(pixel_collide, m)?)?;
     Ok(())
 }
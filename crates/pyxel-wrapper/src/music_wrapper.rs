@@ -72,6 +72,10 @@ impl Music {
         self.inner.lock().set(&rust_seqs);
     }
 
+    pub fn save(&self, filename: &str, sec: f64) -> PyResult<()> {
+        python_result!(self.inner.lock().save(filename, sec))
+    }
+
     #[getter]
     pub fn snds_list(&self) -> Seqs {
         SNDS_LIST_ONCE.call_once(|| {
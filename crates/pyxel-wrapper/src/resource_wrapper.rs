@@ -1,4 +1,5 @@
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
 
 use crate::pyxel_singleton::pyxel;
 
@@ -15,8 +16,8 @@ fn load(
     incl_colors: Option<bool>,
     incl_channels: Option<bool>,
     incl_tones: Option<bool>,
-) {
-    pyxel().load(
+) -> PyResult<()> {
+    python_result!(pyxel().load(
         filename,
         excl_images,
         excl_tilemaps,
@@ -25,7 +26,55 @@ fn load(
         incl_colors,
         incl_channels,
         incl_tones,
-    );
+    ))
+}
+
+#[pyclass]
+pub struct ResourceLoad {
+    inner: pyxel::ResourceLoad,
+}
+
+#[pymethods]
+impl ResourceLoad {
+    pub fn progress(&self) -> f64 {
+        self.inner.progress()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.inner.is_done()
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.inner.error()
+    }
+}
+
+#[pyfunction]
+#[pyo3(
+    text_signature = "(filename, *, excl_images, excl_tilemaps, excl_sounds, excl_musics, incl_colors, incl_channels, incl_tones)"
+)]
+fn load_async(
+    filename: &str,
+    excl_images: Option<bool>,
+    excl_tilemaps: Option<bool>,
+    excl_sounds: Option<bool>,
+    excl_musics: Option<bool>,
+    incl_colors: Option<bool>,
+    incl_channels: Option<bool>,
+    incl_tones: Option<bool>,
+) -> ResourceLoad {
+    ResourceLoad {
+        inner: pyxel().load_async(
+            filename,
+            excl_images,
+            excl_tilemaps,
+            excl_sounds,
+            excl_musics,
+            incl_colors,
+            incl_channels,
+            incl_tones,
+        ),
+    }
 }
 
 #[pyfunction]
@@ -41,8 +90,8 @@ fn save(
     incl_colors: Option<bool>,
     incl_channels: Option<bool>,
     incl_tones: Option<bool>,
-) {
-    pyxel().save(
+) -> PyResult<()> {
+    python_result!(pyxel().save(
         filename,
         excl_images,
         excl_tilemaps,
@@ -51,17 +100,45 @@ fn save(
         incl_colors,
         incl_channels,
         incl_tones,
-    );
+    ))
 }
 
 #[pyfunction]
-fn screenshot(scale: Option<u32>) {
-    pyxel().screenshot(scale);
+#[pyo3(text_signature = "(filename, msc, snd, *, speed)")]
+fn load_midi(filename: &str, msc: u32, snd: u32, speed: Option<pyxel::Speed>) -> PyResult<()> {
+    python_result!(pyxel().load_midi(filename, msc, snd, speed))
 }
 
 #[pyfunction]
-fn screencast(scale: Option<u32>) {
-    pyxel().screencast(scale);
+fn screenshot(scale: Option<u32>) -> PyResult<()> {
+    python_result!(pyxel().screenshot(scale))
+}
+
+#[pyfunction]
+fn screencast(scale: Option<u32>) -> PyResult<()> {
+    python_result!(pyxel().screencast(scale))
+}
+
+#[pyfunction]
+#[pyo3(signature = (dir, *, name_template = None), text_signature = "(dir, *, name_template)")]
+fn set_capture_dir(dir: Option<&str>, name_template: Option<&str>) {
+    pyxel().set_capture_dir(dir, name_template);
+}
+
+#[pyfunction]
+fn screenshot_to_clipboard(enabled: bool) {
+    pyxel().screenshot_to_clipboard(enabled);
+}
+
+#[pyfunction]
+fn capture_filter(filter: u32) {
+    pyxel().capture_filter(filter);
+}
+
+#[pyfunction]
+#[pyo3(signature = (format, *, capture_sec = None), text_signature = "(format, *, capture_sec)")]
+fn screencast_format(format: u32, capture_sec: Option<u32>) -> PyResult<()> {
+    python_result!(pyxel().screencast_format(format, capture_sec))
 }
 
 #[pyfunction]
@@ -69,11 +146,55 @@ fn reset_screencast() {
     pyxel().reset_screencast();
 }
 
+#[pyfunction]
+fn save_state(py: Python) -> PyResult<&PyBytes> {
+    let state = python_result!(pyxel().save_state())?;
+    Ok(PyBytes::new(py, &state))
+}
+
+#[pyfunction]
+fn load_state(state: &[u8]) -> PyResult<()> {
+    python_result!(pyxel().load_state(state))
+}
+
+#[pyfunction]
+fn set_save_name(name: &str) {
+    pyxel().set_save_name(name);
+}
+
+#[pyfunction]
+fn save_data(slot: u32, data: &[u8]) -> PyResult<()> {
+    python_result!(pyxel().save_data(slot, data))
+}
+
+#[pyfunction]
+fn load_data(py: Python, slot: u32) -> Option<&PyBytes> {
+    pyxel().load_data(slot).map(|data| PyBytes::new(py, &data))
+}
+
+#[pyfunction]
+fn hot_reload(enabled: bool) {
+    pyxel().hot_reload(enabled);
+}
+
 pub fn add_resource_functions(m: &PyModule) -> PyResult<()> {
+    m.add_class::<ResourceLoad>()?;
     m.add_function(wrap_pyfunction!(load, m)?)?;
+    m.add_function(wrap_pyfunction!(load_async, m)?)?;
     m.add_function(wrap_pyfunction!(save, m)?)?;
+    m.add_function(wrap_pyfunction!(load_midi, m)?)?;
     m.add_function(wrap_pyfunction!(screenshot, m)?)?;
     m.add_function(wrap_pyfunction!(screencast, m)?)?;
+    m.add_function(wrap_pyfunction!(set_capture_dir, m)?)?;
+    m.add_function(wrap_pyfunction!(screenshot_to_clipboard, m)?)?;
+    m.add_function(wrap_pyfunction!(capture_filter, m)?)?;
+    m.add_function(wrap_pyfunction!(screencast_format, m)?)?;
     m.add_function(wrap_pyfunction!(reset_screencast, m)?)?;
+    m.add_function(wrap_pyfunction!(save_state, m)?)?;
+    m.add_function(wrap_pyfunction!(load_state, m)?)?;
+    m.add_function(wrap_pyfunction!(set_save_name, m)?)?;
+    m.add_function(wrap_pyfunction!(save_data, m)?)?;
+    m.add_function(wrap_pyfunction!(load_data, m)?)?;
+    m.add_function(wrap_pyfunction!(hot_reload, m)?)?;
     Ok(())
 }
@@ -0,0 +1,51 @@
+use pyo3::prelude::*;
+
+#[pyclass]
+#[derive(Clone)]
+pub struct Sample {
+    pub(crate) inner: pyxel::SharedSample,
+}
+
+impl Sample {
+    pub fn wrap(inner: pyxel::SharedSample) -> Self {
+        Self { inner }
+    }
+}
+
+#[pymethods]
+impl Sample {
+    #[new]
+    pub fn new() -> Self {
+        Self::wrap(pyxel::Sample::new())
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.inner.lock().data.len()
+    }
+
+    #[getter]
+    pub fn sample_rate(&self) -> u32 {
+        self.inner.lock().sample_rate
+    }
+
+    #[getter]
+    pub fn base_note(&self) -> pyxel::Note {
+        self.inner.lock().base_note
+    }
+
+    #[setter]
+    pub fn set_base_note(&self, base_note: pyxel::Note) {
+        self.inner.lock().base_note = base_note;
+    }
+
+    pub fn load(&self, filename: &str) -> PyResult<()> {
+        let (sample_rate, data) = python_result!(pyxel::Sample::read_wav_file(filename))?;
+        self.inner.lock().set_data(sample_rate, data);
+        Ok(())
+    }
+}
+
+pub fn add_sample_class(m: &PyModule) -> PyResult<()> {
+    m.add_class::<Sample>()?;
+    Ok(())
+}
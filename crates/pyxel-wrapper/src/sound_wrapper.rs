@@ -95,6 +95,14 @@ impl Sound {
     pub fn set_effects(&self, effects: &str) {
         self.inner.lock().set_effects(effects);
     }
+
+    pub fn render(&self, sample_rate: u32) -> Vec<i16> {
+        self.inner.lock().render(sample_rate)
+    }
+
+    pub fn save(&self, filename: &str, sec: f64) -> PyResult<()> {
+        python_result!(self.inner.lock().save(filename, sec))
+    }
 }
 
 pub fn add_sound_class(m: &PyModule) -> PyResult<()> {
@@ -10,7 +10,7 @@ use crate::pyxel_singleton::{pyxel, set_pyxel_instance};
 
 #[pyfunction]
 #[pyo3(
-    text_signature = "(width, height, *, title, fps, quit_key, display_scale, capture_scale, capture_sec)"
+    text_signature = "(width, height, *, title, fps, quit_key, display_scale, capture_scale, capture_sec, max_update_per_frame, max_elapsed_ms, resizable, scaling_mode, pause_key, vsync)"
 )]
 fn init(
     py: Python,
@@ -22,6 +22,12 @@ fn init(
     display_scale: Option<u32>,
     capture_scale: Option<u32>,
     capture_sec: Option<u32>,
+    max_update_per_frame: Option<u32>,
+    max_elapsed_ms: Option<u32>,
+    resizable: Option<bool>,
+    scaling_mode: Option<u32>,
+    pause_key: Option<pyxel::Key>,
+    vsync: Option<bool>,
 ) -> PyResult<()> {
     let locals = PyDict::new(py);
     locals.set_item("os", py.import("os")?)?;
@@ -31,16 +37,31 @@ fn init(
         None,
         Some(locals),
     )?;
-    set_pyxel_instance(pyxel::init(
-        width,
-        height,
+    let mut builder = Pyxel::builder(width, height);
+    macro_rules! set_options {
+        ($($option: ident),*) => {
+            $(
+                if let Some(value) = $option {
+                    builder = builder.$option(value);
+                }
+            )*
+        };
+    }
+    set_options!(
         title,
         fps,
         quit_key,
         display_scale,
         capture_scale,
         capture_sec,
-    ));
+        max_update_per_frame,
+        max_elapsed_ms,
+        resizable,
+        scaling_mode,
+        pause_key,
+        vsync
+    );
+    set_pyxel_instance(python_result!(builder.build())?);
     Ok(())
 }
 
@@ -69,16 +90,26 @@ fn run(py: Python, update: &PyAny, draw: &PyAny) {
     }
 
     pyxel().run(PythonCallback { py, update, draw });
+    pyxel().quit();
+}
+
+#[cfg(feature = "lua")]
+#[pyfunction]
+fn run_lua(filename: &str) -> PyResult<()> {
+    let result = python_result!(pyxel().run_lua(filename));
+    pyxel().quit();
+    result
 }
 
 #[pyfunction]
 fn show() {
     pyxel().show();
+    pyxel().quit();
 }
 
 #[pyfunction]
-fn flip() {
-    pyxel().flip();
+fn flip() -> PyResult<()> {
+    python_result!(pyxel().flip())
 }
 
 #[pyfunction]
@@ -86,19 +117,95 @@ fn quit() {
     pyxel().quit();
 }
 
+#[pyfunction]
+fn set_quit_handler(handler: Option<PyObject>) {
+    pyxel().set_quit_handler(handler.map(|handler| {
+        Box::new(move |_: &mut Pyxel| {
+            Python::with_gil(
+                |py| match handler.call0(py).and_then(|ret| ret.extract::<bool>(py)) {
+                    Ok(should_quit) => should_quit,
+                    Err(err) => {
+                        err.print(py);
+                        exit(1);
+                    }
+                },
+            )
+        }) as pyxel::QuitHandler
+    }));
+}
+
+#[pyfunction]
+fn set_announce_handler(handler: Option<PyObject>) {
+    pyxel().set_announce_handler(handler.map(|handler| {
+        Box::new(move |text: &str| {
+            Python::with_gil(|py| {
+                if let Err(err) = handler.call1(py, (text,)) {
+                    err.print(py);
+                    exit(1);
+                }
+            });
+        }) as pyxel::AnnounceHandler
+    }));
+}
+
+#[pyfunction]
+fn announce(text: &str) {
+    pyxel().announce(text);
+}
+
+#[pyfunction]
+fn pause_key(key: pyxel::Key) {
+    pyxel().pause_key(key);
+}
+
 #[pyfunction]
 fn title(title: &str) {
     pyxel().title(title);
 }
 
 #[pyfunction]
-fn icon(data: Vec<&str>, scale: u32, colkey: Option<pyxel::Color>) {
-    pyxel().icon(&data, scale, colkey);
+fn icon(data: Vec<&str>, scale: u32, colkey: Option<pyxel::Color>) -> PyResult<()> {
+    python_result!(pyxel().icon(&data, scale, colkey))
 }
 
 #[pyfunction]
-fn fullscreen(full: bool) {
-    pyxel().fullscreen(full);
+fn fullscreen(full: bool, exclusive: Option<bool>) {
+    pyxel().fullscreen(full, exclusive);
+}
+
+#[pyfunction]
+fn dpi_scale() -> f64 {
+    pyxel().dpi_scale()
+}
+
+#[pyfunction]
+fn window_pos() -> (i32, i32) {
+    pyxel().window_pos()
+}
+
+#[pyfunction]
+fn set_window_pos(x: i32, y: i32) {
+    pyxel().set_window_pos(x, y);
+}
+
+#[pyfunction]
+fn borderless(enabled: bool) {
+    pyxel().borderless(enabled);
+}
+
+#[pyfunction]
+fn always_on_top(enabled: bool) {
+    pyxel().always_on_top(enabled);
+}
+
+#[pyfunction]
+fn displays() -> Vec<String> {
+    pyxel().displays()
+}
+
+#[pyfunction]
+fn set_display(idx: u32) {
+    pyxel().set_display(idx);
 }
 
 #[pyfunction]
@@ -106,6 +213,131 @@ fn screen_mode(scr: u32) {
     pyxel().screen_mode(scr);
 }
 
+#[pyfunction]
+fn scaling_mode(mode: u32) {
+    pyxel().scaling_mode(mode);
+}
+
+#[pyfunction]
+fn current_scaling_mode() -> u32 {
+    pyxel().current_scaling_mode()
+}
+
+#[pyfunction]
+fn set_screen_size(width: u32, height: u32) -> PyResult<()> {
+    python_result!(pyxel().set_screen_size(width, height))
+}
+
+#[pyfunction]
+fn set_fps(fps: u32) {
+    pyxel().set_fps(fps);
+}
+
+#[pyfunction]
+fn vsync(enabled: bool) {
+    pyxel().vsync(enabled);
+}
+
+#[pyfunction]
+fn set_quit_key(key: Option<pyxel::Key>) {
+    pyxel().set_quit_key(key);
+}
+
+#[pyfunction]
+#[pyo3(signature = (
+    *,
+    modifier=None,
+    dump_modifier=None,
+    fullscreen=None,
+    perf_monitor=None,
+    screenshot=None,
+    reset_screencast=None,
+    screencast=None,
+    screen_mode=None,
+    debug_console=None,
+    palette_editor=None,
+    bank_viewer=None,
+    frame_step=None,
+    frame_advance=None,
+))]
+fn set_system_keys(
+    modifier: Option<pyxel::Key>,
+    dump_modifier: Option<pyxel::Key>,
+    fullscreen: Option<pyxel::Key>,
+    perf_monitor: Option<pyxel::Key>,
+    screenshot: Option<pyxel::Key>,
+    reset_screencast: Option<pyxel::Key>,
+    screencast: Option<pyxel::Key>,
+    screen_mode: Option<pyxel::Key>,
+    debug_console: Option<pyxel::Key>,
+    palette_editor: Option<pyxel::Key>,
+    bank_viewer: Option<pyxel::Key>,
+    frame_step: Option<pyxel::Key>,
+    frame_advance: Option<pyxel::Key>,
+) {
+    let keys = pyxel().system_keys();
+    pyxel().set_system_keys(pyxel::SystemKeys {
+        modifier: modifier.unwrap_or(keys.modifier),
+        dump_modifier: dump_modifier.unwrap_or(keys.dump_modifier),
+        fullscreen: fullscreen.unwrap_or(keys.fullscreen),
+        perf_monitor: perf_monitor.unwrap_or(keys.perf_monitor),
+        screenshot: screenshot.unwrap_or(keys.screenshot),
+        reset_screencast: reset_screencast.unwrap_or(keys.reset_screencast),
+        screencast: screencast.unwrap_or(keys.screencast),
+        screen_mode: screen_mode.unwrap_or(keys.screen_mode),
+        debug_console: debug_console.unwrap_or(keys.debug_console),
+        palette_editor: palette_editor.unwrap_or(keys.palette_editor),
+        bank_viewer: bank_viewer.unwrap_or(keys.bank_viewer),
+        frame_step: frame_step.unwrap_or(keys.frame_step),
+        frame_advance: frame_advance.unwrap_or(keys.frame_advance),
+    });
+}
+
+#[pyfunction]
+fn elapsed_time() -> u32 {
+    pyxel().elapsed_time()
+}
+
+#[pyfunction]
+fn dropped_frames() -> u32 {
+    pyxel().dropped_frames()
+}
+
+#[pyfunction]
+fn dbg_print(text: &str) {
+    pyxel().dbg_print(text);
+}
+
+#[pyfunction]
+fn perf(py: Python) -> PyResult<&PyDict> {
+    fn frame_stats(py: Python, stats: pyxel::FrameStats) -> PyResult<&PyDict> {
+        let dict = PyDict::new(py);
+        dict.set_item("average", stats.average)?;
+        dict.set_item("p50", stats.p50)?;
+        dict.set_item("p95", stats.p95)?;
+        dict.set_item("p99", stats.p99)?;
+        dict.set_item("max", stats.max)?;
+        Ok(dict)
+    }
+
+    let perf = pyxel().perf();
+    let dict = PyDict::new(py);
+    dict.set_item("fps", perf.fps)?;
+    dict.set_item("update", frame_stats(py, perf.update)?)?;
+    dict.set_item("draw", frame_stats(py, perf.draw)?)?;
+    dict.set_item("frame", frame_stats(py, perf.frame)?)?;
+    dict.set_item("histogram", perf.histogram)?;
+    dict.set_item("draw_calls", perf.draw_calls)?;
+    dict.set_item("blit_pixels", perf.blit_pixels)?;
+    dict.set_item("slow_audio_callbacks", perf.slow_audio_callbacks)?;
+    Ok(dict)
+}
+
+#[pyfunction]
+fn perf_window(frames: u32) {
+    pyxel().perf_window(frames);
+}
+
 #[cfg(not(target_os = "emscripten"))]
 #[pyfunction]
 fn process_exists(pid: u32) -> bool {
@@ -113,17 +345,48 @@ fn process_exists(pid: u32) -> bool {
     system.process(Pid::from_u32(pid)).is_some()
 }
 
+#[pyfunction]
+fn init_logger(level: Option<&str>) -> bool {
+    pyxel::init_logger(level)
+}
+
 pub fn add_system_functions(m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(init, m)?)?;
     m.add_function(wrap_pyfunction!(run, m)?)?;
+    #[cfg(feature = "lua")]
+    m.add_function(wrap_pyfunction!(run_lua, m)?)?;
     m.add_function(wrap_pyfunction!(show, m)?)?;
     m.add_function(wrap_pyfunction!(flip, m)?)?;
     m.add_function(wrap_pyfunction!(quit, m)?)?;
+    m.add_function(wrap_pyfunction!(set_quit_handler, m)?)?;
+    m.add_function(wrap_pyfunction!(set_announce_handler, m)?)?;
+    m.add_function(wrap_pyfunction!(announce, m)?)?;
+    m.add_function(wrap_pyfunction!(pause_key, m)?)?;
     m.add_function(wrap_pyfunction!(title, m)?)?;
     m.add_function(wrap_pyfunction!(icon, m)?)?;
     m.add_function(wrap_pyfunction!(fullscreen, m)?)?;
+    m.add_function(wrap_pyfunction!(dpi_scale, m)?)?;
+    m.add_function(wrap_pyfunction!(window_pos, m)?)?;
+    m.add_function(wrap_pyfunction!(set_window_pos, m)?)?;
+    m.add_function(wrap_pyfunction!(borderless, m)?)?;
+    m.add_function(wrap_pyfunction!(always_on_top, m)?)?;
+    m.add_function(wrap_pyfunction!(displays, m)?)?;
+    m.add_function(wrap_pyfunction!(set_display, m)?)?;
     m.add_function(wrap_pyfunction!(screen_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(scaling_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(current_scaling_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(set_screen_size, m)?)?;
+    m.add_function(wrap_pyfunction!(set_fps, m)?)?;
+    m.add_function(wrap_pyfunction!(vsync, m)?)?;
+    m.add_function(wrap_pyfunction!(set_quit_key, m)?)?;
+    m.add_function(wrap_pyfunction!(set_system_keys, m)?)?;
+    m.add_function(wrap_pyfunction!(elapsed_time, m)?)?;
+    m.add_function(wrap_pyfunction!(dropped_frames, m)?)?;
+    m.add_function(wrap_pyfunction!(dbg_print, m)?)?;
+    m.add_function(wrap_pyfunction!(perf, m)?)?;
+    m.add_function(wrap_pyfunction!(perf_window, m)?)?;
     #[cfg(not(target_os = "emscripten"))]
     m.add_function(wrap_pyfunction!(process_exists, m)?)?;
+    m.add_function(wrap_pyfunction!(init_logger, m)?)?;
     Ok(())
 }
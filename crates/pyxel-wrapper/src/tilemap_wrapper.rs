@@ -35,8 +35,8 @@ impl Tilemap {
     }
 
     #[staticmethod]
-    pub fn from_tmx(filename: &str, layer: u32) -> Self {
-        Self::wrap(pyxel::Tilemap::from_tmx(filename, layer))
+    pub fn from_tmx(filename: &str, layer: u32) -> PyResult<Self> {
+        python_result!(pyxel::Tilemap::from_tmx(filename, layer)).map(Self::wrap)
     }
 
     #[getter]
@@ -81,12 +81,12 @@ impl Tilemap {
         locals.get_item("c_uint8_array").unwrap().to_object(py)
     }
 
-    pub fn set(&mut self, x: i32, y: i32, data: Vec<&str>) {
-        self.inner.lock().set(x, y, &data);
+    pub fn set(&mut self, x: i32, y: i32, data: Vec<&str>) -> PyResult<()> {
+        python_result!(self.inner.lock().set(x, y, &data))
     }
 
-    pub fn load(&self, x: i32, y: i32, filename: &str, layer: u32) {
-        self.inner.lock().load(x, y, filename, layer);
+    pub fn load(&self, x: i32, y: i32, filename: &str, layer: u32) -> PyResult<()> {
+        python_result!(pyxel().load_tilemap_file(self.inner.clone(), x, y, filename, layer))
     }
 
     pub fn clip(
@@ -129,6 +129,25 @@ impl Tilemap {
         self.inner.lock().pset(x, y, tile);
     }
 
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<pyxel::Tile> {
+        self.inner.lock().get_tile(x, y)
+    }
+
+    pub fn set_tile(&self, x: i32, y: i32, tile: pyxel::Tile) -> bool {
+        self.inner.lock().set_tile(x, y, tile)
+    }
+
+    pub fn rect_overlaps(
+        &self,
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        solid_tiles: Vec<pyxel::Tile>,
+    ) -> bool {
+        self.inner.lock().rect_overlaps(x, y, w, h, &solid_tiles)
+    }
+
     pub fn line(&self, x1: f64, y1: f64, x2: f64, y2: f64, tile: pyxel::Tile) {
         self.inner.lock().line(x1, y1, x2, y2, tile);
     }
@@ -56,6 +56,16 @@ impl Tone {
     pub fn waveform(&self) -> Waveform {
         Waveform::wrap(self.inner.clone())
     }
+
+    #[getter]
+    pub fn sample(&self) -> Option<u32> {
+        self.inner.lock().sample
+    }
+
+    #[setter]
+    pub fn set_sample(&self, sample: Option<u32>) {
+        self.inner.lock().sample = sample;
+    }
 }
 
 pub fn add_tone_class(m: &PyModule) -> PyResult<()> {
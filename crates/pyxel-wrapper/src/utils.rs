@@ -4,6 +4,20 @@ macro_rules! python_type_error {
     };
 }
 
+macro_rules! python_result {
+    ($result: expr) => {
+        $result.map_err(|err| match err {
+            pyxel::PyxelError::FileOpen(_) | pyxel::PyxelError::FileWrite(_) => {
+                pyo3::exceptions::PyIOError::new_err(err.to_string())
+            }
+            pyxel::PyxelError::InvalidArgument(_) => {
+                pyo3::exceptions::PyValueError::new_err(err.to_string())
+            }
+            _ => pyo3::exceptions::PyRuntimeError::new_err(err.to_string()),
+        })
+    };
+}
+
 macro_rules! cast_pyany {
     ($pyany: ident, $(($type: ty, $block: block)),*) => {
         {
@@ -5,6 +5,7 @@ use crate::channel_wrapper::Channel;
 use crate::image_wrapper::Image;
 use crate::music_wrapper::Music;
 use crate::pyxel_singleton::pyxel;
+use crate::sample_wrapper::Sample;
 use crate::sound_wrapper::Sound;
 use crate::tilemap_wrapper::Tilemap;
 use crate::tone_wrapper::Tone;
@@ -49,6 +50,7 @@ wrap_shared_vec_as_python_list!(Images, Image, images);
 wrap_shared_vec_as_python_list!(Tilemaps, Tilemap, tilemaps);
 wrap_shared_vec_as_python_list!(Channels, Channel, channels);
 wrap_shared_vec_as_python_list!(Tones, Tone, tones);
+wrap_shared_vec_as_python_list!(Samples, Sample, samples);
 wrap_shared_vec_as_python_list!(Sounds, Sound, sounds);
 wrap_shared_vec_as_python_list!(Musics, Music, musics);
 
@@ -58,6 +60,8 @@ fn __getattr__(py: Python, name: &str) -> PyResult<PyObject> {
         // System
         "width" => pyxel().width.to_object(py),
         "height" => pyxel().height.to_object(py),
+        "display_width" => pyxel().display_width.to_object(py),
+        "display_height" => pyxel().display_height.to_object(py),
         "frame_count" => pyxel().frame_count.to_object(py),
 
         // Input
@@ -65,6 +69,8 @@ fn __getattr__(py: Python, name: &str) -> PyResult<PyObject> {
         "mouse_y" => pyxel().mouse_y.to_object(py),
         "mouse_wheel" => pyxel().mouse_wheel.to_object(py),
         "input_text" => pyxel().input_text.to_object(py),
+        "input_text_composition" => pyxel().input_text_composition.to_object(py),
+        "input_text_cursor" => pyxel().input_text_cursor.to_object(py),
         "dropped_files" => pyxel().dropped_files.to_object(py),
 
         // Graphics
@@ -78,6 +84,7 @@ fn __getattr__(py: Python, name: &str) -> PyResult<PyObject> {
         // Audio
         "channels" => Py::new(py, Channels::wrap(0))?.into_py(py),
         "tones" => Py::new(py, Tones::wrap(0))?.into_py(py),
+        "samples" => Py::new(py, Samples::wrap(0))?.into_py(py),
         "sounds" => Py::new(py, Sounds::wrap(0))?.into_py(py),
         "musics" => Py::new(py, Musics::wrap(0))?.into_py(py),
 
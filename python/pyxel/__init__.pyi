@@ -1,16 +1,46 @@
 # flake8: noqa
 from ctypes import POINTER, c_uint8
-from typing import Callable, Generic, List, Optional, Tuple, TypeVar, Union
+from typing import Any, Callable, Dict, Generic, List, Optional, Tuple, TypeVar, Union
 
 # Constants
 VERSION: str
 WORKING_DIR: str
 WATCH_INFO_FILE_ENVVAR: str
+SCALING_INTEGER: int
+SCALING_STRETCH: int
+SCALING_FIT: int
+TRANSITION_FADE: int
+TRANSITION_WIPE: int
+TRANSITION_PIXELATE: int
+TEXT_ALIGN_LEFT: int
+TEXT_ALIGN_CENTER: int
+TEXT_ALIGN_RIGHT: int
+COLOR_FILTER_NONE: int
+COLOR_FILTER_PROTANOPIA: int
+COLOR_FILTER_DEUTERANOPIA: int
+COLOR_FILTER_TRITANOPIA: int
+COLOR_FILTER_HIGH_CONTRAST: int
+SYSTEM_CURSOR_ARROW: int
+SYSTEM_CURSOR_CROSSHAIR: int
+SYSTEM_CURSOR_HAND: int
+SYSTEM_CURSOR_IBEAM: int
+SYSTEM_CURSOR_WAIT: int
 
 APP_FILE_EXTENSION: str
 APP_STARTUP_SCRIPT_FILE: str
+APP_METADATA_FILE: str
 RESOURCE_FILE_EXTENSION: str
 PALETTE_FILE_EXTENSION: str
+CAPTURE_FILTER_NEAREST: int
+CAPTURE_FILTER_SCALE2X: int
+CAPTURE_FILTER_SCALE3X: int
+SCREENCAST_FORMAT_GIF: int
+SCREENCAST_FORMAT_WEBM: int
+
+HOT_NONE: int
+HOT_HOVER: int
+HOT_PRESS: int
+HOT_CLICK: int
 
 NUM_COLORS: int
 NUM_IMAGES: int
@@ -41,6 +71,7 @@ FONT_HEIGHT: int
 
 NUM_CHANNELS: int
 NUM_TONES: int
+NUM_SAMPLE_SLOTS: int
 NUM_SOUNDS: int
 NUM_MUSICS: int
 
@@ -53,6 +84,7 @@ EFFECT_NONE: int
 EFFECT_SLIDE: int
 EFFECT_VIBRATO: int
 EFFECT_FADEOUT: int
+EFFECT_HALF_FADEOUT: int
 
 # Keys
 KEY_UNKNOWN: int
@@ -333,6 +365,12 @@ GAMEPAD1_BUTTON_DPAD_UP: int
 GAMEPAD1_BUTTON_DPAD_DOWN: int
 GAMEPAD1_BUTTON_DPAD_LEFT: int
 GAMEPAD1_BUTTON_DPAD_RIGHT: int
+GAMEPAD1_GYRO_X: int
+GAMEPAD1_GYRO_Y: int
+GAMEPAD1_GYRO_Z: int
+GAMEPAD1_ACCEL_X: int
+GAMEPAD1_ACCEL_Y: int
+GAMEPAD1_ACCEL_Z: int
 
 GAMEPAD2_AXIS_LEFTX: int
 GAMEPAD2_AXIS_LEFTY: int
@@ -355,6 +393,12 @@ GAMEPAD2_BUTTON_DPAD_UP: int
 GAMEPAD2_BUTTON_DPAD_DOWN: int
 GAMEPAD2_BUTTON_DPAD_LEFT: int
 GAMEPAD2_BUTTON_DPAD_RIGHT: int
+GAMEPAD2_GYRO_X: int
+GAMEPAD2_GYRO_Y: int
+GAMEPAD2_GYRO_Z: int
+GAMEPAD2_ACCEL_X: int
+GAMEPAD2_ACCEL_Y: int
+GAMEPAD2_ACCEL_Z: int
 
 GAMEPAD3_AXIS_LEFTX: int
 GAMEPAD3_AXIS_LEFTY: int
@@ -377,6 +421,12 @@ GAMEPAD3_BUTTON_DPAD_UP: int
 GAMEPAD3_BUTTON_DPAD_DOWN: int
 GAMEPAD3_BUTTON_DPAD_LEFT: int
 GAMEPAD3_BUTTON_DPAD_RIGHT: int
+GAMEPAD3_GYRO_X: int
+GAMEPAD3_GYRO_Y: int
+GAMEPAD3_GYRO_Z: int
+GAMEPAD3_ACCEL_X: int
+GAMEPAD3_ACCEL_Y: int
+GAMEPAD3_ACCEL_Z: int
 
 GAMEPAD4_AXIS_LEFTX: int
 GAMEPAD4_AXIS_LEFTY: int
@@ -399,6 +449,15 @@ GAMEPAD4_BUTTON_DPAD_UP: int
 GAMEPAD4_BUTTON_DPAD_DOWN: int
 GAMEPAD4_BUTTON_DPAD_LEFT: int
 GAMEPAD4_BUTTON_DPAD_RIGHT: int
+GAMEPAD4_GYRO_X: int
+GAMEPAD4_GYRO_Y: int
+GAMEPAD4_GYRO_Z: int
+GAMEPAD4_ACCEL_X: int
+GAMEPAD4_ACCEL_Y: int
+GAMEPAD4_ACCEL_Z: int
+
+MIDI_NOTE_0: int
+NUM_MIDI_NOTES: int
 
 # Sequence class
 T = TypeVar("T")
@@ -466,6 +525,33 @@ class Image:
         h: float,
         colkey: Optional[int] = None,
     ) -> None: ...
+    def blt_tiled(
+        self,
+        x: float,
+        y: float,
+        w: float,
+        h: float,
+        img: Union[int, Image],
+        u: float,
+        v: float,
+        tile_w: float,
+        tile_h: float,
+        colkey: Optional[int] = None,
+    ) -> None: ...
+    def blt_9slice(
+        self,
+        x: float,
+        y: float,
+        w: float,
+        h: float,
+        img: Union[int, Image],
+        u: float,
+        v: float,
+        src_w: float,
+        src_h: float,
+        margins: Tuple[int, int, int, int],
+        colkey: Optional[int] = None,
+    ) -> None: ...
     def bltm(
         self,
         x: float,
@@ -478,6 +564,8 @@ class Image:
         colkey: Optional[int] = None,
     ) -> None: ...
     def text(self, x: float, y: float, s: str, col: int) -> None: ...
+    def text_aligned(self, x: float, y: float, s: str, col: int, align: int) -> None: ...
+    def text_wrapped(self, x: float, y: float, w: float, s: str, col: int) -> int: ...
 
 # Tilemap class
 class Tilemap:
@@ -505,6 +593,11 @@ class Tilemap:
     def cls(self, tile: Tuple[int, int]) -> None: ...
     def pget(self, x: float, y: float) -> Tuple[int, int]: ...
     def pset(self, x: float, y: float, tile: Tuple[int, int]) -> None: ...
+    def get_tile(self, x: int, y: int) -> Optional[Tuple[int, int]]: ...
+    def set_tile(self, x: int, y: int, tile: Tuple[int, int]) -> bool: ...
+    def rect_overlaps(
+        self, x: float, y: float, w: float, h: float, solid_tiles: List[Tuple[int, int]]
+    ) -> bool: ...
     def line(
         self, x1: float, y1: float, x2: float, y2: float, tile: Tuple[int, int]
     ) -> None: ...
@@ -563,6 +656,13 @@ class Tilemap:
 class Channel:
     gain: float
     detune: int
+    pan: float
+    reverb_send: float
+    lowpass_cutoff: Optional[float]
+    highpass_cutoff: Optional[float]
+    envelope: Tuple[int, int, float, int]
+    vibrato_depth: float
+    vibrato_frequency: float
 
     def __init__(self) -> None: ...
     def play(
@@ -580,9 +680,19 @@ class Tone:
     gain: float
     noise: int
     waveform: Seq[int]
+    sample: Optional[int]
 
     def __init__(self) -> None: ...
 
+# Sample class
+class Sample:
+    sample_rate: int
+    base_note: int
+
+    def __init__(self) -> None: ...
+    def __len__(self) -> int: ...
+    def load(self, filename: str) -> None: ...
+
 # Sound class
 class Sound:
     notes: Seq[int]
@@ -604,6 +714,8 @@ class Sound:
     def set_tones(self, tones: str) -> None: ...
     def set_volumes(self, volumes: str) -> None: ...
     def set_effects(self, effects: str) -> None: ...
+    def render(self, sample_rate: int) -> List[int]: ...
+    def save(self, filename: str, sec: float) -> None: ...
 
 # Music class
 class Music:
@@ -614,6 +726,7 @@ class Music:
         self,
         *seqs: List[int],
     ) -> None: ...
+    def save(self, filename: str, sec: float) -> None: ...
 
     # Deprecated field
     snds_list: Seq[Seq[int]]
@@ -621,6 +734,8 @@ class Music:
 # System
 width: int
 height: int
+display_width: int
+display_height: int
 frame_count: int
 
 def init(
@@ -633,18 +748,69 @@ def init(
     display_scale: Optional[int] = None,
     capture_scale: Optional[int] = None,
     capture_sec: Optional[int] = None,
+    max_update_per_frame: Optional[int] = None,
+    max_elapsed_ms: Optional[int] = None,
+    resizable: Optional[bool] = None,
+    scaling_mode: Optional[int] = None,
+    pause_key: Optional[int] = None,
+    vsync: Optional[bool] = None,
 ) -> None: ...
 def run(update: Callable[[], None], draw: Callable[[], None]) -> None: ...
+def run_lua(filename: str) -> None: ...
 def show() -> None: ...
 def flip() -> None: ...
 def quit() -> None: ...
+def set_quit_handler(handler: Optional[Callable[[], bool]]) -> None: ...
+def set_announce_handler(handler: Optional[Callable[[str], None]]) -> None: ...
+def announce(text: str) -> None: ...
+def pause_key(key: int) -> None: ...
 def title(title: str) -> None: ...
 def icon(data: List[str], scale: int, colkey: Optional[int]) -> None: ...
-def fullscreen(full: bool) -> None: ...
+def fullscreen(full: bool, exclusive: Optional[bool] = None) -> None: ...
+def dpi_scale() -> float: ...
+def window_pos() -> Tuple[int, int]: ...
+def set_window_pos(x: int, y: int) -> None: ...
+def borderless(enabled: bool) -> None: ...
+def always_on_top(enabled: bool) -> None: ...
+def displays() -> List[str]: ...
+def set_display(idx: int) -> None: ...
 def screen_mode(scr: int) -> None: ...
+def scaling_mode(mode: int) -> None: ...
+def current_scaling_mode() -> int: ...
+def set_screen_size(width: int, height: int) -> None: ...
+def set_fps(fps: int) -> None: ...
+def vsync(enabled: bool) -> None: ...
+def set_quit_key(key: Optional[int]) -> None: ...
+def set_system_keys(
+    *,
+    modifier: Optional[int] = None,
+    dump_modifier: Optional[int] = None,
+    fullscreen: Optional[int] = None,
+    perf_monitor: Optional[int] = None,
+    screenshot: Optional[int] = None,
+    reset_screencast: Optional[int] = None,
+    screencast: Optional[int] = None,
+    screen_mode: Optional[int] = None,
+    debug_console: Optional[int] = None,
+    palette_editor: Optional[int] = None,
+    bank_viewer: Optional[int] = None,
+    frame_step: Optional[int] = None,
+    frame_advance: Optional[int] = None,
+) -> None: ...
+def elapsed_time() -> int: ...
+def dropped_frames() -> int: ...
+def dbg_print(text: str) -> None: ...
+def perf() -> Dict[str, Any]: ...
+def perf_window(frames: int) -> None: ...
 def process_exists(pid: int) -> bool: ...
+def init_logger(level: Optional[str] = None) -> bool: ...
 
 # Resource
+class ResourceLoad:
+    def progress(self) -> float: ...
+    def is_done(self) -> bool: ...
+    def error(self) -> Optional[str]: ...
+
 def load(
     filename: str,
     *,
@@ -656,6 +822,17 @@ def load(
     incl_channels: Optional[bool] = None,
     incl_tones: Optional[bool] = None,
 ) -> None: ...
+def load_async(
+    filename: str,
+    *,
+    excl_images: Optional[bool] = None,
+    excl_tilemaps: Optional[bool] = None,
+    excl_sounds: Optional[bool] = None,
+    excl_musics: Optional[bool] = None,
+    incl_colors: Optional[bool] = None,
+    incl_channels: Optional[bool] = None,
+    incl_tones: Optional[bool] = None,
+) -> ResourceLoad: ...
 def save(
     filename: str,
     *,
@@ -667,15 +844,32 @@ def save(
     incl_channels: Optional[bool] = None,
     incl_tones: Optional[bool] = None,
 ) -> None: ...
+def load_midi(
+    filename: str, msc: int, snd: int, *, speed: Optional[int] = None
+) -> None: ...
 def screenshot(scale: Optional[int] = None) -> None: ...
 def screencast(scale: Optional[int] = None) -> None: ...
+def set_capture_dir(
+    dir: Optional[str], *, name_template: Optional[str] = None
+) -> None: ...
+def screenshot_to_clipboard(enabled: bool) -> None: ...
+def capture_filter(filter: int) -> None: ...
+def screencast_format(format: int, *, capture_sec: Optional[int] = None) -> None: ...
 def reset_screencast() -> None: ...
+def save_state() -> bytes: ...
+def load_state(state: bytes) -> None: ...
+def set_save_name(name: str) -> None: ...
+def save_data(slot: int, data: bytes) -> None: ...
+def load_data(slot: int) -> Optional[bytes]: ...
+def hot_reload(enabled: bool) -> None: ...
 
 # Input
 mouse_x: int
 mouse_y: int
 mouse_wheel: int
 input_text: str
+input_text_composition: str
+input_text_cursor: int
 dropped_files: List[str]
 
 def btn(key: int) -> bool: ...
@@ -683,9 +877,41 @@ def btnp(
     key: int, *, hold: Optional[int] = None, repeat: Optional[int] = None
 ) -> bool: ...
 def btnr(key: int) -> bool: ...
+def btn_press_time(key: int) -> Optional[int]: ...
 def btnv(key: int) -> int: ...
+def bind_action(action: str, keys: List[int]) -> None: ...
+def unbind_action(action: str) -> None: ...
+def action_keys(action: str) -> List[int]: ...
+def action(action: str) -> bool: ...
+def actionp(
+    action: str, *, hold: Optional[int] = None, repeat: Optional[int] = None
+) -> bool: ...
+def actionr(action: str) -> bool: ...
+def save_input_map(filename: str) -> None: ...
+def load_input_map(filename: str) -> None: ...
+def last_pressed_key() -> Optional[int]: ...
+def key_name(key: int) -> str: ...
+def key_from_name(name: str) -> Optional[int]: ...
+def gamepad_count() -> int: ...
+def rumble(
+    gamepad: int, low_freq: float, high_freq: float, duration_ms: int
+) -> bool: ...
 def mouse(visible: bool) -> None: ...
+def set_cursor(image: Image, hot_x: int, hot_y: int) -> None: ...
+def set_cursor_hotspot(hot_x: int, hot_y: int) -> None: ...
+def reset_cursor() -> None: ...
+def use_system_cursor(kind: int) -> None: ...
+def virtual_gamepad(enabled: bool) -> None: ...
+def on_idle(frames: int, callback: Callable[[], None]) -> None: ...
+def calibrate_motion() -> None: ...
+def mouse_in(x: float, y: float, w: float, h: float) -> bool: ...
+def hot_rect(id: int, x: float, y: float, w: float, h: float) -> int: ...
+def midi_ports() -> List[str]: ...
+def midi_in(
+    port: int, channel: Optional[int] = None, tone: Optional[int] = None
+) -> bool: ...
 def warp_mouse(x: float, y: float) -> None: ...
+def dropped_file_data(i: int) -> Optional[bytes]: ...
 
 # Graphics
 class Image: ...
@@ -760,15 +986,45 @@ def bltm(
     colkey: Optional[int] = None,
 ) -> None: ...
 def text(x: float, y: float, s: str, col: int) -> None: ...
+def text_width(s: str) -> int: ...
+def text_aligned(x: float, y: float, s: str, col: int, align: int) -> None: ...
+def text_wrapped(x: float, y: float, w: float, s: str, col: int) -> int: ...
+def load_font(filename: str) -> None: ...
+def reset_font() -> None: ...
+def set_render_target(img: Union[int, Image]) -> None: ...
+def reset_render_target() -> None: ...
+def border_color(rgb: int) -> None: ...
+def border_image(img: Optional[Union[int, Image]] = None) -> None: ...
+def color_filter(mode: int) -> None: ...
+def viewport(
+    idx: Optional[int] = None,
+    x: Optional[float] = None,
+    y: Optional[float] = None,
+    w: Optional[float] = None,
+    h: Optional[float] = None,
+    draw: Optional[Callable[[], None]] = None,
+) -> None: ...
+def viewport_camera(idx: int, x: float, y: float) -> None: ...
+def palette_cycle(
+    start: Optional[int] = None, end: Optional[int] = None, speed: Optional[int] = None
+) -> None: ...
+def draw_offset(x: float, y: float) -> None: ...
+def screen_shake(intensity: float, frames: int) -> None: ...
+def start_transition(kind: int, frames: int) -> None: ...
+def is_transitioning() -> bool: ...
+def open_debug_window(w: int, h: int) -> Image: ...
+def close_debug_window() -> None: ...
 
 # Audio
 class Channel: ...
 class Tone: ...
+class Sample: ...
 class Sound: ...
 class Music: ...
 
 channels: Seq[Channel]
 tones: Seq[Tone]
+samples: Seq[Sample]
 sounds: Seq[Sound]
 musics: Seq[Music]
 
@@ -784,6 +1040,11 @@ def playm(
 ) -> None: ...
 def stop(ch: Optional[int] = None) -> None: ...
 def play_pos(ch: int) -> Optional[Tuple[int, int]]: ...
+def play_stream(filename: str, *, loop: Optional[bool] = None) -> None: ...
+def stop_stream() -> None: ...
+def is_stream_playing() -> bool: ...
+def master_volume() -> float: ...
+def set_master_volume(vol: float) -> None: ...
 
 # Math
 def ceil(x: float) -> int: ...
@@ -798,6 +1059,42 @@ def rndi(a: int, b: int) -> int: ...
 def rndf(a: float, b: float) -> int: ...
 def nseed(seed: int) -> None: ...
 def noise(x: float, y: Optional[float] = None, z: Optional[float] = None) -> float: ...
+def rect_collide(
+    x1: float, y1: float, w1: float, h1: float, x2: float, y2: float, w2: float, h2: float
+) -> bool: ...
+def circ_rect_collide(
+    cx: float, cy: float, r: float, x: float, y: float, w: float, h: float
+) -> bool: ...
+def sweep_rect(
+    x1: float,
+    y1: float,
+    w1: float,
+    h1: float,
+    dx: float,
+    dy: float,
+    x2: float,
+    y2: float,
+    w2: float,
+    h2: float,
+) -> Optional[Tuple[float, float, float]]: ...
+def pixel_collide(
+    x1: float,
+    y1: float,
+    img1: int,
+    u1: float,
+    v1: float,
+    w1: float,
+    h1: float,
+    colkey1: Optional[int],
+    x2: float,
+    y2: float,
+    img2: int,
+    u2: float,
+    v2: float,
+    w2: float,
+    h2: float,
+    colkey2: Optional[int],
+) -> bool: ...
 
 # Deprecated functions
 def image(img: int) -> Image: ...
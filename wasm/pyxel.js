@@ -284,6 +284,32 @@ _virtualGamepadStates = [
   false, // Y
 ];
 
+_deviceMotionValues = [
+  0, // Gyro X
+  0, // Gyro Y
+  0, // Gyro Z
+  0, // Accel X
+  0, // Accel Y
+  0, // Accel Z
+];
+
+function _addDeviceMotionListener() {
+  window.addEventListener("devicemotion", (event) => {
+    let rotationRate = event.rotationRate;
+    if (rotationRate) {
+      _deviceMotionValues[0] = ((rotationRate.beta || 0) * Math.PI) / 180;
+      _deviceMotionValues[1] = ((rotationRate.gamma || 0) * Math.PI) / 180;
+      _deviceMotionValues[2] = ((rotationRate.alpha || 0) * Math.PI) / 180;
+    }
+    let acceleration = event.accelerationIncludingGravity;
+    if (acceleration) {
+      _deviceMotionValues[3] = acceleration.x || 0;
+      _deviceMotionValues[4] = acceleration.y || 0;
+      _deviceMotionValues[5] = acceleration.z || 0;
+    }
+  });
+}
+
 function _addVirtualGamepad(mode) {
   if (mode !== "enabled" || !_isTouchDevice()) {
     return;
@@ -380,6 +406,7 @@ async function _executePyxelCommand(pyodide, params) {
   }
   if (params.command === "run" || params.command === "play") {
     _addVirtualGamepad(params.gamepad);
+    _addDeviceMotionListener();
   }
   _copyFileFromBase64(pyodide, params.name, params.base64);
   let pythonCode = "";